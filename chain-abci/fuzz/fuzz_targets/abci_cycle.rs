@@ -13,6 +13,7 @@ use chain_core::init::coin::Coin;
 use chain_core::init::config::InitConfig;
 use chain_core::init::config::NetworkParameters;
 use chain_core::state::tendermint::{TendermintValidatorPubKey, TendermintVotePower};
+use chain_core::state::ChainState;
 use chain_storage::{Storage, NUM_COLUMNS};
 use kvdb::KeyValueDB;
 use kvdb_memorydb::create;
@@ -178,9 +179,11 @@ fuzz_target!(|data: &[u8]| {
 
                                     let genesis_app_hash = compute_app_hash(
                                         &tx_tree,
-                                        &new_account_root,
-                                        &state.rewards_pool,
-                                        &network_params,
+                                        &ChainState::genesis(
+                                            new_account_root,
+                                            state.rewards_pool.clone(),
+                                            network_params.clone(),
+                                        ),
                                     );
                                     if req.chain_id.len() > 3 {
                                         if let Ok(netid) =
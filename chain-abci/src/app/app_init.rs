@@ -18,7 +18,11 @@ use crate::enclave_bridge::edp::start_zmq;
 #[cfg(all(not(feature = "mock-enclave"), feature = "legacy", target_os = "linux"))]
 use crate::enclave_bridge::real::start_zmq;
 use crate::enclave_bridge::EnclaveProxy;
+use crate::governance::GovernanceState;
+use crate::migration::SCHEMA_VERSION;
+use crate::peer_filter::SharedPeerFilter;
 use crate::staking::StakingTable;
+use crate::tdbe::MLSGroupState;
 use chain_core::common::MerkleTree;
 use chain_core::common::Timespec;
 use chain_core::common::{H256, HASH_SIZE_256};
@@ -37,7 +41,7 @@ use chain_storage::buffer::{
     flush_storage, GetStaking, KVBuffer, StakingBuffer, StoreKV, StoreStaking,
 };
 use chain_storage::jellyfish::{compute_staking_root, sum_staking_coins, StakingGetter, Version};
-use chain_storage::{Storage, StoredChainState};
+use chain_storage::{PruningMode, Storage, StoredChainState};
 
 /// ABCI app state snapshot
 #[derive(Serialize, Deserialize, Clone, Encode, Decode)]
@@ -64,6 +68,11 @@ pub struct ChainNodeState {
     /// Record the biggest enclave ISVSVN (Security Version Number of the Enclave) we've seen in
     /// keypackage so far
     pub enclave_isv_svn: u16,
+    /// Public view of the TDBE MLS group state
+    pub mls_group: MLSGroupState,
+    /// Network parameter change proposals in the voting period
+    #[serde(skip)]
+    pub governance: GovernanceState,
 
     /// The parts of states which involved in computing app_hash
     pub top_level: ChainState,
@@ -110,11 +119,9 @@ impl ChainNodeState {
             staking_version: 0,
             utxo_coins: Coin::zero(),
             enclave_isv_svn,
-            top_level: ChainState {
-                account_root,
-                rewards_pool,
-                network_params,
-            },
+            mls_group: MLSGroupState::default(),
+            governance: GovernanceState::default(),
+            top_level: ChainState::genesis(account_root, rewards_pool, network_params),
         }
     }
 
@@ -149,6 +156,12 @@ pub struct ChainNodeApp<T: EnclaveProxy> {
     pub rewards_pool_updated: bool,
     /// address of tx query enclave to supply to clients (if any)
     pub tx_query_address: Option<String>,
+    /// rules for accepting Tendermint peer connections
+    pub peer_filter: SharedPeerFilter,
+    /// how much of the historical states is kept in the storage
+    pub pruning: PruningMode,
+    /// whether the spent outputs and inclusion heights of the committed transactions are indexed
+    pub utxo_index: bool,
 
     /// consensus buffer of staking merkle trie storage
     pub staking_buffer: StakingBuffer,
@@ -241,9 +254,11 @@ pub fn init_app_hash(conf: &InitConfig, genesis_time: Timespec) -> H256 {
 
     compute_app_hash(
         &MerkleTree::empty(),
-        &compute_staking_root(&state.accounts),
-        &state.rewards_pool,
-        &NetworkParameters::Genesis(conf.network_params.clone()),
+        &ChainState::genesis(
+            compute_staking_root(&state.accounts),
+            state.rewards_pool,
+            NetworkParameters::Genesis(conf.network_params.clone()),
+        ),
     )
 }
 
@@ -285,6 +300,9 @@ impl<T: EnclaveProxy + 'static> ChainNodeApp<T> {
             tx_validator,
             rewards_pool_updated: false,
             tx_query_address,
+            peer_filter: SharedPeerFilter::default(),
+            pruning: PruningMode::default(),
+            utxo_index: false,
 
             staking_buffer: HashMap::new(),
             mempool_staking_buffer: HashMap::new(),
@@ -337,6 +355,19 @@ impl<T: EnclaveProxy + 'static> ChainNodeApp<T> {
 
         if let Some(data) = storage.get_last_app_state() {
             info!("last app state stored");
+            // the state layout may have changed, so the schema version is checked before decoding it
+            let schema_version = chain_storage::get_schema_version(&storage);
+            if schema_version > SCHEMA_VERSION {
+                panic!(
+                    "storage schema version {} is newer than the supported one: {}",
+                    schema_version, SCHEMA_VERSION
+                );
+            } else if schema_version < SCHEMA_VERSION {
+                panic!(
+                    "storage schema version {} is outdated (the current one: {}), run `chain-abci inspect migrate-schema`",
+                    schema_version, SCHEMA_VERSION
+                );
+            }
             let mut last_state =
                 ChainNodeState::decode(&mut data.as_slice()).expect("deserialize app state");
 
@@ -406,6 +437,9 @@ impl<T: EnclaveProxy + 'static> ChainNodeApp<T> {
                 tx_validator,
                 rewards_pool_updated: false,
                 tx_query_address,
+                peer_filter: SharedPeerFilter::default(),
+                pruning: PruningMode::default(),
+                utxo_index: false,
 
                 staking_buffer: HashMap::new(),
                 mempool_staking_buffer: HashMap::new(),
@@ -415,6 +449,27 @@ impl<T: EnclaveProxy + 'static> ChainNodeApp<T> {
         }
     }
 
+    /// Uses the provided (possibly hot-reloaded) peer filtering rules
+    /// instead of the default ones (that accept all peers)
+    pub fn with_peer_filter(mut self, peer_filter: SharedPeerFilter) -> Self {
+        self.peer_filter = peer_filter;
+        self
+    }
+
+    /// Prunes the historical states in commits according to the provided mode
+    /// instead of keeping everything
+    pub fn with_pruning(mut self, pruning: PruningMode) -> Self {
+        self.pruning = pruning;
+        self
+    }
+
+    /// Indexes the spent outputs (which transaction spent them and at which height)
+    /// and the inclusion heights of the transactions committed from now on
+    pub fn with_utxo_index(mut self, utxo_index: bool) -> Self {
+        self.utxo_index = utxo_index;
+        self
+    }
+
     /// Handles InitChain requests:
     /// should validate initial genesis distribution, initialize everything in the key-value DB and check it matches the expected values
     /// provided as arguments.
@@ -458,9 +513,11 @@ impl<T: EnclaveProxy + 'static> ChainNodeApp<T> {
         let new_account_root = self.storage.put_stakings(0, &state.accounts);
         let genesis_app_hash = compute_app_hash(
             &MerkleTree::empty(),
-            &new_account_root,
-            &state.rewards_pool,
-            &network_params,
+            &ChainState::genesis(
+                new_account_root,
+                state.rewards_pool.clone(),
+                network_params.clone(),
+            ),
         );
 
         if self.genesis_app_hash != genesis_app_hash {
@@ -508,6 +565,11 @@ impl<T: EnclaveProxy + 'static> ChainNodeApp<T> {
             &genesis_state,
             self.tx_query_address.is_some(),
         );
+        chain_storage::store_network_params(
+            &mut kv_store!(self),
+            &genesis_state.top_level.network_params,
+        );
+        chain_storage::store_schema_version(&mut kv_store!(self), SCHEMA_VERSION);
         flush_storage(&mut self.storage, mem::take(&mut self.kv_buffer)).expect("storage io error");
 
         self.last_state = Some(genesis_state);
@@ -552,7 +614,7 @@ impl<T: EnclaveProxy + 'static> ChainNodeApp<T> {
         let min_fee = state
             .top_level
             .network_params
-            .calculate_fee(tx_len)
+            .calculate_dynamic_fee(tx_len, state.top_level.base_fee)
             .expect("invalid fee policy");
         ChainInfo {
             min_fee_computed: min_fee,
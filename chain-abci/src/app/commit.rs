@@ -4,14 +4,19 @@ use super::ChainNodeApp;
 use crate::enclave_bridge::EnclaveProxy;
 use abci::*;
 use chain_core::common::MerkleTree;
-use chain_core::compute_app_hash;
 use chain_core::tx::data::input::{TxoPointer, TxoSize};
 use chain_core::tx::data::TxId;
 use chain_core::tx::{TxAux, TxEnclaveAux, TxPublicAux};
+use chain_core::AppHashParts;
 use chain_storage::buffer::{flush_storage, StoreKV};
 use chain_storage::jellyfish::flush_stakings;
+use log::debug;
 use parity_scale_codec::Encode;
 
+/// At most so many heights are pruned in one commit
+/// (so that enabling the pruning on an existing database doesn't stall the block processing)
+const MAX_PRUNED_HEIGHTS_PER_COMMIT: u64 = 100;
+
 /// Given a db and a DB transaction, it will go through TX inputs and mark them as spent
 /// in the TX_META storage and it will create a new entry for TX in TX_META with all outputs marked as unspent.
 fn update_utxos_commit(
@@ -24,12 +29,22 @@ fn update_utxos_commit(
     chain_storage::create_utxo(db, no_of_outputs, &txid);
 }
 
+/// UTXOs spent by the transaction (recorded with the block, so that it can be reverted in a rollback)
+fn spent_utxos(txaux: &TxAux) -> Vec<TxoPointer> {
+    match txaux {
+        TxAux::EnclaveTx(TxEnclaveAux::TransferTx { inputs, .. }) => inputs.clone(),
+        TxAux::EnclaveTx(TxEnclaveAux::DepositStakeTx { tx, .. }) => tx.inputs.clone(),
+        _ => vec![],
+    }
+}
+
 fn process_txs(delivered_txs: &[TxAux], db: &mut impl StoreKV) {
     for txaux in delivered_txs.iter() {
         let txid: TxId = txaux.tx_id();
         match &txaux {
-            TxAux::MLSHandshake(_) => {
-                // FIXME
+            TxAux::MLSHandshake(handshake) => {
+                chain_storage::store_tx_body(db, &txid, &handshake.encode());
+                // group state updated in deliver_tx
             }
             TxAux::EnclaveTx(TxEnclaveAux::TransferTx {
                 inputs,
@@ -70,6 +85,32 @@ fn process_txs(delivered_txs: &[TxAux], db: &mut impl StoreKV) {
                 // staked state updated in deliver_tx
                 // validator state updated in end_block
             }
+            TxAux::PublicTx(TxPublicAux::NodeMetadataUpdateTx(tx, witness)) => {
+                chain_storage::store_tx_body(db, &txid, &tx.encode());
+                chain_storage::store_tx_witness(db, &txid, &witness.encode());
+                // staked state updated in deliver_tx
+                // consensus key rotation (if any) applied in end_block
+            }
+            TxAux::PublicTx(TxPublicAux::TransferStakeTx(tx, from_witness, to_witness)) => {
+                chain_storage::store_tx_body(db, &txid, &tx.encode());
+                chain_storage::store_tx_witness(db, &txid, &(from_witness, to_witness).encode());
+                // accounts should be already updated in deliver_tx
+            }
+            TxAux::PublicTx(TxPublicAux::ParamChangeProposalTx(tx, witness)) => {
+                chain_storage::store_tx_body(db, &txid, &tx.encode());
+                chain_storage::store_tx_witness(db, &txid, &witness.encode());
+                // proposal recorded in deliver_tx, the accepted change applied in end_block
+            }
+            TxAux::PublicTx(TxPublicAux::ParamChangeVoteTx(tx, witness)) => {
+                chain_storage::store_tx_body(db, &txid, &tx.encode());
+                chain_storage::store_tx_witness(db, &txid, &witness.encode());
+                // vote recorded in deliver_tx
+            }
+            TxAux::PublicTx(TxPublicAux::DelegateTx(tx, witness)) => {
+                chain_storage::store_tx_body(db, &txid, &tx.encode());
+                chain_storage::store_tx_witness(db, &txid, &witness.encode());
+                // staked state and delegators index updated in deliver_tx
+            }
         }
     }
 }
@@ -110,15 +151,30 @@ impl<T: EnclaveProxy + 'static> ChainNodeApp<T> {
             .expect("merkle trie io error");
         }
 
-        let app_hash = compute_app_hash(
-            &tree,
-            &top_level.account_root,
-            &top_level.rewards_pool,
-            &top_level.network_params,
-        );
+        top_level.stake_transfers_hash = new_state.staking_table.stake_transfers_hash();
+        let app_hash_parts = AppHashParts::new(&tree, top_level);
+        let app_hash = app_hash_parts.app_hash();
         new_state.last_apphash = app_hash;
 
         chain_storage::store_txs_merkle_tree(&mut kv_store!(self), &app_hash, &tree.encode());
+        chain_storage::store_app_hash_parts(&mut kv_store!(self), &app_hash, &app_hash_parts);
+        let block_txs = self
+            .delivered_txs
+            .iter()
+            .map(|txaux| (txaux.tx_id(), spent_utxos(txaux)))
+            .collect::<Vec<_>>();
+        chain_storage::store_block_txs(
+            &mut kv_store!(self),
+            new_state.last_block_height,
+            &block_txs,
+        );
+        if self.utxo_index {
+            chain_storage::index_block_txs(
+                &mut kv_store!(self),
+                new_state.last_block_height,
+                &block_txs,
+            );
+        }
         chain_storage::store_chain_state(
             &mut kv_store!(self),
             &*new_state,
@@ -130,6 +186,18 @@ impl<T: EnclaveProxy + 'static> ChainNodeApp<T> {
         flush_storage(&mut self.storage, mem::take(&mut self.kv_buffer))
             .expect("kv storage io error");
 
+        let pruned = self
+            .storage
+            .prune(
+                self.pruning,
+                new_state.last_block_height,
+                MAX_PRUNED_HEIGHTS_PER_COMMIT,
+            )
+            .expect("kv storage io error");
+        if pruned > 0 {
+            debug!("pruned the history of {} heights", pruned);
+        }
+
         resp.data = new_state.last_apphash.to_vec();
 
         self.mempool_state = Some(new_state.clone());
@@ -1,14 +1,19 @@
 use std::convert::TryInto;
 
 use crate::app::app_init::ChainNodeApp;
+use crate::app::governance_event::ProposalResultEvent;
 use crate::enclave_bridge::EnclaveProxy;
 use abci::{Event, Pair as KVPair, PubKey, RequestEndBlock, ResponseEndBlock, ValidatorUpdate};
 use chain_core::common::TendermintEventType;
+use chain_core::init::params::NetworkParameterChange;
 use chain_tx_filter::BlockFilter;
 use enclave_protocol::{IntraEnclaveRequest, IntraEnclaveResponseOk};
+use log::warn;
+use parity_scale_codec::Encode;
 
 impl<T: EnclaveProxy + 'static> ChainNodeApp<T> {
-    /// tags the block with the transaction filter + computes validator set changes
+    /// tags the block with the transaction filter + applies the accepted governance proposals
+    /// + computes validator set changes
     pub fn end_block_handler(&mut self, req: &RequestEndBlock) -> ResponseEndBlock {
         let mut resp = ResponseEndBlock::new();
         if !self.delivered_txs.is_empty() {
@@ -32,8 +37,67 @@ impl<T: EnclaveProxy + 'static> ChainNodeApp<T> {
                 panic!("end block request to obtain the block filter failed");
             }
         }
+        let block_size = self
+            .delivered_txs
+            .iter()
+            .map(|txaux| txaux.encode().len())
+            .sum();
         // TODO: skipchain-based validator changes?
         let state = self.last_state.as_mut().expect("executing end block, but no app state stored (i.e. no initchain or recovery was executed)");
+
+        // the votes are counted with the voting power of the current validators
+        let voting_powers = state.staking_table.get_chosen_validators().clone();
+        let governance_params = state.top_level.network_params.get_governance_config();
+        let ended =
+            state
+                .governance
+                .end_block(state.block_time, &voting_powers, &governance_params);
+        for (proposal, accepted) in ended {
+            let new_version = if !accepted {
+                None
+            } else {
+                match state
+                    .top_level
+                    .network_params
+                    .apply_change(&proposal.change)
+                {
+                    Ok(params) => {
+                        if let NetworkParameterChange::RequiredCouncilNodeStake(stake) =
+                            proposal.change
+                        {
+                            state.staking_table.set_minimal_required_staking(
+                                &mut staking_store!(self, state.staking_version),
+                                state.block_time,
+                                state.block_height,
+                                stake,
+                            );
+                        }
+                        chain_storage::store_network_params(&mut kv_store!(self), &params);
+                        let version = params.get_version();
+                        state.top_level.network_params = params;
+                        Some(version)
+                    }
+                    Err(e) => {
+                        warn!(
+                            "accepted proposal {} can't be applied: {}",
+                            hex::encode(&proposal.id),
+                            e
+                        );
+                        None
+                    }
+                }
+            };
+            resp.events
+                .push(ProposalResultEvent(&proposal.id, new_version).into());
+        }
+
+        // the base fee for the next block goes up or down with the size of this one
+        state.top_level.base_fee = state
+            .top_level
+            .network_params
+            .get_dynamic_fee_config()
+            .next_base_fee(state.top_level.base_fee, block_size);
+
         let val_updates = state.staking_table.end_block(
             &staking_getter!(self, state.staking_version),
             state.top_level.network_params.get_max_validators(),
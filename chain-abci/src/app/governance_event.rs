@@ -0,0 +1,59 @@
+use abci::Pair as KVPair;
+use abci::*;
+use chain_core::common::{TendermintEventKey, TendermintEventType};
+use chain_core::init::params::NetworkParametersVersion;
+use chain_core::state::governance::ProposalId;
+
+/// Result of the proposal at the end of its voting period
+/// (with the new network parameters version if it was accepted and applied)
+pub(crate) struct ProposalResultEvent<'a>(pub &'a ProposalId, pub Option<NetworkParametersVersion>);
+
+impl<'a> From<ProposalResultEvent<'a>> for Event {
+    fn from(event: ProposalResultEvent) -> Self {
+        let ProposalResultEvent(proposal_id, maybe_version) = event;
+        let mut event = Event::new();
+        event.field_type = TendermintEventType::Governance.to_string();
+
+        let mut id_kv_pair = KVPair::new();
+        id_kv_pair.key = TendermintEventKey::ProposalId.into();
+        id_kv_pair.value = hex::encode(proposal_id).into_bytes();
+        event.attributes.push(id_kv_pair);
+
+        let mut result_kv_pair = KVPair::new();
+        result_kv_pair.key = TendermintEventKey::ProposalResult.into();
+        result_kv_pair.value = if maybe_version.is_some() {
+            b"accepted".to_vec()
+        } else {
+            b"rejected".to_vec()
+        };
+        event.attributes.push(result_kv_pair);
+
+        if let Some(version) = maybe_version {
+            let mut version_kv_pair = KVPair::new();
+            version_kv_pair.key = TendermintEventKey::ParamsVersion.into();
+            version_kv_pair.value = version.to_string().into_bytes();
+            event.attributes.push(version_kv_pair);
+        }
+
+        event
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_proposal_result_event() {
+        let event: Event = ProposalResultEvent(&[1u8; 32], Some(2)).into();
+        assert_eq!(event.field_type, "governance");
+        assert_eq!(event.attributes.len(), 3);
+        assert_eq!(event.attributes[0].key, TendermintEventKey::ProposalId);
+        assert_eq!(event.attributes[1].value, b"accepted".to_vec());
+        assert_eq!(event.attributes[2].value, b"2".to_vec());
+
+        let event: Event = ProposalResultEvent(&[1u8; 32], None).into();
+        assert_eq!(event.attributes.len(), 2);
+        assert_eq!(event.attributes[1].value, b"rejected".to_vec());
+    }
+}
@@ -0,0 +1,88 @@
+use std::fmt;
+
+use abci::Pair as KVPair;
+use abci::*;
+use chain_core::common::{TendermintEventKey, TendermintEventType};
+
+use crate::tdbe::MLSHandshakeAction;
+
+/// TDBE MLS group change after the handshake and the resulting group epoch
+pub(crate) struct MLSHandshakeEvent<'a>(pub &'a MLSHandshakeAction, pub u64);
+
+impl<'a> From<MLSHandshakeEvent<'a>> for Event {
+    fn from(event: MLSHandshakeEvent) -> Self {
+        let MLSHandshakeEvent(action, epoch) = event;
+        let mut event = Event::new();
+        event.field_type = TendermintEventType::MLSHandshake.to_string();
+
+        let mut optype_kv_pair = KVPair::new();
+        optype_kv_pair.key = TendermintEventKey::MLSOpType.into();
+        optype_kv_pair.value = MLSEventOpType::from(action).to_string().into_bytes();
+        event.attributes.push(optype_kv_pair);
+
+        let mut epoch_kv_pair = KVPair::new();
+        epoch_kv_pair.key = TendermintEventKey::MLSEpoch.into();
+        epoch_kv_pair.value = epoch.to_string().into_bytes();
+        event.attributes.push(epoch_kv_pair);
+
+        let mut sender_kv_pair = KVPair::new();
+        sender_kv_pair.key = TendermintEventKey::MLSSender.into();
+        sender_kv_pair.value = action.sender().to_string().into_bytes();
+        event.attributes.push(sender_kv_pair);
+
+        event
+    }
+}
+
+enum MLSEventOpType {
+    Commit,
+    SelfUpdate,
+}
+
+impl From<&MLSHandshakeAction> for MLSEventOpType {
+    fn from(action: &MLSHandshakeAction) -> Self {
+        match action {
+            MLSHandshakeAction::Commit { .. } => MLSEventOpType::Commit,
+            MLSHandshakeAction::SelfUpdate { .. } => MLSEventOpType::SelfUpdate,
+        }
+    }
+}
+
+impl fmt::Display for MLSEventOpType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MLSEventOpType::Commit => write!(f, "commit"),
+            MLSEventOpType::SelfUpdate => write!(f, "selfupdate"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_commit_event() {
+        let action = MLSHandshakeAction::Commit {
+            sender: 2,
+            added: 1,
+            removed: 0,
+        };
+        let event: Event = MLSHandshakeEvent(&action, 5).into();
+        assert_eq!(event.field_type, "mls_handshake");
+        assert_eq!(event.attributes.len(), 3);
+        assert_eq!(event.attributes[0].key, TendermintEventKey::MLSOpType);
+        assert_eq!(event.attributes[0].value, b"commit".to_vec());
+        assert_eq!(event.attributes[1].value, b"5".to_vec());
+        assert_eq!(event.attributes[2].value, b"2".to_vec());
+    }
+
+    #[test]
+    fn check_self_update_event() {
+        let action = MLSHandshakeAction::SelfUpdate { sender: 1 };
+        let event: Event = MLSHandshakeEvent(&action, 3).into();
+        assert_eq!(event.attributes.len(), 3);
+        assert_eq!(event.attributes[0].value, b"selfupdate".to_vec());
+        assert_eq!(event.attributes[2].value, b"1".to_vec());
+    }
+}
@@ -4,6 +4,8 @@ mod macros;
 mod app_init;
 mod commit;
 mod end_block;
+mod governance_event;
+mod mls_event;
 mod query;
 mod rewards;
 mod staking_event;
@@ -20,16 +22,19 @@ pub use self::app_init::check_validators;
 pub use self::app_init::{
     get_validator_key, init_app_hash, BufferType, ChainNodeApp, ChainNodeState,
 };
+use crate::app::mls_event::MLSHandshakeEvent;
 use crate::app::staking_event::StakingEvent;
 use crate::app::validate_tx::ResponseWithCodeAndLog;
 use crate::enclave_bridge::EnclaveProxy;
 use crate::staking::RewardsDistribution;
 use crate::storage::{TxAction, TxEnclaveAction, TxPublicAction};
+use crate::tdbe::MLSGroupState;
 use chain_core::common::{TendermintEventKey, TendermintEventType, Timespec};
 use chain_core::init::coin::Coin;
 use chain_core::init::config::NetworkParameters;
-use chain_core::state::account::PunishmentKind;
+use chain_core::state::account::SlashRecord;
 use chain_core::state::tendermint::{BlockHeight, TendermintValidatorAddress, TendermintVotePower};
+use chain_core::tx::fee::Fee;
 use chain_core::tx::TxAux;
 use parity_scale_codec::Decode;
 
@@ -42,6 +47,30 @@ fn get_version() -> String {
     )
 }
 
+/// Types of the byzantine evidence reported by Tendermint
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EvidenceKind {
+    /// conflicting votes signed for the same height and round (double signing)
+    DuplicateVote,
+    /// attacks on light clients (lunatic / equivocation / amnesia)
+    LightClientAttack,
+    /// evidence type not known to this version (still verified by Tendermint)
+    Unknown,
+}
+
+impl EvidenceKind {
+    /// Parse the evidence type from `Evidence.type` in abci requests
+    pub fn from_abci(field_type: &str) -> Self {
+        match field_type {
+            "duplicate/vote" | "duplicate_vote" => EvidenceKind::DuplicateVote,
+            "lunatic" | "equivocation" | "amnesia" | "light_client_attack" => {
+                EvidenceKind::LightClientAttack
+            }
+            _ => EvidenceKind::Unknown,
+        }
+    }
+}
+
 /// Encapsulate some information for begin block event
 pub struct BeginBlockInfo<'a> {
     pub params: &'a NetworkParameters,
@@ -49,7 +78,12 @@ pub struct BeginBlockInfo<'a> {
     pub block_height: BlockHeight,
     pub max_evidence_age: Timespec,
     pub voters: &'a [(TendermintValidatorAddress, bool)],
-    pub evidences: &'a [(TendermintValidatorAddress, BlockHeight, Timespec)],
+    pub evidences: &'a [(
+        TendermintValidatorAddress,
+        BlockHeight,
+        Timespec,
+        EvidenceKind,
+    )],
 }
 
 impl<'a> BeginBlockInfo<'a> {
@@ -95,8 +129,10 @@ impl<T: EnclaveProxy + 'static> abci::Application for ChainNodeApp<T> {
         info!("received checktx request");
         let mut resp = ResponseCheckTx::new();
         match self.process_tx(req, BufferType::Mempool) {
-            Ok(_) => {
+            Ok((_, tx_action)) => {
                 resp.set_code(0);
+                resp.events
+                    .push(generate_priority_event(tx_action.fee(), req.tx.len()));
             }
             Err(msg) => {
                 resp.set_code(1);
@@ -154,19 +190,20 @@ impl<T: EnclaveProxy + 'static> abci::Application for ChainNodeApp<T> {
         last_state.block_time = block_time;
         last_state.block_height = block_height;
 
-        // ignore the invalid items (logged)
+        // ignore the invalid items (logged),
+        // the evidence age and duplicates are checked by the staking table
         let evidences = req
             .byzantine_validators
             .iter()
             .filter_map(|ev| {
                 abci_validator(&ev.validator).and_then(|(addr, _)| {
                     abci_block_height(ev.height).and_then(|height| {
-                        abci_timespec(&ev.time).and_then(|time| {
-                            if time.saturating_add(last_state.max_evidence_age) > block_time {
-                                Some((addr, height, time))
-                            } else {
-                                None
+                        abci_timespec(&ev.time).map(|time| {
+                            let kind = EvidenceKind::from_abci(&ev.field_type);
+                            if kind == EvidenceKind::Unknown {
+                                log::warn!("unknown evidence type: {}", ev.field_type);
                             }
+                            (addr, height, time, kind)
                         })
                     })
                 })
@@ -196,6 +233,15 @@ impl<T: EnclaveProxy + 'static> abci::Application for ChainNodeApp<T> {
                 .expect("sum of bonded and unbonded slash amount exceed maximum coin");
             rewards_pool.period_bonus = (rewards_pool.period_bonus + slashed_amount)
                 .expect("rewards pool + fee greater than max coin?");
+            chain_storage::append_slash_record(
+                &mut kv_store!(self),
+                &punishment_outcome.staking_address,
+                SlashRecord {
+                    kind: punishment_outcome.punishment_kind,
+                    time: last_state.block_time,
+                    amount: slashed_amount,
+                },
+            );
 
             self.rewards_pool_updated = true;
 
@@ -204,13 +250,12 @@ impl<T: EnclaveProxy + 'static> abci::Application for ChainNodeApp<T> {
                 punishment_outcome.slashed_coin.bonded,
                 punishment_outcome.slashed_coin.unbonded,
                 punishment_outcome.punishment_kind,
+                punishment_outcome.delegated_to.as_ref(),
             );
             response.events.push(event.into());
 
-            if punishment_outcome.punishment_kind == PunishmentKind::ByzantineFault {
-                let jailed_until = punishment_outcome
-                    .jailed_until
-                    .expect("jailed until should exist when being jailed");
+            // the stake transferred out of the jailed staking is only slashed
+            if let Some(jailed_until) = punishment_outcome.jailed_until {
                 let event = StakingEvent::Jail(
                     &punishment_outcome.staking_address,
                     jailed_until,
@@ -255,7 +300,11 @@ impl<T: EnclaveProxy + 'static> abci::Application for ChainNodeApp<T> {
         match result {
             Ok((txaux, tx_action)) => {
                 let fee_amount = tx_action.fee().to_coin();
-                let tx_events = generate_tx_events(&txaux, tx_action);
+                let tx_events = generate_tx_events(
+                    &txaux,
+                    tx_action,
+                    &self.last_state.as_ref().unwrap().mls_group,
+                );
 
                 resp.set_code(0);
 
@@ -341,7 +390,13 @@ fn generate_reward_events(distribution: RewardsDistribution, minted: Coin) -> Ve
     let mut events: Vec<Event> = Vec::new();
 
     for reward in distribution.iter() {
-        let event = StakingEvent::Reward(&reward.0, reward.1).into();
+        let event = StakingEvent::Reward(
+            &reward.staking_address,
+            reward.amount,
+            reward.commission,
+            reward.delegated_to.as_ref(),
+        )
+        .into();
 
         events.push(event);
     }
@@ -362,7 +417,11 @@ fn generate_reward_events(distribution: RewardsDistribution, minted: Coin) -> Ve
     events
 }
 
-fn generate_tx_events(txaux: &TxAux, tx_action: TxAction) -> Vec<abci::Event> {
+fn generate_tx_events(
+    txaux: &TxAux,
+    tx_action: TxAction,
+    mls_group: &MLSGroupState,
+) -> Vec<abci::Event> {
     let mut events = Vec::new();
 
     let mut valid_txs_event = Event::new();
@@ -381,14 +440,60 @@ fn generate_tx_events(txaux: &TxAux, tx_action: TxAction) -> Vec<abci::Event> {
 
     events.push(valid_txs_event);
 
+    if let TxAction::MLSHandshake(action) = &tx_action {
+        events.push(MLSHandshakeEvent(action, mls_group.epoch).into());
+    }
+
+    // the destination of the stake transfer has its own staking change event
+    let maybe_tx_transfer_in_event = match &tx_action {
+        TxAction::Public(TxPublicAction::TransferStake {
+            to,
+            kind,
+            value,
+            unbonded_from,
+            ..
+        }) => Some(StakingEvent::TransferStakeIn(to, *kind, *value, *unbonded_from).into()),
+        _ => None,
+    };
+
     let maybe_tx_staking_event = generate_tx_staking_change_event(tx_action);
     if let Some(tx_staking_event) = maybe_tx_staking_event {
         events.push(tx_staking_event);
     }
+    if let Some(tx_transfer_in_event) = maybe_tx_transfer_in_event {
+        events.push(tx_transfer_in_event);
+    }
 
     events
 }
 
+/// the priority of a transaction in the mempool: the paid fee per byte
+/// (the transactions paying more than the minimal fee get a higher priority)
+fn tx_priority(fee: Fee, tx_len: usize) -> u64 {
+    let fee: u64 = fee.to_coin().into();
+    fee / (tx_len.max(1) as u64)
+}
+
+/// Tendermint 0.33 mempool doesn't order transactions by the application's priority
+/// (`ResponseCheckTx.priority` is only in the ABCI of Tendermint 0.35, which isn't supported yet),
+/// so it's only reported as an event attribute of the checked transaction
+fn generate_priority_event(fee: Fee, tx_len: usize) -> abci::Event {
+    let mut event = Event::new();
+    event.field_type = TendermintEventType::ValidTransactions.to_string();
+
+    let mut fee_kvpair = KVPair::new();
+    fee_kvpair.key = TendermintEventKey::Fee.into();
+    fee_kvpair.value = Vec::from(format!("{}", fee.to_coin()));
+    event.attributes.push(fee_kvpair);
+
+    let mut priority_kvpair = KVPair::new();
+    priority_kvpair.key = TendermintEventKey::Priority.into();
+    priority_kvpair.value = Vec::from(format!("{}", tx_priority(fee, tx_len)));
+    event.attributes.push(priority_kvpair);
+
+    event
+}
+
 fn generate_tx_staking_change_event(tx_action: TxAction) -> Option<abci::Event> {
     match tx_action {
         TxAction::Enclave(tx_enclave_action) => match tx_enclave_action {
@@ -412,10 +517,31 @@ fn generate_tx_staking_change_event(tx_action: TxAction) -> Option<abci::Event>
                 council_node,
                 ..
             } => Some(StakingEvent::NodeJoin(&address, council_node).into()),
+            TxPublicAction::NodeMetadataUpdate {
+                address,
+                council_node,
+                ..
+            } => Some(StakingEvent::NodeMetadataUpdate(&address, council_node).into()),
             TxPublicAction::Unjail(staking_address) => {
                 Some(StakingEvent::Unjail(&staking_address).into())
             }
+            TxPublicAction::TransferStake {
+                fee,
+                from,
+                kind,
+                value,
+                ..
+            } => Some(StakingEvent::TransferStakeOut(&from, kind, value, fee).into()),
+            // only the nonce of the council node is changed
+            TxPublicAction::ParamChangeProposal { .. } | TxPublicAction::ParamChangeVote { .. } => {
+                None
+            }
+            TxPublicAction::Delegate {
+                address,
+                council_node,
+            } => Some(StakingEvent::Delegate(&address, council_node.as_ref()).into()),
         },
+        TxAction::MLSHandshake(_) => None,
     }
 }
 
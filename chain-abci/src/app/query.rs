@@ -1,15 +1,21 @@
 use std::convert::{TryFrom, TryInto};
 
-use super::ChainNodeApp;
+use super::{ChainNodeApp, ChainNodeState};
 use crate::enclave_bridge::EnclaveProxy;
+use crate::peer_filter::PeerFilterError;
 use abci::*;
 use chain_core::common::{MerkleTree, Proof as MerkleProof, H256, HASH_SIZE_256};
+use chain_core::init::params::NetworkParametersVersion;
 use chain_core::state::account::StakedStateAddress;
 use chain_core::state::tendermint::BlockHeight;
 use chain_core::state::ChainState;
+use chain_core::tx::data::input::TxoPointer;
 use chain_core::tx::data::{txid_hash, TXID_HASH_ID};
-use chain_storage::jellyfish::get_with_proof;
+use chain_core::AppHashParts;
+use chain_storage::buffer::Get;
+use chain_storage::jellyfish::{get_with_proof, SparseMerkleProof};
 use chain_storage::LookupItem;
+use log::warn;
 use parity_scale_codec::{Decode, Encode};
 
 /// Generate generic ABCI ProofOp for the witness
@@ -21,6 +27,26 @@ fn get_witness_proof_op(witness: &[u8]) -> ProofOp {
     op
 }
 
+/// Generate ABCI ProofOp for the staked state (leaf -> staking trie root)
+fn get_staking_proof_op(address: &StakedStateAddress, proof: &SparseMerkleProof) -> ProofOp {
+    ProofOp {
+        field_type: "staking".to_owned(),
+        key: address.encode(),
+        data: proof.encode(),
+        ..Default::default()
+    }
+}
+
+/// Generate ABCI ProofOp for the hashed parts (e.g. staking trie root or txs merkle root) -> app hash
+fn get_app_hash_proof_op(parts: &AppHashParts) -> ProofOp {
+    ProofOp {
+        field_type: "app_hash".to_owned(),
+        key: parts.app_hash().to_vec(),
+        data: parts.encode(),
+        ..Default::default()
+    }
+}
+
 fn get_key(resp: &mut ResponseQuery, data_key: &[u8]) -> Option<H256> {
     if data_key.len() != HASH_SIZE_256 {
         resp.log += "invalid txid or app hash length";
@@ -70,6 +96,68 @@ impl<T: EnclaveProxy + 'static> ChainNodeApp<T> {
         None
     }
 
+    /// the last committed state (or the genesis one if no block was committed yet);
+    /// None if the node isn't initialized or the stored state can't be decoded
+    fn committed_state(&self) -> Option<ChainNodeState> {
+        match self.storage.get_last_app_state() {
+            Some(raw) => match ChainNodeState::decode(&mut raw.as_slice()) {
+                Ok(state) => Some(state),
+                Err(e) => {
+                    warn!(
+                        "failed to decode the last committed chain node state: {}",
+                        e
+                    );
+                    None
+                }
+            },
+            None => self.last_state.clone(),
+        }
+    }
+
+    /// the hashed parts of the committed state's app hash (None if the stored data can't be decoded)
+    fn committed_app_hash_parts(&self, state: &ChainNodeState) -> Option<AppHashParts> {
+        if let Some(parts) = chain_storage::get_app_hash_parts(&self.storage, &state.last_apphash) {
+            return Some(parts);
+        }
+        // genesis (no block committed yet)
+        let tree = match self
+            .storage
+            .lookup_item(LookupItem::TxsMerkle, &state.last_apphash)
+        {
+            Some(data) => MerkleTree::decode(&mut data.as_slice()).ok()?,
+            None => MerkleTree::empty(),
+        };
+        Some(AppHashParts::new(&tree, &state.top_level))
+    }
+
+    /// checks the peer in "/p2p/filter/addr/<IP:PORT>" or "/p2p/filter/id/<ID>" query path
+    fn filter_peer(&self, path: &str) -> Result<(), PeerFilterError> {
+        let filter = self.peer_filter.read().expect("peer filter lock poisoned");
+        let mut parts = path.trim_start_matches('/').splitn(4, '/');
+        match (parts.next(), parts.next(), parts.next(), parts.next()) {
+            (Some("p2p"), Some("filter"), Some("addr"), Some(addr)) => filter.check_addr(addr),
+            (Some("p2p"), Some("filter"), Some("id"), Some(id)) => {
+                filter.check_id(id)?;
+                let jailed = filter
+                    .validator_address(id)
+                    .map_or(false, |address| self.is_jailed_validator(&address));
+                if filter.deny_jailed_validators && jailed {
+                    Err(PeerFilterError::JailedValidator)
+                } else {
+                    Ok(())
+                }
+            }
+            _ => Err(PeerFilterError::InvalidPath),
+        }
+    }
+
+    /// if the staking address belongs to a jailed validator (in the committed state)
+    fn is_jailed_validator(&self, address: &StakedStateAddress) -> bool {
+        self.staking_getter_committed()
+            .get(address)
+            .map_or(false, |staking| staking.is_jailed())
+    }
+
     /// Responds to query requests -- note that path is hex-encoded in the original request on the client side
     /// e.g. "store" == 0x73746f7265.
     pub fn query_handler(&self, _req: &RequestQuery) -> ResponseQuery {
@@ -80,7 +168,10 @@ impl<T: EnclaveProxy + 'static> ChainNodeApp<T> {
         // * p2p/filter/id/<ID>, where <ID> is the peer node ID (ie. the pubkey.Address() for the peer's PubKey)
         // If either of these queries return a non-zero ABCI code, Tendermint will refuse to connect to the peer."
         if _req.path.starts_with("/p2p") || _req.path.starts_with("p2p") {
-            // TODO: peer filtering
+            if let Err(e) = self.filter_peer(&_req.path) {
+                resp.log += &e.to_string();
+                resp.code = 1;
+            }
             return resp;
         }
 
@@ -121,21 +212,43 @@ impl<T: EnclaveProxy + 'static> ChainNodeApp<T> {
                             };
                         // note this should not crash if Tendermint delivers all blocks with height in order
                         // TODO: invariant / sanity check in rust-abci?
-                        let app_hash = self.storage.get_historical_app_hash(height).unwrap();
-                        let data = self
-                            .storage
-                            .lookup_item(LookupItem::TxsMerkle, &app_hash)
-                            .unwrap();
+                        let mtree =
+                            self.storage
+                                .get_historical_app_hash(height)
+                                .and_then(|app_hash| {
+                                    self.storage
+                                        .lookup_item(LookupItem::TxsMerkle, &app_hash)
+                                        .map(|data| (app_hash, data))
+                                });
+                        let (app_hash, data) = match mtree {
+                            Some(tree) => tree,
+                            None => {
+                                resp.log += "proof error: history of the height was pruned";
+                                resp.code = 2;
+                                return resp;
+                            }
+                        };
                         let tree = MerkleTree::decode(&mut data.as_slice()).expect("merkle tree");
 
-                        // TODO: Change this in future to include individual ops?
-                        let proof_ops = match tree.generate_proof(txid) {
-                            None => vec![get_witness_proof_op(&witness[..])],
-                            Some(merkle_proof) => vec![
+                        // the txs merkle root -> app hash part is missing in the blocks
+                        // committed before the app hash parts were stored
+                        let proof_ops = match (
+                            tree.generate_proof(txid),
+                            chain_storage::get_app_hash_parts(&self.storage, &app_hash),
+                        ) {
+                            (None, _) => vec![get_witness_proof_op(&witness[..])],
+                            (Some(merkle_proof), None) => vec![
+                                into_proof_op(tree.root_hash(), merkle_proof),
+                                get_witness_proof_op(&witness[..]),
+                            ],
+                            (Some(merkle_proof), Some(parts)) => vec![
                                 into_proof_op(tree.root_hash(), merkle_proof),
+                                get_app_hash_proof_op(&parts),
                                 get_witness_proof_op(&witness[..]),
                             ],
                         };
+                        // the app hash is included in the header of the next block
+                        resp.height = height.value().try_into().expect("block height overflow");
 
                         let mut proof = Proof::new();
                         proof.set_ops(proof_ops.into());
@@ -172,13 +285,35 @@ impl<T: EnclaveProxy + 'static> ChainNodeApp<T> {
             }
             "account" => {
                 let account_address = StakedStateAddress::try_from(_req.data.as_slice());
-                if let (Some(state), Ok(address)) = (&self.last_state, account_address) {
-                    let (account, _proof) =
+                if let (Some(state), Ok(address)) = (self.committed_state(), account_address) {
+                    let (account, proof) =
                         get_with_proof(&self.storage, state.staking_version, &address);
                     match account {
                         Some(a) => {
                             resp.value = a.encode();
-                            // TODO: inclusion proof
+                            if _req.prove {
+                                match (
+                                    state.last_block_height.value().try_into(),
+                                    self.committed_app_hash_parts(&state),
+                                ) {
+                                    (Ok(height), Some(parts)) => {
+                                        // the app hash is included in the header of the next block
+                                        resp.height = height;
+                                        resp.set_proof(Proof {
+                                            ops: vec![
+                                                get_staking_proof_op(&address, &proof),
+                                                get_app_hash_proof_op(&parts),
+                                            ]
+                                            .into(),
+                                            ..Default::default()
+                                        });
+                                    }
+                                    _ => {
+                                        resp.log += "account proof failed: committed app hash parts not available";
+                                        resp.code = 2;
+                                    }
+                                }
+                            }
                         }
                         None => {
                             resp.log += "account lookup failed: account not exists";
@@ -202,13 +337,7 @@ impl<T: EnclaveProxy + 'static> ChainNodeApp<T> {
                     resp.value = mstaking.encode();
                     if _req.prove {
                         resp.set_proof(Proof {
-                            ops: vec![ProofOp {
-                                field_type: "staking".to_owned(),
-                                key: address.encode(),
-                                data: proof.encode(),
-                                ..Default::default()
-                            }]
-                            .into(),
+                            ops: vec![get_staking_proof_op(&address, &proof)].into(),
                             ..Default::default()
                         });
                     }
@@ -253,6 +382,97 @@ impl<T: EnclaveProxy + 'static> ChainNodeApp<T> {
                     .expect("Unable to serialize validator metadata into json")
                     .into_bytes();
             }
+            "network-params" => {
+                // the current network parameters or the version in data (u32 SCALE-encoded)
+                let params = if _req.data.is_empty() {
+                    self.last_state
+                        .as_ref()
+                        .map(|state| state.top_level.network_params.clone())
+                } else {
+                    NetworkParametersVersion::decode(&mut _req.data.as_slice())
+                        .ok()
+                        .and_then(|version| {
+                            chain_storage::get_network_params(&self.storage, version)
+                        })
+                };
+                match params {
+                    Some(params) => {
+                        resp.value = params.encode();
+                    }
+                    None => {
+                        resp.log += "network parameters not found";
+                        resp.code = 1;
+                    }
+                }
+            }
+            "base-fee" => {
+                // the multiplier of the fee policy for the next block
+                match self.last_state.as_ref() {
+                    Some(state) => {
+                        resp.value = state.top_level.base_fee.encode();
+                    }
+                    None => {
+                        resp.log +=
+                            "base fee lookup failed: node not correctly restored / initialized";
+                        resp.code = 3;
+                    }
+                }
+            }
+            "governance" => {
+                resp.value = self
+                    .last_state
+                    .as_ref()
+                    .expect("Missing last_state: init chain was not called")
+                    .governance
+                    .get_proposals()
+                    .encode();
+            }
+            "slash-history" => match StakedStateAddress::try_from(_req.data.as_slice()) {
+                Ok(address) => {
+                    resp.value = chain_storage::get_slash_history(&self.storage, &address).encode();
+                }
+                Err(_) => {
+                    resp.log += "slash history lookup failed: invalid address";
+                    resp.code = 3;
+                }
+            },
+            "spent-by" => {
+                // the transaction which spent the output (SCALE-encoded TxoPointer in data);
+                // empty value if the output wasn't spent
+                if !self.utxo_index {
+                    resp.log += "utxo index not enabled";
+                    resp.code = 1;
+                } else {
+                    match TxoPointer::decode(&mut _req.data.as_slice()) {
+                        Ok(txin) => {
+                            if let Some(spent) = chain_storage::get_txo_spent(&self.storage, &txin)
+                            {
+                                resp.value = spent.encode();
+                            }
+                        }
+                        Err(_) => {
+                            resp.log += "invalid txo pointer";
+                            resp.code = 4;
+                        }
+                    }
+                }
+            }
+            "tx-height" => {
+                if !self.utxo_index {
+                    resp.log += "utxo index not enabled";
+                    resp.code = 1;
+                } else if let Some(txid) = get_key(&mut resp, &_req.data) {
+                    match chain_storage::get_tx_height(&self.storage, &txid) {
+                        Some(height) => {
+                            resp.value = height.encode();
+                        }
+                        None => {
+                            resp.log += "tx not found";
+                            resp.code = 1;
+                        }
+                    }
+                }
+            }
             "sealed" => {
                 self.lookup(
                     &mut resp,
@@ -2,9 +2,9 @@ use std::cmp::min;
 
 use crate::app::ChainNodeApp;
 use crate::enclave_bridge::EnclaveProxy;
+use crate::staking::RewardsDistribution;
 use chain_core::fixed::monetary_expansion;
 use chain_core::init::coin::Coin;
-use chain_core::state::account::StakedStateAddress;
 use chain_storage::jellyfish::StakingGetter;
 
 // rate < 1_000_000, no overflow.
@@ -15,8 +15,6 @@ fn mul_micro(n: u64, rate: u64) -> u64 {
     div * rate + rem * rate / 1_000_000
 }
 
-pub type RewardsDistribution = Vec<(StakedStateAddress, Coin)>;
-
 impl<T: EnclaveProxy + 'static> ChainNodeApp<T> {
     /// Distribute rewards pool
     pub fn rewards_try_distribute(&mut self) -> Option<(RewardsDistribution, Coin)> {
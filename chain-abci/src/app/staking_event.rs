@@ -6,7 +6,7 @@ use abci::Pair as KVPair;
 use abci::*;
 use chain_core::common::{TendermintEventKey, TendermintEventType, Timespec};
 use chain_core::init::coin::Coin;
-use chain_core::state::account::{CouncilNodeMeta, PunishmentKind, StakedStateAddress};
+use chain_core::state::account::{CouncilNodeMeta, PunishmentKind, StakeKind, StakedStateAddress};
 use chain_core::tx::fee::Fee;
 
 pub(crate) enum StakingEvent<'a> {
@@ -14,10 +14,27 @@ pub(crate) enum StakingEvent<'a> {
     Unbond(&'a StakedStateAddress, Coin, Timespec, Fee),
     Withdraw(&'a StakedStateAddress, Coin),
     NodeJoin(&'a StakedStateAddress, CouncilNodeMeta),
-    Reward(&'a StakedStateAddress, Coin),
+    NodeMetadataUpdate(&'a StakedStateAddress, CouncilNodeMeta),
+    /// reward amount, commission kept (council node) and the council node backed (delegator)
+    Reward(
+        &'a StakedStateAddress,
+        Coin,
+        Coin,
+        Option<&'a StakedStateAddress>,
+    ),
     Jail(&'a StakedStateAddress, Timespec, PunishmentKind),
-    Slash(&'a StakedStateAddress, Coin, Coin, PunishmentKind),
+    /// the council node is set if the staking address is slashed because it backs it
+    Slash(
+        &'a StakedStateAddress,
+        Coin,
+        Coin,
+        PunishmentKind,
+        Option<&'a StakedStateAddress>,
+    ),
     Unjail(&'a StakedStateAddress),
+    TransferStakeOut(&'a StakedStateAddress, StakeKind, Coin, Fee),
+    TransferStakeIn(&'a StakedStateAddress, StakeKind, Coin, Option<Timespec>),
+    Delegate(&'a StakedStateAddress, Option<&'a StakedStateAddress>),
 }
 
 impl<'a> From<StakingEvent<'a>> for Event {
@@ -37,8 +54,11 @@ impl<'a> From<StakingEvent<'a>> for Event {
             StakingEvent::NodeJoin(staking_address, council_node) => {
                 builder.node_join(staking_address, council_node)
             }
-            StakingEvent::Reward(staking_address, reward_amount) => {
-                builder.reward(staking_address, reward_amount)
+            StakingEvent::NodeMetadataUpdate(staking_address, council_node) => {
+                builder.node_metadata_update(staking_address, council_node)
+            }
+            StakingEvent::Reward(staking_address, reward_amount, commission, delegated_to) => {
+                builder.reward(staking_address, reward_amount, commission, delegated_to)
             }
             StakingEvent::Jail(staking_address, timespec, punishment_kind) => {
                 builder.jail(staking_address, timespec, punishment_kind)
@@ -48,13 +68,24 @@ impl<'a> From<StakingEvent<'a>> for Event {
                 bonded_slash_amount,
                 unbonded_slash_amount,
                 punishment_kind,
+                delegated_to,
             ) => builder.slash(
                 staking_address,
                 bonded_slash_amount,
                 unbonded_slash_amount,
                 punishment_kind,
+                delegated_to,
             ),
             StakingEvent::Unjail(staking_address) => builder.unjail(staking_address),
+            StakingEvent::TransferStakeOut(staking_address, kind, amount, fee) => {
+                builder.transfer_stake_out(staking_address, kind, amount, fee)
+            }
+            StakingEvent::TransferStakeIn(staking_address, kind, amount, unbonded_from) => {
+                builder.transfer_stake_in(staking_address, kind, amount, unbonded_from)
+            }
+            StakingEvent::Delegate(staking_address, council_node) => {
+                builder.delegate(staking_address, council_node)
+            }
         }
 
         builder.to_event()
@@ -127,7 +158,26 @@ impl StakingEventBuilder {
             .push(StakingDiffField(vec![StakingDiff::NodeJoin(node)]).into());
     }
 
-    fn reward(&mut self, staking_address: &StakedStateAddress, reward_amount: Coin) {
+    fn node_metadata_update(
+        &mut self,
+        staking_address: &StakedStateAddress,
+        node: CouncilNodeMeta,
+    ) {
+        self.attributes
+            .push(staking_address_attribute(staking_address));
+        self.attributes
+            .push(StakingEventOpType::NodeMetadataUpdate.into());
+        self.attributes
+            .push(StakingDiffField(vec![StakingDiff::NodeJoin(node)]).into());
+    }
+
+    fn reward(
+        &mut self,
+        staking_address: &StakedStateAddress,
+        reward_amount: Coin,
+        commission: Coin,
+        delegated_to: Option<&StakedStateAddress>,
+    ) {
         self.attributes
             .push(staking_address_attribute(staking_address));
         self.attributes.push(StakingEventOpType::Reward.into());
@@ -138,6 +188,16 @@ impl StakingEventBuilder {
             )])
             .into(),
         );
+
+        if commission != Coin::zero() {
+            let mut commission_kv_pair = KVPair::new();
+            commission_kv_pair.key = TendermintEventKey::Commission.into();
+            commission_kv_pair.value = u64::from(commission).to_string().into_bytes();
+            self.attributes.push(commission_kv_pair);
+        }
+        if let Some(council_node) = delegated_to {
+            self.attributes.push(delegated_to_attribute(council_node));
+        }
     }
 
     fn jail(
@@ -164,6 +224,7 @@ impl StakingEventBuilder {
         bonded_slash_amount: Coin,
         unbonded_slash_amount: Coin,
         punishment_kind: PunishmentKind,
+        delegated_to: Option<&StakedStateAddress>,
     ) {
         self.attributes
             .push(staking_address_attribute(staking_address));
@@ -179,7 +240,11 @@ impl StakingEventBuilder {
         let mut reason_kv_pair = KVPair::new();
         reason_kv_pair.key = TendermintEventKey::StakingOpReason.into();
         reason_kv_pair.value = punishment_reason(punishment_kind).into_bytes();
-        self.attributes.push(reason_kv_pair)
+        self.attributes.push(reason_kv_pair);
+
+        if let Some(council_node) = delegated_to {
+            self.attributes.push(delegated_to_attribute(council_node));
+        }
     }
 
     fn unjail(&mut self, staking_address: &StakedStateAddress) {
@@ -188,6 +253,61 @@ impl StakingEventBuilder {
         self.attributes.push(StakingEventOpType::Unjail.into());
     }
 
+    fn transfer_stake_out(
+        &mut self,
+        staking_address: &StakedStateAddress,
+        kind: StakeKind,
+        amount: Coin,
+        fee: Fee,
+    ) {
+        self.attributes
+            .push(staking_address_attribute(staking_address));
+        self.attributes
+            .push(StakingEventOpType::TransferStake.into());
+
+        let amount = (amount + fee.to_coin()).unwrap();
+        let diff = match kind {
+            StakeKind::Bonded => StakingDiff::Bonded(StakingCoinChange::Decrease, amount),
+            StakeKind::Unbonded => StakingDiff::Unbonded(StakingCoinChange::Decrease, amount),
+        };
+        self.attributes.push(StakingDiffField(vec![diff]).into());
+    }
+
+    fn transfer_stake_in(
+        &mut self,
+        staking_address: &StakedStateAddress,
+        kind: StakeKind,
+        amount: Coin,
+        unbonded_from: Option<Timespec>,
+    ) {
+        self.attributes
+            .push(staking_address_attribute(staking_address));
+        self.attributes
+            .push(StakingEventOpType::TransferStake.into());
+
+        let mut diff = vec![match kind {
+            StakeKind::Bonded => StakingDiff::Bonded(StakingCoinChange::Increase, amount),
+            StakeKind::Unbonded => StakingDiff::Unbonded(StakingCoinChange::Increase, amount),
+        }];
+        if let Some(unbonded_from) = unbonded_from {
+            diff.push(StakingDiff::UnbondedFrom(unbonded_from));
+        }
+        self.attributes.push(StakingDiffField(diff).into());
+    }
+
+    fn delegate(
+        &mut self,
+        staking_address: &StakedStateAddress,
+        council_node: Option<&StakedStateAddress>,
+    ) {
+        self.attributes
+            .push(staking_address_attribute(staking_address));
+        self.attributes.push(StakingEventOpType::Delegate.into());
+        if let Some(council_node) = council_node {
+            self.attributes.push(delegated_to_attribute(council_node));
+        }
+    }
+
     fn to_event(&self) -> Event {
         let mut event = Event::new();
         event.field_type = TendermintEventType::StakingChange.to_string();
@@ -208,6 +328,15 @@ fn staking_address_attribute(staking_address: &StakedStateAddress) -> KVPair {
     kv_pair
 }
 
+#[inline]
+fn delegated_to_attribute(council_node: &StakedStateAddress) -> KVPair {
+    let mut kv_pair = KVPair::new();
+    kv_pair.key = TendermintEventKey::DelegatedTo.into();
+    kv_pair.value = council_node.to_string().into_bytes();
+
+    kv_pair
+}
+
 #[inline]
 fn punishment_reason(punishment_kind: PunishmentKind) -> String {
     match punishment_kind {
@@ -221,10 +350,13 @@ enum StakingEventOpType {
     Unbond,
     Withdraw,
     NodeJoin,
+    NodeMetadataUpdate,
     Reward,
     Jail,
     Slash,
     Unjail,
+    TransferStake,
+    Delegate,
 }
 
 impl fmt::Display for StakingEventOpType {
@@ -234,10 +366,13 @@ impl fmt::Display for StakingEventOpType {
             StakingEventOpType::Unbond => write!(f, "unbond"),
             StakingEventOpType::Withdraw => write!(f, "withdraw"),
             StakingEventOpType::NodeJoin => write!(f, "nodejoin"),
+            StakingEventOpType::NodeMetadataUpdate => write!(f, "nodemetadataupdate"),
             StakingEventOpType::Reward => write!(f, "reward"),
             StakingEventOpType::Jail => write!(f, "jail"),
             StakingEventOpType::Slash => write!(f, "slash"),
             StakingEventOpType::Unjail => write!(f, "unjail"),
+            StakingEventOpType::TransferStake => write!(f, "transferstake"),
+            StakingEventOpType::Delegate => write!(f, "delegate"),
         }
     }
 }
@@ -437,7 +572,7 @@ mod tests {
 
                 assert_eq!(
                     staking_diff.to_string(),
-                    "{\"key\":\"CouncilNode\",\"value\":{\"name\":\"Council Node\",\"security_contact\":\"security@crypto.com\",\"confidential_init\":{\"keypackage\":\"AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA=\"},\"consensus_pubkey\":{\"type\":\"tendermint/PubKeyEd25519\",\"value\":\"AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA=\"}}}",
+                    "{\"key\":\"CouncilNode\",\"value\":{\"name\":\"Council Node\",\"security_contact\":\"security@crypto.com\",\"confidential_init\":{\"keypackage\":\"AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA=\"},\"consensus_pubkey\":{\"type\":\"tendermint/PubKeyEd25519\",\"value\":\"AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA=\"},\"commission_rate\":\"0.000\"}}",
                 );
             }
 
@@ -538,7 +673,31 @@ mod tests {
                 let event: Event =
                     StakingEvent::NodeJoin(&any_staking_address, any_council_node.clone()).into();
 
-                assert_node_join_event(event, &any_staking_address, any_council_node)
+                assert_node_join_event(
+                    event,
+                    &any_staking_address,
+                    StakingEventOpType::NodeJoin,
+                    any_council_node,
+                )
+            }
+
+            #[test]
+            fn should_create_node_metadata_update_event() {
+                let any_staking_address = any_staking_address();
+                let any_council_node = any_council_node();
+
+                let event: Event = StakingEvent::NodeMetadataUpdate(
+                    &any_staking_address,
+                    any_council_node.clone(),
+                )
+                .into();
+
+                assert_node_join_event(
+                    event,
+                    &any_staking_address,
+                    StakingEventOpType::NodeMetadataUpdate,
+                    any_council_node,
+                )
             }
 
             fn any_council_node() -> CouncilNodeMeta {
@@ -566,10 +725,46 @@ mod tests {
                 let any_staking_address = any_staking_address();
                 let any_amount = Coin::unit();
 
-                let event: Event = StakingEvent::Reward(&any_staking_address, any_amount).into();
+                let event: Event =
+                    StakingEvent::Reward(&any_staking_address, any_amount, Coin::zero(), None)
+                        .into();
 
                 assert_reward_event(event, any_staking_address, any_amount);
             }
+
+            #[test]
+            fn should_create_delegated_reward_event() {
+                let any_staking_address = any_staking_address();
+                let any_council_node =
+                    StakedStateAddress::from_str("0xbc2e4e4e3e0a8c3c2e2a6d2c7e9f3e0d5d2c5b4a")
+                        .unwrap();
+                let any_amount = Coin::unit();
+
+                let event: Event = StakingEvent::Reward(
+                    &any_staking_address,
+                    any_amount,
+                    Coin::zero(),
+                    Some(&any_council_node),
+                )
+                .into();
+
+                assert_eq!(event.attributes.len(), 4);
+                assert_kv_pair(
+                    event.attributes.get(3).unwrap(),
+                    TendermintEventKey::DelegatedTo.to_string(),
+                    any_council_node.to_string(),
+                );
+
+                let event: Event =
+                    StakingEvent::Reward(&any_council_node, any_amount, Coin::unit(), None).into();
+
+                assert_eq!(event.attributes.len(), 4);
+                assert_kv_pair(
+                    event.attributes.get(3).unwrap(),
+                    TendermintEventKey::Commission.to_string(),
+                    "1".to_owned(),
+                );
+            }
         }
 
         mod jail {
@@ -603,6 +798,7 @@ mod tests {
                     any_bonded_slash_amount,
                     any_unbonded_slash_amount,
                     any_jail_reason,
+                    None,
                 )
                 .into();
 
@@ -629,6 +825,74 @@ mod tests {
             }
         }
 
+        mod transfer_stake {
+            use super::*;
+
+            #[test]
+            fn should_create_transfer_stake_events() {
+                let any_staking_address = any_staking_address();
+                let any_amount = Coin::unit();
+                let any_fee = Fee::new(Coin::unit());
+                let any_time: Timespec = 1587071014;
+
+                let event: Event = StakingEvent::TransferStakeOut(
+                    &any_staking_address,
+                    StakeKind::Bonded,
+                    any_amount,
+                    any_fee,
+                )
+                .into();
+                assert_transfer_stake_event(
+                    event,
+                    &any_staking_address,
+                    "[{\"key\":\"Bonded\",\"value\":\"-2\"}]".to_owned(),
+                );
+
+                let event: Event = StakingEvent::TransferStakeIn(
+                    &any_staking_address,
+                    StakeKind::Unbonded,
+                    any_amount,
+                    Some(any_time),
+                )
+                .into();
+                assert_transfer_stake_event(
+                    event,
+                    &any_staking_address,
+                    format!(
+                        "[{{\"key\":\"Unbonded\",\"value\":\"1\"}},{{\"key\":\"UnbondedFrom\",\"value\":{}}}]",
+                        any_time
+                    ),
+                );
+            }
+
+            fn assert_transfer_stake_event(
+                event: Event,
+                staking_address: &StakedStateAddress,
+                expected_diff: String,
+            ) {
+                assert_eq!(
+                    event.field_type,
+                    TendermintEventType::StakingChange.to_string()
+                );
+                assert_eq!(event.attributes.len(), 3);
+                assert_kv_pair(
+                    event.attributes.first().unwrap(),
+                    TendermintEventKey::StakingAddress.to_string(),
+                    staking_address.to_string(),
+                );
+                assert_kv_pair(
+                    event.attributes.get(1).unwrap(),
+                    TendermintEventKey::StakingOpType.to_string(),
+                    StakingEventOpType::TransferStake.to_string(),
+                );
+                assert_kv_pair(
+                    event.attributes.get(2).unwrap(),
+                    TendermintEventKey::StakingDiff.to_string(),
+                    expected_diff,
+                );
+            }
+        }
+
         fn assert_deposit_event(
             event: Event,
             staking_address: StakedStateAddress,
@@ -746,6 +1010,7 @@ mod tests {
         fn assert_node_join_event(
             event: Event,
             staking_address: &StakedStateAddress,
+            op_type: StakingEventOpType,
             council_node: CouncilNodeMeta,
         ) {
             assert_eq!(
@@ -765,7 +1030,7 @@ mod tests {
             assert_kv_pair(
                 staking_optype_attribute,
                 TendermintEventKey::StakingOpType.to_string(),
-                StakingEventOpType::NodeJoin.to_string(),
+                op_type.to_string(),
             );
 
             let staking_diff_attribute = event.attributes.get(2).unwrap();
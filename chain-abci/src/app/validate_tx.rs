@@ -3,12 +3,15 @@ use crate::enclave_bridge::EnclaveProxy;
 use crate::storage::{
     process_public_tx, verify_enclave_tx, TxAction, TxEnclaveAction, TxPublicAction,
 };
+use crate::tdbe::verify_mls_handshake;
 use crate::tx_error::TxError;
 use abci::*;
+use chain_core::tx::data::output::TxInclusion;
 use chain_core::tx::data::TxId;
 use chain_core::tx::TxAux;
 use chain_storage::buffer::{StoreKV, StoreStaking};
 use parity_scale_codec::Decode;
+use ra_client::ENCLAVE_CERT_VERIFIER;
 
 /// Wrapper to abstract over CheckTx and DeliverTx requests
 pub trait RequestWithTx {
@@ -74,7 +77,23 @@ impl<T: EnclaveProxy + 'static> ChainNodeApp<T> {
         let txaux = TxAux::decode(&mut req.tx())?;
         let txid = txaux.tx_id();
         let tx_action = match &txaux {
-            TxAux::MLSHandshake(_) => return Err(TxError::WIPMLSData),
+            TxAux::MLSHandshake(handshake) => {
+                let staking_getter = staking_getter!(self, state.staking_version, buffer_type);
+                let verified = verify_mls_handshake(
+                    &state.mls_group,
+                    handshake,
+                    |keypackage| {
+                        state
+                            .staking_table
+                            .is_active_council_keypackage(&staking_getter, keypackage)
+                    },
+                    &*ENCLAVE_CERT_VERIFIER,
+                    extra_info.block_time,
+                )?;
+                state.mls_group.apply(&txid, &verified, state.block_height);
+                state.top_level.mls_group_hash = state.mls_group.hash();
+                TxAction::MLSHandshake(verified.action)
+            }
             TxAux::EnclaveTx(tx) => {
                 let action = verify_enclave_tx(
                     &mut self.tx_validator,
@@ -118,9 +137,12 @@ impl<T: EnclaveProxy + 'static> ChainNodeApp<T> {
                 TxAction::Enclave(action)
             }
             TxAux::PublicTx(tx) => {
+                let governance_params = state.top_level.network_params.get_governance_config();
                 let action = process_public_tx(
                     &mut staking_store!(self, state.staking_version, buffer_type),
                     &mut state.staking_table,
+                    &mut state.governance,
+                    &governance_params,
                     state.enclave_isv_svn,
                     &extra_info,
                     &tx,
@@ -154,6 +176,11 @@ fn execute_enclave_tx(
             // Done in commit event
             // storage.create_utxo(no_of_outputs, txid);
             chain_storage::store_sealed_log(kvdb, &txid, sealed_log);
+            chain_storage::store_tx_inclusion(
+                kvdb,
+                &txid,
+                &TxInclusion::new(state.block_time, state.block_height),
+            );
         }
         TxEnclaveAction::Deposit {
             spend_utxo,
@@ -174,6 +201,11 @@ fn execute_enclave_tx(
             // Done in commit event
             // storage.create_utxo(no_of_outputs, txid);
             chain_storage::store_sealed_log(kvdb, &txid, sealed_log);
+            chain_storage::store_tx_inclusion(
+                kvdb,
+                &txid,
+                &TxInclusion::new(state.block_time, state.block_height),
+            );
 
             // no panic: tx is already verified, all the error in execution is not allowed.
             // operations are sequential in the state machine, so no concurrent updates
@@ -1,5 +1,6 @@
 use crate::app::ChainNodeState;
 use crate::enclave_bridge::EnclaveProxy;
+use crate::storage::lookup_sealed_input;
 use chain_core::state::account::StakedState;
 use chain_core::state::account::StakedStateOpWitness;
 use chain_core::tx::data::TxId;
@@ -8,10 +9,10 @@ use chain_storage::jellyfish::StakingGetter;
 use chain_storage::ReadOnlyStorage;
 use chain_tx_validation::witness::verify_tx_recover_address;
 use chain_tx_validation::ChainInfo;
-use enclave_protocol::IntraEnclaveRequest;
 use enclave_protocol::{
     EnclaveRequest, EnclaveResponse, IntraEnclaveResponseOk, IntraEncryptRequest, FLAGS,
 };
+use enclave_protocol::{IntraEnclaveRequest, SealedTxInput};
 use parity_scale_codec::{Decode, Encode};
 use std::sync::mpsc::Sender;
 use zmq::{Context, Error, Socket, REP};
@@ -60,6 +61,17 @@ impl<T: EnclaveProxy> TxValidationServer<T> {
         Some(result)
     }
 
+    fn lookup_tx_inputs<I>(&self, inputs: I) -> Option<Vec<SealedTxInput>>
+    where
+        I: IntoIterator<Item = TxId> + ExactSizeIterator,
+    {
+        let mut result = Vec::with_capacity(inputs.len());
+        for input in inputs.into_iter() {
+            result.push(lookup_sealed_input(&self.storage, &input)?);
+        }
+        Some(result)
+    }
+
     fn lookup_state(
         &self,
         txid: &TxId,
@@ -85,7 +97,7 @@ impl<T: EnclaveProxy> TxValidationServer<T> {
                     Ok(EnclaveRequest::EncryptTx(req)) => {
                         let result = {
                             let tx_inputs = match req.tx_inputs {
-                                Some(inputs) => self.lookup_txids(inputs.iter().map(|x| x.id)),
+                                Some(inputs) => self.lookup_tx_inputs(inputs.iter().map(|x| x.id)),
                                 _ => None,
                             };
                             match self.storage.get_last_app_state() {
@@ -105,7 +117,10 @@ impl<T: EnclaveProxy> TxValidationServer<T> {
                                     let min_fee = last_state
                                         .top_level
                                         .network_params
-                                        .calculate_fee(req.tx_size as usize)
+                                        .calculate_dynamic_fee(
+                                            req.tx_size as usize,
+                                            last_state.top_level.base_fee,
+                                        )
                                         .expect("valid fee");
                                     let info = ChainInfo {
                                         min_fee_computed: min_fee,
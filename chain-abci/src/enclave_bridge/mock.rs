@@ -85,7 +85,7 @@ impl EnclaveProxy for MockClient {
                         payload,
                         inputs
                             .iter()
-                            .map(|log| unseal(&log))
+                            .map(|input| unseal(&input.sealed_log).map(|tx| (tx, input.inclusion)))
                             .collect::<Result<_, _>>()?,
                     ),
                     (
@@ -99,7 +99,7 @@ impl EnclaveProxy for MockClient {
                         payload,
                         inputs
                             .iter()
-                            .map(|log| unseal(&log))
+                            .map(|input| unseal(&input.sealed_log).map(|tx| (tx, input.inclusion)))
                             .collect::<Result<_, _>>()?,
                     ),
                     (TxEnclaveAux::WithdrawUnbondedStakeTx { payload, .. }, _) => (payload, vec![]),
@@ -1,5 +1,6 @@
 use crate::app::ChainNodeState;
 use crate::enclave_bridge::real::enclave_u::encrypt_tx;
+use crate::storage::lookup_sealed_input;
 use chain_core::state::account::StakedState;
 use chain_core::state::account::StakedStateOpWitness;
 use chain_core::tx::data::TxId;
@@ -8,8 +9,8 @@ use chain_storage::jellyfish::StakingGetter;
 use chain_storage::ReadOnlyStorage;
 use chain_tx_validation::witness::verify_tx_recover_address;
 use chain_tx_validation::ChainInfo;
-use enclave_protocol::IntraEnclaveRequest;
 use enclave_protocol::{EnclaveRequest, EnclaveResponse, IntraEncryptRequest, FLAGS};
+use enclave_protocol::{IntraEnclaveRequest, SealedTxInput};
 use parity_scale_codec::{Decode, Encode};
 use sgx_urts::SgxEnclave;
 use std::sync::mpsc::Sender;
@@ -59,6 +60,17 @@ impl TxValidationServer {
         Some(result)
     }
 
+    fn lookup_tx_inputs<I>(&self, inputs: I) -> Option<Vec<SealedTxInput>>
+    where
+        I: IntoIterator<Item = TxId> + ExactSizeIterator,
+    {
+        let mut result = Vec::with_capacity(inputs.len());
+        for input in inputs.into_iter() {
+            result.push(lookup_sealed_input(&self.storage, &input)?);
+        }
+        Some(result)
+    }
+
     fn lookup_state(
         &self,
         txid: &TxId,
@@ -84,7 +96,7 @@ impl TxValidationServer {
                     Ok(EnclaveRequest::EncryptTx(req)) => {
                         let result = {
                             let tx_inputs = match req.tx_inputs {
-                                Some(inputs) => self.lookup_txids(inputs.iter().map(|x| x.id)),
+                                Some(inputs) => self.lookup_tx_inputs(inputs.iter().map(|x| x.id)),
                                 _ => None,
                             };
                             match self.storage.get_last_app_state() {
@@ -104,7 +116,10 @@ impl TxValidationServer {
                                     let min_fee = last_state
                                         .top_level
                                         .network_params
-                                        .calculate_fee(req.tx_size as usize)
+                                        .calculate_dynamic_fee(
+                                            req.tx_size as usize,
+                                            last_state.top_level.base_fee,
+                                        )
                                         .expect("valid fee");
                                     let info = ChainInfo {
                                         min_fee_computed: min_fee,
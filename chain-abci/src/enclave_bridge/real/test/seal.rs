@@ -18,7 +18,7 @@ use chain_core::tx::{
         address::ExtendedAddr,
         attribute::TxAttributes,
         input::{TxoPointer, TxoSize},
-        output::TxOut,
+        output::{TxInclusion, TxOut},
         Tx, TxId,
     },
     witness::TxInWitness,
@@ -27,7 +27,7 @@ use chain_core::tx::{
 use chain_core::ChainInfo;
 use chain_tx_validation::Error;
 use enclave_protocol::{
-    EncryptionRequest, IntraEnclaveRequest, IntraEnclaveResponseOk, VerifyTxRequest,
+    EncryptionRequest, IntraEnclaveRequest, IntraEnclaveResponseOk, SealedTxInput, VerifyTxRequest,
 };
 use enclave_u_common::enclave_u::init_enclave;
 use env_logger::{Builder, WriteStyle};
@@ -162,6 +162,7 @@ pub fn test_sealing() {
         block_height: BlockHeight::genesis(),
         max_evidence_age: 0,
     };
+    let inclusion = TxInclusion::new(info.block_time, info.block_height);
 
     let request0 = IntraEnclaveRequest::ValidateTx {
         request: Box::new(VerifyTxRequest {
@@ -222,7 +223,7 @@ pub fn test_sealing() {
             account: None,
             info,
         }),
-        tx_inputs: Some(vec![sealedtx.clone()]),
+        tx_inputs: Some(vec![SealedTxInput::new(sealedtx.clone(), inclusion)]),
     };
 
     check_tx(enclave.geteid(), request1).unwrap();
@@ -258,7 +259,7 @@ pub fn test_sealing() {
             account: None,
             info,
         }),
-        tx_inputs: Some(vec![sealedtx]),
+        tx_inputs: Some(vec![SealedTxInput::new(sealedtx, inclusion)]),
     };
 
     let r3 = check_tx(enclave.geteid(), request2);
@@ -0,0 +1,264 @@
+//! Council node governance of the network parameters:
+//! - an active validator submits `ParamChangeProposalTx` (which counts as its approval)
+//! - active validators vote with `ParamChangeVoteTx` during the voting period
+//! - in `end_block` of the first block after the voting period, the proposal is accepted
+//!   if the approving validators have the quorum of the current voting power,
+//!   and the change is applied as a new version of `NetworkParameters`
+//! (the voting period, the maximum number of pending proposals and the quorum
+//! are in `GovernanceParameters` of the current network parameters)
+use std::collections::BTreeMap;
+
+use parity_scale_codec::{Decode, Encode};
+
+use chain_core::common::Timespec;
+use chain_core::init::config::GovernanceParameters;
+use chain_core::state::account::StakedStateAddress;
+use chain_core::state::governance::{
+    ParamChangeProposalTx, ParamChangeVoteTx, Proposal, ProposalId,
+};
+use chain_core::state::tendermint::TendermintVotePower;
+
+use crate::tx_error::GovernanceError;
+
+/// Proposals in the voting period
+#[derive(Clone, Debug, Default, PartialEq, Eq, Encode, Decode)]
+pub struct GovernanceState {
+    proposals: BTreeMap<ProposalId, Proposal>,
+}
+
+impl GovernanceState {
+    /// Handle `ParamChangeProposalTx`
+    /// (the proposer should be checked to be an active validator by the caller)
+    pub fn propose(
+        &mut self,
+        proposal_id: ProposalId,
+        tx: &ParamChangeProposalTx,
+        block_time: Timespec,
+        params: &GovernanceParameters,
+    ) -> Result<(), GovernanceError> {
+        tx.change
+            .validate()
+            .map_err(|e| GovernanceError::InvalidChange(e.to_owned()))?;
+        if self.proposals.len() >= usize::from(params.max_pending_proposals) {
+            return Err(GovernanceError::TooManyProposals);
+        }
+        if self.proposals.contains_key(&proposal_id) {
+            return Err(GovernanceError::DuplicateProposal);
+        }
+        let mut votes = BTreeMap::new();
+        votes.insert(tx.address, true);
+        self.proposals.insert(
+            proposal_id,
+            Proposal {
+                id: proposal_id,
+                proposer: tx.address,
+                change: tx.change.clone(),
+                voting_end_time: block_time.saturating_add(params.voting_period),
+                votes,
+            },
+        );
+        Ok(())
+    }
+
+    /// Handle `ParamChangeVoteTx`
+    /// (the voter should be checked to be an active validator by the caller)
+    pub fn vote(
+        &mut self,
+        tx: &ParamChangeVoteTx,
+        block_time: Timespec,
+    ) -> Result<(), GovernanceError> {
+        let proposal = self
+            .proposals
+            .get_mut(&tx.proposal_id)
+            .ok_or(GovernanceError::ProposalNotFound)?;
+        if block_time >= proposal.voting_end_time {
+            return Err(GovernanceError::VotingEnded);
+        }
+        proposal.votes.insert(tx.address, tx.approve);
+        Ok(())
+    }
+
+    /// Removes the proposals whose voting period ended,
+    /// returns them together with the result (accepted or not) in the order of proposal ids.
+    /// Only the votes of the current validators count.
+    pub fn end_block(
+        &mut self,
+        block_time: Timespec,
+        voting_powers: &BTreeMap<StakedStateAddress, TendermintVotePower>,
+        params: &GovernanceParameters,
+    ) -> Vec<(Proposal, bool)> {
+        let ended = self
+            .proposals
+            .iter()
+            .filter(|(_, proposal)| block_time >= proposal.voting_end_time)
+            .map(|(id, _)| *id)
+            .collect::<Vec<_>>();
+        ended
+            .into_iter()
+            .filter_map(|id| self.proposals.remove(&id))
+            .map(|proposal| {
+                let accepted = is_accepted(&proposal, voting_powers, params);
+                (proposal, accepted)
+            })
+            .collect()
+    }
+
+    /// the proposals in the voting period
+    pub fn get_proposals(&self) -> Vec<Proposal> {
+        self.proposals.values().cloned().collect()
+    }
+}
+
+/// approving voting power >= quorum of the total voting power
+fn is_accepted(
+    proposal: &Proposal,
+    voting_powers: &BTreeMap<StakedStateAddress, TendermintVotePower>,
+    params: &GovernanceParameters,
+) -> bool {
+    let power = |power: &TendermintVotePower| u128::from(u64::from(*power));
+    let total: u128 = voting_powers.values().map(power).sum();
+    let approved: u128 = proposal
+        .votes
+        .iter()
+        .filter(|(_, approve)| **approve)
+        .filter_map(|(address, _)| voting_powers.get(address))
+        .map(power)
+        .sum();
+    params.is_quorum(approved, total)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chain_core::init::coin::Coin;
+    use chain_core::init::config::NetworkParameterChange;
+    use chain_core::state::account::StakedStateOpAttributes;
+    use chain_core::tx::TransactionId;
+
+    const PROPOSAL_VOTING_PERIOD: Timespec = 24 * 60 * 60;
+
+    fn address(i: u8) -> StakedStateAddress {
+        StakedStateAddress::BasicRedeem([i; 20].into())
+    }
+
+    fn voting_powers() -> BTreeMap<StakedStateAddress, TendermintVotePower> {
+        (1..=3)
+            .map(|i| (address(i), TendermintVotePower::new(10).unwrap()))
+            .collect()
+    }
+
+    fn proposal_tx(change: NetworkParameterChange) -> ParamChangeProposalTx {
+        ParamChangeProposalTx::new(0, address(1), StakedStateOpAttributes::new(0), change)
+    }
+
+    fn vote_tx(i: u8, proposal_id: ProposalId, approve: bool) -> ParamChangeVoteTx {
+        ParamChangeVoteTx::new(
+            0,
+            address(i),
+            StakedStateOpAttributes::new(0),
+            proposal_id,
+            approve,
+        )
+    }
+
+    #[test]
+    fn check_proposal_accepted_with_quorum() {
+        let mut state = GovernanceState::default();
+        let params = GovernanceParameters::default();
+        let tx = proposal_tx(NetworkParameterChange::MaxValidators(10));
+        let id = tx.id();
+        state.propose(id, &tx, 0, &params).unwrap();
+        assert_eq!(
+            state.propose(id, &tx, 0, &params).unwrap_err().to_string(),
+            GovernanceError::DuplicateProposal.to_string()
+        );
+        state.vote(&vote_tx(2, id, true), 1).unwrap();
+        state.vote(&vote_tx(3, id, true), 2).unwrap();
+
+        // voting period not ended
+        assert!(state
+            .end_block(PROPOSAL_VOTING_PERIOD - 1, &voting_powers(), &params)
+            .is_empty());
+        assert!(state
+            .vote(&vote_tx(3, id, false), PROPOSAL_VOTING_PERIOD)
+            .is_err());
+
+        let ended = state.end_block(PROPOSAL_VOTING_PERIOD, &voting_powers(), &params);
+        assert_eq!(ended.len(), 1);
+        assert_eq!(ended[0].0.id, id);
+        assert!(ended[0].1);
+        assert!(state.get_proposals().is_empty());
+    }
+
+    #[test]
+    fn check_proposal_rejected_without_quorum() {
+        let mut state = GovernanceState::default();
+        let params = GovernanceParameters::default();
+        let tx = proposal_tx(NetworkParameterChange::RequiredCouncilNodeStake(
+            Coin::unit(),
+        ));
+        let id = tx.id();
+        state.propose(id, &tx, 0, &params).unwrap();
+        state.vote(&vote_tx(2, id, true), 1).unwrap();
+        // the later vote replaces the previous one
+        state.vote(&vote_tx(2, id, false), 2).unwrap();
+        // not a validator
+        state.vote(&vote_tx(4, id, true), 2).unwrap();
+
+        let ended = state.end_block(PROPOSAL_VOTING_PERIOD, &voting_powers(), &params);
+        assert_eq!(ended.len(), 1);
+        assert!(!ended[0].1);
+    }
+
+    #[test]
+    fn check_governance_parameters() {
+        let mut state = GovernanceState::default();
+        let mut params = GovernanceParameters::default();
+        let tx = proposal_tx(NetworkParameterChange::MaxValidators(10));
+        let id = tx.id();
+        state.propose(id, &tx, 0, &params).unwrap();
+        state.vote(&vote_tx(2, id, true), 1).unwrap();
+
+        // exactly 2/3 isn't enough for the default quorum
+        let mut pending = state.clone();
+        let ended = pending.end_block(PROPOSAL_VOTING_PERIOD, &voting_powers(), &params);
+        assert!(!ended[0].1);
+
+        params.voting_period = 10;
+        params.max_pending_proposals = 1;
+        params.quorum = "0.6".parse().unwrap();
+        assert!(params.validate().is_ok());
+        let other = proposal_tx(NetworkParameterChange::MaxValidators(20));
+        assert_eq!(
+            state
+                .propose(other.id(), &other, 0, &params)
+                .unwrap_err()
+                .to_string(),
+            GovernanceError::TooManyProposals.to_string()
+        );
+
+        // the quorum is taken from the parameters when the votes are counted
+        let ended = state.end_block(PROPOSAL_VOTING_PERIOD, &voting_powers(), &params);
+        assert!(ended[0].1);
+
+        // the voting period is taken from the parameters when the proposal is submitted
+        state.propose(other.id(), &other, 0, &params).unwrap();
+        assert_eq!(state.get_proposals()[0].voting_end_time, 10);
+
+        params.quorum = "0.5".parse().unwrap();
+        assert!(params.validate().is_err());
+    }
+
+    #[test]
+    fn check_invalid_proposal_is_rejected() {
+        let mut state = GovernanceState::default();
+        let params = GovernanceParameters::default();
+        let tx = proposal_tx(NetworkParameterChange::MaxValidators(0));
+        assert!(state.propose(tx.id(), &tx, 0, &params).is_err());
+        assert!(state
+            .vote(&vote_tx(2, tx.id(), true), 0)
+            .unwrap_err()
+            .to_string()
+            .contains("not found"));
+    }
+}
@@ -0,0 +1,182 @@
+//! Offline inspection of the node storage (the node needs to be stopped),
+//! so that the stored history can be looked into without writing code against the columns.
+//!
+//! The top-level chain state is only stored for the past heights when tx-query is enabled;
+//! otherwise it's only available at the last committed height.
+use bit_vec::BitVec;
+use parity_scale_codec::Decode;
+
+use crate::app::ChainNodeState;
+use chain_core::common::{MerkleTree, H256};
+use chain_core::state::account::{StakedState, StakedStateAddress};
+use chain_core::state::tendermint::BlockHeight;
+use chain_core::state::ChainState;
+use chain_core::tx::data::TxId;
+use chain_core::AppHashParts;
+use chain_storage::buffer::Get;
+use chain_storage::jellyfish::{get_staking_root, StakingGetter, Version};
+use chain_storage::{LookupItem, Storage, COL_APP_HASHS};
+
+#[derive(thiserror::Error, Debug)]
+pub enum InspectError {
+    #[error("storage item decode failed: {0}")]
+    Decode(#[from] parity_scale_codec::Error),
+    #[error("no app state stored")]
+    NoState,
+    #[error("no history stored for the height {0} (it was pruned, not committed yet or the app state history isn't stored)")]
+    UnavailableHeight(BlockHeight),
+    #[error("staking version {0} is not stored (it was pruned or not committed yet)")]
+    UnavailableVersion(Version),
+}
+
+/// Result of the app hash recomputation at one height
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AppHashCheck {
+    /// recomputed from the stored chain state, staking trie root and txs merkle tree
+    Verified,
+    /// the chain state isn't stored at the height, so only the staking trie root
+    /// and txs merkle tree are checked against the stored app hash parts
+    PartiallyVerified,
+    /// neither the chain state nor the app hash parts are stored at the height
+    Unverifiable,
+    /// the stored data doesn't match the app hash
+    Mismatch(String),
+}
+
+/// Decodes the last committed chain node state
+pub fn last_state(storage: &Storage) -> Result<ChainNodeState, InspectError> {
+    let raw_state = storage.get_last_app_state().ok_or(InspectError::NoState)?;
+    Ok(ChainNodeState::decode(&mut raw_state.as_slice())?)
+}
+
+/// All the heights with the stored app hashes (ordered)
+pub fn stored_heights(storage: &Storage) -> Result<Vec<(BlockHeight, H256)>, InspectError> {
+    let mut heights = storage
+        .iter_column(COL_APP_HASHS)
+        .map(|(key, value)| {
+            let height = BlockHeight::decode(&mut key.as_ref())?;
+            let app_hash = H256::decode(&mut value.as_ref())?;
+            Ok((height, app_hash))
+        })
+        .collect::<Result<Vec<_>, InspectError>>()?;
+    // the keys are little endian encoded
+    heights.sort();
+    Ok(heights)
+}
+
+/// The top-level chain state at the height
+pub fn chain_state_at(storage: &Storage, height: BlockHeight) -> Result<ChainState, InspectError> {
+    let last = last_state(storage)?;
+    if last.last_block_height == height {
+        return Ok(last.top_level);
+    }
+    let raw_state = storage
+        .get_historical_state(height)
+        .ok_or(InspectError::UnavailableHeight(height))?;
+    Ok(ChainState::decode(&mut raw_state.as_slice())?)
+}
+
+/// The staking version committed at the height
+pub fn staking_version_at(storage: &Storage, height: BlockHeight) -> Result<Version, InspectError> {
+    storage
+        .get_historical_staking_version(height)
+        .ok_or(InspectError::UnavailableHeight(height))
+}
+
+/// The staked state in the staking trie at the version
+pub fn staking_at(
+    storage: &Storage,
+    address: &StakedStateAddress,
+    version: Version,
+) -> Result<Option<StakedState>, InspectError> {
+    if get_staking_root(storage, version).is_none() {
+        return Err(InspectError::UnavailableVersion(version));
+    }
+    Ok(StakingGetter::new(storage, version).get(address))
+}
+
+/// The spent flags of the transaction outputs (None if the transaction isn't known);
+/// the bitmap is padded to whole bytes, so it may be longer than the number of outputs
+pub fn utxo_spent_flags(storage: &Storage, txid: &TxId) -> Option<BitVec> {
+    storage
+        .lookup_item(LookupItem::TxMetaSpent, txid)
+        .map(|flags| BitVec::from_bytes(&flags))
+}
+
+fn check_app_hash(
+    storage: &Storage,
+    last: &ChainNodeState,
+    height: BlockHeight,
+    app_hash: &H256,
+) -> Result<AppHashCheck, InspectError> {
+    // no txs merkle tree is stored for genesis
+    let tree = match storage.lookup_item(LookupItem::TxsMerkle, app_hash) {
+        Some(data) => MerkleTree::decode(&mut data.as_slice())?,
+        None => MerkleTree::empty(),
+    };
+    let staking_root = staking_version_at(storage, height)
+        .ok()
+        .and_then(|version| get_staking_root(storage, version));
+    let staking_root = match staking_root {
+        Some(root) => root,
+        None => {
+            return Ok(AppHashCheck::Mismatch(
+                "staking trie root not found".to_owned(),
+            ))
+        }
+    };
+
+    let state = if last.last_block_height == height {
+        Some(last.top_level.clone())
+    } else {
+        storage
+            .get_historical_state(height)
+            .map(|raw_state| ChainState::decode(&mut raw_state.as_slice()))
+            .transpose()?
+    };
+    if let Some(state) = state {
+        if state.account_root != staking_root {
+            return Ok(AppHashCheck::Mismatch(format!(
+                "staking trie root {} doesn't match the chain state",
+                hex::encode(staking_root)
+            )));
+        }
+        let computed = AppHashParts::new(&tree, &state).app_hash();
+        return Ok(if computed == *app_hash {
+            AppHashCheck::Verified
+        } else {
+            AppHashCheck::Mismatch(format!("computed app hash {}", hex::encode(computed)))
+        });
+    }
+
+    Ok(match chain_storage::get_app_hash_parts(storage, app_hash) {
+        Some(parts) => {
+            if parts.app_hash() != *app_hash {
+                AppHashCheck::Mismatch("app hash parts don't match".to_owned())
+            } else if parts.valid_tx_root != tree.root_hash() {
+                AppHashCheck::Mismatch("txs merkle tree root doesn't match".to_owned())
+            } else if parts.account_root != staking_root {
+                AppHashCheck::Mismatch(format!(
+                    "staking trie root {} doesn't match",
+                    hex::encode(staking_root)
+                ))
+            } else {
+                AppHashCheck::PartiallyVerified
+            }
+        }
+        None => AppHashCheck::Unverifiable,
+    })
+}
+
+/// Recomputes the app hashes of all the stored heights
+pub fn verify_app_hashes(
+    storage: &Storage,
+) -> Result<Vec<(BlockHeight, AppHashCheck)>, InspectError> {
+    let last = last_state(storage)?;
+    stored_heights(storage)?
+        .into_iter()
+        .map(|(height, app_hash)| {
+            check_app_hash(storage, &last, height, &app_hash).map(|check| (height, check))
+        })
+        .collect()
+}
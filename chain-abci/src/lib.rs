@@ -1,6 +1,13 @@
 pub mod app;
 pub mod enclave_bridge;
+pub mod governance;
+pub mod inspect;
 pub mod liveness;
+pub mod migration;
+pub mod peer_filter;
+pub mod rollback;
+pub mod snapshot;
 pub mod staking;
 pub mod storage;
+pub mod tdbe;
 pub mod tx_error;
@@ -11,7 +11,7 @@ pub struct LivenessTracker {
     ///
     /// # Note
     ///
-    /// - Size of this `BitVec` should be equal to `block_signing_window` in jailing parameters in genesis.
+    /// - Size of this `BitVec` should be equal to `block_signing_window` in the current jailing parameters.
     /// - Stores `true` at `index = height % block_signing_window`, if validator has signed that block, `false`
     ///   otherwise.
     liveness: BitVec,
@@ -45,10 +45,11 @@ impl LivenessTracker {
         self.liveness.set_all();
     }
 
-    /// grow BitVec to size, no need to shrink.
+    /// grow or shrink BitVec to size (the signing window may be changed by governance).
     fn resize(&mut self, size: usize) {
-        if let Some(grow) = size.checked_sub(self.liveness.len()) {
-            self.liveness.grow(grow, true);
+        match size.checked_sub(self.liveness.len()) {
+            Some(grow) => self.liveness.grow(grow, true),
+            None => self.liveness.truncate(size),
         }
     }
 }
@@ -104,4 +105,16 @@ mod tests {
         assert!(tracker.is_live(3));
         assert!(!tracker.is_live(2));
     }
+
+    #[test]
+    fn check_liveness_tracker_window_change() {
+        let mut tracker = LivenessTracker::new();
+        tracker.update(5, 3.into(), false);
+        tracker.update(5, 4.into(), false);
+        assert!(!tracker.is_live(2));
+
+        // the missed blocks out of the new window are dropped
+        tracker.update(3, 5.into(), true);
+        assert!(tracker.is_live(2));
+    }
 }
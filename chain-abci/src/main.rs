@@ -3,7 +3,7 @@ use std::fs::File;
 use std::net::SocketAddr;
 use std::path::{Path, PathBuf};
 
-use chain_abci::app::{sanity_check_enabled, ChainNodeApp};
+use chain_abci::app::{sanity_check_enabled, ChainNodeApp, ChainNodeState};
 #[cfg(all(
     not(feature = "mock-enclave"),
     not(feature = "legacy"),
@@ -15,11 +15,24 @@ use chain_abci::enclave_bridge::edp::{launch_tx_validation, TxValidationApp};
 use chain_abci::enclave_bridge::mock::MockClient;
 #[cfg(all(not(feature = "mock-enclave"), feature = "legacy", target_os = "linux"))]
 use chain_abci::enclave_bridge::real::TxValidationApp;
+use chain_abci::inspect::{
+    chain_state_at, last_state, staking_at, staking_version_at, stored_heights, utxo_spent_flags,
+    verify_app_hashes, AppHashCheck,
+};
+use chain_abci::migration::{migrate_schema, migrate_storage, SCHEMA_VERSION};
+use chain_abci::peer_filter::{spawn_config_watcher, PeerFilterConfig, CONFIG_RELOAD_INTERVAL};
+use chain_abci::rollback::rollback_to_height;
+use chain_abci::snapshot::{export_snapshot, import_snapshot, read_snapshot, write_snapshot};
+use chain_core::common::{H256, HASH_SIZE_256};
 use chain_core::init::network::{get_network, get_network_id, init_chain_id};
-use chain_storage::{Storage, StorageConfig, StorageType};
+use chain_core::state::account::StakedStateAddress;
+use chain_core::state::tendermint::BlockHeight;
+use chain_storage::{PruningMode, Storage, StorageBackend, StorageConfig, StorageType};
 use log::warn;
+use parity_scale_codec::Decode;
 use serde::Deserialize;
 use std::io::BufReader;
+use std::sync::{Arc, RwLock};
 use structopt::StructOpt;
 
 #[derive(Deserialize, Debug)]
@@ -30,6 +43,12 @@ pub struct Config {
     chain_id: Option<String>,
     enclave_server: Option<String>,
     tx_query: Option<String>,
+    pruning_keep_recent: Option<u64>,
+    storage_backend: Option<String>,
+    #[serde(default)]
+    utxo_index: bool,
+    #[serde(default)]
+    peer_filter: PeerFilterConfig,
 }
 
 impl Default for Config {
@@ -41,6 +60,10 @@ impl Default for Config {
             chain_id: None,
             enclave_server: None,
             tx_query: None,
+            pruning_keep_recent: None,
+            storage_backend: None,
+            utxo_index: false,
+            peer_filter: PeerFilterConfig::default(),
         }
     }
 }
@@ -71,6 +94,15 @@ impl Config {
         if opt.tx_query.is_some() {
             self.tx_query = opt.tx_query.clone();
         }
+        if opt.pruning_keep_recent.is_some() {
+            self.pruning_keep_recent = opt.pruning_keep_recent;
+        }
+        if opt.storage_backend.is_some() {
+            self.storage_backend = opt.storage_backend.clone();
+        }
+        if opt.utxo_index {
+            self.utxo_index = true;
+        }
     }
     pub fn is_valid(&self) -> bool {
         let mut valid = true;
@@ -86,8 +118,17 @@ impl Config {
             log::error!("enclave_server should be set");
             valid = false
         }
+        if self.pruning_keep_recent == Some(0) {
+            log::error!("pruning_keep_recent should keep at least the last committed height");
+            valid = false
+        }
         valid
     }
+    pub fn get_storage_backend(&self) -> Result<StorageBackend, String> {
+        self.storage_backend
+            .as_ref()
+            .map_or(Ok(StorageBackend::default()), |backend| backend.parse())
+    }
 }
 
 #[derive(Debug, StructOpt)]
@@ -133,6 +174,416 @@ pub struct AbciOpt {
         help = "Optional transaction query support for clients (tx query enclave listening address, e.g. mydomain.com:4444)"
     )]
     tx_query: Option<String>,
+    #[structopt(
+        long = "pruning_keep_recent",
+        help = "Optional number of the recent heights whose history (app states, merkle trees etc.) is kept (everything is kept if not set)"
+    )]
+    pruning_keep_recent: Option<u64>,
+    #[structopt(
+        long = "storage_backend",
+        help = "Key-value database backend: rocksdb (default), sled or memory (nothing is persisted)"
+    )]
+    storage_backend: Option<String>,
+    #[structopt(
+        long = "utxo_index",
+        help = "Indexes which transaction spent each output and the inclusion heights of the transactions (for \"spent-by\" and \"tx-height\" queries)"
+    )]
+    utxo_index: bool,
+    #[structopt(subcommand)]
+    cmd: Option<AbciCommand>,
+}
+
+#[derive(Debug, StructOpt)]
+pub enum AbciCommand {
+    #[structopt(
+        name = "compact",
+        about = "Prunes the history of an existing database offline (the node needs to be stopped)"
+    )]
+    Compact {
+        #[structopt(
+            long = "keep_recent",
+            help = "Number of the recent heights whose history is kept"
+        )]
+        keep_recent: u64,
+    },
+    #[structopt(name = "snapshot", about = "Exports or imports a state snapshot")]
+    Snapshot(SnapshotCommand),
+    #[structopt(
+        name = "migrate",
+        about = "Copies the data storage into a different backend and verifies the resulting app hash (the node needs to be stopped)"
+    )]
+    Migrate {
+        #[structopt(
+            long = "to_backend",
+            help = "The target key-value database backend: rocksdb or sled"
+        )]
+        to_backend: StorageBackend,
+        #[structopt(
+            long = "to_data",
+            help = "The target data storage directory (the same one as the source if not set)"
+        )]
+        to_data: Option<String>,
+    },
+    #[structopt(
+        name = "rollback",
+        about = "Reverts the data storage to an earlier height, so that the later blocks are replayed (the node needs to be stopped)"
+    )]
+    Rollback {
+        #[structopt(
+            long = "to_height",
+            alias = "to-height",
+            help = "The height whose committed state is restored"
+        )]
+        to_height: u64,
+    },
+    #[structopt(
+        name = "inspect",
+        about = "Inspects or upgrades the data storage offline (the node needs to be stopped)"
+    )]
+    Inspect(InspectCommand),
+}
+
+#[derive(Debug, StructOpt)]
+pub enum InspectCommand {
+    #[structopt(
+        name = "heights",
+        about = "Lists the stored heights with their app hashes"
+    )]
+    Heights,
+    #[structopt(name = "state", about = "Prints the top-level chain state at a height")]
+    State {
+        #[structopt(
+            long = "height",
+            help = "Block height (the last committed one if not set)"
+        )]
+        height: Option<u64>,
+    },
+    #[structopt(
+        name = "staking",
+        about = "Prints a staked state from the staking trie at a historical version"
+    )]
+    Staking {
+        #[structopt(long = "address", help = "Staking address (hex-encoded)")]
+        address: StakedStateAddress,
+        #[structopt(
+            long = "version",
+            help = "Staking version (the one at the last committed height if neither the version nor height is set)"
+        )]
+        version: Option<u64>,
+        #[structopt(
+            long = "height",
+            help = "Block height whose staking version is used",
+            conflicts_with = "version"
+        )]
+        height: Option<u64>,
+    },
+    #[structopt(
+        name = "utxo",
+        about = "Prints the spent flags of a transaction's outputs (the bitmap is padded to whole bytes)"
+    )]
+    Utxo {
+        #[structopt(long = "txid", help = "Transaction id (hex-encoded)")]
+        txid: String,
+    },
+    #[structopt(
+        name = "verify-app-hashes",
+        about = "Recomputes the app hashes of all the stored heights"
+    )]
+    VerifyAppHashes,
+    #[structopt(
+        name = "migrate-schema",
+        about = "Upgrades the storage schema to the version supported by this binary"
+    )]
+    MigrateSchema,
+}
+
+#[derive(Debug, StructOpt)]
+pub enum SnapshotCommand {
+    #[structopt(
+        name = "export",
+        about = "Writes a snapshot of the last committed state (the node needs to be stopped)"
+    )]
+    Export {
+        #[structopt(
+            long = "height",
+            help = "The expected last committed block height (only the last committed state can be exported)"
+        )]
+        height: Option<u64>,
+        #[structopt(long = "dir", help = "Directory to write the snapshot into")]
+        dir: PathBuf,
+    },
+    #[structopt(
+        name = "import",
+        about = "Verifies a snapshot and writes it into the empty data storage, so that the node resumes from its height"
+    )]
+    Import {
+        #[structopt(long = "dir", help = "Directory with the snapshot")]
+        dir: PathBuf,
+        #[structopt(
+            long = "app_hash",
+            help = "The trusted app hash (hex-encoded) at the snapshot height, e.g. from the next block header"
+        )]
+        app_hash: String,
+    },
+}
+
+/// At most so many heights are pruned in one database write
+const COMPACT_BATCH_HEIGHTS: u64 = 10_000;
+
+/// opens the node storage, the error is logged
+fn open_storage(data: &str, backend: StorageBackend) -> Option<Storage> {
+    match Storage::new(&StorageConfig::new(data, StorageType::Node).with_backend(backend)) {
+        Ok(storage) => Some(storage),
+        Err(e) => {
+            log::error!("failed to open the {} storage in {}: {}", backend, data, e);
+            None
+        }
+    }
+}
+
+fn compact(data: &str, backend: StorageBackend, keep_recent: u64) {
+    if keep_recent == 0 {
+        log::error!("at least the last committed height should be kept");
+        return;
+    }
+    let mut storage = match open_storage(data, backend) {
+        Some(storage) => storage,
+        None => return,
+    };
+    let last_state = match storage.get_last_app_state() {
+        Some(raw) => ChainNodeState::decode(&mut raw.as_slice()).expect("deserialize app state"),
+        None => {
+            log::error!("no app state stored in {}", data);
+            return;
+        }
+    };
+    let mode = PruningMode::KeepRecent(keep_recent);
+    let mut total = 0;
+    loop {
+        let pruned = storage
+            .prune(mode, last_state.last_block_height, COMPACT_BATCH_HEIGHTS)
+            .expect("kv storage io error");
+        if pruned == 0 {
+            break;
+        }
+        total += pruned;
+        info!("pruned the history of {} heights", total);
+    }
+    info!(
+        "kept the history of the last {} heights (up to {})",
+        keep_recent, last_state.last_block_height
+    );
+}
+
+fn parse_h256(hex_str: &str) -> Option<H256> {
+    match hex::decode(hex_str) {
+        Ok(raw) if raw.len() == HASH_SIZE_256 => {
+            let mut hash = H256::default();
+            hash.copy_from_slice(&raw);
+            Some(hash)
+        }
+        _ => None,
+    }
+}
+
+fn snapshot(
+    data: &str,
+    backend: StorageBackend,
+    cmd: &SnapshotCommand,
+    write_history_states: bool,
+) {
+    let mut storage = match open_storage(data, backend) {
+        Some(storage) => storage,
+        None => return,
+    };
+    match cmd {
+        SnapshotCommand::Export { height, dir } => {
+            let result = export_snapshot(&storage, height.map(BlockHeight::new))
+                .and_then(|snapshot| write_snapshot(dir, &snapshot).map(|_| snapshot.manifest));
+            match result {
+                Ok(manifest) => info!(
+                    "exported snapshot at height {} (app hash: {}, {} chunks) into {:?}",
+                    manifest.height,
+                    hex::encode(manifest.app_hash),
+                    manifest.chunk_hashes.len(),
+                    dir
+                ),
+                Err(e) => log::error!("snapshot export failed: {}", e),
+            }
+        }
+        SnapshotCommand::Import { dir, app_hash } => {
+            let trusted_app_hash = match parse_h256(app_hash) {
+                Some(trusted_app_hash) => trusted_app_hash,
+                None => {
+                    log::error!("invalid app hash: {}", app_hash);
+                    return;
+                }
+            };
+            let result = read_snapshot(dir).and_then(|snapshot| {
+                import_snapshot(
+                    &mut storage,
+                    &snapshot,
+                    &trusted_app_hash,
+                    write_history_states,
+                )
+            });
+            match result {
+                Ok(state) => info!(
+                    "imported snapshot at height {}, the node resumes from the next block",
+                    state.last_block_height
+                ),
+                Err(e) => log::error!("snapshot import failed: {}", e),
+            }
+        }
+    }
+}
+
+fn migrate(data: &str, backend: StorageBackend, to_data: &str, to_backend: StorageBackend) {
+    if to_backend == StorageBackend::Memory
+        || (to_backend == backend && Path::new(to_data) == Path::new(data))
+    {
+        log::error!(
+            "invalid migration target: {} backend in {}",
+            to_backend,
+            to_data
+        );
+        return;
+    }
+    let (from, mut to) = match (
+        open_storage(data, backend),
+        open_storage(to_data, to_backend),
+    ) {
+        (Some(from), Some(to)) => (from, to),
+        _ => return,
+    };
+    match migrate_storage(&from, &mut to) {
+        Ok(app_hash) => info!(
+            "migrated the storage from {} to {} backend (app hash: {})",
+            backend,
+            to_backend,
+            hex::encode(app_hash)
+        ),
+        Err(e) => log::error!("storage migration failed: {}", e),
+    }
+}
+
+fn rollback(data: &str, backend: StorageBackend, to_height: u64) {
+    let mut storage = match open_storage(data, backend) {
+        Some(storage) => storage,
+        None => return,
+    };
+    match rollback_to_height(&mut storage, BlockHeight::new(to_height)) {
+        Ok(state) => info!(
+            "rolled back to height {} (app hash: {}), the later blocks will be replayed",
+            state.last_block_height,
+            hex::encode(state.last_apphash)
+        ),
+        Err(e) => log::error!("rollback failed: {}", e),
+    }
+}
+
+fn print_json<T: serde::Serialize>(value: &T) {
+    println!(
+        "{}",
+        serde_json::to_string_pretty(value).expect("serialize to json")
+    );
+}
+
+fn inspect(data: &str, backend: StorageBackend, cmd: &InspectCommand) {
+    let mut storage = match open_storage(data, backend) {
+        Some(storage) => storage,
+        None => return,
+    };
+    match cmd {
+        InspectCommand::Heights => match stored_heights(&storage) {
+            Ok(heights) => {
+                if let Some(pruned) = chain_storage::get_pruned_height(&storage) {
+                    println!("history pruned up to height {}", pruned);
+                }
+                for (height, app_hash) in heights.iter() {
+                    println!("{}\t{}", height, hex::encode(app_hash));
+                }
+            }
+            Err(e) => log::error!("failed to list heights: {}", e),
+        },
+        InspectCommand::State { height } => {
+            let result = match height {
+                Some(height) => chain_state_at(&storage, BlockHeight::new(*height)),
+                None => last_state(&storage).map(|state| state.top_level),
+            };
+            match result {
+                Ok(state) => print_json(&state),
+                Err(e) => log::error!("failed to get chain state: {}", e),
+            }
+        }
+        InspectCommand::Staking {
+            address,
+            version,
+            height,
+        } => {
+            let result = match (version, height) {
+                (Some(version), _) => Ok(*version),
+                (None, Some(height)) => staking_version_at(&storage, BlockHeight::new(*height)),
+                (None, None) => last_state(&storage).map(|state| state.staking_version),
+            }
+            .and_then(|version| staking_at(&storage, address, version));
+            match result {
+                Ok(Some(staking)) => print_json(&staking),
+                Ok(None) => println!("staking address {} not found", address),
+                Err(e) => log::error!("failed to get staked state: {}", e),
+            }
+        }
+        InspectCommand::Utxo { txid } => {
+            let txid = match parse_h256(txid) {
+                Some(txid) => txid,
+                None => {
+                    log::error!("invalid transaction id: {}", txid);
+                    return;
+                }
+            };
+            match utxo_spent_flags(&storage, &txid) {
+                Some(flags) => {
+                    for (index, spent) in flags.iter().enumerate() {
+                        println!("{}\t{}", index, if spent { "spent" } else { "unspent" });
+                    }
+                }
+                None => println!("transaction {} not found", hex::encode(txid)),
+            }
+        }
+        InspectCommand::VerifyAppHashes => match verify_app_hashes(&storage) {
+            Ok(checks) => {
+                let mut mismatches = 0;
+                for (height, check) in checks.iter() {
+                    match check {
+                        AppHashCheck::Verified => println!("{}\tverified", height),
+                        AppHashCheck::PartiallyVerified => {
+                            println!("{}\tpartially verified (no chain state stored)", height)
+                        }
+                        AppHashCheck::Unverifiable => {
+                            println!("{}\tnot verifiable (no chain state stored)", height)
+                        }
+                        AppHashCheck::Mismatch(reason) => {
+                            mismatches += 1;
+                            println!("{}\tMISMATCH: {}", height, reason)
+                        }
+                    }
+                }
+                if mismatches > 0 {
+                    log::error!("{} app hash mismatches found", mismatches);
+                }
+            }
+            Err(e) => log::error!("app hash verification failed: {}", e),
+        },
+        InspectCommand::MigrateSchema => match migrate_schema(&mut storage) {
+            Ok(applied) => {
+                for name in applied.iter() {
+                    info!("applied schema migration: {}", name);
+                }
+                info!("storage schema version: {}", SCHEMA_VERSION);
+            }
+            Err(e) => log::error!("schema migration failed: {}", e),
+        },
+    }
 }
 
 /// legacy
@@ -173,12 +624,47 @@ fn main() {
     let mut default_config_file = PathBuf::from(&opt.data);
     default_config_file.push("config.yaml");
     let config_file = opt.config.clone().unwrap_or(default_config_file);
-    let mut config = if config_file.exists() {
+    let config_file_exists = config_file.exists();
+    let mut config = if config_file_exists {
         Config::from_file(config_file.as_path())
     } else {
         Config::default()
     };
     config.update(&opt);
+    let storage_backend = match config.get_storage_backend() {
+        Ok(backend) => backend,
+        Err(e) => {
+            log::error!("{}", e);
+            return;
+        }
+    };
+    match &opt.cmd {
+        Some(AbciCommand::Compact { keep_recent }) => {
+            compact(&opt.data, storage_backend, *keep_recent);
+            return;
+        }
+        Some(AbciCommand::Snapshot(cmd)) => {
+            snapshot(&opt.data, storage_backend, cmd, config.tx_query.is_some());
+            return;
+        }
+        Some(AbciCommand::Migrate {
+            to_backend,
+            to_data,
+        }) => {
+            let to_data = to_data.as_ref().unwrap_or(&opt.data);
+            migrate(&opt.data, storage_backend, to_data, *to_backend);
+            return;
+        }
+        Some(AbciCommand::Rollback { to_height }) => {
+            rollback(&opt.data, storage_backend, *to_height);
+            return;
+        }
+        Some(AbciCommand::Inspect(cmd)) => {
+            inspect(&opt.data, storage_backend, cmd);
+            return;
+        }
+        None => {}
+    }
     if !config.is_valid() {
         return;
     }
@@ -196,7 +682,17 @@ fn main() {
 
     let host = config.host.parse().expect("invalid host");
     let addr = SocketAddr::new(host, config.port);
-    let storage = Storage::new(&StorageConfig::new(&opt.data, StorageType::Node));
+    let storage = match open_storage(&opt.data, storage_backend) {
+        Some(storage) => storage,
+        None => return,
+    };
+    let pruning = config
+        .pruning_keep_recent
+        .map_or(PruningMode::Archive, PruningMode::KeepRecent);
+    let peer_filter = Arc::new(RwLock::new(config.peer_filter));
+    if config_file_exists {
+        spawn_config_watcher(config_file, peer_filter.clone(), CONFIG_RELOAD_INTERVAL);
+    }
     info!("starting up");
     abci::run(
         addr,
@@ -207,6 +703,9 @@ fn main() {
             storage,
             config.tx_query,
             config.enclave_server,
-        ),
+        )
+        .with_peer_filter(peer_filter)
+        .with_pruning(pruning)
+        .with_utxo_index(config.utxo_index),
     );
 }
@@ -0,0 +1,188 @@
+//! Migration of the node storage between the key-value database backends
+//! (e.g. from RocksDB to sled): all the columns are copied and the app hash
+//! of the last committed state is then recomputed from the target storage.
+//!
+//! It also contains the versioned upgrades of the storage schema:
+//! when the layout of the columns changes, a migration is appended to `SCHEMA_MIGRATIONS`.
+mod legacy;
+
+use parity_scale_codec::Decode;
+
+use crate::app::ChainNodeState;
+use chain_core::common::{MerkleTree, H256};
+use chain_core::AppHashParts;
+use chain_storage::buffer::{flush_storage, BufferStore, KVBuffer};
+use chain_storage::jellyfish::{
+    compute_staking_root, iter_staking_blobs, iter_stakings, put_stakings,
+};
+use chain_storage::{LookupItem, Storage};
+
+#[derive(thiserror::Error, Debug)]
+pub enum MigrationError {
+    #[error("storage io error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("chain node state decode failed: {0}")]
+    Decode(#[from] parity_scale_codec::Error),
+    #[error("no app state stored in the source storage")]
+    NoState,
+    #[error("target storage already contains an app state")]
+    NotEmpty,
+    #[error("staking trie error: {0}")]
+    Trie(String),
+    #[error("staking trie root doesn't match the chain node state")]
+    StakingRootMismatch,
+    #[error("app hash mismatch: expected {expected}, computed {computed}")]
+    AppHashMismatch { expected: String, computed: String },
+    #[error("storage schema version {0} is newer than the supported one")]
+    UnsupportedSchema(u32),
+}
+
+/// Upgrades the storage written with the previous schema version
+/// (the changes are written into the buffer)
+type SchemaMigration = fn(&Storage, &mut KVBuffer) -> Result<(), MigrationError>;
+
+/// `SCHEMA_MIGRATIONS[i]` upgrades the storage from schema version `i` to `i + 1`
+const SCHEMA_MIGRATIONS: [(&str, SchemaMigration); 2] = [
+    (
+        "upgrade the layouts of the last state and staked states",
+        upgrade_legacy_state,
+    ),
+    (
+        "store the network parameters and app hash parts of the last state",
+        store_last_state_parts,
+    ),
+];
+
+/// Schema version of the storage written by this node
+pub const SCHEMA_VERSION: u32 = SCHEMA_MIGRATIONS.len() as u32;
+
+/// The chain node state and staked states stored before the schema versioning
+/// are re-encoded with the current layouts (the new parts start in their genesis state).
+/// The upgraded staked states are put into a new version of the staking trie,
+/// so the app hash of the next block is computed from the new account root;
+/// the stored app hash of the last block is kept, as that's the one Tendermint knows.
+/// The previous trie versions (and hence historical staking queries) aren't upgraded.
+fn upgrade_legacy_state(storage: &Storage, kv_buffer: &mut KVBuffer) -> Result<(), MigrationError> {
+    let raw_state = match storage.get_last_app_state() {
+        Some(raw_state) => raw_state,
+        None => return Ok(()),
+    };
+    let legacy_state = legacy::ChainNodeState::decode(&mut raw_state.as_slice())?;
+    let stakings = iter_staking_blobs(storage, legacy_state.staking_version)
+        .map(|(_, blob)| legacy::StakedState::decode(&mut blob.as_slice())?.upgrade())
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut state = legacy_state.upgrade();
+    let write_history_states = storage
+        .get_historical_state(state.last_block_height)
+        .is_some();
+    let mut store = BufferStore::new(storage, kv_buffer);
+    if !stakings.is_empty() {
+        state.staking_version = state
+            .staking_version
+            .checked_add(1)
+            .expect("staking version overflow, no way to recover");
+        state.top_level.account_root =
+            put_stakings(&mut store, state.staking_version, stakings.iter())
+                .map_err(|e| MigrationError::Trie(e.to_string()))?;
+    }
+    chain_storage::store_chain_state(
+        &mut store,
+        &state,
+        state.last_block_height,
+        write_history_states,
+    );
+    Ok(())
+}
+
+/// The network parameters and app hash parts weren't stored in the databases
+/// created before the schema versioning
+fn store_last_state_parts(
+    storage: &Storage,
+    kv_buffer: &mut KVBuffer,
+) -> Result<(), MigrationError> {
+    let raw_state = match storage.get_last_app_state() {
+        Some(raw_state) => raw_state,
+        None => return Ok(()),
+    };
+    let state = ChainNodeState::decode(&mut raw_state.as_slice())?;
+    let mut store = BufferStore::new(storage, kv_buffer);
+    let params = &state.top_level.network_params;
+    if chain_storage::get_network_params(&store, params.get_version()).is_none() {
+        chain_storage::store_network_params(&mut store, params);
+    }
+    if chain_storage::get_app_hash_parts(&store, &state.last_apphash).is_none() {
+        // no txs merkle tree is stored for genesis
+        let tree = match storage.lookup_item(LookupItem::TxsMerkle, &state.last_apphash) {
+            Some(data) => MerkleTree::decode(&mut data.as_slice())?,
+            None => MerkleTree::empty(),
+        };
+        let parts = AppHashParts::new(&tree, &state.top_level);
+        chain_storage::store_app_hash_parts(&mut store, &state.last_apphash, &parts);
+    }
+    Ok(())
+}
+
+/// Upgrades the storage schema to `SCHEMA_VERSION` (each step is written atomically)
+/// and returns the names of the applied migrations
+pub fn migrate_schema(storage: &mut Storage) -> Result<Vec<&'static str>, MigrationError> {
+    let version = chain_storage::get_schema_version(&*storage);
+    if version > SCHEMA_VERSION {
+        return Err(MigrationError::UnsupportedSchema(version));
+    }
+    let mut applied = Vec::new();
+    for (i, (name, migration)) in SCHEMA_MIGRATIONS.iter().enumerate().skip(version as usize) {
+        let mut kv_buffer = KVBuffer::new();
+        migration(storage, &mut kv_buffer)?;
+        chain_storage::store_schema_version(
+            &mut BufferStore::new(&*storage, &mut kv_buffer),
+            i as u32 + 1,
+        );
+        flush_storage(storage, kv_buffer)?;
+        applied.push(*name);
+    }
+    Ok(applied)
+}
+
+/// Recomputes the app hash of the last committed state from the stored staking trie
+/// and txs merkle tree (and checks it against the stored one)
+pub fn verify_stored_app_hash(storage: &Storage) -> Result<H256, MigrationError> {
+    let raw_state = storage
+        .get_last_app_state()
+        .ok_or(MigrationError::NoState)?;
+    let state = ChainNodeState::decode(&mut raw_state.as_slice())?;
+    let stakings = iter_stakings(storage, state.staking_version).collect::<Vec<_>>();
+    if stakings.is_empty() || compute_staking_root(&stakings) != state.top_level.account_root {
+        return Err(MigrationError::StakingRootMismatch);
+    }
+    // no txs merkle tree is stored for genesis
+    let tree = match storage.lookup_item(LookupItem::TxsMerkle, &state.last_apphash) {
+        Some(data) => MerkleTree::decode(&mut data.as_slice())?,
+        None => MerkleTree::empty(),
+    };
+    let app_hash = AppHashParts::new(&tree, &state.top_level).app_hash();
+    if app_hash != state.last_apphash {
+        return Err(MigrationError::AppHashMismatch {
+            expected: hex::encode(state.last_apphash),
+            computed: hex::encode(app_hash),
+        });
+    }
+    Ok(app_hash)
+}
+
+/// Copies the whole source storage into the (empty) target one
+/// and returns the verified app hash of the last committed state
+pub fn migrate_storage(from: &Storage, to: &mut Storage) -> Result<H256, MigrationError> {
+    let expected = verify_stored_app_hash(from)?;
+    if to.get_last_app_state().is_some() {
+        return Err(MigrationError::NotEmpty);
+    }
+    to.copy_from(from)?;
+    let computed = verify_stored_app_hash(to)?;
+    if computed != expected {
+        return Err(MigrationError::AppHashMismatch {
+            expected: hex::encode(expected),
+            computed: hex::encode(computed),
+        });
+    }
+    Ok(computed)
+}
@@ -0,0 +1,353 @@
+//! Layouts of the chain node state and staked states stored before the schema versioning
+//! (decoded by the schema migration and converted to the current ones)
+use std::collections::BTreeMap;
+
+use parity_scale_codec::{Decode, Encode, Error};
+
+use crate::app::ChainNodeState as CurrentChainNodeState;
+use crate::governance::GovernanceState;
+use crate::liveness::LivenessTracker;
+use crate::staking::StakingTable as CurrentStakingTable;
+use crate::tdbe::MLSGroupState;
+use chain_core::common::{Timespec, H256};
+use chain_core::init::coin::Coin;
+use chain_core::init::config::{
+    DynamicFeeParameters, GovernanceParameters,
+    InitNetworkParameters as CurrentInitNetworkParameters, JailingParameters,
+    NetworkParameters as CurrentNetworkParameters, RewardsParameters, SlashingParameters,
+};
+use chain_core::state::account::{
+    ConfidentialInit, CouncilNodeMeta as CurrentCouncilNodeMeta, NodeCommonInfo,
+    NodeState as CurrentNodeState, Nonce, SlashRecord, StakedState as CurrentStakedState,
+    StakedStateAddress, Validator as CurrentValidator,
+};
+use chain_core::state::tendermint::{
+    BlockHeight, TendermintValidatorAddress, TendermintValidatorPubKey, TendermintVotePower,
+};
+use chain_core::state::{ChainState as CurrentChainState, RewardsPoolState};
+use chain_core::tx::fee::LinearFee;
+use chain_storage::jellyfish::Version;
+
+/// council node metadata without the commission rate
+#[derive(Encode, Decode)]
+pub struct CouncilNodeMeta {
+    pub name: Vec<u8>,
+    pub security_contact: Option<Vec<u8>>,
+    pub consensus_pubkey: TendermintValidatorPubKey,
+    pub keypackage: Vec<u8>,
+}
+
+impl CouncilNodeMeta {
+    fn upgrade(self) -> Result<CurrentCouncilNodeMeta, Error> {
+        let name =
+            String::from_utf8(self.name).map_err(|_| Error::from("Invalid validator name"))?;
+        let security_contact = self
+            .security_contact
+            .map(String::from_utf8)
+            .transpose()
+            .map_err(|_| Error::from("Invalid security contact"))?;
+        Ok(CurrentCouncilNodeMeta::new_with_details(
+            name,
+            security_contact,
+            self.consensus_pubkey,
+            ConfidentialInit {
+                keypackage: self.keypackage,
+            },
+        ))
+    }
+}
+
+#[derive(Encode, Decode)]
+pub struct Validator {
+    pub council_node: CouncilNodeMeta,
+    pub jailed_until: Option<Timespec>,
+    pub inactive_time: Option<Timespec>,
+    pub inactive_block: Option<BlockHeight>,
+    pub used_validator_addresses: Vec<(TendermintValidatorAddress, Timespec)>,
+}
+
+#[derive(Encode, Decode)]
+pub enum NodeState {
+    CouncilNode(Validator),
+    CommunityNode(NodeCommonInfo),
+}
+
+impl NodeState {
+    fn upgrade(self) -> Result<CurrentNodeState, Error> {
+        match self {
+            NodeState::CouncilNode(validator) => {
+                Ok(CurrentNodeState::CouncilNode(CurrentValidator {
+                    council_node: validator.council_node.upgrade()?,
+                    jailed_until: validator.jailed_until,
+                    inactive_time: validator.inactive_time,
+                    inactive_block: validator.inactive_block,
+                    used_validator_addresses: validator.used_validator_addresses,
+                }))
+            }
+            NodeState::CommunityNode(info) => Ok(CurrentNodeState::CommunityNode(info)),
+        }
+    }
+}
+
+/// staked state without the delegation
+#[derive(Encode, Decode)]
+pub struct StakedState {
+    pub nonce: Nonce,
+    pub bonded: Coin,
+    pub unbonded: Coin,
+    pub unbonded_from: Timespec,
+    pub address: StakedStateAddress,
+    pub node_meta: Option<NodeState>,
+    pub last_slash: Option<SlashRecord>,
+}
+
+impl StakedState {
+    pub fn upgrade(self) -> Result<CurrentStakedState, Error> {
+        Ok(CurrentStakedState {
+            nonce: self.nonce,
+            bonded: self.bonded,
+            unbonded: self.unbonded,
+            unbonded_from: self.unbonded_from,
+            address: self.address,
+            node_meta: self.node_meta.map(NodeState::upgrade).transpose()?,
+            last_slash: self.last_slash,
+            delegated_to: None,
+        })
+    }
+}
+
+/// network parameters without the dynamic fee and governance configurations
+#[derive(Encode, Decode)]
+pub struct InitNetworkParameters {
+    pub initial_fee_policy: LinearFee,
+    pub required_council_node_stake: Coin,
+    pub jailing_config: JailingParameters,
+    pub slashing_config: SlashingParameters,
+    pub rewards_config: RewardsParameters,
+    pub max_validators: u16,
+}
+
+/// only genesis parameters (no governance)
+#[derive(Encode, Decode)]
+pub enum NetworkParameters {
+    Genesis(InitNetworkParameters),
+}
+
+impl NetworkParameters {
+    fn upgrade(self) -> CurrentNetworkParameters {
+        let NetworkParameters::Genesis(params) = self;
+        CurrentNetworkParameters::Genesis(CurrentInitNetworkParameters {
+            initial_fee_policy: params.initial_fee_policy,
+            required_council_node_stake: params.required_council_node_stake,
+            jailing_config: params.jailing_config,
+            slashing_config: params.slashing_config,
+            rewards_config: params.rewards_config,
+            max_validators: params.max_validators,
+            // disabled
+            dynamic_fee_config: DynamicFeeParameters::default(),
+            governance_config: GovernanceParameters::default(),
+        })
+    }
+}
+
+/// app hash inputs without the TDBE group
+#[derive(Encode, Decode)]
+pub struct ChainState {
+    pub account_root: H256,
+    pub rewards_pool: RewardsPoolState,
+    pub network_params: NetworkParameters,
+}
+
+/// staking table without the stake transfers, delegators, tombstones and evidences
+#[derive(Encode, Decode)]
+pub struct StakingTable {
+    pub chosen_validators: BTreeMap<StakedStateAddress, TendermintVotePower>,
+    pub liveness: BTreeMap<StakedStateAddress, LivenessTracker>,
+    pub participator_stats: BTreeMap<StakedStateAddress, u64>,
+}
+
+/// chain node state without the TDBE group, governance and the base fee
+#[derive(Encode, Decode)]
+pub struct ChainNodeState {
+    pub last_block_height: BlockHeight,
+    pub last_apphash: H256,
+    pub block_time: Timespec,
+    pub block_height: BlockHeight,
+    pub staking_table: StakingTable,
+    pub genesis_time: Timespec,
+    pub max_evidence_age: Timespec,
+    pub staking_version: Version,
+    pub utxo_coins: Coin,
+    pub enclave_isv_svn: u16,
+    pub top_level: ChainState,
+}
+
+impl ChainNodeState {
+    /// the new parts are in their genesis state (the staking trie needs to be upgraded separately)
+    pub fn upgrade(self) -> CurrentChainNodeState {
+        CurrentChainNodeState {
+            last_block_height: self.last_block_height,
+            last_apphash: self.last_apphash,
+            block_time: self.block_time,
+            block_height: self.block_height,
+            staking_table: CurrentStakingTable::from_legacy(
+                self.staking_table.chosen_validators,
+                self.staking_table.liveness,
+                self.staking_table.participator_stats,
+            ),
+            genesis_time: self.genesis_time,
+            max_evidence_age: self.max_evidence_age,
+            staking_version: self.staking_version,
+            utxo_coins: self.utxo_coins,
+            enclave_isv_svn: self.enclave_isv_svn,
+            mls_group: MLSGroupState::default(),
+            governance: GovernanceState::default(),
+            top_level: CurrentChainState::genesis(
+                self.top_level.account_root,
+                self.top_level.rewards_pool,
+                self.top_level.network_params.upgrade(),
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chain_core::init::config::SlashRatio;
+    use chain_core::tx::fee::Milli;
+    use std::str::FromStr;
+
+    fn legacy_state() -> ChainNodeState {
+        let address = StakedStateAddress::BasicRedeem([1u8; 20].into());
+        let mut chosen_validators = BTreeMap::new();
+        chosen_validators.insert(address, TendermintVotePower::from(Coin::unit()));
+        let mut participator_stats = BTreeMap::new();
+        participator_stats.insert(address, 3);
+        ChainNodeState {
+            last_block_height: BlockHeight::new(10),
+            last_apphash: [2u8; 32],
+            block_time: 1000,
+            block_height: BlockHeight::new(10),
+            staking_table: StakingTable {
+                chosen_validators,
+                liveness: BTreeMap::new(),
+                participator_stats,
+            },
+            genesis_time: 10,
+            max_evidence_age: 100,
+            staking_version: 4,
+            utxo_coins: Coin::unit(),
+            enclave_isv_svn: 2,
+            top_level: ChainState {
+                account_root: [3u8; 32],
+                rewards_pool: RewardsPoolState::new(10, 5),
+                network_params: NetworkParameters::Genesis(InitNetworkParameters {
+                    initial_fee_policy: LinearFee::new(
+                        Milli::try_new(1, 1).unwrap(),
+                        Milli::try_new(1, 1).unwrap(),
+                    ),
+                    required_council_node_stake: Coin::unit(),
+                    jailing_config: JailingParameters {
+                        block_signing_window: 5,
+                        missed_block_threshold: 1,
+                    },
+                    slashing_config: SlashingParameters {
+                        liveness_slash_percent: SlashRatio::from_str("0.1").unwrap(),
+                        byzantine_slash_percent: SlashRatio::from_str("0.2").unwrap(),
+                    },
+                    rewards_config: RewardsParameters {
+                        monetary_expansion_cap: Coin::unit(),
+                        reward_period_seconds: 24 * 60 * 60,
+                        monetary_expansion_r0: "0.5".parse().unwrap(),
+                        monetary_expansion_tau: 1_4500_0000_0000_0000,
+                        monetary_expansion_decay: 999_860,
+                    },
+                    max_validators: 50,
+                }),
+            },
+        }
+    }
+
+    #[test]
+    fn check_baseline_chain_node_state_is_upgraded() {
+        let encoded = legacy_state().encode();
+        let state = ChainNodeState::decode(&mut encoded.as_slice())
+            .expect("decode baseline state")
+            .upgrade();
+        assert_eq!(state.last_block_height, BlockHeight::new(10));
+        assert_eq!(state.last_apphash, [2u8; 32]);
+        assert_eq!(state.staking_version, 4);
+        assert_eq!(state.enclave_isv_svn, 2);
+        assert_eq!(state.mls_group, MLSGroupState::default());
+        assert_eq!(state.top_level.account_root, [3u8; 32]);
+        assert_eq!(state.top_level.mls_group_hash, [0u8; 32]);
+        assert_eq!(state.top_level.stake_transfers_hash, [0u8; 32]);
+        let params = &state.top_level.network_params;
+        assert_eq!(params.get_version(), 0);
+        assert_eq!(params.get_max_validators(), 50);
+        assert_eq!(
+            params.get_dynamic_fee_config(),
+            DynamicFeeParameters::default()
+        );
+        assert_eq!(
+            params.get_governance_config(),
+            GovernanceParameters::default()
+        );
+
+        // the upgraded state can be stored and loaded with the current layout
+        let encoded = state.encode();
+        let decoded =
+            CurrentChainNodeState::decode(&mut encoded.as_slice()).expect("decode upgraded state");
+        assert_eq!(decoded.top_level, state.top_level);
+        assert_eq!(decoded.last_apphash, state.last_apphash);
+    }
+
+    #[test]
+    fn check_baseline_staked_state_is_upgraded() {
+        let address = StakedStateAddress::BasicRedeem([1u8; 20].into());
+        let legacy = StakedState {
+            nonce: 1,
+            bonded: Coin::unit(),
+            unbonded: Coin::zero(),
+            unbonded_from: 0,
+            address,
+            node_meta: Some(NodeState::CouncilNode(Validator {
+                council_node: CouncilNodeMeta {
+                    name: b"node".to_vec(),
+                    security_contact: None,
+                    consensus_pubkey: TendermintValidatorPubKey::Ed25519([4u8; 32]),
+                    keypackage: vec![5u8; 10],
+                },
+                jailed_until: None,
+                inactive_time: None,
+                inactive_block: None,
+                used_validator_addresses: vec![],
+            })),
+            last_slash: None,
+        };
+        let encoded = legacy.encode();
+        let staking = StakedState::decode(&mut encoded.as_slice())
+            .expect("decode baseline staked state")
+            .upgrade()
+            .expect("valid staked state");
+        assert_eq!(staking.address, address);
+        assert_eq!(staking.bonded, Coin::unit());
+        assert_eq!(staking.delegated_to, None);
+        match staking.node_meta {
+            Some(CurrentNodeState::CouncilNode(validator)) => {
+                assert_eq!(validator.council_node.node_info.name, "node");
+                assert_eq!(
+                    validator
+                        .council_node
+                        .node_info
+                        .confidential_init
+                        .keypackage,
+                    vec![5u8; 10]
+                );
+                assert_eq!(validator.council_node.commission_rate.as_millis(), 0);
+            }
+            _ => panic!("expected council node"),
+        }
+    }
+}
@@ -0,0 +1,306 @@
+//! Tendermint peer filtering
+//! ref: https://docs.tendermint.com/master/spec/abci/apps.html#peer-filtering
+//!
+//! When Tendermint connects to a peer, it queries the ABCI application with
+//! `/p2p/filter/addr/<IP:PORT>` and `/p2p/filter/id/<ID>`
+//! and refuses the connection if either of them returns a non-zero code.
+//! The allow / deny lists are loaded from the `peer_filter` section of chain-abci config file
+//! and can be reloaded (when the file changes) without restarting the process.
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+use std::fs::File;
+use std::io::BufReader;
+use std::net::{IpAddr, SocketAddr};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::{Arc, RwLock};
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+use chain_core::state::account::StakedStateAddress;
+use log::{info, warn};
+use serde::Deserialize;
+
+/// how often the config file is checked for changes
+pub const CONFIG_RELOAD_INTERVAL: Duration = Duration::from_secs(5);
+
+/// The peer filter shared between the ABCI connections and the config file watcher
+pub type SharedPeerFilter = Arc<RwLock<PeerFilterConfig>>;
+
+#[derive(thiserror::Error, Debug, PartialEq, Eq)]
+pub enum PeerFilterError {
+    #[error("invalid peer address: {0}")]
+    InvalidAddress(String),
+    #[error("peer address is denied")]
+    AddressDenied,
+    #[error("peer address is not in the allowed ranges")]
+    AddressNotAllowed,
+    #[error("peer node ID is denied")]
+    IdDenied,
+    #[error("peer node ID is not in the allowed IDs")]
+    IdNotAllowed,
+    #[error("peer node ID belongs to a jailed validator")]
+    JailedValidator,
+    #[error("invalid p2p query path")]
+    InvalidPath,
+}
+
+/// IP address range in CIDR notation (e.g. "10.0.0.0/8");
+/// a plain IP address is treated as a single-address range
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub struct IpRange {
+    network: IpAddr,
+    prefix_len: u8,
+}
+
+impl IpRange {
+    /// checks if the address is in this range
+    pub fn contains(&self, addr: &IpAddr) -> bool {
+        match (self.network, addr) {
+            (IpAddr::V4(network), IpAddr::V4(addr)) => {
+                let mask = u32::max_value()
+                    .checked_shl(32 - u32::from(self.prefix_len))
+                    .unwrap_or(0);
+                u32::from(network) & mask == u32::from(*addr) & mask
+            }
+            (IpAddr::V6(network), IpAddr::V6(addr)) => {
+                let mask = u128::max_value()
+                    .checked_shl(128 - u32::from(self.prefix_len))
+                    .unwrap_or(0);
+                u128::from(network) & mask == u128::from(*addr) & mask
+            }
+            _ => false,
+        }
+    }
+}
+
+impl FromStr for IpRange {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.splitn(2, '/');
+        let network = parts
+            .next()
+            .unwrap_or_default()
+            .parse::<IpAddr>()
+            .map_err(|e| format!("invalid IP range {}: {}", s, e))?;
+        let max_len = if network.is_ipv4() { 32 } else { 128 };
+        let prefix_len = match parts.next() {
+            Some(len) => len
+                .parse::<u8>()
+                .ok()
+                .filter(|len| *len <= max_len)
+                .ok_or_else(|| format!("invalid prefix length in IP range {}", s))?,
+            None => max_len,
+        };
+        Ok(IpRange {
+            network,
+            prefix_len,
+        })
+    }
+}
+
+impl TryFrom<String> for IpRange {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+/// Peer filtering rules:
+/// - denied addresses / IDs are always refused
+/// - if the allowed addresses (or IDs) are not empty, only the matching peers are accepted
+#[derive(Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(default)]
+pub struct PeerFilterConfig {
+    /// IP ranges the peers can connect from (all if empty)
+    pub allowed_addresses: Vec<IpRange>,
+    /// IP ranges the peers can't connect from
+    pub denied_addresses: Vec<IpRange>,
+    /// node IDs (hex-encoded) of the peers that can connect (all if empty)
+    pub allowed_ids: Vec<String>,
+    /// node IDs (hex-encoded) of the peers that can't connect
+    pub denied_ids: Vec<String>,
+    /// refuse the peers whose node ID is mapped (in `validator_node_ids`)
+    /// to the staking address of a jailed validator
+    pub deny_jailed_validators: bool,
+    /// node IDs (hex-encoded) of the known validator nodes and their staking addresses
+    /// (the p2p node key is not related to the validator's consensus key)
+    pub validator_node_ids: BTreeMap<String, StakedStateAddress>,
+}
+
+/// the part of chain-abci config file that is reloaded
+#[derive(Deserialize, Default)]
+struct ReloadableConfig {
+    #[serde(default)]
+    peer_filter: PeerFilterConfig,
+}
+
+impl PeerFilterConfig {
+    /// reads the `peer_filter` section of chain-abci config file
+    pub fn from_config_file(path: &Path) -> Result<Self, String> {
+        let file = File::open(path).map_err(|e| format!("can not open {:?}: {}", path, e))?;
+        let config: ReloadableConfig = serde_yaml::from_reader(BufReader::new(file))
+            .map_err(|e| format!("invalid config {:?}: {}", path, e))?;
+        Ok(config.peer_filter)
+    }
+
+    /// checks the peer address ("IP:PORT" or "IP")
+    pub fn check_addr(&self, addr: &str) -> Result<(), PeerFilterError> {
+        let ip = addr
+            .parse::<SocketAddr>()
+            .map(|socket| socket.ip())
+            .or_else(|_| addr.parse::<IpAddr>())
+            .map_err(|_| PeerFilterError::InvalidAddress(addr.to_owned()))?;
+        if self
+            .denied_addresses
+            .iter()
+            .any(|range| range.contains(&ip))
+        {
+            return Err(PeerFilterError::AddressDenied);
+        }
+        if !self.allowed_addresses.is_empty()
+            && !self
+                .allowed_addresses
+                .iter()
+                .any(|range| range.contains(&ip))
+        {
+            return Err(PeerFilterError::AddressNotAllowed);
+        }
+        Ok(())
+    }
+
+    /// checks the peer node ID
+    pub fn check_id(&self, id: &str) -> Result<(), PeerFilterError> {
+        let matches = |ids: &[String]| ids.iter().any(|x| x.eq_ignore_ascii_case(id));
+        if matches(&self.denied_ids) {
+            return Err(PeerFilterError::IdDenied);
+        }
+        if !self.allowed_ids.is_empty() && !matches(&self.allowed_ids) {
+            return Err(PeerFilterError::IdNotAllowed);
+        }
+        Ok(())
+    }
+
+    /// the staking address of the validator node with this ID (if it's configured)
+    pub fn validator_address(&self, id: &str) -> Option<StakedStateAddress> {
+        self.validator_node_ids
+            .iter()
+            .find(|(node_id, _)| node_id.eq_ignore_ascii_case(id))
+            .map(|(_, address)| *address)
+    }
+}
+
+/// Reloads the peer filter whenever the config file is modified.
+/// If the modified file is invalid, the previous rules are kept.
+pub fn spawn_config_watcher(
+    path: PathBuf,
+    filter: SharedPeerFilter,
+    interval: Duration,
+) -> thread::JoinHandle<()> {
+    let modified_time = |path: &Path| -> Option<SystemTime> {
+        path.metadata().and_then(|meta| meta.modified()).ok()
+    };
+    thread::spawn(move || {
+        let mut last_modified = modified_time(&path);
+        loop {
+            thread::sleep(interval);
+            let modified = modified_time(&path);
+            if modified == last_modified {
+                continue;
+            }
+            last_modified = modified;
+            match PeerFilterConfig::from_config_file(&path) {
+                Ok(config) => {
+                    info!("peer filter reloaded from {:?}", path);
+                    *filter.write().expect("peer filter lock poisoned") = config;
+                }
+                Err(e) => {
+                    warn!("peer filter not reloaded: {}", e);
+                }
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_ip_range() {
+        let range: IpRange = "10.1.0.0/16".parse().unwrap();
+        assert!(range.contains(&"10.1.2.3".parse().unwrap()));
+        assert!(!range.contains(&"10.2.0.1".parse().unwrap()));
+        assert!(!range.contains(&"::1".parse().unwrap()));
+        let single: IpRange = "::1".parse().unwrap();
+        assert!(single.contains(&"::1".parse().unwrap()));
+        let all: IpRange = "0.0.0.0/0".parse().unwrap();
+        assert!(all.contains(&"192.168.0.1".parse().unwrap()));
+        assert!("10.0.0.0/33".parse::<IpRange>().is_err());
+        assert!("localhost".parse::<IpRange>().is_err());
+    }
+
+    #[test]
+    fn check_filter_rules() {
+        let config: ReloadableConfig = serde_yaml::from_str(
+            r#"
+port: 26658
+peer_filter:
+  allowed_addresses: ["10.0.0.0/8"]
+  denied_addresses: ["10.0.0.1"]
+  denied_ids: ["ABCDEF"]
+"#,
+        )
+        .unwrap();
+        let filter = config.peer_filter;
+        assert_eq!(filter.check_addr("10.1.1.1:26656"), Ok(()));
+        assert_eq!(
+            filter.check_addr("10.0.0.1:26656"),
+            Err(PeerFilterError::AddressDenied)
+        );
+        assert_eq!(
+            filter.check_addr("192.168.0.1:26656"),
+            Err(PeerFilterError::AddressNotAllowed)
+        );
+        assert!(filter.check_addr("somewhere").is_err());
+        assert_eq!(filter.check_id("abcdef"), Err(PeerFilterError::IdDenied));
+        assert_eq!(filter.check_id("123456"), Ok(()));
+        assert!(!filter.deny_jailed_validators);
+        assert_eq!(filter.validator_address("123456"), None);
+    }
+
+    #[test]
+    fn check_validator_node_ids() {
+        let config: ReloadableConfig = serde_yaml::from_str(
+            r#"
+peer_filter:
+  deny_jailed_validators: true
+  validator_node_ids:
+    ABCDEF: "0x0e7c045110b8dbf29765047380898919c5cb56f4"
+"#,
+        )
+        .unwrap();
+        let filter = config.peer_filter;
+        assert!(filter.deny_jailed_validators);
+        assert_eq!(
+            filter.validator_address("abcdef"),
+            Some(
+                "0x0e7c045110b8dbf29765047380898919c5cb56f4"
+                    .parse()
+                    .unwrap()
+            )
+        );
+        assert_eq!(filter.validator_address("123456"), None);
+    }
+
+    #[test]
+    fn check_missing_section_allows_all() {
+        let config: ReloadableConfig = serde_yaml::from_str("port: 26658").unwrap();
+        assert_eq!(config.peer_filter, PeerFilterConfig::default());
+        assert_eq!(config.peer_filter.check_addr("[::1]:26656"), Ok(()));
+        assert_eq!(config.peer_filter.check_id("abcdef"), Ok(()));
+    }
+}
@@ -0,0 +1,61 @@
+//! Rollback of the node storage to an earlier height (e.g. after a bad upgrade or a non-deterministic bug
+//! caused an app hash mismatch): the node state stored at the height becomes the last one,
+//! so that Tendermint replays the later blocks (with the fixed binary) during the handshake.
+use parity_scale_codec::Decode;
+
+use crate::app::ChainNodeState;
+use crate::migration::{verify_stored_app_hash, MigrationError};
+use chain_core::state::tendermint::BlockHeight;
+use chain_storage::Storage;
+
+#[derive(thiserror::Error, Debug)]
+pub enum RollbackError {
+    #[error("storage io error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("chain node state decode failed: {0}")]
+    Decode(#[from] parity_scale_codec::Error),
+    #[error("no app state stored")]
+    NoState,
+    #[error("rollback height {requested} should be lower than the last committed height {last}")]
+    InvalidHeight {
+        requested: BlockHeight,
+        last: BlockHeight,
+    },
+    #[error("history of the height {0} was pruned")]
+    Pruned(BlockHeight),
+    #[error(
+        "node state at the height {0} not stored (only the last {} heights can be restored)",
+        chain_storage::NODE_STATES_KEPT_HEIGHTS
+    )]
+    NodeStateNotStored(BlockHeight),
+    #[error("verification of the restored state failed: {0}")]
+    Verification(#[from] MigrationError),
+}
+
+/// Reverts the storage to the state committed at `height` and returns the restored state
+pub fn rollback_to_height(
+    storage: &mut Storage,
+    height: BlockHeight,
+) -> Result<ChainNodeState, RollbackError> {
+    let raw_state = storage.get_last_app_state().ok_or(RollbackError::NoState)?;
+    let last_state = ChainNodeState::decode(&mut raw_state.as_slice())?;
+    if height >= last_state.last_block_height {
+        return Err(RollbackError::InvalidHeight {
+            requested: height,
+            last: last_state.last_block_height,
+        });
+    }
+    if let Some(pruned) = chain_storage::get_pruned_height(&*storage) {
+        if height <= pruned {
+            return Err(RollbackError::Pruned(height));
+        }
+    }
+    let raw_state = storage
+        .get_historical_node_state(height)
+        .ok_or(RollbackError::NodeStateNotStored(height))?;
+    let state = ChainNodeState::decode(&mut raw_state.as_slice())?;
+
+    storage.rollback(last_state.last_block_height, height, state.block_time)?;
+    verify_stored_app_hash(storage)?;
+    Ok(state)
+}
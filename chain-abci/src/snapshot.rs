@@ -0,0 +1,288 @@
+//! State snapshots for bootstrapping nodes without replaying all the blocks from genesis.
+//!
+//! A snapshot consists of a manifest (snapshot height, app hash and hashes of the chunks)
+//! and SCALE-encoded chunks with the staked states (at the snapshot's staking version)
+//! and the key-value items (UTXO metadata, chain node state, chain id, network parameters etc.).
+//! When imported, the staking trie root and the top-level state are checked against
+//! a trusted app hash (e.g. from a block header at the snapshot height).
+//! Note that the UTXO metadata is not committed in the app hash, so it's only
+//! verified against the chunk hashes in the manifest.
+//! Sealed transaction payloads are tied to the machine they were sealed on, so they are not included.
+use std::fs;
+use std::path::Path;
+
+use parity_scale_codec::{Decode, Encode};
+
+use crate::app::ChainNodeState;
+use crate::migration::SCHEMA_VERSION;
+use chain_core::common::{MerkleTree, H256};
+use chain_core::state::account::StakedState;
+use chain_core::state::tendermint::BlockHeight;
+use chain_core::AppHashParts;
+use chain_storage::buffer::{flush_storage, BufferStore, Get, KVBuffer, SimpleStore};
+use chain_storage::jellyfish::{compute_staking_root, iter_stakings, restore_stakings};
+use chain_storage::{
+    LookupItem, Storage, COL_APP_HASH_PARTS, COL_EXTRA, COL_MERKLE_PROOFS, COL_NETWORK_PARAMS,
+    COL_NODE_INFO, COL_SLASH_HISTORY, COL_TX_META, GENESIS_APP_HASH_KEY, LAST_STATE_KEY,
+    PRUNED_HEIGHT_KEY,
+};
+
+/// Maximal number of the staked states or key-value items in one chunk
+pub const SNAPSHOT_CHUNK_ITEMS: usize = 10_000;
+
+const MANIFEST_FILE: &str = "manifest";
+
+/// Columns which are (fully) included in snapshots
+const SNAPSHOT_COLUMNS: [u32; 4] = [
+    COL_EXTRA,
+    COL_NETWORK_PARAMS,
+    COL_SLASH_HISTORY,
+    COL_TX_META,
+];
+
+#[derive(thiserror::Error, Debug)]
+pub enum SnapshotError {
+    #[error("snapshot io error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("snapshot decode failed: {0}")]
+    Decode(#[from] parity_scale_codec::Error),
+    #[error("no app state stored")]
+    NoState,
+    #[error("only the last committed height {last} can be exported, requested: {requested}")]
+    UnavailableHeight {
+        requested: BlockHeight,
+        last: BlockHeight,
+    },
+    #[error("storage already contains an app state")]
+    NotEmpty,
+    #[error("snapshot contains {found} chunks, but the manifest lists {expected}")]
+    ChunkCountMismatch { expected: usize, found: usize },
+    #[error("hash of chunk {0} doesn't match the manifest")]
+    ChunkHashMismatch(usize),
+    #[error("unexpected item in column {0}")]
+    UnexpectedItem(u32),
+    #[error("snapshot doesn't contain the chain node state")]
+    MissingState,
+    #[error("chain node state doesn't match the manifest")]
+    StateMismatch,
+    #[error("staking trie root doesn't match the chain node state")]
+    StakingRootMismatch,
+    #[error("app hash doesn't match the trusted one")]
+    AppHashMismatch,
+}
+
+/// Describes the snapshot content
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode)]
+pub struct SnapshotManifest {
+    /// the last committed block height when the snapshot was taken
+    pub height: BlockHeight,
+    /// the app hash after committing the block at `height`
+    pub app_hash: H256,
+    /// blake3 hashes of the encoded chunks
+    pub chunk_hashes: Vec<H256>,
+}
+
+/// Part of the snapshot content
+#[derive(Debug, Clone, Encode, Decode)]
+pub enum SnapshotChunk {
+    /// staked states in the staking trie
+    Stakings(Vec<StakedState>),
+    /// (column, key, value) items in the key-value storage
+    Items(Vec<(u32, Vec<u8>, Vec<u8>)>),
+}
+
+/// Manifest and the encoded chunks
+pub struct Snapshot {
+    pub manifest: SnapshotManifest,
+    pub chunks: Vec<Vec<u8>>,
+}
+
+fn chunk_hash(chunk: &[u8]) -> H256 {
+    blake3::hash(chunk).into()
+}
+
+fn chunk_file_name(index: usize) -> String {
+    format!("chunk-{:06}", index)
+}
+
+/// Takes a snapshot of the last committed state (`height` is only checked if provided)
+pub fn export_snapshot(
+    storage: &Storage,
+    height: Option<BlockHeight>,
+) -> Result<Snapshot, SnapshotError> {
+    let raw_state = storage.get_last_app_state().ok_or(SnapshotError::NoState)?;
+    let state = ChainNodeState::decode(&mut raw_state.as_slice())?;
+    if let Some(requested) = height {
+        if requested != state.last_block_height {
+            return Err(SnapshotError::UnavailableHeight {
+                requested,
+                last: state.last_block_height,
+            });
+        }
+    }
+
+    let mut chunks = Vec::new();
+    let stakings = iter_stakings(storage, state.staking_version).collect::<Vec<_>>();
+    for part in stakings.chunks(SNAPSHOT_CHUNK_ITEMS) {
+        chunks.push(SnapshotChunk::Stakings(part.to_vec()).encode());
+    }
+
+    let mut items = vec![(COL_NODE_INFO, LAST_STATE_KEY.to_vec(), raw_state)];
+    if let Some(genesis_app_hash) = storage.get(&(COL_NODE_INFO, GENESIS_APP_HASH_KEY.to_vec())) {
+        items.push((
+            COL_NODE_INFO,
+            GENESIS_APP_HASH_KEY.to_vec(),
+            genesis_app_hash,
+        ));
+    }
+    for item in [LookupItem::TxsMerkle, LookupItem::AppHashParts].iter() {
+        if let Some(value) = storage.lookup_item(*item, &state.last_apphash) {
+            items.push((*item as u32, state.last_apphash.to_vec(), value));
+        }
+    }
+    for col in SNAPSHOT_COLUMNS.iter() {
+        items.extend(
+            storage
+                .iter_column(*col)
+                .map(|(key, value)| (*col, key.to_vec(), value.to_vec())),
+        );
+    }
+    for part in items.chunks(SNAPSHOT_CHUNK_ITEMS) {
+        chunks.push(SnapshotChunk::Items(part.to_vec()).encode());
+    }
+
+    Ok(Snapshot {
+        manifest: SnapshotManifest {
+            height: state.last_block_height,
+            app_hash: state.last_apphash,
+            chunk_hashes: chunks.iter().map(|chunk| chunk_hash(chunk)).collect(),
+        },
+        chunks,
+    })
+}
+
+fn check_item(col: u32, key: &[u8]) -> Result<(), SnapshotError> {
+    let valid = match col {
+        COL_NODE_INFO => key == LAST_STATE_KEY || key == GENESIS_APP_HASH_KEY,
+        COL_MERKLE_PROOFS | COL_APP_HASH_PARTS => true,
+        _ => SNAPSHOT_COLUMNS.contains(&col),
+    };
+    if valid {
+        Ok(())
+    } else {
+        Err(SnapshotError::UnexpectedItem(col))
+    }
+}
+
+/// Verifies the snapshot against the trusted app hash and writes it into the (empty) storage,
+/// so that the node resumes from the snapshot height
+pub fn import_snapshot(
+    storage: &mut Storage,
+    snapshot: &Snapshot,
+    trusted_app_hash: &H256,
+    write_history_states: bool,
+) -> Result<ChainNodeState, SnapshotError> {
+    if storage.get_last_app_state().is_some() {
+        return Err(SnapshotError::NotEmpty);
+    }
+    let manifest = &snapshot.manifest;
+    if manifest.app_hash != *trusted_app_hash {
+        return Err(SnapshotError::AppHashMismatch);
+    }
+    if manifest.chunk_hashes.len() != snapshot.chunks.len() {
+        return Err(SnapshotError::ChunkCountMismatch {
+            expected: manifest.chunk_hashes.len(),
+            found: snapshot.chunks.len(),
+        });
+    }
+
+    let mut stakings = Vec::new();
+    let mut items = Vec::new();
+    let mut mstate = None;
+    for (i, (chunk, hash)) in snapshot
+        .chunks
+        .iter()
+        .zip(manifest.chunk_hashes.iter())
+        .enumerate()
+    {
+        if chunk_hash(chunk) != *hash {
+            return Err(SnapshotError::ChunkHashMismatch(i));
+        }
+        match SnapshotChunk::decode(&mut chunk.as_slice())? {
+            SnapshotChunk::Stakings(part) => stakings.extend(part),
+            SnapshotChunk::Items(part) => {
+                for (col, key, value) in part.into_iter() {
+                    check_item(col, &key)?;
+                    if col == COL_NODE_INFO && key == LAST_STATE_KEY {
+                        mstate = Some(ChainNodeState::decode(&mut value.as_slice())?);
+                    } else {
+                        items.push((col, key, value));
+                    }
+                }
+            }
+        }
+    }
+
+    let state = mstate.ok_or(SnapshotError::MissingState)?;
+    if state.last_block_height != manifest.height || state.last_apphash != manifest.app_hash {
+        return Err(SnapshotError::StateMismatch);
+    }
+    if stakings.is_empty() || compute_staking_root(&stakings) != state.top_level.account_root {
+        return Err(SnapshotError::StakingRootMismatch);
+    }
+    // no txs merkle tree is stored for genesis
+    let tree = match items
+        .iter()
+        .find(|(col, key, _)| *col == COL_MERKLE_PROOFS && key.as_slice() == &manifest.app_hash[..])
+    {
+        Some((_, _, value)) => MerkleTree::decode(&mut value.as_slice())?,
+        None => MerkleTree::empty(),
+    };
+    let app_hash = AppHashParts::new(&tree, &state.top_level).app_hash();
+    if app_hash != *trusted_app_hash {
+        return Err(SnapshotError::AppHashMismatch);
+    }
+
+    let mut kv_buffer = KVBuffer::new();
+    {
+        let mut store = BufferStore::new(&*storage, &mut kv_buffer);
+        restore_stakings(&mut store, state.staking_version, &stakings)
+            .expect("merkle trie io error");
+        for (col, key, value) in items.into_iter() {
+            store.set((col, key), value);
+        }
+        // nothing to prune below the snapshot height
+        if let Some(pruned) = state.last_block_height.checked_sub(1) {
+            store.set((COL_NODE_INFO, PRUNED_HEIGHT_KEY.to_vec()), pruned.encode());
+        }
+        chain_storage::store_schema_version(&mut store, SCHEMA_VERSION);
+        chain_storage::store_chain_state(
+            &mut store,
+            &state,
+            state.last_block_height,
+            write_history_states,
+        );
+    }
+    flush_storage(storage, kv_buffer)?;
+    Ok(state)
+}
+
+/// Writes the manifest and chunks as separate files into the directory
+pub fn write_snapshot(dir: &Path, snapshot: &Snapshot) -> Result<(), SnapshotError> {
+    fs::create_dir_all(dir)?;
+    for (i, chunk) in snapshot.chunks.iter().enumerate() {
+        fs::write(dir.join(chunk_file_name(i)), chunk)?;
+    }
+    // the manifest is written last, so an incomplete snapshot doesn't have it
+    fs::write(dir.join(MANIFEST_FILE), snapshot.manifest.encode())?;
+    Ok(())
+}
+
+/// Reads the snapshot written by `write_snapshot` (the chunks are verified when imported)
+pub fn read_snapshot(dir: &Path) -> Result<Snapshot, SnapshotError> {
+    let manifest = SnapshotManifest::decode(&mut fs::read(dir.join(MANIFEST_FILE))?.as_slice())?;
+    let chunks = (0..manifest.chunk_hashes.len())
+        .map(|i| fs::read(dir.join(chunk_file_name(i))))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(Snapshot { manifest, chunks })
+}
@@ -5,6 +5,7 @@ pub use table::{RewardsDistribution, StakingTable};
 
 #[cfg(test)]
 mod tests {
+    use parity_scale_codec::{Decode, Encode};
     use secp256k1::{
         key::{PublicKey, SecretKey},
         Secp256k1,
@@ -16,10 +17,13 @@ mod tests {
     use chain_core::init::config::SlashRatio;
     use chain_core::init::params::NetworkParameters;
     use chain_core::state::account::{
-        NodeState, PunishmentKind, StakedState, StakedStateAddress, UnbondTx, UnjailTx, Validator,
+        CommissionRate, DelegateTx, NodeState, PunishmentKind, StakeKind, StakedState,
+        StakedStateAddress, TransferStakeTx, UnbondTx, UnjailTx, Validator,
     };
-    use chain_core::state::tendermint::{BlockHeight, TendermintValidatorPubKey};
-    use chain_core::state::validator::NodeJoinRequestTx;
+    use chain_core::state::tendermint::{
+        BlockHeight, TendermintValidatorAddress, TendermintValidatorPubKey, TendermintVotePower,
+    };
+    use chain_core::state::validator::{NodeJoinRequestTx, NodeMetadataUpdateTx};
     use chain_core::tx::fee::Fee;
     use chain_storage::buffer::{Get, GetStaking, MemStore, StoreStaking};
     use test_common::chain_env::{
@@ -27,10 +31,11 @@ mod tests {
     };
 
     use super::*;
-    use crate::app::BeginBlockInfo;
-    use crate::staking::table::{PunishmentOutcome, SlashedCoin};
+    use crate::app::{BeginBlockInfo, EvidenceKind};
+    use crate::staking::table::{PunishmentOutcome, RewardRecord, SlashedCoin};
     use crate::tx_error::{
-        DepositError, NodeJoinError, PublicTxError, UnbondError, UnjailError, WithdrawError,
+        DelegateError, DepositError, NodeJoinError, NodeMetadataUpdateError, PublicTxError,
+        TransferStakeError, UnbondError, UnjailError, WithdrawError,
     };
 
     macro_rules! matches {
@@ -200,7 +205,12 @@ mod tests {
         let (mut table, mut store) = init_staking_table();
         let addr1 = staking_address(&[0xcc; 32]);
         let val_pk1 = validator_pubkey(&[0xcc; 32]);
-        let evidence = (val_pk1.clone().into(), 1.into(), DEFAULT_GENESIS_TIME);
+        let evidence = (
+            val_pk1.clone().into(),
+            1.into(),
+            DEFAULT_GENESIS_TIME,
+            EvidenceKind::LightClientAttack,
+        );
         let block_time: u64 = DEFAULT_GENESIS_TIME;
         let punishment_outcomes = table.begin_block(
             &mut store,
@@ -222,6 +232,7 @@ mod tests {
             },
             punishment_kind: PunishmentKind::ByzantineFault,
             jailed_until: Some(block_time.saturating_add(info.get_unbonding_period())),
+            delegated_to: None,
         };
         assert_eq!(punishment_outcomes, vec![punishment_outcome]);
         let staking = store.get(&addr1).unwrap();
@@ -299,6 +310,72 @@ mod tests {
         assert!(!staking.is_jailed());
     }
 
+    #[test]
+    fn check_tombstoning() {
+        let params = NetworkParameters::Genesis(get_init_network_params(Coin::zero()));
+        let info = BeginBlockInfo {
+            params: &params,
+            max_evidence_age: 10,
+            block_time: DEFAULT_GENESIS_TIME,
+            block_height: 0.into(),
+            voters: &[],
+            evidences: &[],
+        };
+
+        let (mut table, mut store) = init_staking_table();
+        let addr1 = staking_address(&[0xcc; 32]);
+        let val_pk1 = validator_pubkey(&[0xcc; 32]);
+        let evidence = (
+            val_pk1.clone().into(),
+            1.into(),
+            DEFAULT_GENESIS_TIME,
+            EvidenceKind::DuplicateVote,
+        );
+        let punishment_outcomes = table.begin_block(
+            &mut store,
+            &BeginBlockInfo {
+                block_height: 1.into(),
+                evidences: &[evidence.clone()],
+                ..info
+            },
+        );
+        assert_eq!(punishment_outcomes.len(), 1);
+        assert!(table.is_tombstoned(&addr1, &val_pk1.clone().into()));
+
+        // the same evidence included in a later block is ignored
+        let punishment_outcomes = table.begin_block(
+            &mut store,
+            &BeginBlockInfo {
+                block_time: DEFAULT_GENESIS_TIME + 1,
+                block_height: 2.into(),
+                evidences: &[evidence],
+                ..info
+            },
+        );
+        assert_eq!(punishment_outcomes, vec![]);
+
+        let tx = UnjailTx {
+            nonce: 0,
+            address: addr1,
+            attributes: Default::default(),
+        };
+        table
+            .unjail(&mut store, DEFAULT_GENESIS_TIME + 1 + 10, &tx)
+            .unwrap();
+
+        // can't re-join even with a new validator key after the jail time expired
+        let node_join = NodeJoinRequestTx {
+            nonce: 1,
+            address: addr1,
+            attributes: Default::default(),
+            node_meta: mock_council_node(validator_pubkey(&[0xcf; 32])),
+        };
+        assert!(matches!(
+            table.node_join(&mut store, DEFAULT_GENESIS_TIME + 12, 0, 0, &node_join),
+            Err(PublicTxError::NodeJoin(NodeJoinError::Tombstoned))
+        ));
+    }
+
     fn unbond_deposit_rejoin(
         table: &mut StakingTable,
         store: &mut impl StoreStaking,
@@ -403,6 +480,473 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn check_node_metadata_update() {
+        let (mut table, mut store) = init_staking_table();
+        let addr1 = staking_address(&[0xcc; 32]);
+        let val_pk1 = validator_pubkey(&[0xcc; 32]);
+        let val_pk_new = validator_pubkey(&[0xc0; 32]);
+        let staking = store.get(&addr1).unwrap();
+        let fee = Fee::new(Coin::new(1000).unwrap());
+
+        let update = NodeMetadataUpdateTx::new(
+            staking.nonce,
+            addr1,
+            Default::default(),
+            "new name".to_owned(),
+            None,
+            Some(val_pk_new.clone()),
+        );
+        let council_node = table
+            .node_metadata_update(
+                &mut store,
+                DEFAULT_GENESIS_TIME + 1,
+                2.into(),
+                1,
+                &update,
+                fee,
+            )
+            .unwrap();
+        assert_eq!(council_node.node_info.name, "new name");
+        assert_eq!(council_node.consensus_pubkey, val_pk_new);
+        assert_eq!(store.get(&addr1).unwrap().nonce, staking.nonce + 1);
+        // the fee is paid from the bonded coins
+        let bonded = (staking.bonded - fee.to_coin()).unwrap();
+        assert_eq!(store.get(&addr1).unwrap().bonded, bonded);
+
+        // old key removed, new key added with the power after the fee
+        assert_eq!(
+            table.end_block(&store, 3),
+            vec![
+                (val_pk1.clone(), Coin::zero().into()),
+                (val_pk_new.clone(), bonded.into())
+            ]
+        );
+        assert_eq!(table.end_block(&store, 3), vec![]);
+        // old key is still known for evidences
+        assert_eq!(
+            table.lookup_address(&TendermintValidatorAddress::from(&val_pk1)),
+            Some(&addr1)
+        );
+
+        // replay
+        assert!(matches!(
+            table.node_metadata_update(
+                &mut store,
+                DEFAULT_GENESIS_TIME + 1,
+                2.into(),
+                1,
+                &update,
+                fee
+            ),
+            Err(PublicTxError::IncorrectNonce)
+        ));
+
+        // can't rotate back to the old key while it's still known for evidences
+        let update = NodeMetadataUpdateTx::new(
+            staking.nonce + 1,
+            addr1,
+            Default::default(),
+            "new name".to_owned(),
+            None,
+            Some(val_pk1),
+        );
+        assert!(matches!(
+            table.node_metadata_update(
+                &mut store,
+                DEFAULT_GENESIS_TIME + 1,
+                2.into(),
+                1,
+                &update,
+                fee
+            ),
+            Err(PublicTxError::NodeMetadataUpdate(
+                NodeMetadataUpdateError::DuplicateValidatorAddress
+            ))
+        ));
+
+        // can't rotate to the key of another validator
+        let addr2 = staking_address(&[0xcd; 32]);
+        let update = NodeMetadataUpdateTx::new(
+            store.get(&addr2).unwrap().nonce,
+            addr2,
+            Default::default(),
+            "name".to_owned(),
+            None,
+            Some(val_pk_new),
+        );
+        assert!(matches!(
+            table.node_metadata_update(
+                &mut store,
+                DEFAULT_GENESIS_TIME + 1,
+                2.into(),
+                1,
+                &update,
+                fee
+            ),
+            Err(PublicTxError::NodeMetadataUpdate(
+                NodeMetadataUpdateError::DuplicateValidatorAddress
+            ))
+        ));
+
+        // not a council node
+        let addr_new = staking_address(&[0xcf; 32]);
+        let update = NodeMetadataUpdateTx::new(
+            0,
+            addr_new,
+            Default::default(),
+            "name".to_owned(),
+            None,
+            None,
+        );
+        assert!(matches!(
+            table.node_metadata_update(
+                &mut store,
+                DEFAULT_GENESIS_TIME + 1,
+                2.into(),
+                1,
+                &update,
+                fee
+            ),
+            Err(PublicTxError::NodeMetadataUpdate(
+                NodeMetadataUpdateError::NotCouncilNode
+            ))
+        ));
+    }
+
+    #[test]
+    fn check_transfer_stake() {
+        let (mut table, mut store) = init_staking_table();
+        let mut init_params = get_init_network_params(Coin::zero());
+        let slash_percent = "0.1";
+        init_params.slashing_config.byzantine_slash_percent = slash_percent.parse().unwrap();
+        let params = NetworkParameters::Genesis(init_params);
+
+        let addr1 = staking_address(&[0xcc; 32]);
+        let addr3 = staking_address(&[0xce; 32]);
+        let val_pk3 = validator_pubkey(&[0xce; 32]);
+        let amount = Coin::new(1_0000_0000).unwrap();
+
+        let mut tx = TransferStakeTx::new(
+            addr3,
+            addr3,
+            0,
+            amount,
+            StakeKind::Bonded,
+            Default::default(),
+        );
+        assert!(matches!(
+            table.transfer_stake(
+                &mut store,
+                10,
+                DEFAULT_GENESIS_TIME + 1,
+                1.into(),
+                &tx,
+                Fee::zero()
+            ),
+            Err(PublicTxError::TransferStake(
+                TransferStakeError::SameAddress
+            ))
+        ));
+
+        tx.to_staked_account = addr1;
+        let unbonded_from = table
+            .transfer_stake(
+                &mut store,
+                10,
+                DEFAULT_GENESIS_TIME + 1,
+                1.into(),
+                &tx,
+                Fee::zero(),
+            )
+            .unwrap();
+        assert_eq!(unbonded_from, None);
+        let bonded1 = store.get(&addr1).unwrap().bonded;
+        let bonded3 = store.get(&addr3).unwrap().bonded;
+        assert_eq!(bonded1, Coin::new(12_0000_0000).unwrap());
+        assert_eq!(bonded3, Coin::new(12_0000_0000).unwrap());
+        assert_eq!(store.get(&addr3).unwrap().nonce, 1);
+        // both voting powers are updated
+        assert_eq!(table.end_block(&store, 3).len(), 2);
+
+        // the transferred stake is slashed for the faults of the source
+        let punishment_outcomes = table.begin_block(
+            &mut store,
+            &BeginBlockInfo {
+                params: &params,
+                block_time: DEFAULT_GENESIS_TIME + 2,
+                block_height: 2.into(),
+                max_evidence_age: 10,
+                voters: &[],
+                evidences: &[(
+                    val_pk3.into(),
+                    1.into(),
+                    DEFAULT_GENESIS_TIME + 1,
+                    EvidenceKind::LightClientAttack,
+                )],
+            },
+        );
+        let slash_ratio = SlashRatio::from_str(slash_percent).unwrap();
+        assert_eq!(punishment_outcomes.len(), 2);
+        assert_eq!(punishment_outcomes[0].staking_address, addr3);
+        assert_eq!(
+            punishment_outcomes[1],
+            PunishmentOutcome {
+                staking_address: addr1,
+                slashed_coin: SlashedCoin {
+                    bonded: amount * slash_ratio,
+                    unbonded: Coin::zero(),
+                },
+                punishment_kind: PunishmentKind::ByzantineFault,
+                jailed_until: None,
+                delegated_to: None,
+            }
+        );
+        let staking1 = store.get(&addr1).unwrap();
+        assert_eq!(staking1.bonded, (bonded1 - amount * slash_ratio).unwrap());
+        assert!(!staking1.is_jailed());
+    }
+
+    #[test]
+    fn check_transfer_stake_slash_after_reload() {
+        let (mut table, mut store) = init_staking_table();
+        let mut init_params = get_init_network_params(Coin::zero());
+        init_params.slashing_config.byzantine_slash_percent = "0.1".parse().unwrap();
+        let params = NetworkParameters::Genesis(init_params);
+
+        let addr1 = staking_address(&[0xcc; 32]);
+        let addr3 = staking_address(&[0xce; 32]);
+        let val_pk3 = validator_pubkey(&[0xce; 32]);
+        assert_eq!(table.stake_transfers_hash(), [0u8; 32]);
+
+        let tx = TransferStakeTx::new(
+            addr3,
+            addr1,
+            0,
+            Coin::new(1_0000_0000).unwrap(),
+            StakeKind::Bonded,
+            Default::default(),
+        );
+        table
+            .transfer_stake(
+                &mut store,
+                10,
+                DEFAULT_GENESIS_TIME + 1,
+                1.into(),
+                &tx,
+                Fee::zero(),
+            )
+            .unwrap();
+        table.end_block(&store, 3);
+        // the transfer is committed in the app hash
+        assert_ne!(table.stake_transfers_hash(), [0u8; 32]);
+
+        // restart: the table is restored from its stored encoding
+        let mut reloaded_store = store.clone();
+        let mut reloaded = StakingTable::decode(&mut table.encode().as_slice()).unwrap();
+        reloaded.initialize(&reloaded_store, Coin::new(10_0000_0000).unwrap());
+        assert_eq!(
+            reloaded.stake_transfers_hash(),
+            table.stake_transfers_hash()
+        );
+
+        let info = BeginBlockInfo {
+            params: &params,
+            block_time: DEFAULT_GENESIS_TIME + 2,
+            block_height: 2.into(),
+            max_evidence_age: 10,
+            voters: &[],
+            evidences: &[(
+                val_pk3.into(),
+                1.into(),
+                DEFAULT_GENESIS_TIME + 1,
+                EvidenceKind::LightClientAttack,
+            )],
+        };
+        let punishment_outcomes = table.begin_block(&mut store, &info);
+        let reloaded_outcomes = reloaded.begin_block(&mut reloaded_store, &info);
+        assert_eq!(punishment_outcomes.len(), 2);
+        assert_eq!(reloaded_outcomes, punishment_outcomes);
+        assert_eq!(reloaded_store.get(&addr1), store.get(&addr1));
+        assert_eq!(reloaded_store.get(&addr3), store.get(&addr3));
+        assert_eq!(
+            reloaded.stake_transfers_hash(),
+            table.stake_transfers_hash()
+        );
+    }
+
+    #[test]
+    fn check_delegation() {
+        let (mut table, mut store) = init_staking_table();
+        let mut init_params = get_init_network_params(Coin::zero());
+        let slash_percent = "0.1";
+        init_params.slashing_config.byzantine_slash_percent = slash_percent.parse().unwrap();
+        let params = NetworkParameters::Genesis(init_params);
+
+        let addr1 = staking_address(&[0xcc; 32]);
+        let addr3 = staking_address(&[0xce; 32]);
+        let val_pk3 = validator_pubkey(&[0xce; 32]);
+        let addr = staking_address(&[0xdd; 32]);
+        let mut staking = StakedState::default(addr);
+        staking.bonded = Coin::new(10_0000_0000).unwrap();
+        store.set_staking(staking);
+
+        // the council node keeps 10% of the rewards of the backing staked states
+        let mut council_node = store.get(&addr3).unwrap();
+        if let Some(NodeState::CouncilNode(val)) = council_node.node_meta.as_mut() {
+            val.council_node.commission_rate = CommissionRate::from_millis(100).unwrap();
+        }
+        store.set_staking(council_node);
+
+        let mut tx = DelegateTx::new(addr, Some(addr), 0, Default::default());
+        assert!(matches!(
+            table.delegate(&mut store, &tx),
+            Err(PublicTxError::Delegate(DelegateError::NotCouncilNode))
+        ));
+        let council_tx = DelegateTx::new(addr1, Some(addr3), 0, Default::default());
+        assert!(matches!(
+            table.delegate(&mut store, &council_tx),
+            Err(PublicTxError::Delegate(DelegateError::IsCouncilNode))
+        ));
+
+        tx.council_node = Some(addr3);
+        table.delegate(&mut store, &tx).unwrap();
+        let staking = store.get(&addr).unwrap();
+        assert_eq!(staking.delegated_to, Some(addr3));
+        assert_eq!(staking.nonce, 1);
+        tx.nonce = 1;
+        assert!(matches!(
+            table.delegate(&mut store, &tx),
+            Err(PublicTxError::Delegate(DelegateError::Unchanged))
+        ));
+
+        // the backing staked state can't join as a council node
+        let node_join = NodeJoinRequestTx {
+            nonce: 1,
+            address: addr,
+            attributes: Default::default(),
+            node_meta: mock_council_node(validator_pubkey(&[0xdd; 32])),
+        };
+        assert!(matches!(
+            table.node_join(&mut store, DEFAULT_GENESIS_TIME + 1, 0, 0, &node_join),
+            Err(PublicTxError::NodeJoin(NodeJoinError::IsDelegating))
+        ));
+
+        // rewards are split proportionally to the stake, minus the commission
+        assert_eq!(
+            table.reward_total_staking(&store),
+            Coin::new(46_0000_0000).unwrap()
+        );
+        assert!(table.reward_record(
+            &store,
+            &val_pk3.clone().into(),
+            TendermintVotePower::new(13).unwrap()
+        ));
+        let (remainder, distributed) =
+            table.reward_distribute(&mut store, Coin::new(23_0000).unwrap());
+        assert_eq!(remainder, Coin::zero());
+        assert_eq!(
+            distributed,
+            vec![
+                RewardRecord {
+                    staking_address: addr,
+                    amount: Coin::new(9_0000).unwrap(),
+                    commission: Coin::zero(),
+                    delegated_to: Some(addr3),
+                },
+                RewardRecord {
+                    staking_address: addr3,
+                    amount: Coin::new(14_0000).unwrap(),
+                    commission: Coin::new(1_0000).unwrap(),
+                    delegated_to: None,
+                },
+            ]
+        );
+        let bonded = store.get(&addr).unwrap().bonded;
+        assert_eq!(bonded, Coin::new(10_0009_0000).unwrap());
+
+        // the backing staked state is slashed together with the council node
+        let punishment_outcomes = table.begin_block(
+            &mut store,
+            &BeginBlockInfo {
+                params: &params,
+                block_time: DEFAULT_GENESIS_TIME + 2,
+                block_height: 2.into(),
+                max_evidence_age: 10,
+                voters: &[],
+                evidences: &[(
+                    val_pk3.into(),
+                    1.into(),
+                    DEFAULT_GENESIS_TIME + 1,
+                    EvidenceKind::LightClientAttack,
+                )],
+            },
+        );
+        let slash_ratio = SlashRatio::from_str(slash_percent).unwrap();
+        assert_eq!(punishment_outcomes.len(), 2);
+        assert_eq!(punishment_outcomes[0].staking_address, addr3);
+        assert_eq!(
+            punishment_outcomes[1],
+            PunishmentOutcome {
+                staking_address: addr,
+                slashed_coin: SlashedCoin {
+                    bonded: bonded * slash_ratio,
+                    unbonded: Coin::zero(),
+                },
+                punishment_kind: PunishmentKind::ByzantineFault,
+                jailed_until: None,
+                delegated_to: Some(addr3),
+            }
+        );
+
+        // the jailed council node can't be backed
+        let tx = DelegateTx::new(addr, None, 1, Default::default());
+        table.delegate(&mut store, &tx).unwrap();
+        let tx = DelegateTx::new(addr, Some(addr3), 2, Default::default());
+        assert!(matches!(
+            table.delegate(&mut store, &tx),
+            Err(PublicTxError::Delegate(DelegateError::CouncilNodeJailed))
+        ));
+        assert_eq!(store.get(&addr).unwrap().delegated_to, None);
+    }
+
+    #[test]
+    fn check_delegation_voting_power() {
+        let (mut table, mut store) = init_staking_table();
+        let addr1 = staking_address(&[0xcc; 32]);
+        let val_pk1 = validator_pubkey(&[0xcc; 32]);
+        let addr = staking_address(&[0xdd; 32]);
+        let mut staking = StakedState::default(addr);
+        staking.bonded = Coin::new(10_0000_0000).unwrap();
+        store.set_staking(staking);
+
+        // the council node with the least own bonded coins is backed by the most stake
+        let tx = DelegateTx::new(addr, Some(addr1), 0, Default::default());
+        table.delegate(&mut store, &tx).unwrap();
+        assert_eq!(
+            table.delegated_staking(&store, &addr1),
+            Coin::new(10_0000_0000).unwrap()
+        );
+
+        // the backed stake doesn't change the voting power
+        assert_eq!(table.end_block(&store, 3), vec![]);
+        let council_node = table
+            .list_council_nodes(&store)
+            .into_iter()
+            .find(|node| node.staking_address == addr1)
+            .unwrap();
+        assert_eq!(
+            council_node.voting_power,
+            Coin::new(11_0000_0000).unwrap().into()
+        );
+
+        // nor the validator selection
+        assert_eq!(
+            table.end_block(&store, 2),
+            vec![(val_pk1, Coin::zero().into())]
+        );
+    }
+
     #[test]
     fn check_nonlive_fault() {
         let (mut table, mut store) = init_staking_table();
@@ -738,7 +1282,12 @@ mod tests {
             &BeginBlockInfo {
                 block_time,
                 block_height: 2.into(),
-                evidences: &[(val_pk1.clone().into(), 1.into(), DEFAULT_GENESIS_TIME + 1)],
+                evidences: &[(
+                    val_pk1.clone().into(),
+                    1.into(),
+                    DEFAULT_GENESIS_TIME + 1,
+                    EvidenceKind::LightClientAttack,
+                )],
                 ..info
             },
         );
@@ -756,6 +1305,7 @@ mod tests {
                 },
                 punishment_kind: PunishmentKind::ByzantineFault,
                 jailed_until: Some(expected_jailed_until),
+                delegated_to: None,
             }]
         );
         let staking = store.get(&addr1).unwrap();
@@ -817,7 +1367,12 @@ mod tests {
             &BeginBlockInfo {
                 block_time,
                 block_height: 3.into(),
-                evidences: &[(val_pk2.clone().into(), 2.into(), DEFAULT_GENESIS_TIME + 2)],
+                evidences: &[(
+                    val_pk2.clone().into(),
+                    2.into(),
+                    DEFAULT_GENESIS_TIME + 2,
+                    EvidenceKind::LightClientAttack,
+                )],
                 ..info
             },
         );
@@ -832,6 +1387,7 @@ mod tests {
                 },
                 punishment_kind: PunishmentKind::ByzantineFault,
                 jailed_until: Some(expected_jailed_until),
+                delegated_to: None,
             }]
         );
         let staking = store.get(&addr2).unwrap();
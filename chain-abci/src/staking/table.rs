@@ -8,22 +8,34 @@ use core::cmp::Ordering;
 use parity_scale_codec::{Decode, Encode};
 use serde::{Deserialize, Serialize};
 
-use chain_core::common::Timespec;
+use chain_core::common::{Timespec, H256};
 use chain_core::init::coin::{sum_coins, Coin, CoinError, CoinResult};
 use chain_core::init::config::SlashRatio;
 use chain_core::state::account::{
-    NodeName, NodeSecurityContact, NodeState, PunishmentKind, SlashRecord, StakedState,
-    StakedStateAddress,
+    CommissionRate, NodeName, NodeSecurityContact, NodeState, PunishmentKind, SlashRecord,
+    StakedState, StakedStateAddress,
 };
 use chain_core::state::tendermint::{
     BlockHeight, TendermintValidatorAddress, TendermintValidatorPubKey, TendermintVotePower,
 };
 use chain_storage::buffer::{GetStaking, StoreStaking};
 
-use crate::app::BeginBlockInfo;
+use crate::app::{BeginBlockInfo, EvidenceKind};
 use crate::liveness::LivenessTracker;
 
-pub type RewardsDistribution = Vec<(StakedStateAddress, Coin)>;
+/// Rewards received by a staking address in one distribution
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RewardRecord {
+    pub staking_address: StakedStateAddress,
+    /// the amount added to the bonded coins
+    pub amount: Coin,
+    /// the commission kept from the rewards of the backing staked states (council nodes only)
+    pub commission: Coin,
+    /// the council node whose rewards were shared (backing staked states only)
+    pub delegated_to: Option<StakedStateAddress>,
+}
+
+pub type RewardsDistribution = Vec<RewardRecord>;
 
 #[derive(Debug, Clone, Serialize)]
 /// Metadata of a validator
@@ -41,6 +53,12 @@ pub struct CouncilNodeMetadata {
 }
 
 /// order by bonded desc, staking_address
+///
+/// Only the council node's own bonded coins count (for the validator selection and the voting power),
+/// the bonded coins of the staked states backing it (`DelegateTx`) don't:
+/// keeping the index sorted by the backed stake would require re-sorting the council node
+/// on every change of a backing staked state's bonded coins.
+/// The backed stake is only counted in the rewards distribution (and shared by the slashing).
 #[derive(Clone, Debug, PartialEq, Eq, Encode, Decode, Serialize, Deserialize)]
 pub struct ValidatorSortKey {
     pub bonded: Coin,
@@ -84,6 +102,17 @@ pub struct PunishmentOutcome {
     pub slashed_coin: SlashedCoin,
     pub punishment_kind: PunishmentKind,
     pub jailed_until: Option<Timespec>,
+    /// the punished council node if the staking address is slashed because it backs it
+    pub delegated_to: Option<StakedStateAddress>,
+}
+
+/// Stake moved out of a staked state by `TransferStakeTx`,
+/// it's slashed together with the source until the end of the source's unbonding window.
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode)]
+pub struct StakeTransferRecord {
+    pub to: StakedStateAddress,
+    pub amount: Coin,
+    pub until: Timespec,
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -120,12 +149,28 @@ impl SlashedCoin {
 /// Invariant 2.4:
 ///   idx_* only contains CouncilNode not CommunityNode
 ///   Proof: checked during insertion
+///
+/// Invariant 2.5:
+///   `delegators` is consistent with `StakedState::delegated_to` on heap,
+///   and the keys of `delegators` have validator record.
+///   Proof: `delegated_to` is only changed by `delegate` and `cleanup` together with the index.
 #[derive(Clone, Debug, Default, Encode, Decode)]
 pub struct StakingTable {
     // Selected validator voting powers of last executed end block
     chosen_validators: BTreeMap<StakedStateAddress, TendermintVotePower>,
     liveness: BTreeMap<StakedStateAddress, LivenessTracker>,
     participator_stats: BTreeMap<StakedStateAddress, u64>,
+    // Stake transferred out of the source address that may still be slashed
+    pub(crate) stake_transfers: BTreeMap<StakedStateAddress, Vec<StakeTransferRecord>>,
+    // Staking addresses backing each council node
+    pub(crate) delegators: BTreeMap<StakedStateAddress, BTreeSet<StakedStateAddress>>,
+    // Staking addresses and consensus keys of the council nodes punished for double signing,
+    // they are permanently banned from joining again
+    pub(crate) tombstones: BTreeSet<StakedStateAddress>,
+    pub(crate) tombstoned_validator_addresses: BTreeSet<TendermintValidatorAddress>,
+    // Processed byzantine evidences (validator address + height) and their time,
+    // kept until they are too old to be processed again
+    processed_evidences: BTreeMap<(TendermintValidatorAddress, BlockHeight), Timespec>,
 
     // Call `initialize` to populate the indexes after deserialized.
     // Keep the recent value of minimal_required_staking to do sanity check on validator states.
@@ -135,6 +180,10 @@ pub struct StakingTable {
     pub(crate) idx_validator_address: BTreeMap<TendermintValidatorAddress, StakedStateAddress>,
     #[codec(skip)]
     idx_sort: BTreeSet<ValidatorSortKey>,
+    // Consensus keys known to Tendermint for validators that rotated them since the last end block.
+    // It's drained in `end_block`, so it's always empty when the table is persisted.
+    #[codec(skip)]
+    pub(crate) pending_key_rotations: BTreeMap<StakedStateAddress, TendermintValidatorPubKey>,
 }
 
 /// Returned if the caller did not do the necessary validations
@@ -172,6 +221,21 @@ impl StakingTable {
         tbl
     }
 
+    /// Upgrade the table stored before the schema versioning
+    /// (call `initialize` afterwards to populate the indexes)
+    pub(crate) fn from_legacy(
+        chosen_validators: BTreeMap<StakedStateAddress, TendermintVotePower>,
+        liveness: BTreeMap<StakedStateAddress, LivenessTracker>,
+        participator_stats: BTreeMap<StakedStateAddress, u64>,
+    ) -> Self {
+        Self {
+            chosen_validators,
+            liveness,
+            participator_stats,
+            ..Default::default()
+        }
+    }
+
     /// After restored from storage, call initialize to populate the indexes
     pub fn initialize(&mut self, heap: &impl GetStaking, minimal_required_staking: Coin) {
         assert!(self.idx_sort.is_empty());
@@ -224,6 +288,37 @@ impl StakingTable {
         updates
     }
 
+    /// Update the minimal required staking (changed by governance),
+    /// the active validators with less bonded coins are inactivated
+    pub fn set_minimal_required_staking(
+        &mut self,
+        heap: &mut impl StoreStaking,
+        block_time: Timespec,
+        block_height: BlockHeight,
+        minimal_required_staking: Coin,
+    ) {
+        let below = self
+            .idx_sort
+            .iter()
+            .rev()
+            .take_while(|key| key.bonded < minimal_required_staking)
+            .map(|key| key.address)
+            .collect::<Vec<_>>();
+        self.minimal_required_staking = minimal_required_staking;
+        for addr in below.iter() {
+            // no panic: Invariant 2.1
+            let mut staking = heap.get(addr).unwrap();
+            if let Some(NodeState::CouncilNode(val)) = staking.node_meta.as_mut() {
+                if val.is_active() {
+                    val.inactivate(block_time, block_height);
+                    set_staking(heap, staking, self.minimal_required_staking);
+                }
+            }
+        }
+        #[cfg(debug_assertions)]
+        self.check_invariants(heap);
+    }
+
     /// Handle reward statistics record
     pub fn reward_record(
         &mut self,
@@ -252,48 +347,126 @@ impl StakingTable {
     /// The heap should not use the uncommited buffer.
     pub fn reward_total_staking(&self, heap: &impl GetStaking) -> Coin {
         // Sum of all the coins should not overflow max supply, TODO proof.
-        sum_coins(
-            self.chosen_validators
-                .keys()
-                .map(|addr| heap.get(addr).unwrap().bonded),
-        )
+        sum_coins(self.chosen_validators.keys().map(|addr| {
+            (heap.get(addr).unwrap().bonded + self.delegated_staking(heap, addr)).unwrap()
+        }))
         .unwrap()
     }
 
+    /// Sum of the bonded coins of the staking addresses backing the council node
+    pub fn delegated_staking(&self, heap: &impl GetStaking, addr: &StakedStateAddress) -> Coin {
+        match self.delegators.get(addr) {
+            // Sum of all the coins should not overflow max supply
+            // no panic: Invariant 2.5 + 2.1
+            Some(delegators) => {
+                sum_coins(delegators.iter().map(|d| heap.get(d).unwrap().bonded)).unwrap()
+            }
+            None => Coin::zero(),
+        }
+    }
+
     /// Returns (remainder, distribution)
+    ///
+    /// The rewards are split by the participation of council nodes weighted by the stake
+    /// backing them (own bonded coins plus the bonded coins of the backing staked states),
+    /// each council node's share is then split proportionally to the stake,
+    /// and the council node keeps its commission from the backing staked states' parts.
     pub fn reward_distribute(
         &mut self,
         heap: &mut impl StoreStaking,
         total_rewards: Coin,
     ) -> (Coin, RewardsDistribution) {
-        let sum_power: u64 = self
-            .participator_stats
+        let stats = std::mem::take(&mut self.participator_stats);
+        let weights = stats
+            .into_iter()
+            .map(|(addr, count)| {
+                let own = u64::from(self.get_or_default(heap, &addr).bonded) as u128;
+                let delegated = u64::from(self.delegated_staking(heap, &addr)) as u128;
+                let weight = if own == 0 {
+                    count as u128
+                } else {
+                    count as u128 * (own + delegated) / own
+                };
+                (addr, weight)
+            })
+            .collect::<Vec<_>>();
+        let sum_weight = weights
             .iter()
-            .map(|(_, count)| count)
-            .fold(0, |acc, value| acc.saturating_add(*value));
-        if sum_power == 0 {
+            .map(|(_, weight)| weight)
+            .fold(0u128, |acc, value| acc.saturating_add(*value));
+        if sum_weight == 0 {
             return (total_rewards, vec![]);
         }
 
         let mut distributed = Vec::new();
         let mut remainder = total_rewards;
-        let stats = std::mem::take(&mut self.participator_stats);
-        for (addr, count) in stats.into_iter() {
-            let mut staking = self.get_or_default(heap, &addr);
-            let amount = Coin::new(
-                (((u64::from(total_rewards) as u128) * count as u128) / sum_power as u128) as u64,
-            )
-            .expect("Overflow while distributing rewards");
-            remainder = (remainder - amount).unwrap();
-            distributed.push((addr, amount));
-            self.add_bonded(amount, &mut staking).unwrap();
-            set_staking(heap, staking, self.minimal_required_staking);
+        for (addr, weight) in weights.into_iter() {
+            let share =
+                Coin::new(((u64::from(total_rewards) as u128) * weight / sum_weight) as u64)
+                    .expect("Overflow while distributing rewards");
+            remainder = (remainder - share).unwrap();
+            self.reward_share(heap, &addr, share, &mut distributed);
         }
         #[cfg(debug_assertions)]
         self.check_invariants(heap);
         (remainder, distributed)
     }
 
+    /// Split the rewards of a council node between itself and the staked states backing it
+    fn reward_share(
+        &mut self,
+        heap: &mut impl StoreStaking,
+        addr: &StakedStateAddress,
+        share: Coin,
+        distributed: &mut RewardsDistribution,
+    ) {
+        let mut staking = self.get_or_default(heap, addr);
+        let commission_rate = match staking.node_meta.as_ref() {
+            Some(NodeState::CouncilNode(val)) => val.council_node.commission_rate,
+            _ => CommissionRate::default(),
+        };
+        let stake =
+            u64::from((staking.bonded + self.delegated_staking(heap, addr)).unwrap()) as u128;
+        let mut amount = share;
+        let mut commission = Coin::zero();
+        let delegators = self.delegators.get(addr).cloned().unwrap_or_default();
+        for delegator in delegators.iter() {
+            // no panic: Invariant 2.5 + 2.1
+            let mut delegator_staking = heap.get(delegator).unwrap();
+            // no panic: the bonded coins of the delegator is part of the stake
+            let part = Coin::new(
+                ((u64::from(share) as u128) * (u64::from(delegator_staking.bonded) as u128) / stake)
+                    as u64,
+            )
+            .unwrap();
+            let fee = commission_rate.commission(part);
+            // no panic: fee <= part
+            let net = (part - fee).unwrap();
+            // no panic: sum of the parts <= share
+            amount = (amount - net).unwrap();
+            commission = (commission + fee).unwrap();
+            if net == Coin::zero() {
+                continue;
+            }
+            self.add_bonded(net, &mut delegator_staking).unwrap();
+            set_staking(heap, delegator_staking, self.minimal_required_staking);
+            distributed.push(RewardRecord {
+                staking_address: *delegator,
+                amount: net,
+                commission: Coin::zero(),
+                delegated_to: Some(*addr),
+            });
+        }
+        self.add_bonded(amount, &mut staking).unwrap();
+        set_staking(heap, staking, self.minimal_required_staking);
+        distributed.push(RewardRecord {
+            staking_address: *addr,
+            amount,
+            commission,
+            delegated_to: None,
+        });
+    }
+
     /// list council nodes for abci_query
     pub fn list_council_nodes(&self, heap: &impl GetStaking) -> Vec<CouncilNodeMetadata> {
         self.idx_sort
@@ -320,6 +493,20 @@ impl StakingTable {
             .collect()
     }
 
+    /// check if the (encoded) keypackage belongs to an active council node
+    pub fn is_active_council_keypackage(&self, heap: &impl GetStaking, keypackage: &[u8]) -> bool {
+        self.idx_sort.iter().any(|key| {
+            let staking = heap.get(&key.address).unwrap();
+            match staking.node_meta.as_ref() {
+                Some(NodeState::CouncilNode(val)) => {
+                    val.is_active()
+                        && val.council_node.node_info.confidential_init.keypackage == keypackage
+                }
+                _ => false,
+            }
+        })
+    }
+
     /// Query staking address by validator address
     pub fn lookup_address(
         &self,
@@ -328,6 +515,24 @@ impl StakingTable {
         self.idx_validator_address.get(val_addr)
     }
 
+    /// Check if the staking address or the validator address is banned for double signing
+    pub fn is_tombstoned(
+        &self,
+        addr: &StakedStateAddress,
+        val_addr: &TendermintValidatorAddress,
+    ) -> bool {
+        self.tombstones.contains(addr) || self.tombstoned_validator_addresses.contains(val_addr)
+    }
+
+    /// Hash of the stake transfers committed in the app hash (zeros if there are none)
+    pub fn stake_transfers_hash(&self) -> H256 {
+        if self.stake_transfers.is_empty() {
+            [0u8; 32]
+        } else {
+            blake3::hash(&self.stake_transfers.encode()).into()
+        }
+    }
+
     /// Query chosen validator and it's voting power.
     pub fn get_chosen_validators(&self) -> &BTreeMap<StakedStateAddress, TendermintVotePower> {
         &self.chosen_validators
@@ -433,6 +638,8 @@ impl StakingTable {
         }
     }
 
+    /// The active council nodes with the most own bonded coins (see `ValidatorSortKey`),
+    /// their voting power is their own bonded coins.
     fn choose_validators(
         &self,
         heap: &impl GetStaking,
@@ -469,6 +676,13 @@ impl StakingTable {
         unbonding_period: Timespec,
         block_time: Timespec,
     ) {
+        self.stake_transfers.retain(|_, records| {
+            records.retain(|record| record.until > block_time);
+            !records.is_empty()
+        });
+        self.processed_evidences
+            .retain(|_, ev_time| ev_time.saturating_add(unbonding_period) > block_time);
+
         let to_delete = self
             .idx_validator_address
             .values()
@@ -513,6 +727,14 @@ impl StakingTable {
             }
             staking.node_meta = None;
             set_staking(heap, staking, self.minimal_required_staking);
+
+            // the backing staked states stop backing the removed council node
+            for delegator in self.delegators.remove(addr).unwrap_or_default().iter() {
+                // no panic: Invariant 2.5 + 2.1
+                let mut delegator_staking = heap.get(delegator).unwrap();
+                delegator_staking.delegated_to = None;
+                set_staking(heap, delegator_staking, self.minimal_required_staking);
+            }
         }
 
         #[cfg(debug_assertions)]
@@ -573,18 +795,36 @@ impl StakingTable {
         }
 
         // handle byzantine evidences, ignore invalid addresses
-        for (val_addr, _, ev_time) in info.evidences.iter() {
+        for (val_addr, height, ev_time, kind) in info.evidences.iter() {
+            if *ev_time > info.block_time || *height > info.block_height {
+                log::warn!("evidence from the future detected");
+                continue;
+            }
             if info.block_time >= ev_time.saturating_add(info.get_unbonding_period()) {
                 // ignore evidence too long ago
                 log::warn!("evidence older than unbonding period detected");
                 continue;
             }
+            // the same evidence may be reported again in the later blocks
+            if self
+                .processed_evidences
+                .insert((val_addr.clone(), *height), *ev_time)
+                .is_some()
+            {
+                log::warn!("duplicated evidence detected");
+                continue;
+            }
             // slash and jail if not already jailed.
             if let Some(addr) = self.idx_validator_address.get(val_addr) {
                 // panic: Invariant 2.1
                 let mut staking = heap.get(addr).unwrap();
                 // panic: Invariant 2.2
                 if let Some(NodeState::CouncilNode(val)) = staking.node_meta.as_mut() {
+                    // double signing is punished with permanent ban (even if it's already jailed)
+                    if *kind == EvidenceKind::DuplicateVote && self.tombstones.insert(*addr) {
+                        log::info!("tombstone council node: {}", addr);
+                        self.tombstoned_validator_addresses.insert(val_addr.clone());
+                    }
                     if !val.is_jailed() {
                         let jailed_until = val.jail(
                             info.block_time,
@@ -606,7 +846,7 @@ impl StakingTable {
         }
 
         // execute slashes
-        let slashes = slashes
+        let mut slashes = slashes
             .into_iter()
             .map(|(addr, kind, maybe_jailed_until)| {
                 let mut staking = heap.get(&addr).unwrap();
@@ -637,15 +877,118 @@ impl StakingTable {
                     slashed_coin,
                     punishment_kind: kind,
                     jailed_until: maybe_jailed_until,
+                    delegated_to: None,
                 }
             })
             .collect::<Vec<_>>();
+        let delegated_slashes = self.slash_delegated(heap, info, &slashes);
+        let transferred_slashes = self.slash_transferred(heap, info, &slashes);
+        slashes.extend(delegated_slashes);
+        slashes.extend(transferred_slashes);
 
         #[cfg(debug_assertions)]
         self.check_invariants(heap);
         slashes
     }
 
+    /// Slash the staked states backing the punished council nodes with the same ratio
+    fn slash_delegated(
+        &mut self,
+        heap: &mut impl StoreStaking,
+        info: &BeginBlockInfo,
+        punished: &[PunishmentOutcome],
+    ) -> Vec<PunishmentOutcome> {
+        let mut outcomes = Vec::new();
+        for outcome in punished.iter() {
+            let delegators = match self.delegators.get(&outcome.staking_address) {
+                Some(delegators) => delegators.clone(),
+                None => continue,
+            };
+            let ratio = match outcome.punishment_kind {
+                PunishmentKind::NonLive => info.params.get_liveness_slash_percent(),
+                PunishmentKind::ByzantineFault => info.params.get_byzantine_slash_percent(),
+            };
+            for delegator in delegators.iter() {
+                // no panic: Invariant 2.5 + 2.1
+                let mut staking = heap.get(delegator).unwrap();
+                let slashed_coin =
+                    self.slash(info.block_time, info.block_height, &mut staking, ratio);
+                // no panic: Invariant 4.1
+                let total_slashed_amount = slashed_coin.sum().unwrap();
+                if total_slashed_amount == Coin::zero() {
+                    continue;
+                }
+                staking.last_slash = Some(SlashRecord {
+                    kind: outcome.punishment_kind,
+                    time: info.block_time,
+                    amount: total_slashed_amount,
+                });
+                set_staking(heap, staking, self.minimal_required_staking);
+                outcomes.push(PunishmentOutcome {
+                    staking_address: *delegator,
+                    slashed_coin,
+                    punishment_kind: outcome.punishment_kind,
+                    jailed_until: None,
+                    delegated_to: Some(outcome.staking_address),
+                });
+            }
+        }
+        outcomes
+    }
+
+    /// Slash the stake transferred out of the punished addresses (in the source's unbonding window)
+    /// with the same ratio, from the bonded and then from the unbonded coins of the destination.
+    fn slash_transferred(
+        &mut self,
+        heap: &mut impl StoreStaking,
+        info: &BeginBlockInfo,
+        punished: &[PunishmentOutcome],
+    ) -> Vec<PunishmentOutcome> {
+        let mut outcomes = Vec::new();
+        for outcome in punished.iter() {
+            let records = match self.stake_transfers.get(&outcome.staking_address) {
+                Some(records) => records.clone(),
+                None => continue,
+            };
+            let ratio = match outcome.punishment_kind {
+                PunishmentKind::NonLive => info.params.get_liveness_slash_percent(),
+                PunishmentKind::ByzantineFault => info.params.get_byzantine_slash_percent(),
+            };
+            for record in records.iter().filter(|r| r.until > info.block_time) {
+                let mut staking = self.get_or_default(heap, &record.to);
+                let due = record.amount * ratio;
+                let bonded = std::cmp::min(due, staking.bonded);
+                // no panic: bonded <= due
+                let unbonded = std::cmp::min((due - bonded).unwrap(), staking.unbonded);
+                // no panic: bonded <= staking.bonded
+                self.sub_bonded(info.block_time, info.block_height, bonded, &mut staking)
+                    .unwrap();
+                // no panic: unbonded <= staking.unbonded
+                staking.unbonded = (staking.unbonded - unbonded).unwrap();
+                let slashed_coin = SlashedCoin { bonded, unbonded };
+                // no panic: Invariant 4.1
+                let total_slashed_amount = slashed_coin.sum().unwrap();
+                if total_slashed_amount == Coin::zero() {
+                    continue;
+                }
+                staking.last_slash = Some(SlashRecord {
+                    kind: outcome.punishment_kind,
+                    time: info.block_time,
+                    amount: total_slashed_amount,
+                });
+                set_staking(heap, staking, self.minimal_required_staking);
+                outcomes.push(PunishmentOutcome {
+                    staking_address: record.to,
+                    slashed_coin,
+                    punishment_kind: outcome.punishment_kind,
+                    jailed_until: None,
+                    delegated_to: None,
+                });
+            }
+        }
+        outcomes
+    }
+
     /// Choose new validator set and diff with current set
     fn update_validators(
         &mut self,
@@ -653,12 +996,25 @@ impl StakingTable {
         max_validators: usize,
     ) -> Vec<(TendermintValidatorPubKey, TendermintVotePower)> {
         let new = self.choose_validators(heap, max_validators);
-        let updates = diff_validators(&self.chosen_validators, &new);
-        self.chosen_validators = new;
-        updates
+        let mut updates = diff_validators(&self.chosen_validators, &new)
             .into_iter()
+            .filter(|(addr, _)| !self.pending_key_rotations.contains_key(addr))
             .map(|(addr, power)| (self.get_validator_pubkey(heap, &addr), power))
-            .collect()
+            .collect::<Vec<_>>();
+        // rotated consensus keys: remove the old key and add the new one with the current power
+        for (addr, old_pubkey) in std::mem::take(&mut self.pending_key_rotations).into_iter() {
+            let pubkey = self.get_validator_pubkey(heap, &addr);
+            let old_power = self.chosen_validators.get(&addr);
+            let new_power = new.get(&addr);
+            if old_power.is_some() {
+                updates.push((old_pubkey, TendermintVotePower::zero()));
+            }
+            if let Some(power) = new_power {
+                updates.push((pubkey, *power));
+            }
+        }
+        self.chosen_validators = new;
+        updates
     }
 
     #[cfg(debug_assertions)]
@@ -666,6 +1022,7 @@ impl StakingTable {
         self.check_invariant2_1(heap);
         self.check_invariant2_2(heap);
         self.check_invariant2_3();
+        self.check_invariant2_5(heap);
 
         self.check_validator_invariant(heap);
     }
@@ -740,6 +1097,20 @@ impl StakingTable {
         );
     }
 
+    #[cfg(debug_assertions)]
+    fn check_invariant2_5(&mut self, heap: &impl GetStaking) {
+        for (addr, delegators) in self.delegators.iter() {
+            assert!(heap
+                .get(addr)
+                .expect("delegators doesn't match heap")
+                .has_council_node_meta());
+            for delegator in delegators.iter() {
+                let staking = heap.get(delegator).expect("delegators doesn't match heap");
+                assert_eq!(staking.delegated_to, Some(*addr));
+            }
+        }
+    }
+
     #[cfg(debug_assertions)]
     fn check_validator_invariant(&mut self, heap: &impl GetStaking) {
         for addr in self.idx_validator_address.values() {
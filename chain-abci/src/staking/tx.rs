@@ -3,18 +3,20 @@ use log::warn;
 use chain_core::common::Timespec;
 use chain_core::init::coin::Coin;
 use chain_core::state::account::{
-    NodeMetadata, NodeState, StakedStateAddress, UnbondTx, UnjailTx, Validator,
+    CouncilNodeMeta, DelegateTx, NodeMetadata, NodeState, Nonce, StakeKind, StakedStateAddress,
+    TransferStakeTx, UnbondTx, UnjailTx, Validator,
 };
 use chain_core::state::tendermint::{BlockHeight, TendermintValidatorAddress};
-use chain_core::state::validator::NodeJoinRequestTx;
+use chain_core::state::validator::{NodeJoinRequestTx, NodeMetadataUpdateTx};
 use chain_core::tx::fee::Fee;
 use chain_storage::buffer::StoreStaking;
 use mls::{Codec, KeyPackage};
 use ra_client::ENCLAVE_CERT_VERIFIER;
 
-use super::table::{set_staking, StakingTable};
+use super::table::{set_staking, StakeTransferRecord, StakingTable};
 use crate::tx_error::{
-    DepositError, NodeJoinError, PublicTxError, UnbondError, UnjailError, WithdrawError,
+    DelegateError, DepositError, GovernanceError, NodeJoinError, NodeMetadataUpdateError,
+    PublicTxError, TransferStakeError, UnbondError, UnjailError, WithdrawError,
 };
 
 const MAX_USED_VALIDATOR_ADDR: usize = 10;
@@ -36,6 +38,9 @@ impl StakingTable {
         if staking.bonded < self.minimal_required_staking {
             return Err(NodeJoinError::BondedNotEnough.into());
         }
+        if staking.delegated_to.is_some() {
+            return Err(NodeJoinError::IsDelegating.into());
+        }
 
         let isv_svn = if cfg!(feature = "mock-enclave") {
             0
@@ -62,6 +67,9 @@ impl StakingTable {
             }
             _ => Err(NodeJoinError::WIPNotValidator),
         }?;
+        if self.is_tombstoned(&tx.address, &val_addr) {
+            return Err(NodeJoinError::Tombstoned.into());
+        }
         if let Some(NodeState::CouncilNode(val)) = &mut staking.node_meta {
             if val.is_jailed() {
                 return Err(NodeJoinError::IsJailed.into());
@@ -129,6 +137,170 @@ impl StakingTable {
         Ok(new_isv_svn)
     }
 
+    /// Handle `NodeMetadataUpdateTx`, the fee is paid from the bonded coins
+    /// The rotated consensus key is used in the validator updates of the next `end_block`,
+    /// the old validator address is kept in `used_validator_addresses` for the evidence processing.
+    pub fn node_metadata_update(
+        &mut self,
+        heap: &mut impl StoreStaking,
+        block_time: Timespec,
+        block_height: BlockHeight,
+        max_evidence_age: Timespec,
+        tx: &NodeMetadataUpdateTx,
+        fee: Fee,
+    ) -> Result<CouncilNodeMeta, PublicTxError> {
+        let mut staking = self.get_or_default(heap, &tx.address);
+        if tx.nonce != staking.nonce {
+            return Err(PublicTxError::IncorrectNonce);
+        }
+        let val = match staking.node_meta.as_mut() {
+            Some(NodeState::CouncilNode(val)) => val,
+            _ => return Err(NodeMetadataUpdateError::NotCouncilNode.into()),
+        };
+        if val.is_jailed() {
+            return Err(NodeMetadataUpdateError::IsJailed.into());
+        }
+
+        // the indexes are only updated once all the checks passed
+        let mut rotation = None;
+        if let Some(consensus_pubkey) = &tx.consensus_pubkey {
+            let old_val_addr = val.validator_address();
+            let val_addr = TendermintValidatorAddress::from(consensus_pubkey);
+            if old_val_addr != val_addr {
+                // the old addresses stay indexed until they're out of date,
+                // so rotating back to one of them is rejected too
+                if self.idx_validator_address.contains_key(&val_addr) {
+                    return Err(NodeMetadataUpdateError::DuplicateValidatorAddress.into());
+                }
+
+                // Add the old one to the used list.
+                let out_of_date = add_old_val_addr(
+                    &mut val.used_validator_addresses,
+                    block_time,
+                    &old_val_addr,
+                    MAX_USED_VALIDATOR_ADDR,
+                    max_evidence_age,
+                )
+                .ok_or(NodeMetadataUpdateError::UsedValidatorAddrFull)?;
+
+                let old_pubkey = std::mem::replace(
+                    &mut val.council_node.consensus_pubkey,
+                    consensus_pubkey.clone(),
+                );
+                rotation = Some((val_addr, out_of_date, old_pubkey));
+            }
+        }
+        val.council_node.node_info.name = tx.name.clone();
+        val.council_node.node_info.security_contact = tx.security_contact.clone();
+        let council_node = val.council_node.clone();
+
+        self.sub_bonded(block_time, block_height, fee.to_coin(), &mut staking)
+            .map_err(NodeMetadataUpdateError::CoinError)?;
+
+        if let Some((val_addr, out_of_date, old_pubkey)) = rotation {
+            for used_addr in out_of_date.into_iter() {
+                assert_eq!(
+                    self.idx_validator_address.remove(&used_addr),
+                    Some(tx.address)
+                );
+            }
+            self.idx_validator_address.insert(val_addr, tx.address);
+            // keep the key Tendermint knows about if rotated several times in one block
+            self.pending_key_rotations
+                .entry(tx.address)
+                .or_insert(old_pubkey);
+        }
+
+        staking.inc_nonce();
+        set_staking(heap, staking, self.minimal_required_staking);
+
+        #[cfg(debug_assertions)]
+        self.check_invariants(heap);
+
+        Ok(council_node)
+    }
+
+    /// Handle the staking part of governance txs (`ParamChangeProposalTx` / `ParamChangeVoteTx`):
+    /// only the current (not jailed) validators can submit and vote on proposals,
+    /// the nonce is incremented if the governance operation succeeds
+    pub fn governance_op<F>(
+        &mut self,
+        heap: &mut impl StoreStaking,
+        address: &StakedStateAddress,
+        nonce: Nonce,
+        op: F,
+    ) -> Result<(), PublicTxError>
+    where
+        F: FnOnce() -> Result<(), GovernanceError>,
+    {
+        let mut staking = self.get_or_default(heap, address);
+        if nonce != staking.nonce {
+            return Err(PublicTxError::IncorrectNonce);
+        }
+        if staking.is_jailed() || !self.get_chosen_validators().contains_key(address) {
+            return Err(GovernanceError::NotValidator.into());
+        }
+        op()?;
+
+        staking.inc_nonce();
+        set_staking(heap, staking, self.minimal_required_staking);
+        Ok(())
+    }
+
+    /// Handle `DelegateTx`
+    /// The bonded coins of a staked state without council node record back a (not jailed) council node,
+    /// or stop backing it.
+    /// The backing staked state shares the rewards and the slashes of the council node,
+    /// but its bonded coins don't add to the council node's voting power.
+    pub fn delegate(
+        &mut self,
+        heap: &mut impl StoreStaking,
+        tx: &DelegateTx,
+    ) -> Result<(), PublicTxError> {
+        let mut staking = self.get_or_default(heap, &tx.address);
+        if tx.nonce != staking.nonce {
+            return Err(PublicTxError::IncorrectNonce);
+        }
+        if staking.node_meta.is_some() {
+            return Err(DelegateError::IsCouncilNode.into());
+        }
+        if staking.delegated_to == tx.council_node {
+            return Err(DelegateError::Unchanged.into());
+        }
+        if let Some(council_node) = &tx.council_node {
+            match heap.get(council_node).and_then(|s| s.node_meta) {
+                Some(NodeState::CouncilNode(val)) => {
+                    if val.is_jailed() {
+                        return Err(DelegateError::CouncilNodeJailed.into());
+                    }
+                }
+                _ => return Err(DelegateError::NotCouncilNode.into()),
+            }
+        }
+
+        if let Some(old) = staking.delegated_to {
+            if let Some(delegators) = self.delegators.get_mut(&old) {
+                delegators.remove(&tx.address);
+                if delegators.is_empty() {
+                    self.delegators.remove(&old);
+                }
+            }
+        }
+        if let Some(council_node) = tx.council_node {
+            self.delegators
+                .entry(council_node)
+                .or_default()
+                .insert(tx.address);
+        }
+        staking.delegated_to = tx.council_node;
+        staking.inc_nonce();
+        set_staking(heap, staking, self.minimal_required_staking);
+
+        #[cfg(debug_assertions)]
+        self.check_invariants(heap);
+        Ok(())
+    }
+
     /// Handle `UnjailTx`
     pub fn unjail(
         &mut self,
@@ -223,6 +395,76 @@ impl StakingTable {
         Ok(unbonded_from)
     }
 
+    /// Handle transfer stake tx
+    /// Returns the new `unbonded_from` of the destination if unbonded coins are transferred
+    pub fn transfer_stake(
+        &mut self,
+        heap: &mut impl StoreStaking,
+        unbonding_period: Timespec,
+        block_time: Timespec,
+        block_height: BlockHeight,
+        tx: &TransferStakeTx,
+        fee: Fee,
+    ) -> Result<Option<Timespec>, PublicTxError> {
+        if tx.from_staked_account == tx.to_staked_account {
+            return Err(TransferStakeError::SameAddress.into());
+        }
+        if tx.value == Coin::zero() {
+            return Err(TransferStakeError::ZeroValue.into());
+        }
+        let mut from = self.get_or_default(heap, &tx.from_staked_account);
+        if tx.nonce != from.nonce {
+            return Err(PublicTxError::IncorrectNonce);
+        }
+        if from.is_jailed() {
+            return Err(TransferStakeError::SourceJailed.into());
+        }
+        let mut to = self.get_or_default(heap, &tx.to_staked_account);
+        if to.is_jailed() {
+            return Err(TransferStakeError::DestinationJailed.into());
+        }
+        let amount = (tx.value + fee.to_coin()).map_err(TransferStakeError::CoinError)?;
+        // check the destination total before touching the indexes
+        (to.bonded + to.unbonded)
+            .and_then(|total| total + tx.value)
+            .map_err(TransferStakeError::CoinError)?;
+
+        let (until, unbonded_from) = match tx.kind {
+            StakeKind::Bonded => {
+                self.sub_bonded(block_time, block_height, amount, &mut from)
+                    .map_err(TransferStakeError::CoinError)?;
+                // no panic: checked above
+                self.add_bonded(tx.value, &mut to).unwrap();
+                (block_time.saturating_add(unbonding_period), None)
+            }
+            StakeKind::Unbonded => {
+                from.unbonded = (from.unbonded - amount).map_err(TransferStakeError::CoinError)?;
+                // no panic: checked above
+                to.unbonded = (to.unbonded + tx.value).unwrap();
+                // the transferred coins can't be withdrawn earlier than from the source
+                to.unbonded_from = std::cmp::max(to.unbonded_from, from.unbonded_from);
+                (from.unbonded_from, Some(to.unbonded_from))
+            }
+        };
+        if until > block_time {
+            self.stake_transfers
+                .entry(tx.from_staked_account)
+                .or_default()
+                .push(StakeTransferRecord {
+                    to: tx.to_staked_account,
+                    amount: tx.value,
+                    until,
+                });
+        }
+
+        from.inc_nonce();
+        set_staking(heap, from, self.minimal_required_staking);
+        set_staking(heap, to, self.minimal_required_staking);
+        #[cfg(debug_assertions)]
+        self.check_invariants(heap);
+        Ok(unbonded_from)
+    }
+
     /// Handle withdraw tx
     /// Enclave validation is done in enclave, only incomplete check here.
     pub fn withdraw(
@@ -1,21 +1,29 @@
 use crate::enclave_bridge::EnclaveProxy;
+use crate::governance::GovernanceState;
 use crate::staking::StakingTable;
+use crate::tdbe::MLSHandshakeAction;
 use crate::tx_error::PublicTxError;
 use chain_core::common::Timespec;
 use chain_core::init::coin::Coin;
+use chain_core::init::config::GovernanceParameters;
 use chain_core::state::account::{
-    CouncilNodeMeta, NodeMetadata, StakedStateAddress, StakedStateOpAttributes,
+    CouncilNodeMeta, NodeMetadata, StakeKind, StakedStateAddress, StakedStateOpAttributes,
 };
+use chain_core::state::governance::ProposalId;
+use chain_core::state::tendermint::BlockHeight;
 use chain_core::tx::data::input::{TxoPointer, TxoSize};
+use chain_core::tx::data::output::TxInclusion;
+use chain_core::tx::data::TxId;
 use chain_core::tx::fee::Fee;
 use chain_core::tx::{TransactionId, TxEnclaveAux, TxObfuscated, TxPublicAux};
 use chain_storage::buffer::{GetKV, GetStaking, StoreStaking};
 use chain_tx_validation::{verify_unjailed, witness::verify_tx_recover_address, ChainInfo, Error};
-use enclave_protocol::{IntraEnclaveRequest, IntraEnclaveResponseOk, SealedLog};
+use enclave_protocol::{IntraEnclaveRequest, IntraEnclaveResponseOk, SealedLog, SealedTxInput};
 
 pub enum TxAction {
     Enclave(TxEnclaveAction),
     Public(TxPublicAction),
+    MLSHandshake(MLSHandshakeAction),
 }
 
 impl TxAction {
@@ -23,6 +31,7 @@ impl TxAction {
         match self {
             Self::Enclave(action) => action.fee(),
             Self::Public(action) => action.fee(),
+            Self::MLSHandshake(_) => Fee::new(Coin::zero()),
         }
     }
 
@@ -30,6 +39,7 @@ impl TxAction {
         match self {
             Self::Enclave(action) => action.staking_address(),
             Self::Public(action) => action.staking_address(),
+            Self::MLSHandshake(_) => None,
         }
     }
 }
@@ -124,7 +134,34 @@ pub enum TxPublicAction {
         // most recent isv_svn
         isv_svn: u16,
     },
+    NodeMetadataUpdate {
+        fee: Fee,
+        address: StakedStateAddress,
+        council_node: CouncilNodeMeta,
+    },
     Unjail(StakedStateAddress),
+    TransferStake {
+        fee: Fee,
+        from: StakedStateAddress,
+        to: StakedStateAddress,
+        kind: StakeKind,
+        value: Coin,
+        // new unbonded_from of the destination (unbonded coins transfer)
+        unbonded_from: Option<Timespec>,
+    },
+    ParamChangeProposal {
+        address: StakedStateAddress,
+        proposal_id: ProposalId,
+    },
+    ParamChangeVote {
+        address: StakedStateAddress,
+        proposal_id: ProposalId,
+        approve: bool,
+    },
+    Delegate {
+        address: StakedStateAddress,
+        council_node: Option<StakedStateAddress>,
+    },
 }
 
 impl TxPublicAction {
@@ -142,15 +179,71 @@ impl TxPublicAction {
             isv_svn,
         }
     }
+    fn node_metadata_update(
+        fee: Fee,
+        address: StakedStateAddress,
+        council_node: CouncilNodeMeta,
+    ) -> Self {
+        Self::NodeMetadataUpdate {
+            fee,
+            address,
+            council_node,
+        }
+    }
     fn unjail(staking_address: StakedStateAddress) -> Self {
         Self::Unjail(staking_address)
     }
+    fn transfer_stake(
+        fee: Fee,
+        from: StakedStateAddress,
+        to: StakedStateAddress,
+        kind: StakeKind,
+        value: Coin,
+        unbonded_from: Option<Timespec>,
+    ) -> Self {
+        Self::TransferStake {
+            fee,
+            from,
+            to,
+            kind,
+            value,
+            unbonded_from,
+        }
+    }
+    fn param_change_proposal(address: StakedStateAddress, proposal_id: ProposalId) -> Self {
+        Self::ParamChangeProposal {
+            address,
+            proposal_id,
+        }
+    }
+    fn param_change_vote(
+        address: StakedStateAddress,
+        proposal_id: ProposalId,
+        approve: bool,
+    ) -> Self {
+        Self::ParamChangeVote {
+            address,
+            proposal_id,
+            approve,
+        }
+    }
+    fn delegate(address: StakedStateAddress, council_node: Option<StakedStateAddress>) -> Self {
+        Self::Delegate {
+            address,
+            council_node,
+        }
+    }
 
     pub fn fee(&self) -> Fee {
         match self {
             Self::Unbond { fee, .. } => *fee,
             Self::NodeJoin { .. } => Fee::new(Coin::zero()),
+            Self::NodeMetadataUpdate { fee, .. } => *fee,
             Self::Unjail(_) => Fee::new(Coin::zero()),
+            Self::TransferStake { fee, .. } => *fee,
+            Self::ParamChangeProposal { .. } => Fee::new(Coin::zero()),
+            Self::ParamChangeVote { .. } => Fee::new(Coin::zero()),
+            Self::Delegate { .. } => Fee::new(Coin::zero()),
         }
     }
 
@@ -158,15 +251,29 @@ impl TxPublicAction {
         match self {
             Self::Unbond { unbond, .. } => Some(unbond.0),
             Self::NodeJoin { address, .. } => Some(*address),
+            Self::NodeMetadataUpdate { address, .. } => Some(*address),
             Self::Unjail(staking_address) => Some(*staking_address),
+            Self::TransferStake { from, .. } => Some(*from),
+            Self::ParamChangeProposal { address, .. } => Some(*address),
+            Self::ParamChangeVote { address, .. } => Some(*address),
+            Self::Delegate { address, .. } => Some(*address),
         }
     }
 }
 
+/// Returns the sealed transaction + when it was included in the chain (if the transaction is stored)
+pub fn lookup_sealed_input(kvdb: &impl GetKV, txid: &TxId) -> Option<SealedTxInput> {
+    let sealed_log = chain_storage::get_sealed_log(kvdb, txid)?;
+    // transactions stored before the inclusion was tracked can't have relative timelocks
+    let inclusion = chain_storage::get_tx_inclusion(kvdb, txid)
+        .unwrap_or_else(|| TxInclusion::new(0, BlockHeight::genesis()));
+    Some(SealedTxInput::new(sealed_log, inclusion))
+}
+
 fn check_spent_input_lookup(
     kvdb: &impl GetKV,
     inputs: &[TxoPointer],
-) -> Result<Vec<SealedLog>, Error> {
+) -> Result<Vec<SealedTxInput>, Error> {
     // check that there are inputs
     if inputs.is_empty() {
         return Err(Error::NoInputs);
@@ -178,7 +285,7 @@ fn check_spent_input_lookup(
             return Err(Error::InputSpent);
         } else {
             result.push(
-                chain_storage::get_sealed_log(kvdb, &txin.id)
+                lookup_sealed_input(kvdb, &txin.id)
                     .expect("valid unspent tx output should be stored"),
             );
         }
@@ -297,6 +404,8 @@ fn check_staking_attributes(
 pub fn process_public_tx(
     staking_store: &mut impl StoreStaking,
     staking_table: &mut StakingTable,
+    governance: &mut GovernanceState,
+    governance_params: &GovernanceParameters,
     enclave_isv_svn: u16,
     chain_info: &ChainInfo,
     txaux: &TxPublicAux,
@@ -358,5 +467,93 @@ pub fn process_public_tx(
                 isv_svn,
             ))
         }
+        // TODO: delay checking witness, as address is contained in Tx?
+        TxPublicAux::NodeMetadataUpdateTx(maintx, witness) => {
+            let address = verify_tx_recover_address(&witness, &maintx.id())?;
+            if address != maintx.address {
+                return Err(PublicTxError::StakingWitnessNotMatch);
+            }
+            let council_node = staking_table.node_metadata_update(
+                staking_store,
+                chain_info.block_time,
+                chain_info.block_height,
+                chain_info.max_evidence_age,
+                maintx,
+                chain_info.min_fee_computed,
+            )?;
+
+            Ok(TxPublicAction::node_metadata_update(
+                chain_info.min_fee_computed,
+                address,
+                council_node,
+            ))
+        }
+        // TODO: delay checking witness, as address is contained in Tx?
+        TxPublicAux::TransferStakeTx(maintx, from_witness, to_witness) => {
+            let txid = maintx.id();
+            let from = verify_tx_recover_address(&from_witness, &txid)?;
+            let to = verify_tx_recover_address(&to_witness, &txid)?;
+            if from != maintx.from_staked_account || to != maintx.to_staked_account {
+                return Err(PublicTxError::StakingWitnessNotMatch);
+            }
+            let unbonded_from = staking_table.transfer_stake(
+                staking_store,
+                chain_info.get_unbonding_period(),
+                chain_info.block_time,
+                chain_info.block_height,
+                &maintx,
+                chain_info.min_fee_computed,
+            )?;
+
+            Ok(TxPublicAction::transfer_stake(
+                chain_info.min_fee_computed,
+                from,
+                to,
+                maintx.kind,
+                maintx.value,
+                unbonded_from,
+            ))
+        }
+        TxPublicAux::ParamChangeProposalTx(maintx, witness) => {
+            let proposal_id = maintx.id();
+            let address = verify_tx_recover_address(&witness, &proposal_id)?;
+            if address != maintx.address {
+                return Err(PublicTxError::StakingWitnessNotMatch);
+            }
+            staking_table.governance_op(staking_store, &address, maintx.nonce, || {
+                governance.propose(
+                    proposal_id,
+                    maintx,
+                    chain_info.block_time,
+                    governance_params,
+                )
+            })?;
+
+            Ok(TxPublicAction::param_change_proposal(address, proposal_id))
+        }
+        TxPublicAux::ParamChangeVoteTx(maintx, witness) => {
+            let address = verify_tx_recover_address(&witness, &maintx.id())?;
+            if address != maintx.address {
+                return Err(PublicTxError::StakingWitnessNotMatch);
+            }
+            staking_table.governance_op(staking_store, &address, maintx.nonce, || {
+                governance.vote(maintx, chain_info.block_time)
+            })?;
+
+            Ok(TxPublicAction::param_change_vote(
+                address,
+                maintx.proposal_id,
+                maintx.approve,
+            ))
+        }
+        TxPublicAux::DelegateTx(maintx, witness) => {
+            let address = verify_tx_recover_address(&witness, &maintx.id())?;
+            if address != maintx.address {
+                return Err(PublicTxError::StakingWitnessNotMatch);
+            }
+            staking_table.delegate(staking_store, maintx)?;
+
+            Ok(TxPublicAction::delegate(address, maintx.council_node))
+        }
     }
 }
@@ -0,0 +1,435 @@
+//! Tracking of the "Transaction Data Bootstrapping Enclave" (TDBE) MLS group
+//! ref: https://github.com/crypto-com/chain-docs/blob/master/docs/modules/tdbe.md
+//!
+//! The chain maintains the public view of the group (the group context and the ratchet tree
+//! without any secrets) and verifies the handshakes against it: the message signatures
+//! of the group members, the attested keypackages and the tree changes.
+//! Only the keypackages of active council nodes can be added to the group.
+//! The confirmation can only be checked by the group members inside TDBE,
+//! as it requires the epoch secrets.
+//!
+//! NACKs are out of scope for now: their payload (a DLEQ proof referring to the faulty
+//! message part) isn't specified yet, so they are always rejected and never change the group.
+use chain_core::common::H256;
+use chain_core::mls::MLSHandshakeAux;
+use chain_core::state::tendermint::BlockHeight;
+use chain_core::tx::data::TxId;
+use mls::group::PublicGroup;
+use mls::keypackage::Timespec;
+use mls::message::{CommitContent, ContentType, MLSPlaintext};
+use mls::{Codec, KeyPackage};
+use parity_scale_codec::{Decode, Encode};
+use ra_client::AttestedCertVerifier;
+use serde::{Deserialize, Serialize};
+
+use crate::tx_error::MLSHandshakeError;
+
+/// Public view of the TDBE MLS group state
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, Encode, Decode)]
+pub struct MLSGroupState {
+    /// the current epoch (incremented by 1 for each accepted Commit)
+    pub epoch: u64,
+    /// running hash over the accepted handshake transaction ids
+    /// (blake3(previous transcript hash || txid))
+    pub transcript_hash: H256,
+    /// block height when the last handshake was accepted
+    pub last_updated: BlockHeight,
+    /// TLS-encoded `PublicGroup` (None before the group is created)
+    pub public_group: Option<Vec<u8>>,
+}
+
+impl Default for MLSGroupState {
+    fn default() -> Self {
+        MLSGroupState {
+            epoch: 0,
+            transcript_hash: [0u8; 32],
+            last_updated: BlockHeight::genesis(),
+            public_group: None,
+        }
+    }
+}
+
+impl MLSGroupState {
+    /// hash committed in the app hash (zeros before the group is created)
+    pub fn hash(&self) -> H256 {
+        if self.public_group.is_none() {
+            [0u8; 32]
+        } else {
+            blake3::hash(&self.encode()).into()
+        }
+    }
+
+    fn get_public_group(&self) -> Result<PublicGroup, MLSHandshakeError> {
+        let encoded = self
+            .public_group
+            .as_ref()
+            .ok_or(MLSHandshakeError::GroupMissing)?;
+        PublicGroup::read_bytes(encoded).ok_or(MLSHandshakeError::GroupDecodeError)
+    }
+
+    /// applies the verified handshake to the group state
+    pub fn apply(&mut self, txid: &TxId, handshake: &VerifiedHandshake, block_height: BlockHeight) {
+        self.epoch = handshake.group.context.epoch;
+        self.public_group = Some(handshake.group.get_encoding());
+        let mut hasher = blake3::Hasher::new();
+        hasher.update(&self.transcript_hash);
+        hasher.update(txid);
+        self.transcript_hash = hasher.finalize().into();
+        self.last_updated = block_height;
+    }
+}
+
+/// The outcome of the verified MLS handshake
+#[derive(Debug, Clone)]
+pub enum MLSHandshakeAction {
+    /// commit of Add/Remove proposals (or the initial commit that created the group)
+    Commit {
+        /// leaf index of the committer
+        sender: u32,
+        /// number of added members
+        added: usize,
+        /// number of removed members
+        removed: usize,
+    },
+    /// member renewed its keypackage
+    SelfUpdate {
+        /// leaf index of the updated member
+        sender: u32,
+    },
+}
+
+impl MLSHandshakeAction {
+    /// leaf index of the handshake sender
+    pub fn sender(&self) -> u32 {
+        match self {
+            Self::Commit { sender, .. } | Self::SelfUpdate { sender } => *sender,
+        }
+    }
+}
+
+/// The verified handshake and the resulting public group (in the next epoch)
+#[derive(Debug, Clone)]
+pub struct VerifiedHandshake {
+    pub action: MLSHandshakeAction,
+    group: PublicGroup,
+}
+
+fn decode_plaintext(payload: &[u8]) -> Result<MLSPlaintext, MLSHandshakeError> {
+    MLSPlaintext::read_bytes(payload).ok_or(MLSHandshakeError::MessageDecodeError)
+}
+
+fn decode_commit(
+    messages: &[Vec<u8>],
+) -> Result<(MLSPlaintext, Vec<MLSPlaintext>), MLSHandshakeError> {
+    let (commit, proposals) = messages
+        .split_last()
+        .ok_or(MLSHandshakeError::CommitMissing)?;
+    let commit = decode_plaintext(commit)?;
+    let proposals = proposals
+        .iter()
+        .map(|p| decode_plaintext(p))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok((commit, proposals))
+}
+
+/// checks the commit and its proposals are for the current group epoch,
+/// that the commit only references the included proposals
+/// and that it's signed by a group member with a valid path / keypackages
+fn verify_commit(
+    group: &mut PublicGroup,
+    commit: &MLSPlaintext,
+    proposals: &[MLSPlaintext],
+    ra_verifier: &impl AttestedCertVerifier,
+    now: Timespec,
+) -> Result<CommitContent, MLSHandshakeError> {
+    if !matches!(commit.content.content, ContentType::Commit { .. }) {
+        return Err(MLSHandshakeError::CommitMissing);
+    }
+    for msg in proposals.iter().chain(std::iter::once(commit)) {
+        if msg.content.epoch != group.context.epoch {
+            return Err(MLSHandshakeError::EpochMismatch);
+        }
+        if msg.content.group_id != group.context.group_id {
+            return Err(MLSHandshakeError::GroupIdMismatch);
+        }
+    }
+    let content = group.process_commit(commit, proposals, ra_verifier, now)?;
+    let referenced = content.additions.len() + content.updates.len() + content.removes.len();
+    if referenced != proposals.len() {
+        return Err(MLSHandshakeError::UnreferencedProposal);
+    }
+    Ok(content)
+}
+
+/// checks the added keypackages belong to active council nodes
+/// and that the welcome is included iff there are any
+fn verify_additions(
+    content: &CommitContent,
+    welcome: bool,
+    is_council_keypackage: &impl Fn(&[u8]) -> bool,
+) -> Result<(), MLSHandshakeError> {
+    if content
+        .additions
+        .iter()
+        .any(|add| !is_council_keypackage(&add.key_package.get_encoding()))
+    {
+        return Err(MLSHandshakeError::NotCouncilNode);
+    }
+    match (content.additions.is_empty(), welcome) {
+        (false, false) => Err(MLSHandshakeError::WelcomeMissing),
+        (true, true) => Err(MLSHandshakeError::UnexpectedWelcome),
+        _ => Ok(()),
+    }
+}
+
+/// Verifies the MLS handshake against the current (public) group state
+///
+/// `is_council_keypackage` checks whether the (encoded) keypackage belongs to an active council node.
+pub fn verify_mls_handshake(
+    state: &MLSGroupState,
+    handshake: &MLSHandshakeAux,
+    is_council_keypackage: impl Fn(&[u8]) -> bool,
+    ra_verifier: &impl AttestedCertVerifier,
+    now: Timespec,
+) -> Result<VerifiedHandshake, MLSHandshakeError> {
+    match handshake {
+        MLSHandshakeAux::GroupInit {
+            creator,
+            messages,
+            welcome,
+        } => {
+            if state.public_group.is_some() {
+                return Err(MLSHandshakeError::GroupAlreadyCreated);
+            }
+            if !is_council_keypackage(creator) {
+                return Err(MLSHandshakeError::NotCouncilNode);
+            }
+            let creator =
+                KeyPackage::read_bytes(creator).ok_or(MLSHandshakeError::MessageDecodeError)?;
+            let mut group = PublicGroup::init(creator);
+            let (commit, proposals) = decode_commit(messages)?;
+            let content = verify_commit(&mut group, &commit, &proposals, ra_verifier, now)?;
+            if !content.updates.is_empty() || !content.removes.is_empty() {
+                return Err(MLSHandshakeError::InvalidCommit);
+            }
+            verify_additions(&content, !welcome.is_empty(), &is_council_keypackage)?;
+            Ok(VerifiedHandshake {
+                action: MLSHandshakeAction::Commit {
+                    sender: content.sender.0,
+                    added: content.additions.len(),
+                    removed: 0,
+                },
+                group,
+            })
+        }
+        MLSHandshakeAux::CommitProposal { messages, welcome } => {
+            let mut group = state.get_public_group()?;
+            let (commit, proposals) = decode_commit(messages)?;
+            let content = verify_commit(&mut group, &commit, &proposals, ra_verifier, now)?;
+            // self-updates have their own handshake type
+            if !content.updates.is_empty() {
+                return Err(MLSHandshakeError::UnexpectedUpdate);
+            }
+            verify_additions(&content, welcome.is_some(), &is_council_keypackage)?;
+            Ok(VerifiedHandshake {
+                action: MLSHandshakeAction::Commit {
+                    sender: content.sender.0,
+                    added: content.additions.len(),
+                    removed: content.removes.len(),
+                },
+                group,
+            })
+        }
+        MLSHandshakeAux::SelfUpdateProposal { proposal, commit } => {
+            let mut group = state.get_public_group()?;
+            let proposal = decode_plaintext(proposal)?;
+            let commit = decode_plaintext(commit)?;
+            if proposal.get_update().is_none() {
+                return Err(MLSHandshakeError::UpdateMissing);
+            }
+            let content = verify_commit(&mut group, &commit, &[proposal], ra_verifier, now)?;
+            // the member can only renew its own keypackage
+            match content.updates.as_slice() {
+                [(updated, _)] if *updated == content.sender => {}
+                _ => return Err(MLSHandshakeError::SenderMismatch),
+            }
+            Ok(VerifiedHandshake {
+                action: MLSHandshakeAction::SelfUpdate {
+                    sender: content.sender.0,
+                },
+                group,
+            })
+        }
+        // not supported: the NACK payload isn't specified, so it can't be verified
+        MLSHandshakeAux::MsgNack(_) => Err(MLSHandshakeError::NackUnsupported),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mls::message::{Commit, MLSPlaintextCommon, Sender, SenderType};
+    use ra_client::ENCLAVE_CERT_VERIFIER;
+
+    static KEYPACKAGE: &[u8] =
+        include_bytes!("../../chain-tx-enclave-next/mls/tests/test_vectors/keypackage.bin");
+    const NOW: Timespec = 1594955685;
+
+    fn commit_plaintext(group_id: &[u8], epoch: u64) -> Vec<u8> {
+        MLSPlaintext {
+            content: MLSPlaintextCommon {
+                group_id: group_id.to_vec(),
+                epoch,
+                sender: Sender {
+                    sender_type: SenderType::Member,
+                    sender: 0,
+                },
+                authenticated_data: vec![],
+                content: ContentType::Commit {
+                    commit: Commit {
+                        updates: vec![],
+                        removes: vec![],
+                        adds: vec![],
+                        path: None,
+                    },
+                    confirmation: vec![],
+                },
+            },
+            signature: vec![],
+        }
+        .get_encoding()
+    }
+
+    fn created_group() -> (MLSGroupState, PublicGroup) {
+        let creator = KeyPackage::read_bytes(KEYPACKAGE).expect("decode");
+        let group = PublicGroup::init(creator);
+        let state = MLSGroupState {
+            public_group: Some(group.get_encoding()),
+            ..Default::default()
+        };
+        (state, group)
+    }
+
+    fn verify(
+        state: &MLSGroupState,
+        handshake: &MLSHandshakeAux,
+    ) -> Result<VerifiedHandshake, MLSHandshakeError> {
+        verify_mls_handshake(state, handshake, |_| true, &*ENCLAVE_CERT_VERIFIER, NOW)
+    }
+
+    #[test]
+    fn check_handshake_requires_group() {
+        let state = MLSGroupState::default();
+        assert_eq!(state.hash(), [0u8; 32]);
+        let handshake = MLSHandshakeAux::CommitProposal {
+            messages: vec![commit_plaintext(b"test", 0)],
+            welcome: None,
+        };
+        assert!(matches!(
+            verify(&state, &handshake),
+            Err(MLSHandshakeError::GroupMissing)
+        ));
+    }
+
+    #[test]
+    fn check_group_init_is_authorized() {
+        let handshake = MLSHandshakeAux::GroupInit {
+            creator: KEYPACKAGE.to_vec(),
+            messages: vec![],
+            welcome: vec![],
+        };
+        assert!(matches!(
+            verify_mls_handshake(
+                &MLSGroupState::default(),
+                &handshake,
+                |_| false,
+                &*ENCLAVE_CERT_VERIFIER,
+                NOW
+            ),
+            Err(MLSHandshakeError::NotCouncilNode)
+        ));
+        let (state, _) = created_group();
+        assert_ne!(state.hash(), [0u8; 32]);
+        assert!(matches!(
+            verify(&state, &handshake),
+            Err(MLSHandshakeError::GroupAlreadyCreated)
+        ));
+    }
+
+    #[test]
+    fn check_malformed_handshake_is_rejected() {
+        let (state, _) = created_group();
+        let handshake = MLSHandshakeAux::CommitProposal {
+            messages: vec![vec![1, 2, 3]],
+            welcome: None,
+        };
+        assert!(matches!(
+            verify(&state, &handshake),
+            Err(MLSHandshakeError::MessageDecodeError)
+        ));
+        let handshake = MLSHandshakeAux::CommitProposal {
+            messages: vec![],
+            welcome: None,
+        };
+        assert!(matches!(
+            verify(&state, &handshake),
+            Err(MLSHandshakeError::CommitMissing)
+        ));
+    }
+
+    #[test]
+    fn check_commit_epoch_and_signature_are_verified() {
+        let (state, group) = created_group();
+        let group_id = group.context.group_id.clone();
+        let handshake = MLSHandshakeAux::CommitProposal {
+            messages: vec![commit_plaintext(&group_id, 1)],
+            welcome: None,
+        };
+        assert!(matches!(
+            verify(&state, &handshake),
+            Err(MLSHandshakeError::EpochMismatch)
+        ));
+        let handshake = MLSHandshakeAux::CommitProposal {
+            messages: vec![commit_plaintext(b"test", 0)],
+            welcome: None,
+        };
+        assert!(matches!(
+            verify(&state, &handshake),
+            Err(MLSHandshakeError::GroupIdMismatch)
+        ));
+        // unsigned commit
+        let handshake = MLSHandshakeAux::CommitProposal {
+            messages: vec![commit_plaintext(&group_id, 0)],
+            welcome: None,
+        };
+        assert!(matches!(
+            verify(&state, &handshake),
+            Err(MLSHandshakeError::CommitVerifyFailed(_))
+        ));
+    }
+
+    #[test]
+    fn check_corrupt_group_is_rejected() {
+        let state = MLSGroupState {
+            public_group: Some(vec![1, 2, 3]),
+            ..Default::default()
+        };
+        let handshake = MLSHandshakeAux::CommitProposal {
+            messages: vec![commit_plaintext(b"test", 0)],
+            welcome: None,
+        };
+        assert!(matches!(
+            verify(&state, &handshake),
+            Err(MLSHandshakeError::GroupDecodeError)
+        ));
+    }
+
+    #[test]
+    fn check_nack_is_rejected() {
+        let (state, _) = created_group();
+        assert!(matches!(
+            verify(&state, &MLSHandshakeAux::MsgNack(vec![0])),
+            Err(MLSHandshakeError::NackUnsupported)
+        ));
+    }
+}
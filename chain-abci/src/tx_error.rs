@@ -9,8 +9,8 @@ pub enum TxError {
     Enclave(#[from] chain_tx_validation::Error),
     #[error("public tx process failed: {0}")]
     Public(#[from] PublicTxError),
-    #[error("FIXME/WIP payload for MLS handshake (not yet supported)")]
-    WIPMLSData,
+    #[error("MLS handshake process failed: {0}")]
+    MLSHandshake(#[from] MLSHandshakeError),
 }
 
 #[derive(thiserror::Error, Debug)]
@@ -29,8 +29,16 @@ pub enum PublicTxError {
     Unjail(#[from] UnjailError),
     #[error("node join tx process failed: {0}")]
     NodeJoin(#[from] NodeJoinError),
+    #[error("node metadata update tx process failed: {0}")]
+    NodeMetadataUpdate(#[from] NodeMetadataUpdateError),
     #[error("unbond tx process failed: {0}")]
     Unbond(#[from] UnbondError),
+    #[error("transfer stake tx process failed: {0}")]
+    TransferStake(#[from] TransferStakeError),
+    #[error("governance tx process failed: {0}")]
+    Governance(#[from] GovernanceError),
+    #[error("delegate tx process failed: {0}")]
+    Delegate(#[from] DelegateError),
 }
 
 #[derive(thiserror::Error, Debug)]
@@ -59,6 +67,24 @@ pub enum NodeJoinError {
     KeyPackageVerifyError(#[from] keypackage::Error),
     #[error("FIXME: WIP -- community node not yet supported")]
     WIPNotValidator,
+    #[error("the staking address backs a council node")]
+    IsDelegating,
+    #[error("the staking address or validator address is banned for double signing")]
+    Tombstoned,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum NodeMetadataUpdateError {
+    #[error("the staking address is not a council node")]
+    NotCouncilNode,
+    #[error("the staking address is jailed")]
+    IsJailed,
+    #[error("validator address already exists")]
+    DuplicateValidatorAddress,
+    #[error("the used_validator_addresses queue is full")]
+    UsedValidatorAddrFull,
+    #[error("the bonded amount can't pay the fee: {0}")]
+    CoinError(#[from] CoinError),
 }
 
 #[derive(thiserror::Error, Debug)]
@@ -83,6 +109,48 @@ pub enum UnbondError {
     ZeroValue,
 }
 
+#[derive(thiserror::Error, Debug)]
+pub enum TransferStakeError {
+    #[error("coin error in transfer stake tx: {0}")]
+    CoinError(#[from] CoinError),
+    #[error("the source and destination staking addresses are the same")]
+    SameAddress,
+    #[error("the source staking address is jailed")]
+    SourceJailed,
+    #[error("the destination staking address is jailed")]
+    DestinationJailed,
+    #[error("the value of tx is zero")]
+    ZeroValue,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum GovernanceError {
+    #[error("the staking address is not an active validator")]
+    NotValidator,
+    #[error("invalid network parameter change: {0}")]
+    InvalidChange(String),
+    #[error("too many proposals in the voting period")]
+    TooManyProposals,
+    #[error("the proposal already exists")]
+    DuplicateProposal,
+    #[error("the proposal is not found")]
+    ProposalNotFound,
+    #[error("the voting period of the proposal ended")]
+    VotingEnded,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum DelegateError {
+    #[error("the staking address is a council node")]
+    IsCouncilNode,
+    #[error("the target staking address is not a council node")]
+    NotCouncilNode,
+    #[error("the target council node is jailed")]
+    CouncilNodeJailed,
+    #[error("the staking address already backs the council node")]
+    Unchanged,
+}
+
 #[derive(thiserror::Error, Debug)]
 pub enum DepositError {
     #[error("coin error in deposit tx: {0}")]
@@ -90,3 +158,41 @@ pub enum DepositError {
     #[error("the staking address is jailed")]
     IsJailed,
 }
+
+#[derive(thiserror::Error, Debug)]
+pub enum MLSHandshakeError {
+    #[error("the group hasn't been created yet")]
+    GroupMissing,
+    #[error("the group was already created")]
+    GroupAlreadyCreated,
+    #[error("the stored group state can't be decoded")]
+    GroupDecodeError,
+    #[error("the keypackage doesn't belong to an active council node")]
+    NotCouncilNode,
+    #[error("MLSPlaintext decode failed")]
+    MessageDecodeError,
+    #[error("the last message is not a commit")]
+    CommitMissing,
+    #[error("the message epoch doesn't match the group epoch")]
+    EpochMismatch,
+    #[error("the messages are for different groups")]
+    GroupIdMismatch,
+    #[error("invalid commit or referenced proposals")]
+    InvalidCommit,
+    #[error("commit verification failed: {0}")]
+    CommitVerifyFailed(#[from] mls::group::ProcessCommitError),
+    #[error("proposal is not referenced in the commit")]
+    UnreferencedProposal,
+    #[error("update proposals should be submitted as self-update")]
+    UnexpectedUpdate,
+    #[error("self-update proposal is not an update")]
+    UpdateMissing,
+    #[error("self-update is not committed by the updated member")]
+    SenderMismatch,
+    #[error("welcome is required for add proposals")]
+    WelcomeMissing,
+    #[error("welcome without add proposals")]
+    UnexpectedWelcome,
+    #[error("nack is not supported until its DLEQ proof can be verified")]
+    NackUnsupported,
+}
@@ -2,9 +2,19 @@ use abci::*;
 use bit_vec::BitVec;
 use chain_abci::app::*;
 use chain_abci::enclave_bridge::mock::MockClient;
+use chain_abci::governance::GovernanceState;
+use chain_abci::inspect::{
+    chain_state_at, staking_at, stored_heights, utxo_spent_flags, verify_app_hashes, AppHashCheck,
+};
+use chain_abci::migration::{
+    migrate_schema, migrate_storage, verify_stored_app_hash, MigrationError, SCHEMA_VERSION,
+};
+use chain_abci::peer_filter::PeerFilterConfig;
+use chain_abci::rollback::{rollback_to_height, RollbackError};
+use chain_abci::snapshot::{export_snapshot, import_snapshot, Snapshot, SnapshotError};
 use chain_abci::staking::StakingTable;
-use chain_core::common::{MerkleTree, Proof, H256, HASH_SIZE_256};
-use chain_core::compute_app_hash;
+use chain_abci::tdbe::MLSGroupState;
+use chain_core::common::{MerkleTree, Proof, TendermintEventKey, H256, HASH_SIZE_256};
 use chain_core::init::address::RedeemAddress;
 use chain_core::init::coin::Coin;
 use chain_core::init::config::InitConfig;
@@ -30,17 +40,19 @@ use chain_core::tx::{
         access::{TxAccess, TxAccessPolicy},
         address::ExtendedAddr,
         attribute::TxAttributes,
-        input::{TxoPointer, TxoSize},
+        input::{TxoPointer, TxoSize, TxoSpent},
         output::TxOut,
         txid_hash, Tx, TxId,
     },
     witness::{TxInWitness, TxWitness},
     PlainTxAux, TransactionId, TxAux, TxEnclaveAux, TxPublicAux,
 };
-use chain_storage::buffer::Get;
-use chain_storage::jellyfish::SparseMerkleProof;
+use chain_core::{compute_app_hash, AppHashParts};
+use chain_storage::buffer::{flush_storage, BufferStore, Get, KVBuffer, Store};
+use chain_storage::jellyfish::{iter_stakings, SparseMerkleProof};
 use chain_storage::{
-    LookupItem, Storage, CHAIN_ID_KEY, COL_EXTRA, COL_NODE_INFO, GENESIS_APP_HASH_KEY,
+    LookupItem, Storage, StorageBackend, StorageConfig, StorageType, CHAIN_ID_KEY,
+    COL_APP_HASH_PARTS, COL_EXTRA, COL_NETWORK_PARAMS, COL_NODE_INFO, GENESIS_APP_HASH_KEY,
     LAST_STATE_KEY, NUM_COLUMNS,
 };
 use chain_tx_filter::BlockFilter;
@@ -55,7 +67,7 @@ use std::collections::BTreeMap;
 use std::convert::TryFrom;
 use std::convert::TryInto;
 use std::str::FromStr;
-use std::sync::Arc;
+use std::sync::{Arc, RwLock};
 use test_common::chain_env::{
     mock_confidential_init, mock_council_node, ChainEnv, DEFAULT_GENESIS_TIME,
 };
@@ -205,6 +217,8 @@ fn get_dummy_network_params() -> NetworkParameters {
             monetary_expansion_decay: 999_860,
         },
         max_validators: 2,
+        dynamic_fee_config: Default::default(),
+        governance_config: Default::default(),
     })
 }
 
@@ -221,11 +235,13 @@ fn get_dummy_app_state(app_hash: H256) -> ChainNodeState {
         staking_version: 0,
         utxo_coins: Coin::zero(),
         enclave_isv_svn: 0,
-        top_level: ChainState {
-            account_root: [0u8; 32],
-            rewards_pool: RewardsPoolState::new(0, params.get_rewards_monetary_expansion_tau()),
-            network_params: params,
-        },
+        mls_group: MLSGroupState::default(),
+        governance: GovernanceState::default(),
+        top_level: ChainState::genesis(
+            [0u8; 32],
+            RewardsPoolState::new(0, params.get_rewards_monetary_expansion_tau()),
+            params,
+        ),
     }
 }
 
@@ -264,7 +280,7 @@ fn init_chain_for(address: RedeemAddress) -> ChainNodeApp<MockClient> {
     .iter()
     .cloned()
     .collect();
-    let NetworkParameters::Genesis(params) = get_dummy_network_params();
+    let params = get_dummy_network_params().get_params().clone();
     let mut nodes = BTreeMap::new();
     let pub_key =
         TendermintValidatorPubKey::from_base64(b"MDAwMDAwMDAwMDAwMDAwMDAwMDAwMDAwMDAwMDAwMDA=")
@@ -298,9 +314,11 @@ fn init_chain_for(address: RedeemAddress) -> ChainNodeApp<MockClient> {
         let new_account_root = storage.put_stakings(0, &genesis_state.accounts);
         let genesis_app_hash = compute_app_hash(
             &tx_tree,
-            &new_account_root,
-            &genesis_state.rewards_pool,
-            &get_dummy_network_params(),
+            &ChainState::genesis(
+                new_account_root,
+                genesis_state.rewards_pool.clone(),
+                get_dummy_network_params(),
+            ),
         );
 
         let example_hash = hex::encode_upper(genesis_app_hash);
@@ -388,6 +406,8 @@ fn init_chain_panics_with_different_app_hash() {
             monetary_expansion_decay: 999_860,
         },
         max_validators: 1,
+        dynamic_fee_config: Default::default(),
+        governance_config: Default::default(),
     };
     let c = InitConfig::new(distribution, params, BTreeMap::new());
 
@@ -495,6 +515,13 @@ fn check_tx_should_accept_valid_tx() {
     creq.set_tx(txaux.encode());
     let cresp = app.check_tx(&creq);
     assert_eq!(0, cresp.code, "{}", cresp.log);
+    // the paid fee and the mempool priority
+    assert_eq!(1, cresp.events.len());
+    assert_eq!(2, cresp.events[0].attributes.len());
+    assert_eq!(
+        cresp.events[0].attributes[1].key,
+        TendermintEventKey::Priority.to_vec()
+    );
 }
 
 #[test]
@@ -820,6 +847,38 @@ fn query_should_return_an_account() {
     assert_eq!(account.address, StakedStateAddress::from_str(addr).unwrap());
 }
 
+#[test]
+fn account_query_should_return_proof_to_app_hash() {
+    let addr = "fe7c045110b8dbf29765047380898919c5cb56f9";
+    let mut app = init_chain_for(addr.parse().unwrap());
+    let address = StakedStateAddress::from_str(addr).unwrap();
+    let mut qreq = RequestQuery::new();
+    qreq.data = hex::decode(&addr).unwrap();
+    qreq.path = "account".into();
+    qreq.prove = true;
+    let qresp = app.query(&qreq);
+    let account = StakedState::decode(&mut qresp.value.as_slice()).unwrap();
+    let ops = &qresp.proof.get_ref().ops;
+    assert_eq!(ops.len(), 2);
+    assert_eq!(ops[0].field_type, "staking");
+    assert_eq!(ops[1].field_type, "app_hash");
+
+    let parts = AppHashParts::decode(&mut ops[1].data.as_slice()).unwrap();
+    assert_eq!(
+        parts.app_hash(),
+        app.last_state.as_ref().unwrap().last_apphash
+    );
+    let proof = SparseMerkleProof::decode(&mut ops[0].data.as_slice()).unwrap();
+    assert!(proof
+        .verify(parts.account_root, &address, Some(&account))
+        .is_ok());
+    let mut tampered = account.clone();
+    tampered.nonce += 1;
+    assert!(proof
+        .verify(parts.account_root, &address, Some(&tampered))
+        .is_err());
+}
+
 #[test]
 fn staking_query_should_return_an_account() {
     let addr = "fe7c045110b8dbf29765047380898919c5cb56f9";
@@ -838,6 +897,29 @@ fn staking_query_should_return_an_account() {
     );
 }
 
+#[test]
+fn p2p_query_should_filter_peers() {
+    let addr = "fe7c045110b8dbf29765047380898919c5cb56f9";
+    let app = init_chain_for(addr.parse().unwrap());
+    let mut qreq = RequestQuery::new();
+    qreq.path = "/p2p/filter/addr/10.0.0.1:26656".into();
+    assert_eq!(app.query_handler(&qreq).code, 0);
+
+    let filter = PeerFilterConfig {
+        allowed_addresses: vec!["10.0.0.0/8".parse().unwrap()],
+        denied_ids: vec!["0123456789abcdef0123456789abcdef01234567".into()],
+        ..Default::default()
+    };
+    let app = app.with_peer_filter(Arc::new(RwLock::new(filter)));
+    assert_eq!(app.query_handler(&qreq).code, 0);
+    qreq.path = "/p2p/filter/addr/192.168.0.1:26656".into();
+    assert_ne!(app.query_handler(&qreq).code, 0);
+    qreq.path = "/p2p/filter/id/0123456789ABCDEF0123456789ABCDEF01234567".into();
+    assert_ne!(app.query_handler(&qreq).code, 0);
+    qreq.path = "/p2p/filter/id/1123456789abcdef0123456789abcdef01234567".into();
+    assert_eq!(app.query_handler(&qreq).code, 0);
+}
+
 fn block_commit_with_check(app: &mut ChainNodeApp<MockClient>, tx: TxAux, block_height: i64) {
     let r = RequestInfo::default();
     let info_1 = app.info(&r);
@@ -898,6 +980,13 @@ fn get_tx_meta(txid: &TxId, app: &ChainNodeApp<MockClient>) -> BitVec {
     )
 }
 
+fn spent_by_query(app: &ChainNodeApp<MockClient>, txo: &TxoPointer) -> ResponseQuery {
+    let mut qreq = RequestQuery::new();
+    qreq.data = txo.encode();
+    qreq.path = "spent-by".into();
+    app.query_handler(&qreq)
+}
+
 #[test]
 #[allow(clippy::cognitive_complexity)]
 fn all_valid_tx_types_should_commit() {
@@ -913,6 +1002,12 @@ fn all_valid_tx_types_should_commit() {
     let addr2 = RedeemAddress::from(&public_key2);
 
     let mut app = init_chain_for(addr);
+    assert_eq!(
+        spent_by_query(&app, &TxoPointer::new([0; 32], 0)).code,
+        1,
+        "utxo index is disabled by default"
+    );
+    app.utxo_index = true;
 
     let merkle_tree = MerkleTree::new(vec![RawXOnlyPubkey::from(x_public_key.serialize())]);
 
@@ -990,6 +1085,25 @@ fn all_valid_tx_types_should_commit() {
         assert!(spent_utxos0[0] && !spent_utxos0[1]);
         let spent_utxos1 = get_tx_meta(&txid1, &app);
         assert!(!spent_utxos1.any());
+        let spent = spent_by_query(&app, &utxo1);
+        assert_eq!(
+            TxoSpent::decode(&mut spent.value.as_slice()).unwrap(),
+            TxoSpent {
+                spent_by: txid1,
+                block_height: BlockHeight::new(2),
+            }
+        );
+        assert!(spent_by_query(&app, &TxoPointer::new(*txid, 1))
+            .value
+            .is_empty());
+        let mut qreq = RequestQuery::new();
+        qreq.data = txid1.to_vec();
+        qreq.path = "tx-height".into();
+        let height = app.query_handler(&qreq);
+        assert_eq!(
+            BlockHeight::decode(&mut height.value.as_slice()).unwrap(),
+            BlockHeight::new(2)
+        );
     }
     let utxo2 = TxoPointer::new(*txid, 1);
     let tx2 = DepositBondTx::new(vec![utxo2], addr.into(), StakedStateOpAttributes::new(0));
@@ -1020,6 +1134,13 @@ fn all_valid_tx_types_should_commit() {
     {
         let spent_utxos0 = get_tx_meta(&txid, &app);
         assert!(spent_utxos0[0] && spent_utxos0[1]);
+        let spent = spent_by_query(&app, &TxoPointer::new(*txid, 1));
+        assert_eq!(
+            TxoSpent::decode(&mut spent.value.as_slice())
+                .unwrap()
+                .spent_by,
+            tx2.id()
+        );
         let account = get_account(&addr, &app).expect("acount not exist");
         // TODO: more precise amount assertions
         assert!(account.bonded > Coin::zero());
@@ -1165,7 +1286,7 @@ fn query_should_return_proof_for_committed_tx() {
 
     let proof = qresp.proof.unwrap();
     let merkle = MerkleTree::new(vec![tx_aux.tx_id()]);
-    assert_eq!(proof.ops.len(), 2);
+    assert_eq!(proof.ops.len(), 3);
 
     let mut transaction_root_hash = [0u8; 32];
     transaction_root_hash.copy_from_slice(proof.ops[0].key.as_slice());
@@ -1177,15 +1298,14 @@ fn query_should_return_proof_for_committed_tx() {
     assert_eq!(merkle.root_hash(), transaction_root_hash);
     let last_state = app.last_state.clone().unwrap();
     assert_eq!(
-        compute_app_hash(
-            &merkle,
-            &last_state.top_level.account_root,
-            &last_state.top_level.rewards_pool,
-            &last_state.top_level.network_params
-        )
-        .to_vec(),
+        compute_app_hash(&merkle, &last_state.top_level).to_vec(),
         cresp.data
     );
+    assert_eq!(proof.ops[1].field_type, "app_hash");
+    let parts = AppHashParts::decode(&mut proof.ops[1].data.as_slice()).unwrap();
+    assert_eq!(parts.valid_tx_root, transaction_root_hash);
+    assert_eq!(parts.app_hash().to_vec(), cresp.data);
+    assert_eq!(qresp.height, 1);
     let mut qreq2 = RequestQuery::new();
     qreq2.data = tx_aux.tx_id().to_vec();
     qreq2.path = "witness".into();
@@ -1196,7 +1316,7 @@ fn query_should_return_proof_for_committed_tx() {
         }
         _ => unreachable!(),
     }
-    assert_eq!(proof.ops[1].data, txid_hash(&qresp.value));
+    assert_eq!(proof.ops[2].data, txid_hash(&qresp.value));
 }
 
 #[test]
@@ -1207,3 +1327,233 @@ fn check_invalid_punishment_config() {
         parameters.jailing_config.block_signing_window = 360;
     });
 }
+
+#[test]
+fn snapshot_should_restore_committed_state() {
+    let (mut app, tx, _, _) = deliver_valid_tx();
+    let mut endreq = RequestEndBlock::default();
+    endreq.set_height(10);
+    app.end_block(&endreq);
+    let cresp = app.commit(&RequestCommit::default());
+    let state = app.last_state.clone().unwrap();
+
+    let snapshot = export_snapshot(&app.storage, Some(BlockHeight::new(10))).unwrap();
+    assert_eq!(snapshot.manifest.height, BlockHeight::new(10));
+    assert_eq!(&snapshot.manifest.app_hash[..], &cresp.data[..]);
+    assert!(export_snapshot(&app.storage, Some(BlockHeight::new(9))).is_err());
+
+    // the snapshot is checked against the trusted app hash
+    let mut storage = Storage::new_db(create_db());
+    let wrong_app_hash = [0u8; 32];
+    assert!(matches!(
+        import_snapshot(&mut storage, &snapshot, &wrong_app_hash, false),
+        Err(SnapshotError::AppHashMismatch)
+    ));
+    let mut tampered = Snapshot {
+        manifest: snapshot.manifest.clone(),
+        chunks: snapshot.chunks.clone(),
+    };
+    tampered.chunks[0][0] ^= 1;
+    assert!(matches!(
+        import_snapshot(&mut storage, &tampered, &state.last_apphash, false),
+        Err(SnapshotError::ChunkHashMismatch(0))
+    ));
+
+    let imported = import_snapshot(&mut storage, &snapshot, &state.last_apphash, false).unwrap();
+    assert_eq!(imported.last_block_height, state.last_block_height);
+    assert!(matches!(
+        import_snapshot(&mut storage, &snapshot, &state.last_apphash, false),
+        Err(SnapshotError::NotEmpty)
+    ));
+    assert_eq!(
+        storage.lookup_item(LookupItem::TxMetaSpent, &tx.id()),
+        app.storage.lookup_item(LookupItem::TxMetaSpent, &tx.id())
+    );
+
+    // the node resumes from the snapshot height
+    let restored = ChainNodeApp::new_with_storage(
+        get_enclave_bridge_mock(),
+        &hex::encode_upper(app.genesis_app_hash),
+        TEST_CHAIN_ID,
+        storage,
+        None,
+        None,
+    );
+    let restored_state = restored.last_state.as_ref().unwrap();
+    assert_eq!(restored_state.last_apphash, state.last_apphash);
+    assert_eq!(restored_state.staking_version, state.staking_version);
+    assert_eq!(
+        iter_stakings(&restored.storage, state.staking_version).collect::<Vec<_>>(),
+        iter_stakings(&app.storage, state.staking_version).collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn migrated_storage_should_have_same_app_hash() {
+    let (mut app, tx, _, _) = deliver_valid_tx();
+    let mut endreq = RequestEndBlock::default();
+    endreq.set_height(10);
+    app.end_block(&endreq);
+    app.commit(&RequestCommit::default());
+    let app_hash = app.last_state.as_ref().unwrap().last_apphash;
+
+    let config = StorageConfig::new("", StorageType::Node).with_backend(StorageBackend::Memory);
+    let mut storage = Storage::new(&config).unwrap();
+    assert_eq!(
+        migrate_storage(&app.storage, &mut storage).unwrap(),
+        app_hash
+    );
+    assert_eq!(verify_stored_app_hash(&storage).unwrap(), app_hash);
+    assert_eq!(
+        storage.lookup_item(LookupItem::TxWitness, &tx.id()),
+        app.storage.lookup_item(LookupItem::TxWitness, &tx.id())
+    );
+    assert!(matches!(
+        migrate_storage(&app.storage, &mut storage),
+        Err(MigrationError::NotEmpty)
+    ));
+}
+
+#[test]
+fn inspector_should_read_stored_history() {
+    let (mut app, tx, _, _) = deliver_valid_tx();
+    let mut endreq = RequestEndBlock::default();
+    endreq.set_height(10);
+    app.end_block(&endreq);
+    app.commit(&RequestCommit::default());
+    let state = app.last_state.clone().unwrap();
+
+    let heights = stored_heights(&app.storage).unwrap();
+    assert_eq!(
+        heights,
+        vec![
+            (BlockHeight::genesis(), app.genesis_app_hash),
+            (BlockHeight::new(10), state.last_apphash)
+        ]
+    );
+    // no chain state history is stored without tx-query
+    assert_eq!(
+        verify_app_hashes(&app.storage).unwrap(),
+        vec![
+            (BlockHeight::genesis(), AppHashCheck::Unverifiable),
+            (BlockHeight::new(10), AppHashCheck::Verified)
+        ]
+    );
+    assert_eq!(
+        chain_state_at(&app.storage, BlockHeight::new(10)).unwrap(),
+        state.top_level
+    );
+    assert!(chain_state_at(&app.storage, BlockHeight::new(5)).is_err());
+
+    let staking = iter_stakings(&app.storage, state.staking_version)
+        .next()
+        .unwrap();
+    assert_eq!(
+        staking_at(&app.storage, &staking.address, state.staking_version).unwrap(),
+        Some(staking.clone())
+    );
+    assert!(staking_at(&app.storage, &staking.address, state.staking_version + 1).is_err());
+
+    let flags = utxo_spent_flags(&app.storage, &tx.id()).unwrap();
+    assert!(flags.len() >= tx.outputs.len());
+    assert!(flags.none());
+    assert!(utxo_spent_flags(&app.storage, &[0u8; 32]).is_none());
+}
+
+#[test]
+fn schema_migration_should_store_missing_last_state_parts() {
+    let (mut app, _, _, _) = deliver_valid_tx();
+    let mut endreq = RequestEndBlock::default();
+    endreq.set_height(10);
+    app.end_block(&endreq);
+    app.commit(&RequestCommit::default());
+    let state = app.last_state.clone().unwrap();
+    let params = state.top_level.network_params.clone();
+    assert_eq!(
+        chain_storage::get_schema_version(&app.storage),
+        SCHEMA_VERSION
+    );
+    assert!(migrate_schema(&mut app.storage).unwrap().is_empty());
+
+    // the database created before the network parameters and app hash parts were stored
+    let mut kv_buffer = KVBuffer::new();
+    {
+        let mut store = BufferStore::new(&app.storage, &mut kv_buffer);
+        chain_storage::store_schema_version(&mut store, 1);
+        store.delete((COL_APP_HASH_PARTS, state.last_apphash.to_vec()));
+        store.delete((COL_NETWORK_PARAMS, params.get_version().encode()));
+    }
+    flush_storage(&mut app.storage, kv_buffer).unwrap();
+    assert_eq!(chain_storage::get_schema_version(&app.storage), 1);
+
+    assert_eq!(migrate_schema(&mut app.storage).unwrap().len(), 1);
+    assert_eq!(
+        chain_storage::get_schema_version(&app.storage),
+        SCHEMA_VERSION
+    );
+    assert_eq!(
+        chain_storage::get_app_hash_parts(&app.storage, &state.last_apphash)
+            .unwrap()
+            .app_hash(),
+        state.last_apphash
+    );
+    assert_eq!(
+        chain_storage::get_network_params(&app.storage, params.get_version()),
+        Some(params)
+    );
+}
+
+#[test]
+fn rollback_should_allow_replaying_blocks() {
+    let (mut app, txaux, tx) = prepare_app_valid_tx();
+    let genesis_app_hash = app.genesis_app_hash;
+    begin_block(&mut app);
+    let mut creq = RequestDeliverTx::default();
+    creq.set_tx(txaux.encode());
+    assert_eq!(0, app.deliver_tx(&creq).code);
+    let mut endreq = RequestEndBlock::default();
+    endreq.set_height(10);
+    app.end_block(&endreq);
+    let cresp = app.commit(&RequestCommit::default());
+    assert!(app
+        .storage
+        .lookup_item(LookupItem::TxMetaSpent, &tx.id())
+        .is_some());
+
+    let mut storage = app.storage;
+    assert!(matches!(
+        rollback_to_height(&mut storage, BlockHeight::new(10)),
+        Err(RollbackError::InvalidHeight { .. })
+    ));
+    let state = rollback_to_height(&mut storage, BlockHeight::genesis()).unwrap();
+    assert_eq!(state.last_block_height, BlockHeight::genesis());
+    assert_eq!(state.last_apphash, genesis_app_hash);
+    assert_eq!(verify_stored_app_hash(&storage).unwrap(), genesis_app_hash);
+    assert!(storage
+        .lookup_item(LookupItem::TxMetaSpent, &tx.id())
+        .is_none());
+    assert!(storage
+        .lookup_item(LookupItem::TxWitness, &tx.id())
+        .is_none());
+    assert!(storage
+        .get_historical_app_hash(BlockHeight::new(10))
+        .is_none());
+
+    // the block is replayed with the same result
+    let mut app = ChainNodeApp::new_with_storage(
+        get_enclave_bridge_mock(),
+        &hex::encode_upper(genesis_app_hash),
+        TEST_CHAIN_ID,
+        storage,
+        None,
+        None,
+    );
+    assert_eq!(
+        app.last_state.as_ref().unwrap().last_block_height,
+        BlockHeight::genesis()
+    );
+    begin_block(&mut app);
+    assert_eq!(0, app.deliver_tx(&creq).code);
+    app.end_block(&endreq);
+    assert_eq!(app.commit(&RequestCommit::default()).data, cresp.data);
+}
@@ -4,6 +4,7 @@ use bit_vec::BitVec;
 /// FIXME: organize better / refactor (group by tx, less duplication or unneeded arguments)
 use chain_abci::enclave_bridge::mock::MockClient;
 use chain_abci::enclave_bridge::EnclaveProxy;
+use chain_abci::governance::GovernanceState;
 use chain_abci::staking::StakingTable;
 use chain_abci::storage::{
     process_public_tx, verify_enclave_tx as verify_enclave_tx_inner, TxEnclaveAction,
@@ -12,6 +13,7 @@ use chain_abci::tx_error::{NodeJoinError, PublicTxError, TxError, UnbondError, U
 use chain_core::common::{MerkleTree, Timespec};
 use chain_core::init::address::RedeemAddress;
 use chain_core::init::coin::{Coin, CoinError};
+use chain_core::init::config::GovernanceParameters;
 use chain_core::state::account::StakedState;
 use chain_core::state::account::StakedStateAddress;
 use chain_core::state::account::StakedStateOpAttributes;
@@ -26,7 +28,7 @@ use chain_core::tx::data::{
     address::ExtendedAddr,
     attribute::TxAttributes,
     input::{TxoPointer, TxoSize},
-    output::TxOut,
+    output::{Timelock, TxInclusion, TxOut},
 };
 use chain_core::tx::data::{Tx, TxId};
 use chain_core::tx::fee::FeeAlgorithm;
@@ -84,7 +86,15 @@ fn verify_public_tx(
     let mut buffer = HashMap::new();
 
     let mut store = StakingBufferStore::new(StakingGetter::new(storage, version), &mut buffer);
-    let tx_action = process_public_tx(&mut store, &mut tbl, 0, extra_info, txaux)?;
+    let tx_action = process_public_tx(
+        &mut store,
+        &mut tbl,
+        &mut GovernanceState::default(),
+        &GovernanceParameters::default(),
+        0,
+        extra_info,
+        txaux,
+    )?;
 
     let fee = tx_action.fee();
     let maddress = tx_action.staking_address();
@@ -144,6 +154,10 @@ fn get_old_tx(addr: ExtendedAddr, timelocked: bool) -> Tx {
     old_tx
 }
 
+fn genesis_inclusion() -> TxInclusion {
+    TxInclusion::new(DEFAULT_GENESIS_TIME, BlockHeight::genesis())
+}
+
 fn get_address<C: Signing>(
     secp: &Secp256k1<C>,
     secret_key: &SecretKey,
@@ -797,7 +811,7 @@ fn test_deposit_verify_fail() {
             &tx,
             &witness,
             &extra_info,
-            vec![TxWithOutputs::Transfer(input_tx)],
+            vec![(TxWithOutputs::Transfer(input_tx), genesis_inclusion())],
         );
         expect_error(
             &result,
@@ -1086,7 +1100,7 @@ fn test_transfer_verify_fail() {
             &tx,
             &witness,
             &extra_info,
-            vec![TxWithOutputs::Transfer(input_tx)],
+            vec![(TxWithOutputs::Transfer(input_tx), genesis_inclusion())],
         );
         expect_error(
             &result,
@@ -1128,7 +1142,7 @@ fn test_transfer_verify_fail() {
             &tx,
             &witness,
             &extra_info,
-            vec![TxWithOutputs::Transfer(input_tx)],
+            vec![(TxWithOutputs::Transfer(input_tx), genesis_inclusion())],
         );
         expect_error(&result, Error::OutputInTimelock);
         let result = verify_enclave_tx(&mut mock_bridge, &txaux, &extra_info, 0, &storage);
@@ -1471,3 +1485,107 @@ fn test_nodejoin_fail() {
         expect_error_joinnode(&result, NodeJoinError::AlreadyJoined);
     }
 }
+
+fn prepare_locked_spend(lock: Timelock) -> (Tx, TxWitness, TxWithOutputs) {
+    let secp = Secp256k1::new();
+    let secret_key = SecretKey::from_slice(&[0xcd; 32]).expect("32 bytes, within curve order");
+    let (addr, merkle_tree) = get_address(&secp, &secret_key);
+    let mut old_tx = Tx::new();
+    old_tx.add_output(TxOut::new_with_lock(addr.clone(), Coin::one(), lock));
+    let mut tx = Tx::new();
+    tx.add_input(TxoPointer::new(old_tx.id(), 0));
+    tx.add_output(TxOut::new(addr, Coin::one()));
+    let witness: Vec<TxInWitness> = vec![get_tx_witness(secp, &tx.id(), &secret_key, &merkle_tree)];
+    (tx, witness.into(), TxWithOutputs::Transfer(old_tx))
+}
+
+#[test]
+fn test_transfer_height_and_relative_locks() {
+    let (tx, witness, input) = prepare_locked_spend(Timelock::Height(BlockHeight::new(10)));
+    let mut extra_info = get_chain_info(&TxAux::EnclaveTx(TxEnclaveAux::TransferTx {
+        inputs: tx.inputs.clone(),
+        no_of_outputs: 1,
+        payload: encrypt(&PlainTxAux::new(tx.clone(), witness.clone()), tx.id()),
+    }));
+    extra_info.min_fee_computed = Fee::new(Coin::zero());
+    extra_info.block_height = BlockHeight::new(9);
+    let result = verify_transfer(
+        &tx,
+        &witness,
+        &extra_info,
+        vec![(input.clone(), genesis_inclusion())],
+    );
+    expect_error(&result, Error::OutputInTimelock);
+    extra_info.block_height = BlockHeight::new(10);
+    let result = verify_transfer(
+        &tx,
+        &witness,
+        &extra_info,
+        vec![(input, genesis_inclusion())],
+    );
+    assert!(result.is_ok());
+
+    let (tx, witness, input) = prepare_locked_spend(Timelock::RelativeHeight(5));
+    let included = TxInclusion::new(DEFAULT_GENESIS_TIME, BlockHeight::new(8));
+    extra_info.block_height = BlockHeight::new(12);
+    let result = verify_transfer(&tx, &witness, &extra_info, vec![(input.clone(), included)]);
+    expect_error(&result, Error::OutputInTimelock);
+    extra_info.block_height = BlockHeight::new(13);
+    let result = verify_transfer(&tx, &witness, &extra_info, vec![(input, included)]);
+    assert!(result.is_ok());
+
+    let (tx, witness, input) = prepare_locked_spend(Timelock::RelativeTime(20));
+    let included = TxInclusion::new(DEFAULT_GENESIS_TIME, BlockHeight::new(8));
+    extra_info.block_time = DEFAULT_GENESIS_TIME + 19;
+    let result = verify_transfer(&tx, &witness, &extra_info, vec![(input.clone(), included)]);
+    expect_error(&result, Error::OutputInTimelock);
+    extra_info.block_time = DEFAULT_GENESIS_TIME + 20;
+    let result = verify_transfer(&tx, &witness, &extra_info, vec![(input, included)]);
+    assert!(result.is_ok());
+}
+
+#[test]
+fn test_transfer_fee_above_minimum() {
+    let (mut tx, _, input) = prepare_locked_spend(Timelock::Height(BlockHeight::new(0)));
+    let mut old_tx = match input {
+        TxWithOutputs::Transfer(old_tx) => old_tx,
+        _ => unreachable!(),
+    };
+    old_tx.outputs[0].value = Coin::new(10).unwrap();
+    tx.inputs[0] = TxoPointer::new(old_tx.id(), 0);
+    tx.outputs[0].value = Coin::new(7).unwrap();
+    let secret_key = SecretKey::from_slice(&[0xcd; 32]).expect("32 bytes, within curve order");
+    let (_, merkle_tree) = get_address(&Secp256k1::new(), &secret_key);
+    let witness: TxWitness = vec![get_tx_witness(
+        Secp256k1::new(),
+        &tx.id(),
+        &secret_key,
+        &merkle_tree,
+    )]
+    .into();
+    let input = TxWithOutputs::Transfer(old_tx);
+    let mut extra_info = get_chain_info(&TxAux::EnclaveTx(TxEnclaveAux::TransferTx {
+        inputs: tx.inputs.clone(),
+        no_of_outputs: 1,
+        payload: encrypt(&PlainTxAux::new(tx.clone(), witness.clone()), tx.id()),
+    }));
+
+    // the whole difference of inputs and outputs is paid as the fee
+    extra_info.min_fee_computed = Fee::new(Coin::new(2).unwrap());
+    let result = verify_transfer(
+        &tx,
+        &witness,
+        &extra_info,
+        vec![(input.clone(), genesis_inclusion())],
+    );
+    assert_eq!(result.unwrap(), Fee::new(Coin::new(3).unwrap()));
+
+    extra_info.min_fee_computed = Fee::new(Coin::new(4).unwrap());
+    let result = verify_transfer(
+        &tx,
+        &witness,
+        &extra_info,
+        vec![(input, genesis_inclusion())],
+    );
+    expect_error(&result, Error::InputOutputDoNotMatch);
+}
@@ -43,6 +43,10 @@ pub enum TendermintEventType {
     StakingChange,
     /// when reward was distributed
     Reward,
+    /// TDBE MLS group changes (commits, self-updates, nacks)
+    MLSHandshake,
+    /// network parameter change proposal results
+    Governance,
 }
 
 impl fmt::Display for TendermintEventType {
@@ -52,6 +56,8 @@ impl fmt::Display for TendermintEventType {
             TendermintEventType::BlockFilter => write!(f, "block_filter"),
             TendermintEventType::StakingChange => write!(f, "staking_change"),
             TendermintEventType::Reward => write!(f, "reward"),
+            TendermintEventType::MLSHandshake => write!(f, "mls_handshake"),
+            TendermintEventType::Governance => write!(f, "governance"),
         }
     }
 }
@@ -77,6 +83,24 @@ pub enum TendermintEventKey {
     CoinMinted,
     /// when state was slashed
     Slash,
+    /// TDBE MLS group epoch after the handshake
+    MLSEpoch,
+    /// TDBE MLS handshake operation type
+    MLSOpType,
+    /// leaf index of the TDBE MLS handshake sender
+    MLSSender,
+    /// network parameter change proposal identifier
+    ProposalId,
+    /// network parameter change proposal result (accepted or rejected)
+    ProposalResult,
+    /// network parameters version after the accepted proposal
+    ParamsVersion,
+    /// the council node backed by the staking address
+    DelegatedTo,
+    /// commission kept by the council node from the rewards of the backing staking addresses
+    Commission,
+    /// mempool priority of the transaction (paid fee per byte)
+    Priority,
 }
 
 impl From<TendermintEventKey> for Vec<u8> {
@@ -109,6 +133,15 @@ impl fmt::Display for TendermintEventKey {
             TendermintEventKey::StakingOpReason => write!(f, "staking_opreason"),
             TendermintEventKey::CoinMinted => write!(f, "minted"),
             TendermintEventKey::Slash => write!(f, "slash"),
+            TendermintEventKey::MLSEpoch => write!(f, "mls_epoch"),
+            TendermintEventKey::MLSOpType => write!(f, "mls_optype"),
+            TendermintEventKey::MLSSender => write!(f, "mls_sender"),
+            TendermintEventKey::ProposalId => write!(f, "proposal_id"),
+            TendermintEventKey::ProposalResult => write!(f, "proposal_result"),
+            TendermintEventKey::ParamsVersion => write!(f, "params_version"),
+            TendermintEventKey::DelegatedTo => write!(f, "delegated_to"),
+            TendermintEventKey::Commission => write!(f, "commission"),
+            TendermintEventKey::Priority => write!(f, "priority"),
         }
     }
 }
@@ -133,6 +166,15 @@ impl TendermintEventKey {
             TendermintEventKey::StakingOpReason => String::from("c3Rha2luZ19vcHJlYXNvbg=="),
             TendermintEventKey::CoinMinted => String::from("bWludGVk"),
             TendermintEventKey::Slash => String::from("c2xhc2g="),
+            TendermintEventKey::MLSEpoch => String::from("bWxzX2Vwb2No"),
+            TendermintEventKey::MLSOpType => String::from("bWxzX29wdHlwZQ=="),
+            TendermintEventKey::MLSSender => String::from("bWxzX3NlbmRlcg=="),
+            TendermintEventKey::ProposalId => String::from("cHJvcG9zYWxfaWQ="),
+            TendermintEventKey::ProposalResult => String::from("cHJvcG9zYWxfcmVzdWx0"),
+            TendermintEventKey::ParamsVersion => String::from("cGFyYW1zX3ZlcnNpb24="),
+            TendermintEventKey::DelegatedTo => String::from("ZGVsZWdhdGVkX3Rv"),
+            TendermintEventKey::Commission => String::from("Y29tbWlzc2lvbg=="),
+            TendermintEventKey::Priority => String::from("cHJpb3JpdHk="),
         }
     }
 }
@@ -51,6 +51,9 @@ pub enum DistributionError {
     /// Invalid punishment configuration parameter
     #[error("Invalid punishment parameters")]
     InvalidPunishmentParamter,
+    /// problems with governance configuration
+    #[error("Invalid governance parameters: {0}")]
+    InvalidGovernanceParameter(&'static str),
     /// keypackage decode error
     #[error("key package decode failed")]
     KeyPackageDecodeError,
@@ -176,6 +179,10 @@ impl InitConfig {
             .rewards_config
             .validate()
             .map_err(DistributionError::InvalidRewardsParamter)?;
+        self.network_params
+            .governance_config
+            .validate()
+            .map_err(DistributionError::InvalidGovernanceParameter)?;
         if self.council_nodes.is_empty() {
             return Err(DistributionError::NoValidators);
         }
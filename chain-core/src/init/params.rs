@@ -1,6 +1,6 @@
-use crate::common::H256;
+use crate::common::{Timespec, H256};
 use crate::init::coin::{Coin, CoinError};
-use crate::tx::fee::{Fee, FeeAlgorithm};
+use crate::tx::fee::{DynamicFee, Fee, FeeAlgorithm};
 use crate::tx::fee::{LinearFee, Milli, MilliError};
 use parity_scale_codec::{Decode, Encode};
 #[cfg(not(feature = "mesalock_sgx"))]
@@ -31,14 +31,25 @@ pub struct InitNetworkParameters {
     pub rewards_config: RewardsParameters,
     /// maximum number of active validators at a time (may be reshuffled)
     pub max_validators: u16,
+    /// Dynamic base fee configuration (disabled if missing)
+    #[cfg_attr(not(feature = "mesalock_sgx"), serde(default))]
+    pub dynamic_fee_config: DynamicFeeParameters,
+    /// Council node governance configuration (`GovernanceParameters::default()` if missing)
+    #[cfg_attr(not(feature = "mesalock_sgx"), serde(default))]
+    pub governance_config: GovernanceParameters,
 }
 
-/// so far only one specified at genesis
+/// version of the network parameters (0 for the genesis ones, incremented by each applied change)
+pub type NetworkParametersVersion = u32;
+
+/// network parameters: specified at genesis and then changed by the council node governance proposals
 #[derive(Debug, PartialEq, Eq, Clone, Encode, Decode)]
 #[cfg_attr(not(feature = "mesalock_sgx"), derive(Serialize, Deserialize))]
 pub enum NetworkParameters {
     /// parameters specified at genesis time
     Genesis(InitNetworkParameters),
+    /// parameters after the accepted parameter change proposals
+    Updated(NetworkParametersVersion, InitNetworkParameters),
 }
 
 /// TODO: extract these to a trait?
@@ -48,100 +59,214 @@ impl NetworkParameters {
         blake3::hash(&self.encode()).into()
     }
 
-    /// cap on validators in tendermint
-    pub fn get_max_validators(&self) -> usize {
+    /// the current values of all parameters
+    pub fn get_params(&self) -> &InitNetworkParameters {
         match self {
-            NetworkParameters::Genesis(params) => params.max_validators as usize,
+            NetworkParameters::Genesis(params) => params,
+            NetworkParameters::Updated(_, params) => params,
         }
     }
 
-    /// minimal stake required for node joining (to be a validator)
-    pub fn get_required_council_node_stake(&self) -> Coin {
+    /// 0 for the genesis parameters, incremented by each applied change
+    pub fn get_version(&self) -> NetworkParametersVersion {
         match self {
-            NetworkParameters::Genesis(params) => params.required_council_node_stake,
+            NetworkParameters::Genesis(_) => 0,
+            NetworkParameters::Updated(version, _) => *version,
         }
     }
 
+    /// returns the next version of parameters with the change applied
+    pub fn apply_change(
+        &self,
+        change: &NetworkParameterChange,
+    ) -> Result<NetworkParameters, &'static str> {
+        let version = self
+            .get_version()
+            .checked_add(1)
+            .ok_or("network parameters version overflow")?;
+        let mut params = self.get_params().clone();
+        change.apply(&mut params)?;
+        Ok(NetworkParameters::Updated(version, params))
+    }
+
+    /// cap on validators in tendermint
+    pub fn get_max_validators(&self) -> usize {
+        self.get_params().max_validators as usize
+    }
+
+    /// minimal stake required for node joining (to be a validator)
+    pub fn get_required_council_node_stake(&self) -> Coin {
+        self.get_params().required_council_node_stake
+    }
+
     /// infraction configuration for byzantine fault
     pub fn get_byzantine_slash_percent(&self) -> SlashRatio {
-        match self {
-            NetworkParameters::Genesis(params) => params.slashing_config.byzantine_slash_percent,
-        }
+        self.get_params().slashing_config.byzantine_slash_percent
     }
 
     /// infraction configuration for liveness fault
     pub fn get_liveness_slash_percent(&self) -> SlashRatio {
-        match self {
-            NetworkParameters::Genesis(params) => params.slashing_config.liveness_slash_percent,
-        }
+        self.get_params().slashing_config.liveness_slash_percent
     }
 
     /// infraction configuration for liveness fault
     pub fn get_missed_block_threshold(&self) -> u16 {
-        match self {
-            NetworkParameters::Genesis(params) => params.jailing_config.missed_block_threshold,
-        }
+        self.get_params().jailing_config.missed_block_threshold
     }
 
     /// infraction configuration for liveness fault
     pub fn get_block_signing_window(&self) -> u16 {
-        match self {
-            NetworkParameters::Genesis(params) => params.jailing_config.block_signing_window,
-        }
+        self.get_params().jailing_config.block_signing_window
     }
 
     /// The period of reward being distributed
     pub fn get_rewards_reward_period_seconds(&self) -> u64 {
-        match self {
-            NetworkParameters::Genesis(params) => params.rewards_config.reward_period_seconds,
-        }
+        self.get_params().rewards_config.reward_period_seconds
     }
 
     /// The upper bound for the reward rate per annum
     pub fn get_rewards_monetary_expansion_r0(&self) -> Milli {
-        match self {
-            NetworkParameters::Genesis(params) => params.rewards_config.monetary_expansion_r0,
-        }
+        self.get_params().rewards_config.monetary_expansion_r0
     }
 
     /// Initial value of tau in the reward function
     pub fn get_rewards_monetary_expansion_tau(&self) -> u64 {
-        match self {
-            NetworkParameters::Genesis(params) => params.rewards_config.monetary_expansion_tau,
-        }
+        self.get_params().rewards_config.monetary_expansion_tau
     }
 
     /// The decay rate of tau.
     pub fn get_rewards_monetary_expansion_decay(&self) -> u64 {
-        match self {
-            NetworkParameters::Genesis(params) => params.rewards_config.monetary_expansion_decay,
-        }
+        self.get_params().rewards_config.monetary_expansion_decay
     }
 
     /// The total amount of tokens reserved for validator's reward in the basic unit
     pub fn get_rewards_monetary_expansion_cap(&self) -> Coin {
-        match self {
-            NetworkParameters::Genesis(params) => params.rewards_config.monetary_expansion_cap,
-        }
+        self.get_params().rewards_config.monetary_expansion_cap
     }
 
     /// constant fee -- TODO: will it be necessary? (used in the tx-query fee?)
     pub fn get_min_const_fee(&self) -> Result<Fee, CoinError> {
+        let coin = Coin::new(
+            self.get_params()
+                .initial_fee_policy
+                .coefficient
+                .to_integral(),
+        )?;
+        Ok(Fee::new(coin))
+    }
+
+    /// calculates the fee based on the specified policy
+    pub fn calculate_fee(&self, num_bytes: usize) -> Result<Fee, CoinError> {
+        self.get_params()
+            .initial_fee_policy
+            .calculate_fee(num_bytes)
+    }
+
+    /// calculates the fee based on the specified policy multiplied by the current base fee
+    pub fn calculate_dynamic_fee(
+        &self,
+        num_bytes: usize,
+        base_fee: Milli,
+    ) -> Result<Fee, CoinError> {
+        DynamicFee::new(self.get_params().initial_fee_policy, base_fee).calculate_fee(num_bytes)
+    }
+
+    /// configuration of the base fee adjustment
+    pub fn get_dynamic_fee_config(&self) -> DynamicFeeParameters {
+        self.get_params().dynamic_fee_config
+    }
+
+    /// configuration of the parameter change proposals
+    pub fn get_governance_config(&self) -> GovernanceParameters {
+        self.get_params().governance_config
+    }
+}
+
+/// a change of network parameters proposed by a council node
+#[derive(Debug, PartialEq, Eq, Clone, Encode, Decode)]
+#[cfg_attr(not(feature = "mesalock_sgx"), derive(Serialize, Deserialize))]
+pub enum NetworkParameterChange {
+    /// new fee policy
+    FeePolicy(LinearFee),
+    /// new minimal council node stake
+    /// (the active validators with less bonded stake are deactivated)
+    RequiredCouncilNodeStake(Coin),
+    /// new jailing configuration
+    Jailing(JailingParameters),
+    /// new slashing configuration
+    Slashing(SlashingParameters),
+    /// new rewards configuration
+    Rewards(RewardsParameters),
+    /// new maximum number of active validators
+    MaxValidators(u16),
+    /// new dynamic base fee configuration
+    DynamicFee(DynamicFeeParameters),
+    /// new governance configuration
+    /// (the voting period applies to the later proposals, the quorum to all the proposals counted later)
+    Governance(GovernanceParameters),
+}
+
+impl NetworkParameterChange {
+    /// checks the new values (they don't depend on the current parameters)
+    pub fn validate(&self) -> Result<(), &'static str> {
         match self {
-            NetworkParameters::Genesis(params) => {
-                let coin = Coin::new(params.initial_fee_policy.coefficient.to_integral())?;
-                Ok(Fee::new(coin))
+            NetworkParameterChange::FeePolicy(_) | NetworkParameterChange::Slashing(_) => Ok(()),
+            NetworkParameterChange::RequiredCouncilNodeStake(stake) => {
+                if *stake == Coin::zero() {
+                    return Err("required council node stake can't == 0");
+                }
+                Ok(())
+            }
+            NetworkParameterChange::Jailing(jailing_config) => {
+                if jailing_config.block_signing_window == 0
+                    || jailing_config.missed_block_threshold > jailing_config.block_signing_window
+                {
+                    return Err("block signing window can't == 0 or < missed block threshold");
+                }
+                Ok(())
             }
+            NetworkParameterChange::Rewards(rewards_config) => rewards_config.validate(),
+            NetworkParameterChange::MaxValidators(max_validators) => {
+                if *max_validators == 0 {
+                    return Err("max validators can't == 0");
+                }
+                Ok(())
+            }
+            NetworkParameterChange::DynamicFee(dynamic_fee_config) => dynamic_fee_config.validate(),
+            NetworkParameterChange::Governance(governance_config) => governance_config.validate(),
         }
     }
 
-    /// calculates the fee based on the specified policy
-    pub fn calculate_fee(&self, num_bytes: usize) -> Result<Fee, CoinError> {
+    /// checks the new values and updates the parameters
+    pub fn apply(&self, params: &mut InitNetworkParameters) -> Result<(), &'static str> {
+        self.validate()?;
         match self {
-            NetworkParameters::Genesis(params) => {
-                params.initial_fee_policy.calculate_fee(num_bytes)
+            NetworkParameterChange::FeePolicy(fee_policy) => {
+                params.initial_fee_policy = *fee_policy;
+            }
+            NetworkParameterChange::RequiredCouncilNodeStake(stake) => {
+                params.required_council_node_stake = *stake;
+            }
+            NetworkParameterChange::Jailing(jailing_config) => {
+                params.jailing_config = *jailing_config;
+            }
+            NetworkParameterChange::Slashing(slashing_config) => {
+                params.slashing_config = *slashing_config;
+            }
+            NetworkParameterChange::Rewards(rewards_config) => {
+                params.rewards_config = *rewards_config;
+            }
+            NetworkParameterChange::MaxValidators(max_validators) => {
+                params.max_validators = *max_validators;
+            }
+            NetworkParameterChange::DynamicFee(dynamic_fee_config) => {
+                params.dynamic_fee_config = *dynamic_fee_config;
+            }
+            NetworkParameterChange::Governance(governance_config) => {
+                params.governance_config = *governance_config;
             }
         }
+        Ok(())
     }
 }
 
@@ -204,6 +329,98 @@ impl RewardsParameters {
     }
 }
 
+/// Maximum base fee (the fees are at most 1000 times the fee policy)
+pub const MAX_BASE_FEE: Milli = Milli::new(1000, 0);
+
+/// dynamic base fee parameters: the fee policy is multiplied by a base fee
+/// that goes up after the blocks larger than the target size and down after the smaller ones
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default, Encode, Decode)]
+#[cfg_attr(not(feature = "mesalock_sgx"), derive(Serialize, Deserialize))]
+pub struct DynamicFeeParameters {
+    /// Size (in bytes of transactions) of the block which keeps the base fee the same
+    /// (0 disables the dynamic base fee)
+    pub target_block_size: u32,
+    /// Maximum change of the base fee after one block (e.g. 0.125)
+    pub max_change_rate: Milli,
+}
+
+impl DynamicFeeParameters {
+    /// check if dynamic fee parameters are correct
+    pub fn validate(&self) -> Result<(), &'static str> {
+        if self.max_change_rate > Milli::integral(1).unwrap() {
+            return Err("max change rate of base fee can't > 1");
+        }
+        Ok(())
+    }
+
+    /// the base fee after a block with transactions of `block_size` bytes
+    /// (it never goes below 1.0, i.e. the fee policy is the minimum, nor above `MAX_BASE_FEE`)
+    pub fn next_base_fee(&self, base_fee: Milli, block_size: usize) -> Milli {
+        let min_base_fee = u128::from(Milli::integral(1).unwrap().as_millis());
+        if self.target_block_size == 0 {
+            return Milli::from_millis(min_base_fee as u64);
+        }
+        let target = u128::from(self.target_block_size);
+        // the change is capped at the max rate for blocks twice as large as the target
+        let size = (block_size as u128).min(target * 2);
+        let current = u128::from(base_fee.as_millis());
+        let rate = u128::from(self.max_change_rate.as_millis());
+        let delta = |diff: u128| current * rate * diff / target / 1000;
+        let next = if size >= target {
+            current + delta(size - target)
+        } else {
+            current.saturating_sub(delta(target - size))
+        };
+        let max_base_fee = u128::from(MAX_BASE_FEE.as_millis());
+        Milli::from_millis(next.max(min_base_fee).min(max_base_fee) as u64)
+    }
+}
+
+/// council node governance parameters
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Encode, Decode)]
+#[cfg_attr(not(feature = "mesalock_sgx"), derive(Serialize, Deserialize))]
+pub struct GovernanceParameters {
+    /// How long the proposals are open for voting (in seconds of block time)
+    pub voting_period: Timespec,
+    /// Maximum number of proposals in the voting period
+    pub max_pending_proposals: u16,
+    /// Fraction of the total voting power of the validators that needs to approve a proposal
+    /// (e.g. 0.667)
+    pub quorum: Milli,
+}
+
+impl Default for GovernanceParameters {
+    /// one day of voting, at most 16 pending proposals and (more than) 2/3 quorum
+    fn default() -> Self {
+        GovernanceParameters {
+            voting_period: 24 * 60 * 60,
+            max_pending_proposals: 16,
+            quorum: Milli::new(0, 667),
+        }
+    }
+}
+
+impl GovernanceParameters {
+    /// check if governance parameters are correct
+    pub fn validate(&self) -> Result<(), &'static str> {
+        if self.voting_period == 0 || self.voting_period > 365 * 86400 {
+            return Err("voting period can't == 0 or exceed 365 days");
+        }
+        if self.max_pending_proposals == 0 {
+            return Err("max pending proposals can't == 0");
+        }
+        if self.quorum <= Milli::new(0, 500) || self.quorum > Milli::integral(1).unwrap() {
+            return Err("quorum can't <= 0.5 or > 1");
+        }
+        Ok(())
+    }
+
+    /// whether the approving voting power reaches the quorum of the total voting power
+    pub fn is_quorum(&self, approved: u128, total: u128) -> bool {
+        total > 0 && approved * 1000 >= total * u128::from(self.quorum.as_millis())
+    }
+}
+
 /// how much to slash from bonded+unbonded
 #[derive(PartialEq, Eq, PartialOrd, Ord, Debug, Clone, Copy, Encode, Decode)]
 pub struct SlashRatio(Milli);
@@ -314,3 +531,40 @@ impl fmt::Display for SlashRatioError {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn check_next_base_fee() {
+        let config = DynamicFeeParameters {
+            target_block_size: 1000,
+            max_change_rate: Milli::new(0, 125),
+        };
+        let one = Milli::new(1, 0);
+        assert_eq!(config.next_base_fee(one, 1000), one);
+        assert_eq!(config.next_base_fee(one, 2000), Milli::new(1, 125));
+        assert_eq!(config.next_base_fee(one, 5000), Milli::new(1, 125));
+        assert_eq!(config.next_base_fee(one, 1500), Milli::new(1, 62));
+        assert_eq!(
+            config.next_base_fee(Milli::new(2, 0), 0),
+            Milli::new(1, 750)
+        );
+        // never below the fee policy
+        assert_eq!(config.next_base_fee(one, 0), one);
+        // never above the maximum
+        assert_eq!(config.next_base_fee(MAX_BASE_FEE, 2000), MAX_BASE_FEE);
+        // disabled
+        assert_eq!(
+            DynamicFeeParameters::default().next_base_fee(Milli::new(2, 0), 5000),
+            one
+        );
+        assert!(DynamicFeeParameters {
+            target_block_size: 1000,
+            max_change_rate: Milli::new(1, 1),
+        }
+        .validate()
+        .is_err());
+    }
+}
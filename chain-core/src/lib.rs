@@ -25,11 +25,10 @@ pub mod tx;
 pub mod fixed;
 
 use common::{MerkleTree, Timespec, H256};
-use init::params::NetworkParameters;
 use parity_scale_codec::{Decode, Encode};
 use state::tendermint::BlockHeight;
-use state::RewardsPoolState;
-use tx::fee::Fee;
+use state::ChainState;
+use tx::fee::{Fee, Milli};
 
 /// The app version returned in Tendermint "Info" response,
 /// included in every header + transaction metadata.
@@ -39,30 +38,69 @@ use tx::fee::Fee;
 ///
 /// version 0 -- 0.4.0 release
 /// version 1 -- 0.5.0 release (wire format didn't change, but unbond tx semantics changed: https://github.com/crypto-com/chain/pull/1516)
-/// version 2 -- 0.6.0 (not yet released --> transaction data bootstrapping, new TX types, genesis changes..);
+/// version 2 -- 0.6.0 (not yet released --> transaction data bootstrapping, new TX types, genesis changes,
+///                      fee market: the fees above the minimum are accepted and the app hash commits
+///                      to the base fee, the stake transfers and the MLS group state..);
 /// FIXME: bump to 2 with https://github.com/crypto-com/chain/issues/1715#issuecomment-650845116
 pub const APP_VERSION: u64 = 1;
 
 /// computes the "global" application hash (used by Tendermint to check consistency + block replaying)
 /// currently: app_hash = blake3(root of valid TX merkle tree
-/// || root of account/staked state trie || blake3(scale bytes(rewards pool state)) || blake3(scale bytes(network params)))
+/// || root of account/staked state trie || blake3(scale bytes(rewards pool state)) || blake3(scale bytes(network params))
+/// || hash of the public TDBE MLS group state || scale bytes(base fee))
 /// TODO: cache (as many parts remain static)
 /// MUST/TODO: include node whitelists
-pub fn compute_app_hash(
-    valid_tx_id_tree: &MerkleTree<H256>,
-    account_state_root: &H256,
-    reward_pool: &RewardsPoolState,
-    params: &NetworkParameters,
-) -> H256 {
-    let valid_tx_part = valid_tx_id_tree.root_hash();
-    let rewards_pool_part = reward_pool.hash();
-    let network_params_part = params.hash();
-    let mut hasher = blake3::Hasher::new();
-    hasher.update(&valid_tx_part);
-    hasher.update(&account_state_root[..]);
-    hasher.update(&rewards_pool_part);
-    hasher.update(&network_params_part);
-    hasher.finalize().into()
+pub fn compute_app_hash(valid_tx_id_tree: &MerkleTree<H256>, state: &ChainState) -> H256 {
+    AppHashParts::new(valid_tx_id_tree, state).app_hash()
+}
+
+/// The hashed parts of the "global" application hash (see `compute_app_hash`):
+/// included in query proofs, so that e.g. the staking trie root can be checked
+/// against the app hash in a (light client) trusted block header
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Encode, Decode)]
+pub struct AppHashParts {
+    /// root of valid TX merkle tree
+    pub valid_tx_root: H256,
+    /// root of account/staked state trie
+    pub account_root: H256,
+    /// blake3(scale bytes(rewards pool state))
+    pub rewards_pool_hash: H256,
+    /// blake3(scale bytes(network params))
+    pub network_params_hash: H256,
+    /// hash of the public TDBE MLS group state (zeros before the group is created)
+    pub mls_group_hash: H256,
+    /// current base fee (multiplier of the fee policy)
+    pub base_fee: Milli,
+    /// hash of the stake transfers that can still be slashed (zeros if there are none)
+    pub stake_transfers_hash: H256,
+}
+
+impl AppHashParts {
+    /// hashes the parts of the application state
+    pub fn new(valid_tx_id_tree: &MerkleTree<H256>, state: &ChainState) -> Self {
+        AppHashParts {
+            valid_tx_root: valid_tx_id_tree.root_hash(),
+            account_root: state.account_root,
+            rewards_pool_hash: state.rewards_pool.hash(),
+            network_params_hash: state.network_params.hash(),
+            mls_group_hash: state.mls_group_hash,
+            base_fee: state.base_fee,
+            stake_transfers_hash: state.stake_transfers_hash,
+        }
+    }
+
+    /// computes the application hash from the parts
+    pub fn app_hash(&self) -> H256 {
+        let mut hasher = blake3::Hasher::new();
+        hasher.update(&self.valid_tx_root);
+        hasher.update(&self.account_root);
+        hasher.update(&self.rewards_pool_hash);
+        hasher.update(&self.network_params_hash);
+        hasher.update(&self.mls_group_hash);
+        hasher.update(&self.base_fee.encode());
+        hasher.update(&self.stake_transfers_hash);
+        hasher.finalize().into()
+    }
 }
 
 /// External information needed for TX validation
@@ -27,6 +27,15 @@ pub enum MLSHandshakeAux {
     /// + some way to refer to the message part that went wrong
     /// FIXME: spec/data type
     MsgNack(Vec<u8>),
+    /// the initial commit that creates the group (if there's no group yet)
+    GroupInit {
+        /// KeyPackage of the group creator -- it should belong to an active council node
+        creator: Vec<u8>,
+        /// MLSPlaintext -- Add proposals of the other council nodes, the last one is assumed to be Commit
+        messages: Vec<Vec<u8>>,
+        /// Welcome -- with encrypted paths/epochs for the added members
+        welcome: Vec<u8>,
+    },
 }
 
 impl TransactionId for MLSHandshakeAux {}
@@ -6,9 +6,13 @@ use crate::state::tendermint::{
     BlockHeight, TendermintValidatorAddress, TendermintValidatorPubKey,
 };
 pub use crate::state::validator::UnjailTx;
+#[cfg(not(feature = "mesalock_sgx"))]
+use crate::tx::fee::Milli;
 pub use address::StakedStateAddress;
 pub use op::data::attribute::StakedStateOpAttributes;
+pub use op::data::delegate::DelegateTx;
 pub use op::data::deposit::DepositBondTx;
+pub use op::data::transfer::{StakeKind, TransferStakeTx};
 pub use op::data::unbond::UnbondTx;
 pub use op::data::withdraw::WithdrawUnbondedTx;
 pub use op::witness::StakedStateOpWitness;
@@ -20,6 +24,8 @@ use std::convert::From;
 use std::fmt;
 use std::prelude::v1::Vec;
 use std::prelude::v1::{String, ToString};
+#[cfg(not(feature = "mesalock_sgx"))]
+use std::str::FromStr;
 
 /// reference counter in the sparse patricia merkle tree/trie
 pub type Count = u64;
@@ -100,7 +106,7 @@ impl Encode for NodeCommonInfo {
     }
 }
 
-fn decode_name_security_contact<I: Input>(
+pub(crate) fn decode_name_security_contact<I: Input>(
     input: &mut I,
 ) -> Result<(NodeName, NodeSecurityContact), Error> {
     let name_raw: Vec<u8> = Vec::decode(input)?;
@@ -144,6 +150,9 @@ pub struct CouncilNodeMeta {
     pub node_info: NodeCommonInfo,
     /// Tendermint consensus validator-associated public key
     pub consensus_pubkey: TendermintValidatorPubKey,
+    /// share of the rewards of the backing staked states kept by the council node
+    #[cfg_attr(not(feature = "mesalock_sgx"), serde(default))]
+    pub commission_rate: CommissionRate,
 }
 
 impl Encode for CouncilNodeMeta {
@@ -161,6 +170,7 @@ impl Encode for CouncilNodeMeta {
         };
         self.consensus_pubkey.encode_to(dest);
         self.node_info.confidential_init.keypackage.encode_to(dest);
+        self.commission_rate.encode_to(dest);
     }
 }
 
@@ -172,12 +182,15 @@ impl Decode for CouncilNodeMeta {
         let (name, security_contact) = decode_name_security_contact(input)?;
         let consensus_pubkey = TendermintValidatorPubKey::decode(input)?;
         let keypackage: Vec<u8> = Vec::decode(input)?;
-        Ok(CouncilNodeMeta::new_with_details(
+        let commission_rate = CommissionRate::decode(input)?;
+        let mut council_node = CouncilNodeMeta::new_with_details(
             name,
             security_contact,
             consensus_pubkey,
             ConfidentialInit { keypackage },
-        ))
+        );
+        council_node.commission_rate = commission_rate;
+        Ok(council_node)
     }
 }
 
@@ -272,6 +285,7 @@ impl CouncilNodeMeta {
                 confidential_init,
             },
             consensus_pubkey,
+            commission_rate: CommissionRate::default(),
         }
     }
 
@@ -289,8 +303,84 @@ impl CouncilNodeMeta {
                 confidential_init,
             },
             consensus_pubkey,
+            commission_rate: CommissionRate::default(),
+        }
+    }
+}
+
+/// share of the rewards of the backing staked states kept by the council node
+/// (3-digit fixed decimal number between 0.0 and 1.0)
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Default, Encode)]
+pub struct CommissionRate(u64);
+
+const MAX_COMMISSION_RATE_MILLIS: u64 = 1000;
+
+impl CommissionRate {
+    /// constructs the rate from the encoding of the fixed decimal number (e.g. 100 = 0.1)
+    pub fn from_millis(millis: u64) -> Result<Self, &'static str> {
+        if millis > MAX_COMMISSION_RATE_MILLIS {
+            Err("commission rate is greater than 1.0")
+        } else {
+            Ok(CommissionRate(millis))
         }
     }
+
+    /// returns the encoding of the fixed decimal number
+    #[inline]
+    pub fn as_millis(self) -> u64 {
+        self.0
+    }
+
+    /// the commission from the rewards (rounded down)
+    pub fn commission(self, rewards: Coin) -> Coin {
+        let value = u128::from(u64::from(rewards)) * u128::from(self.0)
+            / u128::from(MAX_COMMISSION_RATE_MILLIS);
+        // no panic: rate <= 1.0
+        Coin::new(value as u64).unwrap()
+    }
+}
+
+impl Decode for CommissionRate {
+    fn decode<I: Input>(input: &mut I) -> Result<Self, Error> {
+        CommissionRate::from_millis(u64::decode(input)?).map_err(Error::from)
+    }
+}
+
+#[cfg(not(feature = "mesalock_sgx"))]
+impl FromStr for CommissionRate {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let milli = Milli::from_str(s).map_err(|e| e.to_string())?;
+        CommissionRate::from_millis(milli.as_millis()).map_err(ToString::to_string)
+    }
+}
+
+#[cfg(not(feature = "mesalock_sgx"))]
+impl fmt::Display for CommissionRate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", Milli::from_millis(self.0))
+    }
+}
+
+#[cfg(not(feature = "mesalock_sgx"))]
+impl Serialize for CommissionRate {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+#[cfg(not(feature = "mesalock_sgx"))]
+impl<'de> Deserialize<'de> for CommissionRate {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        CommissionRate::from_str(&String::deserialize(deserializer)?).map_err(D::Error::custom)
+    }
 }
 
 /// Types of possible punishments
@@ -455,6 +545,9 @@ pub enum NodeState {
 ///       }
 ///   }
 ///   ```
+///
+/// Invariant 4.3:
+///   council nodes don't back other council nodes: `!(has_council_node_meta() && delegated_to.is_some())`
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Encode, Decode)]
 #[cfg_attr(not(feature = "mesalock_sgx"), derive(Serialize, Deserialize))]
 pub struct StakedState {
@@ -472,6 +565,8 @@ pub struct StakedState {
     pub node_meta: Option<NodeState>,
     /// record the last slash only for query
     pub last_slash: Option<SlashRecord>,
+    /// the council node backed by the bonded coins (see `DelegateTx`)
+    pub delegated_to: Option<StakedStateAddress>,
 }
 
 /// the tree used in StakedState storage db has a hardcoded 32-byte keys,
@@ -508,6 +603,7 @@ impl StakedState {
             address,
             node_meta: validator.map(NodeState::CouncilNode),
             last_slash: None,
+            delegated_to: None,
         }
     }
 
@@ -521,6 +617,7 @@ impl StakedState {
             unbonded_from: 0,
             node_meta: None,
             last_slash: None,
+            delegated_to: None,
         }
     }
 
@@ -577,6 +674,9 @@ impl StakedState {
                 assert!(self.bonded >= minimal_required_staking && !val.is_jailed());
             }
         }
+
+        // check: Invariant 4.3
+        assert!(!(self.has_council_node_meta() && self.delegated_to.is_some()));
     }
 
     /// Increment nonce by 1
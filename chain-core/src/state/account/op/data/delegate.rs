@@ -0,0 +1,114 @@
+use crate::state::account::address::StakedStateAddress;
+use crate::state::account::op::data::attribute::StakedStateOpAttributes;
+use crate::state::account::Nonce;
+use crate::tx::TransactionId;
+use parity_scale_codec::{Decode, Encode, Error, Input, Output};
+#[cfg(not(feature = "mesalock_sgx"))]
+use serde::{Deserialize, Serialize};
+#[cfg(not(feature = "mesalock_sgx"))]
+use std::fmt;
+
+/// makes the bonded coins of a (non-council node) staked state back a council node:
+/// the staked state receives a part of the council node's rewards
+/// (minus the commission set by the council node)
+/// and it's slashed together with the council node.
+/// `None` stops backing the current council node.
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(not(feature = "mesalock_sgx"), derive(Serialize, Deserialize))]
+pub struct DelegateTx {
+    /// the backing (staking) state
+    pub address: StakedStateAddress,
+    /// the council node to back
+    pub council_node: Option<StakedStateAddress>,
+    /// expected counter of the backing state to check against
+    pub nonce: Nonce,
+    /// versioning info etc.
+    pub attributes: StakedStateOpAttributes,
+}
+
+impl Decode for DelegateTx {
+    fn decode<I: Input>(input: &mut I) -> Result<Self, Error> {
+        let address = StakedStateAddress::decode(input)?;
+        let council_node = <Option<StakedStateAddress>>::decode(input)?;
+        let nonce = Nonce::decode(input)?;
+        let attributes = StakedStateOpAttributes::decode(input)?;
+
+        Ok(DelegateTx {
+            address,
+            council_node,
+            nonce,
+            attributes,
+        })
+    }
+}
+
+impl Encode for DelegateTx {
+    fn encode_to<EncOut: Output>(&self, dest: &mut EncOut) {
+        dest.push(&self.address);
+        dest.push(&self.council_node);
+        dest.push(&self.nonce);
+        dest.push(&self.attributes);
+    }
+
+    fn size_hint(&self) -> usize {
+        self.address.size_hint()
+            + self.council_node.size_hint()
+            + self.nonce.size_hint()
+            + self.attributes.size_hint()
+    }
+}
+
+impl TransactionId for DelegateTx {}
+
+impl DelegateTx {
+    /// creates a new tx to back a council node (or to stop backing it)
+    pub fn new(
+        address: StakedStateAddress,
+        council_node: Option<StakedStateAddress>,
+        nonce: Nonce,
+        attributes: StakedStateOpAttributes,
+    ) -> Self {
+        DelegateTx {
+            address,
+            council_node,
+            nonce,
+            attributes,
+        }
+    }
+}
+
+#[cfg(not(feature = "mesalock_sgx"))]
+impl fmt::Display for DelegateTx {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.council_node {
+            Some(council_node) => writeln!(
+                f,
+                "{} backs council node {} (nonce: {})",
+                self.address, council_node, self.nonce
+            )?,
+            None => writeln!(
+                f,
+                "{} stops backing a council node (nonce: {})",
+                self.address, self.nonce
+            )?,
+        }
+        write!(f, "")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_encode_decode() {
+        let tx = DelegateTx::new(
+            StakedStateAddress::BasicRedeem([1u8; 20].into()),
+            Some(StakedStateAddress::BasicRedeem([2u8; 20].into())),
+            1,
+            StakedStateOpAttributes::new(0),
+        );
+        let encoded = tx.encode();
+        assert_eq!(DelegateTx::decode(&mut encoded.as_slice()).unwrap(), tx);
+    }
+}
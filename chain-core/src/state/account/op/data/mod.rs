@@ -1,7 +1,11 @@
 /// versioning info etc.
 pub mod attribute;
+/// backing a council node
+pub mod delegate;
 /// deposit transaction
 pub mod deposit;
+/// stake transfer between staked states
+pub mod transfer;
 /// unbond stake transaction
 pub mod unbond;
 /// withdraw unbonded stake transaction
@@ -0,0 +1,177 @@
+use crate::init::coin::Coin;
+use crate::state::account::address::StakedStateAddress;
+use crate::state::account::op::data::attribute::StakedStateOpAttributes;
+use crate::state::account::Nonce;
+use crate::tx::TransactionId;
+use parity_scale_codec::{Decode, Encode, Error, Input, Output};
+#[cfg(not(feature = "mesalock_sgx"))]
+use serde::{Deserialize, Serialize};
+#[cfg(not(feature = "mesalock_sgx"))]
+use std::fmt;
+
+/// which part of the staked state is transferred
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(not(feature = "mesalock_sgx"), derive(Serialize, Deserialize))]
+pub enum StakeKind {
+    /// bonded coins (the destination bonded amount is increased)
+    Bonded,
+    /// unbonded coins (the destination keeps the later of both unbonding times)
+    Unbonded,
+}
+
+impl Encode for StakeKind {
+    fn encode_to<EncOut: Output>(&self, dest: &mut EncOut) {
+        match *self {
+            StakeKind::Bonded => dest.push_byte(0),
+            StakeKind::Unbonded => dest.push_byte(1),
+        }
+    }
+
+    fn size_hint(&self) -> usize {
+        1
+    }
+}
+
+impl Decode for StakeKind {
+    fn decode<I: Input>(input: &mut I) -> Result<Self, Error> {
+        match input.read_byte()? {
+            0 => Ok(StakeKind::Bonded),
+            1 => Ok(StakeKind::Unbonded),
+            _ => Err("No such variant in enum StakeKind".into()),
+        }
+    }
+}
+
+#[cfg(not(feature = "mesalock_sgx"))]
+impl fmt::Display for StakeKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StakeKind::Bonded => write!(f, "bonded"),
+            StakeKind::Unbonded => write!(f, "unbonded"),
+        }
+    }
+}
+
+/// moves some of the bonded or unbonded amount (+ fee paid from the same part)
+/// of one staked state to another one without going through the unbonding period;
+/// the witnesses of both staked states are required.
+/// The transferred coins can still be slashed for the faults of the source
+/// during the rest of the source's unbonding window.
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(not(feature = "mesalock_sgx"), derive(Serialize, Deserialize))]
+pub struct TransferStakeTx {
+    /// which (staking) state to transfer from
+    pub from_staked_account: StakedStateAddress,
+    /// which (staking) state to transfer to
+    pub to_staked_account: StakedStateAddress,
+    /// expected counter of the source state to check against
+    pub nonce: Nonce,
+    /// amount to transfer
+    pub value: Coin,
+    /// bonded or unbonded coins
+    pub kind: StakeKind,
+    /// versioning info etc.
+    pub attributes: StakedStateOpAttributes,
+}
+
+impl Decode for TransferStakeTx {
+    fn decode<I: Input>(input: &mut I) -> Result<Self, Error> {
+        let from_staked_account = StakedStateAddress::decode(input)?;
+        let to_staked_account = StakedStateAddress::decode(input)?;
+        let nonce = Nonce::decode(input)?;
+        let value = Coin::decode(input)?;
+        let kind = StakeKind::decode(input)?;
+        let attributes = StakedStateOpAttributes::decode(input)?;
+
+        Ok(TransferStakeTx {
+            from_staked_account,
+            to_staked_account,
+            nonce,
+            value,
+            kind,
+            attributes,
+        })
+    }
+}
+
+impl Encode for TransferStakeTx {
+    fn encode_to<EncOut: Output>(&self, dest: &mut EncOut) {
+        dest.push(&self.from_staked_account);
+        dest.push(&self.to_staked_account);
+        dest.push(&self.nonce);
+        dest.push(&self.value);
+        dest.push(&self.kind);
+        dest.push(&self.attributes);
+    }
+
+    fn size_hint(&self) -> usize {
+        self.from_staked_account.size_hint()
+            + self.to_staked_account.size_hint()
+            + self.nonce.size_hint()
+            + self.value.size_hint()
+            + self.kind.size_hint()
+            + self.attributes.size_hint()
+    }
+}
+
+impl TransactionId for TransferStakeTx {}
+
+impl TransferStakeTx {
+    /// creates a new tx to transfer certain amount between staked states
+    pub fn new(
+        from_staked_account: StakedStateAddress,
+        to_staked_account: StakedStateAddress,
+        nonce: Nonce,
+        value: Coin,
+        kind: StakeKind,
+        attributes: StakedStateOpAttributes,
+    ) -> Self {
+        TransferStakeTx {
+            from_staked_account,
+            to_staked_account,
+            nonce,
+            value,
+            kind,
+            attributes,
+        }
+    }
+}
+
+#[cfg(not(feature = "mesalock_sgx"))]
+impl fmt::Display for TransferStakeTx {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{} transferred {} {} to {} (nonce: {})",
+            self.from_staked_account, self.value, self.kind, self.to_staked_account, self.nonce
+        )?;
+        write!(f, "")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_encode_decode() {
+        let tx = TransferStakeTx::new(
+            StakedStateAddress::BasicRedeem([1u8; 20].into()),
+            StakedStateAddress::BasicRedeem([2u8; 20].into()),
+            1,
+            Coin::unit(),
+            StakeKind::Unbonded,
+            StakedStateOpAttributes::new(0),
+        );
+        let encoded = tx.encode();
+        assert_eq!(
+            TransferStakeTx::decode(&mut encoded.as_slice()).unwrap(),
+            tx
+        );
+    }
+
+    #[test]
+    fn check_unknown_kind_is_rejected() {
+        assert!(StakeKind::decode(&mut [2u8].as_ref()).is_err());
+    }
+}
@@ -0,0 +1,255 @@
+use crate::common::Timespec;
+use crate::init::params::NetworkParameterChange;
+use crate::state::account::{Nonce, StakedStateAddress, StakedStateOpAttributes};
+#[cfg(not(feature = "mesalock_sgx"))]
+use crate::tx::data::input::{deserialize_transaction_id, serialize_transaction_id};
+use crate::tx::data::TxId;
+use crate::tx::TransactionId;
+use parity_scale_codec::{Decode, Encode, Error, Input, Output};
+#[cfg(not(feature = "mesalock_sgx"))]
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+#[cfg(not(feature = "mesalock_sgx"))]
+use std::fmt;
+
+/// proposals are identified by the transaction ID of `ParamChangeProposalTx`
+pub type ProposalId = TxId;
+
+/// A network parameter change proposal in the voting period
+#[derive(Debug, PartialEq, Eq, Clone, Encode, Decode)]
+#[cfg_attr(not(feature = "mesalock_sgx"), derive(Serialize, Deserialize))]
+pub struct Proposal {
+    /// the transaction ID of the proposal
+    #[cfg_attr(
+        not(feature = "mesalock_sgx"),
+        serde(serialize_with = "serialize_transaction_id")
+    )]
+    #[cfg_attr(
+        not(feature = "mesalock_sgx"),
+        serde(deserialize_with = "deserialize_transaction_id")
+    )]
+    pub id: ProposalId,
+    /// the council node that submitted the proposal
+    pub proposer: StakedStateAddress,
+    /// the proposed change
+    pub change: NetworkParameterChange,
+    /// the block time when the votes are counted
+    pub voting_end_time: Timespec,
+    /// the latest vote of each council node (true = approve)
+    pub votes: BTreeMap<StakedStateAddress, bool>,
+}
+
+/// Submits a network parameter change proposal; the proposer's vote counts as approval.
+/// The proposal is accepted if the approving council nodes have the required share
+/// of the active validators' voting power at the end of the voting period,
+/// and it's applied in the `end_block` of that block.
+///
+/// tx-validation should check that:
+/// - the staked state is an active council node
+/// - the change is valid for the current parameters
+/// - the witness is correct
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(not(feature = "mesalock_sgx"), derive(Serialize, Deserialize))]
+pub struct ParamChangeProposalTx {
+    /// the expected nonce on the corresponding state
+    pub nonce: Nonce,
+    /// the council node staking address
+    pub address: StakedStateAddress,
+    /// the versioning and network identifier
+    pub attributes: StakedStateOpAttributes,
+    /// the proposed change
+    pub change: NetworkParameterChange,
+}
+
+impl Decode for ParamChangeProposalTx {
+    fn decode<I: Input>(input: &mut I) -> Result<Self, Error> {
+        let nonce = Nonce::decode(input)?;
+        let address = StakedStateAddress::decode(input)?;
+        let attributes = StakedStateOpAttributes::decode(input)?;
+        let change = NetworkParameterChange::decode(input)?;
+
+        Ok(ParamChangeProposalTx {
+            nonce,
+            address,
+            attributes,
+            change,
+        })
+    }
+}
+
+impl Encode for ParamChangeProposalTx {
+    fn encode_to<EncOut: Output>(&self, dest: &mut EncOut) {
+        dest.push(&self.nonce);
+        dest.push(&self.address);
+        dest.push(&self.attributes);
+        dest.push(&self.change);
+    }
+
+    fn size_hint(&self) -> usize {
+        self.nonce.size_hint()
+            + self.address.size_hint()
+            + self.attributes.size_hint()
+            + self.change.size_hint()
+    }
+}
+
+impl TransactionId for ParamChangeProposalTx {}
+
+impl ParamChangeProposalTx {
+    /// constructs a new proposal transaction from the provided components
+    #[inline]
+    pub fn new(
+        nonce: Nonce,
+        address: StakedStateAddress,
+        attributes: StakedStateOpAttributes,
+        change: NetworkParameterChange,
+    ) -> Self {
+        Self {
+            nonce,
+            address,
+            attributes,
+            change,
+        }
+    }
+}
+
+#[cfg(not(feature = "mesalock_sgx"))]
+impl fmt::Display for ParamChangeProposalTx {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{} (nonce: {}) proposes network parameter change: {:?}",
+            self.address, self.nonce, self.change
+        )?;
+        write!(f, "")
+    }
+}
+
+/// Votes on a pending network parameter change proposal
+/// (a later vote of the same council node replaces the previous one).
+///
+/// tx-validation should check that:
+/// - the staked state is an active council node
+/// - the proposal is in the voting period
+/// - the witness is correct
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(not(feature = "mesalock_sgx"), derive(Serialize, Deserialize))]
+pub struct ParamChangeVoteTx {
+    /// the expected nonce on the corresponding state
+    pub nonce: Nonce,
+    /// the council node staking address
+    pub address: StakedStateAddress,
+    /// the versioning and network identifier
+    pub attributes: StakedStateOpAttributes,
+    /// which proposal to vote on
+    pub proposal_id: ProposalId,
+    /// approve or reject
+    pub approve: bool,
+}
+
+impl Decode for ParamChangeVoteTx {
+    fn decode<I: Input>(input: &mut I) -> Result<Self, Error> {
+        let nonce = Nonce::decode(input)?;
+        let address = StakedStateAddress::decode(input)?;
+        let attributes = StakedStateOpAttributes::decode(input)?;
+        let proposal_id = ProposalId::decode(input)?;
+        let approve = bool::decode(input)?;
+
+        Ok(ParamChangeVoteTx {
+            nonce,
+            address,
+            attributes,
+            proposal_id,
+            approve,
+        })
+    }
+}
+
+impl Encode for ParamChangeVoteTx {
+    fn encode_to<EncOut: Output>(&self, dest: &mut EncOut) {
+        dest.push(&self.nonce);
+        dest.push(&self.address);
+        dest.push(&self.attributes);
+        dest.push(&self.proposal_id);
+        dest.push(&self.approve);
+    }
+
+    fn size_hint(&self) -> usize {
+        self.nonce.size_hint()
+            + self.address.size_hint()
+            + self.attributes.size_hint()
+            + self.proposal_id.size_hint()
+            + self.approve.size_hint()
+    }
+}
+
+impl TransactionId for ParamChangeVoteTx {}
+
+impl ParamChangeVoteTx {
+    /// constructs a new vote transaction from the provided components
+    #[inline]
+    pub fn new(
+        nonce: Nonce,
+        address: StakedStateAddress,
+        attributes: StakedStateOpAttributes,
+        proposal_id: ProposalId,
+        approve: bool,
+    ) -> Self {
+        Self {
+            nonce,
+            address,
+            attributes,
+            proposal_id,
+            approve,
+        }
+    }
+}
+
+#[cfg(not(feature = "mesalock_sgx"))]
+impl fmt::Display for ParamChangeVoteTx {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{} (nonce: {}) {} proposal {}",
+            self.address,
+            self.nonce,
+            if self.approve { "approves" } else { "rejects" },
+            hex::encode(&self.proposal_id)
+        )?;
+        write!(f, "")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::init::coin::Coin;
+
+    #[test]
+    fn check_encode_decode() {
+        let proposal = ParamChangeProposalTx::new(
+            1,
+            StakedStateAddress::BasicRedeem([1u8; 20].into()),
+            StakedStateOpAttributes::new(0),
+            NetworkParameterChange::RequiredCouncilNodeStake(Coin::unit()),
+        );
+        let encoded = proposal.encode();
+        assert_eq!(
+            ParamChangeProposalTx::decode(&mut encoded.as_slice()).unwrap(),
+            proposal
+        );
+
+        let vote = ParamChangeVoteTx::new(
+            2,
+            StakedStateAddress::BasicRedeem([1u8; 20].into()),
+            StakedStateOpAttributes::new(0),
+            proposal.id(),
+            true,
+        );
+        let encoded = vote.encode();
+        assert_eq!(
+            ParamChangeVoteTx::decode(&mut encoded.as_slice()).unwrap(),
+            vote
+        );
+    }
+}
@@ -1,5 +1,7 @@
 /// data types related to staked state operations
 pub mod account;
+/// data types related to council node governance (network parameter change proposals and votes)
+pub mod governance;
 /// data types related to working with Tendermint
 pub mod tendermint;
 /// data types related to council node operations in staked state (nodejoin and unjail)
@@ -16,6 +18,7 @@ use crate::compute_app_hash;
 use crate::init::coin::Coin;
 use crate::init::params::NetworkParameters;
 use crate::tx::data::TxId;
+use crate::tx::fee::Milli;
 
 /// ABCI chain state
 #[derive(PartialEq, Debug, Clone, Encode, Decode)]
@@ -27,18 +30,36 @@ pub struct ChainState {
     pub rewards_pool: RewardsPoolState,
     /// network parameters (fee policy, staking configuration etc.)
     pub network_params: NetworkParameters,
+    /// hash of the public TDBE MLS group state (zeros before the group is created)
+    pub mls_group_hash: H256,
+    /// multiplier of the fee policy, adjusted by the size of each block
+    pub base_fee: Milli,
+    /// hash of the stake transfers that can still be slashed together with the source
+    /// (zeros if there are none)
+    pub stake_transfers_hash: H256,
 }
 
 impl ChainState {
+    /// the state at genesis (before any transaction was processed)
+    pub fn genesis(
+        account_root: H256,
+        rewards_pool: RewardsPoolState,
+        network_params: NetworkParameters,
+    ) -> Self {
+        ChainState {
+            account_root,
+            rewards_pool,
+            network_params,
+            mls_group_hash: [0u8; 32],
+            base_fee: Milli::new(1, 0),
+            stake_transfers_hash: [0u8; 32],
+        }
+    }
+
     /// computes the app hash based on the internal parameters
     /// identifiers of valid transactions in a given block
     pub fn compute_app_hash(&self, txids: Vec<TxId>) -> H256 {
-        compute_app_hash(
-            &MerkleTree::new(txids),
-            &self.account_root,
-            &self.rewards_pool,
-            &self.network_params,
-        )
+        compute_app_hash(&MerkleTree::new(txids), self)
     }
 }
 
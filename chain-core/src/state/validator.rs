@@ -1,5 +1,7 @@
+mod metadata_update;
 mod nodejoin;
 mod unjail;
 
+pub use metadata_update::NodeMetadataUpdateTx;
 pub use nodejoin::NodeJoinRequestTx;
 pub use unjail::UnjailTx;
@@ -0,0 +1,152 @@
+use crate::state::account::{
+    decode_name_security_contact, NodeName, NodeSecurityContact, Nonce, StakedStateAddress,
+    StakedStateOpAttributes,
+};
+use crate::state::tendermint::TendermintValidatorPubKey;
+use crate::tx::TransactionId;
+use parity_scale_codec::{Decode, Encode, Error, Input, Output};
+#[cfg(not(feature = "mesalock_sgx"))]
+use serde::{Deserialize, Serialize};
+#[cfg(not(feature = "mesalock_sgx"))]
+use std::fmt;
+
+/// Updates the metadata of an existing council node:
+/// - the informational parts (name, security contact) are replaced
+/// - if `consensus_pubkey` is provided, the consensus key is rotated
+///   (the new key is used from the next validator set update)
+///
+/// tx-validation should check that:
+/// - the staked state has a council node record and it isn't jailed
+/// - the new consensus_pubkey (if any) is not used
+/// - the witness is correct
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(not(feature = "mesalock_sgx"), derive(Serialize, Deserialize))]
+pub struct NodeMetadataUpdateTx {
+    /// the expected nonce on the corresponding state
+    pub nonce: Nonce,
+    /// the expected address on the corresponding state
+    pub address: StakedStateAddress,
+    /// the versioning and network identifier
+    pub attributes: StakedStateOpAttributes,
+    /// new name / moniker
+    pub name: NodeName,
+    /// new (optional) security@... email address
+    pub security_contact: NodeSecurityContact,
+    /// new Tendermint consensus validator-associated public key
+    /// (None keeps the current one)
+    pub consensus_pubkey: Option<TendermintValidatorPubKey>,
+}
+
+impl Decode for NodeMetadataUpdateTx {
+    fn decode<I: Input>(input: &mut I) -> Result<Self, Error> {
+        let nonce = Nonce::decode(input)?;
+        let address = StakedStateAddress::decode(input)?;
+        let attributes = StakedStateOpAttributes::decode(input)?;
+        let (name, security_contact) = decode_name_security_contact(input)?;
+        let consensus_pubkey = Option::<TendermintValidatorPubKey>::decode(input)?;
+
+        Ok(NodeMetadataUpdateTx {
+            nonce,
+            address,
+            attributes,
+            name,
+            security_contact,
+            consensus_pubkey,
+        })
+    }
+}
+
+impl Encode for NodeMetadataUpdateTx {
+    fn encode_to<EncOut: Output>(&self, dest: &mut EncOut) {
+        dest.push(&self.nonce);
+        dest.push(&self.address);
+        dest.push(&self.attributes);
+        dest.push(&self.name);
+        dest.push(&self.security_contact);
+        dest.push(&self.consensus_pubkey);
+    }
+
+    fn size_hint(&self) -> usize {
+        self.nonce.size_hint()
+            + self.address.size_hint()
+            + self.attributes.size_hint()
+            + self.name.size_hint()
+            + self.security_contact.size_hint()
+            + self.consensus_pubkey.size_hint()
+    }
+}
+
+impl TransactionId for NodeMetadataUpdateTx {}
+
+impl NodeMetadataUpdateTx {
+    /// constructs a new node metadata update transaction from the provided components
+    #[inline]
+    pub fn new(
+        nonce: Nonce,
+        address: StakedStateAddress,
+        attributes: StakedStateOpAttributes,
+        name: NodeName,
+        security_contact: NodeSecurityContact,
+        consensus_pubkey: Option<TendermintValidatorPubKey>,
+    ) -> Self {
+        Self {
+            nonce,
+            address,
+            attributes,
+            name,
+            security_contact,
+            consensus_pubkey,
+        }
+    }
+}
+
+#[cfg(not(feature = "mesalock_sgx"))]
+impl fmt::Display for NodeMetadataUpdateTx {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "address: {} (nonce: {}) updates node metadata: {}",
+            self.address, self.nonce, self.name
+        )?;
+        if let Some(pubkey) = &self.consensus_pubkey {
+            writeln!(f, "new consensus key: {}", pubkey)?;
+        }
+        write!(f, "")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_encode_decode() {
+        let tx = NodeMetadataUpdateTx::new(
+            1,
+            StakedStateAddress::BasicRedeem([1u8; 20].into()),
+            StakedStateOpAttributes::new(0),
+            "new name".to_owned(),
+            Some("security@example.com".to_owned()),
+            Some(TendermintValidatorPubKey::Ed25519([2u8; 32])),
+        );
+        let encoded = tx.encode();
+        assert_eq!(
+            NodeMetadataUpdateTx::decode(&mut encoded.as_slice()).unwrap(),
+            tx
+        );
+    }
+
+    #[test]
+    fn check_long_name_is_rejected() {
+        let tx = NodeMetadataUpdateTx::new(
+            1,
+            StakedStateAddress::BasicRedeem([1u8; 20].into()),
+            StakedStateOpAttributes::new(0),
+            "a".repeat(256),
+            None,
+            None,
+        );
+        let encoded = tx.encode();
+        assert!(NodeMetadataUpdateTx::decode(&mut encoded.as_slice()).is_err());
+    }
+}
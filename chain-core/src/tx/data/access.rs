@@ -10,17 +10,50 @@ use serde::de;
 #[cfg(not(feature = "mesalock_sgx"))]
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
+use std::prelude::v1::Vec;
+
 use crate::common::H264;
+use crate::tx::data::input::TxoSize;
 
 /// What can be accessed in TX (enforced by enclave code in HW)
-/// Initial schema will only allow access to all TX data,
-/// but this may later be extended to restrict to e.g. particular tx outputs
-/// or metadata
 #[derive(Debug, PartialEq, Eq, Clone, PartialOrd, Ord)]
 #[cfg_attr(not(feature = "mesalock_sgx"), derive(Serialize, Deserialize))]
 pub enum TxAccess {
     /// everything can be accessed
     AllData,
+    /// only the outputs at the given indices (+ metadata) can be accessed
+    Outputs(Vec<TxoSize>),
+    /// only the metadata (transaction id, number of inputs and outputs) can be accessed
+    /// -- the fee isn't included (it's public in the block results events anyway)
+    Metadata,
+}
+
+impl TxAccess {
+    /// merges two access rules for the same view key:
+    /// `AllData` takes precedence, output indices are joined
+    pub fn merge(self, other: TxAccess) -> TxAccess {
+        match (self, other) {
+            (TxAccess::AllData, _) | (_, TxAccess::AllData) => TxAccess::AllData,
+            (TxAccess::Outputs(mut a), TxAccess::Outputs(b)) => {
+                a.extend(b);
+                a.sort_unstable();
+                a.dedup();
+                TxAccess::Outputs(a)
+            }
+            (TxAccess::Outputs(a), TxAccess::Metadata)
+            | (TxAccess::Metadata, TxAccess::Outputs(a)) => TxAccess::Outputs(a),
+            (TxAccess::Metadata, TxAccess::Metadata) => TxAccess::Metadata,
+        }
+    }
+
+    /// if the output at the given index can be accessed
+    pub fn allows_output(&self, index: TxoSize) -> bool {
+        match self {
+            TxAccess::AllData => true,
+            TxAccess::Outputs(indices) => indices.contains(&index),
+            TxAccess::Metadata => false,
+        }
+    }
 }
 
 impl Default for TxAccess {
@@ -32,9 +65,10 @@ impl Default for TxAccess {
 impl Decode for TxAccess {
     fn decode<DecIn: Input>(input: &mut DecIn) -> Result<Self, Error> {
         let tag = input.read_byte()?;
-        // NOTE: tag 1 likely reserved for txout restriction
         match tag {
             0 => Ok(TxAccess::AllData),
+            1 => Ok(TxAccess::Outputs(Vec::decode(input)?)),
+            2 => Ok(TxAccess::Metadata),
             _ => Err("No such variant in enum TxAccess".into()),
         }
     }
@@ -46,12 +80,21 @@ impl Encode for TxAccess {
             TxAccess::AllData => {
                 dest.push_byte(0);
             }
+            TxAccess::Outputs(ref indices) => {
+                dest.push_byte(1);
+                dest.push(indices);
+            }
+            TxAccess::Metadata => {
+                dest.push_byte(2);
+            }
         }
     }
 
     fn size_hint(&self) -> usize {
-        // NOTE: revisit if extended
-        1
+        1 + match self {
+            TxAccess::Outputs(indices) => indices.size_hint(),
+            _ => 0,
+        }
     }
 }
 
@@ -139,3 +182,62 @@ impl TxAccessPolicy {
         TxAccessPolicy { view_key, access }
     }
 }
+
+/// returns what the view key can access in a transaction with the given policies
+/// (None if the view key isn't in any of them)
+pub fn get_access(allowed_views: &[TxAccessPolicy], view_key: &PublicKey) -> Option<TxAccess> {
+    allowed_views
+        .iter()
+        .filter(|x| x.view_key == *view_key)
+        .map(|x| x.access.clone())
+        .fold(None, |acc, access| match acc {
+            None => Some(access),
+            Some(acc) => Some(acc.merge(access)),
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use secp256k1::{key::SecretKey, Secp256k1};
+
+    fn view_key(b: u8) -> PublicKey {
+        let secp = Secp256k1::new();
+        PublicKey::from_secret_key(&secp, &SecretKey::from_slice(&[b; 32]).unwrap())
+    }
+
+    #[test]
+    fn check_access_encode_decode() {
+        for access in vec![
+            TxAccess::AllData,
+            TxAccess::Outputs(vec![0, 2]),
+            TxAccess::Metadata,
+        ] {
+            let policy = TxAccessPolicy::new(view_key(1), access);
+            let encoded = policy.encode();
+            let decoded = TxAccessPolicy::decode(&mut encoded.as_slice()).unwrap();
+            assert_eq!(decoded, policy);
+        }
+    }
+
+    #[test]
+    fn check_get_access_merges_policies() {
+        let policies = vec![
+            TxAccessPolicy::new(view_key(1), TxAccess::Outputs(vec![1])),
+            TxAccessPolicy::new(view_key(1), TxAccess::Outputs(vec![0, 1])),
+            TxAccessPolicy::new(view_key(2), TxAccess::Metadata),
+            TxAccessPolicy::new(view_key(3), TxAccess::Outputs(vec![1])),
+            TxAccessPolicy::new(view_key(3), TxAccess::AllData),
+        ];
+        assert_eq!(
+            get_access(&policies, &view_key(1)),
+            Some(TxAccess::Outputs(vec![0, 1]))
+        );
+        assert_eq!(
+            get_access(&policies, &view_key(2)),
+            Some(TxAccess::Metadata)
+        );
+        assert_eq!(get_access(&policies, &view_key(3)), Some(TxAccess::AllData));
+        assert_eq!(get_access(&policies, &view_key(4)), None);
+    }
+}
@@ -16,6 +16,12 @@ use bech32::{self, u5, FromBase32, ToBase32};
 use crate::init::network::{get_bech32_human_part_from_network, get_network, Network};
 
 type TreeRoot = H256;
+type ContractHash = H256;
+
+/// the first byte of the textual (bech32) HTLC address payload
+/// (tree addresses are encoded as the plain 32-byte root)
+#[cfg(not(feature = "mesalock_sgx"))]
+const HTLC_ADDRESS_TAG: u8 = 1;
 
 /// MAST of Or operations (records the root).
 /// Root of a Merkle tree where leafs are X-only
@@ -27,6 +33,9 @@ pub enum ExtendedAddr {
     /// but each operation is "OR"
     /// (root of such tree)
     OrTree(TreeRoot),
+    /// hash-time-locked contract
+    /// (hash of `HashTimeLock`, the contract is revealed in `TxInWitness::HashTimeLock`)
+    HashTimeLock(ContractHash),
 }
 
 impl Encode for ExtendedAddr {
@@ -36,12 +45,17 @@ impl Encode for ExtendedAddr {
                 dest.push_byte(0);
                 dest.push(aa);
             }
+            ExtendedAddr::HashTimeLock(ref contract_hash) => {
+                dest.push_byte(1);
+                dest.push(contract_hash);
+            }
         }
     }
 
     fn size_hint(&self) -> usize {
         (match self {
             ExtendedAddr::OrTree(ref aa) => aa.size_hint(),
+            ExtendedAddr::HashTimeLock(ref contract_hash) => contract_hash.size_hint(),
         }) + 1
     }
 }
@@ -49,13 +63,14 @@ impl Encode for ExtendedAddr {
 impl Decode for ExtendedAddr {
     fn decode<DecIn: Input>(input: &mut DecIn) -> Result<Self, Error> {
         let tag = input.read_byte()?;
-        // NOTE: tag 1 may be used for other address types -- e.g. one to denote
-        // requiring a different witness type (leaf may be a combination of root + timelock)
+        // NOTE: other tags may be used for other address types
+        // (requiring different witness types)
         match tag {
             0 => Ok(ExtendedAddr::OrTree({
                 let address: TreeRoot = Decode::decode(input)?;
                 address
             })),
+            1 => Ok(ExtendedAddr::HashTimeLock(ContractHash::decode(input)?)),
             _ => Err("No such variant in enum ExtendedAddr".into()),
         }
     }
@@ -64,15 +79,18 @@ impl Decode for ExtendedAddr {
 #[cfg(not(feature = "mesalock_sgx"))]
 impl CroAddress<ExtendedAddr> for ExtendedAddr {
     fn to_cro(&self, network: Network) -> Result<String, CroAddressError> {
-        match self {
-            ExtendedAddr::OrTree(hash) => {
-                let checked_data: Vec<u5> = hash.to_vec().to_base32();
-                let encoded =
-                    bech32::encode(get_bech32_human_part_from_network(network), checked_data)
-                        .expect("bech32 encoding error");
-                Ok(encoded)
+        let payload = match self {
+            ExtendedAddr::OrTree(hash) => hash.to_vec(),
+            ExtendedAddr::HashTimeLock(hash) => {
+                let mut payload = vec![HTLC_ADDRESS_TAG];
+                payload.extend_from_slice(hash);
+                payload
             }
-        }
+        };
+        let checked_data: Vec<u5> = payload.to_base32();
+        let encoded = bech32::encode(get_bech32_human_part_from_network(network), checked_data)
+            .expect("bech32 encoding error");
+        Ok(encoded)
     }
 
     fn from_cro(encoded_addr: &str, network: Network) -> Result<Self, CroAddressError> {
@@ -85,10 +103,19 @@ impl CroAddress<ExtendedAddr> for ExtendedAddr {
             .and_then(|decoded| {
                 Vec::from_base32(&decoded.1).map_err(|_e| CroAddressError::ConvertError)
             })
-            .map(|hash| {
-                let mut tree_root_hash: TreeRoot = [0 as u8; 32];
-                tree_root_hash.copy_from_slice(&hash.as_slice());
-                ExtendedAddr::OrTree(tree_root_hash)
+            .and_then(|payload| {
+                let mut hash: H256 = [0 as u8; 32];
+                match payload.split_first() {
+                    Some((&HTLC_ADDRESS_TAG, contract_hash)) if contract_hash.len() == 32 => {
+                        hash.copy_from_slice(contract_hash);
+                        Ok(ExtendedAddr::HashTimeLock(hash))
+                    }
+                    _ if payload.len() == 32 => {
+                        hash.copy_from_slice(&payload);
+                        Ok(ExtendedAddr::OrTree(hash))
+                    }
+                    _ => Err(CroAddressError::ConvertError),
+                }
             })
     }
 }
@@ -172,4 +199,20 @@ mod test {
             assert!(result.is_ok());
         }
     }
+
+    #[test]
+    fn should_be_correct_htlc_address() {
+        let network = Network::Devnet;
+        let extended_addr = ExtendedAddr::HashTimeLock([0xaa; 32]);
+        let bech32_addr = extended_addr.to_cro(network).unwrap();
+        let restored_extended_addr = ExtendedAddr::from_cro(&bech32_addr, network).unwrap();
+        assert_eq!(restored_extended_addr, extended_addr);
+
+        let encoded = extended_addr.encode();
+        assert_eq!(encoded.len(), extended_addr.size_hint());
+        assert_eq!(
+            ExtendedAddr::decode(&mut encoded.as_slice()).unwrap(),
+            extended_addr
+        );
+    }
 }
@@ -0,0 +1,98 @@
+use parity_scale_codec::{Decode, Encode};
+#[cfg(not(feature = "mesalock_sgx"))]
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::common::{Timespec, H256};
+use crate::tx::data::address::ExtendedAddr;
+use crate::tx::witness::tree::RawXOnlyPubkey;
+
+/// The secret revealed by the recipient when claiming the locked funds
+pub type HashPreimage = H256;
+
+/// Hash-time-locked contract (e.g. for cross-chain atomic swaps):
+/// the output can be spent either by the recipient revealing the preimage of the hash lock,
+/// or by the refund key once the block time reaches the timeout.
+///
+/// The address only commits to the contract hash (see `ExtendedAddr::HashTimeLock`),
+/// the full contract is revealed in the witness.
+#[derive(Debug, PartialEq, Eq, Clone, Encode, Decode)]
+#[cfg_attr(not(feature = "mesalock_sgx"), derive(Serialize, Deserialize))]
+pub struct HashTimeLock {
+    /// SHA-256 of the preimage (the same hash function as on the other chains of the swap)
+    pub hash_lock: H256,
+    /// key that can claim the funds with the preimage
+    pub recipient: RawXOnlyPubkey,
+    /// key that can get the funds back after the timeout
+    pub refund: RawXOnlyPubkey,
+    /// block time (seconds since UNIX epoch) from which the refund is possible
+    pub timeout: Timespec,
+}
+
+impl HashTimeLock {
+    /// creates the contract from the hash lock, recipient / refund keys and the refund timeout
+    pub fn new(
+        hash_lock: H256,
+        recipient: RawXOnlyPubkey,
+        refund: RawXOnlyPubkey,
+        timeout: Timespec,
+    ) -> Self {
+        HashTimeLock {
+            hash_lock,
+            recipient,
+            refund,
+            timeout,
+        }
+    }
+
+    /// hash committed to in the address
+    pub fn contract_hash(&self) -> H256 {
+        blake3::hash(&self.encode()).into()
+    }
+
+    /// the address to send the locked funds to
+    pub fn address(&self) -> ExtendedAddr {
+        ExtendedAddr::HashTimeLock(self.contract_hash())
+    }
+
+    /// if the preimage matches the hash lock
+    pub fn check_preimage(&self, preimage: &HashPreimage) -> bool {
+        hash_preimage(preimage) == self.hash_lock
+    }
+}
+
+/// computes the hash lock for the given preimage
+pub fn hash_preimage(preimage: &HashPreimage) -> H256 {
+    Sha256::digest(preimage).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn contract() -> HashTimeLock {
+        HashTimeLock::new(
+            hash_preimage(&[1u8; 32]),
+            RawXOnlyPubkey::from([2u8; 32]),
+            RawXOnlyPubkey::from([3u8; 32]),
+            100,
+        )
+    }
+
+    #[test]
+    fn check_encode_decode() {
+        let htlc = contract();
+        let encoded = htlc.encode();
+        assert_eq!(HashTimeLock::decode(&mut encoded.as_slice()).unwrap(), htlc);
+    }
+
+    #[test]
+    fn check_preimage() {
+        let htlc = contract();
+        assert!(htlc.check_preimage(&[1u8; 32]));
+        assert!(!htlc.check_preimage(&[2u8; 32]));
+        let mut other = contract();
+        other.timeout = 101;
+        assert_ne!(htlc.address(), other.address());
+    }
+}
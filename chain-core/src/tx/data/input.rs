@@ -10,6 +10,7 @@ use serde::de::{
 #[cfg(not(feature = "mesalock_sgx"))]
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
+use crate::state::tendermint::BlockHeight;
 use crate::tx::data::TxId;
 
 /// the type for transaction output size or index
@@ -54,8 +55,27 @@ impl Decode for TxoPointer {
     }
 }
 
+/// The transaction which spent an output and the height of the block which included it
+/// (only recorded by the nodes with the spent output index enabled)
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Encode, Decode)]
+#[cfg_attr(not(feature = "mesalock_sgx"), derive(Serialize, Deserialize))]
+pub struct TxoSpent {
+    /// the spending transaction identifier
+    #[cfg_attr(
+        not(feature = "mesalock_sgx"),
+        serde(serialize_with = "serialize_transaction_id")
+    )]
+    #[cfg_attr(
+        not(feature = "mesalock_sgx"),
+        serde(deserialize_with = "deserialize_transaction_id")
+    )]
+    pub spent_by: TxId,
+    /// height of the block which included the spending transaction
+    pub block_height: BlockHeight,
+}
+
 #[cfg(not(feature = "mesalock_sgx"))]
-fn serialize_transaction_id<S>(
+pub(crate) fn serialize_transaction_id<S>(
     transaction_id: &TxId,
     serializer: S,
 ) -> std::result::Result<S::Ok, S::Error>
@@ -66,7 +86,9 @@ where
 }
 
 #[cfg(not(feature = "mesalock_sgx"))]
-fn deserialize_transaction_id<'de, D>(deserializer: D) -> std::result::Result<TxId, D::Error>
+pub(crate) fn deserialize_transaction_id<'de, D>(
+    deserializer: D,
+) -> std::result::Result<TxId, D::Error>
 where
     D: Deserializer<'de>,
 {
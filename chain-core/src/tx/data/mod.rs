@@ -2,10 +2,12 @@ use std::prelude::v1::Vec;
 
 /// For specifying access control to TX data
 pub mod access;
-/// Different address types (Redeem and Tree/MAST)
+/// Different address types (Redeem, Tree/MAST and HTLC)
 pub mod address;
 /// Miscellaneous TX attributes, such as network ID
 pub mod attribute;
+/// Hash-time-locked contracts (for atomic swaps)
+pub mod htlc;
 /// Transaction inputs (pointers to previous transaction outputs)
 pub mod input;
 /// Transaction outputs (amount to an address)
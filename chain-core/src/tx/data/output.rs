@@ -11,8 +11,127 @@ use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
 use crate::common::Timespec;
 use crate::init::coin::Coin;
+use crate::state::tendermint::BlockHeight;
 use crate::tx::data::address::ExtendedAddr;
 
+/// Condition on when the output can be spent
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(not(feature = "mesalock_sgx"), derive(Serialize, Deserialize))]
+pub enum Timelock {
+    /// spendable from the given block time (seconds since UNIX epoch)
+    Time(Timespec),
+    /// spendable from the given block height
+    Height(BlockHeight),
+    /// spendable N seconds after the block time of the transaction which created the output
+    RelativeTime(Timespec),
+    /// spendable N blocks after the block height of the transaction which created the output
+    RelativeHeight(u64),
+}
+
+impl Timelock {
+    /// if the output can be spent in the given block
+    /// (`included` is when the transaction which created the output was included in the chain)
+    pub fn is_unlocked(
+        &self,
+        block_time: Timespec,
+        block_height: BlockHeight,
+        included: &TxInclusion,
+    ) -> bool {
+        match *self {
+            Timelock::Time(valid_from) => valid_from <= block_time,
+            Timelock::Height(valid_from) => valid_from <= block_height,
+            Timelock::RelativeTime(seconds) => {
+                included.block_time.saturating_add(seconds) <= block_time
+            }
+            Timelock::RelativeHeight(blocks) => {
+                included.block_height.saturating_add(blocks) <= block_height
+            }
+        }
+    }
+
+    /// if the lock depends on when the transaction which created the output was included
+    pub fn is_relative(&self) -> bool {
+        match self {
+            Timelock::Time(_) | Timelock::Height(_) => false,
+            Timelock::RelativeTime(_) | Timelock::RelativeHeight(_) => true,
+        }
+    }
+}
+
+#[cfg(not(feature = "mesalock_sgx"))]
+impl fmt::Display for Timelock {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Timelock::Time(valid_from) => write!(f, "time {}", valid_from),
+            Timelock::Height(valid_from) => write!(f, "height {}", valid_from),
+            Timelock::RelativeTime(seconds) => write!(f, "{} seconds after inclusion", seconds),
+            Timelock::RelativeHeight(blocks) => write!(f, "{} blocks after inclusion", blocks),
+        }
+    }
+}
+
+/// When the transaction which created an output was included in the chain
+/// (needed for checking the relative timelocks)
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Encode, Decode)]
+#[cfg_attr(not(feature = "mesalock_sgx"), derive(Serialize, Deserialize))]
+pub struct TxInclusion {
+    /// time of the block which included the transaction
+    pub block_time: Timespec,
+    /// height of the block which included the transaction
+    pub block_height: BlockHeight,
+}
+
+impl TxInclusion {
+    /// creates the inclusion info
+    pub fn new(block_time: Timespec, block_height: BlockHeight) -> Self {
+        TxInclusion {
+            block_time,
+            block_height,
+        }
+    }
+}
+
+// The timelock is encoded as `Option<Timespec>` was before the other lock types were added:
+// 0 = no lock, 1 = absolute time lock, the other lock types use the following tags
+const TIMELOCK_NONE: u8 = 0;
+const TIMELOCK_TIME: u8 = 1;
+const TIMELOCK_HEIGHT: u8 = 2;
+const TIMELOCK_RELATIVE_TIME: u8 = 3;
+const TIMELOCK_RELATIVE_HEIGHT: u8 = 4;
+
+fn encode_timelock<EncOut: Output>(timelock: &Option<Timelock>, dest: &mut EncOut) {
+    match timelock {
+        None => dest.push_byte(TIMELOCK_NONE),
+        Some(Timelock::Time(valid_from)) => {
+            dest.push_byte(TIMELOCK_TIME);
+            dest.push(valid_from);
+        }
+        Some(Timelock::Height(valid_from)) => {
+            dest.push_byte(TIMELOCK_HEIGHT);
+            dest.push(valid_from);
+        }
+        Some(Timelock::RelativeTime(seconds)) => {
+            dest.push_byte(TIMELOCK_RELATIVE_TIME);
+            dest.push(seconds);
+        }
+        Some(Timelock::RelativeHeight(blocks)) => {
+            dest.push_byte(TIMELOCK_RELATIVE_HEIGHT);
+            dest.push(blocks);
+        }
+    }
+}
+
+fn decode_timelock<DecIn: Input>(input: &mut DecIn) -> Result<Option<Timelock>, Error> {
+    match input.read_byte()? {
+        TIMELOCK_NONE => Ok(None),
+        TIMELOCK_TIME => Ok(Some(Timelock::Time(Timespec::decode(input)?))),
+        TIMELOCK_HEIGHT => Ok(Some(Timelock::Height(BlockHeight::decode(input)?))),
+        TIMELOCK_RELATIVE_TIME => Ok(Some(Timelock::RelativeTime(Timespec::decode(input)?))),
+        TIMELOCK_RELATIVE_HEIGHT => Ok(Some(Timelock::RelativeHeight(u64::decode(input)?))),
+        _ => Err("No such variant in enum Timelock".into()),
+    }
+}
+
 /// Tx Output composed of an address and a coin value
 #[derive(Debug, PartialEq, Eq, Clone)]
 #[cfg_attr(not(feature = "mesalock_sgx"), derive(Serialize, Deserialize))]
@@ -30,18 +149,20 @@ pub struct TxOut {
     /// the amount to lock in base units
     pub value: Coin,
     /// the optional timelock
-    pub valid_from: Option<Timespec>,
+    pub valid_from: Option<Timelock>,
 }
 
 impl Encode for TxOut {
     fn encode_to<EncOut: Output>(&self, dest: &mut EncOut) {
         dest.push(&self.address);
         dest.push(&self.value);
-        dest.push(&self.valid_from);
+        encode_timelock(&self.valid_from, dest);
     }
 
     fn size_hint(&self) -> usize {
-        self.address.size_hint() + self.value.size_hint() + self.valid_from.size_hint()
+        // the lock tag + 8 bytes of any lock type
+        let timelock_size = if self.valid_from.is_some() { 9 } else { 1 };
+        self.address.size_hint() + self.value.size_hint() + timelock_size
     }
 }
 
@@ -55,7 +176,7 @@ impl Decode for TxOut {
         // but one needs to careful that "None" isn't required to be encoded
         let address = ExtendedAddr::decode(input)?;
         let value = Coin::decode(input)?;
-        let valid_from: Option<Timespec> = Option::decode(input)?;
+        let valid_from = decode_timelock(input)?;
         Ok(TxOut {
             address,
             value,
@@ -120,10 +241,77 @@ impl TxOut {
 
     /// creates a TX output with timelock
     pub fn new_with_timelock(address: ExtendedAddr, value: Coin, valid_from: Timespec) -> Self {
+        Self::new_with_lock(address, value, Timelock::Time(valid_from))
+    }
+
+    /// creates a TX output with any kind of lock (height-based, relative...)
+    pub fn new_with_lock(address: ExtendedAddr, value: Coin, valid_from: Timelock) -> Self {
         TxOut {
             address,
             value,
             valid_from: Some(valid_from),
         }
     }
+
+    /// if the output can be spent in the given block
+    /// (`included` is when the transaction which created the output was included in the chain)
+    pub fn is_spendable(
+        &self,
+        block_time: Timespec,
+        block_height: BlockHeight,
+        included: &TxInclusion,
+    ) -> bool {
+        self.valid_from.map_or(true, |lock| {
+            lock.is_unlocked(block_time, block_height, included)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn address() -> ExtendedAddr {
+        ExtendedAddr::OrTree([0xaa; 32])
+    }
+
+    #[test]
+    fn check_time_lock_encoding_is_compatible() {
+        let output = TxOut::new_with_timelock(address(), Coin::unit(), 10);
+        let mut legacy = address().encode();
+        legacy.extend(Coin::unit().encode());
+        legacy.extend(Some(10 as Timespec).encode());
+        assert_eq!(output.encode(), legacy);
+        assert_eq!(TxOut::decode(&mut legacy.as_slice()).unwrap(), output);
+    }
+
+    #[test]
+    fn check_lock_encode_decode() {
+        for lock in [
+            Timelock::Height(5.into()),
+            Timelock::RelativeTime(60),
+            Timelock::RelativeHeight(3),
+        ]
+        .iter()
+        {
+            let output = TxOut::new_with_lock(address(), Coin::unit(), *lock);
+            let encoded = output.encode();
+            assert_eq!(encoded.len(), output.size_hint());
+            assert_eq!(TxOut::decode(&mut encoded.as_slice()).unwrap(), output);
+        }
+    }
+
+    #[test]
+    fn check_relative_locks() {
+        let included = TxInclusion::new(100, 10.into());
+        let output = TxOut::new_with_lock(address(), Coin::unit(), Timelock::RelativeTime(60));
+        assert!(!output.is_spendable(159, 20.into(), &included));
+        assert!(output.is_spendable(160, 20.into(), &included));
+        let output = TxOut::new_with_lock(address(), Coin::unit(), Timelock::RelativeHeight(5));
+        assert!(!output.is_spendable(1000, 14.into(), &included));
+        assert!(output.is_spendable(1000, 15.into(), &included));
+        let output = TxOut::new_with_lock(address(), Coin::unit(), Timelock::Height(12.into()));
+        assert!(!output.is_spendable(1000, 11.into(), &included));
+        assert!(TxOut::new(address(), Coin::unit()).is_spendable(0, 0.into(), &included));
+    }
 }
@@ -8,6 +8,7 @@ use crate::tx::TxAux;
 use parity_scale_codec::{Decode, Encode};
 #[cfg(not(feature = "mesalock_sgx"))]
 use serde::{Deserialize, Serialize};
+use std::convert::TryFrom;
 use std::num::ParseIntError;
 use std::ops::{Add, Div, Mul};
 use std::prelude::v1::Vec;
@@ -104,6 +105,18 @@ impl Milli {
     pub fn from_millis(millis: u64) -> Milli {
         Milli(millis)
     }
+
+    /// adds the numbers, returns None if the result overflows
+    #[inline]
+    pub fn checked_add(self, other: Milli) -> Option<Milli> {
+        self.0.checked_add(other.0).map(Milli)
+    }
+
+    /// multiplies the numbers, returns None if the result overflows
+    pub fn checked_mul(self, other: Milli) -> Option<Milli> {
+        let v = u128::from(self.0) * u128::from(other.0) / 1000;
+        u64::try_from(v).ok().map(Milli)
+    }
 }
 
 /// Errors from parsing 3 digit fixed decimal
@@ -251,6 +264,92 @@ impl FeeAlgorithm for LinearFee {
     }
 }
 
+/// How soon a transaction is expected to be included in a block
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+#[cfg_attr(not(feature = "mesalock_sgx"), derive(Serialize, Deserialize))]
+#[cfg_attr(not(feature = "mesalock_sgx"), serde(rename_all = "lowercase"))]
+pub enum InclusionSpeed {
+    /// pays the current minimal fee
+    Slow,
+    /// pays enough for the next block even if the base fee goes up
+    Normal,
+    /// pays enough for a few more blocks of rising base fee
+    /// (the extra fee gives the transaction a higher priority)
+    Fast,
+}
+
+impl InclusionSpeed {
+    /// number of blocks (with the base fee growing at the max rate) the fee is suggested for
+    pub fn blocks(self) -> u32 {
+        match self {
+            InclusionSpeed::Slow => 0,
+            InclusionSpeed::Normal => 1,
+            InclusionSpeed::Fast => 3,
+        }
+    }
+}
+
+impl FromStr for InclusionSpeed {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "slow" => Ok(InclusionSpeed::Slow),
+            "normal" => Ok(InclusionSpeed::Normal),
+            "fast" => Ok(InclusionSpeed::Fast),
+            _ => Err("inclusion speed should be one of: slow, normal, fast"),
+        }
+    }
+}
+
+/// Linear fee multiplied by the dynamic base fee
+/// (which goes up or down with the fullness of the previous blocks)
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub struct DynamicFee {
+    /// the fee policy from the network parameters
+    pub linear: LinearFee,
+    /// the base fee multiplier (>= 1.0)
+    pub base_fee: Milli,
+}
+
+impl DynamicFee {
+    /// initializes the dynamic fee with the current base fee
+    pub fn new(linear: LinearFee, base_fee: Milli) -> Self {
+        DynamicFee { linear, base_fee }
+    }
+
+    /// the fee to pay for the inclusion speed, assuming the base fee
+    /// grows by at most `max_change_rate` after each block
+    pub fn suggest(
+        linear: LinearFee,
+        base_fee: Milli,
+        max_change_rate: Milli,
+        speed: InclusionSpeed,
+    ) -> Self {
+        let base_fee = (0..speed.blocks()).fold(base_fee, |fee, _| fee + fee * max_change_rate);
+        DynamicFee { linear, base_fee }
+    }
+}
+
+impl FeeAlgorithm for DynamicFee {
+    fn calculate_fee(&self, num_bytes: usize) -> Result<Fee, CoinError> {
+        let msz = Milli::integral(num_bytes as u64).map_err(|_| CoinError::Overflow)?;
+        let fee = self
+            .linear
+            .coefficient
+            .checked_mul(msz)
+            .and_then(|fee| fee.checked_add(self.linear.constant))
+            .and_then(|fee| fee.checked_mul(self.base_fee))
+            .ok_or(CoinError::Overflow)?;
+        let coin = Coin::new(fee.to_integral())?;
+        Ok(Fee(coin))
+    }
+
+    fn calculate_for_txaux(&self, txaux: &TxAux) -> Result<Fee, CoinError> {
+        self.calculate_fee(txaux.encode().len())
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -337,6 +436,32 @@ mod test {
         assert!(t.is_err());
     }
 
+    #[test]
+    fn check_dynamic_fee() {
+        let linear = LinearFee::new(Milli::new(1, 0), Milli::new(1, 0));
+        let fee = DynamicFee::new(linear, Milli::new(1, 0));
+        assert_eq!(
+            fee.calculate_fee(9).unwrap(),
+            linear.calculate_fee(9).unwrap()
+        );
+        let fee = DynamicFee::new(linear, Milli::new(1, 500));
+        assert_eq!(
+            fee.calculate_fee(9).unwrap().to_coin(),
+            Coin::new(15).unwrap()
+        );
+
+        let rate = Milli::new(0, 100);
+        let slow = DynamicFee::suggest(linear, Milli::new(1, 0), rate, InclusionSpeed::Slow);
+        let normal = DynamicFee::suggest(linear, Milli::new(1, 0), rate, InclusionSpeed::Normal);
+        let fast = DynamicFee::suggest(linear, Milli::new(1, 0), rate, InclusionSpeed::Fast);
+        assert_eq!(slow.base_fee, Milli::new(1, 0));
+        assert_eq!(normal.base_fee, Milli::new(1, 100));
+        assert_eq!(fast.base_fee, Milli::new(1, 331));
+
+        let fee = DynamicFee::new(linear, Milli::from_millis(u64::MAX));
+        assert_eq!(fee.calculate_fee(1000).unwrap_err(), CoinError::Overflow);
+    }
+
     quickcheck! {
         fn prop_milli_add(n1: u64, n2: u64) -> bool {
             test_milli_add_eq(n1, n2)
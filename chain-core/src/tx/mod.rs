@@ -16,13 +16,15 @@ use self::data::Tx;
 use self::witness::TxWitness;
 use crate::mls::MLSHandshakeAux;
 use crate::state::account::{
-    DepositBondTx, StakedStateOpAttributes, StakedStateOpWitness, UnbondTx, UnjailTx,
-    WithdrawUnbondedTx,
+    DelegateTx, DepositBondTx, StakedStateOpAttributes, StakedStateOpWitness, TransferStakeTx,
+    UnbondTx, UnjailTx, WithdrawUnbondedTx,
 };
+use crate::state::governance::{ParamChangeProposalTx, ParamChangeVoteTx};
 use crate::state::tendermint::BlockHeight;
-use crate::state::validator::NodeJoinRequestTx;
+use crate::state::validator::{NodeJoinRequestTx, NodeMetadataUpdateTx};
 use crate::tx::data::{txid_hash, TxId};
 use aead::Payload;
+use data::access::TxAccess;
 use data::input::{TxoPointer, TxoSize};
 use data::output::TxOut;
 
@@ -54,6 +56,53 @@ impl TxWithOutputs {
             TxWithOutputs::StakeWithdraw(tx) => tx.id(),
         }
     }
+
+    /// returns the number of the transaction's inputs
+    /// (withdrawals have no UTXO inputs)
+    pub fn no_of_inputs(&self) -> usize {
+        match self {
+            TxWithOutputs::Transfer(tx) => tx.inputs.len(),
+            TxWithOutputs::StakeWithdraw(_) => 0,
+        }
+    }
+
+    /// returns the parts of the transaction that can be seen with the given (restricted) access
+    pub fn restricted_view(&self, access: &TxAccess) -> TxRestrictedView {
+        let outputs = self
+            .outputs()
+            .iter()
+            .enumerate()
+            .filter_map(|(i, output)| {
+                let index = i as TxoSize;
+                if access.allows_output(index) {
+                    Some((index, output.clone()))
+                } else {
+                    None
+                }
+            })
+            .collect();
+        TxRestrictedView {
+            txid: self.id(),
+            no_of_inputs: self.no_of_inputs() as u32,
+            no_of_outputs: self.outputs().len() as TxoSize,
+            outputs,
+        }
+    }
+}
+
+/// Transaction data disclosed to a view key with restricted access
+/// (`TxAccess::Outputs` or `TxAccess::Metadata`); the fee can't be computed from it
+/// (the input values aren't disclosed), it's taken from the block results events instead
+#[derive(Debug, PartialEq, Eq, Clone, Encode, Decode)]
+pub struct TxRestrictedView {
+    /// the transaction identifier
+    pub txid: TxId,
+    /// number of inputs
+    pub no_of_inputs: u32,
+    /// number of outputs
+    pub no_of_outputs: TxoSize,
+    /// the disclosed outputs with their indices
+    pub outputs: Vec<(TxoSize, TxOut)>,
 }
 
 #[derive(Debug, PartialEq, Eq, Clone)]
@@ -382,7 +431,7 @@ impl TxEnclaveAux {
 }
 
 /// Transactions that are directly processed in non-enclave execution environment (chain-abci)
-/// TODO/NOTE: other TX types expected -- update of council node metadata, bonus donation, ...
+/// TODO/NOTE: other TX types expected -- bonus donation, ...
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub enum TxPublicAux {
     /// Tx that modifies staked state -- moves some bonded stake into unbonded (witness for staked state)
@@ -391,6 +440,17 @@ pub enum TxPublicAux {
     UnjailTx(UnjailTx, StakedStateOpWitness),
     /// Tx that updates a staked state with node (community or council node) details
     NodeJoinTx(NodeJoinRequestTx, StakedStateOpWitness),
+    /// Tx that updates the council node details (name, security contact, consensus key)
+    NodeMetadataUpdateTx(NodeMetadataUpdateTx, StakedStateOpWitness),
+    /// Tx that moves bonded or unbonded stake between two staked states
+    /// (witnesses for the source and the destination staked state)
+    TransferStakeTx(TransferStakeTx, StakedStateOpWitness, StakedStateOpWitness),
+    /// Tx that submits a network parameter change proposal (witness for council node staked state)
+    ParamChangeProposalTx(ParamChangeProposalTx, StakedStateOpWitness),
+    /// Tx that votes on a network parameter change proposal (witness for council node staked state)
+    ParamChangeVoteTx(ParamChangeVoteTx, StakedStateOpWitness),
+    /// Tx that makes a staked state back a council node (witness for the backing staked state)
+    DelegateTx(DelegateTx, StakedStateOpWitness),
 }
 
 impl Encode for TxPublicAux {
@@ -411,6 +471,32 @@ impl Encode for TxPublicAux {
                 dest.push(tx);
                 dest.push(witness);
             }
+            TxPublicAux::NodeMetadataUpdateTx(ref tx, ref witness) => {
+                dest.push_byte(3);
+                dest.push(tx);
+                dest.push(witness);
+            }
+            TxPublicAux::TransferStakeTx(ref tx, ref from_witness, ref to_witness) => {
+                dest.push_byte(4);
+                dest.push(tx);
+                dest.push(from_witness);
+                dest.push(to_witness);
+            }
+            TxPublicAux::ParamChangeProposalTx(ref tx, ref witness) => {
+                dest.push_byte(5);
+                dest.push(tx);
+                dest.push(witness);
+            }
+            TxPublicAux::ParamChangeVoteTx(ref tx, ref witness) => {
+                dest.push_byte(6);
+                dest.push(tx);
+                dest.push(witness);
+            }
+            TxPublicAux::DelegateTx(ref tx, ref witness) => {
+                dest.push_byte(7);
+                dest.push(tx);
+                dest.push(witness);
+            }
         }
     }
 
@@ -419,6 +505,13 @@ impl Encode for TxPublicAux {
             TxPublicAux::UnbondStakeTx(tx, witness) => tx.size_hint() + witness.size_hint(),
             TxPublicAux::UnjailTx(tx, witness) => tx.size_hint() + witness.size_hint(),
             TxPublicAux::NodeJoinTx(tx, witness) => tx.size_hint() + witness.size_hint(),
+            TxPublicAux::NodeMetadataUpdateTx(tx, witness) => tx.size_hint() + witness.size_hint(),
+            TxPublicAux::TransferStakeTx(tx, from_witness, to_witness) => {
+                tx.size_hint() + from_witness.size_hint() + to_witness.size_hint()
+            }
+            TxPublicAux::ParamChangeProposalTx(tx, witness) => tx.size_hint() + witness.size_hint(),
+            TxPublicAux::ParamChangeVoteTx(tx, witness) => tx.size_hint() + witness.size_hint(),
+            TxPublicAux::DelegateTx(tx, witness) => tx.size_hint() + witness.size_hint(),
         }
     }
 }
@@ -426,7 +519,7 @@ impl Encode for TxPublicAux {
 impl Decode for TxPublicAux {
     fn decode<DecIn: Input>(input: &mut DecIn) -> Result<Self, Error> {
         let tag = input.read_byte()?;
-        // note: 3.. tags reserved for other tx types (node metadata update etc.)
+        // note: 8.. tags reserved for other tx types
         match tag {
             0 => {
                 let tx = UnbondTx::decode(input)?;
@@ -443,6 +536,32 @@ impl Decode for TxPublicAux {
                 let witness = StakedStateOpWitness::decode(input)?;
                 Ok(TxPublicAux::NodeJoinTx(tx, witness))
             }
+            3 => {
+                let tx = NodeMetadataUpdateTx::decode(input)?;
+                let witness = StakedStateOpWitness::decode(input)?;
+                Ok(TxPublicAux::NodeMetadataUpdateTx(tx, witness))
+            }
+            4 => {
+                let tx = TransferStakeTx::decode(input)?;
+                let from_witness = StakedStateOpWitness::decode(input)?;
+                let to_witness = StakedStateOpWitness::decode(input)?;
+                Ok(TxPublicAux::TransferStakeTx(tx, from_witness, to_witness))
+            }
+            5 => {
+                let tx = ParamChangeProposalTx::decode(input)?;
+                let witness = StakedStateOpWitness::decode(input)?;
+                Ok(TxPublicAux::ParamChangeProposalTx(tx, witness))
+            }
+            6 => {
+                let tx = ParamChangeVoteTx::decode(input)?;
+                let witness = StakedStateOpWitness::decode(input)?;
+                Ok(TxPublicAux::ParamChangeVoteTx(tx, witness))
+            }
+            7 => {
+                let tx = DelegateTx::decode(input)?;
+                let witness = StakedStateOpWitness::decode(input)?;
+                Ok(TxPublicAux::DelegateTx(tx, witness))
+            }
             _ => Err("No such variant in enum TxPublicAux".into()),
         }
     }
@@ -455,6 +574,11 @@ impl TxPublicAux {
             TxPublicAux::UnbondStakeTx(tx, _) => tx.id(),
             TxPublicAux::UnjailTx(tx, _) => tx.id(),
             TxPublicAux::NodeJoinTx(tx, _) => tx.id(),
+            TxPublicAux::NodeMetadataUpdateTx(tx, _) => tx.id(),
+            TxPublicAux::TransferStakeTx(tx, _, _) => tx.id(),
+            TxPublicAux::ParamChangeProposalTx(tx, _) => tx.id(),
+            TxPublicAux::ParamChangeVoteTx(tx, _) => tx.id(),
+            TxPublicAux::DelegateTx(tx, _) => tx.id(),
         }
     }
 
@@ -464,6 +588,11 @@ impl TxPublicAux {
             TxPublicAux::UnbondStakeTx(tx, _) => &tx.attributes,
             TxPublicAux::UnjailTx(tx, _) => &tx.attributes,
             TxPublicAux::NodeJoinTx(tx, _) => &tx.attributes,
+            TxPublicAux::NodeMetadataUpdateTx(tx, _) => &tx.attributes,
+            TxPublicAux::TransferStakeTx(tx, _, _) => &tx.attributes,
+            TxPublicAux::ParamChangeProposalTx(tx, _) => &tx.attributes,
+            TxPublicAux::ParamChangeVoteTx(tx, _) => &tx.attributes,
+            TxPublicAux::DelegateTx(tx, _) => &tx.attributes,
         }
     }
 
@@ -607,6 +736,22 @@ impl fmt::Display for TxAux {
             TxAux::PublicTx(TxPublicAux::NodeJoinTx(tx, witness)) => {
                 display_tx_witness(f, tx, witness)
             }
+            TxAux::PublicTx(TxPublicAux::NodeMetadataUpdateTx(tx, witness)) => {
+                display_tx_witness(f, tx, witness)
+            }
+            TxAux::PublicTx(TxPublicAux::TransferStakeTx(tx, from_witness, to_witness)) => {
+                display_tx_witness(f, tx, from_witness)?;
+                writeln!(f, "destination witness: {:?}\n", to_witness)
+            }
+            TxAux::PublicTx(TxPublicAux::ParamChangeProposalTx(tx, witness)) => {
+                display_tx_witness(f, tx, witness)
+            }
+            TxAux::PublicTx(TxPublicAux::ParamChangeVoteTx(tx, witness)) => {
+                display_tx_witness(f, tx, witness)
+            }
+            TxAux::PublicTx(TxPublicAux::DelegateTx(tx, witness)) => {
+                display_tx_witness(f, tx, witness)
+            }
             TxAux::MLSHandshake(_) => {
                 // FIXME
                 writeln!(f, "mls handshake")
@@ -622,6 +767,7 @@ pub mod tests {
     use crate::init::coin::Coin;
     use crate::tx::data::access::{TxAccess, TxAccessPolicy};
     use crate::tx::data::address::ExtendedAddr;
+    use crate::tx::data::htlc::{hash_preimage, HashTimeLock};
     use crate::tx::data::input::TxoPointer;
     use crate::tx::data::output::TxOut;
     use crate::tx::witness::tree::RawXOnlyPubkey;
@@ -637,6 +783,7 @@ pub mod tests {
         // not a valid transaction, only to test enconding-decoding
         let mut tx = Tx::new();
         tx.add_input(TxoPointer::new([0x01; 32], 1));
+        tx.add_input(TxoPointer::new([0x02; 32], 0));
         tx.add_output(TxOut::new(ExtendedAddr::OrTree([0xbb; 32]), Coin::unit()));
         let secp = Secp256k1::new();
         let sk1 = SecretKey::from_slice(&[0xcc; 32][..]).expect("secret key");
@@ -657,10 +804,46 @@ pub mod tests {
             schnorr_sign(&secp, &msg, &sk1, &mut rand::thread_rng()),
             merkle.generate_proof(raw_public_keys[0].clone()).unwrap(),
         );
-        let txa = PlainTxAux::TransferTx(tx, vec![w1].into());
+        let w2 = TxInWitness::HashTimeLock(
+            schnorr_sign(&secp, &msg, &sk1, &mut rand::thread_rng()),
+            HashTimeLock::new(
+                hash_preimage(&[0x07; 32]),
+                raw_public_keys[0].clone(),
+                raw_public_keys[0].clone(),
+                100,
+            ),
+            Some([0x07; 32]),
+        );
+        let txa = PlainTxAux::TransferTx(tx, vec![w1, w2].into());
         let mut encoded: Vec<u8> = txa.encode();
         let mut data: &[u8] = encoded.as_mut();
         let decoded = PlainTxAux::decode(&mut data).expect("decode tx aux");
         assert_eq!(txa, decoded);
     }
+
+    #[test]
+    fn check_restricted_view() {
+        let mut tx = Tx::new();
+        tx.add_input(TxoPointer::new([0x01; 32], 1));
+        tx.add_output(TxOut::new(ExtendedAddr::OrTree([0xaa; 32]), Coin::unit()));
+        tx.add_output(TxOut::new(ExtendedAddr::OrTree([0xbb; 32]), Coin::unit()));
+        let txid = tx.id();
+        let tx = TxWithOutputs::Transfer(tx);
+
+        let view = tx.restricted_view(&TxAccess::Outputs(vec![1]));
+        assert_eq!(view.txid, txid);
+        assert_eq!(view.no_of_inputs, 1);
+        assert_eq!(view.no_of_outputs, 2);
+        assert_eq!(
+            view.outputs,
+            vec![(
+                1,
+                TxOut::new(ExtendedAddr::OrTree([0xbb; 32]), Coin::unit())
+            )]
+        );
+
+        let view = tx.restricted_view(&TxAccess::Metadata);
+        assert_eq!(view.no_of_outputs, 2);
+        assert!(view.outputs.is_empty());
+    }
 }
@@ -8,6 +8,7 @@ use std::prelude::v1::Vec;
 use secp256k1::{self, recovery::RecoverableSignature, schnorrsig::SchnorrSignature};
 
 use crate::common::Proof;
+use crate::tx::data::htlc::{HashPreimage, HashTimeLock};
 use crate::tx::witness::tree::{RawSignature, RawXOnlyPubkey};
 
 /// ETH-style recoverable ECDSA
@@ -101,6 +102,10 @@ pub enum TxInWitness {
     /// BIP340-compatible Schnorr signature
     /// + Merkle proof from the pubkey leaf to the address root
     TreeSig(SchnorrSignature, Proof<RawXOnlyPubkey>),
+    /// BIP340-compatible Schnorr signature + the revealed hash-time-locked contract;
+    /// with the preimage, it's signed by the contract recipient,
+    /// without it, it's signed by the refund key (only valid after the contract timeout)
+    HashTimeLock(SchnorrSignature, HashTimeLock, Option<HashPreimage>),
 }
 
 impl fmt::Display for TxInWitness {
@@ -117,12 +122,21 @@ impl Encode for TxInWitness {
                 schnorrsig.serialize_default().encode_to(dest);
                 proof.encode_to(dest);
             }
+            TxInWitness::HashTimeLock(ref schnorrsig, ref contract, ref preimage) => {
+                dest.push_byte(1);
+                schnorrsig.serialize_default().encode_to(dest);
+                contract.encode_to(dest);
+                preimage.encode_to(dest);
+            }
         }
     }
 
     fn size_hint(&self) -> usize {
         match self {
             TxInWitness::TreeSig(_, ref proof) => 65 + proof.size_hint(),
+            TxInWitness::HashTimeLock(_, ref contract, ref preimage) => {
+                65 + contract.size_hint() + preimage.size_hint()
+            }
         }
     }
 }
@@ -138,6 +152,14 @@ impl Decode for TxInWitness {
                 let proof = Proof::decode(input)?;
                 Ok(TxInWitness::TreeSig(schnorrsig, proof))
             }
+            1 => {
+                let raw_sig = RawSignature::decode(input)?;
+                let schnorrsig = SchnorrSignature::from_default(&raw_sig)
+                    .map_err(|_| Error::from("Unable to parse schnorr signature"))?;
+                let contract = HashTimeLock::decode(input)?;
+                let preimage = Option::<HashPreimage>::decode(input)?;
+                Ok(TxInWitness::HashTimeLock(schnorrsig, contract, preimage))
+            }
             _ => Err(Error::from("Invalid tag")),
         }
     }
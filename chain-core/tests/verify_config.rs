@@ -79,6 +79,8 @@ fn test_verify_test_example_snapshot() {
             monetary_expansion_decay: 999860,
         },
         max_validators: 1,
+        dynamic_fee_config: Default::default(),
+        governance_config: Default::default(),
     };
 
     let config = InitConfig::new(dist.clone(), params.clone(), nodes.clone());
@@ -5,16 +5,22 @@ use parity_scale_codec::{Decode, Encode};
 
 use crate::jellyfish::Version;
 use chain_core::common::H256;
+use chain_core::init::params::{NetworkParameters, NetworkParametersVersion};
+use chain_core::state::account::{SlashRecord, StakedStateAddress};
 use chain_core::state::tendermint::BlockHeight;
 use chain_core::tx::data::{
-    input::{TxoPointer, TxoSize},
+    input::{TxoPointer, TxoSize, TxoSpent},
+    output::TxInclusion,
     TxId,
 };
+use chain_core::AppHashParts;
 
 use super::buffer::{GetKV, StoreKV};
 use super::{
-    LookupItem, StoredChainState, CHAIN_ID_KEY, COL_APP_HASHS, COL_APP_STATES, COL_EXTRA,
-    COL_NODE_INFO, COL_STAKING_VERSIONS, GENESIS_APP_HASH_KEY, LAST_STATE_KEY,
+    LookupItem, StoredChainState, CHAIN_ID_KEY, COL_APP_HASHS, COL_APP_STATES, COL_BLOCK_TXS,
+    COL_EXTRA, COL_NETWORK_PARAMS, COL_NODE_INFO, COL_NODE_STATES, COL_SLASH_HISTORY,
+    COL_STAKING_VERSIONS, COL_TXO_SPENT, COL_TX_HEIGHTS, GENESIS_APP_HASH_KEY, LAST_STATE_KEY,
+    NODE_STATES_KEPT_HEIGHTS, SCHEMA_VERSION_KEY,
 };
 
 pub fn get_last_app_state(db: &impl GetKV) -> Option<Vec<u8>> {
@@ -71,6 +77,24 @@ pub fn get_historical_staking_version(db: &impl GetKV, height: BlockHeight) -> O
     Version::decode(&mut sah.as_slice()).ok()
 }
 
+pub fn get_historical_node_state(db: &impl GetKV, height: BlockHeight) -> Option<Vec<u8>> {
+    db.get(&(COL_NODE_STATES, height.encode()))
+}
+
+/// Records the transactions committed at the height together with the UTXOs they spent
+pub fn store_block_txs(
+    db: &mut impl StoreKV,
+    height: BlockHeight,
+    txs: &[(TxId, Vec<TxoPointer>)],
+) {
+    db.set((COL_BLOCK_TXS, height.encode()), txs.encode())
+}
+
+pub fn get_block_txs(db: &impl GetKV, height: BlockHeight) -> Option<Vec<(TxId, Vec<TxoPointer>)>> {
+    let txs = db.get(&(COL_BLOCK_TXS, height.encode()))?;
+    <Vec<(TxId, Vec<TxoPointer>)>>::decode(&mut txs.as_slice()).ok()
+}
+
 pub fn store_chain_state<T: StoredChainState>(
     db: &mut impl StoreKV,
     genesis_state: &T,
@@ -90,6 +114,14 @@ pub fn store_chain_state<T: StoredChainState>(
         (COL_STAKING_VERSIONS, encoded_height.clone()),
         genesis_state.get_staking_version().encode(),
     );
+    db.set(
+        (COL_NODE_STATES, encoded_height.clone()),
+        genesis_state.get_encoded(),
+    );
+    // the older node states are only needed for rollbacks, so they aren't kept at every height
+    if let Some(expired) = block_height.checked_sub(NODE_STATES_KEPT_HEIGHTS) {
+        db.delete((COL_NODE_STATES, expired.encode()));
+    }
     if write_history_states {
         db.set(
             (COL_APP_STATES, encoded_height),
@@ -153,6 +185,15 @@ pub fn store_sealed_log(db: &mut impl StoreKV, txid: &TxId, sealed_log: &[u8]) {
     insert_item(db, LookupItem::TxSealed, *txid, sealed_log.to_vec());
 }
 
+pub fn store_tx_inclusion(db: &mut impl StoreKV, txid: &TxId, inclusion: &TxInclusion) {
+    insert_item(db, LookupItem::TxInclusion, *txid, inclusion.encode());
+}
+
+pub fn get_tx_inclusion(db: &impl GetKV, txid: &TxId) -> Option<TxInclusion> {
+    let inclusion = lookup_item(db, LookupItem::TxInclusion, txid)?;
+    TxInclusion::decode(&mut inclusion.as_slice()).ok()
+}
+
 pub fn store_tx_body(db: &mut impl StoreKV, txid: &TxId, tx_payload: &[u8]) {
     insert_item(db, LookupItem::TxBody, *txid, tx_payload.to_vec());
 }
@@ -165,6 +206,104 @@ pub fn store_txs_merkle_tree(db: &mut impl StoreKV, app_hash: &H256, tree_payloa
     insert_item(db, LookupItem::TxsMerkle, *app_hash, tree_payload.to_vec());
 }
 
+pub fn store_app_hash_parts(db: &mut impl StoreKV, app_hash: &H256, parts: &AppHashParts) {
+    insert_item(db, LookupItem::AppHashParts, *app_hash, parts.encode());
+}
+
+pub fn get_app_hash_parts(db: &impl GetKV, app_hash: &H256) -> Option<AppHashParts> {
+    let parts = lookup_item(db, LookupItem::AppHashParts, app_hash)?;
+    AppHashParts::decode(&mut parts.as_slice()).ok()
+}
+
+/// Version of the storage layout (0 for the databases created before it was stored)
+pub fn get_schema_version(db: &impl GetKV) -> u32 {
+    db.get(&(COL_NODE_INFO, SCHEMA_VERSION_KEY.to_vec()))
+        .map(|version| u32::decode(&mut version.as_slice()).expect("invalid schema version"))
+        .unwrap_or(0)
+}
+
+pub fn store_schema_version(db: &mut impl StoreKV, version: u32) {
+    db.set(
+        (COL_NODE_INFO, SCHEMA_VERSION_KEY.to_vec()),
+        version.encode(),
+    )
+}
+
+pub fn store_network_params(db: &mut impl StoreKV, params: &NetworkParameters) {
+    db.set(
+        (COL_NETWORK_PARAMS, params.get_version().encode()),
+        params.encode(),
+    )
+}
+
+pub fn get_network_params(
+    db: &impl GetKV,
+    version: NetworkParametersVersion,
+) -> Option<NetworkParameters> {
+    let params = db.get(&(COL_NETWORK_PARAMS, version.encode()))?;
+    NetworkParameters::decode(&mut params.as_slice()).ok()
+}
+
+/// Appends the slash to the history of the staking address
+pub fn append_slash_record(
+    db: &mut impl StoreKV,
+    address: &StakedStateAddress,
+    record: SlashRecord,
+) {
+    let mut history = get_slash_history(db, address);
+    history.push(record);
+    db.set((COL_SLASH_HISTORY, address.encode()), history.encode())
+}
+
+/// Returns the slashes of the staking address (oldest first)
+pub fn get_slash_history(db: &impl GetKV, address: &StakedStateAddress) -> Vec<SlashRecord> {
+    db.get(&(COL_SLASH_HISTORY, address.encode()))
+        .map(|history| {
+            <Vec<SlashRecord>>::decode(&mut history.as_slice())
+                .expect("invalid slash history in storage")
+        })
+        .unwrap_or_default()
+}
+
+/// Indexes the transactions committed at the height:
+/// their inclusion height and the outputs they spent
+pub fn index_block_txs(
+    db: &mut impl StoreKV,
+    height: BlockHeight,
+    txs: &[(TxId, Vec<TxoPointer>)],
+) {
+    for (txid, spent) in txs.iter() {
+        db.set((COL_TX_HEIGHTS, txid.to_vec()), height.encode());
+        for txin in spent.iter() {
+            let spent = TxoSpent {
+                spent_by: *txid,
+                block_height: height,
+            };
+            db.set((COL_TXO_SPENT, txin.encode()), spent.encode());
+        }
+    }
+}
+
+/// Removes the transactions from the index (e.g. when they are reverted in a rollback)
+pub fn unindex_block_txs(db: &mut impl StoreKV, txs: &[(TxId, Vec<TxoPointer>)]) {
+    for (txid, spent) in txs.iter() {
+        db.delete((COL_TX_HEIGHTS, txid.to_vec()));
+        for txin in spent.iter() {
+            db.delete((COL_TXO_SPENT, txin.encode()));
+        }
+    }
+}
+
+pub fn get_txo_spent(db: &impl GetKV, txin: &TxoPointer) -> Option<TxoSpent> {
+    let spent = db.get(&(COL_TXO_SPENT, txin.encode()))?;
+    TxoSpent::decode(&mut spent.as_slice()).ok()
+}
+
+pub fn get_tx_height(db: &impl GetKV, txid: &TxId) -> Option<BlockHeight> {
+    let height = db.get(&(COL_TX_HEIGHTS, txid.to_vec()))?;
+    BlockHeight::decode(&mut height.as_slice()).ok()
+}
+
 pub fn lookup_input(db: &impl GetKV, txin: &TxoPointer) -> Option<bool> {
     lookup_item(db, LookupItem::TxMetaSpent, &txin.id)
         .and_then(|v| BitVec::from_bytes(&v).get(txin.index as usize))
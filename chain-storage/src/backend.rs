@@ -0,0 +1,296 @@
+use std::fmt;
+use std::io;
+use std::mem;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use kvdb::KeyValueDB;
+
+use super::{Storage, StorageConfig, NUM_COLUMNS};
+
+/// Key-value database implementation used by `Storage`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StorageBackend {
+    /// RocksDB (in the "chain" or "account" directory)
+    RocksDB,
+    /// sled embedded database (in the "chain.sled" or "account.sled" directory)
+    Sled,
+    /// in-process memory only, nothing is persisted (e.g. for integration tests)
+    Memory,
+}
+
+impl Default for StorageBackend {
+    fn default() -> Self {
+        StorageBackend::RocksDB
+    }
+}
+
+impl fmt::Display for StorageBackend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StorageBackend::RocksDB => write!(f, "rocksdb"),
+            StorageBackend::Sled => write!(f, "sled"),
+            StorageBackend::Memory => write!(f, "memory"),
+        }
+    }
+}
+
+impl FromStr for StorageBackend {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "rocksdb" => Ok(StorageBackend::RocksDB),
+            "sled" => Ok(StorageBackend::Sled),
+            "memory" => Ok(StorageBackend::Memory),
+            _ => Err(format!(
+                "unknown storage backend: {} (expected \"rocksdb\", \"sled\" or \"memory\")",
+                s
+            )),
+        }
+    }
+}
+
+/// opens the key-value database according to the config
+/// (fails if the backend's feature isn't enabled in this build)
+pub fn open_kvdb(config: &StorageConfig<'_>) -> io::Result<Arc<dyn KeyValueDB>> {
+    match config.backend {
+        #[cfg(feature = "kvdb-rocksdb")]
+        StorageBackend::RocksDB => Ok(Arc::new(kvdb_rocksdb::Database::open(
+            &kvdb_rocksdb::DatabaseConfig::with_columns(NUM_COLUMNS),
+            &config.db_path(),
+        )?)),
+        #[cfg(feature = "sled")]
+        StorageBackend::Sled => Ok(Arc::new(sled_db::SledDB::open(&config.db_path())?)),
+        StorageBackend::Memory => Ok(Arc::new(kvdb_memorydb::create(NUM_COLUMNS))),
+        #[allow(unreachable_patterns)]
+        backend => Err(io::Error::new(
+            io::ErrorKind::Other,
+            format!("storage backend {} is not enabled in this build", backend),
+        )),
+    }
+}
+
+/// At most so many items are copied in one database write
+const COPY_BATCH_ITEMS: usize = 10_000;
+
+impl Storage {
+    /// Copies all the columns of the other storage (e.g. when migrating to a different backend)
+    /// and returns the number of the copied items
+    pub fn copy_from(&mut self, other: &Storage) -> std::io::Result<u64> {
+        let mut copied = 0;
+        for col in 0..NUM_COLUMNS {
+            let mut tx = self.db.transaction();
+            let mut batch_items = 0;
+            for (key, value) in other.iter_column(col) {
+                tx.put(col, &key, &value);
+                batch_items += 1;
+                if batch_items == COPY_BATCH_ITEMS {
+                    self.db
+                        .write(mem::replace(&mut tx, self.db.transaction()))?;
+                    copied += batch_items as u64;
+                    batch_items = 0;
+                }
+            }
+            self.db.write(tx)?;
+            copied += batch_items as u64;
+        }
+        Ok(copied)
+    }
+}
+
+#[cfg(feature = "sled")]
+pub mod sled_db {
+    use std::io;
+
+    use kvdb::{DBOp, DBTransaction, DBValue, KeyValueDB};
+    use parity_util_mem::{MallocSizeOf, MallocSizeOfOps};
+
+    fn to_io_error(e: sled::Error) -> io::Error {
+        io::Error::new(io::ErrorKind::Other, e.to_string())
+    }
+
+    /// the column is stored as a big endian prefix of the keys in a single sled tree,
+    /// so that transactions are applied atomically in one batch
+    fn prefixed_key(col: u32, key: &[u8]) -> Vec<u8> {
+        let mut prefixed = Vec::with_capacity(4 + key.len());
+        prefixed.extend_from_slice(&col.to_be_bytes());
+        prefixed.extend_from_slice(key);
+        prefixed
+    }
+
+    /// `KeyValueDB` backed by sled
+    pub struct SledDB {
+        db: sled::Db,
+    }
+
+    impl SledDB {
+        pub fn open(path: &str) -> io::Result<Self> {
+            let db = sled::open(path).map_err(to_io_error)?;
+            Ok(SledDB { db })
+        }
+
+        /// temporary database removed when dropped
+        pub fn temporary() -> io::Result<Self> {
+            let db = sled::Config::default()
+                .temporary(true)
+                .open()
+                .map_err(to_io_error)?;
+            Ok(SledDB { db })
+        }
+
+        fn scan<'a>(
+            &'a self,
+            col: u32,
+            prefix: &[u8],
+        ) -> impl Iterator<Item = (Box<[u8]>, Box<[u8]>)> + 'a {
+            self.db.scan_prefix(prefixed_key(col, prefix)).map(|item| {
+                let (key, value) = item.expect("sled storage io error");
+                (key[4..].into(), value.as_ref().into())
+            })
+        }
+    }
+
+    impl MallocSizeOf for SledDB {
+        fn size_of(&self, _ops: &mut MallocSizeOfOps) -> usize {
+            // the caches are managed by sled
+            0
+        }
+    }
+
+    impl KeyValueDB for SledDB {
+        fn get(&self, col: u32, key: &[u8]) -> io::Result<Option<DBValue>> {
+            self.db
+                .get(prefixed_key(col, key))
+                .map(|value| value.map(|v| v.to_vec()))
+                .map_err(to_io_error)
+        }
+
+        fn get_by_prefix(&self, col: u32, prefix: &[u8]) -> Option<Box<[u8]>> {
+            self.scan(col, prefix).next().map(|(_, value)| value)
+        }
+
+        fn write(&self, transaction: DBTransaction) -> io::Result<()> {
+            let mut batch = sled::Batch::default();
+            for op in transaction.ops {
+                match op {
+                    DBOp::Insert { col, key, value } => {
+                        batch.insert(prefixed_key(col, &key), value);
+                    }
+                    DBOp::Delete { col, key } => {
+                        batch.remove(prefixed_key(col, &key));
+                    }
+                    DBOp::DeletePrefix { col, prefix } => {
+                        for item in self.db.scan_prefix(prefixed_key(col, &prefix)).keys() {
+                            batch.remove(item.map_err(to_io_error)?);
+                        }
+                    }
+                }
+            }
+            self.db.apply_batch(batch).map_err(to_io_error)?;
+            self.db.flush().map_err(to_io_error)?;
+            Ok(())
+        }
+
+        fn iter<'a>(&'a self, col: u32) -> Box<dyn Iterator<Item = (Box<[u8]>, Box<[u8]>)> + 'a> {
+            Box::new(self.scan(col, &[]))
+        }
+
+        fn iter_with_prefix<'a>(
+            &'a self,
+            col: u32,
+            prefix: &'a [u8],
+        ) -> Box<dyn Iterator<Item = (Box<[u8]>, Box<[u8]>)> + 'a> {
+            Box::new(self.scan(col, prefix))
+        }
+
+        fn restore(&self, _new_db: &str) -> io::Result<()> {
+            Err(io::Error::new(
+                io::ErrorKind::Other,
+                "restore is not supported by the sled backend",
+            ))
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn check_sled_kvdb() {
+            let db = SledDB::temporary().unwrap();
+            let mut tx = db.transaction();
+            tx.put(0, b"key1", b"value1");
+            tx.put(0, b"key2", b"value2");
+            tx.put(0, b"other", b"value3");
+            tx.put(1, b"key1", b"value4");
+            db.write(tx).unwrap();
+
+            assert_eq!(db.get(0, b"key1").unwrap(), Some(b"value1".to_vec()));
+            assert_eq!(db.get(1, b"key1").unwrap(), Some(b"value4".to_vec()));
+            assert_eq!(db.get(2, b"key1").unwrap(), None);
+            assert_eq!(
+                db.iter(0).map(|(key, _)| key.to_vec()).collect::<Vec<_>>(),
+                vec![b"key1".to_vec(), b"key2".to_vec(), b"other".to_vec()]
+            );
+            assert_eq!(db.iter_with_prefix(0, b"key").count(), 2);
+            assert_eq!(
+                db.get_by_prefix(0, b"oth"),
+                Some(b"value3".to_vec().into_boxed_slice())
+            );
+
+            let mut tx = db.transaction();
+            tx.delete(0, b"other");
+            tx.delete_prefix(0, b"key");
+            db.write(tx).unwrap();
+            assert_eq!(db.iter(0).count(), 0);
+            assert_eq!(db.iter(1).count(), 1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{StorageType, COL_TX_META};
+
+    #[test]
+    fn check_copy_from() {
+        let config = StorageConfig::new("", StorageType::Node).with_backend(StorageBackend::Memory);
+        let mut from = Storage::new(&config).unwrap();
+        let mut to = Storage::new(&config).unwrap();
+        from.write_genesis_chain_id(&[1; 32], "test-ab");
+        let mut tx = from.db.transaction();
+        for i in 0..(COPY_BATCH_ITEMS as u32 + 10) {
+            tx.put(COL_TX_META, &i.to_be_bytes(), &[0]);
+        }
+        from.db.write(tx).unwrap();
+
+        assert_eq!(to.copy_from(&from).unwrap(), COPY_BATCH_ITEMS as u64 + 12);
+        assert_eq!(to.get_stored_chain_id(), b"test-ab".to_vec());
+        assert_eq!(to.get_genesis_app_hash(), [1; 32]);
+        assert_eq!(to.iter_column(COL_TX_META).count(), COPY_BATCH_ITEMS + 10);
+    }
+
+    #[test]
+    fn check_storage_backend_from_str() {
+        for backend in [
+            StorageBackend::RocksDB,
+            StorageBackend::Sled,
+            StorageBackend::Memory,
+        ]
+        .iter()
+        {
+            assert_eq!(backend.to_string().parse(), Ok(*backend));
+        }
+        assert!("lmdb".parse::<StorageBackend>().is_err());
+    }
+
+    #[cfg(not(feature = "sled"))]
+    #[test]
+    fn check_disabled_backend_fails_to_open() {
+        let config = StorageConfig::new("", StorageType::Node).with_backend(StorageBackend::Sled);
+        let err = Storage::new(&config).err().expect("sled isn't enabled");
+        assert!(err.to_string().contains("not enabled"));
+    }
+}
@@ -110,12 +110,40 @@ pub fn flush_stakings<S: StoreKV>(
     put_stakings(storage, version, buffer.values())
 }
 
+/// Put stakings into an empty merkle tree at the version,
+/// e.g. when restoring a snapshot without the previous versions
+pub fn restore_stakings<S: StoreKV>(
+    storage: &mut S,
+    version: Version,
+    stakings: &[StakedState],
+) -> Result<H256> {
+    if let Some(base_version) = version.checked_sub(1) {
+        // the tree at a version is built on top of the previous version's one
+        storage.set(
+            (
+                COL_TRIE_NODE,
+                NodeKey::new_empty_path(base_version).encode()?,
+            ),
+            Node::new_null().encode()?,
+        );
+    }
+    put_stakings(storage, version, stakings.iter())
+}
+
 /// Compute root hash of stakings in memory
 pub fn compute_staking_root(stakings: &[StakedState]) -> H256 {
     let mut store = MemStore::new();
     put_stakings(&mut store, 0, stakings.iter()).expect("jellyfish error with in memory storage")
 }
 
+/// Root hash of the stored merkle trie at the version (None if the version doesn't exist or was pruned)
+pub fn get_staking_root<S: GetKV>(storage: &S, version: Version) -> Option<H256> {
+    KVReader::new(storage)
+        .get_node_option(&NodeKey::new_empty_path(version))
+        .expect("merkle trie storage corrupted")
+        .map(|root| *root.hash().as_ref())
+}
+
 /// Wrap `SparseMerkleProof` to support SCALE encoding
 #[derive(Debug, Clone)]
 pub struct SparseMerkleProof(jellyfish_merkle::SparseMerkleProof);
@@ -212,6 +240,63 @@ pub fn collect_stale_node_indices<S: KeyValueDB>(
         .collect::<Vec<_>>()
 }
 
+/// Collect staled nodes which are not reachable from `version` (and any later version)
+pub fn collect_stale_node_indices_until<S: KeyValueDB + ?Sized>(
+    storage: &S,
+    version: Version,
+) -> Vec<StaleNodeIndex> {
+    // the keys are ordered by the big endian encoded versions
+    storage
+        .iter(COL_TRIE_STALED)
+        .map(|(key, _)| decode_stale_node_index(&key).expect("storage corrupted"))
+        .take_while(|index| index.stale_since_version <= version)
+        .collect::<Vec<_>>()
+}
+
+/// Delete the staled nodes together with their indices
+pub fn delete_stale_nodes<S: StoreKV>(storage: &mut S, indices: &[StaleNodeIndex]) -> Result<()> {
+    for index in indices.iter() {
+        storage.delete((COL_TRIE_NODE, index.node_key.encode()?));
+        storage.delete((COL_TRIE_STALED, encode_stale_node_index(index)?));
+    }
+    Ok(())
+}
+
+/// Collect the nodes written after `version` and the indices of the nodes staled after it
+pub fn collect_nodes_after<S: KeyValueDB + ?Sized>(
+    storage: &S,
+    version: Version,
+) -> (Vec<NodeKey>, Vec<StaleNodeIndex>) {
+    let nodes = storage
+        .iter(COL_TRIE_NODE)
+        .map(|(key, _)| NodeKey::decode(&key).expect("storage corrupted"))
+        .filter(|node_key| node_key.version() > version)
+        .collect::<Vec<_>>();
+    // the keys are ordered by the big endian encoded versions
+    let indices = storage
+        .iter(COL_TRIE_STALED)
+        .map(|(key, _)| decode_stale_node_index(&key).expect("storage corrupted"))
+        .skip_while(|index| index.stale_since_version <= version)
+        .collect::<Vec<_>>();
+    (nodes, indices)
+}
+
+/// Revert the tree to the version the nodes were collected after (see `collect_nodes_after`):
+/// the nodes written after it are deleted and the nodes staled after it become live again
+pub fn revert_nodes<S: StoreKV>(
+    storage: &mut S,
+    nodes: &[NodeKey],
+    indices: &[StaleNodeIndex],
+) -> Result<()> {
+    for node_key in nodes.iter() {
+        storage.delete((COL_TRIE_NODE, node_key.encode()?));
+    }
+    for index in indices.iter() {
+        storage.delete((COL_TRIE_STALED, encode_stale_node_index(index)?));
+    }
+    Ok(())
+}
+
 fn encode_stale_node_index(index: &StaleNodeIndex) -> Result<Vec<u8>> {
     let mut encoded = vec![];
     // Encoded as big endian to keep the numeric order
@@ -233,11 +318,12 @@ fn decode_stale_node_index(data: &[u8]) -> Result<StaleNodeIndex> {
     })
 }
 
-/// Iterate through all stakings
-pub fn iter_stakings<S: GetKV>(
+/// Iterate through the keys and encoded values of all stakings
+/// (e.g. when the values were stored with an older layout)
+pub fn iter_staking_blobs<S: GetKV>(
     storage: &S,
     version: Version,
-) -> impl Iterator<Item = StakedState> + '_ {
+) -> impl Iterator<Item = (H256, Vec<u8>)> + '_ {
     JellyfishMerkleIterator::new(
         Arc::new(KVReader::new(storage)),
         version,
@@ -246,8 +332,19 @@ pub fn iter_stakings<S: GetKV>(
     .expect("jellyfish storage internal error")
     .map(|mblob| {
         let (key, blob) = mblob.expect("jellyfish storage internal error");
-        let staking = StakedState::decode(&mut blob.as_ref()).expect("jellyfish storage corrupted");
-        assert_eq!(key, HashValue::new(staking.key()));
+        (*key.as_ref(), blob.as_ref().to_vec())
+    })
+}
+
+/// Iterate through all stakings
+pub fn iter_stakings<S: GetKV>(
+    storage: &S,
+    version: Version,
+) -> impl Iterator<Item = StakedState> + '_ {
+    iter_staking_blobs(storage, version).map(|(key, blob)| {
+        let staking =
+            StakedState::decode(&mut blob.as_slice()).expect("jellyfish storage corrupted");
+        assert_eq!(key, staking.key());
         staking
     })
 }
@@ -530,4 +627,32 @@ mod tests {
         binary.push(num as u8);
         binary
     }
+
+    #[test]
+    fn check_restore_stakings() {
+        let stakings = (0..10u8)
+            .map(|i| StakedState::default(StakedStateAddress::BasicRedeem([i; 20].into())))
+            .collect::<Vec<_>>();
+        let mut store = MemStore::new();
+        let root = restore_stakings(&mut store, 5, &stakings).unwrap();
+        assert_eq!(root, compute_staking_root(&stakings));
+        assert_eq!(get_staking_root(&store, 5), Some(root));
+        assert_eq!(get_staking_root(&store, 7), None);
+        let getter = StakingGetter::new(&store, 5);
+        for staking in stakings.iter() {
+            assert_eq!(getter.get(&staking.address).as_ref(), Some(staking));
+        }
+        assert_eq!(iter_stakings(&store, 5).count(), stakings.len());
+
+        // the next versions can be built on top of the restored one
+        let mut staking = stakings[0].clone();
+        staking.nonce = 1;
+        let root = put_stakings(&mut store, 6, [staking.clone()].iter()).unwrap();
+        assert_ne!(root, compute_staking_root(&stakings));
+        assert_eq!(get_staking_root(&store, 6), Some(root));
+        assert_eq!(
+            StakingGetter::new(&store, 6).get(&staking.address),
+            Some(staking)
+        );
+    }
 }
@@ -1,18 +1,25 @@
 mod api;
+mod backend;
 pub mod buffer;
 pub mod jellyfish;
+mod pruning;
+mod rollback;
 
 use crate::buffer::{flush_storage, BufferStore, Get, KVBuffer};
 use crate::jellyfish::{put_stakings, Version};
 use chain_core::common::H256;
 use chain_core::state::account::StakedState;
 use chain_core::state::tendermint::BlockHeight;
+use chain_core::tx::data::output::TxInclusion;
 use chain_core::tx::data::TxId;
 use kvdb::{DBTransaction, KeyValueDB};
 use std::path::Path;
 use std::sync::Arc;
 
 pub use api::*;
+pub use backend::*;
+pub use pruning::*;
+pub use rollback::*;
 
 // database columns
 /// Column for UTXOs: TxId => BitVec (where each bit indicates whether the output was spent or not, e.g. b[0] == true if output 0 was spent in a given TX)
@@ -39,23 +46,46 @@ pub const COL_TRIE_NODE: u32 = 9;
 pub const COL_TRIE_STALED: u32 = 10;
 /// Column to store block height -> staking version
 pub const COL_STAKING_VERSIONS: u32 = 11;
+/// Column for transaction inclusion: TxId => block time and height when the transaction was included
+pub const COL_TX_INCLUSION: u32 = 12;
+/// Column for the hashed parts of app hash: app hash => AppHashParts
+pub const COL_APP_HASH_PARTS: u32 = 13;
+/// Column to store network parameters version -> network parameters
+pub const COL_NETWORK_PARAMS: u32 = 14;
+/// Column to store staking address -> history of the slashes
+pub const COL_SLASH_HISTORY: u32 = 15;
+/// Column to store block height -> chain node state, only kept for the last `NODE_STATES_KEPT_HEIGHTS` heights
+/// (so that the node can be rolled back to them)
+pub const COL_NODE_STATES: u32 = 16;
+/// Column to store block height -> ids of the committed transactions with the UTXOs they spent
+pub const COL_BLOCK_TXS: u32 = 17;
+/// Column for the optional spent output index: TxoPointer => spending TxId and block height
+pub const COL_TXO_SPENT: u32 = 18;
+/// Column for the optional transaction index: TxId => height of the block which included the transaction
+pub const COL_TX_HEIGHTS: u32 = 19;
 /// Number of columns in DB
-pub const NUM_COLUMNS: u32 = 12;
+pub const NUM_COLUMNS: u32 = 20;
+
+/// Number of the last heights whose chain node states are kept for rollbacks
+pub const NODE_STATES_KEPT_HEIGHTS: u64 = 100;
 
 pub const CHAIN_ID_KEY: &[u8] = b"chain_id";
 pub const GENESIS_APP_HASH_KEY: &[u8] = b"genesis_app_hash";
 pub const LAST_STATE_KEY: &[u8] = b"last_state";
+pub const PRUNED_HEIGHT_KEY: &[u8] = b"pruned_height";
+pub const SCHEMA_VERSION_KEY: &[u8] = b"schema_version";
 
 pub enum StorageType {
     Node,
     AccountTrie,
 }
 
-/// Storage configuration -- currently only the path to RocksDB directory
+/// Storage configuration -- the base directory and the key-value database backend
 /// TODO: other options? e.g. HDD vs SDD?
 pub struct StorageConfig<'a> {
     base_dbs_path: &'a str,
     purpose: StorageType,
+    backend: StorageBackend,
 }
 
 impl<'a> StorageConfig<'a> {
@@ -63,22 +93,31 @@ impl<'a> StorageConfig<'a> {
         StorageConfig {
             base_dbs_path,
             purpose,
+            backend: StorageBackend::default(),
         }
     }
 
+    /// uses the provided backend instead of RocksDB
+    pub fn with_backend(mut self, backend: StorageBackend) -> Self {
+        self.backend = backend;
+        self
+    }
+
     pub fn db_path(&self) -> String {
-        match self.purpose {
-            StorageType::Node => Path::new(self.base_dbs_path)
-                .join("chain")
-                .to_str()
-                .expect("invalid storage path")
-                .to_string(),
-            StorageType::AccountTrie => Path::new(self.base_dbs_path)
-                .join("account")
-                .to_str()
-                .expect("invalid storage path")
-                .to_string(),
-        }
+        let name = match self.purpose {
+            StorageType::Node => "chain",
+            StorageType::AccountTrie => "account",
+        };
+        // different directories, so that one backend doesn't try to open the files of another one
+        let name = match self.backend {
+            StorageBackend::Sled => format!("{}.sled", name),
+            _ => name.to_owned(),
+        };
+        Path::new(self.base_dbs_path)
+            .join(name)
+            .to_str()
+            .expect("invalid storage path")
+            .to_string()
     }
 }
 
@@ -127,6 +166,10 @@ impl ReadOnlyStorage {
             .expect("IO fail")
             .map(|x| x.to_vec())
     }
+
+    pub fn get_tx_inclusion(&self, txid: &TxId) -> Option<TxInclusion> {
+        get_tx_inclusion(self, txid)
+    }
 }
 
 pub trait StoredChainState {
@@ -148,6 +191,8 @@ pub enum LookupItem {
     TxMetaSpent = COL_TX_META,
     TxsMerkle = COL_MERKLE_PROOFS,
     TxSealed = COL_ENCLAVE_TX,
+    TxInclusion = COL_TX_INCLUSION,
+    AppHashParts = COL_APP_HASH_PARTS,
 }
 
 impl Storage {
@@ -171,19 +216,11 @@ impl Storage {
     }
 
     /// inititalizes Storage based on the provided config
-    #[cfg(feature = "kvdb-rocksdb")]
-    pub fn new(config: &StorageConfig<'_>) -> Self {
-        let db = Arc::new(
-            kvdb_rocksdb::Database::open(
-                &kvdb_rocksdb::DatabaseConfig::with_columns(NUM_COLUMNS),
-                &config.db_path(),
-            )
-            .expect("failed to open db"),
-        );
-        Storage {
-            db,
+    pub fn new(config: &StorageConfig<'_>) -> std::io::Result<Self> {
+        Ok(Storage {
+            db: open_kvdb(config)?,
             current_tx: None,
-        }
+        })
     }
 
     fn get_or_create_tx(&mut self) -> &mut DBTransaction {
@@ -232,6 +269,15 @@ impl Storage {
         get_historical_app_hash(self, height)
     }
 
+    pub fn get_historical_node_state(&self, height: BlockHeight) -> Option<Vec<u8>> {
+        get_historical_node_state(self, height)
+    }
+
+    /// iterates over all the committed key-value pairs in the column (e.g. for state snapshots)
+    pub fn iter_column(&self, col: u32) -> impl Iterator<Item = (Box<[u8]>, Box<[u8]>)> + '_ {
+        self.db.iter(col)
+    }
+
     pub fn write_genesis_chain_id(&mut self, genesis_app_hash: &H256, chain_id: &str) {
         let inittx = self.get_or_create_tx();
         inittx.put(COL_NODE_INFO, GENESIS_APP_HASH_KEY, genesis_app_hash);
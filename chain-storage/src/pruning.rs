@@ -0,0 +1,239 @@
+use parity_scale_codec::{Decode, Encode};
+
+use crate::buffer::{flush_storage, BufferStore, GetKV, KVBuffer, SimpleStore, StoreKV};
+use crate::jellyfish::{collect_stale_node_indices_until, delete_stale_nodes};
+use chain_core::state::tendermint::BlockHeight;
+
+use super::{
+    get_historical_app_hash, get_historical_staking_version, LookupItem, Storage, COL_APP_HASHS,
+    COL_APP_STATES, COL_BLOCK_TXS, COL_NODE_INFO, COL_NODE_STATES, COL_STAKING_VERSIONS,
+    PRUNED_HEIGHT_KEY,
+};
+
+/// How much of the per-height history (app states, app hashes, txs merkle trees, staking versions
+/// and the staking merkle trie nodes) is kept in the storage
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PruningMode {
+    /// nothing is pruned
+    Archive,
+    /// only the last N heights (including the last committed one) are kept
+    KeepRecent(u64),
+}
+
+impl Default for PruningMode {
+    fn default() -> Self {
+        PruningMode::Archive
+    }
+}
+
+impl PruningMode {
+    /// the last height that can be pruned when `last_height` is the last committed one
+    pub fn prunable_until(self, last_height: BlockHeight) -> Option<BlockHeight> {
+        match self {
+            PruningMode::Archive => None,
+            PruningMode::KeepRecent(n) => last_height.checked_sub(n.max(1)),
+        }
+    }
+}
+
+/// the last height whose history was already pruned (if any)
+pub fn get_pruned_height(db: &impl GetKV) -> Option<BlockHeight> {
+    let value = db.get(&(COL_NODE_INFO, PRUNED_HEIGHT_KEY.to_vec()))?;
+    BlockHeight::decode(&mut value.as_slice()).ok()
+}
+
+/// Removes the records of `height`: app state, app hash, staking version, node state, committed transaction ids and the txs merkle tree + app hash parts stored under its app hash
+pub fn prune_height(db: &mut impl StoreKV, height: BlockHeight) {
+    let encoded_height = height.encode();
+    if let Some(app_hash) = get_historical_app_hash(db, height) {
+        // consecutive blocks without any state change (e.g. empty blocks) share the app hash,
+        // so the entries are only removed once the last of them gets pruned
+        let next_app_hash = get_historical_app_hash(db, height.saturating_add(1));
+        if next_app_hash != Some(app_hash) {
+            db.delete((LookupItem::TxsMerkle as u32, app_hash.to_vec()));
+            db.delete((LookupItem::AppHashParts as u32, app_hash.to_vec()));
+        }
+    }
+    db.delete((COL_APP_HASHS, encoded_height.clone()));
+    db.delete((COL_APP_STATES, encoded_height.clone()));
+    db.delete((COL_STAKING_VERSIONS, encoded_height.clone()));
+    db.delete((COL_NODE_STATES, encoded_height.clone()));
+    db.delete((COL_BLOCK_TXS, encoded_height));
+}
+
+impl Storage {
+    /// Prunes the history according to the pruning mode (at most `max_heights` heights at once,
+    /// so that the pruning of an existing database is spread over several calls)
+    /// and returns the number of the pruned heights
+    pub fn prune(
+        &mut self,
+        mode: PruningMode,
+        last_height: BlockHeight,
+        max_heights: u64,
+    ) -> std::io::Result<u64> {
+        let until = match mode.prunable_until(last_height) {
+            Some(until) => until,
+            None => return Ok(0),
+        };
+        let from = match get_pruned_height(&*self) {
+            Some(pruned) => pruned.saturating_add(1),
+            None => BlockHeight::genesis(),
+        };
+        if from > until || max_heights == 0 {
+            return Ok(0);
+        }
+        let until = std::cmp::min(until, from.saturating_add(max_heights - 1));
+        // trie nodes are only needed by the versions of the kept heights
+        let kept_version = get_historical_staking_version(&*self, until.saturating_add(1));
+        let stale_indices = kept_version
+            .map(|version| collect_stale_node_indices_until(&*self.db, version))
+            .unwrap_or_default();
+
+        let mut kv_buffer = KVBuffer::new();
+        {
+            let mut store = BufferStore::new(&*self, &mut kv_buffer);
+            let mut height = from;
+            while height <= until {
+                prune_height(&mut store, height);
+                height = height.saturating_add(1);
+            }
+            delete_stale_nodes(&mut store, &stale_indices)
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e.to_string()))?;
+            store.set((COL_NODE_INFO, PRUNED_HEIGHT_KEY.to_vec()), until.encode());
+        }
+        flush_storage(self, kv_buffer)?;
+        Ok(until.value() - from.value() + 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::jellyfish::{iter_stakings, Version};
+    use crate::{
+        get_historical_node_state, get_historical_state, lookup_item, store_chain_state,
+        store_txs_merkle_tree, StoredChainState, NODE_STATES_KEPT_HEIGHTS, NUM_COLUMNS,
+    };
+    use chain_core::common::H256;
+    use chain_core::state::account::{StakedState, StakedStateAddress};
+
+    struct DummyState {
+        app_hash: H256,
+        staking_version: Version,
+    }
+
+    impl StoredChainState for DummyState {
+        fn get_encoded(&self) -> Vec<u8> {
+            self.app_hash.to_vec()
+        }
+        fn get_encoded_top_level(&self) -> Vec<u8> {
+            self.app_hash.to_vec()
+        }
+        fn get_last_app_hash(&self) -> H256 {
+            self.app_hash
+        }
+        fn get_staking_version(&self) -> Version {
+            self.staking_version
+        }
+    }
+
+    fn staking(i: u8, nonce: u64) -> StakedState {
+        StakedState {
+            nonce,
+            ..StakedState::default(StakedStateAddress::BasicRedeem([i; 20].into()))
+        }
+    }
+
+    #[test]
+    fn check_prune_keep_recent() {
+        let mut storage = Storage::new_db(Arc::new(kvdb_memorydb::create(NUM_COLUMNS)));
+        // heights 0..=9, the stakings are updated at every height, heights 4 and 5 share the app hash
+        for height in 0..10u64 {
+            let version = height;
+            storage.put_stakings(version, &[staking(1, height), staking(2, 0)]);
+            let app_hash = if height == 5 {
+                [4; 32]
+            } else {
+                [height as u8; 32]
+            };
+            let mut kv_buffer = KVBuffer::new();
+            let mut store = BufferStore::new(&storage, &mut kv_buffer);
+            store_txs_merkle_tree(&mut store, &app_hash, &[height as u8]);
+            store_chain_state(
+                &mut store,
+                &DummyState {
+                    app_hash,
+                    staking_version: version,
+                },
+                height.into(),
+                true,
+            );
+            flush_storage(&mut storage, kv_buffer).unwrap();
+        }
+
+        assert_eq!(
+            storage
+                .prune(PruningMode::Archive, BlockHeight::new(9), u64::max_value())
+                .unwrap(),
+            0
+        );
+        // keep the last 5 heights, but prune at most 3 at once
+        let mode = PruningMode::KeepRecent(5);
+        assert_eq!(storage.prune(mode, BlockHeight::new(9), 3).unwrap(), 3);
+        assert_eq!(get_pruned_height(&storage), Some(BlockHeight::new(2)));
+        assert_eq!(storage.prune(mode, BlockHeight::new(9), 3).unwrap(), 2);
+        assert_eq!(get_pruned_height(&storage), Some(BlockHeight::new(4)));
+        assert_eq!(storage.prune(mode, BlockHeight::new(9), 3).unwrap(), 0);
+
+        for height in 0..10u64 {
+            let kept = height >= 5;
+            assert_eq!(
+                get_historical_state(&storage, height.into()).is_some(),
+                kept
+            );
+            assert_eq!(
+                get_historical_staking_version(&storage, height.into()).is_some(),
+                kept
+            );
+            // the stakings are still readable at the kept versions
+            if kept {
+                assert_eq!(iter_stakings(&storage, height).count(), 2);
+            }
+        }
+        // the merkle tree shared with the kept height 5 is still there
+        assert!(lookup_item(&storage, LookupItem::TxsMerkle, &[4; 32]).is_some());
+        assert!(lookup_item(&storage, LookupItem::TxsMerkle, &[3; 32]).is_none());
+        // stale trie nodes of the pruned versions are gone
+        assert!(collect_stale_node_indices_until(&*storage.db, 5).is_empty());
+        assert!(!collect_stale_node_indices_until(&*storage.db, 9).is_empty());
+    }
+
+    #[test]
+    fn check_node_states_are_kept_for_recent_heights() {
+        let mut storage = Storage::new_db(Arc::new(kvdb_memorydb::create(NUM_COLUMNS)));
+        let last_height = NODE_STATES_KEPT_HEIGHTS + 10;
+        for height in 0..=last_height {
+            let mut kv_buffer = KVBuffer::new();
+            let mut store = BufferStore::new(&storage, &mut kv_buffer);
+            store_chain_state(
+                &mut store,
+                &DummyState {
+                    app_hash: [0; 32],
+                    staking_version: height,
+                },
+                height.into(),
+                false,
+            );
+            flush_storage(&mut storage, kv_buffer).unwrap();
+        }
+        for height in 0..=last_height {
+            let kept = height > last_height - NODE_STATES_KEPT_HEIGHTS;
+            assert_eq!(
+                get_historical_node_state(&storage, height.into()).is_some(),
+                kept
+            );
+        }
+    }
+}
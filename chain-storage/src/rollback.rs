@@ -0,0 +1,133 @@
+use std::io;
+
+use bit_vec::BitVec;
+use parity_scale_codec::{Decode, Encode};
+
+use crate::buffer::{flush_storage, BufferStore, KVBuffer, SimpleStore, Store, StoreKV};
+use crate::jellyfish::{collect_nodes_after, revert_nodes};
+use chain_core::common::{Timespec, H256};
+use chain_core::state::account::SlashRecord;
+use chain_core::state::tendermint::BlockHeight;
+use chain_core::tx::data::input::TxoPointer;
+
+use super::{
+    get_block_txs, get_historical_app_hash, get_historical_node_state,
+    get_historical_staking_version, insert_item, lookup_item, unindex_block_txs, LookupItem,
+    Storage, COL_APP_HASHS, COL_APP_STATES, COL_BLOCK_TXS, COL_NODE_INFO, COL_NODE_STATES,
+    COL_SLASH_HISTORY, COL_STAKING_VERSIONS, LAST_STATE_KEY,
+};
+
+fn not_found(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::NotFound, message)
+}
+
+/// Marks the outputs as unspent again (the ones of the removed transactions are skipped)
+fn unspend_utxos(db: &mut impl StoreKV, txins: &[TxoPointer]) {
+    for txin in txins.iter() {
+        if let Some(meta) = lookup_item(db, LookupItem::TxMetaSpent, &txin.id) {
+            let mut bv = BitVec::from_bytes(&meta);
+            bv.set(txin.index as usize, false);
+            insert_item(db, LookupItem::TxMetaSpent, txin.id, bv.to_bytes());
+        }
+    }
+}
+
+/// Removes the records of the reverted `height` (the txs merkle tree and app hash parts
+/// are kept if the height shares the app hash with the rollback target)
+fn revert_height(db: &mut impl StoreKV, height: BlockHeight, kept_app_hash: Option<H256>) {
+    let encoded_height = height.encode();
+    if let Some(app_hash) = get_historical_app_hash(db, height) {
+        if Some(app_hash) != kept_app_hash {
+            db.delete((LookupItem::TxsMerkle as u32, app_hash.to_vec()));
+            db.delete((LookupItem::AppHashParts as u32, app_hash.to_vec()));
+        }
+    }
+    db.delete((COL_APP_HASHS, encoded_height.clone()));
+    db.delete((COL_APP_STATES, encoded_height.clone()));
+    db.delete((COL_STAKING_VERSIONS, encoded_height.clone()));
+    db.delete((COL_NODE_STATES, encoded_height.clone()));
+    db.delete((COL_BLOCK_TXS, encoded_height));
+}
+
+impl Storage {
+    /// Reverts the storage to the state committed at `height` (`last_height` is the last committed one):
+    /// the transactions committed after it are removed together with the outputs they created,
+    /// the outputs they spent become unspent (and they are removed from the spent output index), the staking trie is reverted to the version at `height`
+    /// and the slashes after `block_time` (the block time at `height`) are dropped.
+    /// The node state stored at `height` becomes the last state, so the later blocks can be replayed.
+    /// Returns the number of the reverted heights.
+    pub fn rollback(
+        &mut self,
+        last_height: BlockHeight,
+        height: BlockHeight,
+        block_time: Timespec,
+    ) -> io::Result<u64> {
+        let node_state = get_historical_node_state(&*self, height)
+            .ok_or_else(|| not_found(format!("node state at height {} not stored", height)))?;
+        let version = get_historical_staking_version(&*self, height)
+            .ok_or_else(|| not_found(format!("staking version at height {} not stored", height)))?;
+        let kept_app_hash = get_historical_app_hash(&*self, height);
+        let mut reverted = Vec::new();
+        let mut h = height.saturating_add(1);
+        while h <= last_height {
+            let txs = get_block_txs(&*self, h).ok_or_else(|| {
+                not_found(format!(
+                    "transactions committed at height {} not recorded",
+                    h
+                ))
+            })?;
+            reverted.push((h, txs));
+            h = h.saturating_add(1);
+        }
+        let (nodes, stale_indices) = collect_nodes_after(&*self.db, version);
+        let slash_histories = self
+            .iter_column(COL_SLASH_HISTORY)
+            .map(|(key, value)| {
+                <Vec<SlashRecord>>::decode(&mut value.as_ref())
+                    .map(|history| (key.to_vec(), history))
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))?;
+
+        let mut kv_buffer = KVBuffer::new();
+        {
+            let mut store = BufferStore::new(&*self, &mut kv_buffer);
+            // the latest changes are reverted first
+            for (h, txs) in reverted.iter().rev() {
+                for (txid, spent) in txs.iter().rev() {
+                    unspend_utxos(&mut store, spent);
+                    for item in [
+                        LookupItem::TxMetaSpent,
+                        LookupItem::TxBody,
+                        LookupItem::TxWitness,
+                        LookupItem::TxSealed,
+                        LookupItem::TxInclusion,
+                    ]
+                    .iter()
+                    {
+                        store.delete((*item as u32, txid.to_vec()));
+                    }
+                }
+                unindex_block_txs(&mut store, txs);
+                revert_height(&mut store, *h, kept_app_hash);
+            }
+            revert_nodes(&mut store, &nodes, &stale_indices)
+                .map_err(|e| io::Error::new(io::ErrorKind::Other, e.to_string()))?;
+            for (key, history) in slash_histories.into_iter() {
+                let kept = history
+                    .iter()
+                    .filter(|record| record.time <= block_time)
+                    .cloned()
+                    .collect::<Vec<_>>();
+                if kept.is_empty() {
+                    store.delete((COL_SLASH_HISTORY, key));
+                } else if kept.len() < history.len() {
+                    store.set((COL_SLASH_HISTORY, key), kept.encode());
+                }
+            }
+            store.set((COL_NODE_INFO, LAST_STATE_KEY.to_vec()), node_state);
+        }
+        flush_storage(self, kv_buffer)?;
+        Ok(reverted.len() as u64)
+    }
+}
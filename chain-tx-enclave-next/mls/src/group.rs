@@ -1,4 +1,6 @@
 use std::convert::TryFrom;
+use std::fmt;
+use std::iter;
 
 use crate::ciphersuite::*;
 use crate::extensions as ext;
@@ -11,8 +13,8 @@ use crate::secrets::*;
 use crate::tree::*;
 use crate::tree_math::{LeafSize, NodeSize};
 use crate::utils::{
-    encode_vec_option_u32, encode_vec_u8_u16, encode_vec_u8_u8, read_vec_option_u32,
-    read_vec_u8_u16, read_vec_u8_u8,
+    encode_vec_option_u32, encode_vec_u32, encode_vec_u8_u16, encode_vec_u8_u8,
+    read_vec_option_u32, read_vec_u32, read_vec_u8_u16, read_vec_u8_u8,
 };
 use ra_client::AttestedCertVerifier;
 use rustls::internal::msgs::codec::{self, Codec, Reader};
@@ -29,6 +31,34 @@ pub struct GroupAux {
     pub kp_secret: KeyPackageSecret,
     // record the new secret when waiting for self update commit
     pub kp_secret_pending: Option<KeyPackageSecret>,
+    /// running hash over the confirmed transcript and the last commit's confirmation and signature
+    pub interim_transcript_hash: Vec<u8>,
+}
+
+/// spec: draft-ietf-mls-protocol.md#transcript-hashes
+/// confirmed_transcript_hash_[n] = Hash(interim_transcript_hash_[n-1] || MLSPlaintextCommitContent_[n])
+fn confirmed_transcript_hash(
+    cs: CipherSuite,
+    interim_transcript_hash: &[u8],
+    content_to_commit: &[u8],
+) -> Vec<u8> {
+    cs.hash(&[interim_transcript_hash, content_to_commit].concat())
+}
+
+/// spec: draft-ietf-mls-protocol.md#transcript-hashes
+/// interim_transcript_hash_[n] = Hash(confirmed_transcript_hash_[n] || MLSPlaintextCommitAuthData_[n])
+fn interim_transcript_hash(
+    cs: CipherSuite,
+    confirmed_transcript_hash: &[u8],
+    confirmation: Vec<u8>,
+    signature: Vec<u8>,
+) -> Vec<u8> {
+    let commit_auth = MLSPlaintextCommitAuthData {
+        confirmation,
+        signature,
+    }
+    .get_encoding();
+    cs.hash(&[confirmed_transcript_hash, commit_auth.as_slice()].concat())
 }
 
 impl GroupAux {
@@ -44,6 +74,8 @@ impl GroupAux {
             secrets,
             kp_secret,
             kp_secret_pending: None,
+            // both transcript hashes are empty in the initial epoch
+            interim_transcript_hash: vec![],
         }
     }
 
@@ -136,8 +168,7 @@ impl GroupAux {
         }
     }
 
-    fn get_init_confirmed_transcript_hash(&self, sender: Sender, commit: &Commit) -> Vec<u8> {
-        let interim_transcript_hash = b"".to_vec(); // TODO
+    fn get_confirmed_transcript_hash(&self, sender: Sender, commit: &Commit) -> Vec<u8> {
         let content_to_commit = MLSPlaintextCommitContent::new(
             self.context.group_id.clone(),
             self.context.epoch,
@@ -145,8 +176,11 @@ impl GroupAux {
             commit.clone(),
         )
         .get_encoding();
-        let to_hash = [interim_transcript_hash, content_to_commit].concat();
-        self.tree.cs.hash(&to_hash)
+        confirmed_transcript_hash(
+            self.tree.cs,
+            &self.interim_transcript_hash,
+            &content_to_commit,
+        )
     }
 
     fn get_interim_transcript_hash(
@@ -155,14 +189,12 @@ impl GroupAux {
         commit_msg_sig: Vec<u8>,
         confirmed_transcript: Vec<u8>,
     ) -> Vec<u8> {
-        let commit_auth = MLSPlaintextCommitAuthData {
-            confirmation: commit_confirmation,
-            signature: commit_msg_sig,
-        }
-        .get_encoding();
-        self.tree
-            .cs
-            .hash(&[confirmed_transcript, commit_auth].concat())
+        interim_transcript_hash(
+            self.tree.cs,
+            &confirmed_transcript,
+            commit_confirmation,
+            commit_msg_sig,
+        )
     }
 
     fn get_signed_commit(&self, plain: &MLSPlaintextCommon) -> MLSPlaintext {
@@ -302,7 +334,7 @@ impl GroupAux {
         };
         let updated_epoch = self.context.epoch + 1;
         let confirmed_transcript_hash =
-            self.get_init_confirmed_transcript_hash(self.get_sender(), &commit);
+            self.get_confirmed_transcript_hash(self.get_sender(), &commit);
         let updated_group_context = GroupContext {
             tree_hash: updated_tree.compute_tree_hash(),
             epoch: updated_epoch,
@@ -404,13 +436,9 @@ impl GroupAux {
         )?;
 
         // "Update the new GroupContext's confirmed and interim transcript hashes using the new Commit."
-        let confirmed_transcript_hash = self.get_init_confirmed_transcript_hash(
-            commit.content.sender.clone(),
-            &commit_content.commit,
-        );
-
-        // FIXME: store interim transcript hash?
-        let _interim_transcript_hash = self.get_interim_transcript_hash(
+        let confirmed_transcript_hash = self
+            .get_confirmed_transcript_hash(commit.content.sender.clone(), &commit_content.commit);
+        let interim_transcript_hash = self.get_interim_transcript_hash(
             commit_content.confirmation.clone(),
             commit.signature,
             confirmed_transcript_hash.clone(),
@@ -469,6 +497,7 @@ impl GroupAux {
         self.context = updated_group_context;
         self.secrets = epoch_secrets;
         self.tree = updated_tree;
+        self.interim_transcript_hash = interim_transcript_hash;
 
         Ok(())
     }
@@ -619,6 +648,7 @@ impl GroupAux {
             kp_secret,
             secrets,
             kp_secret_pending: None,
+            interim_transcript_hash: group_info.payload.interim_transcript_hash.clone(),
         };
         // * "Verify the confirmation MAC in the GroupInfo using the derived confirmation key and the confirmed_transcript_hash from the GroupInfo."
         let confirmation = group
@@ -633,6 +663,161 @@ impl GroupAux {
     }
 }
 
+/// public view of the group: context + ratchet tree without any secrets
+///
+/// It can be maintained by observers that are not group members (e.g. chain nodes ordering the handshakes):
+/// they can verify the message signatures and the tree changes, but not the confirmation
+/// (that requires the epoch secrets).
+#[derive(Clone)]
+pub struct PublicGroup {
+    pub context: GroupContext,
+    pub tree: Tree,
+    /// the interim transcript hash (needed for the confirmed transcript hash of the next commit)
+    pub interim_transcript_hash: Vec<u8>,
+}
+
+impl fmt::Debug for PublicGroup {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PublicGroup")
+            .field("context", &self.context)
+            .field("nodes", &self.tree.nodes)
+            .field("interim_transcript_hash", &self.interim_transcript_hash)
+            .finish()
+    }
+}
+
+impl Codec for PublicGroup {
+    fn encode(&self, bytes: &mut Vec<u8>) {
+        self.context.encode(bytes);
+        encode_vec_u32(bytes, &self.tree.nodes);
+        encode_vec_u8_u8(bytes, &self.interim_transcript_hash);
+    }
+
+    fn read(r: &mut Reader) -> Option<Self> {
+        let context = GroupContext::read(r)?;
+        let nodes: Vec<Node> = read_vec_u32(r)?;
+        // check the node count
+        NodeSize(u32::try_from(nodes.len()).ok()?).leafs_len()?;
+        let interim_transcript_hash = read_vec_u8_u8(r)?;
+        Some(Self {
+            context,
+            tree: Tree {
+                nodes,
+                cs: CipherSuite::MLS10_128_DHKEMP256_AES128GCM_SHA256_P256,
+                my_pos: LeafSize(0),
+            },
+            interim_transcript_hash,
+        })
+    }
+}
+
+impl PublicGroup {
+    /// the group with only the creator (before the initial commit)
+    pub fn init(creator_kp: KeyPackage) -> Self {
+        let (context, tree) = GroupContext::init(creator_kp);
+        PublicGroup {
+            context,
+            tree,
+            interim_transcript_hash: vec![],
+        }
+    }
+
+    fn verify_msg_signature(
+        &self,
+        msg: &MLSPlaintext,
+        ra_verifier: &impl AttestedCertVerifier,
+        now: Timespec,
+    ) -> Result<(), ProcessCommitError> {
+        let sender = msg.content.sender.sender;
+        if !matches!(msg.content.sender.sender_type, SenderType::Member)
+            || sender >= self.tree.leaf_len().0
+        {
+            return Err(ProcessCommitError::SenderNotFound);
+        }
+        let kp = self
+            .tree
+            .get_package(LeafSize(sender))
+            .ok_or(ProcessCommitError::SenderNotFound)?;
+        let pk = IdentityPublicKey::new_unsafe(kp.verify(ra_verifier, now)?.public_key.to_vec());
+        msg.verify_signature(&self.context, &pk)
+            .map_err(ProcessCommitError::MsgSignatureVerifyFailed)
+    }
+
+    /// verifies the commit + proposals (signatures of the members, new keypackages, tree changes)
+    /// and moves the group to the next epoch
+    pub fn process_commit(
+        &mut self,
+        commit: &MLSPlaintext,
+        proposals: &[MLSPlaintext],
+        ra_verifier: &impl AttestedCertVerifier,
+        now: Timespec,
+    ) -> Result<CommitContent, ProcessCommitError> {
+        for msg in proposals.iter().chain(iter::once(commit)) {
+            if self.context.epoch != msg.content.epoch
+                || self.context.group_id != msg.content.group_id
+            {
+                return Err(ProcessCommitError::GroupEpochError);
+            }
+            self.verify_msg_signature(msg, ra_verifier, now)?;
+        }
+
+        let commit_content = CommitContent::new(self.tree.cs, commit, proposals)
+            .map_err(|_| ProcessCommitError::CommitError)?;
+        for add in commit_content.additions.iter() {
+            add.key_package.verify(ra_verifier, now)?;
+        }
+        for (_, update) in commit_content.updates.iter() {
+            update.key_package.verify(ra_verifier, now)?;
+        }
+        for remove in commit_content.removes.iter() {
+            if remove.removed >= self.tree.leaf_len().0
+                || self.tree.get_package(LeafSize(remove.removed)).is_none()
+            {
+                return Err(ProcessCommitError::CommitError);
+            }
+        }
+        if let Some(path) = &commit_content.commit.path {
+            path.leaf_key_package.verify(ra_verifier, now)?;
+        }
+
+        let mut updated_tree = self.tree.clone();
+        updated_tree.apply_commit_public(&commit_content)?;
+
+        let content_to_commit = MLSPlaintextCommitContent::new(
+            self.context.group_id.clone(),
+            self.context.epoch,
+            commit.content.sender.clone(),
+            commit_content.commit.clone(),
+        )
+        .get_encoding();
+        let confirmed_transcript_hash = confirmed_transcript_hash(
+            self.tree.cs,
+            &self.interim_transcript_hash,
+            &content_to_commit,
+        );
+        let interim_transcript_hash = interim_transcript_hash(
+            self.tree.cs,
+            &confirmed_transcript_hash,
+            commit_content.confirmation.clone(),
+            commit.signature.clone(),
+        );
+        let epoch = self
+            .context
+            .epoch
+            .checked_add(1)
+            .ok_or(ProcessCommitError::GroupEpochError)?;
+        self.context = GroupContext {
+            epoch,
+            tree_hash: updated_tree.compute_tree_hash(),
+            confirmed_transcript_hash,
+            ..self.context.clone()
+        };
+        self.tree = updated_tree;
+        self.interim_transcript_hash = interim_transcript_hash;
+        Ok(commit_content)
+    }
+}
+
 const TDBE_GROUP_ID: &[u8] = b"Crypto.com Chain Council Node Transaction Data Bootstrap Enclave";
 
 /// spec: draft-ietf-mls-protocol.md#group-state
@@ -991,12 +1176,18 @@ mod test {
         let (member3, member3_secret) = get_fake_keypackage();
         let ra_verifier = MockVerifier {};
 
+        // the public view of the group follows the same commits
+        let mut public_group = PublicGroup::init(member1.clone());
+
         // add member2 in genesis
         let (mut member1_group, proposals, commit, welcome) =
             GroupAux::init_group(member1, member1_secret, &[member2.clone()], &ra_verifier, 0)
                 .expect("group init");
 
         // after commit/welcome get confirmed
+        public_group
+            .process_commit(&commit, &proposals, &ra_verifier, 0)
+            .expect("public commit ok");
         member1_group
             .process_commit(commit, &proposals, &ra_verifier, 0)
             .expect("commit ok");
@@ -1006,12 +1197,20 @@ mod test {
 
         // they should get to the same context
         assert_eq!(&member1_group.context, &member2_group.context);
+        assert_eq!(&member1_group.context, &public_group.context);
+        assert_eq!(
+            member2_group.interim_transcript_hash,
+            public_group.interim_transcript_hash
+        );
 
         // add member3
         let proposals = vec![member1_group.get_signed_add(&member3)];
         let (commit, welcome) = member1_group.commit_proposals(&proposals);
 
         // after commit/welcome get confirmed
+        public_group
+            .process_commit(&commit, &proposals, &ra_verifier, 0)
+            .expect("public commit ok");
         member1_group
             .process_commit(commit.clone(), &proposals, &ra_verifier, 0)
             .expect("commit ok");
@@ -1025,6 +1224,15 @@ mod test {
         // they should get to the same context
         assert_eq!(&member1_group.context, &member2_group.context);
         assert_eq!(&member2_group.context, &member3_group.context);
+        assert_eq!(&member3_group.context, &public_group.context);
+        assert_eq!(
+            member3_group.interim_transcript_hash,
+            member1_group.interim_transcript_hash
+        );
+        assert_eq!(
+            member3_group.interim_transcript_hash,
+            public_group.interim_transcript_hash
+        );
 
         // check add result
         assert_eq!(member3_group.tree.my_pos, LeafSize(2));
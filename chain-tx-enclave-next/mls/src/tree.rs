@@ -507,6 +507,43 @@ impl Tree {
         Ok(commit_secret)
     }
 
+    /// apply proposals and commit without the path secrets
+    /// (only the public keys from the direct path are merged,
+    /// used by observers that are not group members)
+    pub fn apply_commit_public(
+        &mut self,
+        commit: &CommitContent,
+    ) -> Result<(), ProcessCommitError> {
+        let should_populate_path =
+            (commit.additions.is_empty() && commit.updates.is_empty() && commit.removes.is_empty())
+                || !commit.updates.is_empty()
+                || !commit.removes.is_empty();
+        if should_populate_path && commit.commit.path.is_none() {
+            return Err(ProcessCommitError::CommitPathNotPopulated);
+        }
+
+        self.update(&commit.additions, &commit.updates, &commit.removes);
+        if let Some(path) = &commit.commit.path {
+            let direct_path = NodeSize::from(commit.sender).direct_path(self.leaf_len());
+            if direct_path.is_empty() || direct_path.len() != path.nodes.len() {
+                return Err(ProcessCommitError::CommitError);
+            }
+            for (node_index, path_node) in direct_path.into_iter().zip(path.nodes.iter()) {
+                self.nodes[node_index.node_index()].merge_public(path_node.public_key.clone());
+            }
+            let leaf_parent_hash = self.set_parent_hash_path(commit.sender);
+            let ext = path
+                .leaf_key_package
+                .payload
+                .find_extension::<ext::ParentHashExt>()?;
+            if !bool::from(ext.0.ct_eq(&leaf_parent_hash)) {
+                return Err(ProcessCommitError::LeafParentHashDontMatch);
+            }
+            self.set_package(commit.sender, path.leaf_key_package.clone());
+        }
+        Ok(())
+    }
+
     fn node_hash(&self, index: NodeSize) -> Vec<u8> {
         node_hash(&self.nodes, self.cs, index, self.leaf_len())
     }
@@ -6,14 +6,18 @@ use std::{
 };
 
 use parity_scale_codec::{Decode, Encode};
-use secp256k1::{key::PublicKey, Secp256k1};
+use secp256k1::Secp256k1;
 use zeroize::Zeroize;
 
 use chain_core::{
     common::H256,
     state::account::WithdrawUnbondedTx,
     tx::{
-        data::{access::TxAccessPolicy, attribute::TxAttributes, Tx},
+        data::{
+            access::{get_access, TxAccess},
+            attribute::TxAttributes,
+            Tx,
+        },
         TxWithOutputs,
     },
 };
@@ -69,6 +73,7 @@ pub fn handle_decryption_request(
             let txids = decryption_request.body.txs.clone();
             let view_key = decryption_request.body.view_key;
             let mut return_result = Vec::with_capacity(sealed_logs.len());
+            let mut restricted_result = Vec::new();
 
             for (txid, sealed_log) in txids.into_iter().zip(sealed_logs.into_iter()) {
                 let sealed_data = match SealedData::try_copy_from(&sealed_log) {
@@ -86,34 +91,42 @@ pub fn handle_decryption_request(
                     .unseal()
                     .map_err(|e| format!("Error while unsealing sealed data: {:?}", e))?;
                 let otx = TxWithOutputs::decode(&mut unsealed_data.as_slice());
-                let push: bool;
+                let access: Option<TxAccess>;
 
                 match &otx {
                     Ok(TxWithOutputs::Transfer(Tx {
                         attributes: TxAttributes { allowed_view, .. },
                         ..
                     })) => {
-                        push = is_allowed_view(&allowed_view, &view_key);
+                        access = get_access(&allowed_view, &view_key);
                     }
                     Ok(TxWithOutputs::StakeWithdraw(WithdrawUnbondedTx {
                         attributes: TxAttributes { allowed_view, .. },
                         ..
                     })) => {
-                        push = is_allowed_view(&allowed_view, &view_key);
+                        access = get_access(&allowed_view, &view_key);
                     }
                     _ => {
                         return Err("Invalid transaction type".to_owned());
                     }
                 }
 
-                if push {
-                    return_result.push(otx.unwrap());
+                match access {
+                    Some(TxAccess::AllData) => return_result.push(otx.unwrap()),
+                    // only the filtered plaintext leaves the enclave
+                    Some(access) => {
+                        restricted_result.push(otx.unwrap().restricted_view(&access));
+                    }
+                    None => {}
                 }
 
                 unsealed_data.zeroize();
             }
 
-            let decryption_response = DecryptionResponse { txs: return_result };
+            let decryption_response = DecryptionResponse {
+                txs: return_result,
+                restricted_txs: restricted_result,
+            };
             Ok(decryption_response)
         }
         Ok(_) => Err("Unexpected response from ZeroMQ".to_owned()),
@@ -123,9 +136,3 @@ pub fn handle_decryption_request(
         )),
     }
 }
-
-#[inline]
-fn is_allowed_view(allowed_views: &[TxAccessPolicy], view_key: &PublicKey) -> bool {
-    // TODO: policy != alldata + const eq?
-    allowed_views.iter().any(|x| x.view_key == *view_key)
-}
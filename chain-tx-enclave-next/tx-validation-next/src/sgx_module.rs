@@ -135,7 +135,7 @@ mod tests {
             address::ExtendedAddr,
             attribute::TxAttributes,
             input::{TxoPointer, TxoSize},
-            output::TxOut,
+            output::{TxInclusion, TxOut},
             Tx, TxId,
         },
         witness::TxInWitness,
@@ -144,7 +144,9 @@ mod tests {
     use chain_core::tx::{PlainTxAux, TxToObfuscate};
     use chain_core::ChainInfo;
     use chain_tx_validation::Error;
-    use enclave_protocol::{IntraEnclaveRequest, IntraEnclaveResponseOk, VerifyTxRequest};
+    use enclave_protocol::{
+        IntraEnclaveRequest, IntraEnclaveResponseOk, SealedTxInput, VerifyTxRequest,
+    };
     use log::debug;
     use parity_scale_codec::{Decode, Encode};
     use secp256k1::{
@@ -313,6 +315,7 @@ mod tests {
             block_height: BlockHeight::genesis(),
             max_evidence_age: 0,
         };
+        let inclusion = TxInclusion::new(info.block_time, info.block_height);
 
         let request0 = IntraEnclaveRequest::ValidateTx {
             request: Box::new(VerifyTxRequest {
@@ -383,7 +386,7 @@ mod tests {
                 account: None,
                 info,
             }),
-            tx_inputs: Some(vec![sealedtx.clone()]),
+            tx_inputs: Some(vec![SealedTxInput::new(sealedtx.clone(), inclusion)]),
         };
         push_bytes(stream2.clone(), &request1.encode());
         let _ = receiver.recv().unwrap();
@@ -427,7 +430,7 @@ mod tests {
                 account: None,
                 info,
             }),
-            tx_inputs: Some(vec![sealedtx]),
+            tx_inputs: Some(vec![SealedTxInput::new(sealedtx, inclusion)]),
         };
         push_bytes(stream2.clone(), &request2.encode());
         let _ = receiver.recv().unwrap();
@@ -3,6 +3,7 @@ use crate::sgx_module::write_response;
 use aead::{generic_array::GenericArray, Aead, NewAead};
 use aes_gcm_siv::Aes128GcmSiv;
 use chain_core::state::tendermint::BlockHeight;
+use chain_core::tx::data::output::TxInclusion;
 use chain_core::tx::data::TxId;
 use chain_core::tx::TransactionId;
 use chain_core::tx::TxWithOutputs;
@@ -13,7 +14,7 @@ use chain_tx_validation::{
     witness::verify_tx_recover_address,
 };
 use enclave_macro::mock_key;
-use enclave_protocol::{EncryptionRequest, IntraEncryptRequest};
+use enclave_protocol::{EncryptionRequest, IntraEncryptRequest, SealedTxInput};
 use enclave_protocol::{IntraEnclaveResponse, IntraEnclaveResponseOk};
 use enclave_utils::SealedData;
 use parity_scale_codec::Decode;
@@ -73,14 +74,17 @@ fn unseal_request(request: &IntraEncryptRequest) -> Option<EncryptionRequest> {
 }
 
 #[inline]
-pub fn check_unseal<I>(txids: I, sealed_logs: Vec<Vec<u8>>) -> Option<Vec<TxWithOutputs>>
+pub fn check_unseal<I>(
+    txids: I,
+    sealed_inputs: Vec<SealedTxInput>,
+) -> Option<Vec<(TxWithOutputs, TxInclusion)>>
 where
     I: IntoIterator<Item = TxId> + ExactSizeIterator,
 {
-    let mut return_result = Vec::with_capacity(sealed_logs.len());
+    let mut return_result = Vec::with_capacity(sealed_inputs.len());
 
-    for (txid, sealed_log) in txids.into_iter().zip(sealed_logs.into_iter()) {
-        let sealed_data = SealedData::try_copy_from(&sealed_log)?;
+    for (txid, input) in txids.into_iter().zip(sealed_inputs.into_iter()) {
+        let sealed_data = SealedData::try_copy_from(&input.sealed_log)?;
 
         if sealed_data.aes_data.additional_txt != txid {
             return None;
@@ -89,7 +93,7 @@ where
         let mut unsealed_data = sealed_data.unseal().ok()?;
         let otx = TxWithOutputs::decode(&mut unsealed_data.as_slice());
         if let Ok(tx) = otx {
-            return_result.push(tx.clone());
+            return_result.push((tx, input.inclusion));
         } else {
             return None;
         }
@@ -12,7 +12,8 @@ use chain_tx_validation::{
     verify_bonded_deposit_core, verify_transfer, verify_unbonded_withdraw_core, TxWithOutputs,
 };
 use enclave_protocol::{
-    is_basic_valid_tx_request, IntraEnclaveResponse, IntraEnclaveResponseOk, VerifyTxRequest,
+    is_basic_valid_tx_request, IntraEnclaveResponse, IntraEnclaveResponseOk, SealedTxInput,
+    VerifyTxRequest,
 };
 use enclave_utils::SealedData;
 use parity_scale_codec::Encode;
@@ -71,7 +72,7 @@ fn decrypt(payload: &TxObfuscated) -> Result<PlainTxAux, ()> {
 #[inline]
 pub(crate) fn handle_validate_tx<I: Write>(
     request: Box<VerifyTxRequest>,
-    tx_inputs: Option<Vec<Vec<u8>>>,
+    tx_inputs: Option<Vec<SealedTxInput>>,
     filter: &mut BlockFilter,
     output: &mut I,
 ) {
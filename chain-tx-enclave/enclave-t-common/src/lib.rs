@@ -5,7 +5,7 @@
 extern crate sgx_tstd as std;
 
 use chain_core::state::account::WithdrawUnbondedTx;
-use chain_core::tx::data::access::TxAccessPolicy;
+use chain_core::tx::data::access::{get_access, TxAccess, TxAccessPolicy};
 use chain_core::tx::data::attribute::TxAttributes;
 use chain_core::tx::data::Tx;
 use chain_core::tx::data::TxId;
@@ -25,8 +25,9 @@ fn is_allowed_view(
 ) -> bool {
     match view_key {
         Some(view_key) if check_allowed_views => {
-            // TODO: policy != alldata + const eq?
-            allowed_views.iter().any(|x| x.view_key == *view_key)
+            // TODO: const eq?
+            // restricted views are only served by the filtering tx-query
+            get_access(allowed_views, view_key) == Some(TxAccess::AllData)
         }
         _ => !check_allowed_views,
     }
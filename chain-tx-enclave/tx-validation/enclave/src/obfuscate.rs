@@ -1,4 +1,4 @@
-use crate::validate::write_back_response;
+use crate::validate::{unseal_inputs, write_back_response};
 use aead::{generic_array::GenericArray, Aead, NewAead};
 use aes_gcm_siv::Aes128GcmSiv;
 use chain_core::state::tendermint::BlockHeight;
@@ -11,7 +11,6 @@ use chain_tx_validation::{
 use enclave_macro::mock_key;
 use enclave_protocol::{EncryptionRequest, IntraEncryptRequest};
 use enclave_protocol::{IntraEnclaveResponse, IntraEnclaveResponseOk};
-use enclave_t_common::check_unseal;
 use parity_scale_codec::Decode;
 #[cfg(feature = "sgx-test")]
 use parity_scale_codec::Encode;
@@ -167,8 +166,7 @@ pub(crate) fn handle_encrypt_request(
 ) -> sgx_status_t {
     match (unseal_request(&mut request), request.tx_inputs) {
         (Some(EncryptionRequest::TransferTx(tx, witness)), Some(sealed_inputs)) => {
-            let unsealed_inputs =
-                check_unseal(None, false, tx.inputs.iter().map(|x| x.id), sealed_inputs);
+            let unsealed_inputs = unseal_inputs(tx.inputs.iter().map(|x| x.id), sealed_inputs);
             if let Some(inputs) = unsealed_inputs {
                 let result = verify_transfer(&tx, &witness, &request.info, inputs);
                 let txid = tx.id();
@@ -183,8 +181,7 @@ pub(crate) fn handle_encrypt_request(
             }
         }
         (Some(EncryptionRequest::DepositStake(tx, witness)), Some(sealed_inputs)) => {
-            let unsealed_inputs =
-                check_unseal(None, false, tx.inputs.iter().map(|x| x.id), sealed_inputs);
+            let unsealed_inputs = unseal_inputs(tx.inputs.iter().map(|x| x.id), sealed_inputs);
             if let Some(inputs) = unsealed_inputs {
                 let result = verify_bonded_deposit_core(&tx, &witness, &request.info, inputs);
                 let txid = tx.id();
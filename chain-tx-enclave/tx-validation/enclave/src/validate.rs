@@ -1,4 +1,5 @@
 use chain_core::init::coin::Coin;
+use chain_core::tx::data::output::TxInclusion;
 use chain_core::tx::data::TxId;
 use chain_core::tx::fee::Fee;
 use chain_core::tx::TransactionId;
@@ -9,7 +10,8 @@ use chain_tx_validation::{
     verify_bonded_deposit_core, verify_transfer, verify_unbonded_withdraw_core, TxWithOutputs,
 };
 use enclave_protocol::{
-    is_basic_valid_tx_request, IntraEnclaveResponse, IntraEnclaveResponseOk, VerifyTxRequest,
+    is_basic_valid_tx_request, IntraEnclaveResponse, IntraEnclaveResponseOk, SealedTxInput,
+    VerifyTxRequest,
 };
 use enclave_t_common::check_unseal;
 use lazy_static::lazy_static;
@@ -140,6 +142,24 @@ pub(crate) fn write_back_response(
     }
 }
 
+/// unseals the transaction inputs and pairs them with their block inclusion
+/// (needed for checking the relative timelocks)
+#[inline]
+pub(crate) fn unseal_inputs<I>(
+    txids: I,
+    sealed_inputs: Vec<SealedTxInput>,
+) -> Option<Vec<(TxWithOutputs, TxInclusion)>>
+where
+    I: IntoIterator<Item = TxId> + ExactSizeIterator,
+{
+    let (sealed_logs, inclusions): (Vec<_>, Vec<_>) = sealed_inputs
+        .into_iter()
+        .map(|input| (input.sealed_log, input.inclusion))
+        .unzip();
+    let unsealed = check_unseal(None, false, txids, sealed_logs)?;
+    Some(unsealed.into_iter().zip(inclusions.into_iter()).collect())
+}
+
 #[inline]
 fn decrypt(payload: &TxObfuscated) -> Result<PlainTxAux, ()> {
     crate::obfuscate::decrypt(payload)
@@ -149,7 +169,7 @@ fn decrypt(payload: &TxObfuscated) -> Result<PlainTxAux, ()> {
 #[inline]
 pub(crate) fn handle_validate_tx(
     request: Box<VerifyTxRequest>,
-    tx_inputs: Option<Vec<Vec<u8>>>,
+    tx_inputs: Option<Vec<SealedTxInput>>,
     response_buf: *mut u8,
     response_len: u32,
 ) -> sgx_status_t {
@@ -167,8 +187,7 @@ pub(crate) fn handle_validate_tx(
             },
         ) => {
             let plaintx = decrypt(&payload);
-            let unsealed_inputs =
-                check_unseal(None, false, inputs.iter().map(|x| x.id), sealed_inputs);
+            let unsealed_inputs = unseal_inputs(inputs.iter().map(|x| x.id), sealed_inputs);
             match (plaintx, unsealed_inputs) {
                 (Ok(PlainTxAux::TransferTx(tx, witness)), Some(inputs)) => {
                     if tx.id() != payload.txid || tx.outputs.len() as TxoSize != no_of_outputs {
@@ -191,7 +210,7 @@ pub(crate) fn handle_validate_tx(
         }
         (Some(sealed_inputs), TxEnclaveAux::DepositStakeTx { tx, payload }) => {
             let plaintx = decrypt(&payload);
-            let inputs = check_unseal(None, false, tx.inputs.iter().map(|x| x.id), sealed_inputs);
+            let inputs = unseal_inputs(tx.inputs.iter().map(|x| x.id), sealed_inputs);
             match (plaintx, inputs) {
                 (Ok(PlainTxAux::DepositStakeTx(witness)), Some(inputs)) => {
                     let result = verify_bonded_deposit_core(&tx, &witness, &request.info, inputs);
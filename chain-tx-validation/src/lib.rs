@@ -17,7 +17,7 @@ extern crate sgx_tstd as std;
 use chain_core::init::coin::Coin;
 use chain_core::state::account::{DepositBondTx, StakedState, WithdrawUnbondedTx};
 use chain_core::tx::data::input::TxoPointer;
-use chain_core::tx::data::output::TxOut;
+use chain_core::tx::data::output::{Timelock, TxInclusion, TxOut};
 use chain_core::tx::data::Tx;
 use chain_core::tx::data::TxId;
 use chain_core::tx::fee::Fee;
@@ -29,7 +29,7 @@ use parity_scale_codec::{Decode, Encode};
 use std::collections::BTreeSet;
 use std::fmt;
 use std::prelude::v1::Vec;
-use witness::verify_tx_address;
+use witness::{verify_tx_address, verify_tx_witness_timeout};
 
 /// All possible TX validation errors
 #[derive(Debug, Encode, Decode)]
@@ -187,13 +187,13 @@ fn check_inputs(
     inputs: &[TxoPointer],
     witness: &TxWitness,
     extra_info: &ChainInfo,
-    transaction_inputs: Vec<TxWithOutputs>,
+    transaction_inputs: Vec<(TxWithOutputs, TxInclusion)>,
 ) -> Result<Coin, Error> {
     let mut incoins = Coin::zero();
     // verify that txids of inputs correspond to the owner/signer
     // and it'd check they are not spent
     // TODO: zip3 / itertools?
-    for (txin, (tx, in_witness)) in inputs
+    for (txin, ((tx, included), in_witness)) in inputs
         .iter()
         .zip(transaction_inputs.iter().zip(witness.iter()))
     {
@@ -206,15 +206,16 @@ fn check_inputs(
             return Err(Error::InvalidInput);
         }
         let txout = &outputs[input_index];
-        if let Some(valid_from) = &txout.valid_from {
-            if *valid_from > extra_info.block_time {
-                return Err(Error::OutputInTimelock);
-            }
+        if !txout.is_spendable(extra_info.block_time, extra_info.block_height, included) {
+            return Err(Error::OutputInTimelock);
         }
         let wv = verify_tx_address(&in_witness, main_txid, &txout.address);
         if let Err(_e) = wv {
             return Err(Error::EcdsaCrypto); // FIXME: Err(Error::EcdsaCrypto(e));
         }
+        if !verify_tx_witness_timeout(&in_witness, extra_info.block_time) {
+            return Err(Error::OutputInTimelock);
+        }
         let sum = incoins + txout.value;
         if let Err(_e) = sum {
             return Err(Error::InvalidSum); // FIXME: Err(Error::InvalidSum(e));
@@ -249,16 +250,18 @@ fn check_input_output_sums(
     outcoins: Coin,
     extra_info: &ChainInfo,
 ) -> Result<Fee, Error> {
-    // check sum(input amounts) == sum(output amounts) + minimum fee
+    // check sum(input amounts) >= sum(output amounts) + minimum fee
+    // (the fee paid above the minimum gives the transaction a higher priority)
     let min_fee: Coin = extra_info.min_fee_computed.to_coin();
     let total_outsum = outcoins + min_fee;
     if let Err(_coin_err) = total_outsum {
         return Err(Error::InvalidSum); // FIXME: Err(Error::InvalidSum(coin_err));
     }
-    if incoins != total_outsum.unwrap() {
+    if incoins < total_outsum.unwrap() {
         return Err(Error::InputOutputDoNotMatch);
     }
-    Ok(Fee::new(min_fee))
+    let paid_fee = (incoins - outcoins).map_err(|_| Error::InvalidSum)?;
+    Ok(Fee::new(paid_fee))
 }
 
 /// checks TransferTx -- TODO: this will be moved to an enclave
@@ -267,7 +270,7 @@ pub fn verify_transfer(
     maintx: &Tx,
     witness: &TxWitness,
     extra_info: &ChainInfo,
-    transaction_inputs: Vec<TxWithOutputs>,
+    transaction_inputs: Vec<(TxWithOutputs, TxInclusion)>,
 ) -> Result<Fee, Error> {
     check_attributes(
         maintx.attributes.chain_hex_id,
@@ -296,7 +299,7 @@ pub fn verify_bonded_deposit_core(
     maintx: &DepositBondTx,
     witness: &TxWitness,
     extra_info: &ChainInfo,
-    transaction_inputs: Vec<TxWithOutputs>,
+    transaction_inputs: Vec<(TxWithOutputs, TxInclusion)>,
 ) -> Result<Coin, Error> {
     check_attributes(
         maintx.attributes.chain_hex_id,
@@ -348,7 +351,7 @@ pub fn verify_unbonded_withdraw_core(
     if !maintx
         .outputs
         .iter()
-        .all(|x| x.valid_from == Some(account.unbonded_from))
+        .all(|x| x.valid_from == Some(Timelock::Time(account.unbonded_from)))
     {
         return Err(Error::AccountWithdrawOutputNotLocked);
     }
@@ -1,3 +1,4 @@
+use chain_core::common::Timespec;
 use chain_core::init::address::RedeemAddress;
 use chain_core::state::account::{StakedStateAddress, StakedStateOpWitness};
 use chain_core::tx::data::address::ExtendedAddr;
@@ -32,6 +33,36 @@ pub fn verify_tx_address(
                 )
             }
         }
+        (
+            TxInWitness::HashTimeLock(sig, contract, preimage),
+            ExtendedAddr::HashTimeLock(contract_hash),
+        ) => {
+            if contract.contract_hash() != *contract_hash {
+                return Err(secp256k1::Error::InvalidPublicKey);
+            }
+            let signer = match preimage {
+                Some(preimage) if contract.check_preimage(preimage) => &contract.recipient,
+                Some(_) => return Err(secp256k1::Error::InvalidMessage),
+                None => &contract.refund,
+            };
+            schnorr_verify(
+                &secp,
+                &message,
+                &sig,
+                &XOnlyPublicKey::from_slice(signer.as_bytes())?,
+            )
+        }
+        _ => Err(secp256k1::Error::InvalidPublicKey),
+    }
+}
+
+/// verify the time conditions of the witness at the given block time
+/// (the refund of a hash-time-locked contract is only possible after its timeout)
+pub fn verify_tx_witness_timeout(witness: &TxInWitness, block_time: Timespec) -> bool {
+    match witness {
+        TxInWitness::TreeSig(..) => true,
+        TxInWitness::HashTimeLock(_, _, Some(_)) => true,
+        TxInWitness::HashTimeLock(_, contract, None) => contract.timeout <= block_time,
     }
 }
 
@@ -63,6 +94,7 @@ pub mod tests {
     use secp256k1::{PublicKey, SecretKey};
 
     use chain_core::common::MerkleTree;
+    use chain_core::tx::data::htlc::{hash_preimage, HashTimeLock};
     use chain_core::tx::data::Tx;
     use chain_core::tx::witness::tree::RawXOnlyPubkey;
     use chain_core::tx::TransactionId;
@@ -166,6 +198,52 @@ pub mod tests {
         assert!(verify_tx_address(&witness, &transation.id(), &address).is_err())
     }
 
+    #[test]
+    fn check_htlc_verify() {
+        let transation = Tx::new();
+
+        let secp = Secp256k1::new();
+
+        let recipient_key =
+            SecretKey::from_slice(&[0xcd; 32]).expect("Unable to create secret key");
+        let refund_key = SecretKey::from_slice(&[0xde; 32]).expect("Unable to create secret key");
+        let contract = HashTimeLock::new(
+            hash_preimage(&[0x01; 32]),
+            RawXOnlyPubkey::from(
+                XOnlyPublicKey::from_secret_key(&secp, &recipient_key).serialize(),
+            ),
+            RawXOnlyPubkey::from(XOnlyPublicKey::from_secret_key(&secp, &refund_key).serialize()),
+            100,
+        );
+        let address = contract.address();
+
+        let message = Message::from_slice(&transation.id()).unwrap();
+        let sign = |key| schnorr_sign(&secp, &message, key, &mut rand::thread_rng());
+
+        let claim =
+            TxInWitness::HashTimeLock(sign(&recipient_key), contract.clone(), Some([0x01; 32]));
+        assert!(verify_tx_address(&claim, &transation.id(), &address).is_ok());
+        assert!(verify_tx_witness_timeout(&claim, 0));
+
+        let wrong_preimage =
+            TxInWitness::HashTimeLock(sign(&recipient_key), contract.clone(), Some([0x02; 32]));
+        assert!(verify_tx_address(&wrong_preimage, &transation.id(), &address).is_err());
+
+        let refund = TxInWitness::HashTimeLock(sign(&refund_key), contract.clone(), None);
+        assert!(verify_tx_address(&refund, &transation.id(), &address).is_ok());
+        assert!(!verify_tx_witness_timeout(&refund, 99));
+        assert!(verify_tx_witness_timeout(&refund, 100));
+
+        let refund_by_recipient =
+            TxInWitness::HashTimeLock(sign(&recipient_key), contract.clone(), None);
+        assert!(verify_tx_address(&refund_by_recipient, &transation.id(), &address).is_err());
+
+        let mut other_contract = contract;
+        other_contract.timeout = 0;
+        let other_refund = TxInWitness::HashTimeLock(sign(&refund_key), other_contract, None);
+        assert!(verify_tx_address(&other_refund, &transation.id(), &address).is_err());
+    }
+
     #[test]
     fn check_staked_verify() {
         let transation = Tx::new();
@@ -562,6 +562,10 @@ impl Command {
                     Default::default(),
                 ),
             ]),
+            Row::new(vec![
+                Cell::new("Locked", Default::default()),
+                Cell::new(format!("{}", balance.locked).as_str(), Default::default()),
+            ]),
         ];
 
         let table = Table::new(rows, Default::default())
@@ -687,6 +691,7 @@ impl Command {
                         }
                     }
                 }
+                ProgressReport::Transactions { .. } => {}
             };
             true
         };
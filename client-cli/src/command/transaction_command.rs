@@ -3,26 +3,27 @@ use std::collections::BTreeSet;
 use std::convert::TryInto;
 use std::fs::File;
 use std::io::Write;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
 
-use chain_core::common::{Timespec, HASH_SIZE_256};
-use chain_core::init::coin::Coin;
+use chain_core::common::HASH_SIZE_256;
+use chain_core::init::coin::{sum_coins, Coin};
 use chain_core::init::network::get_network_id;
 use chain_core::state::account::{
-    ConfidentialInit, CouncilNodeMeta, StakedStateAddress, StakedStateOpAttributes,
+    ConfidentialInit, CouncilNodeMeta, StakeKind, StakedStateAddress, StakedStateOpAttributes,
 };
-use chain_core::state::tendermint::TendermintValidatorPubKey;
+use chain_core::state::tendermint::{BlockHeight, TendermintValidatorPubKey};
 use chain_core::tx::data::access::{TxAccess, TxAccessPolicy};
 use chain_core::tx::data::address::ExtendedAddr;
 use chain_core::tx::data::attribute::TxAttributes;
-use chain_core::tx::data::input::TxoPointer;
-use chain_core::tx::data::output::TxOut;
+use chain_core::tx::data::input::{str2txid, TxoPointer};
+use chain_core::tx::data::output::{Timelock, TxOut};
+use chain_core::tx::data::TxId;
 use chain_core::tx::TxAux;
 use client_common::{Error, ErrorKind, PublicKey, Result, ResultExt, SecKey, Transaction};
 use client_core::transaction_builder::SignedTransferTransaction;
 use client_core::types::{BalanceChange, TransactionPending};
-use client_core::WalletClient;
+use client_core::{InputSelection, InputSelectionStrategy, WalletClient};
 use client_network::NetworkOpsClient;
 use mls::{Codec, KeyPackage};
 
@@ -38,13 +39,22 @@ use crate::{ask_seckey, coin_from_str};
 use client_core::transaction_builder::UnsignedTransferTransaction;
 use mls::extensions::LifeTimeExt;
 
-const TRANSACTION_TYPE_VARIANTS: [&str; 6] = [
+const INPUT_SELECTION_STRATEGY_VARIANTS: [&str; 4] = [
+    "highest-value-first",
+    "lowest-value-first",
+    "random",
+    "exact-match",
+];
+
+const TRANSACTION_TYPE_VARIANTS: [&str; 8] = [
     "transfer",
     "deposit",
     "unbond",
     "withdraw",
     "unjail",
     "node-join",
+    "node-metadata-update",
+    "transfer-stake",
 ];
 
 #[derive(Debug, PartialEq)]
@@ -58,6 +68,8 @@ pub enum TransactionType {
     Withdraw,
     Unjail,
     NodeJoin,
+    NodeMetadataUpdate,
+    TransferStake,
 }
 
 impl FromStr for TransactionType {
@@ -76,6 +88,10 @@ impl FromStr for TransactionType {
             Ok(TransactionType::Unjail)
         } else if eq_ascii(s, "node-join") {
             Ok(TransactionType::NodeJoin)
+        } else if eq_ascii(s, "node-metadata-update") {
+            Ok(TransactionType::NodeMetadataUpdate)
+        } else if eq_ascii(s, "transfer-stake") {
+            Ok(TransactionType::TransferStake)
         } else {
             Err(ErrorKind::DeserializationError.into())
         }
@@ -117,6 +133,56 @@ pub enum TransactionCommand {
             case_insensitive = true
         )]
         keypackage: Option<PathBuf>,
+        #[structopt(
+            name = "input selection strategy",
+            long = "strategy",
+            help = "Input selection strategy when transaction type is transfer",
+            possible_values = &INPUT_SELECTION_STRATEGY_VARIANTS
+        )]
+        strategy: Option<InputSelectionStrategy>,
+        #[structopt(
+            name = "consolidation max fee",
+            long = "consolidate",
+            help = "Consolidates as many unspent outputs as possible while the fee (in CRO) doesn't exceed the value (transfer only)",
+            conflicts_with = "input selection strategy",
+            parse(try_from_str = coin_from_str)
+        )]
+        consolidate: Option<Coin>,
+        #[structopt(
+            name = "included input",
+            long = "include",
+            help = "Unspent output (<transaction id>:<index>) always spent by the transfer transaction",
+            parse(try_from_str = txo_pointer_from_str)
+        )]
+        include: Vec<TxoPointer>,
+        #[structopt(
+            name = "excluded input",
+            long = "exclude",
+            help = "Unspent output (<transaction id>:<index>) never spent by the transfer transaction",
+            parse(try_from_str = txo_pointer_from_str)
+        )]
+        exclude: Vec<TxoPointer>,
+    },
+    #[structopt(
+        name = "batch",
+        about = "Send a batch payment to the recipients of a CSV file (one `address,amount in CRO` per line)"
+    )]
+    Batch {
+        #[structopt(
+            name = "wallet name",
+            short = "n",
+            long = "name",
+            help = "Name of wallet"
+        )]
+        name: String,
+        #[structopt(
+            name = "file",
+            short = "f",
+            long = "file",
+            parse(from_os_str),
+            help = "CSV file of the recipients"
+        )]
+        file: PathBuf,
     },
     #[structopt(name = "show", about = "Display details of a transaction")]
     Show {
@@ -240,6 +306,80 @@ pub enum TransactionCommand {
         )]
         file: PathBuf,
     },
+    #[structopt(name = "pending", about = "List pending transactions of a wallet")]
+    Pending {
+        #[structopt(
+            name = "wallet name",
+            short = "n",
+            long = "name",
+            help = "Name of wallet"
+        )]
+        name: String,
+    },
+    #[structopt(name = "rebroadcast", about = "Broadcast a pending transaction again")]
+    Rebroadcast {
+        #[structopt(
+            name = "wallet name",
+            short = "n",
+            long = "name",
+            help = "Name of wallet"
+        )]
+        name: String,
+        #[structopt(
+            name = "transaction id",
+            short = "i",
+            long = "id",
+            help = "Transaction ID"
+        )]
+        id: String,
+    },
+    #[structopt(
+        name = "replace",
+        about = "Replace a pending transfer transaction with one paying a higher fee"
+    )]
+    Replace {
+        #[structopt(
+            name = "wallet name",
+            short = "n",
+            long = "name",
+            help = "Name of wallet"
+        )]
+        name: String,
+        #[structopt(
+            name = "transaction id",
+            short = "i",
+            long = "id",
+            help = "Transaction ID"
+        )]
+        id: String,
+        #[structopt(
+            name = "fee",
+            long = "fee",
+            help = "Fee (in CRO) paid by the replacement transaction",
+            parse(try_from_str = coin_from_str)
+        )]
+        fee: Coin,
+    },
+    #[structopt(
+        name = "abandon",
+        about = "Abandon an unconfirmed pending transaction (its inputs become spendable again)"
+    )]
+    Abandon {
+        #[structopt(
+            name = "wallet name",
+            short = "n",
+            long = "name",
+            help = "Name of wallet"
+        )]
+        name: String,
+        #[structopt(
+            name = "transaction id",
+            short = "i",
+            long = "id",
+            help = "Transaction ID"
+        )]
+        id: String,
+    },
 }
 
 impl TransactionCommand {
@@ -254,14 +394,42 @@ impl TransactionCommand {
                 transaction_type,
                 advanced,
                 keypackage,
-            } => new_transaction(
-                wallet_client,
-                network_ops_client,
-                name,
-                transaction_type,
-                *advanced,
-                keypackage.clone(),
-            ),
+                strategy,
+                consolidate,
+                include,
+                exclude,
+            } => {
+                let input_selection = if strategy.is_none()
+                    && consolidate.is_none()
+                    && include.is_empty()
+                    && exclude.is_empty()
+                {
+                    None
+                } else {
+                    let strategy = match consolidate {
+                        Some(max_fee) => InputSelectionStrategy::Consolidate { max_fee: *max_fee },
+                        None => strategy.unwrap_or_default(),
+                    };
+                    Some(InputSelection {
+                        strategy,
+                        include: include.clone(),
+                        exclude: exclude.clone(),
+                        ..Default::default()
+                    })
+                };
+                new_transaction(
+                    wallet_client,
+                    network_ops_client,
+                    name,
+                    transaction_type,
+                    *advanced,
+                    keypackage.clone(),
+                    input_selection,
+                )
+            }
+            TransactionCommand::Batch { name, file } => {
+                send_batch_payment(wallet_client, name, file)
+            }
             TransactionCommand::Show {
                 name,
                 transaction_id,
@@ -329,10 +497,171 @@ impl TransactionCommand {
                 success(hex::encode(tx_id).as_str());
                 Ok(())
             }
+            TransactionCommand::Pending { name } => {
+                display_pending_transactions(wallet_client, name)
+            }
+            TransactionCommand::Rebroadcast { name, id } => {
+                let txid = parse_txid(id)?;
+                let enckey = ask_seckey(None)?;
+                wallet_client.rebroadcast_transaction(name, &enckey, &txid)?;
+                success(&format!("Transaction {} broadcast again", id));
+                Ok(())
+            }
+            TransactionCommand::Replace { name, id, fee } => {
+                let txid = parse_txid(id)?;
+                let enckey = ask_seckey(None)?;
+                let new_txid = wallet_client.replace_transaction(name, &enckey, &txid, *fee)?;
+                success(&format!(
+                    "Transaction {} replaced by {}",
+                    id,
+                    hex::encode(new_txid)
+                ));
+                Ok(())
+            }
+            TransactionCommand::Abandon { name, id } => {
+                let txid = parse_txid(id)?;
+                ask("Warning! The abandoned transaction may still be included in a block, then the transactions spending its inputs are rejected.\nAre you sure to proceed? [yN]");
+                match yesno(false).chain(|| (ErrorKind::IoError, "Unable to read yes/no"))? {
+                    None => return Err(ErrorKind::InvalidInput.into()),
+                    Some(value) => {
+                        if !value {
+                            return Err(Error::new(ErrorKind::InvalidInput, "User canceled"));
+                        }
+                    }
+                }
+                let enckey = ask_seckey(None)?;
+                wallet_client.abandon_transaction(name, &enckey, &txid)?;
+                success(&format!("Transaction {} abandoned", id));
+                Ok(())
+            }
         }
     }
 }
 
+/// Parses the `address,amount` lines (empty lines and the ones starting with `#` are skipped)
+fn parse_recipients(csv: &str) -> Result<Vec<(ExtendedAddr, Coin)>> {
+    csv.lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+        .map(|(line_number, line)| {
+            let columns = line.split(',').map(str::trim).collect::<Vec<&str>>();
+            if columns.len() != 2 {
+                return Err(Error::new(
+                    ErrorKind::DeserializationError,
+                    format!("Line {} should be `address,amount`", line_number),
+                ));
+            }
+            let address = columns[0].parse::<ExtendedAddr>().chain(|| {
+                (
+                    ErrorKind::DeserializationError,
+                    format!("Invalid transfer address on line {}", line_number),
+                )
+            })?;
+            let amount = coin_from_str(columns[1])?;
+            Ok((address, amount))
+        })
+        .collect()
+}
+
+fn send_batch_payment<T: WalletClient>(wallet_client: &T, name: &str, file: &Path) -> Result<()> {
+    let csv =
+        std::fs::read_to_string(file).chain(|| (ErrorKind::IoError, "Unable to read from file"))?;
+    let recipients = parse_recipients(&csv)?;
+    let total = sum_coins(recipients.iter().map(|(_, amount)| *amount)).chain(|| {
+        (
+            ErrorKind::IllegalInput,
+            "Sum of the payments exceeds maximum allowed amount",
+        )
+    })?;
+    let enckey = ask_seckey(None)?;
+    let mut view_keys = ask_view_keys()?.into_iter().collect();
+
+    ask(&format!(
+        "Send {} to {} recipients? [yN] ",
+        total,
+        recipients.len()
+    ));
+    match yesno(false).chain(|| (ErrorKind::IoError, "Unable to read yes/no"))? {
+        None => return Err(ErrorKind::InvalidInput.into()),
+        Some(value) => {
+            if !value {
+                return Err(Error::new(ErrorKind::InvalidInput, "User canceled"));
+            }
+        }
+    }
+
+    let txids = wallet_client.send_to_addresses(
+        name,
+        &enckey,
+        recipients.clone(),
+        &mut view_keys,
+        get_network_id(),
+    )?;
+
+    let bold = CellFormat::builder().bold(true).build();
+    let right_justify = CellFormat::builder().justify(Justify::Right).build();
+    let mut rows = vec![Row::new(vec![
+        Cell::new("Address", bold),
+        Cell::new("Amount", bold),
+        Cell::new("Transaction ID", bold),
+    ])];
+    for ((address, amount), txid) in recipients.iter().zip(txids.iter()) {
+        rows.push(Row::new(vec![
+            Cell::new(address, Default::default()),
+            Cell::new(amount, right_justify),
+            Cell::new(&hex::encode(txid), Default::default()),
+        ]));
+    }
+    let table = Table::new(rows, Default::default())
+        .chain(|| (ErrorKind::InternalError, "Unable to create new table"))?;
+    table
+        .print_stdout()
+        .chain(|| (ErrorKind::IoError, "Unable to print table"))?;
+    Ok(())
+}
+
+fn parse_txid(id: &str) -> Result<TxId> {
+    str2txid(id).chain(|| (ErrorKind::InvalidInput, "Invalid transaction ID"))
+}
+
+fn display_pending_transactions<T: WalletClient>(wallet_client: &T, name: &str) -> Result<()> {
+    let enckey = ask_seckey(None)?;
+    let pending_transactions = wallet_client.pending_transactions(name, &enckey)?;
+    if pending_transactions.is_empty() {
+        success("No pending transactions found!");
+        return Ok(());
+    }
+
+    let bold = CellFormat::builder().bold(true).build();
+    let right_justify = CellFormat::builder().justify(Justify::Right).build();
+    let yes_no = |value: bool| if value { "yes" } else { "no" };
+    let mut rows = vec![Row::new(vec![
+        Cell::new("Transaction ID", bold),
+        Cell::new("Block Height", bold),
+        Cell::new("Age (blocks)", bold),
+        Cell::new("Return Amount", bold),
+        Cell::new("Rebroadcastable", bold),
+        Cell::new("Replaceable", bold),
+    ])];
+    for info in pending_transactions.iter() {
+        rows.push(Row::new(vec![
+            Cell::new(&hex::encode(&info.transaction_id), Default::default()),
+            Cell::new(&info.pending.block_height, right_justify),
+            Cell::new(&info.age, right_justify),
+            Cell::new(&info.pending.return_amount, right_justify),
+            Cell::new(yes_no(info.rebroadcastable), Default::default()),
+            Cell::new(yes_no(info.replaceable), Default::default()),
+        ]));
+    }
+    let table = Table::new(rows, Default::default())
+        .chain(|| (ErrorKind::InternalError, "Unable to create new table"))?;
+    table
+        .print_stdout()
+        .chain(|| (ErrorKind::IoError, "Unable to print table"))?;
+    Ok(())
+}
+
 fn display_transaction<T: WalletClient>(
     wallet_client: &T,
     name: &str,
@@ -489,13 +818,14 @@ fn display_transaction<T: WalletClient>(
                 {
                     let valid_from = match output.valid_from {
                         None => "Not time-locked".to_string(),
-                        Some(valid_from) => {
+                        Some(Timelock::Time(valid_from)) => {
                             let valid_from = <DateTime<Local>>::from(DateTime::<Utc>::from_utc(
                                 NaiveDateTime::from_timestamp(valid_from.try_into().unwrap(), 0),
                                 Utc,
                             ));
                             valid_from.to_string()
                         }
+                        Some(timelock) => timelock.to_string(),
                     };
 
                     outputs_rows.push(Row::new(vec![
@@ -530,6 +860,7 @@ fn new_transaction<T: WalletClient, N: NetworkOpsClient>(
     transaction_type: &TransactionType,
     advanced: bool,
     keypackage: Option<PathBuf>,
+    input_selection: Option<InputSelection>,
 ) -> Result<()> {
     let can_use_advanced = vec![TransactionType::Deposit];
     if advanced && !can_use_advanced.contains(transaction_type) {
@@ -539,25 +870,28 @@ fn new_transaction<T: WalletClient, N: NetworkOpsClient>(
         );
         return Err(error);
     }
+    if input_selection.is_some() && *transaction_type != TransactionType::Transfer {
+        let error = Error::new(
+            ErrorKind::InvalidInput,
+            "input selection is only available when transfer",
+        );
+        return Err(error);
+    }
     let enckey = ask_seckey(None)?;
 
     match transaction_type {
         TransactionType::Transfer => {
-            let (tx_aux, tx_pending) = new_transfer_transaction(wallet_client, name, &enckey)?;
+            let (tx_aux, tx_pending) =
+                new_transfer_transaction(wallet_client, name, &enckey, input_selection)?;
             wallet_client.broadcast_transaction(&tx_aux)?;
-            wallet_client.update_tx_pending_state(&name, &enckey, tx_aux.tx_id(), tx_pending)?;
+            wallet_client.update_tx_pending_state(&name, &enckey, &tx_aux, tx_pending)?;
         }
         TransactionType::Deposit => {
             if advanced {
                 let (tx_aux, tx_pending) =
                     new_deposit_transaction(wallet_client, network_ops_client, name, &enckey)?;
                 wallet_client.broadcast_transaction(&tx_aux)?;
-                wallet_client.update_tx_pending_state(
-                    &name,
-                    &enckey,
-                    tx_aux.tx_id(),
-                    tx_pending,
-                )?;
+                wallet_client.update_tx_pending_state(&name, &enckey, &tx_aux, tx_pending)?;
             } else {
                 new_deposit_amount_transaction(wallet_client, network_ops_client, name, &enckey)?;
             }
@@ -570,7 +904,7 @@ fn new_transaction<T: WalletClient, N: NetworkOpsClient>(
             let (tx_aux, tx_pending) =
                 new_withdraw_transaction(wallet_client, network_ops_client, name, &enckey)?;
             wallet_client.broadcast_transaction(&tx_aux)?;
-            wallet_client.update_tx_pending_state(&name, &enckey, tx_aux.tx_id(), tx_pending)?;
+            wallet_client.update_tx_pending_state(&name, &enckey, &tx_aux, tx_pending)?;
         }
         TransactionType::Unjail => {
             let tx_aux = new_unjail_transaction(network_ops_client, name, &enckey)?;
@@ -580,6 +914,14 @@ fn new_transaction<T: WalletClient, N: NetworkOpsClient>(
             let tx_aux = new_node_join_transaction(network_ops_client, name, &enckey, keypackage)?;
             wallet_client.broadcast_transaction(&tx_aux)?;
         }
+        TransactionType::NodeMetadataUpdate => {
+            let tx_aux = new_node_metadata_update_transaction(network_ops_client, name, &enckey)?;
+            wallet_client.broadcast_transaction(&tx_aux)?;
+        }
+        TransactionType::TransferStake => {
+            let tx_aux = new_transfer_stake_transaction(network_ops_client, name, &enckey)?;
+            wallet_client.broadcast_transaction(&tx_aux)?;
+        }
     };
 
     success("Transaction successfully created!");
@@ -783,7 +1125,7 @@ fn new_deposit_amount_transaction<T: WalletClient, N: NetworkOpsClient>(
         hex::encode(tx_id)
     ));
     wallet_client.broadcast_transaction(&transaction)?;
-    wallet_client.update_tx_pending_state(&name, &enckey, transaction.tx_id(), tx_pending)?;
+    wallet_client.update_tx_pending_state(&name, &enckey, &transaction, tx_pending)?;
     Ok(())
 }
 
@@ -791,6 +1133,7 @@ fn new_transfer_transaction<T: WalletClient>(
     wallet_client: &T,
     name: &str,
     enckey: &SecKey,
+    input_selection: Option<InputSelection>,
 ) -> Result<(TxAux, TransactionPending)> {
     let outputs = ask_outputs()?;
     let mut view_keys = ask_view_keys()?;
@@ -814,7 +1157,7 @@ fn new_transfer_transaction<T: WalletClient>(
         &enckey,
         outputs,
         attributes,
-        None,
+        input_selection,
         return_address,
     )?;
     let tx_pending = TransactionPending {
@@ -856,6 +1199,52 @@ fn new_node_join_transaction<N: NetworkOpsClient>(
     )
 }
 
+fn new_node_metadata_update_transaction<N: NetworkOpsClient>(
+    network_ops_client: &N,
+    name: &str,
+    enckey: &SecKey,
+) -> Result<TxAux> {
+    let attributes = StakedStateOpAttributes::new(get_network_id());
+    let staking_account_address = ask_staking_address()?;
+
+    ask("Enter new validator node name (leave blank to keep the current one): ");
+    let node_name = text().chain(|| (ErrorKind::IoError, "Unable to read validator node name"))?;
+    let node_name = if node_name.is_empty() {
+        None
+    } else {
+        Some(node_name)
+    };
+
+    ask("Enter new security contact (leave blank to keep the current one, \"-\" to remove it): ");
+    let security_contact =
+        text().chain(|| (ErrorKind::IoError, "Unable to read security contact"))?;
+    let security_contact = match security_contact.as_str() {
+        "" => None,
+        "-" => Some(None),
+        _ => Some(Some(security_contact)),
+    };
+
+    ask("Enter new validator pub-key (base64 encoded) (leave blank to keep the current one): ");
+    let validator_pubkey =
+        text().chain(|| (ErrorKind::IoError, "Unable to read validator pub-key"))?;
+    let consensus_pubkey = if validator_pubkey.is_empty() {
+        None
+    } else {
+        Some(parse_validator_pubkey(&validator_pubkey)?)
+    };
+
+    network_ops_client.create_node_metadata_update_transaction(
+        name,
+        enckey,
+        staking_account_address,
+        attributes,
+        node_name,
+        security_contact,
+        consensus_pubkey,
+        true,
+    )
+}
+
 fn ask_view_keys() -> Result<Vec<PublicKey>> {
     ask(
         "Enter view keys (comma separated) (leave blank if you don't want any additional view keys in transaction): ",
@@ -894,23 +1283,16 @@ fn ask_outputs() -> Result<Vec<TxOut>> {
         })?;
         let amount = ask_cro()?;
 
-        ask(
-            "Enter timelock (seconds from UNIX epoch) (leave blank if output is not time locked): ",
-        );
+        ask("Enter timelock (seconds from UNIX epoch, `h<height>` for block height, `+<seconds>` or `+h<blocks>` after inclusion) (leave blank if output is not time locked): ");
         let timelock = text().chain(|| (ErrorKind::IoError, "Unable to read timelock value"))?;
 
         if timelock.is_empty() {
             outputs.push(TxOut::new(address, amount));
         } else {
-            outputs.push(TxOut::new_with_timelock(
+            outputs.push(TxOut::new_with_lock(
                 address,
                 amount,
-                timelock.parse::<Timespec>().chain(|| {
-                    (
-                        ErrorKind::DeserializationError,
-                        "Unable to parse timelock into integer",
-                    )
-                })?,
+                parse_timelock(&timelock)?,
             ));
         }
 
@@ -924,6 +1306,29 @@ fn ask_outputs() -> Result<Vec<TxOut>> {
     Ok(outputs)
 }
 
+fn parse_timelock(timelock: &str) -> Result<Timelock> {
+    let parse_u64 = |value: &str| {
+        value.parse::<u64>().chain(|| {
+            (
+                ErrorKind::DeserializationError,
+                "Unable to parse timelock into integer",
+            )
+        })
+    };
+
+    if timelock.starts_with("+h") {
+        Ok(Timelock::RelativeHeight(parse_u64(&timelock[2..])?))
+    } else if timelock.starts_with('+') {
+        Ok(Timelock::RelativeTime(parse_u64(&timelock[1..])?))
+    } else if timelock.starts_with('h') {
+        Ok(Timelock::Height(BlockHeight::new(parse_u64(
+            &timelock[1..],
+        )?)))
+    } else {
+        Ok(Timelock::Time(parse_u64(timelock)?))
+    }
+}
+
 fn ask_cro() -> Result<Coin> {
     loop {
         ask("Enter amount (in CRO): ");
@@ -944,6 +1349,40 @@ fn ask_cro() -> Result<Coin> {
     }
 }
 
+fn txo_pointer_from_str(pointer: &str) -> Result<TxoPointer> {
+    let parts = pointer.split(':').collect::<Vec<&str>>();
+    if parts.len() != 2 {
+        return Err(Error::new(
+            ErrorKind::DeserializationError,
+            format!(
+                "Transaction output should be <transaction id>:<index>: {}",
+                pointer
+            ),
+        ));
+    }
+    let transaction_id_decoded = decode(parts[0]).chain(|| {
+        (
+            ErrorKind::DeserializationError,
+            "Unable to deserialize transaction ID from bytes",
+        )
+    })?;
+    if transaction_id_decoded.len() != HASH_SIZE_256 {
+        return Err(Error::new(
+            ErrorKind::DeserializationError,
+            "Transaction ID should be of 32 bytes",
+        ));
+    }
+    let mut transaction_id: [u8; HASH_SIZE_256] = [0; HASH_SIZE_256];
+    transaction_id.copy_from_slice(&transaction_id_decoded);
+    let index = parts[1].parse::<usize>().chain(|| {
+        (
+            ErrorKind::DeserializationError,
+            "Unable to parse input index into integer",
+        )
+    })?;
+    Ok(TxoPointer::new(transaction_id, index))
+}
+
 fn ask_inputs() -> Result<Vec<TxoPointer>> {
     let mut inputs = Vec::new();
 
@@ -994,6 +1433,42 @@ fn ask_inputs() -> Result<Vec<TxoPointer>> {
     Ok(inputs)
 }
 
+fn new_transfer_stake_transaction<N: NetworkOpsClient>(
+    network_ops_client: &N,
+    name: &str,
+    enckey: &SecKey,
+) -> Result<TxAux> {
+    let attributes = StakedStateOpAttributes::new(get_network_id());
+    let from_address = ask_staking_address()?;
+    ask("Enter destination staking address: ");
+    let to_address = text()
+        .chain(|| (ErrorKind::IoError, "Unable to read staking address"))?
+        .parse::<StakedStateAddress>()
+        .chain(|| {
+            (
+                ErrorKind::DeserializationError,
+                "Unable to deserialize staking address",
+            )
+        })?;
+    let value = ask_cro()?;
+    ask("Transfer bonded coins? (unbonded coins are transferred otherwise) [yN] ");
+    let kind = match yesno(false).chain(|| (ErrorKind::IoError, "Unable to read yes/no"))? {
+        None => return Err(ErrorKind::InvalidInput.into()),
+        Some(true) => StakeKind::Bonded,
+        Some(false) => StakeKind::Unbonded,
+    };
+    network_ops_client.create_transfer_stake_transaction(
+        name,
+        enckey,
+        from_address,
+        to_address,
+        value,
+        kind,
+        attributes,
+        true,
+    )
+}
+
 fn ask_staking_address() -> Result<StakedStateAddress> {
     ask("Enter staking address: ");
     let address = text()
@@ -1155,7 +1630,18 @@ fn ask_node_metadata(keypackage: Option<PathBuf>) -> Result<CouncilNodeMeta> {
     let info = keypackage_info(&keypackage)?;
     success(&info);
 
-    let decoded_pubkey = base64::decode(&validator_pubkey).chain(|| {
+    Ok(CouncilNodeMeta::new_with_details(
+        name,
+        None,
+        parse_validator_pubkey(&validator_pubkey)?,
+        ConfidentialInit {
+            keypackage: keypackage_raw,
+        },
+    ))
+}
+
+fn parse_validator_pubkey(validator_pubkey: &str) -> Result<TendermintValidatorPubKey> {
+    let decoded_pubkey = base64::decode(validator_pubkey).chain(|| {
         (
             ErrorKind::DeserializationError,
             "Unable to decode base64 encoded bytes of validator pub-key",
@@ -1172,12 +1658,5 @@ fn ask_node_metadata(keypackage: Option<PathBuf>) -> Result<CouncilNodeMeta> {
     let mut pubkey_bytes = [0; 32];
     pubkey_bytes.copy_from_slice(&decoded_pubkey);
 
-    Ok(CouncilNodeMeta::new_with_details(
-        name,
-        None,
-        TendermintValidatorPubKey::Ed25519(pubkey_bytes),
-        ConfidentialInit {
-            keypackage: keypackage_raw,
-        },
-    ))
+    Ok(TendermintValidatorPubKey::Ed25519(pubkey_bytes))
 }
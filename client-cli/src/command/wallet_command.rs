@@ -8,6 +8,7 @@ use client_core::{Mnemonic, WalletClient};
 
 use crate::{ask_passphrase, ask_seckey};
 use client_core::service::WalletInfo;
+use client_core::wallet::backup::{BackupParts, EncryptedWalletBackup};
 use client_core::wallet::WalletRequest;
 use std::fs::File;
 use std::io::Write;
@@ -81,6 +82,60 @@ pub enum WalletCommand {
         )]
         file: PathBuf,
     },
+    #[structopt(
+        name = "backup",
+        about = "Backup wallets to a passphrase encrypted file"
+    )]
+    Backup {
+        #[structopt(
+            name = "wallet name",
+            short = "n",
+            long = "name",
+            help = "Name of wallet (comma separated if many)"
+        )]
+        name: String,
+        #[structopt(
+            name = "to_file",
+            short = "t",
+            long = "to_file",
+            parse(from_os_str),
+            help = "file to write the encrypted backup"
+        )]
+        to_file: PathBuf,
+        #[structopt(
+            name = "no_history",
+            long = "no_history",
+            help = "Only backup the keys (without the sync state and transaction history)"
+        )]
+        no_history: bool,
+    },
+    #[structopt(
+        name = "restore-backup",
+        about = "Restore wallets from a passphrase encrypted backup file"
+    )]
+    RestoreBackup {
+        #[structopt(
+            name = "file",
+            short = "f",
+            long = "file",
+            parse(from_os_str),
+            help = "file of the encrypted backup"
+        )]
+        file: PathBuf,
+        #[structopt(
+            name = "wallet name",
+            short = "n",
+            long = "name",
+            help = "Name of the backed up wallet to restore (comma separated if many, all if not set)"
+        )]
+        name: Option<String>,
+        #[structopt(
+            name = "no_history",
+            long = "no_history",
+            help = "Only restore the keys (the wallet needs to be synchronized again)"
+        )]
+        no_history: bool,
+    },
     #[structopt(name = "list", about = "List all wallets")]
     List,
     #[structopt(name = "restore", about = "Restore HD Wallet")]
@@ -144,6 +199,16 @@ impl WalletCommand {
                 to_file,
             } => Self::export(wallet_client, name, from_file, to_file),
             WalletCommand::Import { file } => Self::import(wallet_client, file),
+            WalletCommand::Backup {
+                name,
+                to_file,
+                no_history,
+            } => Self::backup(wallet_client, name, to_file, *no_history),
+            WalletCommand::RestoreBackup {
+                file,
+                name,
+                no_history,
+            } => Self::restore_backup(wallet_client, file, name, *no_history),
         }
     }
 
@@ -289,6 +354,103 @@ impl WalletCommand {
         Ok(())
     }
 
+    fn backup<T: WalletClient>(
+        wallet_client: T,
+        names: &str,
+        to_file: &PathBuf,
+        no_history: bool,
+    ) -> Result<()> {
+        let parts = if no_history {
+            BackupParts::keys_only()
+        } else {
+            BackupParts::all()
+        };
+        let mut backups = vec![];
+        for name in names.split(',') {
+            let enckey = ask_seckey(Some(&format!("Enter authentication token for {}: ", name)))?;
+            backups.push(wallet_client.backup_wallet(name, &enckey, parts)?);
+        }
+
+        let passphrase = ask_passphrase(Some("Enter backup passphrase: "))?;
+        let confirmed_passphrase = ask_passphrase(Some("Confirm backup passphrase: "))?;
+        if passphrase != confirmed_passphrase {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "Passphrases do not match",
+            ));
+        }
+        let sealed = EncryptedWalletBackup::seal(&backups, &passphrase)?;
+        let sealed_str = serde_json::to_string_pretty(&sealed).chain(|| {
+            (
+                ErrorKind::SerializationError,
+                "Inner serialize wallet backup error",
+            )
+        })?;
+        let mut file =
+            File::create(to_file).chain(|| (ErrorKind::IoError, "Unable to create file"))?;
+        file.write_all(sealed_str.as_bytes())
+            .chain(|| (ErrorKind::IoError, "Unable to write to file"))?;
+        success(&format!(
+            "Backup {} wallet to file {:?} success",
+            backups.len(),
+            to_file
+        ));
+        Ok(())
+    }
+
+    fn restore_backup<T: WalletClient>(
+        wallet_client: T,
+        file: &PathBuf,
+        names: &Option<String>,
+        no_history: bool,
+    ) -> Result<()> {
+        let sealed_str = std::fs::read_to_string(file)
+            .chain(|| (ErrorKind::IoError, "Unable to read from file"))?;
+        let sealed: EncryptedWalletBackup = serde_json::from_str(&sealed_str)
+            .chain(|| (ErrorKind::InvalidInput, "Invalid wallet backup"))?;
+        let passphrase = ask_passphrase(Some("Enter backup passphrase: "))?;
+        let backups = sealed.open(&passphrase)?;
+
+        let selected = names
+            .as_ref()
+            .map(|names| names.split(',').collect::<Vec<_>>());
+        if let Some(selected) = &selected {
+            for name in selected.iter() {
+                if !backups.iter().any(|backup| backup.name() == *name) {
+                    return Err(Error::new(
+                        ErrorKind::InvalidInput,
+                        format!("wallet {} not found in the backup", name),
+                    ));
+                }
+            }
+        }
+        for backup in backups {
+            let name = backup.name().to_owned();
+            if let Some(selected) = &selected {
+                if !selected.contains(&name.as_str()) {
+                    continue;
+                }
+            }
+            // a keys only backup is restored without the history
+            let parts = if no_history {
+                BackupParts::keys_only()
+            } else {
+                backup.parts
+            };
+            let passphrase =
+                ask_passphrase(Some(&format!("Input passphrase for wallet {}:", name)))?;
+            match wallet_client.restore_wallet_backup(&name, &passphrase, backup, parts) {
+                Ok(enckey) => success(&format!(
+                    "Authentication token of wallet {}: {}",
+                    name,
+                    &hex::encode(enckey.unsecure())
+                )),
+                Err(e) => error(&format!("Restore wallet {} failed: {:?}", name, e)),
+            }
+        }
+        Ok(())
+    }
+
     fn restore_wallet<T: WalletClient>(wallet_client: T, name: &str) -> Result<()> {
         let passphrase = ask_passphrase(None)?;
         let confirmed_passphrase = ask_passphrase(Some("Confirm passphrase: "))?;
@@ -8,7 +8,7 @@ pub use mock::MockAbciTransactionObfuscation;
 
 use crate::{PrivateKey, Result, SignedTransaction, Transaction};
 use chain_core::tx::data::TxId;
-use chain_core::tx::TxAux;
+use chain_core::tx::{TxAux, TxRestrictedView};
 
 /// Interface for encryption and decryption of transactions
 pub trait TransactionObfuscation: Send + Sync + Clone {
@@ -20,6 +20,17 @@ pub trait TransactionObfuscation: Send + Sync + Clone {
         private_key: &PrivateKey,
     ) -> Result<Vec<Transaction>>;
 
+    /// Retrieves decrypted transactions with given ids together with the views of the transactions where the
+    /// private key was only given a restricted access (`TxAccess::Outputs` or `TxAccess::Metadata`).
+    /// Obfuscations which can't disclose restricted views return only the fully decrypted transactions.
+    fn decrypt_with_restricted(
+        &self,
+        transaction_ids: &[TxId],
+        private_key: &PrivateKey,
+    ) -> Result<(Vec<Transaction>, Vec<TxRestrictedView>)> {
+        Ok((self.decrypt(transaction_ids, private_key)?, Vec::new()))
+    }
+
     /// Encrypts a signed transaction
     fn encrypt(&self, transaction: SignedTransaction) -> Result<TxAux>;
 }
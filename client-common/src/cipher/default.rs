@@ -10,7 +10,7 @@ use crate::{
     tendermint::{types::AbciQueryExt, Client},
     Error, ErrorKind, PrivateKey, Result, ResultExt, SignedTransaction, Transaction, SECP,
 };
-use chain_core::tx::{data::TxId, TxAux, TxWithOutputs};
+use chain_core::tx::{data::TxId, TxAux, TxRestrictedView, TxWithOutputs};
 use enclave_protocol::{
     DecryptionRequest, DecryptionResponse, EncryptionRequest, EncryptionResponse,
     TxQueryInitRequest, TxQueryInitResponse,
@@ -90,8 +90,17 @@ impl TransactionObfuscation for DefaultTransactionObfuscation {
         transaction_ids: &[TxId],
         private_key: &PrivateKey,
     ) -> Result<Vec<Transaction>> {
+        self.decrypt_with_restricted(transaction_ids, private_key)
+            .map(|(transactions, _)| transactions)
+    }
+
+    fn decrypt_with_restricted(
+        &self,
+        transaction_ids: &[TxId],
+        private_key: &PrivateKey,
+    ) -> Result<(Vec<Transaction>, Vec<TxRestrictedView>)> {
         if transaction_ids.is_empty() {
-            return Ok(vec![]);
+            return Ok((vec![], vec![]));
         }
 
         let client_config = get_tls_config()?;
@@ -163,9 +172,8 @@ impl TransactionObfuscation for DefaultTransactionObfuscation {
                 Ok(_) => {
                     let mresp = DecryptionResponse::decode(&mut plaintext.as_slice());
                     if let Ok(resp) = mresp {
-                        let txs = resp.txs;
-
-                        let transactions = txs
+                        let transactions = resp
+                            .txs
                             .into_iter()
                             .map(|tx| match tx {
                                 TxWithOutputs::Transfer(t) => Transaction::TransferTransaction(t),
@@ -175,7 +183,7 @@ impl TransactionObfuscation for DefaultTransactionObfuscation {
                             })
                             .collect::<Vec<Transaction>>();
 
-                        Ok(transactions)
+                        Ok((transactions, resp.restricted_txs))
                     } else {
                         Err(Error::new(
                             ErrorKind::DeserializationError,
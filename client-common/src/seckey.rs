@@ -63,6 +63,78 @@ pub fn derive_enckey(passphrase: &SecUtf8, name: &str) -> argon2::Result<SecKey>
     Ok(SecKey(SecBox::new(Box::new(arr))))
 }
 
+/// Argon2id cost parameters of the passphrase key derivation
+/// (stored next to the data encrypted with the derived key, so that they can be raised later)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KdfParams {
+    /// memory size in KiB
+    pub mem_cost: u32,
+    /// number of iterations
+    pub time_cost: u32,
+    /// degree of parallelism
+    pub lanes: u32,
+}
+
+impl KdfParams {
+    /// maximum memory size in KiB (1 GiB)
+    pub const MAX_MEM_COST: u32 = 1024 * 1024;
+    /// maximum number of iterations
+    pub const MAX_TIME_COST: u32 = 64;
+    /// maximum degree of parallelism
+    pub const MAX_LANES: u32 = 16;
+
+    /// Checks the parameters (e.g. read from a backup) don't exceed the maximums,
+    /// so that the key derivation can't exhaust the memory or run for hours
+    pub fn validate(&self) -> Result<()> {
+        if self.mem_cost > Self::MAX_MEM_COST
+            || self.time_cost > Self::MAX_TIME_COST
+            || self.lanes > Self::MAX_LANES
+        {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "key derivation parameters exceed the maximums (memory: {} KiB, iterations: {}, lanes: {})",
+                    Self::MAX_MEM_COST,
+                    Self::MAX_TIME_COST,
+                    Self::MAX_LANES
+                ),
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl Default for KdfParams {
+    fn default() -> Self {
+        KdfParams {
+            mem_cost: 65536,
+            time_cost: 3,
+            lanes: 4,
+        }
+    }
+}
+
+/// derive encryption key from passphrase with the random salt (e.g. for wallet backups
+/// that can be restored under a different wallet name)
+pub fn derive_enckey_with_salt(
+    passphrase: &SecUtf8,
+    salt: &[u8],
+    params: &KdfParams,
+) -> argon2::Result<SecKey> {
+    let config = argon2::Config {
+        variant: argon2::Variant::Argon2id,
+        mem_cost: params.mem_cost,
+        time_cost: params.time_cost,
+        lanes: params.lanes,
+        hash_length: SecKeySize::to_u32(),
+        ..Default::default()
+    };
+    let mut raw = argon2::hash_raw(passphrase.unsecure().as_bytes(), salt, &config)?;
+    let arr = GenericArray::clone_from_slice(&raw);
+    raw.zeroize();
+    Ok(SecKey(SecBox::new(Box::new(arr))))
+}
+
 impl<'de> Deserialize<'de> for SecKey {
     fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
     where
@@ -98,6 +170,25 @@ mod tests {
         );
     }
 
+    #[test]
+    fn check_enckey_with_salt_depends_on_salt() {
+        let passphrase = SecUtf8::from("passphrase");
+        let params = KdfParams {
+            mem_cost: 64,
+            time_cost: 1,
+            lanes: 1,
+        };
+        let key = derive_enckey_with_salt(&passphrase, &[1; 16], &params).unwrap();
+        assert_eq!(
+            derive_enckey_with_salt(&passphrase, &[1; 16], &params).unwrap(),
+            key
+        );
+        assert_ne!(
+            derive_enckey_with_salt(&passphrase, &[2; 16], &params).unwrap(),
+            key
+        );
+    }
+
     quickcheck! {
         fn check_serialization(passphrase: String, name: String) -> bool {
             let key = derive_enckey(&SecUtf8::from(passphrase), &name).unwrap();
@@ -6,6 +6,7 @@ mod unauthorized_client;
 
 pub mod lite;
 pub mod mock;
+pub mod proof;
 pub mod types;
 
 pub use client::Client;
@@ -1,6 +1,11 @@
+use parity_scale_codec::{Decode, Encode};
+
 use crate::tendermint::types::*;
-use crate::Result;
+use crate::{ErrorKind, Result, ResultExt};
+use chain_core::state::tendermint::BlockHeight;
 use chain_core::state::ChainState;
+use chain_core::tx::data::input::{TxoPointer, TxoSpent};
+use chain_core::tx::data::TxId;
 
 /// Makes remote calls to tendermint (backend agnostic)
 pub trait Client: Send + Sync + Clone {
@@ -42,4 +47,48 @@ pub trait Client: Send + Sync + Clone {
 
     /// Match batch state `abci_query` call to tendermint
     fn query_state_batch<T: Iterator<Item = u64>>(&self, heights: T) -> Result<Vec<ChainState>>;
+
+    /// Queries the transaction which spent the output and the height of its block
+    /// (`None` if the output wasn't spent; the node needs to have the utxo index enabled)
+    fn query_txo_spent(&self, txo: &TxoPointer) -> Result<Option<TxoSpent>> {
+        let bytes = self.query("spent-by", &txo.encode(), None, false)?.bytes();
+        if bytes.is_empty() {
+            return Ok(None);
+        }
+        TxoSpent::decode(&mut bytes.as_slice()).map(Some).chain(|| {
+            (
+                ErrorKind::DeserializationError,
+                "Cannot deserialize spent output",
+            )
+        })
+    }
+
+    /// Queries the height of the block which included the transaction
+    /// (the node needs to have the utxo index enabled)
+    fn query_tx_height(&self, txid: &TxId) -> Result<BlockHeight> {
+        let bytes = self.query("tx-height", &txid[..], None, false)?.bytes();
+        BlockHeight::decode(&mut bytes.as_slice()).chain(|| {
+            (
+                ErrorKind::DeserializationError,
+                "Cannot deserialize block height",
+            )
+        })
+    }
+
+    /// Finds the inputs of the (e.g. pending) transaction which were spent by a different transaction
+    fn query_conflicting_spends(
+        &self,
+        txid: &TxId,
+        inputs: &[TxoPointer],
+    ) -> Result<Vec<(TxoPointer, TxoSpent)>> {
+        let mut conflicts = Vec::new();
+        for input in inputs.iter() {
+            if let Some(spent) = self.query_txo_spent(input)? {
+                if spent.spent_by != *txid {
+                    conflicts.push((input.clone(), spent));
+                }
+            }
+        }
+        Ok(conflicts)
+    }
 }
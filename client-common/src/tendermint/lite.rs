@@ -3,8 +3,12 @@ use parity_scale_codec::{Decode, Encode, Error, Input, Output};
 use serde::{Deserialize, Serialize};
 use tendermint::{block::signed_header::SignedHeader, block::Header, lite, validator};
 
+use chain_core::tx::data::TxId;
+
 use crate::tendermint::client::Client;
-use crate::Result as CommonResult;
+use crate::tendermint::proof::verify_tx_inclusion_query;
+use crate::tendermint::types::AbciQuery;
+use crate::{ErrorKind, Result as CommonResult, ResultExt};
 
 ///
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -16,6 +20,23 @@ impl TrustedState {
         // FIXME verify the first block against genesis block.
         TrustedState(None)
     }
+
+    /// the last trusted header (if any)
+    pub fn last_header(&self) -> Option<&Header> {
+        self.0.as_ref().map(|state| state.last_header().header())
+    }
+
+    /// Verifies the proof of "store" query (for the block before the last trusted header)
+    /// that the transaction was included in the chain
+    pub fn verify_tx_inclusion(&self, query: &AbciQuery, txid: &TxId) -> CommonResult<()> {
+        let header = self.last_header().chain(|| {
+            (
+                ErrorKind::VerifyError,
+                "There is no trusted header to verify the transaction against",
+            )
+        })?;
+        verify_tx_inclusion_query(query, txid, header)
+    }
 }
 
 impl From<lite::TrustedState<SignedHeader, Header>> for TrustedState {
@@ -0,0 +1,230 @@
+//! Verification of ABCI query proofs against light client trusted headers
+use parity_scale_codec::Decode;
+
+use chain_core::common::Proof as MerkleProof;
+use chain_core::tx::data::TxId;
+use chain_core::AppHashParts;
+use tendermint::merkle::proof::ProofOp;
+
+use crate::tendermint::types::{AbciQuery, Header};
+use crate::{Error, ErrorKind, Result, ResultExt};
+
+/// Verifies the "app_hash" proof op against the app hash in the trusted header
+/// and returns the proven parts of the app hash
+pub fn verify_app_hash_proof_op(op: &ProofOp, trusted_header: &Header) -> Result<AppHashParts> {
+    if op.field_type != "app_hash" {
+        return Err(Error::new(
+            ErrorKind::VerifyError,
+            format!("Unexpected proof op type: {}", op.field_type),
+        ));
+    }
+    let parts = AppHashParts::decode(&mut op.data.as_slice()).chain(|| {
+        (
+            ErrorKind::DeserializationError,
+            "Cannot deserialize app hash parts",
+        )
+    })?;
+    if &parts.app_hash()[..] != trusted_header.app_hash.as_ref() {
+        return Err(Error::new(
+            ErrorKind::VerifyError,
+            "App hash in the proof doesn't match the trusted header",
+        ));
+    }
+    Ok(parts)
+}
+
+/// Checks that the query result is for the state committed in the trusted header
+/// (the app hash of block N is included in the header of block N+1)
+pub fn check_query_height(query: &AbciQuery, trusted_header: &Header) -> Result<()> {
+    if query.height.value() + 1 != trusted_header.height.value() {
+        return Err(Error::new(
+            ErrorKind::VerifyError,
+            format!(
+                "Query result at height {} can't be verified with the header at height {}",
+                query.height.value(),
+                trusted_header.height.value()
+            ),
+        ));
+    }
+    Ok(())
+}
+
+/// Verifies the proof of "store" query that the transaction was included in the queried block
+/// (transaction id -> txs merkle root -> app hash in the trusted header of the next block)
+pub fn verify_tx_inclusion_query(
+    query: &AbciQuery,
+    txid: &TxId,
+    trusted_header: &Header,
+) -> Result<()> {
+    check_query_height(query, trusted_header)?;
+    let ops = query
+        .proof
+        .as_ref()
+        .map(|proof| proof.ops.as_slice())
+        .unwrap_or_default();
+    let (transaction_op, app_hash_op) = match ops {
+        [transaction_op, app_hash_op, ..] if transaction_op.field_type == "transaction" => {
+            (transaction_op, app_hash_op)
+        }
+        _ => {
+            return Err(Error::new(
+                ErrorKind::VerifyError,
+                format!(
+                    "There is no valid proof for transaction: {}",
+                    hex::encode(txid)
+                ),
+            ))
+        }
+    };
+    let parts = verify_app_hash_proof_op(app_hash_op, trusted_header)?;
+    let proof = <MerkleProof<TxId>>::decode(&mut transaction_op.data.as_slice()).chain(|| {
+        (
+            ErrorKind::DeserializationError,
+            "Cannot deserialize transaction inclusion proof",
+        )
+    })?;
+    if proof.value() != txid || !proof.verify(&parts.valid_tx_root) {
+        return Err(Error::new(
+            ErrorKind::VerifyError,
+            format!(
+                "Verify inclusion of transaction {} failed",
+                hex::encode(txid)
+            ),
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use parity_scale_codec::Encode;
+    use tendermint::merkle::proof::Proof;
+
+    use super::*;
+    use crate::tendermint::mock;
+    use crate::tendermint::types::Height;
+    use chain_core::common::{MerkleTree, H256};
+    use chain_core::tx::fee::Milli;
+
+    fn app_hash_parts(valid_tx_root: H256) -> AppHashParts {
+        AppHashParts {
+            valid_tx_root,
+            account_root: [1; 32],
+            rewards_pool_hash: [2; 32],
+            network_params_hash: [3; 32],
+            mls_group_hash: [0; 32],
+            base_fee: Milli::integral(1).unwrap(),
+            stake_transfers_hash: [0; 32],
+        }
+    }
+
+    fn app_hash_op(parts: &AppHashParts) -> ProofOp {
+        ProofOp {
+            field_type: "app_hash".to_owned(),
+            key: parts.app_hash().to_vec(),
+            data: parts.encode(),
+        }
+    }
+
+    fn trusted_header(parts: &AppHashParts, height: u64) -> Header {
+        let mut header = mock::header();
+        header.app_hash = parts.app_hash().to_vec();
+        header.height = Height::from(height);
+        header
+    }
+
+    #[test]
+    fn check_app_hash_proof_op() {
+        let parts = app_hash_parts([0; 32]);
+        let header = trusted_header(&parts, 2);
+        assert_eq!(
+            verify_app_hash_proof_op(&app_hash_op(&parts), &header).unwrap(),
+            parts
+        );
+
+        // tampered parts don't hash to the trusted app hash
+        let mut tampered = parts;
+        tampered.account_root = [4; 32];
+        let mut op = app_hash_op(&parts);
+        op.data = tampered.encode();
+        assert_eq!(
+            ErrorKind::VerifyError,
+            verify_app_hash_proof_op(&op, &header).unwrap_err().kind()
+        );
+
+        // the trusted header has a different app hash
+        let header = trusted_header(&tampered, 2);
+        assert_eq!(
+            ErrorKind::VerifyError,
+            verify_app_hash_proof_op(&app_hash_op(&parts), &header)
+                .unwrap_err()
+                .kind()
+        );
+
+        let mut op = app_hash_op(&parts);
+        op.data.truncate(10);
+        assert_eq!(
+            ErrorKind::DeserializationError,
+            verify_app_hash_proof_op(&op, &trusted_header(&parts, 2))
+                .unwrap_err()
+                .kind()
+        );
+
+        let mut op = app_hash_op(&parts);
+        op.field_type = "staking".to_owned();
+        assert!(verify_app_hash_proof_op(&op, &trusted_header(&parts, 2)).is_err());
+    }
+
+    #[test]
+    fn check_tx_inclusion_query() {
+        let txids: Vec<TxId> = vec![[5; 32], [6; 32], [7; 32]];
+        let tree = MerkleTree::new(txids.clone());
+        let parts = app_hash_parts(tree.root_hash());
+        let query = |txid: TxId, parts: &AppHashParts| AbciQuery {
+            height: Height::from(1u64),
+            proof: Some(Proof {
+                ops: vec![
+                    ProofOp {
+                        field_type: "transaction".to_owned(),
+                        key: txid.to_vec(),
+                        data: tree.generate_proof(txid).unwrap().encode(),
+                    },
+                    app_hash_op(parts),
+                ],
+            }),
+            ..Default::default()
+        };
+        let header = trusted_header(&parts, 2);
+
+        assert!(verify_tx_inclusion_query(&query(txids[1], &parts), &txids[1], &header).is_ok());
+        // the proof is for another transaction
+        assert!(verify_tx_inclusion_query(&query(txids[0], &parts), &txids[1], &header).is_err());
+        // the app hash of the header doesn't commit to the transactions
+        let other_parts = app_hash_parts([0; 32]);
+        assert!(verify_tx_inclusion_query(
+            &query(txids[1], &other_parts),
+            &txids[1],
+            &trusted_header(&other_parts, 2)
+        )
+        .is_err());
+        assert!(verify_tx_inclusion_query(
+            &query(txids[1], &parts),
+            &txids[1],
+            &trusted_header(&other_parts, 2)
+        )
+        .is_err());
+        // the header isn't the one of the next block
+        assert!(verify_tx_inclusion_query(
+            &query(txids[1], &parts),
+            &txids[1],
+            &trusted_header(&parts, 3)
+        )
+        .is_err());
+        // no proof
+        let no_proof = AbciQuery {
+            height: Height::from(1u64),
+            ..Default::default()
+        };
+        assert!(verify_tx_inclusion_query(&no_proof, &txids[1], &header).is_err());
+    }
+}
@@ -32,7 +32,7 @@ type AppTransactionObfuscation = MockAbciTransactionObfuscation<WebsocketRpcClie
 use chain_core::init::coin::CoinError;
 use chain_core::tx::data::TxId;
 use chain_core::tx::fee::{Fee, FeeAlgorithm, LinearFee};
-use chain_core::tx::TxAux;
+use chain_core::tx::{TxAux, TxRestrictedView};
 use futures_util::sink::SinkExt;
 use tokio_tungstenite::tungstenite::protocol::frame::coding::CloseCode;
 use tokio_tungstenite::tungstenite::protocol::frame::CloseFrame;
@@ -69,6 +69,15 @@ impl TransactionObfuscation for SyncRpcClient {
         obfuscator.decrypt(transaction_ids, private_key)
     }
 
+    fn decrypt_with_restricted(
+        &self,
+        transaction_ids: &[TxId],
+        private_key: &PrivateKey,
+    ) -> Result<(Vec<Transaction>, Vec<TxRestrictedView>)> {
+        let obfuscator = self.get_tx_query().map_err(|e| Error::new(e.0, e.1))?;
+        obfuscator.decrypt_with_restricted(transaction_ids, private_key)
+    }
+
     fn encrypt(&self, transaction: SignedTransaction) -> Result<TxAux> {
         let obfuscator = self.get_tx_query().map_err(|e| Error::new(e.0, e.1))?;
         obfuscator.encrypt(transaction)
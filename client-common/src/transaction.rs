@@ -3,9 +3,11 @@ use serde::{Deserialize, Serialize};
 
 use super::{ErrorKind, Result, ResultExt};
 use chain_core::state::account::{
-    DepositBondTx, StakedStateOpWitness, UnbondTx, UnjailTx, WithdrawUnbondedTx,
+    DelegateTx, DepositBondTx, StakedStateOpWitness, TransferStakeTx, UnbondTx, UnjailTx,
+    WithdrawUnbondedTx,
 };
-use chain_core::state::validator::NodeJoinRequestTx;
+use chain_core::state::governance::{ParamChangeProposalTx, ParamChangeVoteTx};
+use chain_core::state::validator::{NodeJoinRequestTx, NodeMetadataUpdateTx};
 use chain_core::tx::data::input::TxoPointer;
 use chain_core::tx::data::output::TxOut;
 use chain_core::tx::data::{Tx, TxId};
@@ -70,6 +72,16 @@ pub enum Transaction {
     UnjailTransaction(UnjailTx),
     /// Node join transaction
     NodejoinTransaction(NodeJoinRequestTx),
+    /// Node metadata update transaction
+    NodeMetadataUpdateTransaction(NodeMetadataUpdateTx),
+    /// Transfer stake transaction
+    TransferStakeTransaction(TransferStakeTx),
+    /// Network parameter change proposal transaction
+    ParamChangeProposalTransaction(ParamChangeProposalTx),
+    /// Network parameter change vote transaction
+    ParamChangeVoteTransaction(ParamChangeVoteTx),
+    /// Delegate (back a council node) transaction
+    DelegateTransaction(DelegateTx),
 }
 
 impl Transaction {
@@ -81,7 +93,12 @@ impl Transaction {
             Transaction::UnbondStakeTransaction(_)
             | Transaction::WithdrawUnbondedStakeTransaction(_)
             | Transaction::UnjailTransaction(_)
-            | Transaction::NodejoinTransaction(_) => &[],
+            | Transaction::NodejoinTransaction(_)
+            | Transaction::NodeMetadataUpdateTransaction(_)
+            | Transaction::TransferStakeTransaction(_)
+            | Transaction::ParamChangeProposalTransaction(_)
+            | Transaction::ParamChangeVoteTransaction(_)
+            | Transaction::DelegateTransaction(_) => &[],
         }
     }
 
@@ -93,7 +110,12 @@ impl Transaction {
             Transaction::UnbondStakeTransaction(_)
             | Transaction::DepositStakeTransaction(_)
             | Transaction::UnjailTransaction(_)
-            | Transaction::NodejoinTransaction(_) => &[],
+            | Transaction::NodejoinTransaction(_)
+            | Transaction::NodeMetadataUpdateTransaction(_)
+            | Transaction::TransferStakeTransaction(_)
+            | Transaction::ParamChangeProposalTransaction(_)
+            | Transaction::ParamChangeVoteTransaction(_)
+            | Transaction::DelegateTransaction(_) => &[],
         }
     }
 }
@@ -107,6 +129,11 @@ impl TransactionId for Transaction {
             Transaction::WithdrawUnbondedStakeTransaction(ref transaction) => transaction.id(),
             Transaction::UnjailTransaction(ref transaction) => transaction.id(),
             Transaction::NodejoinTransaction(ref transaction) => transaction.id(),
+            Transaction::NodeMetadataUpdateTransaction(ref transaction) => transaction.id(),
+            Transaction::TransferStakeTransaction(ref transaction) => transaction.id(),
+            Transaction::ParamChangeProposalTransaction(ref transaction) => transaction.id(),
+            Transaction::ParamChangeVoteTransaction(ref transaction) => transaction.id(),
+            Transaction::DelegateTransaction(ref transaction) => transaction.id(),
         }
     }
 }
@@ -0,0 +1,93 @@
+//! Hash-time-locked contracts (e.g. for cross-chain atomic swaps)
+use chain_core::common::Timespec;
+use chain_core::tx::data::address::ExtendedAddr;
+use chain_core::tx::data::htlc::{hash_preimage, HashPreimage, HashTimeLock};
+use chain_core::tx::witness::TxInWitness;
+use client_common::{
+    Error, ErrorKind, PrivateKey, PrivateKeyAction, PublicKey, Result, Transaction,
+};
+
+/// Creates a new contract locking the funds to the hash of `preimage`
+/// and returns it together with the address to send the funds to
+pub fn new_hash_time_lock(
+    preimage: &HashPreimage,
+    recipient: &PublicKey,
+    refund: &PublicKey,
+    timeout: Timespec,
+) -> (HashTimeLock, ExtendedAddr) {
+    let contract = HashTimeLock::new(
+        hash_preimage(preimage),
+        recipient.into(),
+        refund.into(),
+        timeout,
+    );
+    let address = contract.address();
+    (contract, address)
+}
+
+/// Creates the witness for claiming the locked funds by the recipient (who knows the preimage)
+pub fn htlc_claim_witness(
+    contract: &HashTimeLock,
+    preimage: HashPreimage,
+    private_key: &PrivateKey,
+    tx: &Transaction,
+) -> Result<TxInWitness> {
+    if !contract.check_preimage(&preimage) {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            "Preimage does not match the hash lock",
+        ));
+    }
+    let signature = private_key.schnorr_sign(tx)?;
+    Ok(TxInWitness::HashTimeLock(
+        signature,
+        contract.clone(),
+        Some(preimage),
+    ))
+}
+
+/// Creates the witness for refunding the locked funds after the contract timeout
+pub fn htlc_refund_witness(
+    contract: &HashTimeLock,
+    private_key: &PrivateKey,
+    tx: &Transaction,
+) -> Result<TxInWitness> {
+    let signature = private_key.schnorr_sign(tx)?;
+    Ok(TxInWitness::HashTimeLock(signature, contract.clone(), None))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chain_core::tx::data::Tx;
+
+    #[test]
+    fn check_htlc_witnesses() {
+        let recipient_key = PrivateKey::new().unwrap();
+        let refund_key = PrivateKey::new().unwrap();
+        let (contract, address) = new_hash_time_lock(
+            &[7u8; 32],
+            &PublicKey::from(&recipient_key),
+            &PublicKey::from(&refund_key),
+            100,
+        );
+        assert_eq!(
+            address,
+            ExtendedAddr::HashTimeLock(contract.contract_hash())
+        );
+
+        let tx = Transaction::TransferTransaction(Tx::default());
+        assert!(htlc_claim_witness(&contract, [8u8; 32], &recipient_key, &tx).is_err());
+        match htlc_claim_witness(&contract, [7u8; 32], &recipient_key, &tx).unwrap() {
+            TxInWitness::HashTimeLock(_, witness_contract, Some(preimage)) => {
+                assert_eq!(witness_contract, contract);
+                assert_eq!(preimage, [7u8; 32]);
+            }
+            _ => panic!("unexpected witness"),
+        }
+        assert!(matches!(
+            htlc_refund_witness(&contract, &refund_key, &tx).unwrap(),
+            TxInWitness::HashTimeLock(_, _, None)
+        ));
+    }
+}
@@ -1,8 +1,18 @@
 //! Input selection operations
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use chain_core::init::coin::Coin;
+use chain_core::tx::data::input::TxoPointer;
+use client_common::{Error, ErrorKind, Result};
+
 use crate::unspent_transactions::{Operation, Sorter};
+use crate::UnspentTransactions;
 
 /// Different strategies for input selection
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
 pub enum InputSelectionStrategy {
     /// Selects unspent transactions with highest value first
     HighestValueFirst,
@@ -10,6 +20,16 @@ pub enum InputSelectionStrategy {
     LowestValueFirst,
     /// Selects unspent transactions randomly
     Random,
+    /// Searches (branch and bound) for unspent transactions matching the amount and fee,
+    /// so that no change output is created (at most the fee of a change output is overpaid);
+    /// falls back to highest value first when there's no such match
+    ExactMatch,
+    /// Spends as many unspent transactions as possible (lowest value first)
+    /// while the fee doesn't exceed `max_fee`
+    Consolidate {
+        /// the maximum fee paid by the transaction
+        max_fee: Coin,
+    },
 }
 
 impl Default for InputSelectionStrategy {
@@ -22,13 +42,169 @@ impl Default for InputSelectionStrategy {
 impl AsRef<[Operation]> for InputSelectionStrategy {
     fn as_ref(&self) -> &[Operation] {
         match self {
-            InputSelectionStrategy::HighestValueFirst => {
+            InputSelectionStrategy::HighestValueFirst | InputSelectionStrategy::ExactMatch => {
                 &[Operation::Sort(Sorter::HighestValueFirst)]
             }
-            InputSelectionStrategy::LowestValueFirst => {
+            InputSelectionStrategy::LowestValueFirst
+            | InputSelectionStrategy::Consolidate { .. } => {
                 &[Operation::Sort(Sorter::LowestValueFirst)]
             }
             InputSelectionStrategy::Random => &[],
         }
     }
 }
+
+impl FromStr for InputSelectionStrategy {
+    type Err = Error;
+
+    /// Parses the strategies without parameters
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "highest-value-first" => Ok(InputSelectionStrategy::HighestValueFirst),
+            "lowest-value-first" => Ok(InputSelectionStrategy::LowestValueFirst),
+            "random" => Ok(InputSelectionStrategy::Random),
+            "exact-match" => Ok(InputSelectionStrategy::ExactMatch),
+            _ => Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("unknown input selection strategy: {}", s),
+            )),
+        }
+    }
+}
+
+/// Input selection of a transfer transaction: the strategy and the coin control
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InputSelection {
+    /// strategy for selecting the inputs after the included ones
+    #[serde(default)]
+    pub strategy: InputSelectionStrategy,
+    /// unspent transactions always spent by the transaction
+    #[serde(default)]
+    pub include: Vec<TxoPointer>,
+    /// unspent transactions never spent by the transaction
+    #[serde(default)]
+    pub exclude: Vec<TxoPointer>,
+    /// minimum fee paid by the transaction (e.g. when replacing a pending transaction),
+    /// the change is reduced when the estimated fee is lower
+    #[serde(default)]
+    pub min_fee: Coin,
+}
+
+impl From<InputSelectionStrategy> for InputSelection {
+    #[inline]
+    fn from(strategy: InputSelectionStrategy) -> Self {
+        InputSelection {
+            strategy,
+            ..Default::default()
+        }
+    }
+}
+
+impl InputSelection {
+    /// Orders the unspent transactions according to the strategy and applies the coin control:
+    /// the excluded ones are removed and the included ones are moved to the front.
+    /// Returns the number of the included unspent transactions.
+    pub fn apply(&self, unspent_transactions: &mut UnspentTransactions) -> Result<usize> {
+        if let Some(input) = self
+            .include
+            .iter()
+            .find(|input| self.exclude.contains(input))
+        {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "unspent transaction {}:{} is both included and excluded",
+                    hex::encode(&input.id),
+                    input.index
+                ),
+            ));
+        }
+
+        unspent_transactions.apply_all(self.strategy.as_ref());
+        unspent_transactions.retain(|(input, _)| !self.exclude.contains(input));
+
+        let mut included = Vec::with_capacity(self.include.len());
+        for input in self.include.iter() {
+            let position = unspent_transactions
+                .iter()
+                .position(|(unspent, _)| unspent == input)
+                .ok_or_else(|| {
+                    Error::new(
+                        ErrorKind::InvalidInput,
+                        format!(
+                            "included transaction output {}:{} is not spendable",
+                            hex::encode(&input.id),
+                            input.index
+                        ),
+                    )
+                })?;
+            included.push(unspent_transactions.remove(position));
+        }
+        let pinned = included.len();
+        included.append(unspent_transactions);
+        **unspent_transactions = included;
+        Ok(pinned)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use chain_core::tx::data::address::ExtendedAddr;
+    use chain_core::tx::data::output::TxOut;
+
+    fn sample() -> UnspentTransactions {
+        UnspentTransactions::new(
+            (1..=4)
+                .map(|i| {
+                    (
+                        TxoPointer::new([i; 32], 0),
+                        TxOut::new(
+                            ExtendedAddr::OrTree([0; 32]),
+                            Coin::new(u64::from(i) * 100).unwrap(),
+                        ),
+                    )
+                })
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn check_coin_control() {
+        let mut unspent_transactions = sample();
+        let selection = InputSelection {
+            strategy: InputSelectionStrategy::HighestValueFirst,
+            include: vec![TxoPointer::new([1; 32], 0)],
+            exclude: vec![TxoPointer::new([3; 32], 0)],
+            ..Default::default()
+        };
+        assert_eq!(1, selection.apply(&mut unspent_transactions).unwrap());
+        assert_eq!(
+            unspent_transactions
+                .iter()
+                .map(|(input, _)| input.id[0])
+                .collect::<Vec<_>>(),
+            vec![1, 4, 2]
+        );
+
+        let selection = InputSelection {
+            include: vec![TxoPointer::new([3; 32], 0)],
+            ..Default::default()
+        };
+        assert!(selection.apply(&mut unspent_transactions).is_err());
+    }
+
+    #[test]
+    fn check_strategy_serde() {
+        let strategy = InputSelectionStrategy::Consolidate {
+            max_fee: Coin::new(1000).unwrap(),
+        };
+        let json = serde_json::to_string(&strategy).unwrap();
+        assert_eq!(strategy, serde_json::from_str(&json).unwrap());
+        assert_eq!(
+            InputSelectionStrategy::ExactMatch,
+            "exact-match".parse().unwrap()
+        );
+    }
+}
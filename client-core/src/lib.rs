@@ -12,6 +12,7 @@
 
 pub mod hd_seed;
 pub mod hd_wallet;
+pub mod htlc;
 pub mod input_selection;
 pub mod mnemonic;
 #[cfg(feature = "experimental")]
@@ -27,7 +28,7 @@ pub mod wallet;
 #[doc(inline)]
 pub use crate::hd_seed::HDSeed;
 #[doc(inline)]
-pub use crate::input_selection::InputSelectionStrategy;
+pub use crate::input_selection::{InputSelection, InputSelectionStrategy};
 #[doc(inline)]
 pub use crate::mnemonic::Mnemonic;
 #[doc(inline)]
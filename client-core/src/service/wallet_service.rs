@@ -63,7 +63,7 @@ fn get_wallet_keyspace() -> String {
     format!("{}_walletname", KEYSPACE)
 }
 
-fn serde_to_str<T, S>(value: &T, serializer: S) -> std::result::Result<S::Ok, S::Error>
+pub(crate) fn serde_to_str<T, S>(value: &T, serializer: S) -> std::result::Result<S::Ok, S::Error>
 where
     T: Encode,
     S: Serializer,
@@ -72,7 +72,7 @@ where
     serializer.serialize_str(&value_str)
 }
 
-fn deserde_from_str<'de, D, T>(deserializer: D) -> std::result::Result<T, D::Error>
+pub(crate) fn deserde_from_str<'de, D, T>(deserializer: D) -> std::result::Result<T, D::Error>
 where
     D: Deserializer<'de>,
     T: Decode,
@@ -536,6 +536,8 @@ where
                     return Ok(Some(roothash_found));
                 }
             }
+            // hash-time-locked contracts are not tracked in the wallet
+            ExtendedAddr::HashTimeLock(_) => {}
         }
 
         Err(Error::new(ErrorKind::InvalidInput, "private_key not found"))
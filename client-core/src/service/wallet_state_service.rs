@@ -1,16 +1,51 @@
 use parity_scale_codec::{Decode, Encode};
 use std::collections::BTreeMap;
+use std::convert::TryFrom;
 
 use chain_core::{
+    common::Timespec,
     init::coin::{sum_coins, CoinError},
-    tx::data::{input::TxoPointer, output::TxOut, TxId},
+    state::tendermint::BlockHeight,
+    tx::data::{
+        attribute::TxAttributes,
+        input::TxoPointer,
+        output::{TxInclusion, TxOut},
+        TxId,
+    },
+    tx::TxAux,
 };
+use client_common::tendermint::types::Time;
 use client_common::{Error, ErrorKind, Result, ResultExt, SecKey, SecureStorage, Storage};
 
 use crate::types::{TransactionChange, TransactionPending, WalletBalance};
+use crate::UnspentTransactions;
 
 /// key space of wallet state
 const KEYSPACE: &str = "core_wallet_state";
+/// key space of the broadcast pending transactions (one per wallet)
+const PENDING_KEYSPACE: &str = "core_wallet_pending_tx";
+
+fn get_pending_keyspace(name: &str) -> String {
+    format!("{}_{}", PENDING_KEYSPACE, name)
+}
+
+/// Outputs and attributes of a pending transfer transaction (needed for replacing it)
+#[derive(Debug, Clone, Encode, Decode)]
+pub struct PendingTransfer {
+    /// outputs paid by the transaction (without the change output)
+    pub outputs: Vec<TxOut>,
+    /// attributes of the transaction
+    pub attributes: TxAttributes,
+}
+
+/// Broadcast transaction of a pending transaction (kept while the transaction is pending)
+#[derive(Debug, Clone, Encode, Decode)]
+pub struct PendingTransactionData {
+    /// the broadcast transaction
+    pub tx_aux: TxAux,
+    /// outputs and attributes, if the transaction was built by the wallet as a transfer
+    pub transfer: Option<PendingTransfer>,
+}
 
 /// Maintains mapping `wallet-name -> wallet-state`
 #[derive(Debug, Default, Clone)]
@@ -68,6 +103,21 @@ where
         }
     }
 
+    /// Returns currently stored unspent transactions (which are not used by pending transactions)
+    /// that can be spent in a block with the given time and height
+    #[inline]
+    pub fn get_spendable_transactions(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        block_time: Timespec,
+        block_height: BlockHeight,
+    ) -> Result<BTreeMap<TxoPointer, TxOut>> {
+        Ok(self
+            .get_wallet_state(name, enckey)?
+            .get_spendable_transactions(block_time, block_height))
+    }
+
     /// Returns `true` or `false` depending if input is unspent or not. `true` if the input is unspent, `false`
     /// otherwise
     pub fn are_inputs_unspent(
@@ -130,10 +180,17 @@ where
     }
 
     /// Returns currently stored balance for given wallet
-    pub fn get_balance(&self, name: &str, enckey: &SecKey) -> Result<WalletBalance> {
+    /// (outputs which can't be spent in a block with the given time and height are reported as locked)
+    pub fn get_balance(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        block_time: Timespec,
+        block_height: BlockHeight,
+    ) -> Result<WalletBalance> {
         let wallet_state = self.get_wallet_state(name, enckey)?;
         let balance = wallet_state
-            .get_balance()
+            .get_balance(block_time, block_height)
             .chain(|| (ErrorKind::StorageError, "Calculate balance error"))?;
         Ok(balance)
     }
@@ -166,13 +223,72 @@ where
     pub fn delete_wallet_state(&self, name: &str, enckey: &SecKey) -> Result<()> {
         // Check if the enckey is correct
         let _ = self.get_wallet_state(name, enckey)?;
+        self.storage.clear(get_pending_keyspace(name))?;
         self.storage.delete(KEYSPACE, name).map(|_| ())
     }
 
+    /// Returns the stored data of a pending transaction
+    pub fn get_pending_transaction_data(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        transaction_id: &TxId,
+    ) -> Result<Option<PendingTransactionData>> {
+        self.storage.load_secure(
+            &get_pending_keyspace(name),
+            &hex::encode(transaction_id),
+            enckey,
+        )
+    }
+
+    /// Stores the data of a pending transaction
+    pub fn set_pending_transaction_data(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        transaction_id: &TxId,
+        data: &PendingTransactionData,
+    ) -> Result<()> {
+        self.storage.save_secure(
+            &get_pending_keyspace(name),
+            &hex::encode(transaction_id),
+            enckey,
+            data,
+        )
+    }
+
+    /// Deletes the stored data of the transactions which are no longer pending
+    /// (confirmed, rolled back, abandoned or never broadcast)
+    pub fn prune_pending_transaction_data(&self, name: &str, enckey: &SecKey) -> Result<()> {
+        let wallet_state = self.get_wallet_state(name, enckey)?;
+        let keyspace = get_pending_keyspace(name);
+        for key in self.storage.keys(&keyspace)? {
+            let pending = hex::decode(&key)
+                .ok()
+                .and_then(|bytes| TxId::try_from(bytes.as_slice()).ok())
+                .map_or(false, |transaction_id| {
+                    wallet_state
+                        .pending_transactions
+                        .contains_key(&transaction_id)
+                });
+            if !pending {
+                self.storage.delete(&keyspace, key)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the whole wallet state (e.g. for backups)
     #[inline]
-    fn get_wallet_state(&self, name: &str, enckey: &SecKey) -> Result<WalletState> {
+    pub fn get_wallet_state(&self, name: &str, enckey: &SecKey) -> Result<WalletState> {
         Ok(load_wallet_state(&self.storage, name, enckey)?.unwrap_or_default())
     }
+
+    /// Replaces the whole wallet state (e.g. when restoring a backup)
+    #[inline]
+    pub fn set_wallet_state(&self, name: &str, enckey: &SecKey, state: &WalletState) -> Result<()> {
+        save_wallet_state(&self.storage, name, enckey, state)
+    }
 }
 
 fn parse_wallet_state<T: AsRef<[u8]>>(
@@ -304,8 +420,61 @@ impl WalletState {
             .collect::<Vec<_>>();
         result
     }
+    /// get when the transaction was included in the chain (if it's in the transaction history)
+    fn get_inclusion(&self, txid: &TxId) -> Option<TxInclusion> {
+        self.transaction_history.get(txid).map(|change| {
+            let block_time = change
+                .block_time
+                .duration_since(Time::unix_epoch())
+                .map(|duration| duration.as_secs())
+                .unwrap_or_default();
+            TxInclusion::new(block_time, BlockHeight::new(change.block_height))
+        })
+    }
+
+    /// if the output can be spent in a block with the given time and height
+    /// (outputs with relative timelocks are considered locked if their inclusion is unknown)
+    pub fn is_spendable(
+        &self,
+        input: &TxoPointer,
+        output: &TxOut,
+        block_time: Timespec,
+        block_height: BlockHeight,
+    ) -> bool {
+        match output.valid_from {
+            None => true,
+            Some(timelock) => match self.get_inclusion(&input.id) {
+                Some(included) => timelock.is_unlocked(block_time, block_height, &included),
+                None => {
+                    !timelock.is_relative()
+                        && timelock.is_unlocked(
+                            block_time,
+                            block_height,
+                            &TxInclusion::new(0, BlockHeight::genesis()),
+                        )
+                }
+            },
+        }
+    }
+
+    /// get available transactions which can be spent in a block with the given time and height
+    pub fn get_spendable_transactions(
+        &self,
+        block_time: Timespec,
+        block_height: BlockHeight,
+    ) -> BTreeMap<TxoPointer, TxOut> {
+        let mut result = self.get_available_transactions();
+        result.retain(|input, output| self.is_spendable(input, output, block_time, block_height));
+        result
+    }
+
     /// get the balance info
-    pub fn get_balance(&self) -> std::result::Result<WalletBalance, CoinError> {
+    /// (outputs which can't be spent in a block with the given time and height are reported as locked)
+    pub fn get_balance(
+        &self,
+        block_time: Timespec,
+        block_height: BlockHeight,
+    ) -> std::result::Result<WalletBalance, CoinError> {
         // pending amount
         let pending_coins = self
             .pending_transactions
@@ -314,21 +483,22 @@ impl WalletState {
         let amount_pending = sum_coins(pending_coins)?;
 
         // unavailable amount
-        let pending_inputs = self.get_pending_inputs();
-        let available_coins = self
-            .unspent_transactions
-            .iter()
-            .filter(|(key, _value)| !pending_inputs.contains(key))
-            .map(|(_key, value)| value.value);
-        let amount_available = sum_coins(available_coins)?;
+        let available =
+            UnspentTransactions::new(self.get_available_transactions().into_iter().collect());
+        let (spendable, locked) = available.split_locked(|input, output| {
+            self.is_spendable(input, output, block_time, block_height)
+        });
+        let amount_available = spendable.total_amount()?;
+        let amount_locked = locked.total_amount()?;
 
         // total amount
-        let amount_total = (amount_pending + amount_available)?;
+        let amount_total = ((amount_pending + amount_available)? + amount_locked)?;
 
         let wallet_balances = WalletBalance {
             total: amount_total,
             available: amount_available,
             pending: amount_pending,
+            locked: amount_locked,
         };
         Ok(wallet_balances)
     }
@@ -447,6 +617,16 @@ impl WalletStateMemento {
         self.0
             .push(MementoOperation::RemovePendingTransaction(tx_id))
     }
+
+    /// Returns the transaction changes added to memento
+    pub fn transaction_changes(&self) -> impl Iterator<Item = &TransactionChange> {
+        self.0.iter().filter_map(|operation| match operation {
+            MementoOperation::AddTransactionChange(_, transaction_change) => {
+                Some(transaction_change)
+            }
+            _ => None,
+        })
+    }
 }
 
 #[cfg(test)]
@@ -456,7 +636,9 @@ mod tests {
     use std::str::FromStr;
 
     use chain_core::tx::data::address::ExtendedAddr;
+    use chain_core::tx::data::output::Timelock;
     use chain_core::tx::fee::Fee;
+    use chain_core::tx::{TxEnclaveAux, TxObfuscated};
     use client_common::tendermint::types::Time;
     use client_common::{seckey::derive_enckey, storage::MemoryStorage};
 
@@ -496,7 +678,9 @@ mod tests {
 
         assert_eq!(
             WalletBalance::default(),
-            wallet_state_service.get_balance(name, enckey).unwrap()
+            wallet_state_service
+                .get_balance(name, enckey, 0, BlockHeight::genesis())
+                .unwrap()
         );
 
         // Add an unspent transaction and check if it is added
@@ -646,11 +830,14 @@ mod tests {
             .apply_memento(name, enckey, &memento)
             .unwrap();
         assert_eq!(
-            wallet_state_service.get_balance(name, enckey).unwrap(),
+            wallet_state_service
+                .get_balance(name, enckey, 0, BlockHeight::genesis())
+                .unwrap(),
             WalletBalance {
                 total: Coin::new(140).unwrap(),
                 available: Coin::new(140).unwrap(),
                 pending: Coin::zero(),
+                locked: Coin::zero(),
             }
         );
 
@@ -669,11 +856,14 @@ mod tests {
             .unwrap();
 
         assert_eq!(
-            wallet_state_service.get_balance(name, enckey).unwrap(),
+            wallet_state_service
+                .get_balance(name, enckey, 0, BlockHeight::genesis())
+                .unwrap(),
             WalletBalance {
                 total: Coin::new(90).unwrap(),
                 available: Coin::new(40).unwrap(),
                 pending: Coin::new(50).unwrap(),
+                locked: Coin::zero(),
             }
         );
 
@@ -707,11 +897,14 @@ mod tests {
             .unwrap();
         // now, we can get the balance
         assert_eq!(
-            wallet_state_service.get_balance(name, enckey).unwrap(),
+            wallet_state_service
+                .get_balance(name, enckey, 0, BlockHeight::genesis())
+                .unwrap(),
             WalletBalance {
                 total: Coin::new(90).unwrap(),
                 available: Coin::new(90).unwrap(),
                 pending: Coin::zero(),
+                locked: Coin::zero(),
             }
         );
         let unspent_tx = wallet_state_service
@@ -741,12 +934,143 @@ mod tests {
             .apply_memento(name, enckey, &memento)
             .unwrap();
         assert_eq!(
-            wallet_state_service.get_balance(name, enckey).unwrap(),
+            wallet_state_service
+                .get_balance(name, enckey, 0, BlockHeight::genesis())
+                .unwrap(),
             WalletBalance {
                 total: Coin::new(140).unwrap(),
                 available: Coin::new(140).unwrap(),
                 pending: Coin::new(0).unwrap(),
+                locked: Coin::zero(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_locked_balance() {
+        let name = "name";
+        let enckey = &derive_enckey(&SecUtf8::from("passphrase"), name).unwrap();
+        let wallet_state_service = WalletStateService::new(MemoryStorage::default());
+        let address = ExtendedAddr::OrTree([0; 32]);
+
+        let mut memento = WalletStateMemento::default();
+        memento.add_transaction_change(TransactionChange {
+            transaction_id: [1; 32],
+            inputs: Vec::new(),
+            outputs: Vec::new(),
+            balance_change: BalanceChange::Incoming {
+                value: Coin::new(60).unwrap(),
+            },
+            transaction_type: TransactionType::Transfer,
+            block_height: 5,
+            fee_paid: Fee::new(Coin::zero()),
+            block_time: Time::from_str("2019-04-09T09:38:41Z").unwrap(),
+        });
+        memento.add_unspent_transaction(
+            TxoPointer::new([1; 32], 0),
+            TxOut::new_with_lock(
+                address.clone(),
+                Coin::new(10).unwrap(),
+                Timelock::Height(BlockHeight::new(10)),
+            ),
+        );
+        memento.add_unspent_transaction(
+            TxoPointer::new([1; 32], 1),
+            TxOut::new_with_lock(
+                address.clone(),
+                Coin::new(20).unwrap(),
+                Timelock::RelativeHeight(2),
+            ),
+        );
+        memento.add_unspent_transaction(
+            TxoPointer::new([1; 32], 2),
+            TxOut::new(address.clone(), Coin::new(30).unwrap()),
+        );
+        // the inclusion of this transaction is unknown
+        memento.add_unspent_transaction(
+            TxoPointer::new([2; 32], 0),
+            TxOut::new_with_lock(address, Coin::new(40).unwrap(), Timelock::RelativeTime(0)),
+        );
+        wallet_state_service
+            .apply_memento(name, enckey, &memento)
+            .unwrap();
+
+        assert_eq!(
+            wallet_state_service
+                .get_balance(name, enckey, 0, BlockHeight::new(6))
+                .unwrap(),
+            WalletBalance {
+                total: Coin::new(100).unwrap(),
+                available: Coin::new(30).unwrap(),
+                pending: Coin::zero(),
+                locked: Coin::new(70).unwrap(),
             }
         );
+        assert_eq!(
+            wallet_state_service
+                .get_balance(name, enckey, 0, BlockHeight::new(7))
+                .unwrap()
+                .available,
+            Coin::new(50).unwrap()
+        );
+        let spendable = wallet_state_service
+            .get_spendable_transactions(name, enckey, 0, BlockHeight::new(10))
+            .unwrap();
+        assert_eq!(spendable.len(), 3);
+        assert!(!spendable.contains_key(&TxoPointer::new([2; 32], 0)));
+    }
+
+    #[test]
+    fn check_prune_pending_transaction_data() {
+        let wallet_state_service = WalletStateService::new(MemoryStorage::default());
+        let name = "name";
+        let enckey = &derive_enckey(&SecUtf8::from("passphrase"), name).unwrap();
+        let data = |txid: TxId| PendingTransactionData {
+            tx_aux: TxAux::EnclaveTx(TxEnclaveAux::TransferTx {
+                inputs: vec![],
+                no_of_outputs: 1,
+                payload: TxObfuscated {
+                    txid,
+                    key_from: BlockHeight::genesis(),
+                    init_vector: [0; 12],
+                    txpayload: vec![],
+                },
+            }),
+            transfer: None,
+        };
+        for txid in [[0; 32], [1; 32]].iter() {
+            wallet_state_service
+                .set_pending_transaction_data(name, enckey, txid, &data(*txid))
+                .unwrap();
+        }
+        let mut memento = WalletStateMemento::default();
+        memento.add_pending_transaction(
+            [1; 32],
+            TransactionPending {
+                used_inputs: vec![],
+                block_height: 0,
+                return_amount: Coin::zero(),
+            },
+        );
+        wallet_state_service
+            .apply_memento(name, enckey, &memento)
+            .unwrap();
+
+        // only the data of the pending transactions is kept
+        wallet_state_service
+            .prune_pending_transaction_data(name, enckey)
+            .unwrap();
+        let get = |txid: &TxId| {
+            wallet_state_service
+                .get_pending_transaction_data(name, enckey, txid)
+                .unwrap()
+        };
+        assert!(get(&[0; 32]).is_none());
+        assert_eq!([1; 32], get(&[1; 32]).unwrap().tx_aux.tx_id());
+
+        wallet_state_service
+            .delete_wallet_state(name, enckey)
+            .unwrap();
+        assert!(get(&[1; 32]).is_none());
     }
 }
@@ -18,6 +18,7 @@ use chain_core::tx::data::output::TxOut;
 use chain_core::tx::TxAux;
 use client_common::{PrivateKey, Result, SecKey, SignedTransaction, Transaction};
 
+use crate::input_selection::InputSelection;
 use crate::UnspentTransactions;
 use chain_core::tx::data::TxId;
 
@@ -34,6 +35,7 @@ pub trait WalletTransactionBuilder: Send + Sync + Clone {
     /// - `outputs`: Transaction outputs
     /// - `return_address`: Address to which change amount will get returned
     /// - `attributes`: Transaction attributes,
+    /// - `input_selection`: Strategy and coin control for selecting the unspent transactions
     ///
     /// # return
     /// - `TxAux`: obfuscated transaction
@@ -47,6 +49,7 @@ pub trait WalletTransactionBuilder: Send + Sync + Clone {
         outputs: Vec<TxOut>,
         return_address: ExtendedAddr,
         attributes: TxAttributes,
+        input_selection: &InputSelection,
     ) -> Result<(TxAux, Vec<TxoPointer>, Coin)>;
 
     /// Obfuscates given signed transaction
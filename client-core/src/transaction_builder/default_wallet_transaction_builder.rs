@@ -6,15 +6,19 @@ use chain_core::tx::data::output::TxOut;
 use chain_core::tx::fee::FeeAlgorithm;
 use chain_core::tx::TxAux;
 use client_common::{
-    ErrorKind, PrivateKey, Result, ResultExt, SecKey, SignedTransaction, Storage, Transaction,
-    TransactionObfuscation,
+    Error, ErrorKind, PrivateKey, Result, ResultExt, SecKey, SignedTransaction, Storage,
+    Transaction, TransactionObfuscation,
 };
 
+use crate::input_selection::{InputSelection, InputSelectionStrategy};
 use crate::signer::WalletSignerManager;
 use crate::transaction_builder::RawTransferTransactionBuilder;
 use crate::{SelectedUnspentTransactions, UnspentTransactions, WalletTransactionBuilder};
 use chain_core::tx::{data::TxId, TransactionId};
 
+/// Maximum number of the fee estimation rounds for the exact match strategy
+const EXACT_MATCH_MAX_ROUNDS: usize = 10;
+
 /// Default implementation of `TransactionBuilder`
 ///
 /// # Algorithm
@@ -28,6 +32,10 @@ use chain_core::tx::{data::TxId, TransactionId};
 /// 7. Calculate `new_fees`.
 /// 8. If `new_fees > fees`, then change `fees = new_fees` and goto step 3, otherwise return signed transaction.
 ///
+/// The unspent transactions are ordered by the input selection strategy first (the coin control
+/// included ones go first and are always selected). The exact match strategy searches for
+/// the inputs that don't need the change output in step 3 (and steps 4-8 are the same without it);
+/// the consolidation strategy keeps adding the inputs after step 8 while the fee is within its limit.
 #[derive(Debug, Clone)]
 pub struct DefaultWalletTransactionBuilder<S, F, O>
 where
@@ -58,14 +66,45 @@ where
         attributes: TxAttributes,
         // FIXME: this should be per unspent_transactions
         threshold: u16,
+        input_selection: &InputSelection,
     ) -> Result<(TxAux, Vec<TxoPointer>, Coin)> {
-        let mut raw_builder = self.select_and_build(
-            &unspent_transactions,
-            outputs,
-            return_address.clone(),
-            attributes,
-            threshold,
-        )?;
+        let mut unspent_transactions = unspent_transactions;
+        let pinned = input_selection.apply(&mut unspent_transactions)?;
+        let exact_match = if let InputSelectionStrategy::ExactMatch = input_selection.strategy {
+            self.select_exact_and_build(
+                &unspent_transactions,
+                pinned,
+                &outputs,
+                &return_address,
+                &attributes,
+                threshold,
+                input_selection.min_fee,
+            )?
+        } else {
+            None
+        };
+        let mut raw_builder = match (exact_match, input_selection.strategy) {
+            (Some(raw_builder), _) => raw_builder,
+            (None, InputSelectionStrategy::Consolidate { max_fee }) => self.consolidate_and_build(
+                &unspent_transactions,
+                pinned,
+                outputs,
+                return_address.clone(),
+                attributes,
+                threshold,
+                max_fee,
+                input_selection.min_fee,
+            )?,
+            (None, _) => self.select_and_build(
+                &unspent_transactions,
+                pinned,
+                outputs,
+                return_address.clone(),
+                attributes,
+                threshold,
+                input_selection.min_fee,
+            )?,
+        };
 
         let selected_inputs: Vec<TxoPointer> = raw_builder
             .iter_inputs()
@@ -103,6 +142,7 @@ where
         outputs: Vec<TxOut>,
         return_address: ExtendedAddr,
         attributes: TxAttributes,
+        input_selection: &InputSelection,
     ) -> Result<(TxAux, Vec<TxoPointer>, Coin)> {
         self.build_transfer_tx_ex(
             name,
@@ -112,6 +152,7 @@ where
             return_address,
             attributes,
             1,
+            input_selection,
         )
     }
 
@@ -154,14 +195,18 @@ where
 
     /// Create a `DummySigner` which signs a transaction with dummy values for fees calculation.
     /// Returns a result of unsigned raw transfer transaction builder
+    /// (the first `pinned` unspent transactions are always selected and at least `min_fee` is paid)
+    #[allow(clippy::too_many_arguments)]
     pub fn select_and_build<'a>(
         &self,
         unspent_transactions: &'a UnspentTransactions,
+        pinned: usize,
         outputs: Vec<TxOut>,
         return_address: ExtendedAddr,
         attributes: TxAttributes,
         // FIXME: this should be per UnspentTransactions
         threshold: u16,
+        min_fee: Coin,
     ) -> Result<RawTransferTransactionBuilder<F>> {
         let output_value = sum_coins(outputs.iter().map(|output| output.value)).chain(|| {
             (
@@ -169,15 +214,17 @@ where
                 "Sum of output values exceeds maximum allowed amount",
             )
         })?;
-        let mut fees = Coin::zero();
+        let mut fees = min_fee;
         let raw_tx_builder = loop {
-            let (selected_unspent_txs, change_amount) =
-                unspent_transactions.select((output_value + fees).chain(|| {
+            let (selected_unspent_txs, change_amount) = unspent_transactions.select_with_pinned(
+                (output_value + fees).chain(|| {
                     (
                         ErrorKind::IllegalInput,
                         "Sum of output values and fee exceeds maximum allowed amount",
                     )
-                })?)?;
+                })?,
+                pinned,
+            )?;
             let raw_tx_builder = self.build_raw_transaction(
                 &selected_unspent_txs,
                 &outputs,
@@ -198,6 +245,165 @@ where
         Ok(raw_tx_builder)
     }
 
+    /// Selects the unspent transactions matching the output values and fee without a change output
+    /// (at most the fee of a change output is overpaid); returns None if there's no such match
+    #[allow(clippy::too_many_arguments)]
+    fn select_exact_and_build(
+        &self,
+        unspent_transactions: &UnspentTransactions,
+        pinned: usize,
+        outputs: &[TxOut],
+        return_address: &ExtendedAddr,
+        attributes: &TxAttributes,
+        threshold: u16,
+        min_fee: Coin,
+    ) -> Result<Option<RawTransferTransactionBuilder<F>>> {
+        if unspent_transactions.is_empty() {
+            return Ok(None);
+        }
+        let output_value = sum_coins(outputs.iter().map(|output| output.value)).chain(|| {
+            (
+                ErrorKind::IllegalInput,
+                "Sum of output values exceeds maximum allowed amount",
+            )
+        })?;
+        // the fee of the change output doesn't depend on the inputs
+        let first = unspent_transactions.select_first(1);
+        let without_change = self
+            .build_raw_transaction(
+                &first,
+                outputs,
+                return_address.clone(),
+                Coin::zero(),
+                attributes.clone(),
+                threshold,
+            )
+            .estimate_fee()?;
+        let with_change = self
+            .build_raw_transaction(
+                &first,
+                outputs,
+                return_address.clone(),
+                Coin::unit(),
+                attributes.clone(),
+                threshold,
+            )
+            .estimate_fee()?;
+        let max_waste = (with_change - without_change).unwrap_or_default();
+
+        // starts from the fee with a single input
+        let mut fees = without_change.max(min_fee);
+        for _ in 0..EXACT_MATCH_MAX_ROUNDS {
+            let amount = (output_value + fees).chain(|| {
+                (
+                    ErrorKind::IllegalInput,
+                    "Sum of output values and fee exceeds maximum allowed amount",
+                )
+            })?;
+            let selected_unspent_txs =
+                match unspent_transactions.select_exact(amount, max_waste, pinned) {
+                    Some(selected) => selected,
+                    None => return Ok(None),
+                };
+            let raw_tx_builder = self.build_raw_transaction(
+                &selected_unspent_txs,
+                outputs,
+                return_address.clone(),
+                Coin::zero(),
+                attributes.clone(),
+                threshold,
+            );
+
+            let new_fees = raw_tx_builder.estimate_fee()?;
+            if new_fees > fees {
+                fees = new_fees;
+            } else {
+                return Ok(Some(raw_tx_builder));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Selects the unspent transactions for the output values and then keeps adding
+    /// the next ones (the change grows) as long as the fee doesn't exceed `max_fee`
+    #[allow(clippy::too_many_arguments)]
+    fn consolidate_and_build(
+        &self,
+        unspent_transactions: &UnspentTransactions,
+        pinned: usize,
+        outputs: Vec<TxOut>,
+        return_address: ExtendedAddr,
+        attributes: TxAttributes,
+        threshold: u16,
+        max_fee: Coin,
+        min_fee: Coin,
+    ) -> Result<RawTransferTransactionBuilder<F>> {
+        let output_value = sum_coins(outputs.iter().map(|output| output.value)).chain(|| {
+            (
+                ErrorKind::IllegalInput,
+                "Sum of output values exceeds maximum allowed amount",
+            )
+        })?;
+        let mut raw_tx_builder = self.select_and_build(
+            unspent_transactions,
+            pinned,
+            outputs.clone(),
+            return_address.clone(),
+            attributes.clone(),
+            threshold,
+            min_fee,
+        )?;
+        if raw_tx_builder.estimate_fee()?.max(min_fee) > max_fee {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "Fee of the transaction exceeds the maximum consolidation fee",
+            ));
+        }
+
+        for count in raw_tx_builder.inputs_len() + 1..=unspent_transactions.len() {
+            let selected_unspent_txs = unspent_transactions.select_first(count);
+            // the fee doesn't depend on the change amount
+            let fees = self
+                .build_raw_transaction(
+                    &selected_unspent_txs,
+                    &outputs,
+                    return_address.clone(),
+                    Coin::unit(),
+                    attributes.clone(),
+                    threshold,
+                )
+                .estimate_fee()?
+                .max(min_fee);
+            if fees > max_fee {
+                break;
+            }
+            let selected_value = sum_coins(
+                selected_unspent_txs.iter().map(|(_, output)| output.value),
+            )
+            .chain(|| {
+                (
+                    ErrorKind::IllegalInput,
+                    "Total amount of selected UTXOs exceeds maximum allowed value",
+                )
+            })?;
+            let change_amount = (selected_value - output_value - fees).chain(|| {
+                (
+                    ErrorKind::IllegalInput,
+                    "Amount of selected UTXOs is negative",
+                )
+            })?;
+            raw_tx_builder = self.build_raw_transaction(
+                &selected_unspent_txs,
+                &outputs,
+                return_address.clone(),
+                change_amount,
+                attributes.clone(),
+                threshold,
+            );
+        }
+        Ok(raw_tx_builder)
+    }
+
     fn build_raw_transaction(
         &self,
         selected_unspent_transactions: &SelectedUnspentTransactions<'_>,
@@ -357,6 +563,7 @@ mod default_wallet_transaction_builder_tests {
                 return_address,
                 attributes,
                 2,
+                &InputSelection::default(),
             )
             .unwrap();
 
@@ -412,6 +619,117 @@ mod default_wallet_transaction_builder_tests {
         }
     }
 
+    #[test]
+    fn check_input_selection_strategies() {
+        let name = "name";
+        let passphrase = SecUtf8::from("passphrase");
+
+        let storage = MemoryStorage::default();
+        let wallet_client = DefaultWalletClient::new_read_only(storage.clone());
+
+        let (enckey, _) = wallet_client
+            .new_wallet(name, &passphrase, WalletKind::Basic, None)
+            .unwrap();
+        let address = wallet_client.new_transfer_address(name, &enckey).unwrap();
+        let return_address = wallet_client.new_transfer_address(name, &enckey).unwrap();
+        let unspent = |values: &[u64]| {
+            UnspentTransactions::new(
+                values
+                    .iter()
+                    .enumerate()
+                    .map(|(i, value)| {
+                        (
+                            TxoPointer::new([i as u8; 32], 0),
+                            TxOut::new(address.clone(), Coin::new(*value).unwrap()),
+                        )
+                    })
+                    .collect(),
+            )
+        };
+
+        let signer_manager = WalletSignerManager::new(storage.clone(), HwKeyService::default());
+        let fee_algorithm =
+            LinearFee::new(Milli::try_new(1, 1).unwrap(), Milli::try_new(1, 1).unwrap());
+        let transaction_builder = DefaultWalletTransactionBuilder::new(
+            signer_manager,
+            fee_algorithm,
+            MockTransactionCipher,
+        );
+        let outputs = vec![TxOut::new(address.clone(), Coin::new(1000).unwrap())];
+        let build = |unspent_transactions: UnspentTransactions, input_selection: InputSelection| {
+            transaction_builder.build_transfer_tx(
+                name,
+                &enckey,
+                unspent_transactions,
+                outputs.clone(),
+                return_address.clone(),
+                TxAttributes::new(171),
+                &input_selection,
+            )
+        };
+
+        // coin control
+        let input_selection = InputSelection {
+            strategy: InputSelectionStrategy::HighestValueFirst,
+            include: vec![TxoPointer::new([0; 32], 0)],
+            exclude: vec![TxoPointer::new([3; 32], 0)],
+            ..Default::default()
+        };
+        let (_, inputs, _) =
+            build(unspent(&[500, 100_000, 20_000, 1_000_000]), input_selection).unwrap();
+        assert_eq!(
+            inputs,
+            vec![TxoPointer::new([0; 32], 0), TxoPointer::new([1; 32], 0)]
+        );
+
+        // an output matching the amount with the fee (of the transaction without the change)
+        let (_, _, return_amount) =
+            build(unspent(&[1_000_000]), InputSelection::default()).unwrap();
+        let fee_with_change = 1_000_000 - 1000 - u64::from(return_amount);
+        let (_, inputs, return_amount) = build(
+            unspent(&[1_000_000, 1000 + fee_with_change]),
+            InputSelectionStrategy::ExactMatch.into(),
+        )
+        .unwrap();
+        assert_eq!(inputs, vec![TxoPointer::new([1; 32], 0)]);
+        assert_eq!(return_amount, Coin::zero());
+
+        // a higher fee than the estimated one (e.g. replacing a pending transaction)
+        let (_, _, return_amount) = build(
+            unspent(&[1_000_000]),
+            InputSelection {
+                min_fee: Coin::new(fee_with_change + 500).unwrap(),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            u64::from(return_amount),
+            1_000_000 - 1000 - fee_with_change - 500
+        );
+
+        // consolidation
+        let values = [5_000, 5_000, 5_000, 5_000, 1_000_000];
+        let (_, inputs, _) = build(
+            unspent(&values),
+            InputSelectionStrategy::Consolidate {
+                max_fee: Coin::new(100_000).unwrap(),
+            }
+            .into(),
+        )
+        .unwrap();
+        assert_eq!(inputs.len(), 5);
+        let (_, inputs, _) = build(
+            unspent(&values),
+            InputSelectionStrategy::Consolidate {
+                max_fee: Coin::new(fee_with_change + 1).unwrap(),
+            }
+            .into(),
+        )
+        .unwrap();
+        assert_eq!(inputs, vec![TxoPointer::new([0; 32], 0)]);
+    }
+
     #[test]
     fn check_insufficient_balance_flow() {
         let name = "name";
@@ -482,6 +800,7 @@ mod default_wallet_transaction_builder_tests {
                     outputs,
                     return_address,
                     attributes,
+                    &InputSelection::default(),
                 )
                 .unwrap_err()
                 .kind()
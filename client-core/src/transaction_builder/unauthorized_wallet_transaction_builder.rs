@@ -6,6 +6,7 @@ use chain_core::tx::data::output::TxOut;
 use chain_core::tx::TxAux;
 use client_common::{ErrorKind, PrivateKey, Result, SecKey, SignedTransaction, Transaction};
 
+use crate::input_selection::InputSelection;
 use crate::{UnspentTransactions, WalletTransactionBuilder};
 use chain_core::tx::data::TxId;
 
@@ -23,6 +24,7 @@ impl WalletTransactionBuilder for UnauthorizedWalletTransactionBuilder {
         _: Vec<TxOut>,
         _: ExtendedAddr,
         _: TxAttributes,
+        _: &InputSelection,
     ) -> Result<(TxAux, Vec<TxoPointer>, Coin)> {
         Err(ErrorKind::PermissionDenied.into())
     }
@@ -7,7 +7,7 @@ pub mod transaction_change;
 pub use self::address_type::AddressType;
 #[doc(inline)]
 pub use self::transaction_change::{
-    BalanceChange, TransactionChange, TransactionInput, TransactionPending, TransactionType,
-    WalletBalance,
+    BalanceChange, PendingTransactionInfo, TransactionChange, TransactionInput, TransactionPending,
+    TransactionType, WalletBalance,
 };
 pub use self::wallet_type::WalletKind;
@@ -19,9 +19,10 @@ use client_common::{ErrorKind, Result, ResultExt, Transaction};
 /// The semantic of `WalletBalance` is like this:
 ///
 /// ```plain
-/// total = available + pending
+/// total = available + pending + locked
 /// pending = sum(incoming coins of pending tx)
-/// available = sum(synced utxo - spent by pending tx)
+/// available = sum(synced utxo - spent by pending tx - timelocked utxo)
+/// locked = sum(timelocked utxo - spent by pending tx)
 /// ```
 ///
 /// For pending tx with n incoming coins (transfer from other wallet to our wallet or withdraw):
@@ -51,6 +52,8 @@ pub struct WalletBalance {
     pub available: Coin,
     /// The pending amount balance
     pub pending: Coin,
+    /// The amount balance in outputs that are still timelocked
+    pub locked: Coin,
 }
 
 /// Transaction pending infomation
@@ -64,6 +67,24 @@ pub struct TransactionPending {
     pub return_amount: Coin,
 }
 
+/// Pending transaction with its lifecycle details
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PendingTransactionInfo {
+    /// Transaction ID
+    #[serde(serialize_with = "serialize_transaction_id")]
+    #[serde(deserialize_with = "deserialize_transaction_id")]
+    pub transaction_id: TxId,
+    /// The pending information of the transaction
+    #[serde(flatten)]
+    pub pending: TransactionPending,
+    /// Number of blocks since the transaction was (re)broadcast
+    pub age: u64,
+    /// If the broadcast transaction is stored, so it can be rebroadcast
+    pub rebroadcastable: bool,
+    /// If the outputs of the transaction are stored, so it can be replaced with a higher fee
+    pub replaceable: bool,
+}
+
 /// Transaction data with attached metadata
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct TransactionChange {
@@ -114,6 +135,16 @@ pub enum TransactionType {
     Unjail,
     /// Nodejoin transaction
     Nodejoin,
+    /// Node metadata update transaction
+    NodeMetadataUpdate,
+    /// Transfer stake transaction
+    TransferStake,
+    /// Network parameter change proposal transaction
+    ParamChangeProposal,
+    /// Network parameter change vote transaction
+    ParamChangeVote,
+    /// Delegate transaction
+    Delegate,
 }
 
 impl fmt::Display for TransactionType {
@@ -125,6 +156,11 @@ impl fmt::Display for TransactionType {
             TransactionType::Deposit => write!(f, "Deposit"),
             TransactionType::Unjail => write!(f, "Unfail"),
             TransactionType::Nodejoin => write!(f, "Nodejoin"),
+            TransactionType::NodeMetadataUpdate => write!(f, "NodeMetadataUpdate"),
+            TransactionType::TransferStake => write!(f, "TransferStake"),
+            TransactionType::ParamChangeProposal => write!(f, "ParamChangeProposal"),
+            TransactionType::ParamChangeVote => write!(f, "ParamChangeVote"),
+            TransactionType::Delegate => write!(f, "Delegate"),
         }
     }
 }
@@ -260,6 +296,11 @@ impl From<&Transaction> for TransactionType {
             Transaction::DepositStakeTransaction(_) => TransactionType::Deposit,
             Transaction::UnjailTransaction(_) => TransactionType::Unjail,
             Transaction::NodejoinTransaction(_) => TransactionType::Nodejoin,
+            Transaction::NodeMetadataUpdateTransaction(_) => TransactionType::NodeMetadataUpdate,
+            Transaction::TransferStakeTransaction(_) => TransactionType::TransferStake,
+            Transaction::ParamChangeProposalTransaction(_) => TransactionType::ParamChangeProposal,
+            Transaction::ParamChangeVoteTransaction(_) => TransactionType::ParamChangeVote,
+            Transaction::DelegateTransaction(_) => TransactionType::Delegate,
         }
     }
 }
@@ -1,9 +1,10 @@
 //! Operations on unspent transactions
 use parity_scale_codec::{Decode, Encode};
 use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
 use std::ops::{Deref, DerefMut};
 
-use chain_core::init::coin::Coin;
+use chain_core::init::coin::{sum_coins, Coin, CoinError};
 use chain_core::tx::data::input::TxoPointer;
 use chain_core::tx::data::output::TxOut;
 use client_common::{Error, ErrorKind, Result, ResultExt};
@@ -27,10 +28,13 @@ use client_common::{Error, ErrorKind, Result, ResultExt};
 #[serde(transparent)]
 pub struct UnspentTransactions(Vec<(TxoPointer, TxOut)>);
 
+/// Maximum number of the branches explored when searching for an exact match
+const EXACT_MATCH_MAX_TRIES: usize = 100_000;
+
 /// An iterator over selected unspent transactions
 #[derive(Debug)]
 pub struct SelectedUnspentTransactions<'a> {
-    inner: &'a [(TxoPointer, TxOut)],
+    inner: Cow<'a, [(TxoPointer, TxOut)]>,
 }
 
 impl Deref for UnspentTransactions {
@@ -88,7 +92,18 @@ impl UnspentTransactions {
     }
 
     /// Selects unspent transactions for given amount and returns difference amount
+    #[inline]
     pub fn select(&self, amount: Coin) -> Result<(SelectedUnspentTransactions<'_>, Coin)> {
+        self.select_with_pinned(amount, 0)
+    }
+
+    /// Selects unspent transactions for given amount (at least the first `pinned` ones)
+    /// and returns difference amount
+    pub fn select_with_pinned(
+        &self,
+        amount: Coin,
+        pinned: usize,
+    ) -> Result<(SelectedUnspentTransactions<'_>, Coin)> {
         let mut selected_amount = Coin::zero();
 
         for (i, (_, unspent_transaction)) in self.0.iter().enumerate() {
@@ -99,10 +114,10 @@ impl UnspentTransactions {
                 )
             })?;
 
-            if selected_amount >= amount {
+            if selected_amount >= amount && i + 1 >= pinned {
                 return Ok((
                     SelectedUnspentTransactions {
-                        inner: &self.0[..=i],
+                        inner: Cow::Borrowed(&self.0[..=i]),
                     },
                     (selected_amount - amount).chain(|| {
                         (
@@ -117,12 +132,117 @@ impl UnspentTransactions {
         Err(Error::new(ErrorKind::InvalidInput, "Insufficient balance"))
     }
 
+    /// Searches (branch and bound, in the current order) for unspent transactions with total value
+    /// between `amount` and `amount + max_waste` (the first `pinned` ones are always selected),
+    /// so that no change is returned
+    pub fn select_exact(
+        &self,
+        amount: Coin,
+        max_waste: Coin,
+        pinned: usize,
+    ) -> Option<SelectedUnspentTransactions<'_>> {
+        let low = u64::from(amount);
+        let high = low.saturating_add(max_waste.into());
+        let pinned = pinned.min(self.0.len());
+        let pinned_value = self.0[..pinned]
+            .iter()
+            .map(|(_, output)| u64::from(output.value))
+            .sum::<u64>();
+
+        let values = self.0[pinned..]
+            .iter()
+            .map(|(_, output)| output.value.into())
+            .collect::<Vec<u64>>();
+        // the total value of the candidates after each position (for pruning)
+        let mut remaining = vec![0u64; values.len() + 1];
+        for i in (0..values.len()).rev() {
+            remaining[i] = remaining[i + 1].saturating_add(values[i]);
+        }
+
+        let mut chosen = Vec::new();
+        let mut tries = EXACT_MATCH_MAX_TRIES;
+        if !search_exact(
+            &values,
+            &remaining,
+            0,
+            pinned_value,
+            (low, high),
+            &mut chosen,
+            &mut tries,
+        ) {
+            return None;
+        }
+
+        let mut selected = self.0[..pinned].to_vec();
+        selected.extend(chosen.into_iter().map(|i| self.0[pinned + i].clone()));
+        Some(SelectedUnspentTransactions {
+            inner: Cow::Owned(selected),
+        })
+    }
+
+    /// Selects the first `count` unspent transactions
+    #[inline]
+    pub fn select_first(&self, count: usize) -> SelectedUnspentTransactions<'_> {
+        SelectedUnspentTransactions {
+            inner: Cow::Borrowed(&self.0[..count.min(self.0.len())]),
+        }
+    }
+
     /// Selects all unspent transactions
     pub fn select_all(&self) -> SelectedUnspentTransactions<'_> {
-        SelectedUnspentTransactions { inner: &self.0 }
+        SelectedUnspentTransactions {
+            inner: Cow::Borrowed(&self.0),
+        }
+    }
+
+    /// Splits unspent transactions into spendable and (time/height) locked ones
+    pub fn split_locked<F>(self, mut is_spendable: F) -> (Self, Self)
+    where
+        F: FnMut(&TxoPointer, &TxOut) -> bool,
+    {
+        let (spendable, locked) = self
+            .0
+            .into_iter()
+            .partition(|(input, output)| is_spendable(input, output));
+        (Self(spendable), Self(locked))
+    }
+
+    /// Returns the total amount of unspent transactions
+    pub fn total_amount(&self) -> std::result::Result<Coin, CoinError> {
+        sum_coins(self.0.iter().map(|(_, output)| output.value))
     }
 }
 
+/// Depth first search of the values (from position `i`) adding up to the `current` value
+/// within the `target` range (the indices of the used values are pushed to `chosen`)
+fn search_exact(
+    values: &[u64],
+    remaining: &[u64],
+    i: usize,
+    current: u64,
+    target: (u64, u64),
+    chosen: &mut Vec<usize>,
+    tries: &mut usize,
+) -> bool {
+    if current >= target.0 {
+        return current <= target.1;
+    }
+    if i == values.len() || current.saturating_add(remaining[i]) < target.0 || *tries == 0 {
+        return false;
+    }
+    *tries -= 1;
+
+    let with_value = current.saturating_add(values[i]);
+    if with_value <= target.1 {
+        chosen.push(i);
+        if search_exact(values, remaining, i + 1, with_value, target, chosen, tries) {
+            return true;
+        }
+        chosen.pop();
+    }
+    search_exact(values, remaining, i + 1, current, target, chosen, tries)
+}
+
 /// Builder for unspent transactions
 enum Builder {
     Normal(Vec<(TxoPointer, TxOut)>),
@@ -249,6 +369,55 @@ mod unspent_transactions_tests {
         }
     }
 
+    #[test]
+    fn check_select_with_pinned() {
+        let unspent_transactions = sample();
+        let (selected, change) = unspent_transactions
+            .select_with_pinned(Coin::new(100).unwrap(), 3)
+            .unwrap();
+        assert_eq!(3, selected.len());
+        assert_eq!(Coin::new(500).unwrap(), change);
+    }
+
+    #[test]
+    fn check_select_exact() {
+        let mut unspent_transactions = sample();
+        unspent_transactions.apply_all(&[Operation::Sort(Sorter::HighestValueFirst)]);
+
+        // 300 + 150
+        let selected = unspent_transactions
+            .select_exact(Coin::new(450).unwrap(), Coin::zero(), 0)
+            .unwrap();
+        assert_eq!(
+            Coin::new(450).unwrap(),
+            sum_coins(selected.iter().map(|(_, output)| output.value)).unwrap()
+        );
+
+        // the pinned 300 is always selected: 300 + 100
+        let selected = unspent_transactions
+            .select_exact(Coin::new(390).unwrap(), Coin::new(10).unwrap(), 1)
+            .unwrap();
+        assert_eq!(2, selected.len());
+        assert_eq!(Coin::new(300).unwrap(), selected[0].1.value);
+
+        assert!(unspent_transactions
+            .select_exact(Coin::new(1001).unwrap(), Coin::new(10).unwrap(), 0)
+            .is_none());
+        assert!(unspent_transactions
+            .select_exact(Coin::new(120).unwrap(), Coin::new(10).unwrap(), 0)
+            .is_none());
+    }
+
+    #[test]
+    fn check_split_locked() {
+        let (spendable, locked) =
+            sample().split_locked(|_, output| output.value < Coin::new(200).unwrap());
+        assert_eq!(2, spendable.len());
+        assert_eq!(3, locked.len());
+        assert_eq!(Coin::new(250).unwrap(), spendable.total_amount().unwrap());
+        assert_eq!(Coin::new(750).unwrap(), locked.total_amount().unwrap());
+    }
+
     #[test]
     fn check_lowest_value_first() {
         let operations = &[Operation::Sort(Sorter::LowestValueFirst)];
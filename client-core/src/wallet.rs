@@ -1,4 +1,6 @@
 //! Wallet management
+/// Encrypted wallet backups
+pub mod backup;
 mod default_wallet_client;
 /// Wallet synchronizer
 pub mod syncer;
@@ -16,6 +18,7 @@ use chain_core::common::{Proof, H256};
 use chain_core::init::address::RedeemAddress;
 use chain_core::init::coin::Coin;
 use chain_core::state::account::StakedStateAddress;
+use chain_core::tx::data::access::TxAccessPolicy;
 use chain_core::tx::data::address::ExtendedAddr;
 use chain_core::tx::data::attribute::TxAttributes;
 use chain_core::tx::data::input::TxoPointer;
@@ -23,6 +26,7 @@ use chain_core::tx::data::output::TxOut;
 #[cfg(feature = "experimental")]
 use chain_core::tx::data::Tx;
 use chain_core::tx::data::TxId;
+use chain_core::tx::fee::{DynamicFee, InclusionSpeed};
 use chain_core::tx::witness::tree::RawXOnlyPubkey;
 use chain_core::tx::TxAux;
 use client_common::tendermint::types::BroadcastTxResponse;
@@ -35,8 +39,12 @@ use serde::{Deserialize, Serialize};
 use crate::hd_wallet::HardwareKind;
 use crate::service::{SyncState, WalletInfo};
 use crate::transaction_builder::{SignedTransferTransaction, UnsignedTransferTransaction};
-use crate::types::{AddressType, TransactionChange, TransactionPending, WalletBalance, WalletKind};
-use crate::{InputSelectionStrategy, Mnemonic, UnspentTransactions};
+use crate::types::{
+    AddressType, PendingTransactionInfo, TransactionChange, TransactionPending, WalletBalance,
+    WalletKind,
+};
+use crate::wallet::backup::{BackupParts, WalletBackup};
+use crate::{InputSelection, Mnemonic, UnspentTransactions};
 
 /// information needed when create/delete a wallet
 #[derive(Debug, Deserialize, Serialize, Clone)]
@@ -79,6 +87,35 @@ pub trait WalletClient: Send + Sync {
         network_id: u8,
     ) -> Result<TxId>;
 
+    /// Send balance to a transfer address with the given access policies
+    /// (e.g. view keys restricted to the payment output) and input selection (strategy and coin control),
+    /// return the transaction id directly.
+    /// The wallet's own view key is always given the access to all data.
+    #[allow(clippy::too_many_arguments)]
+    fn send_to_address_with_access(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        amount: Coin,
+        address: ExtendedAddr,
+        access_policies: Vec<TxAccessPolicy>,
+        network_id: u8,
+        input_selection: Option<InputSelection>,
+    ) -> Result<TxId>;
+
+    /// Sends the amounts to many transfer addresses (one output per recipient), returns the id
+    /// of the transaction paying each recipient (in the order of the recipients).
+    /// The recipients are split into multiple transactions when a single one would exceed `TX_AUX_SIZE`;
+    /// all of them are built (and funded) before the first one is broadcast.
+    fn send_to_addresses(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        recipients: Vec<(ExtendedAddr, Coin)>,
+        view_keys: &mut BTreeSet<PublicKey>,
+        network_id: u8,
+    ) -> Result<Vec<TxId>>;
+
     /// send balance to a transfer address, waiting it transaction confirmed then return transaction id
     fn send_to_address_commit(
         &self,
@@ -90,6 +127,35 @@ pub trait WalletClient: Send + Sync {
         network_id: u8,
     ) -> Result<TxId>;
 
+    /// Returns the pending transactions (broadcast but not yet confirmed) with their age in blocks
+    fn pending_transactions(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+    ) -> Result<Vec<PendingTransactionInfo>>;
+
+    /// Broadcasts the stored pending transaction again (its age is reset, so it isn't rolled back
+    /// after `block_height_ensure` blocks from the first broadcast)
+    fn rebroadcast_transaction(&self, name: &str, enckey: &SecKey, txid: &TxId) -> Result<()>;
+
+    /// Replaces the pending transfer transaction with a new one spending the same inputs
+    /// (and more if needed) and paying the same outputs with at least the given fee,
+    /// return the id of the replacement
+    /// (the node rejects the replacement while the replaced transaction is in its mempool,
+    /// so it's meant for the transactions dropped from the mempool)
+    fn replace_transaction(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        txid: &TxId,
+        fee: Coin,
+    ) -> Result<TxId>;
+
+    /// Abandons the unconfirmed pending transaction, its inputs become available again.
+    /// It may still be included in a block (it's then picked up by the wallet synchronization),
+    /// so the transactions spending the released inputs may be rejected.
+    fn abandon_transaction(&self, name: &str, enckey: &SecKey, txid: &TxId) -> Result<()>;
+
     /// Retrieves names of all wallets stored
     fn wallets(&self) -> Result<Vec<String>>;
 
@@ -116,6 +182,23 @@ pub trait WalletClient: Send + Sync {
         wallet_info: WalletInfo,
     ) -> Result<SecKey>;
 
+    /// backup the wallet keys and the selected optional parts (to be encrypted in a backup container)
+    fn backup_wallet(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        parts: BackupParts,
+    ) -> Result<WalletBackup>;
+
+    /// restore the selected parts of the wallet backup under the given name
+    fn restore_wallet_backup(
+        &self,
+        name: &str,
+        passphrase: &SecUtf8,
+        backup: WalletBackup,
+        parts: BackupParts,
+    ) -> Result<SecKey>;
+
     /// Restores a HD wallet from given mnemonic
     fn restore_wallet(
         &self,
@@ -287,7 +370,8 @@ pub trait WalletClient: Send + Sync {
         transaction_id: &TxId,
     ) -> Result<Option<TransactionChange>>;
 
-    /// Retrieves all unspent transactions of wallet
+    /// Retrieves all unspent transactions of wallet which can be spent in the next block
+    /// (the ones used by pending transactions or still timelocked are excluded)
     fn unspent_transactions(&self, name: &str, enckey: &SecKey) -> Result<UnspentTransactions>;
 
     /// Checks if all the provided transaction inputs are present in unspent transaction for given wallet
@@ -310,7 +394,8 @@ pub trait WalletClient: Send + Sync {
     /// Returns output of transaction with given input details
     fn output(&self, name: &str, enckey: &SecKey, input: &TxoPointer) -> Result<TxOut>;
 
-    /// Builds a transaction
+    /// Builds a transaction (the outputs and attributes are stored with it,
+    /// so it can be replaced while it's pending)
     ///
     /// # Attributes
     ///
@@ -318,7 +403,7 @@ pub trait WalletClient: Send + Sync {
     /// - `enckey`: Passphrase of wallet
     /// - `outputs`: Transaction outputs
     /// - `attributes`: Transaction attributes,
-    /// - `input_selection_strategy`: Strategy to use while selecting unspent transactions
+    /// - `input_selection`: Strategy and coin control to use while selecting unspent transactions
     /// - `return_address`: Address to which change amount will get returned
     fn create_transaction(
         &self,
@@ -326,7 +411,7 @@ pub trait WalletClient: Send + Sync {
         enckey: &SecKey,
         outputs: Vec<TxOut>,
         attributes: TxAttributes,
-        input_selection_strategy: Option<InputSelectionStrategy>,
+        input_selection: Option<InputSelection>,
         return_address: ExtendedAddr,
     ) -> Result<(TxAux, Vec<TxoPointer>, Coin)>;
 
@@ -352,12 +437,16 @@ pub trait WalletClient: Send + Sync {
     /// Get the current block height
     fn get_current_block_height(&self) -> Result<u64>;
 
-    /// Update the wallet state
+    /// Suggests the fee policy for the transactions to be included at the given speed
+    /// (based on the current network parameters and base fee)
+    fn suggest_fee_policy(&self, speed: InclusionSpeed) -> Result<DynamicFee>;
+
+    /// Update the wallet state (the broadcast transaction is stored while it's pending)
     fn update_tx_pending_state(
         &self,
         name: &str,
         enckey: &SecKey,
-        tx_id: TxId,
+        tx_aux: &TxAux,
         tx_pending: TransactionPending,
     ) -> Result<()>;
 
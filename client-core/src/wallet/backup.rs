@@ -0,0 +1,316 @@
+//! Passphrase encrypted wallet backups
+//!
+//! The backup container is a JSON document with a plain header (format version, Argon2id parameters
+//! and salt) and the AES-GCM-SIV encrypted list of the wallet backups. The header is authenticated
+//! as the associated data, so it can't be changed without failing the decryption.
+use rand::rngs::OsRng;
+use rand::Rng;
+use secstr::SecUtf8;
+use serde::{Deserialize, Serialize};
+use zeroize::Zeroize;
+
+use client_common::seckey::{derive_enckey_with_salt, KdfParams};
+use client_common::storage::{decrypt_bytes, encrypt_bytes};
+use client_common::{Error, ErrorKind, Result, ResultExt};
+
+use crate::service::{deserde_from_str, serde_to_str, SyncState, WalletInfo, WalletState};
+
+/// Version of the backup container written by this client
+pub const BACKUP_VERSION: u16 = 1;
+
+/// Salt size in bytes
+const SALT_SIZE: usize = 16;
+
+/// Optional parts of a wallet backup: the keys (HD seed, key pairs, hardware wallet paths
+/// and multi-sig addresses) and the wallet meta data are always included
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BackupParts {
+    /// sync state together with the transaction history and unspent outputs
+    /// (they are only consistent with each other)
+    pub history: bool,
+}
+
+impl BackupParts {
+    /// all the parts
+    pub fn all() -> Self {
+        BackupParts { history: true }
+    }
+
+    /// only the keys (the history is synchronized again after the restore)
+    pub fn keys_only() -> Self {
+        BackupParts { history: false }
+    }
+}
+
+impl Default for BackupParts {
+    #[inline]
+    fn default() -> Self {
+        Self::all()
+    }
+}
+
+/// Backup of a single wallet
+#[derive(Serialize, Deserialize)]
+pub struct WalletBackup {
+    /// the parts included in the backup
+    pub parts: BackupParts,
+    /// keys and wallet meta data
+    pub info: WalletInfo,
+    /// wallet sync state
+    #[serde(
+        default,
+        deserialize_with = "deserde_from_str",
+        serialize_with = "serde_to_str"
+    )]
+    pub sync_state: Option<SyncState>,
+    /// wallet state (transaction history, unspent and pending transactions)
+    #[serde(
+        default,
+        deserialize_with = "deserde_from_str",
+        serialize_with = "serde_to_str"
+    )]
+    pub wallet_state: Option<WalletState>,
+}
+
+impl WalletBackup {
+    /// name of the backed up wallet
+    #[inline]
+    pub fn name(&self) -> &str {
+        &self.info.name
+    }
+
+    /// Drops the parts that shouldn't be restored (fails if a requested part isn't included)
+    pub fn select(mut self, parts: BackupParts) -> Result<Self> {
+        if parts.history && !self.parts.history {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "backup of wallet {} doesn't contain the transaction history",
+                    self.name()
+                ),
+            ));
+        }
+        if !parts.history {
+            self.sync_state = None;
+            self.wallet_state = None;
+        }
+        self.parts = parts;
+        Ok(self)
+    }
+}
+
+/// Passphrase encrypted backup container of one or more wallets
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EncryptedWalletBackup {
+    /// backup container version
+    pub version: u16,
+    /// key derivation parameters
+    pub kdf: KdfParams,
+    /// hex encoded key derivation salt
+    pub salt: String,
+    /// base64 encoded (nonce prefixed) encrypted list of the wallet backups
+    pub ciphertext: String,
+}
+
+impl EncryptedWalletBackup {
+    /// Encrypts the wallet backups with the key derived from the backup passphrase
+    pub fn seal(backups: &[WalletBackup], passphrase: &SecUtf8) -> Result<Self> {
+        Self::seal_with_params(backups, passphrase, KdfParams::default())
+    }
+
+    /// Encrypts the wallet backups with the given key derivation parameters
+    pub fn seal_with_params(
+        backups: &[WalletBackup],
+        passphrase: &SecUtf8,
+        kdf: KdfParams,
+    ) -> Result<Self> {
+        kdf.validate()?;
+        let mut salt = [0; SALT_SIZE];
+        OsRng.fill(&mut salt);
+        let mut backup = EncryptedWalletBackup {
+            version: BACKUP_VERSION,
+            kdf,
+            salt: hex::encode(&salt),
+            ciphertext: String::new(),
+        };
+        let enckey = derive_enckey_with_salt(passphrase, &salt, &kdf)
+            .err_kind(ErrorKind::EncryptionError, || {
+                "unable to derive encryption key from backup passphrase"
+            })?;
+        let mut plain = serde_json::to_vec(backups).chain(|| {
+            (
+                ErrorKind::SerializationError,
+                "Unable to serialize wallet backups",
+            )
+        })?;
+        let cipher = encrypt_bytes(backup.associated_data(), &enckey, &plain);
+        plain.zeroize();
+        backup.ciphertext = base64::encode(&cipher?);
+        Ok(backup)
+    }
+
+    /// Decrypts the wallet backups
+    pub fn open(&self, passphrase: &SecUtf8) -> Result<Vec<WalletBackup>> {
+        if self.version > BACKUP_VERSION {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("unsupported wallet backup version: {}", self.version),
+            ));
+        }
+        self.kdf.validate()?;
+        let salt = hex::decode(&self.salt)
+            .chain(|| (ErrorKind::DeserializationError, "Invalid backup salt"))?;
+        let cipher = base64::decode(&self.ciphertext)
+            .chain(|| (ErrorKind::DeserializationError, "Invalid backup ciphertext"))?;
+        let enckey = derive_enckey_with_salt(passphrase, &salt, &self.kdf)
+            .err_kind(ErrorKind::DecryptionError, || {
+                "unable to derive encryption key from backup passphrase"
+            })?;
+        let mut plain = decrypt_bytes(self.associated_data(), &enckey, &cipher)
+            .err_kind(ErrorKind::DecryptionError, || {
+                "Incorrect backup passphrase or corrupted backup"
+            })?;
+        let backups = serde_json::from_slice(&plain).chain(|| {
+            (
+                ErrorKind::DeserializationError,
+                "Unable to deserialize wallet backups",
+            )
+        });
+        plain.zeroize();
+        backups
+    }
+
+    /// the plain header authenticated together with the ciphertext
+    fn associated_data(&self) -> String {
+        format!(
+            "crypto.com chain wallet backup:{}:{}:{}:{}:{}",
+            self.version, self.kdf.mem_cost, self.kdf.time_cost, self.kdf.lanes, self.salt
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    use client_common::{PrivateKey, PublicKey};
+
+    use crate::service::Wallet;
+    use crate::types::WalletKind;
+
+    fn test_kdf() -> KdfParams {
+        KdfParams {
+            mem_cost: 64,
+            time_cost: 1,
+            lanes: 1,
+        }
+    }
+
+    fn wallet_backup(name: &str) -> WalletBackup {
+        let private_key = PrivateKey::new().unwrap();
+        let view_key = PublicKey::from(&private_key);
+        WalletBackup {
+            parts: BackupParts::all(),
+            info: WalletInfo {
+                name: name.to_owned(),
+                wallet: Wallet::new(view_key, WalletKind::Basic),
+                private_key,
+                passphrase: None,
+                key_pairs: BTreeMap::new(),
+                key_chainpath: BTreeMap::new(),
+                hdkey: None,
+                multisig_address_pair: BTreeMap::new(),
+            },
+            sync_state: Some(SyncState {
+                last_block_height: 10,
+                ..SyncState::genesis([0; 32])
+            }),
+            wallet_state: Some(WalletState::default()),
+        }
+    }
+
+    fn sealed() -> EncryptedWalletBackup {
+        EncryptedWalletBackup::seal_with_params(
+            &[wallet_backup("first"), wallet_backup("second")],
+            &SecUtf8::from("backup passphrase"),
+            test_kdf(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn check_seal_open_round_trip() {
+        let backup = sealed();
+        assert_eq!(backup.version, BACKUP_VERSION);
+        assert_eq!(backup.kdf, test_kdf());
+        let serialized = serde_json::to_string(&backup).unwrap();
+        let backup: EncryptedWalletBackup = serde_json::from_str(&serialized).unwrap();
+
+        let backups = backup.open(&SecUtf8::from("backup passphrase")).unwrap();
+        assert_eq!(backups.len(), 2);
+        assert_eq!(backups[0].name(), "first");
+        assert_eq!(backups[1].name(), "second");
+        assert_eq!(backups[0].parts, BackupParts::all());
+        assert_eq!(
+            backups[0].sync_state.as_ref().unwrap().last_block_height,
+            10
+        );
+        assert!(backups[0].wallet_state.is_some());
+    }
+
+    #[test]
+    fn check_wrong_passphrase_fails() {
+        let error = sealed()
+            .open(&SecUtf8::from("wrong passphrase"))
+            .err()
+            .unwrap();
+        assert_eq!(error.kind(), ErrorKind::DecryptionError);
+    }
+
+    #[test]
+    fn check_tampered_header_fails() {
+        let passphrase = SecUtf8::from("backup passphrase");
+        let backup = sealed();
+
+        let mut tampered = backup.clone();
+        tampered.version = 0;
+        let error = tampered.open(&passphrase).err().unwrap();
+        assert_eq!(error.kind(), ErrorKind::DecryptionError);
+
+        let mut tampered = backup.clone();
+        tampered.kdf.time_cost = 2;
+        let error = tampered.open(&passphrase).err().unwrap();
+        assert_eq!(error.kind(), ErrorKind::DecryptionError);
+
+        let mut tampered = backup;
+        tampered.salt = hex::encode(&[0; SALT_SIZE]);
+        let error = tampered.open(&passphrase).err().unwrap();
+        assert_eq!(error.kind(), ErrorKind::DecryptionError);
+    }
+
+    #[test]
+    fn check_newer_version_is_rejected() {
+        let mut backup = sealed();
+        backup.version = BACKUP_VERSION + 1;
+        let error = backup
+            .open(&SecUtf8::from("backup passphrase"))
+            .err()
+            .unwrap();
+        assert_eq!(error.kind(), ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn check_select_parts() {
+        let backup = wallet_backup("test")
+            .select(BackupParts::keys_only())
+            .unwrap();
+        assert_eq!(backup.parts, BackupParts::keys_only());
+        assert!(backup.sync_state.is_none());
+        assert!(backup.wallet_state.is_none());
+
+        // the history can't be restored from a keys-only backup
+        let error = backup.select(BackupParts::all()).err().unwrap();
+        assert_eq!(error.kind(), ErrorKind::InvalidInput);
+    }
+}
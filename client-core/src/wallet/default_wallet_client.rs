@@ -3,20 +3,24 @@ use crate::service::*;
 use crate::transaction_builder::UnauthorizedWalletTransactionBuilder;
 use crate::transaction_builder::{SignedTransferTransaction, UnsignedTransferTransaction};
 use crate::types::{
-    AddressType, BalanceChange, TransactionChange, TransactionPending, WalletBalance, WalletKind,
+    AddressType, BalanceChange, PendingTransactionInfo, TransactionChange, TransactionPending,
+    WalletBalance, WalletKind,
 };
+use crate::wallet::backup::{BackupParts, WalletBackup};
 use crate::wallet::syncer::{get_genesis_sync_state, AddressRecovery};
 use crate::wallet::syncer_logic::create_transaction_change;
 #[cfg(feature = "experimental")]
 use crate::MultiSigWalletClient;
 use crate::{
-    InputSelectionStrategy, Mnemonic, UnspentTransactions, WalletClient, WalletTransactionBuilder,
+    InputSelection, Mnemonic, UnspentTransactions, WalletClient, WalletTransactionBuilder,
 };
 use bit_vec::BitVec;
-use chain_core::common::{Proof, H256};
+use chain_core::common::{Proof, Timespec, H256};
 use chain_core::init::address::RedeemAddress;
-use chain_core::init::coin::Coin;
+use chain_core::init::coin::{sum_coins, Coin};
+use chain_core::init::params::NetworkParameters;
 use chain_core::state::account::StakedStateAddress;
+use chain_core::state::tendermint::BlockHeight;
 use chain_core::tx::data::access::{TxAccess, TxAccessPolicy};
 use chain_core::tx::data::address::ExtendedAddr;
 use chain_core::tx::data::attribute::TxAttributes;
@@ -25,11 +29,12 @@ use chain_core::tx::data::output::TxOut;
 #[cfg(feature = "experimental")]
 use chain_core::tx::data::Tx;
 use chain_core::tx::data::TxId;
-use chain_core::tx::fee::Fee;
+use chain_core::tx::fee::{DynamicFee, Fee, InclusionSpeed, Milli};
 use chain_core::tx::witness::tree::RawXOnlyPubkey;
 #[cfg(feature = "experimental")]
 use chain_core::tx::witness::{TxInWitness, TxWitness};
-use chain_core::tx::{TransactionId, TxAux, TxEnclaveAux, TxObfuscated};
+use chain_core::tx::{TransactionId, TxAux, TxEnclaveAux, TxObfuscated, TX_AUX_SIZE};
+use chrono::Utc;
 use client_common::tendermint::types::Time;
 use client_common::tendermint::types::{AbciQueryExt, BlockResults, BroadcastTxResponse};
 use client_common::tendermint::{Client, UnauthorizedClient};
@@ -40,13 +45,14 @@ use client_common::{
     PublicKey, Result, ResultExt, SecKey, Storage, Transaction, TransactionInfo,
 };
 use indexmap::IndexSet;
-use parity_scale_codec::Encode;
+use parity_scale_codec::{Decode, Encode};
 #[cfg(feature = "experimental")]
 use secp256k1::schnorrsig::SchnorrSignature;
 use secstr::SecUtf8;
 use std::collections::BTreeMap;
 use std::collections::BTreeSet;
 use std::convert::TryInto;
+use std::sync::{Arc, Mutex, PoisonError};
 use std::time::Duration;
 use zxcvbn::{feedback::Feedback, zxcvbn as estimate_password_strength};
 
@@ -71,8 +77,14 @@ where
     tendermint_client: C,
     transaction_builder: T,
     block_height_ensure: Option<u64>,
+    // outputs and attributes of the transfers built by this client,
+    // stored with the pending transaction once it's broadcast
+    built_transfers: Arc<Mutex<BTreeMap<TxId, PendingTransfer>>>,
 }
 
+/// Maximum number of built (but not yet broadcast) transfers remembered for the replacement
+const MAX_BUILT_TRANSFERS: usize = 64;
+
 impl<S, C, T> DefaultWalletClient<S, C, T>
 where
     S: Storage,
@@ -100,6 +112,7 @@ where
             tendermint_client,
             transaction_builder,
             block_height_ensure,
+            built_transfers: Default::default(),
         }
     }
 
@@ -117,6 +130,101 @@ where
             });
         Ok(tx_change.is_ok())
     }
+
+    /// builds the transactions of a batch payment without broadcasting them:
+    /// the recipients are split into as few transactions (of the maximum size) as needed
+    /// and each transaction spends different unspent transactions
+    fn plan_batch_payment(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        recipients: &[(ExtendedAddr, Coin)],
+        access_policies: Vec<TxAccessPolicy>,
+        network_id: u8,
+    ) -> Result<Vec<PlannedBatch>> {
+        let mut batches = Vec::new();
+        let mut planned = 0;
+        let mut used_inputs = Vec::new();
+        let mut batch_size = recipients.len();
+        let mut return_address = None;
+        while planned < recipients.len() {
+            let batch = &recipients[planned..(planned + batch_size).min(recipients.len())];
+            let outputs = batch
+                .iter()
+                .map(|(address, amount)| TxOut::new(address.clone(), *amount))
+                .collect();
+            let attributes = TxAttributes::new_with_access(network_id, access_policies.clone());
+            // the change address is reused when the transaction is rebuilt with fewer recipients
+            let change_address = match return_address.take() {
+                Some(address) => address,
+                None => self.new_transfer_address(name, enckey)?,
+            };
+            let input_selection = InputSelection {
+                exclude: used_inputs.clone(),
+                ..Default::default()
+            };
+            let (transaction, selected_inputs, return_amount) = self.create_transaction(
+                name,
+                enckey,
+                outputs,
+                attributes,
+                Some(input_selection),
+                change_address.clone(),
+            )?;
+
+            let size = transaction.encode().len();
+            if size > TX_AUX_SIZE {
+                if batch.len() == 1 {
+                    return Err(Error::new(
+                        ErrorKind::IllegalInput,
+                        "Transaction paying a single recipient exceeds the maximum size",
+                    ));
+                }
+                // the size is roughly proportional to the number of outputs (and their inputs)
+                batch_size = (batch.len() * TX_AUX_SIZE / size)
+                    .max(1)
+                    .min(batch.len() - 1);
+                return_address = Some(change_address);
+                continue;
+            }
+
+            used_inputs.extend(selected_inputs.iter().cloned());
+            planned += batch.len();
+            batches.push(PlannedBatch {
+                transaction,
+                selected_inputs,
+                return_amount,
+                recipients: batch.len(),
+            });
+        }
+        Ok(batches)
+    }
+
+    /// the time and height of the next block (used for checking the output timelocks),
+    /// assuming the wallet is synchronized
+    fn next_block(&self, name: &str) -> Result<(Timespec, BlockHeight)> {
+        let last_block_height = self
+            .sync_state_service
+            .get_global_state(name)?
+            .map(|state| state.last_block_height)
+            .unwrap_or_default();
+        let block_time = Utc::now().timestamp().try_into().unwrap_or_default();
+        Ok((block_time, BlockHeight::new(last_block_height + 1)))
+    }
+
+    /// the pending information of the transaction (fails if it isn't pending)
+    fn pending_transaction(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        txid: &TxId,
+    ) -> Result<TransactionPending> {
+        self.wallet_state_service
+            .get_wallet_state(name, enckey)?
+            .pending_transactions
+            .remove(txid)
+            .chain(|| not_pending(txid))
+    }
 }
 
 impl<S> DefaultWalletClient<S, UnauthorizedClient, UnauthorizedWalletTransactionBuilder>
@@ -190,10 +298,8 @@ where
             let newaddress: ExtendedAddr = self
                 .new_transfer_address(name, enckey)
                 .expect("get new transfer address");
-            match newaddress {
-                ExtendedAddr::OrTree(ref root_hash) => {
-                    wallet.root_hashes.insert(*root_hash);
-                }
+            if let ExtendedAddr::OrTree(ref root_hash) = newaddress {
+                wallet.root_hashes.insert(*root_hash);
             }
         }
 
@@ -201,6 +307,41 @@ where
     }
 }
 
+fn not_pending(txid: &TxId) -> (ErrorKind, String) {
+    (
+        ErrorKind::InvalidInput,
+        format!("Transaction {} is not pending", hex::encode(txid)),
+    )
+}
+
+/// Adds the recipients already paid (by the broadcasted transactions) to the error of a batch payment
+/// A transaction of a batch payment, built before any of them is broadcast
+struct PlannedBatch {
+    transaction: TxAux,
+    selected_inputs: Vec<TxoPointer>,
+    return_amount: Coin,
+    /// the number of the recipients paid by the transaction
+    recipients: usize,
+}
+
+fn batch_payment_error(error: Error, txids: &[TxId], recipients: usize) -> Error {
+    if txids.is_empty() {
+        return error;
+    }
+    let mut sent = txids.iter().map(hex::encode).collect::<Vec<_>>();
+    sent.dedup();
+    Error::new(
+        error.kind(),
+        format!(
+            "{} ({} of {} recipients already paid by transactions: {})",
+            error.message(),
+            txids.len(),
+            recipients,
+            sent.join(", ")
+        ),
+    )
+}
+
 impl<S, C, T> WalletClient for DefaultWalletClient<S, C, T>
 where
     S: Storage,
@@ -243,14 +384,11 @@ where
         view_keys: &mut BTreeSet<PublicKey>,
         network_id: u8,
     ) -> Result<TxId> {
-        let current_block_height = self.get_current_block_height()?;
-        let tx_out = TxOut::new(address, amount);
-
         let view_key = self.view_key(name, enckey)?;
 
         view_keys.insert(view_key);
 
-        let access_policies: BTreeSet<_> = view_keys
+        let access_policies = view_keys
             .iter()
             .map(|key| TxAccessPolicy {
                 view_key: key.into(),
@@ -258,12 +396,52 @@ where
             })
             .collect();
 
+        self.send_to_address_with_access(
+            name,
+            enckey,
+            amount,
+            address,
+            access_policies,
+            network_id,
+            None,
+        )
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn send_to_address_with_access(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        amount: Coin,
+        address: ExtendedAddr,
+        access_policies: Vec<TxAccessPolicy>,
+        network_id: u8,
+        input_selection: Option<InputSelection>,
+    ) -> Result<TxId> {
+        let current_block_height = self.get_current_block_height()?;
+        // the payment is the first output, the change (if any) is appended after it
+        let tx_out = TxOut::new(address, amount);
+
+        let view_key = self.view_key(name, enckey)?;
+
+        let mut access_policies: BTreeSet<_> = access_policies.into_iter().collect();
+        access_policies.insert(TxAccessPolicy {
+            view_key: (&view_key).into(),
+            access: TxAccess::AllData,
+        });
+
         let attributes =
             TxAttributes::new_with_access(network_id, access_policies.into_iter().collect());
 
         let return_address = self.new_transfer_address(name, enckey)?;
-        let (transaction, selected_inputs, return_amount) =
-            self.create_transaction(name, enckey, vec![tx_out], attributes, None, return_address)?;
+        let (transaction, selected_inputs, return_amount) = self.create_transaction(
+            name,
+            enckey,
+            vec![tx_out],
+            attributes,
+            input_selection,
+            return_address,
+        )?;
 
         self.broadcast_transaction(&transaction)?;
         //update the wallet state
@@ -273,7 +451,7 @@ where
             return_amount,
         };
 
-        self.update_tx_pending_state(name, enckey, transaction.tx_id(), tx_pending)?;
+        self.update_tx_pending_state(name, enckey, &transaction, tx_pending)?;
 
         if let TxAux::EnclaveTx(TxEnclaveAux::TransferTx {
             payload: TxObfuscated { txid, .. },
@@ -289,6 +467,51 @@ where
         }
     }
 
+    fn send_to_addresses(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        recipients: Vec<(ExtendedAddr, Coin)>,
+        view_keys: &mut BTreeSet<PublicKey>,
+        network_id: u8,
+    ) -> Result<Vec<TxId>> {
+        if recipients.is_empty() {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "No recipients of the batch payment",
+            ));
+        }
+        let view_key = self.view_key(name, enckey)?;
+        view_keys.insert(view_key);
+        let access_policies: Vec<_> = view_keys
+            .iter()
+            .map(|key| TxAccessPolicy {
+                view_key: key.into(),
+                access: TxAccess::AllData,
+            })
+            .collect();
+        // all the transactions are built before the first one is broadcast,
+        // so a batch payment which can't be funded doesn't pay only some of the recipients
+        let batches =
+            self.plan_batch_payment(name, enckey, &recipients, access_policies, network_id)?;
+        let current_block_height = self.get_current_block_height()?;
+
+        let mut txids = Vec::with_capacity(recipients.len());
+        for batch in batches {
+            self.broadcast_transaction(&batch.transaction)
+                .map_err(|e| batch_payment_error(e, &txids, recipients.len()))?;
+            let tx_pending = TransactionPending {
+                used_inputs: batch.selected_inputs,
+                block_height: current_block_height,
+                return_amount: batch.return_amount,
+            };
+            let txid = batch.transaction.tx_id();
+            self.update_tx_pending_state(name, enckey, &batch.transaction, tx_pending)?;
+            txids.extend(std::iter::repeat(txid).take(batch.recipients));
+        }
+        Ok(txids)
+    }
+
     /// broadcast transaction and waiting it confiremed
     fn send_to_address_commit(
         &self,
@@ -323,6 +546,217 @@ where
         Ok(tx_id)
     }
 
+    fn pending_transactions(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+    ) -> Result<Vec<PendingTransactionInfo>> {
+        // Check if wallet exists
+        self.wallet_service.view_key(name, enckey)?;
+
+        let wallet_state = self.wallet_state_service.get_wallet_state(name, enckey)?;
+        let current_block_height = self.get_current_block_height()?;
+        wallet_state
+            .pending_transactions
+            .into_iter()
+            .map(|(transaction_id, pending)| {
+                let data = self.wallet_state_service.get_pending_transaction_data(
+                    name,
+                    enckey,
+                    &transaction_id,
+                )?;
+                Ok(PendingTransactionInfo {
+                    transaction_id,
+                    age: current_block_height.saturating_sub(pending.block_height),
+                    rebroadcastable: data.is_some(),
+                    replaceable: data.map_or(false, |data| data.transfer.is_some()),
+                    pending,
+                })
+            })
+            .collect()
+    }
+
+    fn rebroadcast_transaction(&self, name: &str, enckey: &SecKey, txid: &TxId) -> Result<()> {
+        let mut pending = self.pending_transaction(name, enckey, txid)?;
+        let data = self
+            .wallet_state_service
+            .get_pending_transaction_data(name, enckey, txid)?
+            .chain(|| {
+                (
+                    ErrorKind::InvalidInput,
+                    format!(
+                        "broadcast transaction {} is not stored, it can't be rebroadcast",
+                        hex::encode(txid)
+                    ),
+                )
+            })?;
+        self.broadcast_transaction(&data.tx_aux)?;
+
+        pending.block_height = self.get_current_block_height()?;
+        let mut memento = WalletStateMemento::default();
+        memento.remove_pending_transaction(*txid);
+        memento.add_pending_transaction(*txid, pending);
+        self.wallet_state_service
+            .apply_memento(name, enckey, &memento)
+    }
+
+    fn replace_transaction(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        txid: &TxId,
+        fee: Coin,
+    ) -> Result<TxId> {
+        let mut wallet_state = self.wallet_state_service.get_wallet_state(name, enckey)?;
+        let pending = wallet_state
+            .pending_transactions
+            .remove(txid)
+            .chain(|| not_pending(txid))?;
+        let transfer = self
+            .wallet_state_service
+            .get_pending_transaction_data(name, enckey, txid)?
+            .and_then(|data| data.transfer)
+            .chain(|| {
+                (
+                    ErrorKind::InvalidInput,
+                    "Only the transfer transactions built by this wallet can be replaced",
+                )
+            })?;
+
+        // the fee of the replaced transaction
+        let input_values = pending
+            .used_inputs
+            .iter()
+            .map(|input| {
+                wallet_state
+                    .unspent_transactions
+                    .get(input)
+                    .map(|output| output.value)
+            })
+            .collect::<Option<Vec<_>>>()
+            .chain(|| {
+                (
+                    ErrorKind::InvalidInput,
+                    "Inputs of the pending transaction are already spent",
+                )
+            })?;
+        let input_value = sum_coins(input_values.into_iter()).chain(|| {
+            (
+                ErrorKind::IllegalInput,
+                "Sum of input values exceeds maximum allowed amount",
+            )
+        })?;
+        let output_value = sum_coins(
+            transfer
+                .outputs
+                .iter()
+                .map(|output| output.value)
+                .chain(std::iter::once(pending.return_amount)),
+        )
+        .chain(|| {
+            (
+                ErrorKind::IllegalInput,
+                "Sum of output values exceeds maximum allowed amount",
+            )
+        })?;
+        let replaced_fee = (input_value - output_value).chain(|| {
+            (
+                ErrorKind::IllegalInput,
+                "Sum of output values exceeds the sum of input values",
+            )
+        })?;
+        if fee <= replaced_fee {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "Fee must be higher than the fee of the replaced transaction: {}",
+                    replaced_fee
+                ),
+            ));
+        }
+
+        // the inputs of the replaced transaction are available again
+        let (block_time, block_height) = self.next_block(name)?;
+        let unspent_transactions = UnspentTransactions::new(
+            wallet_state
+                .get_spendable_transactions(block_time, block_height)
+                .into_iter()
+                .collect(),
+        );
+        let input_selection = InputSelection {
+            include: pending.used_inputs,
+            min_fee: fee,
+            ..Default::default()
+        };
+        let return_address = self.new_transfer_address(name, enckey)?;
+        let (transaction, selected_inputs, return_amount) =
+            self.transaction_builder.build_transfer_tx(
+                name,
+                enckey,
+                unspent_transactions,
+                transfer.outputs.clone(),
+                return_address,
+                transfer.attributes.clone(),
+                &input_selection,
+            )?;
+        let current_block_height = self.get_current_block_height()?;
+        self.broadcast_transaction(&transaction)?;
+
+        let replacement_txid = transaction.tx_id();
+        self.wallet_state_service.set_pending_transaction_data(
+            name,
+            enckey,
+            &replacement_txid,
+            &PendingTransactionData {
+                tx_aux: transaction,
+                transfer: Some(transfer),
+            },
+        )?;
+        let mut memento = WalletStateMemento::default();
+        memento.remove_pending_transaction(*txid);
+        memento.add_pending_transaction(
+            replacement_txid,
+            TransactionPending {
+                used_inputs: selected_inputs,
+                block_height: current_block_height,
+                return_amount,
+            },
+        );
+        self.wallet_state_service
+            .apply_memento(name, enckey, &memento)?;
+        self.wallet_state_service
+            .prune_pending_transaction_data(name, enckey)?;
+        Ok(replacement_txid)
+    }
+
+    fn abandon_transaction(&self, name: &str, enckey: &SecKey, txid: &TxId) -> Result<()> {
+        self.pending_transaction(name, enckey, txid)?;
+        let included = self
+            .tendermint_client
+            .query("meta", &txid.to_vec(), None, false)
+            .is_ok();
+        if included {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "Transaction {} is already included in a block, synchronize the wallet instead",
+                    hex::encode(txid)
+                ),
+            ));
+        }
+        log::warn!(
+            "abandoning the unconfirmed transaction {}, it may still be included in a block",
+            hex::encode(txid)
+        );
+
+        let mut memento = WalletStateMemento::default();
+        memento.remove_pending_transaction(*txid);
+        self.wallet_state_service
+            .apply_memento(name, enckey, &memento)?;
+        self.wallet_state_service
+            .prune_pending_transaction_data(name, enckey)
+    }
+
     #[inline]
     fn wallets(&self) -> Result<Vec<String>> {
         self.wallet_service.names()
@@ -457,6 +891,51 @@ where
         Ok(enckey)
     }
 
+    fn backup_wallet(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        parts: BackupParts,
+    ) -> Result<WalletBackup> {
+        let info = self.export_wallet(name, enckey)?;
+        let (sync_state, wallet_state) = if parts.history {
+            (
+                self.sync_state_service.get_global_state(name)?,
+                Some(self.wallet_state_service.get_wallet_state(name, enckey)?),
+            )
+        } else {
+            (None, None)
+        };
+        WalletBackup {
+            parts: BackupParts::all(),
+            info,
+            sync_state,
+            wallet_state,
+        }
+        .select(parts)
+    }
+
+    fn restore_wallet_backup(
+        &self,
+        name: &str,
+        passphrase: &SecUtf8,
+        backup: WalletBackup,
+        parts: BackupParts,
+    ) -> Result<SecKey> {
+        let mut backup = backup.select(parts)?;
+        backup.info.name = name.to_owned();
+        let enckey = self.import_wallet(name, passphrase, backup.info)?;
+        if let Some(sync_state) = backup.sync_state {
+            self.sync_state_service
+                .save_global_state(name, &sync_state)?;
+        }
+        if let Some(wallet_state) = backup.wallet_state {
+            self.wallet_state_service
+                .set_wallet_state(name, &enckey, &wallet_state)?;
+        }
+        Ok(enckey)
+    }
+
     fn new_wallet(
         &self,
         name: &str,
@@ -931,6 +1410,10 @@ where
                 self.root_hash_service
                     .generate_proof(name, address, public_keys, enckey)
             }
+            ExtendedAddr::HashTimeLock(_) => Err(Error::new(
+                ErrorKind::InvalidInput,
+                "Merkle proofs can only be generated for tree addresses",
+            )),
         }
     }
 
@@ -946,7 +1429,9 @@ where
     fn balance(&self, name: &str, enckey: &SecKey) -> Result<WalletBalance> {
         // Check if wallet exists
         self.wallet_service.view_key(name, enckey)?;
-        self.wallet_state_service.get_balance(name, enckey)
+        let (block_time, block_height) = self.next_block(name)?;
+        self.wallet_state_service
+            .get_balance(name, enckey, block_time, block_height)
     }
 
     fn history(
@@ -986,9 +1471,13 @@ where
         // Check if wallet exists
         self.wallet_service.view_key(name, enckey)?;
 
-        let unspent_transactions = self
-            .wallet_state_service
-            .get_unspent_transactions(name, enckey, false)?;
+        let (block_time, block_height) = self.next_block(name)?;
+        let unspent_transactions = self.wallet_state_service.get_spendable_transactions(
+            name,
+            enckey,
+            block_time,
+            block_height,
+        )?;
 
         Ok(UnspentTransactions::new(
             unspent_transactions.into_iter().collect(),
@@ -1042,20 +1531,37 @@ where
         enckey: &SecKey,
         outputs: Vec<TxOut>,
         attributes: TxAttributes,
-        input_selection_strategy: Option<InputSelectionStrategy>,
+        input_selection: Option<InputSelection>,
         return_address: ExtendedAddr,
     ) -> Result<(TxAux, Vec<TxoPointer>, Coin)> {
-        let mut unspent_transactions = self.unspent_transactions(name, enckey)?;
-        unspent_transactions.apply_all(input_selection_strategy.unwrap_or_default().as_ref());
+        let unspent_transactions = self.unspent_transactions(name, enckey)?;
 
-        self.transaction_builder.build_transfer_tx(
-            name,
-            enckey,
-            unspent_transactions,
-            outputs,
-            return_address,
-            attributes,
-        )
+        let (transaction, selected_inputs, return_amount) =
+            self.transaction_builder.build_transfer_tx(
+                name,
+                enckey,
+                unspent_transactions,
+                outputs.clone(),
+                return_address,
+                attributes.clone(),
+                &input_selection.unwrap_or_default(),
+            )?;
+        // only stored once the transaction is broadcast (see `update_tx_pending_state`)
+        let mut built_transfers = self
+            .built_transfers
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        if built_transfers.len() >= MAX_BUILT_TRANSFERS {
+            built_transfers.clear();
+        }
+        built_transfers.insert(
+            transaction.tx_id(),
+            PendingTransfer {
+                outputs,
+                attributes,
+            },
+        );
+        Ok((transaction, selected_inputs, return_amount))
     }
 
     #[inline]
@@ -1154,17 +1660,65 @@ where
         Ok(current_block_height)
     }
 
+    fn suggest_fee_policy(&self, speed: InclusionSpeed) -> Result<DynamicFee> {
+        let bytes = self
+            .tendermint_client
+            .query("network-params", &[], None, false)?
+            .bytes();
+        let params = NetworkParameters::decode(&mut bytes.as_slice()).chain(|| {
+            (
+                ErrorKind::DeserializationError,
+                "Cannot deserialize network parameters",
+            )
+        })?;
+        let bytes = self
+            .tendermint_client
+            .query("base-fee", &[], None, false)?
+            .bytes();
+        let base_fee = Milli::decode(&mut bytes.as_slice()).chain(|| {
+            (
+                ErrorKind::DeserializationError,
+                "Cannot deserialize base fee",
+            )
+        })?;
+        Ok(DynamicFee::suggest(
+            params.get_params().initial_fee_policy,
+            base_fee,
+            params.get_dynamic_fee_config().max_change_rate,
+            speed,
+        ))
+    }
+
     fn update_tx_pending_state(
         &self,
         name: &str,
         enckey: &SecKey,
-        tx_id: TxId,
+        tx_aux: &TxAux,
         tx_pending: TransactionPending,
     ) -> Result<()> {
+        let tx_id = tx_aux.tx_id();
         let mut wallet_state_memento = WalletStateMemento::default();
         wallet_state_memento.add_pending_transaction(tx_id, tx_pending);
         self.wallet_state_service
-            .apply_memento(name, enckey, &wallet_state_memento)
+            .apply_memento(name, enckey, &wallet_state_memento)?;
+
+        // the outputs of the transfers built by this client are kept for the replacement
+        let transfer = self
+            .built_transfers
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(&tx_id);
+        self.wallet_state_service.set_pending_transaction_data(
+            name,
+            enckey,
+            &tx_id,
+            &PendingTransactionData {
+                tx_aux: tx_aux.clone(),
+                transfer,
+            },
+        )?;
+        self.wallet_state_service
+            .prune_pending_transaction_data(name, enckey)
     }
 
     fn build_raw_transfer_tx(
@@ -1222,6 +1776,7 @@ where
                 vec![tx_out],
                 return_address,
                 attributes,
+                &InputSelection::default(),
             )?;
         let signed_tx = SignedTransferTransaction {
             signed_transaction: transaction,
@@ -1250,7 +1805,7 @@ where
 
         let transaction = signed_tx.signed_transaction;
 
-        self.update_tx_pending_state(name, enckey, transaction.tx_id(), tx_pending)?;
+        self.update_tx_pending_state(name, enckey, &transaction, tx_pending)?;
 
         if let TxAux::EnclaveTx(TxEnclaveAux::TransferTx {
             payload: TxObfuscated { txid, .. },
@@ -1541,10 +2096,237 @@ fn import_transaction(
 mod tests {
     use super::*;
     use crate::Mnemonic;
+    use chain_core::init::coin::sum_coins;
+    use chain_core::tx::data::input::TxoSize;
+    use chain_core::tx::data::Tx;
     use client_common::storage::MemoryStorage;
     use client_common::PublicKey;
+    use client_common::SignedTransaction;
     use std::str::FromStr;
 
+    #[test]
+    fn check_batch_payment_error() {
+        let error = Error::new(ErrorKind::InvalidInput, "Insufficient balance");
+        assert_eq!(
+            "Insufficient balance",
+            batch_payment_error(error, &[], 3).message()
+        );
+
+        let error = Error::new(ErrorKind::InvalidInput, "Insufficient balance");
+        let error = batch_payment_error(error, &[[1; 32], [1; 32]], 3);
+        assert_eq!(ErrorKind::InvalidInput, error.kind());
+        assert_eq!(
+            format!(
+                "Insufficient balance (2 of 3 recipients already paid by transactions: {})",
+                hex::encode([1; 32])
+            ),
+            error.message()
+        );
+    }
+
+    /// builds fake transfer transactions without fee (their size is a quarter of the maximum
+    /// per output), spending the unspent transactions with the highest value first
+    #[derive(Clone)]
+    struct MockTransactionBuilder;
+
+    impl WalletTransactionBuilder for MockTransactionBuilder {
+        fn build_transfer_tx(
+            &self,
+            _name: &str,
+            _enckey: &SecKey,
+            mut unspent_transactions: UnspentTransactions,
+            outputs: Vec<TxOut>,
+            _return_address: ExtendedAddr,
+            _attributes: TxAttributes,
+            input_selection: &InputSelection,
+        ) -> Result<(TxAux, Vec<TxoPointer>, Coin)> {
+            input_selection.apply(&mut unspent_transactions)?;
+            let amount = sum_coins(outputs.iter().map(|output| output.value)).unwrap();
+            let (selected, return_amount) = unspent_transactions.select(amount)?;
+            let inputs = selected
+                .iter()
+                .map(|(input, _)| input.clone())
+                .collect::<Vec<_>>();
+            let tx = Tx::new_with(inputs.clone(), outputs.clone(), TxAttributes::default());
+            let transaction = TxAux::EnclaveTx(TxEnclaveAux::TransferTx {
+                inputs: inputs.clone(),
+                no_of_outputs: outputs.len() as TxoSize,
+                payload: TxObfuscated {
+                    key_from: BlockHeight::genesis(),
+                    init_vector: [0; 12],
+                    txpayload: vec![0; outputs.len() * TX_AUX_SIZE / 4],
+                    txid: tx.id(),
+                },
+            });
+            Ok((transaction, inputs, return_amount))
+        }
+
+        fn obfuscate(&self, _signed_transaction: SignedTransaction) -> Result<TxAux> {
+            unreachable!()
+        }
+
+        fn decrypt_tx(&self, _txid: TxId, _private_key: &PrivateKey) -> Result<Transaction> {
+            unreachable!()
+        }
+    }
+
+    fn batch_payment_wallet(
+        unspent: &[u64],
+    ) -> (
+        DefaultWalletClient<MemoryStorage, UnauthorizedClient, MockTransactionBuilder>,
+        SecKey,
+    ) {
+        let wallet = DefaultWalletClient::new(
+            MemoryStorage::default(),
+            UnauthorizedClient,
+            MockTransactionBuilder,
+            None,
+            HwKeyService::default(),
+        );
+        let (enckey, _) = wallet
+            .new_wallet(
+                "name",
+                &SecUtf8::from("passphrase"),
+                WalletKind::Basic,
+                None,
+            )
+            .unwrap();
+        let address = wallet.new_transfer_address("name", &enckey).unwrap();
+        let mut memento = WalletStateMemento::default();
+        for (i, value) in unspent.iter().enumerate() {
+            memento.add_unspent_transaction(
+                TxoPointer::new([i as u8; 32], 0),
+                TxOut::new(address.clone(), Coin::new(*value).unwrap()),
+            );
+        }
+        wallet
+            .wallet_state_service
+            .apply_memento("name", &enckey, &memento)
+            .unwrap();
+        (wallet, enckey)
+    }
+
+    fn batch_recipients(n: usize, amount: u64) -> Vec<(ExtendedAddr, Coin)> {
+        (0..n)
+            .map(|i| {
+                (
+                    ExtendedAddr::OrTree([i as u8; 32]),
+                    Coin::new(amount).unwrap(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn check_batch_payment_split() {
+        let (wallet, enckey) = batch_payment_wallet(&[1000, 1000]);
+        let batches = wallet
+            .plan_batch_payment("name", &enckey, &batch_recipients(5, 300), vec![], 0)
+            .unwrap();
+
+        // at most 3 outputs fit into a transaction
+        assert_eq!(
+            batches
+                .iter()
+                .map(|batch| batch.recipients)
+                .collect::<Vec<_>>(),
+            vec![3, 2]
+        );
+        assert!(batches
+            .iter()
+            .all(|batch| batch.transaction.encode().len() <= TX_AUX_SIZE));
+        assert_eq!(batches[0].return_amount, Coin::new(100).unwrap());
+        assert_eq!(batches[1].return_amount, Coin::new(400).unwrap());
+        // each transaction spends a different unspent transaction
+        assert_eq!(batches[0].selected_inputs.len(), 1);
+        assert_eq!(batches[1].selected_inputs.len(), 1);
+        assert_ne!(batches[0].selected_inputs, batches[1].selected_inputs);
+    }
+
+    #[test]
+    fn check_batch_payment_partial_funding() {
+        // the first transaction can be funded, the second one can't
+        let (wallet, enckey) = batch_payment_wallet(&[1000, 500]);
+        let error = wallet
+            .send_to_addresses(
+                "name",
+                &enckey,
+                batch_recipients(5, 300),
+                &mut BTreeSet::new(),
+                0,
+            )
+            .unwrap_err();
+
+        // nothing reached the (unauthorized) tendermint client and nothing is pending
+        assert_eq!(ErrorKind::InvalidInput, error.kind());
+        assert_eq!("Insufficient balance", error.message());
+        assert!(wallet
+            .wallet_state_service
+            .get_wallet_state("name", &enckey)
+            .unwrap()
+            .pending_transactions
+            .is_empty());
+    }
+
+    #[test]
+    fn check_pending_transfer_stored_after_broadcast() {
+        let (wallet, enckey) = batch_payment_wallet(&[1000]);
+        let return_address = wallet.new_transfer_address("name", &enckey).unwrap();
+        let (transaction, used_inputs, return_amount) = wallet
+            .create_transaction(
+                "name",
+                &enckey,
+                batch_recipients(1, 300)
+                    .into_iter()
+                    .map(|(address, value)| TxOut::new(address, value))
+                    .collect(),
+                TxAttributes::default(),
+                None,
+                return_address,
+            )
+            .unwrap();
+        let txid = transaction.tx_id();
+        // not stored before it's broadcast
+        assert!(wallet
+            .wallet_state_service
+            .get_pending_transaction_data("name", &enckey, &txid)
+            .unwrap()
+            .is_none());
+
+        wallet
+            .update_tx_pending_state(
+                "name",
+                &enckey,
+                &transaction,
+                TransactionPending {
+                    used_inputs,
+                    block_height: 1,
+                    return_amount,
+                },
+            )
+            .unwrap();
+        let data = wallet
+            .wallet_state_service
+            .get_pending_transaction_data("name", &enckey, &txid)
+            .unwrap()
+            .unwrap();
+        // the outputs are kept for the replacement
+        assert_eq!(data.transfer.unwrap().outputs.len(), 1);
+
+        // the unconfirmed transaction can be abandoned
+        wallet.abandon_transaction("name", &enckey, &txid).unwrap();
+        let wallet_state = wallet
+            .wallet_state_service
+            .get_wallet_state("name", &enckey)
+            .unwrap();
+        assert!(wallet_state.pending_transactions.is_empty());
+        assert!(wallet
+            .wallet_state_service
+            .get_pending_transaction_data("name", &enckey, &txid)
+            .unwrap()
+            .is_none());
+    }
+
     #[test]
     fn check_delete_wallet() {
         let words = Mnemonic::from_secstr(&SecUtf8::from("pony thank pluck sweet bless tuna couple eight stove fluid essay debate cinnamon elite only")).unwrap();
@@ -1643,6 +2425,103 @@ mod tests {
         );
     }
 
+    #[test]
+    fn check_backup_and_selective_restore() {
+        use crate::wallet::backup::EncryptedWalletBackup;
+        use client_common::seckey::KdfParams;
+
+        let words = Mnemonic::from_secstr(&SecUtf8::from("pony thank pluck sweet bless tuna couple eight stove fluid essay debate cinnamon elite only")).unwrap();
+        let passphrase = SecUtf8::from("123456");
+        let backup_passphrase = SecUtf8::from("backup passphrase");
+        let client = DefaultWalletClient::new_read_only(MemoryStorage::default());
+        let enckey = client
+            .restore_wallet("Default", &passphrase, &words)
+            .expect("restore wallet");
+        let address = client
+            .new_transfer_address("Default", &enckey)
+            .expect("new transfer address");
+        let mut sync_state = SyncState::genesis([0; 32]);
+        sync_state.last_block_height = 5;
+        client
+            .sync_state_service
+            .save_global_state("Default", &sync_state)
+            .unwrap();
+
+        let backup = client
+            .backup_wallet("Default", &enckey, BackupParts::all())
+            .unwrap();
+        let params = KdfParams {
+            mem_cost: 64,
+            time_cost: 1,
+            lanes: 1,
+        };
+        let sealed =
+            EncryptedWalletBackup::seal_with_params(&[backup], &backup_passphrase, params).unwrap();
+        let sealed: EncryptedWalletBackup =
+            serde_json::from_str(&serde_json::to_string(&sealed).unwrap()).unwrap();
+        assert!(sealed.open(&passphrase).is_err());
+        let mut tampered = sealed.clone();
+        tampered.kdf.time_cost = 2;
+        assert!(tampered.open(&backup_passphrase).is_err());
+        // the key isn't derived with excessive parameters
+        tampered.kdf.mem_cost = u32::MAX;
+        assert_eq!(
+            tampered.open(&backup_passphrase).err().unwrap().kind(),
+            ErrorKind::InvalidInput
+        );
+
+        let mut backups = sealed.open(&backup_passphrase).unwrap();
+        assert_eq!(backups.len(), 1);
+        let backup = backups.remove(0);
+        assert_eq!(backup.name(), "Default");
+
+        // keys only
+        let backup_keys = serde_json::from_str(&serde_json::to_string(&backup).unwrap()).unwrap();
+        let restored_enckey = client
+            .restore_wallet_backup(
+                "Restored",
+                &passphrase,
+                backup_keys,
+                BackupParts::keys_only(),
+            )
+            .unwrap();
+        assert!(client
+            .transfer_addresses("Restored", &restored_enckey)
+            .unwrap()
+            .contains(&address));
+        assert!(client
+            .sync_state_service
+            .get_global_state("Restored")
+            .unwrap()
+            .is_none());
+
+        // with history
+        let restored_enckey = client
+            .restore_wallet_backup("Restored2", &passphrase, backup, BackupParts::all())
+            .unwrap();
+        assert!(client
+            .transfer_addresses("Restored2", &restored_enckey)
+            .unwrap()
+            .contains(&address));
+        assert_eq!(
+            client
+                .sync_state_service
+                .get_global_state("Restored2")
+                .unwrap()
+                .unwrap()
+                .last_block_height,
+            5
+        );
+
+        // the history isn't in a keys only backup
+        let backup = client
+            .backup_wallet("Default", &enckey, BackupParts::keys_only())
+            .unwrap();
+        assert!(client
+            .restore_wallet_backup("Restored3", &passphrase, backup, BackupParts::all())
+            .is_err());
+    }
+
     #[test]
     fn check_restore_basic_wallet() {
         let private_key =
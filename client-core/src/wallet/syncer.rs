@@ -36,9 +36,10 @@ use chain_core::state::ChainState;
 use chain_core::tx::data::address::ExtendedAddr;
 use chain_core::tx::data::TxId;
 use chain_core::tx::fee::Fee;
-use chain_core::tx::TransactionId;
+use chain_core::tx::{TransactionId, TxRestrictedView};
 use chain_storage::jellyfish::compute_staking_root;
 use chain_tx_filter::BlockFilter;
+use client_common::tendermint::proof::verify_tx_inclusion_query;
 use client_common::tendermint::types::{
     Block, BlockExt, BlockResults, BlockResultsResponse, Genesis, Time,
 };
@@ -51,6 +52,7 @@ use client_common::{
 use super::syncer_logic::handle_blocks;
 use crate::service;
 use crate::service::{KeyService, SyncState, Wallet, WalletState, WalletStateMemento};
+use crate::types::{TransactionChange, WalletBalance};
 
 pub trait LightClientHandle: Handle + Send + Sync + Clone {}
 impl<T: Handle + Send + Sync + Clone> LightClientHandle for T {}
@@ -71,6 +73,14 @@ pub trait AddressRecovery: Clone + Send + Sync {
 pub trait TxDecryptor: Clone + Send + Sync {
     /// decrypt transaction
     fn decrypt_tx(&self, txids: &[TxId]) -> Result<Vec<Transaction>>;
+
+    /// decrypt transaction, also returning the views of transactions the wallet only has a restricted access to
+    fn decrypt_tx_with_restricted(
+        &self,
+        txids: &[TxId],
+    ) -> Result<(Vec<Transaction>, Vec<TxRestrictedView>)> {
+        Ok((self.decrypt_tx(txids)?, Vec::new()))
+    }
 }
 
 impl<F> TxDecryptor for F
@@ -103,6 +113,14 @@ impl<O: TransactionObfuscation> TxDecryptor for TxObfuscationDecryptor<O> {
     fn decrypt_tx(&self, txids: &[TxId]) -> Result<Vec<Transaction>> {
         self.obfuscation.decrypt(&txids, &self.private_key)
     }
+
+    fn decrypt_tx_with_restricted(
+        &self,
+        txids: &[TxId],
+    ) -> Result<(Vec<Transaction>, Vec<TxRestrictedView>)> {
+        self.obfuscation
+            .decrypt_with_restricted(txids, &self.private_key)
+    }
 }
 
 /// Configuration options for synchronizer
@@ -342,6 +360,30 @@ impl<
         })
     }
 
+    /// Reports the transactions found in the synchronized blocks (up to `block`)
+    /// together with the wallet balance after them
+    fn report_transactions(
+        &mut self,
+        block: &FilteredBlock,
+        transactions: Vec<TransactionChange>,
+    ) -> Result<bool> {
+        let block_time = block
+            .block_time
+            .duration_since(Time::unix_epoch())
+            .map(|duration| duration.as_secs())
+            .unwrap_or_default();
+        let balance = self
+            .wallet_state
+            .get_balance(block_time, (block.block_height + 1).into())
+            .chain(|| (ErrorKind::StorageError, "Calculate balance error"))?;
+        Ok((self.progress_callback)(ProgressReport::Transactions {
+            wallet_name: self.env.name.clone(),
+            block_height: block.block_height,
+            transactions,
+            balance,
+        }))
+    }
+
     fn update_state(&mut self, memento: &WalletStateMemento) -> Result<()> {
         self.wallet_state = service::modify_wallet_state(
             &self.env.storage,
@@ -413,7 +455,10 @@ impl<
             .iter()
             .flat_map(|block| block.enclave_transaction_ids.iter().copied())
             .collect::<Vec<_>>();
-        let enclave_txs = self.env.decryptor.decrypt_tx(&enclave_txids)?;
+        let (enclave_txs, restricted_txs) = self
+            .env
+            .decryptor
+            .decrypt_tx_with_restricted(&enclave_txids)?;
 
         if self.env.options.enable_address_recovery
             && crate::types::WalletKind::HD == self.wallet.wallet_kind
@@ -422,8 +467,14 @@ impl<
             self.handle_recover_addresses(&blocks)?;
         }
 
-        let memento = handle_blocks(&self.wallet, &mut self.wallet_state, &blocks, &enclave_txs)
-            .map_err(|err| Error::new(ErrorKind::InvalidInput, err.to_string()))?;
+        let memento = handle_blocks(
+            &self.wallet,
+            &mut self.wallet_state,
+            &blocks,
+            &enclave_txs,
+            &restricted_txs,
+        )
+        .map_err(|err| Error::new(ErrorKind::InvalidInput, err.to_string()))?;
 
         let block = blocks.last();
         self.sync_state.last_block_height = block.block_height;
@@ -432,6 +483,10 @@ impl<
         self.sync_state.staking_root = block.staking_root;
         self.save(&memento)?;
 
+        let transactions = memento.transaction_changes().cloned().collect::<Vec<_>>();
+        if !transactions.is_empty() && !self.report_transactions(block, transactions)? {
+            return Err(Error::new(ErrorKind::InvalidInput, "Cancelled by user"));
+        }
         if !self.update_progress(block.block_height) {
             return Err(Error::new(ErrorKind::InvalidInput, "Cancelled by user"));
         }
@@ -500,8 +555,10 @@ impl<
             if self.env.options.enable_fast_forward {
                 if let Some(block) = self.fast_forward_status(&target_app_hash, target_height)? {
                     // Fast forward to latest state if possible
-                    self.handle_batch((batch, block).into())?;
-                    return Ok(());
+                    if self.verify_transaction_inclusion(&block)? {
+                        self.handle_batch((batch, block).into())?;
+                        return Ok(());
+                    }
                 }
             }
 
@@ -512,8 +569,10 @@ impl<
                 let block = self.env.client.block(range[range.len() - 1])?;
                 if let Some(block) = self.fast_forward_block(&block)? {
                     // Fast forward batch if possible
-                    self.handle_batch((batch, block).into())?;
-                    continue;
+                    if self.verify_transaction_inclusion(&block)? {
+                        self.handle_batch((batch, block).into())?;
+                        continue;
+                    }
                 }
             }
 
@@ -607,6 +666,41 @@ impl<
         self.save(&memento)
     }
 
+    /// Verifies the wallet's transactions in the fast forwarded block were included in the chain
+    /// (the app hash chain isn't checked block by block when fast forwarding),
+    /// using the light client verified header of the next block.
+    /// Returns false if the header isn't available yet, the blocks are then synced one by one
+    /// (checking the app hash chain), so nothing is applied without verification.
+    fn verify_transaction_inclusion(&self, block: &FilteredBlock) -> Result<bool> {
+        if block.enclave_transaction_ids.is_empty() {
+            return Ok(true);
+        }
+        let light_block = match self
+            .env
+            .light_client
+            .verify_to_target(block.block_height + 1)
+        {
+            Ok(light_block) => light_block,
+            Err(err) => {
+                // the next block may not be available yet
+                log::warn!(
+                    "transactions in block {} can't be verified yet, not fast forwarding: {}",
+                    block.block_height,
+                    err
+                );
+                return Ok(false);
+            }
+        };
+        for txid in block.enclave_transaction_ids.iter() {
+            let query =
+                self.env
+                    .client
+                    .query("store", txid, Some(block.block_height.into()), true)?;
+            verify_tx_inclusion_query(&query, txid, &light_block.signed_header.header)?;
+        }
+        Ok(true)
+    }
+
     /// Fast forwards state to given status if app hashes match
     fn fast_forward_status(
         &self,
@@ -739,6 +833,18 @@ pub enum ProgressReport {
         /// Current synchronized block height
         current_block_height: u64,
     },
+    /// Report of the wallet transactions found in the synchronized blocks
+    /// (sent before the progress update of the blocks)
+    Transactions {
+        /// Name of wallet
+        wallet_name: String,
+        /// Current synchronized block height
+        block_height: u64,
+        /// Transaction changes of the wallet
+        transactions: Vec<TransactionChange>,
+        /// Wallet balance after the transactions
+        balance: WalletBalance,
+    },
 }
 
 /// Structure for representing a block header on Crypto.com Chain,
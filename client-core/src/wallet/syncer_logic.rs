@@ -10,7 +10,7 @@ use chain_core::tx::{
         TxId,
     },
     fee::Fee,
-    TransactionId,
+    TransactionId, TxRestrictedView,
 };
 use client_common::tendermint::types::Time;
 use client_common::Transaction;
@@ -36,11 +36,16 @@ pub(crate) fn handle_blocks(
     wallet_state: &mut WalletState,
     blocks: &[FilteredBlock],
     enclave_transactions: &[Transaction],
+    restricted_transactions: &[TxRestrictedView],
 ) -> Result<WalletStateMemento, SyncerLogicError> {
     let enclave_transactions = enclave_transactions
         .iter()
         .map(|tx| (tx.id(), tx))
         .collect::<HashMap<_, _>>();
+    let restricted_transactions = restricted_transactions
+        .iter()
+        .map(|view| (view.txid, view))
+        .collect::<HashMap<_, _>>();
     let mut memento = WalletStateMemento::default();
 
     for block in blocks {
@@ -59,16 +64,27 @@ pub(crate) fn handle_blocks(
         }
 
         for txid in block.enclave_transaction_ids.iter() {
-            if let (Some(tx), Some(fee)) = (
-                enclave_transactions.get(txid),
-                block.valid_transaction_fees.get(txid),
-            ) {
+            let fee = match block.valid_transaction_fees.get(txid) {
+                Some(fee) => *fee,
+                None => continue,
+            };
+            if let Some(tx) = enclave_transactions.get(txid) {
                 handle_transaction(
                     wallet,
                     wallet_state,
                     &mut memento,
                     tx,
-                    *fee,
+                    fee,
+                    block.block_height,
+                    block.block_time,
+                )?;
+            } else if let Some(view) = restricted_transactions.get(txid) {
+                handle_restricted_view(
+                    wallet,
+                    wallet_state,
+                    &mut memento,
+                    view,
+                    fee,
                     block.block_height,
                     block.block_time,
                 )?;
@@ -153,6 +169,64 @@ pub(crate) fn handle_transaction(
     Ok(())
 }
 
+/// Update WalletStateMemento with a transaction the wallet was only given a restricted access to
+/// (the sender disclosed some outputs or only the metadata); the disclosed outputs to the wallet's
+/// addresses are recorded as incoming
+pub(crate) fn handle_restricted_view(
+    wallet: &Wallet,
+    wallet_state: &mut WalletState,
+    memento: &mut WalletStateMemento,
+    view: &TxRestrictedView,
+    fee_paid: Fee,
+    block_height: u64,
+    block_time: Time,
+) -> Result<(), SyncerLogicError> {
+    let transfer_addresses = wallet.transfer_addresses();
+    let our_outputs = view
+        .outputs
+        .iter()
+        .filter(|(_, output)| transfer_addresses.contains(&output.address))
+        .collect::<Vec<_>>();
+    if our_outputs.is_empty() {
+        return Ok(());
+    }
+    let value = sum_outputs(our_outputs.iter().map(|(_, output)| output))
+        .map_err(|_| SyncerLogicError::TotalOutputOutOfBound(hex::encode(&view.txid)))?;
+
+    for (index, output) in our_outputs {
+        memento.add_unspent_transaction(
+            TxoPointer::new(view.txid, usize::from(*index)),
+            output.clone(),
+        );
+    }
+
+    // withdrawals are the only transactions with outputs and without UTXO inputs
+    let transaction_type = if view.no_of_inputs == 0 {
+        TransactionType::Withdraw
+    } else {
+        TransactionType::Transfer
+    };
+    let transaction_change = TransactionChange {
+        transaction_id: view.txid,
+        inputs: Vec::new(),
+        outputs: view
+            .outputs
+            .iter()
+            .map(|(_, output)| output.clone())
+            .collect(),
+        fee_paid,
+        balance_change: BalanceChange::Incoming { value },
+        transaction_type,
+        block_height,
+        block_time,
+    };
+    memento.add_transaction_change(transaction_change.clone());
+    // write to state
+    wallet_state.add_transaction_change(transaction_change.transaction_id, transaction_change);
+
+    Ok(())
+}
+
 pub fn decorate_inputs(
     wallet_state: &WalletState,
     raw_inputs: &[TxoPointer],
@@ -251,9 +325,11 @@ mod tests {
     use chain_core::state::account::{
         DepositBondTx, StakedStateAddress, StakedStateOpAttributes, UnbondTx,
     };
+    use chain_core::state::tendermint::BlockHeight;
+    use chain_core::tx::data::access::TxAccess;
     use chain_core::tx::data::{address::ExtendedAddr, attribute::TxAttributes, output::TxOut, Tx};
     use chain_core::tx::fee::Fee;
-    use chain_core::tx::TransactionId;
+    use chain_core::tx::{TransactionId, TxWithOutputs};
     use chain_tx_filter::BlockFilter;
     use client_common::{storage::MemoryStorage, PublicKey, Result, Transaction};
 
@@ -385,7 +461,7 @@ mod tests {
             &[unbond_transaction(), outgoing_staking_tx],
             [0u8; 32],
         )];
-        let memento = handle_blocks(&wallets[0], &mut state, &blocks, &[tx.clone()]).unwrap();
+        let memento = handle_blocks(&wallets[0], &mut state, &blocks, &[tx.clone()], &[]).unwrap();
         state.apply_memento(&memento).expect("apply memento");
         assert!(state.transaction_history.contains_key(&tx_cloned.id()));
         assert!(state
@@ -427,12 +503,12 @@ mod tests {
         let txs = [transactions[0].clone()];
         let blocks = [block_header(&[view_keys[0].clone()], &txs, &[], [0u8; 32])];
         {
-            let memento = handle_blocks(&wallets[0], &mut states[0], &blocks, &txs)
+            let memento = handle_blocks(&wallets[0], &mut states[0], &blocks, &txs, &[])
                 .expect("handle block for wallet1");
             states[0].apply_memento(&memento).expect("apply memento1");
         }
         {
-            let memento = handle_blocks(&wallets[1], &mut states[1], &blocks, &[])
+            let memento = handle_blocks(&wallets[1], &mut states[1], &blocks, &[], &[])
                 .expect("handle block for wallet2");
             states[1].apply_memento(&memento).expect("apply memento2");
         }
@@ -443,29 +519,93 @@ mod tests {
         let blocks = [block_header(&view_keys, &txs, &[], [0u8; 32])];
 
         {
-            let memento = handle_blocks(&wallets[0], &mut states[0], &blocks, &txs)
+            let memento = handle_blocks(&wallets[0], &mut states[0], &blocks, &txs, &[])
                 .expect("handle block for wallet1");
             states[0].apply_memento(&memento).expect("apply memento1");
         }
 
         {
-            let memento = handle_blocks(&wallets[1], &mut states[1], &blocks, &txs)
+            let memento = handle_blocks(&wallets[1], &mut states[1], &blocks, &txs, &[])
                 .expect("handle block for wallet2");
             states[1].apply_memento(&memento).expect("apply memento2");
         }
 
         assert_eq!(
-            states[0].get_balance().unwrap().total,
+            states[0]
+                .get_balance(0, BlockHeight::genesis())
+                .unwrap()
+                .total,
             Coin::new(0).unwrap()
         );
         assert_eq!(states[0].transaction_history.len(), 2);
         assert_eq!(states[0].unspent_transactions.len(), 0);
 
         assert_eq!(
-            states[1].get_balance().unwrap().total,
+            states[1]
+                .get_balance(0, BlockHeight::genesis())
+                .unwrap()
+                .total,
             Coin::new(100).unwrap()
         );
         assert_eq!(states[1].transaction_history.len(), 1);
         assert_eq!(states[1].unspent_transactions.len(), 1);
     }
+
+    #[test]
+    fn check_syncer_logic_restricted_view() {
+        let wallets = create_test_wallet(1).unwrap();
+        let view_keys = wallets
+            .iter()
+            .map(|wallet| wallet.view_key.clone())
+            .collect::<Vec<_>>();
+        let address = wallets[0].transfer_addresses().into_iter().next().unwrap();
+        let tx = Tx::new_with(
+            vec![TxoPointer::new([5; 32], 0)],
+            vec![
+                TxOut::new(ExtendedAddr::OrTree([0; 32]), Coin::new(50).unwrap()),
+                TxOut::new(address, Coin::new(100).unwrap()),
+            ],
+            TxAttributes::default(),
+        );
+        let txid = tx.id();
+        let view = TxWithOutputs::Transfer(tx.clone()).restricted_view(&TxAccess::Outputs(vec![1]));
+        let blocks = [block_header(
+            &view_keys,
+            &[Transaction::TransferTransaction(tx)],
+            &[],
+            [0u8; 32],
+        )];
+
+        let mut state = WalletState::default();
+        let memento = handle_blocks(&wallets[0], &mut state, &blocks, &[], &[view]).unwrap();
+        state.apply_memento(&memento).expect("apply memento");
+
+        assert_eq!(state.unspent_transactions.len(), 1);
+        assert!(state
+            .unspent_transactions
+            .contains_key(&TxoPointer::new(txid, 1)));
+        assert_eq!(
+            state.transaction_history[&txid].balance_change,
+            BalanceChange::Incoming {
+                value: Coin::new(100).unwrap()
+            }
+        );
+        assert_eq!(
+            state.get_balance(0, BlockHeight::genesis()).unwrap().total,
+            Coin::new(100).unwrap()
+        );
+
+        // metadata only access doesn't disclose any output to the wallet
+        let mut state = WalletState::default();
+        let view = TxRestrictedView {
+            txid,
+            no_of_inputs: 1,
+            no_of_outputs: 2,
+            outputs: Vec::new(),
+        };
+        let memento = handle_blocks(&wallets[0], &mut state, &blocks, &[], &[view]).unwrap();
+        state.apply_memento(&memento).expect("apply memento");
+        assert!(state.unspent_transactions.is_empty());
+        assert!(state.transaction_history.is_empty());
+    }
 }
@@ -7,6 +7,7 @@
 //! This crate provides and easy to use client for performing network operations on Crypto.com Chain. Payments, on the
 //! other hand, are handled by `WalletClient` in `client-core` crate.
 pub mod network_ops;
+pub mod proof;
 
 #[doc(inline)]
 pub use self::network_ops::NetworkOpsClient;
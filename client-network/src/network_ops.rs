@@ -3,9 +3,13 @@ mod default_network_ops_client;
 
 pub use self::default_network_ops_client::DefaultNetworkOpsClient;
 use chain_core::init::coin::Coin;
+use chain_core::init::params::{NetworkParameterChange, NetworkParameters};
 use chain_core::state::account::{
-    CouncilNodeMeta, StakedState, StakedStateAddress, StakedStateOpAttributes,
+    CouncilNodeMeta, NodeName, NodeSecurityContact, SlashRecord, StakeKind, StakedState,
+    StakedStateAddress, StakedStateOpAttributes,
 };
+use chain_core::state::governance::{Proposal, ProposalId};
+use chain_core::state::tendermint::TendermintValidatorPubKey;
 use chain_core::tx::data::address::ExtendedAddr;
 use chain_core::tx::data::attribute::TxAttributes;
 use chain_core::tx::data::input::TxoPointer;
@@ -85,6 +89,83 @@ pub trait NetworkOpsClient: Send + Sync {
         verify_staking: bool,
     ) -> Result<TxAux>;
 
+    /// Creates a new transaction for updating the metadata of a council node
+    /// (`None` keeps the current value)
+    #[allow(clippy::too_many_arguments)]
+    fn create_node_metadata_update_transaction(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        staking_account_address: StakedStateAddress,
+        attributes: StakedStateOpAttributes,
+        node_name: Option<NodeName>,
+        security_contact: Option<NodeSecurityContact>,
+        consensus_pubkey: Option<TendermintValidatorPubKey>,
+        verify_staking: bool,
+    ) -> Result<TxAux>;
+
+    /// Creates a new transaction for transferring bonded or unbonded stake
+    /// between two staking accounts of the wallet (the fee is paid by the source account)
+    #[allow(clippy::too_many_arguments)]
+    fn create_transfer_stake_transaction(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        from_address: StakedStateAddress,
+        to_address: StakedStateAddress,
+        value: Coin,
+        kind: StakeKind,
+        attributes: StakedStateOpAttributes,
+        verify_staking: bool,
+    ) -> Result<TxAux>;
+
+    /// Creates a new transaction for proposing a network parameter change
+    /// (the staking account has to be an active council node)
+    fn create_param_change_proposal_transaction(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        address: StakedStateAddress,
+        attributes: StakedStateOpAttributes,
+        change: NetworkParameterChange,
+        verify_staking: bool,
+    ) -> Result<TxAux>;
+
+    /// Creates a new transaction for voting on a network parameter change proposal
+    /// (the staking account has to be an active council node)
+    #[allow(clippy::too_many_arguments)]
+    fn create_param_change_vote_transaction(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        address: StakedStateAddress,
+        attributes: StakedStateOpAttributes,
+        proposal_id: ProposalId,
+        approve: bool,
+        verify_staking: bool,
+    ) -> Result<TxAux>;
+
+    /// Creates a new transaction for backing a council node with the bonded coins of the staking account
+    /// (or for stopping backing it if `council_node` is `None`)
+    fn create_delegate_transaction(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        address: StakedStateAddress,
+        attributes: StakedStateOpAttributes,
+        council_node: Option<StakedStateAddress>,
+        verify_staking: bool,
+    ) -> Result<TxAux>;
+
+    /// Returns the current network parameters
+    fn get_network_params(&self) -> Result<NetworkParameters>;
+
+    /// Returns the network parameter change proposals in the voting period
+    fn get_proposals(&self) -> Result<Vec<Proposal>>;
+
+    /// Returns the history of the slashes of given staking address
+    fn get_slash_history(&self, address: &StakedStateAddress) -> Result<Vec<SlashRecord>>;
+
     /// Returns staked stake corresponding to given address
     fn get_staked_state(
         &self,
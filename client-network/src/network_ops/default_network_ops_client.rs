@@ -3,11 +3,17 @@ use parity_scale_codec::Decode;
 use crate::NetworkOpsClient;
 use chain_core::common::Timespec;
 use chain_core::init::coin::{sum_coins, Coin};
+use chain_core::init::params::{NetworkParameterChange, NetworkParameters};
 use chain_core::state::account::{
-    CouncilNodeMeta, DepositBondTx, NodeMetadata, StakedState, StakedStateAddress,
-    StakedStateOpAttributes, StakedStateOpWitness, UnbondTx, UnjailTx, WithdrawUnbondedTx,
+    CouncilNodeMeta, DelegateTx, DepositBondTx, NodeMetadata, NodeName, NodeSecurityContact,
+    NodeState, SlashRecord, StakeKind, StakedState, StakedStateAddress, StakedStateOpAttributes,
+    StakedStateOpWitness, TransferStakeTx, UnbondTx, UnjailTx, WithdrawUnbondedTx,
 };
-use chain_core::state::validator::NodeJoinRequestTx;
+use chain_core::state::governance::{
+    ParamChangeProposalTx, ParamChangeVoteTx, Proposal, ProposalId,
+};
+use chain_core::state::tendermint::TendermintValidatorPubKey;
+use chain_core::state::validator::{NodeJoinRequestTx, NodeMetadataUpdateTx};
 use chain_core::tx::data::address::ExtendedAddr;
 use chain_core::tx::data::attribute::TxAttributes;
 use chain_core::tx::data::input::TxoPointer;
@@ -94,6 +100,50 @@ where
         Ok(fee)
     }
 
+    /// Returns the staked state of an active council node (required for governance transactions)
+    fn get_council_node_state(
+        &self,
+        name: &str,
+        address: &StakedStateAddress,
+        verify_staking: bool,
+    ) -> Result<StakedState> {
+        let staked_state = self.get_staked_state(name, address, verify_staking)?;
+        match &staked_state.node_meta {
+            Some(NodeState::CouncilNode(validator))
+                if !staked_state.is_jailed() && validator.is_active() =>
+            {
+                Ok(staked_state)
+            }
+            _ => Err(Error::new(
+                ErrorKind::IllegalInput,
+                "Only active council nodes can propose or vote on network parameter changes (synchronizing your wallet may help)",
+            )),
+        }
+    }
+
+    /// Signs the staking operation with the staking key of the address in the wallet
+    fn sign_staking_op(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        address: &StakedStateAddress,
+        tx: &Transaction,
+    ) -> Result<StakedStateOpWitness> {
+        let public_key = match address {
+            StakedStateAddress::BasicRedeem(ref redeem_address) => self
+                .wallet_client
+                .find_staking_key(name, enckey, redeem_address)?
+                .chain(|| {
+                    (
+                        ErrorKind::InvalidInput,
+                        "Address not found in current wallet",
+                    )
+                })?,
+        };
+        let sign_key = self.wallet_client.sign_key(name, enckey, &public_key)?;
+        sign_key.sign(tx).map(StakedStateOpWitness::new)
+    }
+
     fn get_last_block_time(&self) -> Result<Timespec> {
         let status = self.client.status()?;
         Ok(to_timespec(
@@ -462,6 +512,311 @@ where
         )))
     }
 
+    fn create_node_metadata_update_transaction(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        staking_account_address: StakedStateAddress,
+        attributes: StakedStateOpAttributes,
+        node_name: Option<NodeName>,
+        security_contact: Option<NodeSecurityContact>,
+        consensus_pubkey: Option<TendermintValidatorPubKey>,
+        verify_staking: bool,
+    ) -> Result<TxAux> {
+        let staked_state = self.get_staked_state(name, &staking_account_address, verify_staking)?;
+
+        verify_unjailed(&staked_state).map_err(|e| {
+            Error::new(
+                ErrorKind::ValidationError,
+                format!("Failed to validate staking account: {}", e),
+            )
+        })?;
+
+        let council_node = match &staked_state.node_meta {
+            Some(NodeState::CouncilNode(validator)) => &validator.council_node,
+            _ => {
+                return Err(Error::new(
+                    ErrorKind::IllegalInput,
+                    "You can only update the metadata of a council node (synchronizing your wallet may help)",
+                ))
+            }
+        };
+
+        let transaction = NodeMetadataUpdateTx::new(
+            staked_state.nonce,
+            staking_account_address,
+            attributes,
+            node_name.unwrap_or_else(|| council_node.node_info.name.clone()),
+            security_contact.unwrap_or_else(|| council_node.node_info.security_contact.clone()),
+            consensus_pubkey,
+        );
+        let tx = Transaction::NodeMetadataUpdateTransaction(transaction.clone());
+
+        let public_key = match staking_account_address {
+            StakedStateAddress::BasicRedeem(ref redeem_address) => self
+                .wallet_client
+                .find_staking_key(name, enckey, redeem_address)?
+                .chain(|| {
+                    (
+                        ErrorKind::InvalidInput,
+                        "Address not found in current wallet",
+                    )
+                })?,
+        };
+        let sign_key = self.wallet_client.sign_key(name, enckey, &public_key)?;
+        let signature = sign_key.sign(&tx).map(StakedStateOpWitness::new)?;
+
+        let txaux = TxAux::PublicTx(TxPublicAux::NodeMetadataUpdateTx(transaction, signature));
+
+        let fee = self
+            .fee_algorithm
+            .calculate_for_txaux(&txaux)
+            .chain(|| {
+                (
+                    ErrorKind::IllegalInput,
+                    "Calculated fee is more than the maximum allowed value",
+                )
+            })?
+            .to_coin();
+        if staked_state.bonded < fee {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "Staking account does not have enough bonded coins to pay the fee (synchronizing your wallet may help)",
+            ));
+        }
+
+        Ok(txaux)
+    }
+
+    fn create_transfer_stake_transaction(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        from_address: StakedStateAddress,
+        to_address: StakedStateAddress,
+        value: Coin,
+        kind: StakeKind,
+        attributes: StakedStateOpAttributes,
+        verify_staking: bool,
+    ) -> Result<TxAux> {
+        if from_address == to_address {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "Source and destination staking accounts must be different",
+            ));
+        }
+        let from_state = self.get_staked_state(name, &from_address, verify_staking)?;
+        let to_state = self.get_staked_state(name, &to_address, verify_staking)?;
+        for staked_state in [&from_state, &to_state].iter() {
+            verify_unjailed(staked_state).map_err(|e| {
+                Error::new(
+                    ErrorKind::ValidationError,
+                    format!("Failed to validate staking account: {}", e),
+                )
+            })?;
+        }
+
+        let transaction = TransferStakeTx::new(
+            from_address,
+            to_address,
+            from_state.nonce,
+            value,
+            kind,
+            attributes,
+        );
+        let tx = Transaction::TransferStakeTransaction(transaction.clone());
+
+        // both staking accounts have to sign the transfer
+        let sign = |address: &StakedStateAddress| -> Result<StakedStateOpWitness> {
+            let public_key = match address {
+                StakedStateAddress::BasicRedeem(ref redeem_address) => self
+                    .wallet_client
+                    .find_staking_key(name, enckey, redeem_address)?
+                    .chain(|| {
+                        (
+                            ErrorKind::InvalidInput,
+                            "Address not found in current wallet",
+                        )
+                    })?,
+            };
+            let sign_key = self.wallet_client.sign_key(name, enckey, &public_key)?;
+            sign_key.sign(&tx).map(StakedStateOpWitness::new)
+        };
+        let from_signature = sign(&from_address)?;
+        let to_signature = sign(&to_address)?;
+
+        let txaux = TxAux::PublicTx(TxPublicAux::TransferStakeTx(
+            transaction,
+            from_signature,
+            to_signature,
+        ));
+
+        let fee = self
+            .fee_algorithm
+            .calculate_for_txaux(&txaux)
+            .chain(|| {
+                (
+                    ErrorKind::IllegalInput,
+                    "Calculated fee is more than the maximum allowed value",
+                )
+            })?
+            .to_coin();
+        let available = match kind {
+            StakeKind::Bonded => from_state.bonded,
+            StakeKind::Unbonded => from_state.unbonded,
+        };
+        if available < (value + fee).unwrap() {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "Staking account does not have enough coins to transfer (synchronizing your wallet may help)",
+            ));
+        }
+
+        Ok(txaux)
+    }
+
+    fn create_param_change_proposal_transaction(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        address: StakedStateAddress,
+        attributes: StakedStateOpAttributes,
+        change: NetworkParameterChange,
+        verify_staking: bool,
+    ) -> Result<TxAux> {
+        change
+            .validate()
+            .map_err(|e| Error::new(ErrorKind::InvalidInput, e))?;
+        let staked_state = self.get_council_node_state(name, &address, verify_staking)?;
+
+        let transaction =
+            ParamChangeProposalTx::new(staked_state.nonce, address, attributes, change);
+        let tx = Transaction::ParamChangeProposalTransaction(transaction.clone());
+        let signature = self.sign_staking_op(name, enckey, &address, &tx)?;
+
+        Ok(TxAux::PublicTx(TxPublicAux::ParamChangeProposalTx(
+            transaction,
+            signature,
+        )))
+    }
+
+    fn create_param_change_vote_transaction(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        address: StakedStateAddress,
+        attributes: StakedStateOpAttributes,
+        proposal_id: ProposalId,
+        approve: bool,
+        verify_staking: bool,
+    ) -> Result<TxAux> {
+        if !self
+            .get_proposals()?
+            .iter()
+            .any(|proposal| proposal.id == proposal_id)
+        {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "Proposal not found (its voting period may have ended)",
+            ));
+        }
+        let staked_state = self.get_council_node_state(name, &address, verify_staking)?;
+
+        let transaction = ParamChangeVoteTx::new(
+            staked_state.nonce,
+            address,
+            attributes,
+            proposal_id,
+            approve,
+        );
+        let tx = Transaction::ParamChangeVoteTransaction(transaction.clone());
+        let signature = self.sign_staking_op(name, enckey, &address, &tx)?;
+
+        Ok(TxAux::PublicTx(TxPublicAux::ParamChangeVoteTx(
+            transaction,
+            signature,
+        )))
+    }
+
+    fn create_delegate_transaction(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        address: StakedStateAddress,
+        attributes: StakedStateOpAttributes,
+        council_node: Option<StakedStateAddress>,
+        verify_staking: bool,
+    ) -> Result<TxAux> {
+        let staked_state = self.get_staked_state(name, &address, verify_staking)?;
+        if staked_state.node_meta.is_some() {
+            return Err(Error::new(
+                ErrorKind::IllegalInput,
+                "Council nodes can't back other council nodes",
+            ));
+        }
+        if staked_state.delegated_to == council_node {
+            return Err(Error::new(
+                ErrorKind::IllegalInput,
+                "The staking account already backs the council node (synchronizing your wallet may help)",
+            ));
+        }
+        if let Some(council_node) = &council_node {
+            let council_node_state = self.get_staked_state(name, council_node, false)?;
+            match &council_node_state.node_meta {
+                Some(NodeState::CouncilNode(_)) if !council_node_state.is_jailed() => {}
+                _ => {
+                    return Err(Error::new(
+                        ErrorKind::IllegalInput,
+                        "Only council nodes which are not jailed can be backed",
+                    ))
+                }
+            }
+        }
+
+        let transaction = DelegateTx::new(address, council_node, staked_state.nonce, attributes);
+        let tx = Transaction::DelegateTransaction(transaction.clone());
+        let signature = self.sign_staking_op(name, enckey, &address, &tx)?;
+
+        Ok(TxAux::PublicTx(TxPublicAux::DelegateTx(
+            transaction,
+            signature,
+        )))
+    }
+
+    fn get_network_params(&self) -> Result<NetworkParameters> {
+        let bytes = self
+            .client
+            .query("network-params", &[], None, false)?
+            .bytes();
+        NetworkParameters::decode(&mut bytes.as_slice()).chain(|| {
+            (
+                ErrorKind::DeserializationError,
+                "Cannot deserialize network parameters",
+            )
+        })
+    }
+
+    fn get_proposals(&self) -> Result<Vec<Proposal>> {
+        let bytes = self.client.query("governance", &[], None, false)?.bytes();
+        <Vec<Proposal>>::decode(&mut bytes.as_slice()).chain(|| {
+            (
+                ErrorKind::DeserializationError,
+                "Cannot deserialize governance proposals",
+            )
+        })
+    }
+
+    fn get_slash_history(&self, address: &StakedStateAddress) -> Result<Vec<SlashRecord>> {
+        let bytes = self
+            .client
+            .query("slash-history", address.as_ref(), None, false)?
+            .bytes();
+        <Vec<SlashRecord>>::decode(&mut bytes.as_slice())
+            .err_kind(ErrorKind::DeserializationError, || {
+                format!("Cannot deserialize slash history for address: {}", address)
+            })
+    }
+
     fn get_staking(
         &self,
         name: &str,
@@ -1185,4 +1540,102 @@ mod tests {
             _ => unreachable!("`create_node_join_tx()` created invalid transaction"),
         }
     }
+
+    #[test]
+    fn check_node_metadata_update_transaction_not_council_node() {
+        let name = "name";
+        let passphrase = SecUtf8::from("passphrase");
+
+        let storage = MemoryStorage::default();
+        let signer_manager = WalletSignerManager::new(storage.clone(), HwKeyService::default());
+
+        let fee_algorithm = UnitFeeAlgorithm::default();
+
+        let wallet_client = DefaultWalletClient::new_read_only(storage.clone());
+
+        let tendermint_client = MockClient::default();
+        let network_ops_client = DefaultNetworkOpsClient::new(
+            wallet_client,
+            signer_manager,
+            tendermint_client,
+            fee_algorithm,
+            MockTransactionCipher,
+        );
+
+        let (enckey, _) = network_ops_client
+            .get_wallet_client()
+            .new_wallet(name, &passphrase, WalletKind::Basic, None)
+            .unwrap();
+
+        let staking_account_address = network_ops_client
+            .get_wallet_client()
+            .new_staking_address(name, &enckey)
+            .unwrap();
+
+        assert_eq!(
+            ErrorKind::IllegalInput,
+            network_ops_client
+                .create_node_metadata_update_transaction(
+                    name,
+                    &enckey,
+                    staking_account_address,
+                    StakedStateOpAttributes::new(171),
+                    Some("new name".to_owned()),
+                    None,
+                    None,
+                    false,
+                )
+                .unwrap_err()
+                .kind()
+        );
+    }
+
+    #[test]
+    fn check_transfer_stake_transaction_same_address() {
+        let name = "name";
+        let passphrase = SecUtf8::from("passphrase");
+
+        let storage = MemoryStorage::default();
+        let signer_manager = WalletSignerManager::new(storage.clone(), HwKeyService::default());
+
+        let fee_algorithm = UnitFeeAlgorithm::default();
+
+        let wallet_client = DefaultWalletClient::new_read_only(storage.clone());
+
+        let tendermint_client = MockClient::default();
+        let network_ops_client = DefaultNetworkOpsClient::new(
+            wallet_client,
+            signer_manager,
+            tendermint_client,
+            fee_algorithm,
+            MockTransactionCipher,
+        );
+
+        let (enckey, _) = network_ops_client
+            .get_wallet_client()
+            .new_wallet(name, &passphrase, WalletKind::Basic, None)
+            .unwrap();
+
+        let staking_account_address = network_ops_client
+            .get_wallet_client()
+            .new_staking_address(name, &enckey)
+            .unwrap();
+
+        assert_eq!(
+            ErrorKind::InvalidInput,
+            network_ops_client
+                .create_transfer_stake_transaction(
+                    name,
+                    &enckey,
+                    staking_account_address,
+                    staking_account_address,
+                    Coin::new(1).unwrap(),
+                    StakeKind::Bonded,
+                    StakedStateOpAttributes::new(171),
+                    false,
+                )
+                .unwrap_err()
+                .kind()
+        );
+    }
 }
@@ -0,0 +1,175 @@
+//! Verification of staked state query proofs against light client trusted headers
+use parity_scale_codec::Decode;
+
+use chain_core::state::account::{StakedState, StakedStateAddress};
+use chain_storage::jellyfish::SparseMerkleProof;
+use client_common::tendermint::proof::{check_query_height, verify_app_hash_proof_op};
+use client_common::tendermint::types::{AbciQuery, Header};
+use client_common::{Error, ErrorKind, Result, ResultExt};
+
+/// Verifies the result of "account" query with proof
+/// (staked state -> staking trie root -> app hash in the trusted header of the next block),
+/// so that the full node doesn't need to be trusted
+pub fn verify_account_query(
+    query: &AbciQuery,
+    address: &StakedStateAddress,
+    trusted_header: &Header,
+) -> Result<StakedState> {
+    check_query_height(query, trusted_header)?;
+    let account = StakedState::decode(&mut query.value.as_slice()).chain(|| {
+        (
+            ErrorKind::DeserializationError,
+            "Cannot deserialize staked state",
+        )
+    })?;
+    let ops = query
+        .proof
+        .as_ref()
+        .map(|proof| proof.ops.as_slice())
+        .unwrap_or_default();
+    let (staking_op, app_hash_op) = match ops {
+        [staking_op, app_hash_op] if staking_op.field_type == "staking" => {
+            (staking_op, app_hash_op)
+        }
+        _ => {
+            return Err(Error::new(
+                ErrorKind::VerifyError,
+                format!("There is no valid proof for address: {}", address),
+            ))
+        }
+    };
+    let parts = verify_app_hash_proof_op(app_hash_op, trusted_header)?;
+    let proof = SparseMerkleProof::decode(&mut staking_op.data.as_slice()).chain(|| {
+        (
+            ErrorKind::DeserializationError,
+            format!(
+                "Cannot deserialize staked state proof for address: {}",
+                address
+            ),
+        )
+    })?;
+    proof
+        .verify(parts.account_root, address, Some(&account))
+        .chain(|| (ErrorKind::VerifyError, "Verify staked state failed"))?;
+    Ok(account)
+}
+
+#[cfg(test)]
+mod tests {
+    use parity_scale_codec::Encode;
+    use tendermint::merkle::proof::{Proof, ProofOp};
+
+    use super::*;
+    use chain_core::tx::fee::Milli;
+    use chain_core::AppHashParts;
+    use chain_storage::buffer::MemStore;
+    use chain_storage::jellyfish::{get_with_proof, put_stakings};
+    use client_common::tendermint::mock;
+    use client_common::tendermint::types::Height;
+
+    fn address(i: u8) -> StakedStateAddress {
+        StakedStateAddress::BasicRedeem([i; 20].into())
+    }
+
+    /// the "account" query result for the first staked state (with its proof) and the trusted header
+    fn account_query() -> (AbciQuery, Header) {
+        let stakings = (0..4u8)
+            .map(|i| StakedState::default(address(i)))
+            .collect::<Vec<_>>();
+        let mut store = MemStore::new();
+        let account_root = put_stakings(&mut store, 0, stakings.iter()).unwrap();
+        let (account, proof) = get_with_proof(&store, 0, &address(0));
+        let parts = AppHashParts {
+            valid_tx_root: [0; 32],
+            account_root,
+            rewards_pool_hash: [1; 32],
+            network_params_hash: [2; 32],
+            mls_group_hash: [0; 32],
+            base_fee: Milli::integral(1).unwrap(),
+            stake_transfers_hash: [0; 32],
+        };
+        let query = AbciQuery {
+            value: account.unwrap().encode(),
+            height: Height::from(1u64),
+            proof: Some(Proof {
+                ops: vec![
+                    ProofOp {
+                        field_type: "staking".to_owned(),
+                        key: address(0).encode(),
+                        data: proof.encode(),
+                    },
+                    ProofOp {
+                        field_type: "app_hash".to_owned(),
+                        key: parts.app_hash().to_vec(),
+                        data: parts.encode(),
+                    },
+                ],
+            }),
+            ..Default::default()
+        };
+        let mut header = mock::header();
+        header.app_hash = parts.app_hash().to_vec();
+        header.height = Height::from(2u64);
+        (query, header)
+    }
+
+    #[test]
+    fn check_verify_account_query() {
+        let (query, header) = account_query();
+        let account = verify_account_query(&query, &address(0), &header).unwrap();
+        assert_eq!(account, StakedState::default(address(0)));
+
+        // the proof is for another address
+        assert!(verify_account_query(&query, &address(1), &header).is_err());
+
+        // the header isn't the one of the next block
+        let mut next_header = header.clone();
+        next_header.height = Height::from(3u64);
+        assert!(verify_account_query(&query, &address(0), &next_header).is_err());
+    }
+
+    #[test]
+    fn check_verify_tampered_account_query() {
+        let (query, header) = account_query();
+
+        let mut tampered = query.clone();
+        let mut account = StakedState::default(address(0));
+        account.nonce += 1;
+        tampered.value = account.encode();
+        assert_eq!(
+            ErrorKind::VerifyError,
+            verify_account_query(&tampered, &address(0), &header)
+                .unwrap_err()
+                .kind()
+        );
+
+        // the staking trie root is replaced together with the app hash parts
+        let mut tampered = query.clone();
+        let ops = &mut tampered.proof.as_mut().unwrap().ops;
+        let mut parts = AppHashParts::decode(&mut ops[1].data.as_slice()).unwrap();
+        parts.account_root = [3; 32];
+        ops[1].data = parts.encode();
+        assert_eq!(
+            ErrorKind::VerifyError,
+            verify_account_query(&tampered, &address(0), &header)
+                .unwrap_err()
+                .kind()
+        );
+
+        let mut tampered = query;
+        tampered.proof = None;
+        assert!(verify_account_query(&tampered, &address(0), &header).is_err());
+    }
+
+    #[test]
+    fn check_verify_account_query_wrong_app_hash() {
+        let (query, mut header) = account_query();
+        header.app_hash = vec![0; 32];
+        assert_eq!(
+            ErrorKind::VerifyError,
+            verify_account_query(&query, &address(0), &header)
+                .unwrap_err()
+                .kind()
+        );
+    }
+}
@@ -1,5 +1,6 @@
 mod program;
 mod server;
+mod tls;
 
 fn main() {
     crate::program::run_cli();
@@ -2,6 +2,7 @@ use structopt::StructOpt;
 
 use crate::server::Server;
 use std::env;
+use std::path::PathBuf;
 
 #[derive(StructOpt, Debug)]
 #[structopt(
@@ -30,6 +31,43 @@ pub struct Options {
     )]
     pub port: u16,
 
+    #[structopt(
+        name = "ws-port",
+        long,
+        help = "JSON-RPC WebSocket server port (for the wallet event subscriptions)"
+    )]
+    pub ws_port: Option<u16>,
+
+    #[structopt(
+        name = "cors",
+        long,
+        help = "Allowed CORS origin (can be repeated, all the origins are allowed by default)"
+    )]
+    pub cors: Vec<String>,
+
+    #[structopt(
+        name = "tls-cert",
+        long,
+        requires = "tls-key",
+        help = "PEM encoded TLS certificate chain (the servers only accept TLS connections when set)"
+    )]
+    pub tls_cert: Option<PathBuf>,
+
+    #[structopt(
+        name = "tls-key",
+        long,
+        requires = "tls-cert",
+        help = "PEM encoded TLS private key"
+    )]
+    pub tls_key: Option<PathBuf>,
+
+    #[structopt(
+        name = "access-config",
+        long,
+        help = "JSON file with the API tokens, rate limit and audit log path"
+    )]
+    pub access_config: Option<PathBuf>,
+
     #[structopt(name = "chain-id", short, long, help = "Full chain ID")]
     pub chain_id: String,
 
@@ -1,16 +1,91 @@
 use crate::program::Options;
+use crate::tls::{load_tls_config, start_tls_proxy, FORWARDED_FOR_HEADER};
 
+use jsonrpc_http_server::hyper::{header::AUTHORIZATION, Body, Request};
 use jsonrpc_http_server::{AccessControlAllowOrigin, DomainsValidation, ServerBuilder};
-use std::net::SocketAddr;
+use jsonrpc_pubsub::Session;
+use jsonrpc_ws_server::{ws, Origin, RequestContext, ServerBuilder as WsServerBuilder};
+use rustls::ServerConfig;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::path::PathBuf;
+use std::sync::Arc;
 
 use chain_core::init::network::{get_network, get_network_id, init_chain_id};
-use client_common::Result;
+use client_common::{Error, ErrorKind, Result};
 use client_core::wallet::syncer::SyncerOptions;
+use client_rpc_core::access::{AccessConfig, AccessControl, RpcMetadata};
 use client_rpc_core::RpcHandler;
 
+/// Prefix of the WebSocket protocol carrying the API token (`bearer.<token>`),
+/// the browsers can't set the `Authorization` header of the WebSocket requests
+const WS_TOKEN_PROTOCOL_PREFIX: &str = "bearer.";
+
+/// Maximum number of the WebSocket connections
+const WS_MAX_CONNECTIONS: usize = 100;
+
+/// API token of the `bearer.<token>` WebSocket protocol
+fn ws_token_protocol<'a>(protocols: impl IntoIterator<Item = &'a str>) -> Option<&'a str> {
+    protocols
+        .into_iter()
+        .find(|protocol| protocol.starts_with(WS_TOKEN_PROTOCOL_PREFIX))
+}
+
+/// Accepts the `bearer.<token>` protocol in the handshake response (the browsers close
+/// the connection if none of the requested protocols is accepted)
+fn accept_ws_token_protocol(request: &ws::Request) -> Option<ws::Response> {
+    let protocols = request.protocols().ok()?;
+    let protocol = ws_token_protocol(protocols)?;
+    let mut response = ws::Response::from_request(request).ok()?;
+    response.set_protocol(protocol);
+    Some(response)
+}
+
+/// Client address added by the TLS proxy
+fn forwarded_peer(value: Option<&str>) -> Option<IpAddr> {
+    value?.split(',').next()?.trim().parse().ok()
+}
+
+/// Checks the API token of the WebSocket handshake (so that the failed authentication is
+/// counted per client address) and accepts the `bearer.<token>` protocol
+fn ws_handshake(
+    request: &ws::Request,
+    access_control: &AccessControl,
+    behind_proxy: bool,
+) -> Option<ws::Response> {
+    let protocols = request.protocols().unwrap_or_default();
+    let token =
+        ws_token_protocol(protocols).map(|protocol| &protocol[WS_TOKEN_PROTOCOL_PREFIX.len()..]);
+    let peer = if behind_proxy {
+        forwarded_peer(request.client_addr().ok().flatten())
+    } else {
+        None
+    };
+    if let Err(message) = access_control.authenticate(token, peer) {
+        return Some(ws::Response::new(401, "Unauthorized", message.into_bytes()));
+    }
+    accept_ws_token_protocol(request)
+}
+
+/// API token of the `Authorization: Bearer <token>` header
+fn bearer_token(request: &Request<Body>) -> Option<String> {
+    let value = request.headers().get(AUTHORIZATION)?.to_str().ok()?;
+    let mut parts = value.splitn(2, ' ');
+    match (parts.next(), parts.next()) {
+        (Some(scheme), Some(token)) if scheme.eq_ignore_ascii_case("bearer") => {
+            Some(token.trim().to_owned())
+        }
+        _ => None,
+    }
+}
+
 pub(crate) struct Server {
     host: String,
     port: u16,
+    ws_port: Option<u16>,
+    cors: Vec<String>,
+    tls_cert: Option<PathBuf>,
+    tls_key: Option<PathBuf>,
+    access_config: Option<PathBuf>,
     network_id: u8,
     storage_dir: String,
     websocket_url: String,
@@ -27,6 +102,11 @@ impl Server {
         Ok(Server {
             host: options.host,
             port: options.port,
+            ws_port: options.ws_port,
+            cors: options.cors,
+            tls_cert: options.tls_cert,
+            tls_key: options.tls_key,
+            access_config: options.access_config,
             network_id,
             storage_dir: options.storage_dir,
             websocket_url: options.websocket_url,
@@ -39,29 +119,134 @@ impl Server {
         })
     }
 
-    fn create_rpc_handler(&self) -> Result<RpcHandler> {
+    fn access_control(&self) -> Result<AccessControl> {
+        match self.access_config {
+            Some(ref path) => AccessControl::new(AccessConfig::load(path)?),
+            None => {
+                log::warn!("no access config, all the calls are allowed without API tokens");
+                Ok(AccessControl::default())
+            }
+        }
+    }
+
+    fn create_rpc_handler(&self, access_control: AccessControl) -> Result<RpcHandler> {
         if cfg!(feature = "mock-enclave") {
             log::warn!("{}", "WARNING: Using mock (non-enclave) infrastructure");
         }
-        RpcHandler::new(
+        RpcHandler::new_with_access_control(
             &self.storage_dir,
             &self.websocket_url,
             self.network_id,
             self.sync_options.clone(),
             None,
+            access_control,
         )
     }
 
+    fn tls_config(&self) -> Result<Option<Arc<ServerConfig>>> {
+        match (&self.tls_cert, &self.tls_key) {
+            (Some(cert), Some(key)) => load_tls_config(cert, key).map(Some),
+            _ => Ok(None),
+        }
+    }
+
+    /// Address the server listens on: with TLS, the plain text server only listens on
+    /// the loopback interface and the TLS proxy listens on the public address
+    fn bind_address(&self, port: u16, tls: bool) -> SocketAddr {
+        if tls {
+            SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 0)
+        } else {
+            SocketAddr::new(self.host.parse().unwrap(), port)
+        }
+    }
+
+    fn cors(&self) -> Vec<AccessControlAllowOrigin> {
+        if self.cors.is_empty() {
+            vec![AccessControlAllowOrigin::Any]
+        } else {
+            self.cors
+                .iter()
+                .map(|origin| AccessControlAllowOrigin::from(origin.as_str()))
+                .collect()
+        }
+    }
+
     pub(crate) fn start(&mut self) -> Result<()> {
-        let handler = self.create_rpc_handler()?;
-        let server = ServerBuilder::new(handler.io)
-            // TODO: Either make CORS configurable or make it more strict
-            .cors(DomainsValidation::AllowOnly(vec![
-                AccessControlAllowOrigin::Any,
-            ]))
-            .start_http(&SocketAddr::new(self.host.parse().unwrap(), self.port))
+        let access_control = self.access_control()?;
+        let tls_config = self.tls_config()?;
+        if tls_config.is_some() && !access_control.requires_token() {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "TLS requires the API tokens in the access config (the plain text servers behind the TLS proxy are reachable by the local users)",
+            ));
+        }
+        let behind_proxy = tls_config.is_some();
+        let handler = self.create_rpc_handler(access_control.clone())?;
+        // the subscriptions are only available over WebSocket (with the session metadata)
+        let ws_server = self.ws_port.map(|ws_port| {
+            let mut builder = WsServerBuilder::with_meta_extractor(
+                handler.io.clone(),
+                |context: &RequestContext| RpcMetadata {
+                    session: Some(Arc::new(Session::new(context.sender()))),
+                    token: ws_token_protocol(context.protocols.iter().map(String::as_str))
+                        .map(|protocol| protocol[WS_TOKEN_PROTOCOL_PREFIX.len()..].to_owned()),
+                    // the client address is checked in the handshake
+                    peer: None,
+                },
+            )
+            .request_middleware(move |request: &ws::Request| {
+                ws_handshake(request, &access_control, behind_proxy)
+            })
+            .max_connections(WS_MAX_CONNECTIONS);
+            if !self.cors.is_empty() && !self.cors.iter().any(|origin| origin == "*") {
+                builder = builder.allowed_origins(DomainsValidation::AllowOnly(
+                    self.cors
+                        .iter()
+                        .map(|origin| Origin::from(origin.as_str()))
+                        .collect(),
+                ));
+            }
+            let server = builder
+                .start(&self.bind_address(ws_port, tls_config.is_some()))
+                .expect("Unable to start JSON-RPC WebSocket server");
+            (ws_port, server)
+        });
+        let server =
+            ServerBuilder::with_meta_extractor(handler.io, move |request: &Request<Body>| {
+                RpcMetadata {
+                    session: None,
+                    token: bearer_token(request),
+                    peer: if behind_proxy {
+                        forwarded_peer(
+                            request
+                                .headers()
+                                .get(FORWARDED_FOR_HEADER)
+                                .and_then(|value| value.to_str().ok()),
+                        )
+                    } else {
+                        None
+                    },
+                }
+            })
+            .cors(DomainsValidation::AllowOnly(self.cors()))
+            // the TLS proxy only adds the client address to the first request of a connection
+            .keep_alive(!behind_proxy)
+            .start_http(&self.bind_address(self.port, tls_config.is_some()))
             .expect("Unable to start JSON-RPC server");
 
+        if let Some(config) = tls_config {
+            let host = self.host.parse().unwrap();
+            start_tls_proxy(
+                config.clone(),
+                SocketAddr::new(host, self.port),
+                *server.address(),
+            )?;
+            if let Some((ws_port, ref ws_server)) = ws_server {
+                start_tls_proxy(config, SocketAddr::new(host, ws_port), *ws_server.addr())?;
+            }
+            log::info!("TLS enabled");
+        }
+
         log::info!("server wait");
         server.wait();
 
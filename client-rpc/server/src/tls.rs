@@ -0,0 +1,273 @@
+//! TLS termination of the JSON-RPC servers: the HTTP and WebSocket servers listen on the
+//! loopback interface and the TLS connections are forwarded to them in plain text.
+//! The client address is added to the first request of each connection in the
+//! `X-Forwarded-For` header (the HTTP server closes the connections after each response,
+//! so that every request carries it).
+//!
+//! NOTE: the plain text servers are reachable by every local user of the host (on random
+//! loopback ports), so TLS is only enabled together with the API tokens of the access config.
+use std::fs::File;
+use std::io::{self, BufReader, Read, Write};
+use std::net::{Shutdown, SocketAddr, TcpListener, TcpStream};
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use rustls::internal::pemfile::{certs, pkcs8_private_keys, rsa_private_keys};
+use rustls::{NoClientAuth, ServerConfig, ServerSession, Session};
+
+use client_common::{Error, ErrorKind, Result, ResultExt};
+
+const BUFFER_SIZE: usize = 16 * 1024;
+/// Maximum number of the proxied connections (per server), the other ones are closed on accept
+const MAX_CONNECTIONS: usize = 100;
+/// Time to complete the TLS handshake
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+/// Connections without any data from the client for this long are closed
+const IDLE_TIMEOUT: Duration = Duration::from_secs(600);
+/// Time to send the data to the client
+const WRITE_TIMEOUT: Duration = Duration::from_secs(30);
+/// Maximum size of the request line the client address header is inserted after
+const MAX_REQUEST_LINE: usize = 8 * 1024;
+
+/// Header carrying the client address to the plain text servers
+pub(crate) const FORWARDED_FOR_HEADER: &str = "X-Forwarded-For";
+
+fn open(path: &Path) -> Result<BufReader<File>> {
+    File::open(path).map(BufReader::new).chain(|| {
+        (
+            ErrorKind::IoError,
+            format!("Unable to open {}", path.display()),
+        )
+    })
+}
+
+/// Loads the PEM encoded certificate chain and private key (PKCS#8 or RSA)
+pub(crate) fn load_tls_config(cert_file: &Path, key_file: &Path) -> Result<Arc<ServerConfig>> {
+    let cert_chain = certs(&mut open(cert_file)?).map_err(|_| {
+        Error::new(
+            ErrorKind::InvalidInput,
+            format!("Unable to parse TLS certificates: {}", cert_file.display()),
+        )
+    })?;
+    let invalid_key = |_| {
+        Error::new(
+            ErrorKind::InvalidInput,
+            format!("Unable to parse TLS private key: {}", key_file.display()),
+        )
+    };
+    let mut keys = pkcs8_private_keys(&mut open(key_file)?).map_err(invalid_key)?;
+    if keys.is_empty() {
+        keys = rsa_private_keys(&mut open(key_file)?).map_err(invalid_key)?;
+    }
+    let key = keys.into_iter().next().chain(|| {
+        (
+            ErrorKind::InvalidInput,
+            format!("No TLS private key found in {}", key_file.display()),
+        )
+    })?;
+
+    let mut config = ServerConfig::new(NoClientAuth::new());
+    config.set_single_cert(cert_chain, key).chain(|| {
+        (
+            ErrorKind::InvalidInput,
+            "Invalid TLS certificate or private key",
+        )
+    })?;
+    Ok(Arc::new(config))
+}
+
+/// Accepts the TLS connections on `address` and forwards them to the plain text server on `target`
+pub(crate) fn start_tls_proxy(
+    config: Arc<ServerConfig>,
+    address: SocketAddr,
+    target: SocketAddr,
+) -> Result<()> {
+    let listener = TcpListener::bind(address).chain(|| {
+        (
+            ErrorKind::IoError,
+            format!("Unable to listen on {}", address),
+        )
+    })?;
+    let connections = Arc::new(AtomicUsize::new(0));
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let result = stream.and_then(|client| {
+                let slot = match ConnectionSlot::acquire(&connections) {
+                    Some(slot) => slot,
+                    None => {
+                        log::warn!("too many TLS connections, closing the new one");
+                        return client.shutdown(Shutdown::Both);
+                    }
+                };
+                client.set_read_timeout(Some(HANDSHAKE_TIMEOUT))?;
+                client.set_write_timeout(Some(WRITE_TIMEOUT))?;
+                let peer = client.peer_addr()?;
+                let backend = TcpStream::connect(target)?;
+                proxy_connection(ServerSession::new(&config), client, backend, peer, slot)
+            });
+            if let Err(err) = result {
+                log::warn!("unable to accept TLS connection: {}", err);
+            }
+        }
+    });
+    Ok(())
+}
+
+/// Counted proxied connection, released when the connection is dropped
+struct ConnectionSlot(Arc<AtomicUsize>);
+
+impl ConnectionSlot {
+    fn acquire(connections: &Arc<AtomicUsize>) -> Option<Self> {
+        if connections.fetch_add(1, Ordering::SeqCst) >= MAX_CONNECTIONS {
+            connections.fetch_sub(1, Ordering::SeqCst);
+            None
+        } else {
+            Some(ConnectionSlot(connections.clone()))
+        }
+    }
+}
+
+impl Drop for ConnectionSlot {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Inserts the header after the request line (before the headers sent by the client,
+/// so that it's the one read by the servers), returns `None` if the request line isn't complete
+fn insert_header(request: &[u8], header: &[u8]) -> Option<Vec<u8>> {
+    let end = request.windows(2).position(|bytes| bytes == b"\r\n")? + 2;
+    let mut result = Vec::with_capacity(request.len() + header.len());
+    result.extend_from_slice(&request[..end]);
+    result.extend_from_slice(header);
+    result.extend_from_slice(&request[end..]);
+    Some(result)
+}
+
+/// Forwards the connection in both directions (one thread each, sharing the TLS session)
+fn proxy_connection(
+    session: ServerSession,
+    client: TcpStream,
+    backend: TcpStream,
+    peer: SocketAddr,
+    slot: ConnectionSlot,
+) -> io::Result<()> {
+    let connection = Arc::new(Connection {
+        session: Mutex::new(session),
+        client,
+        backend,
+        _slot: slot,
+    });
+    let requests = connection.clone();
+    thread::spawn(move || {
+        if let Err(err) = requests.forward_requests(peer) {
+            log::debug!("TLS connection closed: {}", err);
+        }
+        requests.close();
+    });
+    thread::spawn(move || {
+        if let Err(err) = connection.forward_responses() {
+            log::debug!("TLS connection closed: {}", err);
+        }
+        connection.close();
+    });
+    Ok(())
+}
+
+struct Connection {
+    session: Mutex<ServerSession>,
+    client: TcpStream,
+    backend: TcpStream,
+    _slot: ConnectionSlot,
+}
+
+impl Connection {
+    /// Decrypts the data received from the client and sends it to the server
+    /// with the client address header in the first request
+    /// (the timeouts of the client reads close the connection)
+    fn forward_requests(&self, peer: SocketAddr) -> io::Result<()> {
+        let mut buffer = [0; BUFFER_SIZE];
+        let mut handshaking = true;
+        let header = format!("{}: {}\r\n", FORWARDED_FOR_HEADER, peer.ip());
+        // the start of the first request until its request line is complete
+        let mut first_request = Some(Vec::new());
+        loop {
+            let size = (&self.client).read(&mut buffer)?;
+            if size == 0 {
+                return Ok(());
+            }
+            let mut session = self.session.lock().expect("get TLS session lock");
+            let mut received = &buffer[..size];
+            while !received.is_empty() {
+                session.read_tls(&mut received)?;
+                if let Err(err) = session.process_new_packets() {
+                    // sends the alert to the client
+                    let _ = self.write_tls(&mut session);
+                    return Err(io::Error::new(io::ErrorKind::InvalidData, err));
+                }
+            }
+            let mut plain = Vec::new();
+            let closed = match session.read_to_end(&mut plain) {
+                Ok(_) => false,
+                Err(ref err) if err.kind() == io::ErrorKind::ConnectionAborted => true,
+                Err(err) => return Err(err),
+            };
+            self.write_tls(&mut session)?;
+            if handshaking && !session.is_handshaking() {
+                handshaking = false;
+                self.client.set_read_timeout(Some(IDLE_TIMEOUT))?;
+            }
+            // the server may be waiting for the lock to send a response
+            drop(session);
+            if let Some(mut request) = first_request.take() {
+                request.extend_from_slice(&plain);
+                plain = match insert_header(&request, header.as_bytes()) {
+                    Some(plain) => plain,
+                    None if request.len() < MAX_REQUEST_LINE && !closed => {
+                        first_request = Some(request);
+                        continue;
+                    }
+                    None => {
+                        return Err(io::Error::new(
+                            io::ErrorKind::InvalidData,
+                            "invalid request line",
+                        ))
+                    }
+                };
+            }
+            (&self.backend).write_all(&plain)?;
+            if closed {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Encrypts the data received from the server and sends it to the client
+    fn forward_responses(&self) -> io::Result<()> {
+        let mut buffer = [0; BUFFER_SIZE];
+        loop {
+            let size = (&self.backend).read(&mut buffer)?;
+            if size == 0 {
+                return Ok(());
+            }
+            let mut session = self.session.lock().expect("get TLS session lock");
+            session.write_all(&buffer[..size])?;
+            self.write_tls(&mut session)?;
+        }
+    }
+
+    fn write_tls(&self, session: &mut ServerSession) -> io::Result<()> {
+        while session.wants_write() {
+            session.write_tls(&mut &self.client)?;
+        }
+        Ok(())
+    }
+
+    fn close(&self) {
+        let _ = self.client.shutdown(Shutdown::Both);
+        let _ = self.backend.shutdown(Shutdown::Both);
+    }
+}
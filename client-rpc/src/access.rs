@@ -0,0 +1,774 @@
+//! Access control of the JSON-RPC calls: API tokens scoped to wallets and methods,
+//! rate limiting per token, rate limiting of the failed authentication per client address
+//! and the audit log of the state-changing calls.
+//!
+//! Without an access config all the calls are allowed (the enckeys passed in the calls
+//! are the only authentication, which is only suitable for localhost).
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use jsonrpc_core::futures::future::{self, Either};
+use jsonrpc_core::futures::Future;
+use jsonrpc_core::{
+    Call, Error, ErrorCode, FutureOutput, FutureResponse, Metadata, MethodCall, Middleware, Output,
+    Params, Value,
+};
+use jsonrpc_pubsub::{PubSubMetadata, Session};
+use serde::{Deserialize, Serialize};
+
+use client_common::{ErrorKind, Result as CommonResult, ResultExt};
+
+/// Methods which don't change the state of the wallets or the chain
+/// (the other ones are written to the audit log)
+pub const READ_ONLY_METHODS: [&str; 25] = [
+    "genesis",
+    "status",
+    "multiSig_listAddressPublicKeys",
+    "staking_state",
+    "staking_networkParams",
+    "staking_proposals",
+    "staking_slashHistory",
+    "sync_progress",
+    "sync_subscribeProgress",
+    "sync_unsubscribeProgress",
+    "transaction_createRaw",
+    "wallet_balance",
+    "wallet_estimateFee",
+    "wallet_getViewKey",
+    "wallet_list",
+    "wallet_listPublicKeys",
+    "wallet_listStakingAddresses",
+    "wallet_listTransferAddresses",
+    "wallet_listUTxO",
+    "wallet_pendingTransactions",
+    "wallet_transactions",
+    "wallet_subscribeTransactions",
+    "wallet_unsubscribeTransactions",
+    "wallet_subscribeBalance",
+    "wallet_unsubscribeBalance",
+];
+
+/// Method pattern matching all the read-only methods
+const READ_ONLY_PATTERN: &str = "@read";
+
+/// Error code of the calls rejected by the access control
+const UNAUTHORIZED_CODE: i64 = -32001;
+/// Error code of the calls rejected by the rate limit
+const RATE_LIMITED_CODE: i64 = -32005;
+
+/// Maximum number of the failed authentications per client address within `AUTH_FAILURE_PERIOD`
+/// (the following calls are rejected until the period ends)
+const MAX_AUTH_FAILURES: u32 = 10;
+/// Period of the failed authentication limit
+const AUTH_FAILURE_PERIOD: Duration = Duration::from_secs(60);
+/// Number of the tracked client addresses above which the expired ones are removed
+const MAX_TRACKED_PEERS: usize = 1024;
+
+/// Metadata of the RPC requests
+#[derive(Clone, Default)]
+pub struct RpcMetadata {
+    /// WebSocket session (the subscriptions are only available with a session)
+    pub session: Option<Arc<Session>>,
+    /// API token of the request
+    pub token: Option<String>,
+    /// address of the client (`None` if the server can't tell it, the failed authentications
+    /// of all such clients are then counted together)
+    pub peer: Option<IpAddr>,
+}
+
+impl Metadata for RpcMetadata {}
+
+impl PubSubMetadata for RpcMetadata {
+    fn session(&self) -> Option<Arc<Session>> {
+        self.session.clone()
+    }
+}
+
+/// API token scoped to wallets and methods
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiToken {
+    /// name of the token (written to the audit log instead of the token)
+    pub name: String,
+    /// secret token sent in the `Authorization: Bearer <token>` header
+    /// (or as the `bearer.<token>` WebSocket protocol)
+    pub token: String,
+    /// names of the wallets the calls can access (`*` for all the wallets, which is also
+    /// needed by the calls operating on a wallet without naming it)
+    #[serde(default)]
+    pub wallets: Vec<String>,
+    /// allowed methods: method names, prefixes ending with `*` (e.g. `wallet_*`)
+    /// or `@read` for all the read-only methods
+    #[serde(default)]
+    pub methods: Vec<String>,
+}
+
+impl ApiToken {
+    fn allows_method(&self, method: &str) -> bool {
+        self.methods.iter().any(|pattern| {
+            if pattern == READ_ONLY_PATTERN {
+                READ_ONLY_METHODS.contains(&method)
+            } else if pattern.ends_with('*') {
+                method.starts_with(&pattern[..pattern.len() - 1])
+            } else {
+                pattern == method
+            }
+        })
+    }
+
+    fn allows_wallet(&self, wallet: &str) -> bool {
+        self.wallets
+            .iter()
+            .any(|name| name == "*" || name == wallet)
+    }
+
+    fn allows_all_wallets(&self) -> bool {
+        self.wallets.iter().any(|name| name == "*")
+    }
+}
+
+/// Maximum number of calls per token within the period
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct RateLimit {
+    /// number of calls
+    pub calls: u32,
+    /// period in seconds
+    pub period: u64,
+}
+
+/// Access config of the JSON-RPC server (loaded from a JSON file)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AccessConfig {
+    /// API tokens (the calls without a valid token are rejected if it's not empty)
+    #[serde(default)]
+    pub tokens: Vec<ApiToken>,
+    /// rate limit of the calls (per token)
+    #[serde(default)]
+    pub rate_limit: Option<RateLimit>,
+    /// file the state-changing calls are appended to
+    #[serde(default)]
+    pub audit_log: Option<PathBuf>,
+}
+
+impl AccessConfig {
+    /// Loads the access config from the JSON file
+    pub fn load(path: &Path) -> CommonResult<Self> {
+        let file = File::open(path).chain(|| {
+            (
+                ErrorKind::IoError,
+                format!("Unable to open access config file: {}", path.display()),
+            )
+        })?;
+        serde_json::from_reader(file).chain(|| {
+            (
+                ErrorKind::DeserializationError,
+                "Unable to deserialize access config",
+            )
+        })
+    }
+}
+
+/// Entry of the audit log (the params aren't written, they contain enckeys and passphrases)
+#[derive(Debug, Serialize)]
+struct AuditEntry<'a> {
+    time: u64,
+    token: Option<&'a str>,
+    method: &'a str,
+    wallets: &'a [String],
+    result: &'a str,
+}
+
+/// How the params of a method name the wallets it operates on
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum WalletParam {
+    /// the method doesn't operate on a wallet
+    NoWallet,
+    /// the first param is a wallet request (`{"name": .., ..}` or `[name, ..]`) or a wallet name
+    First,
+    /// the first param is a list of wallet requests
+    FirstList,
+    /// the method operates on a wallet without naming it (e.g. the multi-sig sessions)
+    Unnamed,
+}
+
+/// Wallet param of the method (`None` for the unknown methods, which are rejected
+/// when the API tokens are configured)
+fn wallet_param(method: &str) -> Option<WalletParam> {
+    use WalletParam::*;
+    let param = match method {
+        "genesis"
+        | "status"
+        | "staking_networkParams"
+        | "staking_proposals"
+        | "staking_slashHistory"
+        | "transaction_createRaw"
+        | "wallet_estimateFee"
+        | "wallet_list"
+        | "wallet_unsubscribeTransactions"
+        | "wallet_unsubscribeBalance"
+        | "sync_unsubscribeProgress" => NoWallet,
+        "multiSig_newAddressPublicKey"
+        | "multiSig_listAddressPublicKeys"
+        | "multiSig_createAddress"
+        | "multiSig_newSession"
+        | "multiSig_broadcastWithSignature"
+        | "staking_depositStake"
+        | "staking_depositAmountStake"
+        | "staking_state"
+        | "staking_unbondStake"
+        | "staking_withdrawAllUnbondedStake"
+        | "staking_unjail"
+        | "staking_validatorNodeJoin"
+        | "staking_validatorNodeMetadataUpdate"
+        | "staking_transferStake"
+        | "staking_proposeParamChange"
+        | "staking_voteParamChange"
+        | "staking_delegate"
+        | "sync"
+        | "sync_progress"
+        | "sync_stop"
+        | "sync_subscribeProgress"
+        | "wallet_balance"
+        | "wallet_create"
+        | "wallet_restore"
+        | "wallet_restoreBasic"
+        | "wallet_delete"
+        | "wallet_createStakingAddress"
+        | "wallet_createStakingAddressBatch"
+        | "wallet_createWatchStakingAddress"
+        | "wallet_createTransferAddress"
+        | "wallet_createTransferAddressBatch"
+        | "wallet_createWatchTransferAddress"
+        | "wallet_getViewKey"
+        | "wallet_listPublicKeys"
+        | "wallet_listStakingAddresses"
+        | "wallet_listTransferAddresses"
+        | "wallet_listUTxO"
+        | "wallet_sendToAddress"
+        | "wallet_sendToAddresses"
+        | "wallet_buildRawTransferTx"
+        | "wallet_broadcastSignedTransferTx"
+        | "wallet_transactions"
+        | "wallet_pendingTransactions"
+        | "wallet_rebroadcastTransaction"
+        | "wallet_abandonTransaction"
+        | "wallet_exportTransaction"
+        | "wallet_importTransaction"
+        | "wallet_getEncKey"
+        | "wallet_export"
+        | "wallet_import"
+        | "wallet_restoreBackup"
+        | "wallet_subscribeTransactions"
+        | "wallet_subscribeBalance" => First,
+        "wallet_backup" => FirstList,
+        "multiSig_nonceCommitment"
+        | "multiSig_addNonceCommitment"
+        | "multiSig_nonce"
+        | "multiSig_addNonce"
+        | "multiSig_partialSign"
+        | "multiSig_addPartialSignature"
+        | "multiSig_signature" => Unnamed,
+        _ => return None,
+    };
+    Some(param)
+}
+
+/// Wallets a call operates on
+#[derive(Debug, Clone, PartialEq)]
+enum CallWallets {
+    /// the wallets named in the params (empty if the call doesn't operate on a wallet)
+    Named(Vec<String>),
+    /// the call operates on a wallet without naming it,
+    /// only allowed with the tokens scoped to all the wallets (`*`)
+    Unnamed,
+}
+
+impl CallWallets {
+    fn names(&self) -> &[String] {
+        match self {
+            CallWallets::Named(names) => names,
+            CallWallets::Unnamed => &[],
+        }
+    }
+}
+
+/// Resolves the wallets the call operates on by the wallet param of the method
+/// (`None` if the method is unknown or the wallet names are missing in the params)
+fn call_wallets(method: &str, params: &Params) -> Option<CallWallets> {
+    let first = match params {
+        Params::Array(params) => params.first(),
+        _ => None,
+    };
+    let name = |request: &Value| match request {
+        Value::Object(request) => request
+            .get("name")
+            .and_then(Value::as_str)
+            .map(String::from),
+        Value::Array(request) => request.first().and_then(Value::as_str).map(String::from),
+        Value::String(name) => Some(name.clone()),
+        _ => None,
+    };
+    match wallet_param(method)? {
+        WalletParam::NoWallet => Some(CallWallets::Named(Vec::new())),
+        WalletParam::First => first
+            .and_then(name)
+            .map(|name| CallWallets::Named(vec![name])),
+        WalletParam::FirstList => match first {
+            Some(Value::Array(requests)) => requests
+                .iter()
+                .map(name)
+                .collect::<Option<Vec<_>>>()
+                .map(CallWallets::Named),
+            _ => None,
+        },
+        WalletParam::Unnamed => Some(CallWallets::Unnamed),
+    }
+}
+
+/// Compares the tokens in constant time
+fn token_eq(a: &str, b: &str) -> bool {
+    a.len() == b.len()
+        && a.bytes()
+            .zip(b.bytes())
+            .fold(0, |acc, (x, y)| acc | (x ^ y))
+            == 0
+}
+
+fn reject(code: i64, message: &str) -> Error {
+    Error {
+        code: ErrorCode::ServerError(code),
+        message: message.to_owned(),
+        data: None,
+    }
+}
+
+struct AccessState {
+    config: AccessConfig,
+    /// start of the rate limit period and the number of the calls in it (per token)
+    calls: Mutex<HashMap<String, (Instant, u32)>>,
+    /// start of the failure period and the number of the failed authentications in it (per client address)
+    auth_failures: Mutex<HashMap<Option<IpAddr>, (Instant, u32)>>,
+    audit_log: Option<Mutex<File>>,
+}
+
+/// Access control middleware of the JSON-RPC calls
+#[derive(Clone, Default)]
+pub struct AccessControl {
+    state: Option<Arc<AccessState>>,
+}
+
+impl AccessControl {
+    /// Creates the access control with the config (opens the audit log)
+    pub fn new(config: AccessConfig) -> CommonResult<Self> {
+        let audit_log = match config.audit_log {
+            Some(ref path) => Some(Mutex::new(
+                OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(path)
+                    .chain(|| (ErrorKind::IoError, "Unable to open audit log"))?,
+            )),
+            None => None,
+        };
+        Ok(AccessControl {
+            state: Some(Arc::new(AccessState {
+                config,
+                calls: Mutex::new(HashMap::new()),
+                auth_failures: Mutex::new(HashMap::new()),
+                audit_log,
+            })),
+        })
+    }
+
+    /// if the calls need an API token
+    pub fn requires_token(&self) -> bool {
+        self.state
+            .as_ref()
+            .map_or(false, |state| !state.config.tokens.is_empty())
+    }
+
+    /// Checks the API token of a client (e.g. in the WebSocket handshake), so that
+    /// the failed authentication is counted before any call
+    pub fn authenticate(&self, token: Option<&str>, peer: Option<IpAddr>) -> Result<(), String> {
+        match self.state {
+            Some(ref state) if !state.config.tokens.is_empty() => {
+                Self::find_token(state, token, peer)
+                    .map(|_| ())
+                    .map_err(|error| error.message)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Finds the API token, the failed authentications are limited per client address
+    fn find_token<'a>(
+        state: &'a AccessState,
+        secret: Option<&str>,
+        peer: Option<IpAddr>,
+    ) -> Result<&'a ApiToken, Error> {
+        let now = Instant::now();
+        let mut failures = state.auth_failures.lock().expect("get auth failures lock");
+        if let Some((start, count)) = failures.get(&peer) {
+            if now.duration_since(*start) < AUTH_FAILURE_PERIOD && *count >= MAX_AUTH_FAILURES {
+                return Err(reject(
+                    RATE_LIMITED_CODE,
+                    "too many failed authentication attempts",
+                ));
+            }
+        }
+        let token = secret.and_then(|secret| {
+            state
+                .config
+                .tokens
+                .iter()
+                .find(|token| token_eq(&token.token, secret))
+        });
+        match token {
+            Some(token) => Ok(token),
+            None => {
+                if failures.len() >= MAX_TRACKED_PEERS {
+                    failures
+                        .retain(|_, (start, _)| now.duration_since(*start) < AUTH_FAILURE_PERIOD);
+                }
+                let (start, count) = failures.entry(peer).or_insert((now, 0));
+                if now.duration_since(*start) >= AUTH_FAILURE_PERIOD {
+                    *start = now;
+                    *count = 0;
+                }
+                *count += 1;
+                let message = if secret.is_some() {
+                    "invalid API token"
+                } else {
+                    "missing API token"
+                };
+                Err(reject(UNAUTHORIZED_CODE, message))
+            }
+        }
+    }
+
+    /// Checks the token, its scope and the rate limit, returns the name of the token
+    fn check(
+        state: &AccessState,
+        call: &MethodCall,
+        wallets: Option<&CallWallets>,
+        meta: &RpcMetadata,
+    ) -> Result<Option<String>, Error> {
+        let token = if state.config.tokens.is_empty() {
+            None
+        } else {
+            let token =
+                Self::find_token(state, meta.token.as_ref().map(String::as_str), meta.peer)?;
+            if !token.allows_method(&call.method) {
+                return Err(reject(
+                    UNAUTHORIZED_CODE,
+                    &format!("API token isn't allowed to call {}", call.method),
+                ));
+            }
+            match wallets {
+                Some(CallWallets::Named(names)) => {
+                    if let Some(wallet) = names.iter().find(|wallet| !token.allows_wallet(wallet)) {
+                        return Err(reject(
+                            UNAUTHORIZED_CODE,
+                            &format!("API token isn't allowed to access wallet {}", wallet),
+                        ));
+                    }
+                }
+                Some(CallWallets::Unnamed) => {
+                    if !token.allows_all_wallets() {
+                        return Err(reject(
+                            UNAUTHORIZED_CODE,
+                            &format!(
+                                "API token isn't allowed to call {} (it needs access to all the wallets)",
+                                call.method
+                            ),
+                        ));
+                    }
+                }
+                None => {
+                    return Err(reject(
+                        UNAUTHORIZED_CODE,
+                        &format!("unable to resolve the wallets of {}", call.method),
+                    ));
+                }
+            }
+            Some(token.name.clone())
+        };
+
+        if let Some(limit) = state.config.rate_limit {
+            let key = token.clone().unwrap_or_default();
+            let now = Instant::now();
+            let mut calls = state.calls.lock().expect("get rate limit lock");
+            let (start, count) = calls.entry(key).or_insert((now, 0));
+            if now.duration_since(*start) >= Duration::from_secs(limit.period) {
+                *start = now;
+                *count = 0;
+            }
+            if *count >= limit.calls {
+                return Err(reject(RATE_LIMITED_CODE, "rate limit exceeded"));
+            }
+            *count += 1;
+        }
+        Ok(token)
+    }
+
+    fn audit(
+        state: &AccessState,
+        token: Option<&str>,
+        method: &str,
+        wallets: &[String],
+        result: &str,
+    ) {
+        if let Some(ref audit_log) = state.audit_log {
+            let entry = AuditEntry {
+                time: SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map(|duration| duration.as_secs())
+                    .unwrap_or_default(),
+                token,
+                method,
+                wallets,
+                result,
+            };
+            let line = serde_json::to_string(&entry).expect("serialize audit entry");
+            let mut file = audit_log.lock().expect("get audit log lock");
+            if let Err(err) = writeln!(file, "{}", line) {
+                log::error!("unable to write audit log: {}", err);
+            }
+        }
+    }
+}
+
+impl Middleware<RpcMetadata> for AccessControl {
+    type Future = FutureResponse;
+    type CallFuture = FutureOutput;
+
+    fn on_call<F, X>(&self, call: Call, meta: RpcMetadata, next: F) -> Either<Self::CallFuture, X>
+    where
+        F: Fn(Call, RpcMetadata) -> X + Send + Sync,
+        X: Future<Item = Option<Output>, Error = ()> + Send + 'static,
+    {
+        let (state, method_call) = match (&self.state, &call) {
+            (Some(state), Call::MethodCall(method_call)) => (state.clone(), method_call.clone()),
+            _ => return Either::B(next(call, meta)),
+        };
+        let call_wallets = call_wallets(&method_call.method, &method_call.params);
+        let wallets = call_wallets
+            .as_ref()
+            .map(|wallets| wallets.names().to_vec())
+            .unwrap_or_default();
+        let token = match Self::check(&state, &method_call, call_wallets.as_ref(), &meta) {
+            Ok(token) => token,
+            Err(error) => {
+                log::warn!("rejected call {}: {}", method_call.method, error.message);
+                Self::audit(&state, None, &method_call.method, &wallets, &error.message);
+                return Either::A(Box::new(future::ok(Some(Output::from(
+                    Err(error),
+                    method_call.id,
+                    method_call.jsonrpc,
+                )))));
+            }
+        };
+        if READ_ONLY_METHODS.contains(&method_call.method.as_str()) {
+            return Either::B(next(call, meta));
+        }
+        Either::A(Box::new(next(call, meta).map(move |output| {
+            let result = match output {
+                Some(Output::Failure(ref failure)) => failure.error.message.as_str(),
+                _ => "ok",
+            };
+            Self::audit(
+                &state,
+                token.as_ref().map(String::as_str),
+                &method_call.method,
+                &wallets,
+                result,
+            );
+            output
+        })))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use jsonrpc_core::{Id, Version};
+
+    fn method_call(method: &str, params: Value) -> MethodCall {
+        MethodCall {
+            jsonrpc: Some(Version::V2),
+            method: method.to_owned(),
+            params: serde_json::from_value(params).unwrap(),
+            id: Id::Num(1),
+        }
+    }
+
+    fn meta(token: &str) -> RpcMetadata {
+        RpcMetadata {
+            session: None,
+            token: Some(token.to_owned()),
+            peer: None,
+        }
+    }
+
+    fn named(names: &[&str]) -> Option<CallWallets> {
+        Some(CallWallets::Named(
+            names.iter().map(|name| (*name).to_owned()).collect(),
+        ))
+    }
+
+    #[test]
+    fn check_call_wallets() {
+        let wallets =
+            |method, params| call_wallets(method, &serde_json::from_value(params).unwrap());
+        assert_eq!(
+            named(&["a"]),
+            wallets(
+                "wallet_sendToAddress",
+                serde_json::json!([{"name": "a", "enckey": "00"}, "addr", 1, []])
+            )
+        );
+        assert_eq!(
+            named(&["b"]),
+            wallets("wallet_balance", serde_json::json!([["b", "00"]]))
+        );
+        assert_eq!(
+            named(&["c"]),
+            wallets("staking_state", serde_json::json!(["c", "0x00"]))
+        );
+        assert_eq!(
+            named(&["a", "b"]),
+            wallets(
+                "wallet_backup",
+                serde_json::json!([[{"name": "a"}, {"name": "b"}], "passphrase"])
+            )
+        );
+        assert_eq!(named(&[]), wallets("wallet_list", serde_json::json!([])));
+        assert_eq!(
+            Some(CallWallets::Unnamed),
+            wallets("multiSig_nonce", serde_json::json!(["session", "00"]))
+        );
+        assert_eq!(None, wallets("wallet_balance", serde_json::json!([])));
+        assert_eq!(
+            None,
+            wallets("wallet_unknown", serde_json::json!([["a", "00"]]))
+        );
+    }
+
+    #[test]
+    fn check_read_only_methods_are_known() {
+        for method in READ_ONLY_METHODS.iter() {
+            assert!(wallet_param(method).is_some(), "{}", method);
+        }
+    }
+
+    #[test]
+    fn check_token_scope_and_rate_limit() {
+        let access_control = AccessControl::new(AccessConfig {
+            tokens: vec![ApiToken {
+                name: "reader".to_owned(),
+                token: "secret".to_owned(),
+                wallets: vec!["a".to_owned()],
+                methods: vec![
+                    "@read".to_owned(),
+                    "sync*".to_owned(),
+                    "multiSig_nonce".to_owned(),
+                ],
+            }],
+            rate_limit: Some(RateLimit {
+                calls: 3,
+                period: 3600,
+            }),
+            audit_log: None,
+        })
+        .unwrap();
+        let state = access_control.state.unwrap();
+        let check = |call: &MethodCall, meta: &RpcMetadata| {
+            let wallets = call_wallets(&call.method, &call.params);
+            AccessControl::check(&state, call, wallets.as_ref(), meta)
+        };
+
+        let balance = method_call("wallet_balance", serde_json::json!([["a", "00"]]));
+        assert_eq!(
+            Some("reader".to_owned()),
+            check(&balance, &meta("secret")).unwrap()
+        );
+        assert!(check(&balance, &RpcMetadata::default()).is_err());
+        assert!(check(&balance, &meta("secreT")).is_err());
+
+        let other = method_call("wallet_balance", serde_json::json!([["b", "00"]]));
+        assert!(check(&other, &meta("secret")).is_err());
+        let send = method_call("wallet_sendToAddress", serde_json::json!([["a", "00"]]));
+        assert!(check(&send, &meta("secret")).is_err());
+        let sync = method_call("sync", serde_json::json!([["a", "00"], {}]));
+        assert!(check(&sync, &meta("secret")).is_ok());
+        // the wallet name is the first param of staking_state
+        let staking_state = method_call("staking_state", serde_json::json!(["b", "0x00"]));
+        assert!(check(&staking_state, &meta("secret")).is_err());
+        let staking_state = method_call("staking_state", serde_json::json!(["a", "0x00"]));
+        assert!(check(&staking_state, &meta("secret")).is_ok());
+        // the multi-sig sessions don't name the wallet
+        let nonce = method_call("multiSig_nonce", serde_json::json!(["session", "00"]));
+        assert!(check(&nonce, &meta("secret")).is_err());
+
+        // the rejected calls aren't counted
+        assert_eq!(
+            ErrorCode::ServerError(RATE_LIMITED_CODE),
+            check(&balance, &meta("secret")).unwrap_err().code
+        );
+    }
+
+    #[test]
+    fn check_failed_authentication_limit() {
+        let access_control = AccessControl::new(AccessConfig {
+            tokens: vec![ApiToken {
+                name: "reader".to_owned(),
+                token: "secret".to_owned(),
+                wallets: vec!["*".to_owned()],
+                methods: vec!["@read".to_owned()],
+            }],
+            rate_limit: None,
+            audit_log: None,
+        })
+        .unwrap();
+        assert!(access_control.requires_token());
+        let attacker = Some("10.0.0.1".parse().unwrap());
+        let client = Some("10.0.0.2".parse().unwrap());
+
+        assert!(access_control
+            .authenticate(Some("secret"), attacker)
+            .is_ok());
+        for _ in 0..MAX_AUTH_FAILURES {
+            assert!(access_control
+                .authenticate(Some("guess"), attacker)
+                .is_err());
+        }
+        // the valid token is rejected too until the period ends
+        assert_eq!(
+            Err("too many failed authentication attempts".to_owned()),
+            access_control.authenticate(Some("secret"), attacker)
+        );
+        let state = access_control.state.clone().unwrap();
+        let balance = method_call("wallet_balance", serde_json::json!([["a", "00"]]));
+        let wallets = call_wallets(&balance.method, &balance.params);
+        let meta = RpcMetadata {
+            peer: attacker,
+            ..meta("secret")
+        };
+        assert_eq!(
+            ErrorCode::ServerError(RATE_LIMITED_CODE),
+            AccessControl::check(&state, &balance, wallets.as_ref(), &meta)
+                .unwrap_err()
+                .code
+        );
+        // the other clients aren't affected
+        assert!(access_control.authenticate(Some("secret"), client).is_ok());
+        assert!(access_control.authenticate(None, client).is_err());
+
+        assert!(!AccessControl::default().requires_token());
+        assert!(AccessControl::default().authenticate(None, None).is_ok());
+    }
+}
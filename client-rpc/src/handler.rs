@@ -1,4 +1,5 @@
-use jsonrpc_core::IoHandler;
+use jsonrpc_core::MetaIoHandler;
+use jsonrpc_pubsub::PubSubHandler;
 
 use chain_core::tx::fee::FeeAlgorithm;
 use client_common::cipher::TransactionObfuscation;
@@ -17,9 +18,11 @@ use client_network::network_ops::DefaultNetworkOpsClient;
 #[cfg(feature = "experimental")]
 use crate::rpc::multisig_rpc::{MultiSigRpc, MultiSigRpcImpl};
 
+use crate::access::{AccessControl, RpcMetadata};
 use crate::rpc::{
     info_rpc::{InfoRpc, InfoRpcImpl},
     staking_rpc::{StakingRpc, StakingRpcImpl},
+    subscription_rpc::{SubscriptionHub, SubscriptionRpc, SubscriptionRpcImpl},
     sync_rpc::{CBindingCore, SyncRpc, SyncRpcImpl},
     transaction_rpc::{TransactionRpc, TransactionRpcImpl},
     wallet_rpc::{WalletRpc, WalletRpcImpl},
@@ -36,7 +39,9 @@ type AppSyncerConfig<O, L> = ObfuscationSyncerConfig<SledStorage, WebsocketRpcCl
 
 #[derive(Clone)]
 pub struct RpcHandler {
-    pub io: IoHandler,
+    /// the subscriptions are only available with the WebSocket session metadata,
+    /// the API token of the metadata is checked by the access control
+    pub io: MetaIoHandler<RpcMetadata, AccessControl>,
 }
 
 impl RpcHandler {
@@ -46,8 +51,9 @@ impl RpcHandler {
         network_id: u8,
         sync_options: SyncerOptions,
         progress_callback: Option<CBindingCore>,
+        access_control: AccessControl,
     ) -> Result<Self> {
-        let mut io = IoHandler::new();
+        let mut io = PubSubHandler::new(MetaIoHandler::with_middleware(access_control));
         let storage = SledStorage::new(&storage_dir)?;
         let tendermint_client = WebsocketRpcClient::new(&websocket_url)?;
         let obfuscation = tendermint_client.clone();
@@ -88,8 +94,15 @@ impl RpcHandler {
         let sync_wallet_client =
             make_wallet_client(storage, tendermint_client, fee_policy, obfuscation)?;
 
-        let sync_rpc =
-            SyncRpcImpl::new(syncer_config, progress_callback, sync_wallet_client, handle);
+        let subscriptions = SubscriptionHub::default();
+        let sync_rpc = SyncRpcImpl::new(
+            syncer_config,
+            progress_callback,
+            sync_wallet_client,
+            handle,
+            subscriptions.clone(),
+        );
+        let subscription_rpc = SubscriptionRpcImpl::new(wallet_client.clone(), subscriptions);
         let wallet_rpc = WalletRpcImpl::new(wallet_client, network_id);
 
         #[cfg(feature = "experimental")]
@@ -99,8 +112,9 @@ impl RpcHandler {
         io.extend_with(sync_rpc.to_delegate());
         io.extend_with(wallet_rpc.to_delegate());
         io.extend_with(info_rpc.to_delegate());
+        io.extend_with(subscription_rpc.to_delegate());
 
-        Ok(RpcHandler { io })
+        Ok(RpcHandler { io: io.into() })
     }
 
     pub fn new(
@@ -116,11 +130,31 @@ impl RpcHandler {
             network_id,
             sync_options,
             progress_callback,
+            AccessControl::default(),
+        )
+    }
+
+    /// Creates the handler checking the API tokens, rate limit and writing the audit log
+    pub fn new_with_access_control(
+        storage_dir: &str,
+        websocket_url: &str,
+        network_id: u8,
+        sync_options: SyncerOptions,
+        progress_callback: Option<CBindingCore>,
+        access_control: AccessControl,
+    ) -> Result<Self> {
+        Self::new_impl(
+            storage_dir,
+            websocket_url,
+            network_id,
+            sync_options,
+            progress_callback,
+            access_control,
         )
     }
 
     pub fn handle(&self, req: &str) -> Option<String> {
-        self.io.handle_request_sync(req)
+        self.io.handle_request_sync(req, RpcMetadata::default())
     }
 }
 
@@ -1,5 +1,6 @@
 use std::fmt::Debug;
 
+pub mod access;
 pub mod handler;
 pub mod rpc;
 
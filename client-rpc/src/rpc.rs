@@ -2,6 +2,7 @@ pub mod info_rpc;
 #[cfg(feature = "experimental")]
 pub mod multisig_rpc;
 pub mod staking_rpc;
+pub mod subscription_rpc;
 pub mod sync_rpc;
 pub mod sync_worker;
 pub mod transaction_rpc;
@@ -6,9 +6,12 @@ use jsonrpc_derive::rpc;
 
 use crate::{rpc_error_from_string, to_rpc_error};
 use chain_core::init::coin::Coin;
+use chain_core::init::params::{NetworkParameterChange, NetworkParameters};
 use chain_core::state::account::{
-    ConfidentialInit, CouncilNodeMeta, StakedState, StakedStateAddress, StakedStateOpAttributes,
+    CommissionRate, ConfidentialInit, CouncilNodeMeta, SlashRecord, StakeKind, StakedState,
+    StakedStateAddress, StakedStateOpAttributes,
 };
+use chain_core::state::governance::{Proposal, ProposalId};
 use chain_core::state::tendermint::TendermintValidatorPubKey;
 use chain_core::tx::data::access::{TxAccess, TxAccessPolicy};
 use chain_core::tx::data::address::ExtendedAddr;
@@ -69,7 +72,62 @@ pub trait StakingRpc: Send + Sync {
         validator_pubkey: String,
         staking_address: String,
         keypackage: String,
+        commission_rate: Option<String>,
     ) -> Result<String>;
+
+    #[rpc(name = "staking_validatorNodeMetadataUpdate")]
+    fn node_metadata_update(
+        &self,
+        request: WalletRequest,
+        staking_address: String,
+        validator_node_name: Option<String>,
+        security_contact: Option<String>,
+        validator_pubkey: Option<String>,
+    ) -> Result<String>;
+
+    #[rpc(name = "staking_transferStake")]
+    fn transfer_stake(
+        &self,
+        request: WalletRequest,
+        from_address: String,
+        to_address: String,
+        amount: Coin,
+        kind: StakeKind,
+    ) -> Result<String>;
+
+    #[rpc(name = "staking_proposeParamChange")]
+    fn propose_param_change(
+        &self,
+        request: WalletRequest,
+        staking_address: String,
+        change: NetworkParameterChange,
+    ) -> Result<String>;
+
+    #[rpc(name = "staking_voteParamChange")]
+    fn vote_param_change(
+        &self,
+        request: WalletRequest,
+        staking_address: String,
+        proposal_id: String,
+        approve: bool,
+    ) -> Result<String>;
+
+    #[rpc(name = "staking_delegate")]
+    fn delegate(
+        &self,
+        request: WalletRequest,
+        staking_address: String,
+        council_node_address: Option<String>,
+    ) -> Result<String>;
+
+    #[rpc(name = "staking_networkParams")]
+    fn network_params(&self) -> Result<NetworkParameters>;
+
+    #[rpc(name = "staking_proposals")]
+    fn proposals(&self) -> Result<Vec<Proposal>>;
+
+    #[rpc(name = "staking_slashHistory")]
+    fn slash_history(&self, address: StakedStateAddress) -> Result<Vec<SlashRecord>>;
 }
 
 pub struct StakingRpcImpl<T, N>
@@ -155,12 +213,7 @@ where
 
         // update the wallet pending transaction state
         self.client
-            .update_tx_pending_state(
-                &request.name,
-                &request.enckey,
-                transaction.tx_id(),
-                tx_pending,
-            )
+            .update_tx_pending_state(&request.name, &request.enckey, &transaction, tx_pending)
             .map_err(to_rpc_error)?;
 
         Ok(hex::encode(transaction.tx_id()))
@@ -242,12 +295,7 @@ where
             .map_err(to_rpc_error)?;
         // update the wallet pending transaction state
         self.client
-            .update_tx_pending_state(
-                &request.name,
-                &request.enckey,
-                transaction.tx_id(),
-                tx_pending,
-            )
+            .update_tx_pending_state(&request.name, &request.enckey, &transaction, tx_pending)
             .map_err(to_rpc_error)?;
         Ok(hex::encode(transaction.tx_id()))
     }
@@ -360,12 +408,7 @@ where
             .map_err(to_rpc_error)?;
         // update the wallet pending transaction state
         self.client
-            .update_tx_pending_state(
-                &request.name,
-                &request.enckey,
-                transaction.tx_id(),
-                tx_pending,
-            )
+            .update_tx_pending_state(&request.name, &request.enckey, &transaction, tx_pending)
             .map_err(to_rpc_error)?;
         Ok(hex::encode(transaction.tx_id()))
     }
@@ -407,6 +450,7 @@ where
         validator_pubkey: String,
         staking_addr: String,
         keypackage: String,
+        commission_rate: Option<String>,
     ) -> Result<String> {
         let attributes = StakedStateOpAttributes::new(self.network_id);
         let staking_account_address = staking_addr
@@ -418,8 +462,13 @@ where
                 )
             })
             .map_err(to_rpc_error)?;
-        let node_metadata =
+        let mut node_metadata =
             get_node_metadata(&validator_node_name, &validator_pubkey, &keypackage)?;
+        if let Some(commission_rate) = commission_rate {
+            node_metadata.commission_rate = CommissionRate::from_str(&commission_rate)
+                .map_err(|e| Error::new(ErrorKind::InvalidInput, e))
+                .map_err(to_rpc_error)?;
+        }
         let transaction = self
             .ops_client
             .create_node_join_transaction(
@@ -437,13 +486,239 @@ where
 
         Ok(hex::encode(transaction.tx_id()))
     }
+
+    fn node_metadata_update(
+        &self,
+        request: WalletRequest,
+        staking_addr: String,
+        validator_node_name: Option<String>,
+        security_contact: Option<String>,
+        validator_pubkey: Option<String>,
+    ) -> Result<String> {
+        let attributes = StakedStateOpAttributes::new(self.network_id);
+        let staking_account_address = staking_addr
+            .parse::<StakedStateAddress>()
+            .chain(|| {
+                (
+                    ErrorKind::DeserializationError,
+                    "Unable to deserialize staking address",
+                )
+            })
+            .map_err(to_rpc_error)?;
+        // empty security contact removes the current one
+        let security_contact = security_contact.map(|contact| {
+            if contact.is_empty() {
+                None
+            } else {
+                Some(contact)
+            }
+        });
+        let consensus_pubkey = validator_pubkey
+            .map(|pubkey| parse_validator_pubkey(&pubkey))
+            .transpose()?;
+        let transaction = self
+            .ops_client
+            .create_node_metadata_update_transaction(
+                &request.name,
+                &request.enckey,
+                staking_account_address,
+                attributes,
+                validator_node_name,
+                security_contact,
+                consensus_pubkey,
+                true,
+            )
+            .map_err(to_rpc_error)?;
+        self.client
+            .broadcast_transaction(&transaction)
+            .map_err(to_rpc_error)?;
+
+        Ok(hex::encode(transaction.tx_id()))
+    }
+
+    fn transfer_stake(
+        &self,
+        request: WalletRequest,
+        from_address: String,
+        to_address: String,
+        amount: Coin,
+        kind: StakeKind,
+    ) -> Result<String> {
+        let attr = StakedStateOpAttributes::new(self.network_id);
+        let parse_address = |address: &str| {
+            StakedStateAddress::from_str(address)
+                .chain(|| {
+                    (
+                        ErrorKind::DeserializationError,
+                        format!("Unable to deserialize staking address ({})", address),
+                    )
+                })
+                .map_err(to_rpc_error)
+        };
+        let from_addr = parse_address(&from_address)?;
+        let to_addr = parse_address(&to_address)?;
+
+        let transaction = self
+            .ops_client
+            .create_transfer_stake_transaction(
+                &request.name,
+                &request.enckey,
+                from_addr,
+                to_addr,
+                amount,
+                kind,
+                attr,
+                true,
+            )
+            .map_err(to_rpc_error)?;
+        self.client
+            .broadcast_transaction(&transaction)
+            .map_err(to_rpc_error)?;
+
+        Ok(hex::encode(transaction.tx_id()))
+    }
+
+    fn propose_param_change(
+        &self,
+        request: WalletRequest,
+        staking_address: String,
+        change: NetworkParameterChange,
+    ) -> Result<String> {
+        let attr = StakedStateOpAttributes::new(self.network_id);
+        let addr = parse_staking_address(&staking_address)?;
+
+        let transaction = self
+            .ops_client
+            .create_param_change_proposal_transaction(
+                &request.name,
+                &request.enckey,
+                addr,
+                attr,
+                change,
+                true,
+            )
+            .map_err(to_rpc_error)?;
+        self.client
+            .broadcast_transaction(&transaction)
+            .map_err(to_rpc_error)?;
+
+        // the transaction ID is the proposal ID
+        Ok(hex::encode(transaction.tx_id()))
+    }
+
+    fn vote_param_change(
+        &self,
+        request: WalletRequest,
+        staking_address: String,
+        proposal_id: String,
+        approve: bool,
+    ) -> Result<String> {
+        let attr = StakedStateOpAttributes::new(self.network_id);
+        let addr = parse_staking_address(&staking_address)?;
+        let id = parse_proposal_id(&proposal_id)?;
+
+        let transaction = self
+            .ops_client
+            .create_param_change_vote_transaction(
+                &request.name,
+                &request.enckey,
+                addr,
+                attr,
+                id,
+                approve,
+                true,
+            )
+            .map_err(to_rpc_error)?;
+        self.client
+            .broadcast_transaction(&transaction)
+            .map_err(to_rpc_error)?;
+
+        Ok(hex::encode(transaction.tx_id()))
+    }
+
+    fn delegate(
+        &self,
+        request: WalletRequest,
+        staking_address: String,
+        council_node_address: Option<String>,
+    ) -> Result<String> {
+        let attr = StakedStateOpAttributes::new(self.network_id);
+        let addr = parse_staking_address(&staking_address)?;
+        let council_node = match council_node_address {
+            Some(address) => Some(parse_staking_address(&address)?),
+            None => None,
+        };
+
+        let transaction = self
+            .ops_client
+            .create_delegate_transaction(
+                &request.name,
+                &request.enckey,
+                addr,
+                attr,
+                council_node,
+                true,
+            )
+            .map_err(to_rpc_error)?;
+        self.client
+            .broadcast_transaction(&transaction)
+            .map_err(to_rpc_error)?;
+
+        Ok(hex::encode(transaction.tx_id()))
+    }
+
+    fn network_params(&self) -> Result<NetworkParameters> {
+        self.ops_client.get_network_params().map_err(to_rpc_error)
+    }
+
+    fn proposals(&self) -> Result<Vec<Proposal>> {
+        self.ops_client.get_proposals().map_err(to_rpc_error)
+    }
+
+    fn slash_history(&self, address: StakedStateAddress) -> Result<Vec<SlashRecord>> {
+        self.ops_client
+            .get_slash_history(&address)
+            .map_err(to_rpc_error)
+    }
 }
 
-fn get_node_metadata(
-    validator_name: &str,
-    validator_pubkey: &str,
-    keypackage: &str,
-) -> Result<CouncilNodeMeta> {
+fn parse_staking_address(staking_address: &str) -> Result<StakedStateAddress> {
+    StakedStateAddress::from_str(staking_address)
+        .chain(|| {
+            (
+                ErrorKind::DeserializationError,
+                format!(
+                    "Unable to deserialize staking address ({})",
+                    staking_address
+                ),
+            )
+        })
+        .map_err(to_rpc_error)
+}
+
+fn parse_proposal_id(proposal_id: &str) -> Result<ProposalId> {
+    let decoded = hex::decode(proposal_id)
+        .chain(|| {
+            (
+                ErrorKind::DeserializationError,
+                format!("Unable to decode proposal id ({})", proposal_id),
+            )
+        })
+        .map_err(to_rpc_error)?;
+
+    let mut id = ProposalId::default();
+    if decoded.len() != id.len() {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            "Expected proposal id of 32 bytes",
+        ))
+        .map_err(to_rpc_error);
+    }
+    id.copy_from_slice(&decoded);
+    Ok(id)
+}
+
+fn parse_validator_pubkey(validator_pubkey: &str) -> Result<TendermintValidatorPubKey> {
     let decoded_pubkey = base64::decode(validator_pubkey)
         .chain(|| {
             (
@@ -464,6 +739,14 @@ fn get_node_metadata(
     let mut pubkey_bytes = [0; 32];
     pubkey_bytes.copy_from_slice(&decoded_pubkey);
 
+    Ok(TendermintValidatorPubKey::Ed25519(pubkey_bytes))
+}
+
+fn get_node_metadata(
+    validator_name: &str,
+    validator_pubkey: &str,
+    keypackage: &str,
+) -> Result<CouncilNodeMeta> {
     let keypackage = base64::decode(keypackage)
         .err_kind(ErrorKind::InvalidInput, || "invalid base64")
         .map_err(to_rpc_error)?;
@@ -471,7 +754,7 @@ fn get_node_metadata(
     Ok(CouncilNodeMeta::new_with_details(
         validator_name.to_string(),
         None,
-        TendermintValidatorPubKey::Ed25519(pubkey_bytes),
+        parse_validator_pubkey(validator_pubkey)?,
         ConfidentialInit { keypackage },
     ))
 }
@@ -0,0 +1,291 @@
+//! Wallet event subscriptions (only available over the WebSocket transport):
+//! the notifications are sent by the wallet syncer as soon as the blocks are synchronized.
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use jsonrpc_core::futures::Future;
+use jsonrpc_core::Result;
+use jsonrpc_derive::rpc;
+use jsonrpc_pubsub::typed::{Sink, Subscriber};
+use jsonrpc_pubsub::SubscriptionId;
+use serde::Serialize;
+
+use client_core::types::{TransactionChange, WalletBalance};
+use client_core::wallet::WalletRequest;
+use client_core::WalletClient;
+
+use super::sync_rpc::RunSyncProgressResult;
+use crate::access::RpcMetadata;
+use crate::to_rpc_error;
+
+#[rpc(server)]
+pub trait SubscriptionRpc {
+    type Metadata;
+
+    #[pubsub(
+        subscription = "wallet_transactions",
+        subscribe,
+        name = "wallet_subscribeTransactions"
+    )]
+    fn subscribe_transactions(
+        &self,
+        meta: Self::Metadata,
+        subscriber: Subscriber<Vec<TransactionChange>>,
+        request: WalletRequest,
+    );
+
+    #[pubsub(
+        subscription = "wallet_transactions",
+        unsubscribe,
+        name = "wallet_unsubscribeTransactions"
+    )]
+    fn unsubscribe_transactions(
+        &self,
+        meta: Option<Self::Metadata>,
+        id: SubscriptionId,
+    ) -> Result<bool>;
+
+    #[pubsub(
+        subscription = "wallet_balance",
+        subscribe,
+        name = "wallet_subscribeBalance"
+    )]
+    fn subscribe_balance(
+        &self,
+        meta: Self::Metadata,
+        subscriber: Subscriber<WalletBalance>,
+        request: WalletRequest,
+    );
+
+    #[pubsub(
+        subscription = "wallet_balance",
+        unsubscribe,
+        name = "wallet_unsubscribeBalance"
+    )]
+    fn unsubscribe_balance(&self, meta: Option<Self::Metadata>, id: SubscriptionId)
+        -> Result<bool>;
+
+    #[pubsub(
+        subscription = "sync_progress",
+        subscribe,
+        name = "sync_subscribeProgress"
+    )]
+    fn subscribe_progress(
+        &self,
+        meta: Self::Metadata,
+        subscriber: Subscriber<RunSyncProgressResult>,
+        request: WalletRequest,
+    );
+
+    #[pubsub(
+        subscription = "sync_progress",
+        unsubscribe,
+        name = "sync_unsubscribeProgress"
+    )]
+    fn unsubscribe_progress(
+        &self,
+        meta: Option<Self::Metadata>,
+        id: SubscriptionId,
+    ) -> Result<bool>;
+}
+
+/// Subscribers of one kind of notifications (with the names of the subscribed wallets)
+struct Subscribers<T> {
+    sinks: Mutex<HashMap<SubscriptionId, (String, Sink<T>)>>,
+}
+
+impl<T> Default for Subscribers<T> {
+    fn default() -> Self {
+        Subscribers {
+            sinks: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+impl<T: Serialize> Subscribers<T> {
+    fn add(&self, id: SubscriptionId, name: &str, subscriber: Subscriber<T>) {
+        if let Ok(sink) = subscriber.assign_id(id.clone()) {
+            self.sinks
+                .lock()
+                .expect("get subscribers lock")
+                .insert(id, (name.to_owned(), sink));
+        }
+    }
+
+    fn remove(&self, id: &SubscriptionId) -> bool {
+        self.sinks
+            .lock()
+            .expect("get subscribers lock")
+            .remove(id)
+            .is_some()
+    }
+
+    /// Sends the notification to the subscribers of the wallet
+    /// (the ones with a closed session are removed)
+    fn notify<F: Fn() -> T>(&self, name: &str, value: F) {
+        self.sinks
+            .lock()
+            .expect("get subscribers lock")
+            .retain(|_, (wallet_name, sink)| {
+                wallet_name.as_str() != name || sink.notify(Ok(value())).wait().is_ok()
+            });
+    }
+}
+
+/// Shared registry of the subscriptions, notified by the wallet syncer
+#[derive(Clone, Default)]
+pub struct SubscriptionHub {
+    next_id: Arc<AtomicU64>,
+    transactions: Arc<Subscribers<Vec<TransactionChange>>>,
+    balance: Arc<Subscribers<WalletBalance>>,
+    progress: Arc<Subscribers<RunSyncProgressResult>>,
+}
+
+impl SubscriptionHub {
+    fn next_id(&self) -> SubscriptionId {
+        SubscriptionId::Number(self.next_id.fetch_add(1, Ordering::SeqCst))
+    }
+
+    /// Notifies the wallet transactions found in the synchronized blocks
+    /// and the wallet balance after them
+    pub fn notify_transactions(
+        &self,
+        name: &str,
+        transactions: &[TransactionChange],
+        balance: &WalletBalance,
+    ) {
+        self.transactions.notify(name, || transactions.to_vec());
+        self.balance.notify(name, || balance.clone());
+    }
+
+    /// Notifies the synchronization progress of the wallet
+    pub fn notify_progress(&self, name: &str, progress: &RunSyncProgressResult) {
+        self.progress.notify(name, || progress.clone());
+    }
+}
+
+pub struct SubscriptionRpcImpl<T: WalletClient> {
+    client: T,
+    hub: SubscriptionHub,
+}
+
+impl<T> SubscriptionRpcImpl<T>
+where
+    T: WalletClient,
+{
+    pub fn new(client: T, hub: SubscriptionHub) -> Self {
+        SubscriptionRpcImpl { client, hub }
+    }
+
+    /// Checks the wallet name and encryption key before the subscription
+    fn authorize<V>(
+        &self,
+        subscriber: Subscriber<V>,
+        request: &WalletRequest,
+    ) -> Option<Subscriber<V>> {
+        match self.client.view_key(&request.name, &request.enckey) {
+            Ok(_) => Some(subscriber),
+            Err(err) => {
+                let _ = subscriber.reject(to_rpc_error(err));
+                None
+            }
+        }
+    }
+}
+
+impl<T> SubscriptionRpc for SubscriptionRpcImpl<T>
+where
+    T: WalletClient + 'static,
+{
+    type Metadata = RpcMetadata;
+
+    fn subscribe_transactions(
+        &self,
+        _meta: Self::Metadata,
+        subscriber: Subscriber<Vec<TransactionChange>>,
+        request: WalletRequest,
+    ) {
+        if let Some(subscriber) = self.authorize(subscriber, &request) {
+            self.hub
+                .transactions
+                .add(self.hub.next_id(), &request.name, subscriber);
+        }
+    }
+
+    fn unsubscribe_transactions(
+        &self,
+        _meta: Option<Self::Metadata>,
+        id: SubscriptionId,
+    ) -> Result<bool> {
+        Ok(self.hub.transactions.remove(&id))
+    }
+
+    fn subscribe_balance(
+        &self,
+        _meta: Self::Metadata,
+        subscriber: Subscriber<WalletBalance>,
+        request: WalletRequest,
+    ) {
+        if let Some(subscriber) = self.authorize(subscriber, &request) {
+            self.hub
+                .balance
+                .add(self.hub.next_id(), &request.name, subscriber);
+        }
+    }
+
+    fn unsubscribe_balance(
+        &self,
+        _meta: Option<Self::Metadata>,
+        id: SubscriptionId,
+    ) -> Result<bool> {
+        Ok(self.hub.balance.remove(&id))
+    }
+
+    fn subscribe_progress(
+        &self,
+        _meta: Self::Metadata,
+        subscriber: Subscriber<RunSyncProgressResult>,
+        request: WalletRequest,
+    ) {
+        if let Some(subscriber) = self.authorize(subscriber, &request) {
+            self.hub
+                .progress
+                .add(self.hub.next_id(), &request.name, subscriber);
+        }
+    }
+
+    fn unsubscribe_progress(
+        &self,
+        _meta: Option<Self::Metadata>,
+        id: SubscriptionId,
+    ) -> Result<bool> {
+        Ok(self.hub.progress.remove(&id))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use jsonrpc_core::futures::Stream;
+
+    #[test]
+    fn check_notify_subscribers_of_wallet() {
+        let hub = SubscriptionHub::default();
+        let (subscriber, _id, notifications) =
+            Subscriber::<WalletBalance>::new_test("wallet_balance");
+        let id = hub.next_id();
+        hub.balance.add(id.clone(), "Default", subscriber);
+        let (other, _other_id, _other_notifications) =
+            Subscriber::<WalletBalance>::new_test("wallet_balance");
+        hub.balance.add(hub.next_id(), "Other", other);
+
+        hub.notify_transactions("Default", &[], &WalletBalance::default());
+        let notification = notifications.wait().next().unwrap().unwrap();
+        assert!(notification.contains("wallet_balance"));
+
+        assert!(hub.balance.remove(&id));
+        assert!(!hub.balance.remove(&id));
+        assert_eq!(1, hub.balance.sinks.lock().unwrap().len());
+    }
+}
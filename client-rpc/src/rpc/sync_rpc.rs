@@ -1,3 +1,4 @@
+use super::subscription_rpc::SubscriptionHub;
 use super::sync_worker::SyncWorker;
 use super::sync_worker::WorkerShared;
 use crate::to_rpc_error;
@@ -89,6 +90,7 @@ where
     worker: WorkerShared,
     recover_address: T,
     light_client_handle: L,
+    subscriptions: SubscriptionHub,
 }
 
 impl<S, C, O, T, L> SyncRpcImpl<S, C, O, T, L>
@@ -105,6 +107,7 @@ where
 
         recover_address: T,
         light_client_handle: L,
+        subscriptions: SubscriptionHub,
     ) -> Self {
         SyncRpcImpl {
             config,
@@ -114,6 +117,7 @@ where
 
             recover_address,
             light_client_handle,
+            subscriptions,
         }
     }
 }
@@ -124,6 +128,7 @@ fn process_sync<S, C, O, T, L>(
     reset: bool,
     progress_callback: Option<CBindingCore>,
     recover_address: T,
+    subscriptions: &SubscriptionHub,
 ) -> Result<()>
 where
     S: Storage,
@@ -132,6 +137,7 @@ where
     T: AddressRecovery,
     L: Handle + Send + Sync + Clone,
 {
+    let name = request.name.clone();
     let mut syncer = WalletSyncer::with_obfuscation_config(
         config,
         request.name,
@@ -143,10 +149,6 @@ where
         syncer.reset_state().map_err(to_rpc_error)?;
     }
 
-    if progress_callback.is_none() {
-        return syncer.sync(|_| true).map_err(to_rpc_error);
-    }
-
     let mut init_block_height = 0;
     let mut final_block_height = 0;
     syncer
@@ -159,6 +161,15 @@ where
                 } => {
                     init_block_height = start_block_height;
                     final_block_height = finish_block_height;
+                    subscriptions.notify_progress(
+                        &name,
+                        &progress_result(
+                            &name,
+                            start_block_height,
+                            start_block_height,
+                            final_block_height,
+                        ),
+                    );
                     if let Some(delegator) = &progress_callback {
                         {
                             let mut user_callback =
@@ -173,6 +184,15 @@ where
                     current_block_height,
                     ..
                 } => {
+                    subscriptions.notify_progress(
+                        &name,
+                        &progress_result(
+                            &name,
+                            current_block_height,
+                            init_block_height,
+                            final_block_height,
+                        ),
+                    );
                     if let Some(delegator) = &progress_callback {
                         {
                             let mut user_callback =
@@ -187,11 +207,39 @@ where
                     }
                     true
                 }
+                ProgressReport::Transactions {
+                    transactions,
+                    balance,
+                    ..
+                } => {
+                    subscriptions.notify_transactions(&name, &transactions, &balance);
+                    true
+                }
             }
         })
         .map_err(to_rpc_error)
 }
 
+/// Progress notified to the subscribers (the same as the one of the sync worker)
+fn progress_result(name: &str, current: u64, start: u64, end: u64) -> RunSyncProgressResult {
+    let percent = if current >= start && end > start {
+        (current - start) as f32 / (end - start) as f32 * 100.0
+    } else {
+        0.0
+    };
+    RunSyncProgressResult {
+        name: name.to_owned(),
+        message: format!(
+            "sync {} progress {} percent  {} {}~{}",
+            name, percent, current, start, end
+        ),
+        percent,
+        current,
+        start,
+        end,
+    }
+}
+
 impl<S, C, O, T, L> SyncRpcImpl<S, C, O, T, L>
 where
     S: Storage + 'static,
@@ -209,6 +257,7 @@ where
         log::info!("run_sync");
         let config = self.config.clone();
         let recover_address = self.recover_address.clone();
+        let subscriptions = self.subscriptions.clone();
 
         let name = request.name.clone();
         let worker = self.worker.clone();
@@ -239,6 +288,7 @@ where
                     reset,
                     usercallback.clone(),
                     recover_address.clone(),
+                    &subscriptions,
                 );
                 log::info!("process_sync finished {} {:?}", name, result);
                 if let Err(error_message) = result {
@@ -305,6 +355,7 @@ where
                 sync_request.reset,
                 self.progress_callback.clone(),
                 self.recover_address.clone(),
+                &self.subscriptions,
             )?;
             Ok(RunSyncResult::default())
         } else {
@@ -6,19 +6,39 @@ use jsonrpc_derive::rpc;
 use secstr::SecUtf8;
 
 use chain_core::init::coin::Coin;
+use chain_core::tx::data::access::{TxAccess, TxAccessPolicy};
 use chain_core::tx::data::address::ExtendedAddr;
+use chain_core::tx::data::input::str2txid;
+use chain_core::tx::fee::{FeeAlgorithm, InclusionSpeed};
 use client_common::{PrivateKey, PublicKey, Result as CommonResult, SecKey};
 use client_core::service::WalletInfo;
 use client_core::transaction_builder::SignedTransferTransaction;
-use client_core::types::{TransactionChange, WalletBalance, WalletKind};
+use client_core::types::{PendingTransactionInfo, TransactionChange, WalletBalance, WalletKind};
+use client_core::wallet::backup::{BackupParts, EncryptedWalletBackup};
 use client_core::wallet::{CreateWalletRequest, WalletRequest};
 #[cfg(feature = "experimental")]
 use client_core::MultiSigWalletClient;
-use client_core::{Mnemonic, UnspentTransactions, WalletClient};
+use client_core::{InputSelection, Mnemonic, UnspentTransactions, WalletClient};
 use parity_scale_codec::{Decode, Encode};
+use serde::{Deserialize, Serialize};
 
 use crate::{rpc_error_from_string, to_rpc_error};
 
+/// Recipient of a batch payment
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Recipient {
+    pub to_address: String,
+    pub amount: Coin,
+}
+
+/// Payment of a batch payment recipient
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RecipientPayment {
+    pub to_address: String,
+    pub amount: Coin,
+    pub txid: String,
+}
+
 #[rpc(server)]
 pub trait WalletRpc: Send + Sync {
     #[rpc(name = "wallet_balance")]
@@ -92,8 +112,18 @@ pub trait WalletRpc: Send + Sync {
         to_address: String,
         amount: Coin,
         view_keys: Vec<String>,
+        input_selection: Option<InputSelection>,
+        access: Option<TxAccess>,
     ) -> Result<String>;
 
+    #[rpc(name = "wallet_sendToAddresses")]
+    fn send_to_addresses(
+        &self,
+        request: WalletRequest,
+        recipients: Vec<Recipient>,
+        view_keys: Vec<String>,
+    ) -> Result<Vec<RecipientPayment>>;
+
     #[rpc(name = "wallet_buildRawTransferTx")]
     fn build_raw_transfer_tx(
         &self,
@@ -119,6 +149,23 @@ pub trait WalletRpc: Send + Sync {
         reversed: bool,
     ) -> Result<Vec<TransactionChange>>;
 
+    #[rpc(name = "wallet_pendingTransactions")]
+    fn pending_transactions(&self, request: WalletRequest) -> Result<Vec<PendingTransactionInfo>>;
+
+    #[rpc(name = "wallet_rebroadcastTransaction")]
+    fn rebroadcast_transaction(&self, request: WalletRequest, txid: String) -> Result<()>;
+
+    #[rpc(name = "wallet_replaceTransaction")]
+    fn replace_transaction(
+        &self,
+        request: WalletRequest,
+        txid: String,
+        fee: Coin,
+    ) -> Result<String>;
+
+    #[rpc(name = "wallet_abandonTransaction")]
+    fn abandon_transaction(&self, request: WalletRequest, txid: String) -> Result<()>;
+
     #[rpc(name = "wallet_exportTransaction")]
     fn export_plain_tx(&self, request: WalletRequest, txid: String) -> Result<String>;
 
@@ -133,6 +180,26 @@ pub trait WalletRpc: Send + Sync {
 
     #[rpc(name = "wallet_import")]
     fn import(&self, request: CreateWalletRequest, wallet_info: WalletInfo) -> Result<SecKey>;
+
+    #[rpc(name = "wallet_backup")]
+    fn backup(
+        &self,
+        requests: Vec<WalletRequest>,
+        backup_passphrase: SecUtf8,
+        parts: Option<BackupParts>,
+    ) -> Result<EncryptedWalletBackup>;
+
+    #[rpc(name = "wallet_restoreBackup")]
+    fn restore_backup(
+        &self,
+        request: CreateWalletRequest,
+        backup: EncryptedWalletBackup,
+        backup_passphrase: SecUtf8,
+        parts: Option<BackupParts>,
+    ) -> Result<SecKey>;
+
+    #[rpc(name = "wallet_estimateFee")]
+    fn estimate_fee(&self, tx_size: usize, speed: InclusionSpeed) -> Result<Coin>;
 }
 
 pub struct WalletRpcImpl<T>
@@ -345,29 +412,85 @@ where
         to_address: String,
         amount: Coin,
         view_keys: Vec<String>,
+        input_selection: Option<InputSelection>,
+        access: Option<TxAccess>,
     ) -> Result<String> {
         let address = to_address
             .parse::<ExtendedAddr>()
             .map_err(|err| rpc_error_from_string(format!("{}", err)))?;
-        let mut view_keys = view_keys
+        let view_keys = view_keys
             .iter()
             .map(|view_key| PublicKey::from_str(view_key))
             .collect::<CommonResult<BTreeSet<PublicKey>>>()
             .map_err(to_rpc_error)?;
+        // the payment is the first output of the transaction
+        let access = access.unwrap_or_default();
+        let access_policies = view_keys
+            .iter()
+            .map(|key| TxAccessPolicy {
+                view_key: key.into(),
+                access: access.clone(),
+            })
+            .collect();
         let tx_id = self
             .client
-            .send_to_address(
+            .send_to_address_with_access(
                 &request.name,
                 &request.enckey,
                 amount,
                 address,
-                &mut view_keys,
+                access_policies,
                 self.network_id,
+                input_selection,
             )
             .map_err(to_rpc_error)?;
         Ok(hex::encode(tx_id))
     }
 
+    fn send_to_addresses(
+        &self,
+        request: WalletRequest,
+        recipients: Vec<Recipient>,
+        view_keys: Vec<String>,
+    ) -> Result<Vec<RecipientPayment>> {
+        let outputs = recipients
+            .iter()
+            .map(|recipient| {
+                recipient
+                    .to_address
+                    .parse::<ExtendedAddr>()
+                    .map(|address| (address, recipient.amount))
+                    .map_err(|err| {
+                        rpc_error_from_string(format!("{}: {}", recipient.to_address, err))
+                    })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let mut view_keys = view_keys
+            .iter()
+            .map(|view_key| PublicKey::from_str(view_key))
+            .collect::<CommonResult<BTreeSet<PublicKey>>>()
+            .map_err(to_rpc_error)?;
+        let txids = self
+            .client
+            .send_to_addresses(
+                &request.name,
+                &request.enckey,
+                outputs,
+                &mut view_keys,
+                self.network_id,
+            )
+            .map_err(to_rpc_error)?;
+        Ok(recipients
+            .into_iter()
+            .zip(txids)
+            .map(|(recipient, txid)| RecipientPayment {
+                to_address: recipient.to_address,
+                amount: recipient.amount,
+                txid: hex::encode(txid),
+            })
+            .collect())
+    }
+
     fn build_raw_transfer_tx(
         &self,
         request: WalletRequest,
@@ -440,6 +563,40 @@ where
             .map_err(to_rpc_error)
     }
 
+    fn pending_transactions(&self, request: WalletRequest) -> Result<Vec<PendingTransactionInfo>> {
+        self.client
+            .pending_transactions(&request.name, &request.enckey)
+            .map_err(to_rpc_error)
+    }
+
+    fn rebroadcast_transaction(&self, request: WalletRequest, txid: String) -> Result<()> {
+        let txid = str2txid(&txid).map_err(to_rpc_error)?;
+        self.client
+            .rebroadcast_transaction(&request.name, &request.enckey, &txid)
+            .map_err(to_rpc_error)
+    }
+
+    fn replace_transaction(
+        &self,
+        request: WalletRequest,
+        txid: String,
+        fee: Coin,
+    ) -> Result<String> {
+        let txid = str2txid(&txid).map_err(to_rpc_error)?;
+        let replacement_txid = self
+            .client
+            .replace_transaction(&request.name, &request.enckey, &txid, fee)
+            .map_err(to_rpc_error)?;
+        Ok(hex::encode(replacement_txid))
+    }
+
+    fn abandon_transaction(&self, request: WalletRequest, txid: String) -> Result<()> {
+        let txid = str2txid(&txid).map_err(to_rpc_error)?;
+        self.client
+            .abandon_transaction(&request.name, &request.enckey, &txid)
+            .map_err(to_rpc_error)
+    }
+
     fn get_enc_key(&self, request: CreateWalletRequest) -> Result<SecKey> {
         self.client
             .auth_token(&request.name, &request.passphrase)
@@ -454,11 +611,59 @@ where
         Ok(wallet_info)
     }
 
+    fn estimate_fee(&self, tx_size: usize, speed: InclusionSpeed) -> Result<Coin> {
+        let fee_policy = self
+            .client
+            .suggest_fee_policy(speed)
+            .map_err(to_rpc_error)?;
+        let fee = fee_policy.calculate_fee(tx_size).map_err(to_rpc_error)?;
+        Ok(fee.to_coin())
+    }
+
     fn import(&self, request: CreateWalletRequest, wallet_info: WalletInfo) -> Result<SecKey> {
         self.client
             .import_wallet(&request.name, &request.passphrase, wallet_info)
             .map_err(to_rpc_error)
     }
+
+    fn backup(
+        &self,
+        requests: Vec<WalletRequest>,
+        backup_passphrase: SecUtf8,
+        parts: Option<BackupParts>,
+    ) -> Result<EncryptedWalletBackup> {
+        let parts = parts.unwrap_or_default();
+        let backups = requests
+            .iter()
+            .map(|request| {
+                self.client
+                    .backup_wallet(&request.name, &request.enckey, parts)
+            })
+            .collect::<CommonResult<Vec<_>>>()
+            .map_err(to_rpc_error)?;
+        EncryptedWalletBackup::seal(&backups, &backup_passphrase).map_err(to_rpc_error)
+    }
+
+    fn restore_backup(
+        &self,
+        request: CreateWalletRequest,
+        backup: EncryptedWalletBackup,
+        backup_passphrase: SecUtf8,
+        parts: Option<BackupParts>,
+    ) -> Result<SecKey> {
+        let backup = backup
+            .open(&backup_passphrase)
+            .map_err(to_rpc_error)?
+            .into_iter()
+            .find(|backup| backup.name() == request.name)
+            .ok_or_else(|| {
+                rpc_error_from_string(format!("wallet {} not found in the backup", request.name))
+            })?;
+        let parts = parts.unwrap_or(backup.parts);
+        self.client
+            .restore_wallet_backup(&request.name, &request.passphrase, backup, parts)
+            .map_err(to_rpc_error)
+    }
 }
 
 #[cfg(test)]
@@ -1012,7 +1217,43 @@ pub mod tests {
             addrs[0].clone(),
             Coin::from(1_0000u32),
             vec![viewkey],
+            None,
+            None,
         );
         assert!(send_result.is_err());
     }
+
+    #[test]
+    fn wallet_send_to_addresses_should_check_recipients() {
+        let wallet_rpc = setup_wallet_rpc();
+        let (create_request, wallet_request) = create_wallet_request("Default", "123456");
+        wallet_rpc
+            .create(create_request, WalletKind::Basic, None)
+            .unwrap();
+        let address = wallet_rpc
+            .create_transfer_address(wallet_request.clone())
+            .unwrap();
+
+        let invalid = vec![
+            Recipient {
+                to_address: address.clone(),
+                amount: Coin::from(1_0000u32),
+            },
+            Recipient {
+                to_address: "invalid".to_owned(),
+                amount: Coin::from(1_0000u32),
+            },
+        ];
+        assert!(wallet_rpc
+            .send_to_addresses(wallet_request.clone(), invalid, vec![])
+            .is_err());
+
+        let recipients = vec![Recipient {
+            to_address: address,
+            amount: Coin::from(1_0000u32),
+        }];
+        assert!(wallet_rpc
+            .send_to_addresses(wallet_request, recipients, vec![])
+            .is_err());
+    }
 }
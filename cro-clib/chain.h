@@ -57,6 +57,22 @@ typedef struct CroStakedState {
   uint64_t unbonded_from;
 } CroStakedState;
 
+/**
+ * back up the wallets (`wallet_backup`) with the json-rpc context
+ * wallets: json array of the wallet requests ex) [{"name": "Default", "enckey": "..."}]
+ * backup_passphrase: passphrase encrypting the backup
+ * parts: json of the optional parts ex) {"history": true} or null (all the parts)
+ * buf: json rpc response with the encrypted backup
+ * buf_size: size of buf in bytes
+ * # Safety
+ */
+CroResult cro_backup_wallets(CroJsonRpcPtr rpc_ptr,
+                             const char *wallets,
+                             const char *backup_passphrase,
+                             const char *parts,
+                             char *buf,
+                             uintptr_t buf_size);
+
 /**
  * create staking address
  * # Safety
@@ -251,6 +267,14 @@ CroResult cro_encrypt(const char *tenermint_url_string,
  */
 uint64_t cro_estimate_fee(CroFeePtr fee_ptr, uint32_t tx_payload_size);
 
+/**
+ * estimate fee for the inclusion speed
+ * tx_payload_size: in bytes
+ * speed: 0 (slow), 1 (normal), 2 (fast)
+ * # Safety
+ */
+uint64_t cro_estimate_fee_for_speed(CroFeePtr fee_ptr, uint32_t tx_payload_size, uint8_t speed);
+
 /**
  * estimate fee after encryption
  * tx_payload_size: in bytes
@@ -354,6 +378,26 @@ void cro_jsonrpc_call_dummy(ProgressCallback _progress_callback, ProgressWrapper
  */
 CroResult cro_restore_hdwallet(const char *mnemonics_string, CroHDWalletPtr *wallet_out);
 
+/**
+ * restore a wallet from the backup (`wallet_restoreBackup`) with the json-rpc context
+ * name: name of the restored wallet
+ * passphrase: passphrase of the restored wallet
+ * backup: json of the encrypted backup (the result of `cro_backup_wallets`)
+ * backup_passphrase: passphrase of the backup
+ * parts: json of the parts to restore ex) {"history": false} or null (all the parts)
+ * buf: json rpc response with the encryption key of the restored wallet
+ * buf_size: size of buf in bytes
+ * # Safety
+ */
+CroResult cro_restore_wallet_backup(CroJsonRpcPtr rpc_ptr,
+                                    const char *name,
+                                    const char *passphrase,
+                                    const char *backup,
+                                    const char *backup_passphrase,
+                                    const char *parts,
+                                    char *buf,
+                                    uintptr_t buf_size);
+
 /**
  * request: json rpc request
  * example c code) const char* req = "{\"jsonrpc\": \"2.0\", \"method\": \"wallet_list\", \"params\": [], \"id\": 1}";
@@ -367,6 +411,34 @@ CroResult cro_run_jsonrpc(CroJsonRpcPtr rpc_ptr,
                           uintptr_t buf_size,
                           const void *user_data);
 
+/**
+ * send a batch payment (`wallet_sendToAddresses`) with the json-rpc context
+ * (the recipients are split into multiple transactions if a single one would be too large)
+ * name: wallet name
+ * enckey: hex encoded encryption key of the wallet
+ * recipients: json array ex) [{"to_address": "dcro1...", "amount": "100000000"}]
+ * view_keys: json array of hex encoded view keys ex) [] or ["03..."]
+ * buf: json rpc response with the txid paying each recipient
+ * buf_size: size of buf in bytes
+ * # Safety
+ */
+CroResult cro_send_to_addresses(CroJsonRpcPtr rpc_ptr,
+                                const char *name,
+                                const char *enckey,
+                                const char *recipients,
+                                const char *view_keys,
+                                char *buf,
+                                uintptr_t buf_size);
+
+/**
+ * set the current base fee (multiplier of the fee policy)
+ * and its max change after one block (from the network parameters)
+ * # Safety
+ */
+CroResult cro_set_base_fee(CroFeePtr fee_ptr,
+                           const char *base_fee_string,
+                           const char *max_change_rate_string);
+
 /**
  * add txin
  * txid_string: null terminated string, 64 length hex-char , 32 bytes
@@ -23,6 +23,7 @@ fn do_cro_basic_create_staking_address(private: PrivateKey) -> Box<CroAddress> {
             };
             Box::new(ret)
         }
+        ExtendedAddr::HashTimeLock(_) => unreachable!("multi-sig address is a tree address"),
     }
 }
 
@@ -74,6 +75,7 @@ fn do_cro_basic_create_transfer_address(private: PrivateKey) -> Box<CroAddress>
             };
             Box::new(ret)
         }
+        ExtendedAddr::HashTimeLock(_) => unreachable!("multi-sig address is a tree address"),
     }
 }
 
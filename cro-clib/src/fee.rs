@@ -2,7 +2,7 @@ use crate::types::get_string;
 use crate::types::CroResult;
 use crate::types::{CroFee, CroFeePtr};
 pub use chain_core::init::network::Network;
-use chain_core::tx::fee::{LinearFee, Milli};
+use chain_core::tx::fee::{DynamicFee, FeeAlgorithm, InclusionSpeed, LinearFee, Milli};
 use std::os::raw::c_char;
 use std::ptr;
 use std::str::FromStr;
@@ -17,26 +17,63 @@ pub unsafe extern "C" fn cro_create_fee_algorithm(
 ) -> CroResult {
     let base = get_string(constant_string);
     let coeff = get_string(coeff_string);
-    let fee = CroFee {
-        fee: LinearFee::new(
-            Milli::from_str(&base).expect("read milli from constant"),
-            Milli::from_str(&coeff).expect("read milli from coefficient"),
-        ),
-    };
+    let fee = CroFee::new(LinearFee::new(
+        Milli::from_str(&base).expect("read milli from constant"),
+        Milli::from_str(&coeff).expect("read milli from coefficient"),
+    ));
     let fee_box = Box::new(fee);
     ptr::write(fee_out, Box::into_raw(fee_box));
     CroResult::success()
 }
 
+/// set the current base fee (multiplier of the fee policy)
+/// and its max change after one block (from the network parameters)
+#[no_mangle]
+/// # Safety
+pub unsafe extern "C" fn cro_set_base_fee(
+    fee_ptr: CroFeePtr,
+    base_fee_string: *const c_char,
+    max_change_rate_string: *const c_char,
+) -> CroResult {
+    let fee: &mut CroFee = fee_ptr.as_mut().expect("get fee");
+    let base_fee = get_string(base_fee_string);
+    let max_change_rate = get_string(max_change_rate_string);
+    fee.base_fee = Milli::from_str(&base_fee).expect("read milli from base fee");
+    fee.max_change_rate = Milli::from_str(&max_change_rate).expect("read milli from change rate");
+    CroResult::success()
+}
+
 /// estimate fee
 /// tx_payload_size: in bytes
 #[no_mangle]
 /// # Safety
 pub unsafe extern "C" fn cro_estimate_fee(fee_ptr: CroFeePtr, tx_payload_size: u32) -> u64 {
     let fee: &mut CroFee = fee_ptr.as_mut().expect("get fee");
-    let fee_value = fee
-        .fee
-        .estimate(tx_payload_size as usize)
+    let fee_value = DynamicFee::new(fee.fee, fee.base_fee)
+        .calculate_fee(tx_payload_size as usize)
+        .expect("fee estimate");
+    let coin_value: u64 = fee_value.to_coin().into();
+    coin_value
+}
+
+/// estimate fee for the inclusion speed
+/// tx_payload_size: in bytes
+/// speed: 0 (slow), 1 (normal), 2 (fast)
+#[no_mangle]
+/// # Safety
+pub unsafe extern "C" fn cro_estimate_fee_for_speed(
+    fee_ptr: CroFeePtr,
+    tx_payload_size: u32,
+    speed: u8,
+) -> u64 {
+    let fee: &mut CroFee = fee_ptr.as_mut().expect("get fee");
+    let speed = match speed {
+        0 => InclusionSpeed::Slow,
+        1 => InclusionSpeed::Normal,
+        _ => InclusionSpeed::Fast,
+    };
+    let fee_value = DynamicFee::suggest(fee.fee, fee.base_fee, fee.max_change_rate, speed)
+        .calculate_fee(tx_payload_size as usize)
         .expect("fee estimate");
     let coin_value: u64 = fee_value.to_coin().into();
     coin_value
@@ -53,9 +90,8 @@ pub unsafe extern "C" fn cro_estimate_fee_after_encrypt(
     let fee: &mut CroFee = fee_ptr.as_mut().expect("get fee");
     let block_size = 16; // aes block size 16 bytes == 128 bits
     let after_encrypt_size = tx_payload_size + (block_size - (tx_payload_size % block_size));
-    let fee_value = fee
-        .fee
-        .estimate(after_encrypt_size as usize)
+    let fee_value = DynamicFee::new(fee.fee, fee.base_fee)
+        .calculate_fee(after_encrypt_size as usize)
         .expect("fee estimate");
     let coin_value: u64 = fee_value.to_coin().into();
     coin_value
@@ -178,6 +178,148 @@ pub unsafe extern "C" fn cro_run_jsonrpc(
     CroResult::success()
 }
 
+/// send a batch payment (`wallet_sendToAddresses`) with the json-rpc context
+/// (the recipients are split into multiple transactions if a single one would be too large)
+/// name: wallet name
+/// enckey: hex encoded encryption key of the wallet
+/// recipients: json array ex) [{"to_address": "dcro1...", "amount": "100000000"}]
+/// view_keys: json array of hex encoded view keys ex) [] or ["03..."]
+/// buf: json rpc response with the txid paying each recipient
+/// buf_size: size of buf in bytes
+/// # Safety
+#[no_mangle]
+pub unsafe extern "C" fn cro_send_to_addresses(
+    rpc_ptr: CroJsonRpcPtr,
+    name: *const c_char,
+    enckey: *const c_char,
+    recipients: *const c_char,
+    view_keys: *const c_char,
+    buf: *mut c_char,
+    buf_size: usize,
+) -> CroResult {
+    if rpc_ptr.is_null() {
+        return CroResult::fail();
+    }
+    let rpc = rpc_ptr.as_mut().expect("get wallet");
+    let recipients = serde_json::from_str::<serde_json::Value>(&get_string(recipients));
+    let view_keys = serde_json::from_str::<serde_json::Value>(&get_string(view_keys));
+    let json_request = match (recipients, view_keys) {
+        (Ok(recipients), Ok(view_keys)) => serde_json::json!({
+            "jsonrpc": "2.0",
+            "method": "wallet_sendToAddresses",
+            "params": [
+                {"name": get_string(name), "enckey": get_string(enckey)},
+                recipients,
+                view_keys
+            ],
+            "id": 1
+        }),
+        _ => return CroResult::fail(),
+    };
+    handle_request(rpc, json_request, buf, buf_size)
+}
+
+/// back up the wallets (`wallet_backup`) with the json-rpc context
+/// wallets: json array of the wallet requests ex) [{"name": "Default", "enckey": "..."}]
+/// backup_passphrase: passphrase encrypting the backup
+/// parts: json of the optional parts ex) {"history": true} or null (all the parts)
+/// buf: json rpc response with the encrypted backup
+/// buf_size: size of buf in bytes
+/// # Safety
+#[no_mangle]
+pub unsafe extern "C" fn cro_backup_wallets(
+    rpc_ptr: CroJsonRpcPtr,
+    wallets: *const c_char,
+    backup_passphrase: *const c_char,
+    parts: *const c_char,
+    buf: *mut c_char,
+    buf_size: usize,
+) -> CroResult {
+    if rpc_ptr.is_null() {
+        return CroResult::fail();
+    }
+    let rpc = rpc_ptr.as_mut().expect("get wallet");
+    let wallets = serde_json::from_str::<serde_json::Value>(&get_string(wallets));
+    let parts = serde_json::from_str::<serde_json::Value>(&get_string(parts));
+    let json_request = match (wallets, parts) {
+        (Ok(wallets), Ok(parts)) => serde_json::json!({
+            "jsonrpc": "2.0",
+            "method": "wallet_backup",
+            "params": [wallets, get_string(backup_passphrase), parts],
+            "id": 1
+        }),
+        _ => return CroResult::fail(),
+    };
+    handle_request(rpc, json_request, buf, buf_size)
+}
+
+/// restore a wallet from the backup (`wallet_restoreBackup`) with the json-rpc context
+/// name: name of the restored wallet
+/// passphrase: passphrase of the restored wallet
+/// backup: json of the encrypted backup (the result of `cro_backup_wallets`)
+/// backup_passphrase: passphrase of the backup
+/// parts: json of the parts to restore ex) {"history": false} or null (all the parts)
+/// buf: json rpc response with the encryption key of the restored wallet
+/// buf_size: size of buf in bytes
+/// # Safety
+#[no_mangle]
+pub unsafe extern "C" fn cro_restore_wallet_backup(
+    rpc_ptr: CroJsonRpcPtr,
+    name: *const c_char,
+    passphrase: *const c_char,
+    backup: *const c_char,
+    backup_passphrase: *const c_char,
+    parts: *const c_char,
+    buf: *mut c_char,
+    buf_size: usize,
+) -> CroResult {
+    if rpc_ptr.is_null() {
+        return CroResult::fail();
+    }
+    let rpc = rpc_ptr.as_mut().expect("get wallet");
+    let backup = serde_json::from_str::<serde_json::Value>(&get_string(backup));
+    let parts = serde_json::from_str::<serde_json::Value>(&get_string(parts));
+    let json_request = match (backup, parts) {
+        (Ok(backup), Ok(parts)) => serde_json::json!({
+            "jsonrpc": "2.0",
+            "method": "wallet_restoreBackup",
+            "params": [
+                {"name": get_string(name), "passphrase": get_string(passphrase)},
+                backup,
+                get_string(backup_passphrase),
+                parts
+            ],
+            "id": 1
+        }),
+        _ => return CroResult::fail(),
+    };
+    handle_request(rpc, json_request, buf, buf_size)
+}
+
+/// handles the json rpc request, copies the response to buf
+/// (fails if the response is an error)
+unsafe fn handle_request(
+    rpc: &CroJsonRpc,
+    json_request: serde_json::Value,
+    buf: *mut c_char,
+    buf_size: usize,
+) -> CroResult {
+    let s = rpc
+        .handler
+        .handle(&json_request.to_string())
+        .unwrap_or_default();
+    let failed = serde_json::from_str::<serde_json::Value>(&s)
+        .map(|response| response.get("error").is_some())
+        .unwrap_or(true);
+    let response = CString::new(s).unwrap();
+    libc::strncpy(buf, response.as_ptr(), buf_size);
+    if failed {
+        CroResult::fail()
+    } else {
+        CroResult::success()
+    }
+}
+
 /// destroy json-rpc context
 /// rpc: containing pointer to free
 /// # Safety
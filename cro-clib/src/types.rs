@@ -100,12 +100,26 @@ pub struct CroDepositTx {
 #[derive(Clone)]
 pub struct CroFee {
     pub fee: LinearFee,
+    /// current base fee multiplier of the fee policy
+    pub base_fee: Milli,
+    /// max change of the base fee after one block
+    pub max_change_rate: Milli,
+}
+
+impl CroFee {
+    pub fn new(fee: LinearFee) -> Self {
+        CroFee {
+            fee,
+            base_fee: Milli::try_new(1, 0).unwrap(),
+            max_change_rate: Milli::try_new(0, 0).unwrap(),
+        }
+    }
 }
 
 impl Default for CroFee {
     fn default() -> Self {
         let fee = LinearFee::new(Milli::try_new(0, 0).unwrap(), Milli::try_new(0, 0).unwrap());
-        CroFee { fee }
+        CroFee::new(fee)
     }
 }
 
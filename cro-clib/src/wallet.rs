@@ -132,6 +132,7 @@ pub unsafe extern "C" fn cro_create_transfer_address(
 
             CroResult::success()
         }
+        ExtendedAddr::HashTimeLock(_) => unreachable!("multi-sig address is a tree address"),
     }
 }
 
@@ -342,6 +342,8 @@ pub fn generate_genesis(
         slashing_config: genesis_dev_config.slashing_config,
         rewards_config: genesis_dev_config.rewards_config,
         max_validators: 50,
+        dynamic_fee_config: Default::default(),
+        governance_config: Default::default(),
     };
     let config = InitConfig::new(
         dist,
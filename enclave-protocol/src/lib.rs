@@ -26,10 +26,11 @@ use chain_core::state::account::StakedState;
 use chain_core::state::account::StakedStateOpWitness;
 use chain_core::state::account::WithdrawUnbondedTx;
 use chain_core::tx::data::input::TxoPointer;
+use chain_core::tx::data::output::TxInclusion;
 use chain_core::tx::data::{txid_hash, Tx, TxId};
 use chain_core::tx::witness::TxWitness;
 use chain_core::tx::TxObfuscated;
-use chain_core::tx::{fee::Fee, TxEnclaveAux};
+use chain_core::tx::{fee::Fee, TxEnclaveAux, TxRestrictedView};
 use chain_core::ChainInfo;
 use chain_tx_validation::TxWithOutputs;
 use secp256k1::{
@@ -45,6 +46,26 @@ pub type SealedLog = Vec<u8>;
 /// tx filter
 type TxFilter = [u8; 256];
 
+/// transaction input sealed for the local machine
+/// + when its transaction was included in the chain (for checking the relative timelocks)
+#[derive(Encode, Decode, Clone)]
+pub struct SealedTxInput {
+    /// raw sgx_sealed_data_t of the transaction
+    pub sealed_log: SealedLog,
+    /// block time and height of the transaction
+    pub inclusion: TxInclusion,
+}
+
+impl SealedTxInput {
+    /// creates the input from the stored sealed transaction and its inclusion
+    pub fn new(sealed_log: SealedLog, inclusion: TxInclusion) -> Self {
+        SealedTxInput {
+            sealed_log,
+            inclusion,
+        }
+    }
+}
+
 /// Internal encryption request
 #[derive(Encode, Decode)]
 pub struct IntraEncryptRequest {
@@ -53,7 +74,7 @@ pub struct IntraEncryptRequest {
     /// EncryptionRequest
     pub sealed_enc_request: SealedLog,
     /// transaction inputs (if any)
-    pub tx_inputs: Option<Vec<SealedLog>>,
+    pub tx_inputs: Option<Vec<SealedTxInput>>,
     /// related account if any
     pub account: Option<StakedState>,
     /// last chain info
@@ -66,7 +87,7 @@ pub enum IntraEnclaveRequest {
     InitChainCheck(u8),
     ValidateTx {
         request: Box<VerifyTxRequest>,
-        tx_inputs: Option<Vec<SealedLog>>,
+        tx_inputs: Option<Vec<SealedTxInput>>,
     },
     EndBlock,
     Encrypt(Box<IntraEncryptRequest>),
@@ -76,7 +97,7 @@ impl IntraEnclaveRequest {
     pub fn new_validate_transfer(
         tx: TxEnclaveAux,
         info: ChainInfo,
-        tx_inputs: Vec<SealedLog>,
+        tx_inputs: Vec<SealedTxInput>,
     ) -> Self {
         Self::ValidateTx {
             tx_inputs: Some(tx_inputs),
@@ -92,7 +113,7 @@ impl IntraEnclaveRequest {
         tx: TxEnclaveAux,
         info: ChainInfo,
         account: Option<StakedState>,
-        tx_inputs: Vec<SealedLog>,
+        tx_inputs: Vec<SealedTxInput>,
     ) -> Self {
         Self::ValidateTx {
             tx_inputs: Some(tx_inputs),
@@ -115,7 +136,7 @@ impl IntraEnclaveRequest {
 /// helper method to validate basic assumptions
 pub fn is_basic_valid_tx_request(
     request: &VerifyTxRequest,
-    tx_inputs: &Option<Vec<SealedLog>>,
+    tx_inputs: &Option<Vec<SealedTxInput>>,
     chain_hex_id: u8,
 ) -> Result<(), PError> {
     if request.info.chain_hex_id != chain_hex_id {
@@ -363,7 +384,10 @@ impl Decode for DecryptionRequest {
 /// Response in direct communication (over one-side attested TLS) from TQE
 #[derive(Encode, Decode)]
 pub struct DecryptionResponse {
+    /// transactions where the view key has `TxAccess::AllData`
     pub txs: Vec<TxWithOutputs>,
+    /// filtered transactions where the view key has restricted access
+    pub restricted_txs: Vec<TxRestrictedView>,
 }
 
 #[cfg(test)]
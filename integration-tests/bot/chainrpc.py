@@ -203,11 +203,37 @@ class Wallet:
     def transactions(self, name=DEFAULT_WALLET, offset=0, limit=100, reversed=False, enckey=None):
         return self.client.call('wallet_transactions', [name, enckey or get_enckey()], offset, limit, reversed)
 
-    def send(self, to_address, amount, name=DEFAULT_WALLET, view_keys=None, enckey=None):
+    def send(self, to_address, amount, name=DEFAULT_WALLET, view_keys=None, enckey=None, access=None):
+        '''
+        access: access of the view keys, "AllData" (default), {"Outputs": [indices]} or "Metadata"
+        (the payment is the output at index 0)
+        '''
         return self.client.call(
             'wallet_sendToAddress',
             [name, enckey or get_enckey()],
-            to_address, str(amount), view_keys or [])
+            to_address, str(amount), view_keys or [], None, access)
+
+    def send_many(self, recipients, name=DEFAULT_WALLET, view_keys=None, enckey=None):
+        '''
+        recipients: list of (to_address, amount)
+        '''
+        return self.client.call(
+            'wallet_sendToAddresses',
+            [name, enckey or get_enckey()],
+            [{'to_address': to_address, 'amount': str(amount)} for to_address, amount in recipients],
+            view_keys or [])
+
+    def pending_transactions(self, name=DEFAULT_WALLET, enckey=None):
+        return self.client.call('wallet_pendingTransactions', [name, enckey or get_enckey()])
+
+    def rebroadcast(self, txid, name=DEFAULT_WALLET, enckey=None):
+        return self.client.call('wallet_rebroadcastTransaction', [name, enckey or get_enckey()], txid)
+
+    def replace(self, txid, fee, name=DEFAULT_WALLET, enckey=None):
+        return self.client.call('wallet_replaceTransaction', [name, enckey or get_enckey()], txid, str(fee))
+
+    def abandon(self, txid, name=DEFAULT_WALLET, enckey=None):
+        return self.client.call('wallet_abandonTransaction', [name, enckey or get_enckey()], txid)
 
     def sync(self, name=DEFAULT_WALLET, enckey=None):
         return self.client.call('sync', [name, enckey or get_enckey()],{"blocking":True, "reset":False, "do_loop":False})
@@ -221,6 +247,16 @@ class Wallet:
     def sync_stop(self, name=DEFAULT_WALLET, enckey=None):
         return self.client.call('sync_stop', [name, enckey or get_enckey()])
 
+    def transfer_stake(self, from_address, to_address, amount, bonded=True, name=DEFAULT_WALLET, enckey=None):
+        return self.client.call(
+            'staking_transferStake',
+            [name, enckey or get_enckey()],
+            fix_address(from_address),
+            fix_address(to_address),
+            str(amount),
+            'Bonded' if bonded else 'Unbonded',
+        )
+
     def build_raw_transfer_tx(self, to_address, amount, name=DEFAULT_WALLET,  enckey=None, viewkeys=[]):
         """
         build a raw transfer tx on watch-only wallet
@@ -235,6 +271,14 @@ class Wallet:
         """
         return self.client.call('wallet_broadcastSignedTransferTransaction', [name, enckey or get_enckey()], signed_tx)
 
+    def estimate_fee(self, tx_size, speed='normal'):
+        """
+        suggest the fee of a transaction to be included at the speed
+        :param speed: slow, normal or fast
+        :return: fee in base units
+        """
+        return self.client.call('wallet_estimateFee', tx_size, speed)
+
 
 class Staking:
     def __init__(self, client):
@@ -262,16 +306,67 @@ class Staking:
     def unjail(self, address, name=DEFAULT_WALLET, enckey=None):
         return self.client.call('staking_unjail', [name, enckey or get_enckey()], fix_address(address))
 
-    def join(self, node_name, node_pubkey, node_staking_address, keypackage, name=DEFAULT_WALLET, enckey=None):
+    def join(self, node_name, node_pubkey, node_staking_address, keypackage, commission_rate=None,
+             name=DEFAULT_WALLET, enckey=None):
         return self.client.call(
             'staking_validatorNodeJoin',
             [name, enckey or get_enckey()],
             node_name,
             node_pubkey,
             fix_address(node_staking_address),
-            keypackage
+            keypackage,
+            commission_rate
         )
 
+    def update_node_metadata(self, node_staking_address, node_name=None, security_contact=None, node_pubkey=None,
+                             name=DEFAULT_WALLET, enckey=None):
+        return self.client.call(
+            'staking_validatorNodeMetadataUpdate',
+            [name, enckey or get_enckey()],
+            fix_address(node_staking_address),
+            node_name,
+            security_contact,
+            node_pubkey
+        )
+
+    def propose_param_change(self, node_staking_address, change, name=DEFAULT_WALLET, enckey=None):
+        """
+        change: e.g. {"MaxValidators": 10}
+        returns the proposal id
+        """
+        return self.client.call(
+            'staking_proposeParamChange',
+            [name, enckey or get_enckey()],
+            fix_address(node_staking_address),
+            change
+        )
+
+    def vote_param_change(self, node_staking_address, proposal_id, approve=True, name=DEFAULT_WALLET, enckey=None):
+        return self.client.call(
+            'staking_voteParamChange',
+            [name, enckey or get_enckey()],
+            fix_address(node_staking_address),
+            proposal_id,
+            approve
+        )
+
+    def delegate(self, address, council_node_address=None, name=DEFAULT_WALLET, enckey=None):
+        return self.client.call(
+            'staking_delegate',
+            [name, enckey or get_enckey()],
+            fix_address(address),
+            council_node_address and fix_address(council_node_address)
+        )
+
+    def network_params(self):
+        return self.client.call('staking_networkParams')
+
+    def proposals(self):
+        return self.client.call('staking_proposals')
+
+    def slash_history(self, address):
+        return self.client.call('staking_slashHistory', address)
+
     def build_raw_transfer_tx(self, to_address, amount, name=DEFAULT_WALLET,  enckey=None, viewkeys=[]):
         return self.client.call('wallet_buildRawTransferTx', [name, enckey or get_enckey()], to_address, amount, viewkeys)
 
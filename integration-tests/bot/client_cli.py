@@ -246,11 +246,12 @@ class Wallet():
         if "Error" in text:
             raise Exception(text)
         r = re.findall(r"(?<=\|)(\s*\d+\.*\d*\s*)(?=\|)", text)
-        assert len(r) == 3
+        assert len(r) == 4
         result = {
             "total": to_coin(r[0]),
             "pending": to_coin(r[1]),
-            "available": to_coin(r[2])
+            "available": to_coin(r[2]),
+            "locked": to_coin(r[3])
         }
         return result
 
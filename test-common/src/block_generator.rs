@@ -291,9 +291,11 @@ impl TestnetSpec {
         let network_params = NetworkParameters::Genesis(config.network_params.clone());
         let app_hash = compute_app_hash(
             &MerkleTree::empty(),
-            &account_root,
-            &genesis_state.rewards_pool,
-            &network_params,
+            &ChainState::genesis(
+                account_root,
+                genesis_state.rewards_pool.clone(),
+                network_params.clone(),
+            ),
         );
 
         let share = self.share();
@@ -647,6 +649,8 @@ fn gen_network_params(
             monetary_expansion_decay: 999_860,
         },
         max_validators: 50,
+        dynamic_fee_config: Default::default(),
+        governance_config: Default::default(),
     }
 }
 
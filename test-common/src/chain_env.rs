@@ -30,6 +30,7 @@ use chain_core::state::tendermint::{
     TendermintValidatorAddress, TendermintValidatorPubKey, TendermintVotePower,
 };
 use chain_core::state::validator::NodeJoinRequestTx;
+use chain_core::state::ChainState;
 use chain_core::tx::fee::{LinearFee, Milli};
 use chain_core::tx::witness::EcdsaSignature;
 use chain_core::tx::{data::TxId, TransactionId, TxAux, TxPublicAux};
@@ -113,6 +114,8 @@ pub fn get_init_network_params(expansion_cap: Coin) -> InitNetworkParameters {
             monetary_expansion_decay: 999_860,
         },
         max_validators: 50,
+        dynamic_fee_config: Default::default(),
+        governance_config: Default::default(),
     }
 }
 
@@ -267,9 +270,11 @@ impl ChainEnv {
         let new_account_root = storage.put_stakings(0, &genesis_state.accounts);
         let genesis_app_hash = compute_app_hash(
             &MerkleTree::empty(),
-            &new_account_root,
-            &genesis_state.rewards_pool,
-            &NetworkParameters::Genesis(init_network_params),
+            &ChainState::genesis(
+                new_account_root,
+                genesis_state.rewards_pool,
+                NetworkParameters::Genesis(init_network_params),
+            ),
         );
         (
             ChainEnv {